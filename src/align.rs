@@ -0,0 +1,489 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use semver::Version;
+use serde_json::Value as JsonValue;
+
+use crate::catalog::{catalog_name, Catalogs, DEFAULT_CATALOG};
+use crate::config::AlignConfig;
+use crate::grouping::matches_pattern;
+use crate::pinning::range_style;
+use crate::range::lower_bounds;
+use crate::types::{
+    AlignPreference, AlignSource, AlignmentReport, CatalogCandidate, DeclaredRange, FixAction,
+    VersionMismatch,
+};
+use crate::workspace;
+
+/// Sections aligned across members; peer ranges stay as wide as each
+/// package's consumers need
+const ALIGNED_SECTIONS: &[&str] = &["dependencies", "devDependencies", "optionalDependencies"];
+
+/// Checks that workspace members declare each external dependency alike
+///
+/// Like syncpack, compares the ranges in the root package.json and those of
+/// workspace members: `react` at "^17.0.2" in one and "^18.2.0" in another
+/// installs two copies, or one that half the workspace wasn't tested with.
+/// The range to align to is `[align.pin]` from depx.toml, a range yarn
+/// constraints enforce, the pnpm catalog once any member uses it, or else
+/// the declared range `[align] prefer` picks. `catalog:` specs compare by
+/// the range they stand for. Workspace packages themselves, other protocol
+/// specs (`workspace:`, `file:`, git) and `[align] ignore`d dependencies
+/// are left out.
+pub struct AlignmentAnalyzer<'a> {
+    root: &'a Path,
+    config: &'a AlignConfig,
+    catalogs: &'a Catalogs,
+}
+
+impl<'a> AlignmentAnalyzer<'a> {
+    pub fn new(root: &'a Path, config: &'a AlignConfig, catalogs: &'a Catalogs) -> Self {
+        Self {
+            root,
+            config,
+            catalogs,
+        }
+    }
+
+    pub fn analyze(&self) -> Result<AlignmentReport> {
+        let mut dirs = vec![self.root.to_path_buf()];
+        dirs.extend(workspace::member_dirs(self.root)?);
+
+        let mut manifests = Vec::new();
+        let mut members = HashSet::new();
+        let mut declared: BTreeMap<String, Vec<DeclaredRange>> = BTreeMap::new();
+        for dir in dirs {
+            let path = dir.join("package.json");
+            if !path.exists() {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let value: JsonValue = serde_json::from_str(&content)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            let manifest = path
+                .strip_prefix(self.root)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| path.clone());
+
+            if let Some(name) = value.get("name").and_then(|n| n.as_str()) {
+                members.insert(name.to_string());
+            }
+            for section in ALIGNED_SECTIONS {
+                let Some(deps) = value.get(section).and_then(|d| d.as_object()) else {
+                    continue;
+                };
+                for (package, range) in deps {
+                    let Some(range) = range.as_str() else {
+                        continue;
+                    };
+                    if self.is_ignored(package) {
+                        continue;
+                    }
+                    // A catalog entry that doesn't exist fails the install anyway
+                    let resolved = match catalog_name(range) {
+                        Some(_) => match self.catalogs.resolve(package, range) {
+                            Some(resolved) => Some(resolved.to_string()),
+                            None => continue,
+                        },
+                        None if range_style(range).is_none() => continue,
+                        None => None,
+                    };
+                    declared
+                        .entry(package.clone())
+                        .or_default()
+                        .push(DeclaredRange {
+                            manifest: manifest.clone(),
+                            section: section.to_string(),
+                            range: range.to_string(),
+                            resolved,
+                        });
+                }
+            }
+            manifests.push(manifest);
+        }
+
+        let mut mismatches = Vec::new();
+        let mut catalog_candidates = Vec::new();
+        for (package, declarations) in declared {
+            if members.contains(&package) {
+                continue;
+            }
+            let Some((target, source)) = self.target(&package, &declarations) else {
+                continue;
+            };
+
+            let mut declaring: Vec<&Path> =
+                declarations.iter().map(|d| d.manifest.as_path()).collect();
+            declaring.dedup();
+            if self.catalogs.pnpm_workspace && source != AlignSource::Catalog && declaring.len() > 1
+            {
+                catalog_candidates.push(CatalogCandidate {
+                    package: package.clone(),
+                    range: target.clone(),
+                    manifests: declaring.len(),
+                });
+            }
+
+            if declarations.iter().all(|d| is_aligned(d, &target, source)) {
+                continue;
+            }
+            mismatches.push(VersionMismatch {
+                package,
+                target,
+                source,
+                declarations,
+            });
+        }
+
+        Ok(AlignmentReport {
+            manifests,
+            prefer: self.config.prefer,
+            mismatches,
+            catalog_candidates,
+        })
+    }
+
+    /// The range a dependency's declarations should use, and why
+    fn target(
+        &self,
+        package: &str,
+        declarations: &[DeclaredRange],
+    ) -> Option<(String, AlignSource)> {
+        if let Some(pin) = self.pin(package) {
+            return Some((pin.to_string(), AlignSource::Pin));
+        }
+        if let Some(range) = self.catalogs.enforced(package) {
+            return Some((range.to_string(), AlignSource::Constraint));
+        }
+
+        // The catalog spec members use most (the default catalog on a tie),
+        // then the default catalog's entry
+        let mut specs: Vec<(&str, usize)> = Vec::new();
+        for declaration in declarations.iter().filter(|d| d.resolved.is_some()) {
+            match specs
+                .iter_mut()
+                .find(|(spec, _)| *spec == declaration.range)
+            {
+                Some((_, count)) => *count += 1,
+                None => specs.push((&declaration.range, 1)),
+            }
+        }
+        let most_used = specs
+            .iter()
+            .rev()
+            .max_by_key(|(spec, count)| (*count, catalog_name(spec) == Some(DEFAULT_CATALOG)))
+            .map(|(spec, _)| spec.to_string());
+        if let Some(spec) = most_used {
+            return Some((spec, AlignSource::Catalog));
+        }
+        if self.catalogs.entry(DEFAULT_CATALOG, package).is_some() {
+            return Some(("catalog:".to_string(), AlignSource::Catalog));
+        }
+
+        let range = preferred(declarations, self.config.prefer)?;
+        Some((range, AlignSource::Declared))
+    }
+
+    fn is_ignored(&self, package: &str) -> bool {
+        self.config
+            .ignore
+            .iter()
+            .any(|pattern| matches_pattern(pattern, package))
+    }
+
+    /// The pinned range: an exact name wins over a pattern
+    fn pin(&self, package: &str) -> Option<&str> {
+        self.config
+            .pin
+            .get(package)
+            .map(String::as_str)
+            .or_else(|| {
+                self.config
+                    .pin
+                    .iter()
+                    .find(|(pattern, _)| matches_pattern(pattern, package))
+                    .map(|(_, range)| range.as_str())
+            })
+    }
+}
+
+/// Rewrite the ranges of one manifest that differ from their target
+pub fn align_actions(report: &AlignmentReport, manifest: &Path) -> Vec<FixAction> {
+    report
+        .mismatches
+        .iter()
+        .flat_map(|mismatch| {
+            let reason = match mismatch.source {
+                AlignSource::Declared => format!("{} range across the workspace", report.prefer),
+                AlignSource::Pin => format!("depx.toml pins {}", mismatch.target),
+                AlignSource::Constraint => {
+                    format!("yarn constraints enforce {}", mismatch.target)
+                }
+                AlignSource::Catalog => "managed by the pnpm catalog".to_string(),
+            };
+            mismatch
+                .declarations
+                .iter()
+                .filter(move |d| {
+                    d.manifest == manifest && !is_aligned(d, &mismatch.target, mismatch.source)
+                })
+                .map(move |d| FixAction::Align {
+                    package: mismatch.package.clone(),
+                    section: d.section.clone(),
+                    from: d.range.clone(),
+                    to: mismatch.target.clone(),
+                    reason: reason.clone(),
+                })
+        })
+        .collect()
+}
+
+/// Whether a declaration already uses the target range
+///
+/// Every `catalog:` spec is left as is: a named catalog (`catalog:react17`)
+/// is kept on purpose, and what it stands for is changed in the catalog.
+/// Ranges written out where the catalog manages a dependency aren't.
+fn is_aligned(declaration: &DeclaredRange, target: &str, source: AlignSource) -> bool {
+    declaration.range == target
+        || declaration.resolved.is_some()
+        || (source != AlignSource::Catalog && declaration.effective() == target)
+}
+
+/// The declared range the preference picks, by the lowest version each
+/// admits; ties go to the range declared most often, then the first
+///
+/// Ranges without a lower bound ("*", "latest") only win when nothing
+/// else is declared.
+fn preferred(declarations: &[DeclaredRange], prefer: AlignPreference) -> Option<String> {
+    let mut ranges: Vec<(&str, usize)> = Vec::new();
+    for declaration in declarations {
+        match ranges
+            .iter_mut()
+            .find(|(range, _)| *range == declaration.range)
+        {
+            Some((_, count)) => *count += 1,
+            None => ranges.push((&declaration.range, 1)),
+        }
+    }
+
+    let lowest = |range: &str| -> Option<Version> { lower_bounds(range)?.into_iter().min() };
+    let mut best: Option<(&str, usize, Option<Version>)> = None;
+    for (range, count) in ranges {
+        let bound = lowest(range);
+        let better = match &best {
+            None => true,
+            Some((_, best_count, best_bound)) => match (&bound, best_bound) {
+                (Some(_), None) => true,
+                (None, _) => false,
+                (Some(bound), Some(best_bound)) if bound == best_bound => count > *best_count,
+                (Some(bound), Some(best_bound)) => match prefer {
+                    AlignPreference::Highest => bound > best_bound,
+                    AlignPreference::Lowest => bound < best_bound,
+                },
+            },
+        };
+        if better {
+            best = Some((range, count, bound));
+        }
+    }
+    best.map(|(range, _, _)| range.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_alignment_report() {
+        let root = TempDir::new("align");
+        for member in ["web", "ui", "docs"] {
+            std::fs::create_dir_all(root.join("packages").join(member)).unwrap();
+        }
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write(
+            "package.json",
+            r#"{ "workspaces": ["packages/*"], "devDependencies": { "typescript": "~5.4.0", "@types/node": "^20" } }"#,
+        );
+        write(
+            "packages/web/package.json",
+            r#"{
+  "name": "web",
+  "dependencies": { "react": "^17.0.2", "ui": "workspace:*", "lodash": "^4.17.21" },
+  "devDependencies": { "typescript": "^5.3.0", "@types/node": "^18" }
+}"#,
+        );
+        write(
+            "packages/ui/package.json",
+            r#"{
+  "name": "ui",
+  "dependencies": { "react": "^18.2.0", "lodash": "^4.17.21" },
+  "peerDependencies": { "react": ">=17" }
+}"#,
+        );
+        write(
+            "packages/docs/package.json",
+            r#"{ "name": "docs", "dependencies": { "react": "^18.2.0", "ui": "*", "chalk": "5.3.0" } }"#,
+        );
+
+        let config = AlignConfig {
+            ignore: vec!["@types/*".to_string()],
+            ..AlignConfig::default()
+        };
+        let report = AlignmentAnalyzer::new(&root, &config, &Catalogs::default())
+            .analyze()
+            .unwrap();
+        let lowest = AlignConfig {
+            prefer: AlignPreference::Lowest,
+            pin: BTreeMap::from([("chalk".to_string(), "^5.3.0".to_string())]),
+            ..AlignConfig::default()
+        };
+        let lowest = AlignmentAnalyzer::new(&root, &lowest, &Catalogs::default())
+            .analyze()
+            .unwrap();
+
+        assert_eq!(report.manifests.len(), 4);
+        let targets: Vec<(&str, &str, AlignSource)> = report
+            .mismatches
+            .iter()
+            .map(|m| (m.package.as_str(), m.target.as_str(), m.source))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("react", "^18.2.0", AlignSource::Declared),
+                ("typescript", "~5.4.0", AlignSource::Declared)
+            ]
+        );
+
+        let targets: Vec<(&str, &str, AlignSource)> = lowest
+            .mismatches
+            .iter()
+            .map(|m| (m.package.as_str(), m.target.as_str(), m.source))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("@types/node", "^18", AlignSource::Declared),
+                ("chalk", "^5.3.0", AlignSource::Pin),
+                ("react", "^17.0.2", AlignSource::Declared),
+                ("typescript", "^5.3.0", AlignSource::Declared),
+            ]
+        );
+
+        let actions = align_actions(&report, Path::new("packages/web/package.json"));
+        assert_eq!(
+            actions,
+            vec![
+                FixAction::Align {
+                    package: "react".to_string(),
+                    section: "dependencies".to_string(),
+                    from: "^17.0.2".to_string(),
+                    to: "^18.2.0".to_string(),
+                    reason: "highest range across the workspace".to_string(),
+                },
+                FixAction::Align {
+                    package: "typescript".to_string(),
+                    section: "devDependencies".to_string(),
+                    from: "^5.3.0".to_string(),
+                    to: "~5.4.0".to_string(),
+                    reason: "highest range across the workspace".to_string(),
+                },
+            ]
+        );
+        assert!(align_actions(&report, Path::new("packages/ui/package.json")).is_empty());
+        assert!(report.catalog_candidates.is_empty());
+    }
+
+    #[test]
+    fn test_catalog_alignment() {
+        let root = TempDir::new("align-catalog");
+        for member in ["web", "ui"] {
+            std::fs::create_dir_all(root.join("packages").join(member)).unwrap();
+        }
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write(
+            "pnpm-workspace.yaml",
+            "packages:\n  - packages/*\ncatalog:\n  react: ^18.2.0\n  zod: ^3.23.0\ncatalogs:\n  react17:\n    react: ^17.0.2\n",
+        );
+        write(
+            "package.json",
+            r#"{ "dependencies": { "react": "catalog:react17" }, "devDependencies": { "typescript": "^5.4.0" } }"#,
+        );
+        write(
+            "packages/web/package.json",
+            r#"{
+  "name": "web",
+  "dependencies": { "react": "catalog:", "lodash": "^4.17.21", "left-pad": "catalog:" },
+  "devDependencies": { "typescript": "^5.4.0" }
+}"#,
+        );
+        write(
+            "packages/ui/package.json",
+            r#"{ "name": "ui", "dependencies": { "react": "^18.2.0", "lodash": "^4.17.20", "zod": "catalog:" } }"#,
+        );
+
+        let catalogs = Catalogs::load(&root).unwrap();
+        let report = AlignmentAnalyzer::new(&root, &AlignConfig::default(), &catalogs)
+            .analyze()
+            .unwrap();
+
+        // The entry-less `catalog:` spec of left-pad is left out
+        let targets: Vec<(&str, &str, AlignSource)> = report
+            .mismatches
+            .iter()
+            .map(|m| (m.package.as_str(), m.target.as_str(), m.source))
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                ("lodash", "^4.17.21", AlignSource::Declared),
+                ("react", "catalog:", AlignSource::Catalog),
+            ]
+        );
+        assert_eq!(
+            report.mismatches[1].declarations[2].resolved.as_deref(),
+            Some("^18.2.0")
+        );
+        assert_eq!(
+            align_actions(&report, Path::new("packages/ui/package.json")),
+            vec![
+                FixAction::Align {
+                    package: "lodash".to_string(),
+                    section: "dependencies".to_string(),
+                    from: "^4.17.20".to_string(),
+                    to: "^4.17.21".to_string(),
+                    reason: "highest range across the workspace".to_string(),
+                },
+                FixAction::Align {
+                    package: "react".to_string(),
+                    section: "dependencies".to_string(),
+                    from: "^18.2.0".to_string(),
+                    to: "catalog:".to_string(),
+                    reason: "managed by the pnpm catalog".to_string(),
+                },
+            ]
+        );
+        // The named catalog is kept
+        assert!(align_actions(&report, Path::new("package.json")).is_empty());
+        assert_eq!(
+            report.catalog_candidates,
+            vec![
+                CatalogCandidate {
+                    package: "lodash".to_string(),
+                    range: "^4.17.21".to_string(),
+                    manifests: 2,
+                },
+                CatalogCandidate {
+                    package: "typescript".to_string(),
+                    range: "^5.4.0".to_string(),
+                    manifests: 2,
+                },
+            ]
+        );
+    }
+}
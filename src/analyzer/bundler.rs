@@ -0,0 +1,428 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+
+use super::configs::{parse_config, ConfigValue};
+use super::deno::cdn_package;
+use super::extract_package_name;
+use super::resolve::{self, installed_package, target_of, AliasTarget};
+use crate::workspace;
+
+/// Bundler configuration files declaring `resolve.alias`
+const CONFIG_FILES: &[&str] = &[
+    "vite.config.js",
+    "vite.config.mjs",
+    "vite.config.cjs",
+    "vite.config.ts",
+    "vite.config.mts",
+    "vite.config.cts",
+    "webpack.config.js",
+    "webpack.config.cjs",
+    "webpack.config.mjs",
+    "webpack.config.ts",
+];
+
+/// Standalone import map files, besides the ones inlined in HTML
+const IMPORT_MAP_FILES: &[&str] = &["importmap.json", "import-map.json"];
+
+/// Aliases bundlers and browsers resolve bare specifiers with
+///
+/// Reads `resolve.alias` from vite and webpack configs, and browser import
+/// maps: `<script type="importmap">` in the HTML pages of a directory, or
+/// an importmap.json. Each applies to the files below the root or
+/// workspace member declaring it, the nearest winning.
+#[derive(Debug, Default)]
+pub struct BundlerAliases {
+    /// Deepest directory first
+    scopes: Vec<(PathBuf, Vec<Alias>)>,
+}
+
+#[derive(Debug)]
+struct Alias {
+    key: String,
+    matching: Matching,
+    replacement: Replacement,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Matching {
+    /// Only the key itself: webpack's `name$` and import map keys
+    Exact,
+    /// The key, or a path below it (`@/components`), as bundlers match
+    ExactOrSubpath,
+    /// Anything starting with the key, an import map `prefix/`
+    Prefix,
+}
+
+#[derive(Debug)]
+enum Replacement {
+    /// A file or directory, absolute once loaded
+    Path(PathBuf),
+    /// Another module specifier, like `preact/compat`
+    Specifier(String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ImportMap {
+    #[serde(default)]
+    imports: BTreeMap<String, String>,
+}
+
+impl BundlerAliases {
+    pub fn load(root: &Path) -> Result<Self> {
+        let mut dirs = vec![root.to_path_buf()];
+        dirs.extend(workspace::member_dirs(root)?);
+
+        let mut scopes = Vec::new();
+        for dir in dirs {
+            let mut aliases = Vec::new();
+            for name in CONFIG_FILES {
+                let path = dir.join(name);
+                if path.is_file() {
+                    aliases.extend(config_aliases(&path)?);
+                }
+            }
+            aliases.extend(import_map_aliases(&dir)?);
+            if !aliases.is_empty() {
+                scopes.push((dir, aliases));
+            }
+        }
+
+        scopes.sort_by_key(|(dir, _)| std::cmp::Reverse(dir.components().count()));
+        Ok(Self { scopes })
+    }
+
+    /// Where a bare specifier imported by `file` points, when an alias
+    /// covers it; the longest matching key wins
+    pub fn resolve(&self, file: &Path, specifier: &str) -> Option<AliasTarget> {
+        let (_, aliases) = self.scopes.iter().find(|(dir, _)| file.starts_with(dir))?;
+        let (alias, rest) = aliases
+            .iter()
+            .filter_map(|alias| Some((alias, alias.rest(specifier)?)))
+            .max_by_key(|(alias, _)| alias.key.len())?;
+
+        match &alias.replacement {
+            Replacement::Specifier(target) => {
+                extract_package_name(&format!("{}{}", target, rest)).map(AliasTarget::Package)
+            }
+            Replacement::Path(target) => {
+                let target = format!("{}{}", target.display(), rest);
+                if let Some(file) = resolve::resolve_file(Path::new(&target)) {
+                    return Some(target_of(file));
+                }
+                Some(installed_package(&target).map_or(AliasTarget::Missing, AliasTarget::Package))
+            }
+        }
+    }
+}
+
+impl Alias {
+    /// What follows the key in a specifier the alias matches
+    fn rest<'s>(&self, specifier: &'s str) -> Option<&'s str> {
+        let rest = specifier.strip_prefix(self.key.as_str())?;
+        let matches = match self.matching {
+            Matching::Exact => rest.is_empty(),
+            Matching::ExactOrSubpath => rest.is_empty() || rest.starts_with('/'),
+            Matching::Prefix => true,
+        };
+        matches.then_some(rest)
+    }
+}
+
+/// `resolve.alias` of a vite or webpack config
+///
+/// Aliases are an object (`{ "@": path.resolve(__dirname, "src") }`) or,
+/// in vite, a list of `{ find, replacement }`; regex `find`s are skipped.
+/// A config that doesn't parse, or builds its aliases at runtime, gives none.
+fn config_aliases(path: &Path) -> Result<Vec<Alias>> {
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let Some(config) = parse_config(path, &content) else {
+        return Ok(Vec::new());
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut aliases = Vec::new();
+    let mut stack = vec![&config];
+    while let Some(value) = stack.pop() {
+        let fields = match value {
+            ConfigValue::Map(fields) => fields,
+            ConfigValue::List(items) => {
+                stack.extend(items);
+                continue;
+            }
+            _ => continue,
+        };
+        for (key, _, value) in fields {
+            stack.push(value);
+            if key != "resolve" {
+                continue;
+            }
+            match value.get("alias") {
+                Some(ConfigValue::Map(entries)) => {
+                    for (key, _, replacement) in entries {
+                        // `name: ["./a", "./b"]` tries each in turn; the first is kept
+                        let replacement = match replacement {
+                            ConfigValue::List(items) => items.first(),
+                            other => Some(other),
+                        };
+                        if let Some(ConfigValue::Str(replacement, _)) = replacement {
+                            aliases.push(bundler_alias(dir, key, replacement));
+                        }
+                    }
+                }
+                Some(ConfigValue::List(entries)) => {
+                    for entry in entries {
+                        if let (
+                            Some(ConfigValue::Str(find, _)),
+                            Some(ConfigValue::Str(replacement, _)),
+                        ) = (entry.get("find"), entry.get("replacement"))
+                        {
+                            aliases.push(bundler_alias(dir, find, replacement));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(aliases)
+}
+
+fn bundler_alias(dir: &Path, key: &str, replacement: &str) -> Alias {
+    let (key, matching) = match key.strip_suffix('$') {
+        Some(key) => (key, Matching::Exact),
+        None => (key, Matching::ExactOrSubpath),
+    };
+    Alias {
+        key: key.to_string(),
+        matching,
+        replacement: replacement_path(dir, replacement),
+    }
+}
+
+/// A replacement naming a path, or another specifier
+///
+/// Vite's root-relative "/src" is relative to the config, unless it's an
+/// absolute path into the project already.
+fn replacement_path(dir: &Path, replacement: &str) -> Replacement {
+    if replacement.starts_with('.') {
+        return Replacement::Path(resolve::normalize(&dir.join(replacement)));
+    }
+    if let Some(relative) = replacement.strip_prefix('/') {
+        let absolute = PathBuf::from(replacement);
+        let path = if absolute.starts_with(dir) {
+            absolute
+        } else {
+            dir.join(relative)
+        };
+        return Replacement::Path(resolve::normalize(&path));
+    }
+    Replacement::Specifier(replacement.to_string())
+}
+
+/// Import maps of a directory's HTML pages and import map files
+///
+/// Keys ending in "/" map every specifier below them; other keys only
+/// themselves. URLs on npm CDNs name their package; other URLs and
+/// `scopes` are left alone.
+fn import_map_aliases(dir: &Path) -> Result<Vec<Alias>> {
+    let mut maps = Vec::new();
+    for name in IMPORT_MAP_FILES {
+        let path = dir.join(name);
+        if path.is_file() {
+            let content = std::fs::read_to_string(&path)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            maps.push(content);
+        }
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(Vec::new());
+    };
+    let mut pages: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+        .collect();
+    pages.sort();
+    for page in pages {
+        let content = std::fs::read_to_string(&page)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", page.display()))?;
+        maps.extend(inline_import_maps(&content).map(str::to_string));
+    }
+
+    let mut aliases = Vec::new();
+    for map in maps {
+        let Ok(map) = serde_json::from_str::<ImportMap>(&map) else {
+            continue;
+        };
+        for (key, target) in map.imports {
+            let replacement = if target.starts_with("https://") || target.starts_with("http://") {
+                match cdn_package(&target) {
+                    Some(package) => Replacement::Specifier(package),
+                    None => continue,
+                }
+            } else {
+                replacement_path(dir, &target)
+            };
+            // A prefix key maps to a directory, whose trailing "/" the path lost
+            let (matching, replacement) = match (key.ends_with('/'), replacement) {
+                (true, Replacement::Path(path)) => {
+                    (Matching::Prefix, Replacement::Path(path.join("")))
+                }
+                (true, replacement) => (Matching::Prefix, replacement),
+                (false, replacement) => (Matching::Exact, replacement),
+            };
+            // A CDN package stands for the whole prefix
+            let replacement = match replacement {
+                Replacement::Specifier(package) if matching == Matching::Prefix => {
+                    Replacement::Specifier(format!("{}/", package))
+                }
+                replacement => replacement,
+            };
+            aliases.push(Alias {
+                key,
+                matching,
+                replacement,
+            });
+        }
+    }
+    Ok(aliases)
+}
+
+/// Contents of the `<script type="importmap">` elements of an HTML page
+fn inline_import_maps(html: &str) -> impl Iterator<Item = &str> {
+    let lower = html.to_ascii_lowercase();
+    let mut maps = Vec::new();
+    let mut from = 0;
+    while let Some(start) = lower[from..].find("<script").map(|i| i + from) {
+        let Some(open_end) = lower[start..].find('>').map(|i| i + start + 1) else {
+            break;
+        };
+        let Some(close) = lower[open_end..].find("</script").map(|i| i + open_end) else {
+            break;
+        };
+        let tag = &lower[start..open_end];
+        if tag.contains("type=\"importmap\"") || tag.contains("type='importmap'") {
+            maps.push(&html[open_end..close]);
+        }
+        from = close;
+    }
+    maps.into_iter()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_inline_import_maps() {
+        let html = r#"<html><head>
+<script type="module" src="/main.js"></script>
+<SCRIPT TYPE="importmap">{ "imports": { "lit": "https://esm.sh/lit@3" } }</SCRIPT>
+</head></html>"#;
+        let maps: Vec<&str> = inline_import_maps(html).collect();
+        assert_eq!(
+            maps,
+            vec![r#"{ "imports": { "lit": "https://esm.sh/lit@3" } }"#]
+        );
+    }
+
+    #[test]
+    fn test_bundler_aliases() {
+        let root = TempDir::new("bundler");
+        for dir in [
+            "src/components",
+            "apps/site/lib",
+            "node_modules/@acme/design-system/src",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write("package.json", r#"{ "workspaces": ["apps/*"] }"#);
+        write(
+            "vite.config.ts",
+            r#"import { defineConfig } from "vite";
+import path from "node:path";
+
+export default defineConfig({
+  resolve: {
+    alias: {
+      "@": path.resolve(__dirname, "./src"),
+      "@ds": "@acme/design-system/src",
+      "~icons": path.resolve(__dirname, "node_modules/@acme/design-system/src/icons"),
+      "react$": "preact/compat",
+    },
+  },
+});
+"#,
+        );
+        write("src/components/Button.tsx", "");
+        write("node_modules/@acme/design-system/src/icons.ts", "");
+        write("apps/site/package.json", r#"{ "name": "site" }"#);
+        write(
+            "apps/site/webpack.config.js",
+            r#"const path = require("path");
+module.exports = { resolve: { alias: { lib$: path.join(__dirname, "lib") } } };"#,
+        );
+        write(
+            "apps/site/vite.config.mjs",
+            r#"export default { resolve: { alias: [{ find: "ui", replacement: "/lib" }, { find: /^~/, replacement: "" }] } };"#,
+        );
+        write(
+            "apps/site/index.html",
+            r#"<script type="importmap">
+{ "imports": { "lit": "https://cdn.jsdelivr.net/npm/lit@3/+esm", "lit/": "https://esm.sh/lit@3/", "app/": "./lib/", "cdn": "https://example.com/cdn.js" } }
+</script>"#,
+        );
+        write("apps/site/lib/index.js", "");
+        write("apps/site/lib/util.js", "");
+
+        let aliases = BundlerAliases::load(&root).unwrap();
+        let from_root = root.join("src/main.ts");
+        let from_site = root.join("apps/site/main.js");
+        let results = [
+            aliases.resolve(&from_root, "@/components/Button"),
+            aliases.resolve(&from_root, "@/missing"),
+            aliases.resolve(&from_root, "@ds/Button"),
+            aliases.resolve(&from_root, "~icons"),
+            aliases.resolve(&from_root, "react"),
+            aliases.resolve(&from_root, "react/jsx-runtime"),
+            aliases.resolve(&from_root, "@acme/other"),
+            aliases.resolve(&from_site, "lib"),
+            aliases.resolve(&from_site, "lib/util"),
+            aliases.resolve(&from_site, "ui/util"),
+            aliases.resolve(&from_site, "lit"),
+            aliases.resolve(&from_site, "lit/decorators.js"),
+            aliases.resolve(&from_site, "app/util.js"),
+            aliases.resolve(&from_site, "cdn"),
+        ];
+
+        let package = |name: &str| Some(AliasTarget::Package(name.to_string()));
+        assert_eq!(
+            results,
+            [
+                Some(AliasTarget::File(root.join("src/components/Button.tsx"))),
+                Some(AliasTarget::Missing),
+                package("@acme/design-system"),
+                package("@acme/design-system"),
+                package("preact"),
+                None,
+                None,
+                Some(AliasTarget::File(root.join("apps/site/lib/index.js"))),
+                None,
+                Some(AliasTarget::File(root.join("apps/site/lib/util.js"))),
+                package("lit"),
+                package("lit"),
+                Some(AliasTarget::File(root.join("apps/site/lib/util.js"))),
+                None,
+            ]
+        );
+    }
+}
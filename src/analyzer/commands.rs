@@ -0,0 +1,250 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use miette::{IntoDiagnostic, Result};
+use serde::Deserialize;
+
+use crate::types::{CommandInvocation, Package};
+
+/// Functions that take a whole command line (`exec('eslint .')`)
+pub const COMMAND_LINE_CALLS: &[&str] = &[
+    "exec",
+    "execSync",
+    "execaCommand",
+    "execaCommandSync",
+    "command",
+    "commandSync",
+];
+
+/// Functions that take the binary and its arguments separately (`spawn('eslint', ['.'])`)
+pub const BINARY_CALLS: &[&str] = &[
+    "spawn",
+    "spawnSync",
+    "execFile",
+    "execFileSync",
+    "execa",
+    "execaSync",
+    "execaNode",
+    "sync",
+];
+
+/// Package runners whose first argument is the binary that actually runs
+const RUNNERS: &[&str] = &["npx", "pnpx", "bunx"];
+
+/// Extract the binaries invoked by a shell command line
+///
+/// Examples:
+/// - "eslint . && prettier --check ." -> ["eslint", "prettier"]
+/// - "NODE_ENV=test npx -y vitest run" -> ["vitest"]
+/// - "cross-env CI=1 ./node_modules/.bin/tsc -p ." -> ["cross-env", "tsc"]
+pub fn invoked_binaries(command_line: &str) -> Vec<String> {
+    let mut binaries = Vec::new();
+
+    for segment in command_line.split(['&', '|', ';', '\n']) {
+        let mut tokens = segment.split_whitespace().peekable();
+
+        // Leading environment assignments (FOO=bar cmd)
+        while tokens.peek().is_some_and(|t| is_env_assignment(t)) {
+            tokens.next();
+        }
+
+        let Some(first) = tokens.next() else {
+            continue;
+        };
+        let first = binary_name(first);
+
+        if first == "cross-env" {
+            binaries.push(first.to_string());
+            while tokens.peek().is_some_and(|t| is_env_assignment(t)) {
+                tokens.next();
+            }
+            if let Some(next) = tokens.next() {
+                binaries.push(binary_name(next).to_string());
+            }
+            continue;
+        }
+
+        let is_runner = RUNNERS.contains(&first)
+            || (matches!(first, "pnpm" | "yarn")
+                && tokens.peek().is_some_and(|t| matches!(*t, "exec" | "dlx")));
+        if !is_runner {
+            binaries.push(first.to_string());
+            continue;
+        }
+
+        if matches!(first, "pnpm" | "yarn") {
+            tokens.next();
+        }
+
+        // Skip runner flags; `-p <pkg>` names the package directly
+        while let Some(token) = tokens.next() {
+            if matches!(token, "-p" | "--package") {
+                if let Some(package) = tokens.next() {
+                    binaries.push(strip_version(package).to_string());
+                }
+                continue;
+            }
+            if let Some(package) = token.strip_prefix("--package=") {
+                binaries.push(strip_version(package).to_string());
+                continue;
+            }
+            if token.starts_with('-') {
+                continue;
+            }
+            binaries.push(strip_version(binary_name(token)).to_string());
+            break;
+        }
+    }
+
+    binaries.retain(|b| !b.is_empty());
+    binaries
+}
+
+/// Map invoked binaries back to the installed packages that provide them
+///
+/// A binary resolves to the package declaring it in its `bin` field, or to
+/// a package of the same name (older lockfiles don't record `bin`).
+pub fn resolve_invoked_packages(
+    commands: &[CommandInvocation],
+    packages: &HashMap<String, Package>,
+) -> HashSet<String> {
+    let mut providers: HashMap<&str, &str> = HashMap::new();
+    for pkg in packages.values() {
+        for bin in &pkg.bins {
+            providers.entry(bin.as_str()).or_insert(pkg.name.as_str());
+        }
+    }
+
+    commands
+        .iter()
+        .filter_map(|cmd| {
+            providers
+                .get(cmd.binary.as_str())
+                .map(|name| name.to_string())
+                .or_else(|| {
+                    packages
+                        .contains_key(&cmd.binary)
+                        .then(|| cmd.binary.clone())
+                })
+        })
+        .collect()
+}
+
+/// Binaries invoked by the `scripts` in the project's package.json
+pub fn script_invocations(root: &Path) -> Result<Vec<CommandInvocation>> {
+    let path = root.join("package.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = std::fs::read_to_string(&path).into_diagnostic()?;
+    let Ok(manifest) = serde_json::from_str::<ScriptsManifest>(&content) else {
+        return Ok(Vec::new());
+    };
+
+    let mut invocations = Vec::new();
+    for (name, script) in &manifest.scripts {
+        let key = format!("\"{}\"", name);
+        let line = content
+            .lines()
+            .position(|l| l.contains(&key))
+            .map(|i| i + 1)
+            .unwrap_or(1);
+
+        for binary in invoked_binaries(script) {
+            invocations.push(CommandInvocation {
+                file_path: path.clone(),
+                line,
+                binary,
+            });
+        }
+    }
+
+    Ok(invocations)
+}
+
+#[derive(Deserialize)]
+struct ScriptsManifest {
+    #[serde(default)]
+    scripts: HashMap<String, String>,
+}
+
+fn is_env_assignment(token: &str) -> bool {
+    token
+        .split_once('=')
+        .is_some_and(|(key, _)| !key.is_empty() && !key.contains('/') && !key.starts_with('-'))
+}
+
+/// "./node_modules/.bin/tsc" -> "tsc"
+fn binary_name(token: &str) -> &str {
+    let token = token.trim_matches(|c| c == '"' || c == '\'');
+    token.rsplit('/').next().unwrap_or(token)
+}
+
+/// "prettier@3.0.0" -> "prettier", "@scope/cli@latest" -> "@scope/cli"
+fn strip_version(spec: &str) -> &str {
+    match spec.rfind('@') {
+        Some(0) | None => spec,
+        Some(i) => &spec[..i],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_invoked_binaries() {
+        assert_eq!(
+            invoked_binaries("eslint . && prettier --check ."),
+            vec!["eslint", "prettier"]
+        );
+        assert_eq!(
+            invoked_binaries("NODE_ENV=test npx -y vitest run"),
+            vec!["vitest"]
+        );
+        assert_eq!(
+            invoked_binaries("cross-env CI=1 ./node_modules/.bin/tsc -p ."),
+            vec!["cross-env", "tsc"]
+        );
+        assert_eq!(
+            invoked_binaries("pnpm exec playwright test"),
+            vec!["playwright"]
+        );
+        assert_eq!(
+            invoked_binaries("npx -p @scope/cli@2 run-thing"),
+            vec!["@scope/cli", "run-thing"]
+        );
+        assert_eq!(invoked_binaries("npx prettier@3.0.0 ."), vec!["prettier"]);
+    }
+
+    #[test]
+    fn test_resolve_invoked_packages() {
+        let mut typescript = Package::new("typescript", "5.3.0");
+        typescript.bins = vec!["tsc".to_string(), "tsserver".to_string()];
+
+        let packages: HashMap<String, Package> = [
+            typescript,
+            Package::new("prettier", "3.0.0"),
+            Package::new("lodash", "4.17.21"),
+        ]
+        .into_iter()
+        .map(|p| (p.name.clone(), p))
+        .collect();
+
+        let commands: Vec<CommandInvocation> = ["tsc", "prettier", "git"]
+            .iter()
+            .map(|b| CommandInvocation {
+                file_path: PathBuf::from("build.js"),
+                line: 1,
+                binary: b.to_string(),
+            })
+            .collect();
+
+        let resolved = resolve_invoked_packages(&commands, &packages);
+        assert_eq!(resolved.len(), 2);
+        assert!(resolved.contains("typescript"));
+        assert!(resolved.contains("prettier"));
+    }
+}
@@ -0,0 +1,140 @@
+use std::path::Path;
+
+use super::keep_sections;
+
+/// Component file extensions whose scripts are analyzed
+pub(super) const COMPONENT_EXTENSIONS: &[&str] = &["svelte", "astro"];
+
+/// The scripts of a Svelte or Astro component, as TypeScript source of the
+/// same length, or `None` for other files
+///
+/// Svelte scripts are its `<script>` (and `<script module>`) blocks;
+/// Astro's are the frontmatter between its `---` fences and the `<script>`
+/// tags Astro bundles. `is:inline` scripts, and those whose `type` isn't
+/// JavaScript, go to the browser as they are and are left out.
+pub(super) fn component_script(path: &Path, source: &str) -> Option<String> {
+    let extension = path.extension().and_then(|e| e.to_str())?;
+    if !COMPONENT_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+
+    let mut sections = Vec::new();
+    let mut markup_start = 0;
+    if extension == "astro" {
+        if let Some(frontmatter) = frontmatter(source) {
+            markup_start = frontmatter.end;
+            sections.push(frontmatter);
+        }
+    }
+    sections.extend(script_blocks(source, markup_start, extension == "astro"));
+    Some(keep_sections(source, &sections))
+}
+
+/// The span between the `---` fences opening an Astro component
+fn frontmatter(source: &str) -> Option<std::ops::Range<usize>> {
+    let leading = source.len() - source.trim_start().len();
+    let rest = source[leading..].strip_prefix("---")?;
+    let start = source.len() - rest.len();
+
+    let mut offset = start;
+    for (i, line) in rest.split_inclusive('\n').enumerate() {
+        if i > 0 && line.trim_end() == "---" {
+            return Some(start..offset);
+        }
+        offset += line.len();
+    }
+    None
+}
+
+/// The contents of the `<script>` elements after `from`
+fn script_blocks(source: &str, from: usize, astro: bool) -> Vec<std::ops::Range<usize>> {
+    let mut blocks = Vec::new();
+    let mut offset = from;
+    while let Some(found) = source[offset..].find("<script") {
+        let tag_start = offset + found;
+        let Some(tag_len) = source[tag_start..].find('>') else {
+            break;
+        };
+        let attributes = &source[tag_start + "<script".len()..tag_start + tag_len];
+        let content_start = tag_start + tag_len + 1;
+        offset = content_start;
+
+        // Not `<scripts>`, and a self-closing `<script src="..." />` has no content
+        let is_tag = attributes.is_empty() || attributes.starts_with(char::is_whitespace);
+        if !is_tag || attributes.trim_end().ends_with('/') {
+            continue;
+        }
+        let Some(content_len) = source[content_start..].find("</script") else {
+            break;
+        };
+        offset = content_start + content_len;
+        if is_bundled(attributes, astro) {
+            blocks.push(content_start..offset);
+        }
+    }
+    blocks
+}
+
+fn is_bundled(attributes: &str, astro: bool) -> bool {
+    if astro && attributes.contains("is:inline") {
+        return false;
+    }
+    let Some(at) = attributes.find("type=") else {
+        return true;
+    };
+    let kind = attributes[at + "type=".len()..]
+        .trim_start_matches(['"', '\''])
+        .split(['"', '\'', ' ', '>'])
+        .next()
+        .unwrap_or("");
+    matches!(
+        kind,
+        "module" | "text/javascript" | "application/javascript" | "text/typescript" | "ts"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_component_script() {
+        let svelte = r#"<script context="module" lang="ts">
+  import type { Load } from '@sveltejs/kit';
+</script>
+
+<script lang="ts">
+  import { onMount } from 'svelte';
+</script>
+
+<h1>Hello ✓</h1>
+<script type="application/ld+json">{ "@context": "https://schema.org" }</script>
+"#;
+        let script = component_script(Path::new("Page.svelte"), svelte).unwrap();
+        assert_eq!(script.len(), svelte.len());
+        assert_eq!(script.lines().count(), svelte.lines().count());
+        assert!(script.contains("import type { Load } from '@sveltejs/kit';"));
+        assert!(script.contains("import { onMount } from 'svelte';"));
+        assert!(!script.contains("<h1>") && !script.contains("schema.org"));
+
+        let astro = r#"---
+import Layout from '../layouts/Layout.astro';
+import { format } from 'date-fns';
+---
+<Layout>
+  <p>---</p>
+  <script>
+    import confetti from 'canvas-confetti';
+  </script>
+  <script is:inline src="https://cdn.example.com/analytics.js"></script>
+</Layout>
+"#;
+        let script = component_script(Path::new("index.astro"), astro).unwrap();
+        assert_eq!(script.len(), astro.len());
+        assert!(script.contains("import { format } from 'date-fns';"));
+        assert!(script.contains("import confetti from 'canvas-confetti';"));
+        assert!(!script.contains("---") && !script.contains("analytics"));
+
+        assert_eq!(component_script(Path::new("index.ts"), "import 'x';"), None);
+    }
+}
@@ -0,0 +1,644 @@
+use std::path::{Path, PathBuf};
+
+use miette::{IntoDiagnostic, Result};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{CallExpression, Declaration, Expression, ObjectPropertyKind, Statement};
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+
+use crate::types::{Import, ImportKind};
+
+use super::extract_package_name;
+
+/// Tools that name packages by string in their configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tool {
+    Eslint,
+    Babel,
+    Postcss,
+    Jest,
+    Tailwind,
+}
+
+/// Configuration files of each tool, read from the project root
+const CONFIG_FILES: &[(&str, Tool)] = &[
+    (".eslintrc", Tool::Eslint),
+    (".eslintrc.json", Tool::Eslint),
+    (".eslintrc.yaml", Tool::Eslint),
+    (".eslintrc.yml", Tool::Eslint),
+    (".eslintrc.js", Tool::Eslint),
+    (".eslintrc.cjs", Tool::Eslint),
+    (".babelrc", Tool::Babel),
+    (".babelrc.json", Tool::Babel),
+    (".babelrc.js", Tool::Babel),
+    (".babelrc.cjs", Tool::Babel),
+    ("babel.config.json", Tool::Babel),
+    ("babel.config.js", Tool::Babel),
+    ("babel.config.cjs", Tool::Babel),
+    ("babel.config.mjs", Tool::Babel),
+    (".postcssrc", Tool::Postcss),
+    (".postcssrc.json", Tool::Postcss),
+    (".postcssrc.yaml", Tool::Postcss),
+    (".postcssrc.yml", Tool::Postcss),
+    ("postcss.config.js", Tool::Postcss),
+    ("postcss.config.cjs", Tool::Postcss),
+    ("postcss.config.mjs", Tool::Postcss),
+    ("postcss.config.ts", Tool::Postcss),
+    ("jest.config.json", Tool::Jest),
+    ("jest.config.js", Tool::Jest),
+    ("jest.config.cjs", Tool::Jest),
+    ("jest.config.mjs", Tool::Jest),
+    ("jest.config.ts", Tool::Jest),
+    ("tailwind.config.js", Tool::Tailwind),
+    ("tailwind.config.cjs", Tool::Tailwind),
+    ("tailwind.config.mjs", Tool::Tailwind),
+    ("tailwind.config.ts", Tool::Tailwind),
+];
+
+/// package.json fields holding a tool's configuration
+const MANIFEST_FIELDS: &[(&str, Tool)] = &[
+    ("eslintConfig", Tool::Eslint),
+    ("babel", Tool::Babel),
+    ("postcss", Tool::Postcss),
+    ("jest", Tool::Jest),
+];
+
+/// Jest reporters built into Jest rather than installed
+const JEST_BUILTIN_REPORTERS: &[&str] = &["default", "summary", "github-actions", "verbose"];
+
+/// A configuration value, with the line of each string
+#[derive(Debug)]
+pub(super) enum ConfigValue {
+    Str(String, usize),
+    List(Vec<ConfigValue>),
+    Map(Vec<(String, usize, ConfigValue)>),
+    Other,
+}
+
+impl ConfigValue {
+    /// A field of a map
+    pub(super) fn get(&self, key: &str) -> Option<&ConfigValue> {
+        match self {
+            ConfigValue::Map(fields) => fields
+                .iter()
+                .find(|(field, _, _)| field == key)
+                .map(|(_, _, value)| value),
+            _ => None,
+        }
+    }
+}
+
+/// Packages the project's tool configuration names by string
+///
+/// `extends: ["airbnb"]`, `plugins: ["@babel/transform-runtime"]` or
+/// `testEnvironment: "jsdom"` load `eslint-config-airbnb`,
+/// `@babel/plugin-transform-runtime` and `jest-environment-jsdom`, which
+/// nothing imports. Names are expanded the way each tool resolves them.
+/// JSON, YAML and JS/TS configuration files are read, and the tools'
+/// fields in package.json.
+pub fn config_references(root: &Path) -> Result<Vec<Import>> {
+    let mut references = Vec::new();
+    for (name, tool) in CONFIG_FILES {
+        let path = root.join(name);
+        if !path.is_file() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).into_diagnostic()?;
+        if let Some(config) = parse_config(&path, &content) {
+            references.extend(tool_references(*tool, &path, &config));
+        }
+    }
+
+    let manifest_path = root.join("package.json");
+    if manifest_path.is_file() {
+        let content = std::fs::read_to_string(&manifest_path).into_diagnostic()?;
+        if let Some(ConfigValue::Map(fields)) = parse_script(&manifest_path, &content, true) {
+            for (field, _, config) in &fields {
+                if let Some((_, tool)) = MANIFEST_FIELDS.iter().find(|(f, _)| f == field) {
+                    references.extend(tool_references(*tool, &manifest_path, config));
+                }
+            }
+        }
+    }
+
+    Ok(references)
+}
+
+/// Read a configuration file by its extension; `.eslintrc` and `.postcssrc`
+/// may hold JSON or YAML
+pub(super) fn parse_config(path: &Path, content: &str) -> Option<ConfigValue> {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("js" | "cjs" | "mjs" | "ts") => parse_script(path, content, false),
+        Some("yaml" | "yml") => parse_yaml(content),
+        Some("json") => parse_script(path, content, true),
+        _ => parse_script(path, content, true).or_else(|| parse_yaml(content)),
+    }
+}
+
+/// Read JSON with comments and trailing commas, as tsconfig.json allows
+pub(super) fn parse_json(path: &Path, content: &str) -> Option<ConfigValue> {
+    parse_script(path, content, true)
+}
+
+/// JSON (with comments, as the tools accept) is read as a JS expression,
+/// which keeps the line of every string
+fn parse_script(path: &Path, content: &str, json: bool) -> Option<ConfigValue> {
+    let allocator = Allocator::default();
+    let source = if json {
+        format!("({})", content)
+    } else {
+        content.to_string()
+    };
+    let source_type = if json {
+        SourceType::cjs()
+    } else {
+        SourceType::from_path(path).unwrap_or_default()
+    };
+    let parsed = Parser::new(&allocator, &source, source_type).parse();
+    if json && !parsed.errors.is_empty() {
+        return None;
+    }
+
+    let line = |offset: u32| source[..offset as usize].matches('\n').count() + 1;
+    let mut values: Vec<ConfigValue> = Vec::new();
+    for statement in &parsed.program.body {
+        match statement {
+            // `module.exports = {...}`, or the JSON document
+            Statement::ExpressionStatement(stmt) => {
+                let expr = match stmt.expression.get_inner_expression() {
+                    Expression::AssignmentExpression(assign) => &assign.right,
+                    expr => expr,
+                };
+                values.push(expression_value(expr, &line));
+            }
+            Statement::ExportDefaultDeclaration(decl) => {
+                if let Some(expr) = decl.declaration.as_expression() {
+                    values.push(expression_value(expr, &line));
+                }
+            }
+            // `const config = {...}`, exported further down
+            Statement::VariableDeclaration(decl) => {
+                values.extend(
+                    decl.declarations
+                        .iter()
+                        .filter_map(|d| d.init.as_ref())
+                        .map(|init| expression_value(init, &line)),
+                );
+            }
+            Statement::ExportNamedDeclaration(decl) => {
+                if let Some(Declaration::VariableDeclaration(decl)) = &decl.declaration {
+                    values.extend(
+                        decl.declarations
+                            .iter()
+                            .filter_map(|d| d.init.as_ref())
+                            .map(|init| expression_value(init, &line)),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if json {
+        values.into_iter().next()
+    } else {
+        Some(ConfigValue::List(values))
+    }
+}
+
+fn expression_value(expr: &Expression, line: &impl Fn(u32) -> usize) -> ConfigValue {
+    match expr.get_inner_expression() {
+        Expression::StringLiteral(lit) => {
+            ConfigValue::Str(lit.value.to_string(), line(lit.span.start))
+        }
+        Expression::TemplateLiteral(tpl) if tpl.expressions.is_empty() => {
+            match tpl.quasis.first() {
+                Some(quasi) => ConfigValue::Str(quasi.value.raw.to_string(), line(tpl.span.start)),
+                None => ConfigValue::Other,
+            }
+        }
+        Expression::ArrayExpression(array) => ConfigValue::List(
+            array
+                .elements
+                .iter()
+                .filter_map(|element| element.as_expression())
+                .map(|element| expression_value(element, line))
+                .collect(),
+        ),
+        Expression::ObjectExpression(object) => ConfigValue::Map(
+            object
+                .properties
+                .iter()
+                .filter_map(|property| match property {
+                    ObjectPropertyKind::ObjectProperty(property) => Some((
+                        property.key.static_name()?.to_string(),
+                        line(property.span.start),
+                        expression_value(&property.value, line),
+                    )),
+                    ObjectPropertyKind::SpreadProperty(_) => None,
+                })
+                .collect(),
+        ),
+        Expression::CallExpression(call) if path_call(call).is_some() => path_call(call)
+            .map_or(ConfigValue::Other, |path| {
+                ConfigValue::Str(path, line(call.span.start))
+            }),
+        // `new URL("./src", import.meta.url)`
+        Expression::NewExpression(new) if matches!(&new.callee, Expression::Identifier(id) if id.name == "URL") => {
+            match new.arguments.first().and_then(|a| a.as_expression()) {
+                Some(argument) => expression_value(argument, line),
+                None => ConfigValue::Other,
+            }
+        }
+        // `defineConfig({...})` and similar helpers
+        Expression::CallExpression(call) => match call.arguments.first() {
+            Some(argument) => argument
+                .as_expression()
+                .map_or(ConfigValue::Other, |argument| {
+                    expression_value(argument, line)
+                }),
+            None => ConfigValue::Other,
+        },
+        _ => ConfigValue::Other,
+    }
+}
+
+/// The path `path.resolve(__dirname, "src")` or `join(__dirname, "src")`
+/// builds, relative to the config file: "./src"
+///
+/// `__dirname` and `process.cwd()` stand for the config's directory, which
+/// is where tools run from; any other non-literal argument gives `None`.
+fn path_call(call: &CallExpression) -> Option<String> {
+    let name = match &call.callee {
+        Expression::StaticMemberExpression(member) => match &member.object {
+            Expression::Identifier(object) if object.name == "path" => {
+                member.property.name.as_str()
+            }
+            _ => return None,
+        },
+        Expression::Identifier(id) => id.name.as_str(),
+        _ => return None,
+    };
+    if !matches!(name, "resolve" | "join") {
+        return None;
+    }
+
+    let mut path = String::from(".");
+    for argument in &call.arguments {
+        match argument.as_expression()?.get_inner_expression() {
+            Expression::Identifier(id) if id.name == "__dirname" => {}
+            Expression::CallExpression(cwd) if matches!(&cwd.callee, Expression::StaticMemberExpression(m) if m.property.name == "cwd") =>
+                {}
+            Expression::StringLiteral(lit) if lit.value.starts_with('/') => {
+                path = lit.value.to_string()
+            }
+            Expression::StringLiteral(lit) => {
+                path = format!("{}/{}", path.trim_end_matches('/'), lit.value)
+            }
+            _ => return None,
+        }
+    }
+    Some(path)
+}
+
+/// YAML doesn't give positions; each string is placed on the first line
+/// mentioning it
+fn parse_yaml(content: &str) -> Option<ConfigValue> {
+    let value: serde_yaml::Value = serde_yaml::from_str(content).ok()?;
+
+    fn convert(value: &serde_yaml::Value, content: &str) -> ConfigValue {
+        let line = |text: &str| {
+            content
+                .lines()
+                .position(|l| l.contains(text))
+                .map_or(1, |i| i + 1)
+        };
+        match value {
+            serde_yaml::Value::String(s) => ConfigValue::Str(s.clone(), line(s)),
+            serde_yaml::Value::Sequence(items) => {
+                ConfigValue::List(items.iter().map(|item| convert(item, content)).collect())
+            }
+            serde_yaml::Value::Mapping(map) => ConfigValue::Map(
+                map.iter()
+                    .filter_map(|(key, value)| {
+                        let key = key.as_str()?;
+                        Some((key.to_string(), line(key), convert(value, content)))
+                    })
+                    .collect(),
+            ),
+            _ => ConfigValue::Other,
+        }
+    }
+    Some(convert(&value, content))
+}
+
+/// Packages a tool's configuration loads, as imports of `file`
+///
+/// Fields are looked up at any depth, so ESLint `overrides` and Babel
+/// `env` sections count.
+fn tool_references(tool: Tool, file: &Path, config: &ConfigValue) -> Vec<Import> {
+    let mut references = Vec::new();
+    let mut reference = |specifier: &str, line: usize, package: Option<String>| {
+        let Some(package) = package.as_deref().and_then(extract_package_name) else {
+            return;
+        };
+        references.push(Import {
+            file_path: PathBuf::from(file),
+            line,
+            specifier: specifier.to_string(),
+            kind: ImportKind::Config,
+            resolved_package: Some(package),
+        });
+    };
+
+    let mut stack = vec![config];
+    while let Some(value) = stack.pop() {
+        let fields = match value {
+            ConfigValue::Map(fields) => fields,
+            ConfigValue::List(items) => {
+                stack.extend(items);
+                continue;
+            }
+            _ => continue,
+        };
+        for (key, _, value) in fields {
+            stack.push(value);
+            match (tool, key.as_str()) {
+                (Tool::Eslint, "extends") => {
+                    for (name, line) in strings(value) {
+                        reference(name, line, eslint_config(name));
+                    }
+                }
+                (Tool::Eslint, "plugins") => {
+                    for (name, line) in strings(value) {
+                        reference(name, line, eslint_name(name, "eslint-plugin"));
+                    }
+                }
+                (Tool::Eslint, "parser") | (Tool::Postcss, "parser" | "syntax" | "stringifier") => {
+                    for (name, line) in strings(value) {
+                        reference(name, line, package_path(name));
+                    }
+                }
+                (Tool::Babel, "presets" | "plugins") => {
+                    let kind = if key == "presets" { "preset" } else { "plugin" };
+                    for (name, line) in entry_names(value) {
+                        reference(name, line, babel_name(name, kind));
+                    }
+                }
+                // `plugins: { tailwindcss: {}, autoprefixer: {} }` or a list
+                (Tool::Postcss, "plugins") => match value {
+                    ConfigValue::Map(plugins) => {
+                        for (name, line, _) in plugins {
+                            reference(name, *line, package_path(name));
+                        }
+                    }
+                    _ => {
+                        for (name, line) in entry_names(value) {
+                            reference(name, line, package_path(name));
+                        }
+                    }
+                },
+                (Tool::Tailwind, "plugins" | "presets") => {
+                    for (name, line) in strings(value) {
+                        reference(name, line, package_path(name));
+                    }
+                }
+                (Tool::Jest, "preset" | "testRunner" | "snapshotSerializers" | "watchPlugins")
+                | (Tool::Jest, "setupFiles" | "setupFilesAfterEnv") => {
+                    for (name, line) in strings(value) {
+                        reference(name, line, package_path(name));
+                    }
+                }
+                // `jest-environment-node` ships with Jest
+                (Tool::Jest, "testEnvironment") => {
+                    for (name, line) in strings(value).filter(|(name, _)| *name != "node") {
+                        reference(name, line, eslint_name(name, "jest-environment"));
+                    }
+                }
+                (Tool::Jest, "runner") => {
+                    for (name, line) in strings(value) {
+                        reference(name, line, eslint_name(name, "jest-runner"));
+                    }
+                }
+                (Tool::Jest, "reporters") => {
+                    for (name, line) in entry_names(value)
+                        .filter(|(name, _)| !JEST_BUILTIN_REPORTERS.contains(name))
+                    {
+                        reference(name, line, package_path(name));
+                    }
+                }
+                (Tool::Jest, "transform") => {
+                    if let ConfigValue::Map(transforms) = value {
+                        for (_, _, transformer) in transforms {
+                            for (name, line) in entry_names(transformer) {
+                                reference(name, line, package_path(name));
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    references.sort_by_key(|r| r.line);
+    references
+}
+
+/// A string, or the strings of a list
+pub(super) fn strings(value: &ConfigValue) -> impl Iterator<Item = (&str, usize)> {
+    let items: Vec<&ConfigValue> = match value {
+        ConfigValue::List(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    items.into_iter().filter_map(|item| match item {
+        ConfigValue::Str(s, line) => Some((s.as_str(), *line)),
+        _ => None,
+    })
+}
+
+/// Names of entries given as `"name"` or `["name", options]`
+fn entry_names(value: &ConfigValue) -> impl Iterator<Item = (&str, usize)> {
+    let items: Vec<&ConfigValue> = match value {
+        ConfigValue::List(items) => items.iter().collect(),
+        other => vec![other],
+    };
+    items.into_iter().filter_map(|item| match item {
+        ConfigValue::Str(s, line) => Some((s.as_str(), *line)),
+        ConfigValue::List(entry) => match entry.first() {
+            Some(ConfigValue::Str(s, line)) => Some((s.as_str(), *line)),
+            _ => None,
+        },
+        _ => None,
+    })
+}
+
+/// A package name, or nothing for files (`./plugin.js`, `<rootDir>/setup.ts`)
+fn package_path(name: &str) -> Option<String> {
+    if name.starts_with('.') || name.starts_with('/') || name.starts_with('<') {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+/// An ESLint `extends` entry: a shareable config, or `plugin:<plugin>/<config>`
+///
+/// "airbnb/hooks" -> "eslint-config-airbnb",
+/// "plugin:@typescript-eslint/recommended" -> "@typescript-eslint/eslint-plugin",
+/// "eslint:recommended" -> None
+fn eslint_config(name: &str) -> Option<String> {
+    if name.starts_with("eslint:") {
+        return None;
+    }
+    if let Some(plugin) = name.strip_prefix("plugin:") {
+        let plugin = plugin.rsplit_once('/').map_or(plugin, |(plugin, _)| plugin);
+        return eslint_name(plugin, "eslint-plugin");
+    }
+    eslint_name(name, "eslint-config")
+}
+
+/// Expand a short name the way ESLint (and Jest) resolve them
+///
+/// "react" -> "eslint-plugin-react", "@scope" -> "@scope/eslint-plugin",
+/// "@scope/foo" -> "@scope/eslint-plugin-foo"; full names stay
+fn eslint_name(name: &str, prefix: &str) -> Option<String> {
+    let name = package_path(name)?;
+    Some(match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            None => format!("{}/{}", name, prefix),
+            Some((_, rest)) if rest.starts_with(prefix) => name,
+            Some((scope, rest)) => format!("@{}/{}-{}", scope, prefix, rest),
+        },
+        None if name.starts_with(&format!("{}-", prefix)) || name == prefix => name,
+        None => format!("{}-{}", prefix, name),
+    })
+}
+
+/// Expand a Babel preset or plugin name the way Babel resolves it
+///
+/// "env" -> "babel-preset-env", "@babel/env" -> "@babel/preset-env",
+/// "@scope/foo" -> "@scope/babel-plugin-foo", "module:foo" -> "foo"
+fn babel_name(name: &str, kind: &str) -> Option<String> {
+    if let Some(module) = name.strip_prefix("module:") {
+        return package_path(module);
+    }
+    let name = package_path(name)?;
+    let prefixed = format!("babel-{}", kind);
+    Some(match name.strip_prefix('@') {
+        Some(scoped) => match scoped.split_once('/') {
+            None => format!("{}/{}", name, prefixed),
+            Some(("babel", rest)) if rest.starts_with(&format!("{}-", kind)) => name,
+            Some(("babel", rest)) => format!("@babel/{}-{}", kind, rest),
+            Some((_, rest)) if rest.starts_with(&prefixed) => name,
+            Some((scope, rest)) => format!("@{}/{}-{}", scope, prefixed, rest),
+        },
+        None if name.starts_with(&format!("{}-", prefixed)) || name.contains('/') => name,
+        None => format!("{}-{}", prefixed, name),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_config_references() {
+        let root = TempDir::new("configs");
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write(
+            ".eslintrc.json",
+            r#"{
+                // comments are allowed
+                "extends": ["eslint:recommended", "airbnb/hooks", "plugin:@typescript-eslint/recommended"],
+                "plugins": ["react", "@scope"],
+                "overrides": [{ "files": ["*.test.js"], "plugins": ["jest"] }],
+            }"#,
+        );
+        write(
+            "babel.config.js",
+            "module.exports = {\n  presets: [['@babel/env', { targets: 'defaults' }], 'module:metro-react-native-babel-preset'],\n  plugins: ['./local-plugin', '@babel/plugin-transform-runtime', 'styled-components'],\n};\n",
+        );
+        write(
+            ".postcssrc.yml",
+            "plugins:\n  tailwindcss: {}\n  autoprefixer: {}\n",
+        );
+        write(
+            "jest.config.ts",
+            "const config = {\n  preset: 'ts-jest',\n  testEnvironment: 'jsdom',\n  reporters: ['default', 'jest-junit'],\n  setupFilesAfterEnv: ['<rootDir>/setup.ts', '@testing-library/jest-dom'],\n};\nexport default config;\n",
+        );
+        write(
+            "package.json",
+            r#"{"name": "app", "eslintConfig": {"extends": "next"}}"#,
+        );
+
+        let references = config_references(&root).unwrap();
+
+        let found: Vec<(String, &str, usize)> = references
+            .iter()
+            .map(|r| {
+                (
+                    r.file_path
+                        .file_name()
+                        .unwrap()
+                        .to_string_lossy()
+                        .to_string(),
+                    r.resolved_package.as_deref().unwrap(),
+                    r.line,
+                )
+            })
+            .collect();
+        let found: Vec<(&str, &str, usize)> = found
+            .iter()
+            .map(|(file, package, line)| (file.as_str(), *package, *line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (".eslintrc.json", "eslint-config-airbnb", 3),
+                (".eslintrc.json", "@typescript-eslint/eslint-plugin", 3),
+                (".eslintrc.json", "eslint-plugin-react", 4),
+                (".eslintrc.json", "@scope/eslint-plugin", 4),
+                (".eslintrc.json", "eslint-plugin-jest", 5),
+                ("babel.config.js", "@babel/preset-env", 2),
+                ("babel.config.js", "metro-react-native-babel-preset", 2),
+                ("babel.config.js", "@babel/plugin-transform-runtime", 3),
+                ("babel.config.js", "babel-plugin-styled-components", 3),
+                (".postcssrc.yml", "tailwindcss", 2),
+                (".postcssrc.yml", "autoprefixer", 3),
+                ("jest.config.ts", "ts-jest", 2),
+                ("jest.config.ts", "jest-environment-jsdom", 3),
+                ("jest.config.ts", "jest-junit", 4),
+                ("jest.config.ts", "@testing-library/jest-dom", 5),
+                ("package.json", "eslint-config-next", 1),
+            ]
+        );
+        assert!(references.iter().all(|r| r.kind == ImportKind::Config));
+    }
+
+    #[test]
+    fn test_name_expansion() {
+        assert_eq!(eslint_config("eslint:all"), None);
+        assert_eq!(
+            eslint_config("plugin:react/recommended").as_deref(),
+            Some("eslint-plugin-react")
+        );
+        assert_eq!(
+            eslint_config("@scope/eslint-config-base").as_deref(),
+            Some("@scope/eslint-config-base")
+        );
+        assert_eq!(
+            eslint_name("@scope/foo", "eslint-plugin").as_deref(),
+            Some("@scope/eslint-plugin-foo")
+        );
+        assert_eq!(
+            babel_name("@babel/preset-react", "preset").as_deref(),
+            Some("@babel/preset-react")
+        );
+        assert_eq!(
+            babel_name("@emotion", "plugin").as_deref(),
+            Some("@emotion/babel-plugin")
+        );
+        assert_eq!(babel_name("./my-preset", "preset"), None);
+    }
+}
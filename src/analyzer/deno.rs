@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+
+/// Deno configuration files, in the order Deno looks for them
+const CONFIG_FILES: &[&str] = &["deno.json", "deno.jsonc"];
+
+/// CDNs serving npm packages at `https://<host>/<name>@<version>/<path>`
+const NPM_CDNS: &[&str] = &["esm.sh", "unpkg.com", "cdn.skypack.dev"];
+
+/// The import map of a Deno project
+///
+/// Read from `imports` in deno.json, or from the file its `importMap`
+/// points to. Bare specifiers like `@std/assert` or `oak/mod.ts` resolve
+/// through it to `jsr:`, `npm:` or URL specifiers.
+#[derive(Debug, Default)]
+pub struct DenoImports {
+    imports: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DenoConfig {
+    #[serde(default)]
+    imports: BTreeMap<String, String>,
+    import_map: Option<String>,
+}
+
+impl DenoImports {
+    /// Load the import map, or an empty one outside Deno projects
+    pub fn load(root: &Path) -> Result<Self> {
+        let Some(path) = CONFIG_FILES
+            .iter()
+            .map(|name| root.join(name))
+            .find(|path| path.is_file())
+        else {
+            return Ok(Self::default());
+        };
+
+        let mut config = read_config(&path)?;
+        if let Some(import_map) = &config.import_map {
+            config
+                .imports
+                .extend(read_config(&root.join(import_map))?.imports);
+        }
+
+        Ok(Self {
+            imports: config.imports,
+        })
+    }
+
+    /// Where a specifier points after the import map: an exact key, else
+    /// the longest `prefix/` key it starts with
+    pub fn resolve(&self, specifier: &str) -> Option<String> {
+        if let Some(target) = self.imports.get(specifier) {
+            return Some(target.clone());
+        }
+        self.imports
+            .iter()
+            .filter(|(key, _)| key.ends_with('/') && specifier.starts_with(key.as_str()))
+            .max_by_key(|(key, _)| key.len())
+            .map(|(key, target)| format!("{}{}", target, &specifier[key.len()..]))
+    }
+
+    /// Every specifier the import map points to
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.imports.values().map(|target| target.as_str())
+    }
+}
+
+fn read_config(path: &Path) -> Result<DenoConfig> {
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&strip_comments(&content))
+        .into_diagnostic()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Package named by an `npm:`, `jsr:` or URL specifier
+///
+/// Examples:
+/// - "npm:chalk@5/ansi" -> "chalk"
+/// - "jsr:@std/assert@^1.0.0" -> "@std/assert"
+/// - "https://deno.land/x/oak@v12.6.1/mod.ts" -> "deno.land/x/oak"
+/// - "https://esm.sh/react@18.2.0" -> "react"
+pub fn specifier_package(specifier: &str) -> Option<String> {
+    if let Some(spec) = specifier
+        .strip_prefix("npm:")
+        .or_else(|| specifier.strip_prefix("jsr:"))
+    {
+        let (name, _) = split_spec(spec.trim_start_matches('/'))?;
+        return Some(name);
+    }
+    remote_package(specifier).map(|(name, _)| name)
+}
+
+/// Package and version a remote module belongs to
+///
+/// npm CDNs give the npm package; deno.land modules are named
+/// "deno.land/x/<module>" and "deno.land/std". Other hosts can't be split
+/// into packages, so the host stands for all of its modules.
+pub fn remote_package(url: &str) -> Option<(String, String)> {
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let rest = rest.split(['?', '#']).next()?;
+    let (host, path) = rest.split_once('/').unwrap_or((rest, ""));
+    if host.is_empty() {
+        return None;
+    }
+
+    let npm_path = match host {
+        "cdn.jsdelivr.net" => path.strip_prefix("npm/"),
+        // esm.sh paths may start with a build version, like "/v135/react@18.2.0"
+        "esm.sh" => Some(
+            path.strip_prefix("stable/")
+                .or_else(|| {
+                    let (first, rest) = path.split_once('/')?;
+                    let build = first.strip_prefix('v')?;
+                    build.chars().all(|c| c.is_ascii_digit()).then_some(rest)
+                })
+                .unwrap_or(path),
+        ),
+        _ if NPM_CDNS.contains(&host) => Some(path),
+        _ => None,
+    };
+    if let Some(spec) = npm_path {
+        return split_spec(spec.trim_start_matches('*'));
+    }
+
+    if host == "deno.land" {
+        let (module, version) = match path.strip_prefix("x/") {
+            Some(rest) => {
+                let module = rest.split('/').next()?;
+                let (name, version) = module.split_once('@').unwrap_or((module, ""));
+                (format!("deno.land/x/{}", name), version)
+            }
+            None => {
+                let first = path.split('/').next()?;
+                let (name, version) = first.split_once('@').unwrap_or((first, ""));
+                (format!("deno.land/{}", name), version)
+            }
+        };
+        return Some((module, version.to_string()));
+    }
+
+    Some((host.to_string(), String::new()))
+}
+
+/// npm package served by a CDN URL; other hosts give `None`
+///
+/// "https://cdn.jsdelivr.net/npm/lit@3/+esm" -> "lit"
+pub fn cdn_package(url: &str) -> Option<String> {
+    let (name, _) = remote_package(url)?;
+    let rest = url.split_once("://")?.1;
+    let host = rest.split('/').next()?;
+    let is_cdn = host == "cdn.jsdelivr.net" || NPM_CDNS.contains(&host);
+    (is_cdn && name != host).then_some(name)
+}
+
+/// Split "name@version/path" (name possibly scoped) into name and version
+pub fn split_spec(spec: &str) -> Option<(String, String)> {
+    // Scoped names have a second segment before the version
+    let scope_end = if spec.starts_with('@') {
+        spec.find('/')? + 1
+    } else {
+        0
+    };
+    let end = spec[scope_end..]
+        .find('/')
+        .map_or(spec.len(), |i| scope_end + i);
+    let ident = &spec[..end];
+    let (name, version) = match ident.get(1..)?.rfind('@') {
+        Some(at) => (&ident[..at + 1], &ident[at + 2..]),
+        None => (ident, ""),
+    };
+    Some((name.to_string(), version.to_string()))
+}
+
+/// Drop `//` and `/* */` comments outside strings, for deno.jsonc
+fn strip_comments(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    let mut escaped = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_specifier_package() {
+        let cases = [
+            ("npm:chalk@5/ansi", Some("chalk")),
+            ("npm:@types/node", Some("@types/node")),
+            ("npm:/preact@10", Some("preact")),
+            ("jsr:@std/assert@^1.0.0", Some("@std/assert")),
+            (
+                "https://deno.land/x/oak@v12.6.1/mod.ts",
+                Some("deno.land/x/oak"),
+            ),
+            (
+                "https://deno.land/std@0.200.0/path/mod.ts",
+                Some("deno.land/std"),
+            ),
+            ("https://esm.sh/react@18.2.0", Some("react")),
+            (
+                "https://esm.sh/v135/@preact/signals@1.2.1/es2022/x.js",
+                Some("@preact/signals"),
+            ),
+            (
+                "https://cdn.jsdelivr.net/npm/lodash-es@4/map.js",
+                Some("lodash-es"),
+            ),
+            (
+                "https://raw.githubusercontent.com/a/b/main/mod.ts",
+                Some("raw.githubusercontent.com"),
+            ),
+            ("lodash", None),
+        ];
+        for (specifier, expected) in cases {
+            assert_eq!(
+                specifier_package(specifier).as_deref(),
+                expected,
+                "{}",
+                specifier
+            );
+        }
+
+        assert_eq!(
+            remote_package("https://deno.land/x/oak@v12.6.1/mod.ts"),
+            Some(("deno.land/x/oak".to_string(), "v12.6.1".to_string()))
+        );
+        assert_eq!(
+            remote_package("https://esm.sh/@preact/signals@1.2.1"),
+            Some(("@preact/signals".to_string(), "1.2.1".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_import_map() {
+        let root = TempDir::new("deno-imports");
+        std::fs::write(
+            root.join("deno.jsonc"),
+            r#"{
+  // Dependencies
+  "imports": {
+    "@std/assert": "jsr:@std/assert@^1.0.0",
+    "oak/": "https://deno.land/x/oak@v12.6.1/" /* pinned */
+  },
+  "importMap": "./import_map.json"
+}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("import_map.json"),
+            r#"{ "imports": { "chalk": "npm:chalk@5", "@/": "./src/" } }"#,
+        )
+        .unwrap();
+
+        let imports = DenoImports::load(&root).unwrap();
+
+        assert_eq!(
+            imports.resolve("@std/assert").as_deref(),
+            Some("jsr:@std/assert@^1.0.0")
+        );
+        assert_eq!(
+            imports.resolve("oak/mod.ts").as_deref(),
+            Some("https://deno.land/x/oak@v12.6.1/mod.ts")
+        );
+        assert_eq!(imports.resolve("chalk").as_deref(), Some("npm:chalk@5"));
+        assert_eq!(
+            imports.resolve("@/util.ts").as_deref(),
+            Some("./src/util.ts")
+        );
+        assert_eq!(imports.resolve("lodash"), None);
+    }
+}
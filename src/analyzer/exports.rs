@@ -0,0 +1,250 @@
+use std::path::Path;
+
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{
+    Argument, BindingPattern, BindingPatternKind, CallExpression, Declaration, Expression,
+    ImportDeclarationSpecifier, ImportExpression, Statement, TSModuleDeclarationName,
+};
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+
+use super::script_source;
+
+/// The names a module exports and the names it takes from other modules
+#[derive(Debug, Default)]
+pub struct ModuleSymbols {
+    /// Names declared or re-exported by name (`default` included)
+    pub exports: Vec<String>,
+
+    /// Specifiers of `export * from '...'`, whose names pass through
+    pub star_exports: Vec<String>,
+
+    pub imports: Vec<SymbolImport>,
+}
+
+/// What one import takes from a module
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolImport {
+    pub specifier: String,
+
+    /// The imported names, or `None` when the whole module is taken
+    /// (`import * as`, `require()`, `import()`, `export * from`)
+    pub names: Option<Vec<String>>,
+}
+
+impl ModuleSymbols {
+    /// Parse `source`; a file that doesn't parse yields what could be read
+    pub fn extract(path: &Path, source: &str) -> Self {
+        let allocator = Allocator::default();
+        let script = script_source(path, source);
+        let (source, source_type) = match &script {
+            Some(script) => (script.as_str(), SourceType::ts()),
+            None => (source, SourceType::from_path(path).unwrap_or_default()),
+        };
+        let parsed = Parser::new(&allocator, source, source_type).parse();
+
+        let mut visitor = SymbolVisitor::default();
+        for statement in &parsed.program.body {
+            visitor.top_level(statement);
+        }
+        // require() and import() may sit anywhere
+        visitor.visit_program(&parsed.program);
+        visitor.symbols
+    }
+}
+
+#[derive(Default)]
+struct SymbolVisitor {
+    symbols: ModuleSymbols,
+}
+
+impl SymbolVisitor {
+    fn import(&mut self, specifier: &str, names: Option<Vec<String>>) {
+        self.symbols.imports.push(SymbolImport {
+            specifier: specifier.to_string(),
+            names,
+        });
+    }
+
+    /// Exports and static imports, which only appear at the top level
+    fn top_level(&mut self, statement: &Statement) {
+        match statement {
+            Statement::ImportDeclaration(decl) => {
+                let names = match &decl.specifiers {
+                    // `import './polyfill'` runs the module but takes no names
+                    None => Some(Vec::new()),
+                    Some(specifiers) => specifiers
+                        .iter()
+                        .map(|specifier| match specifier {
+                            ImportDeclarationSpecifier::ImportSpecifier(s) => {
+                                Some(s.imported.name().to_string())
+                            }
+                            ImportDeclarationSpecifier::ImportDefaultSpecifier(_) => {
+                                Some("default".to_string())
+                            }
+                            ImportDeclarationSpecifier::ImportNamespaceSpecifier(_) => None,
+                        })
+                        .collect(),
+                };
+                self.import(&decl.source.value, names);
+            }
+            Statement::ExportNamedDeclaration(decl) => {
+                if let Some(declaration) = &decl.declaration {
+                    declared_names(declaration, &mut self.symbols.exports);
+                }
+                for specifier in &decl.specifiers {
+                    self.symbols
+                        .exports
+                        .push(specifier.exported.name().to_string());
+                }
+                if let Some(source) = &decl.source {
+                    let names = decl
+                        .specifiers
+                        .iter()
+                        .map(|s| s.local.name().to_string())
+                        .collect();
+                    self.import(&source.value, Some(names));
+                }
+            }
+            Statement::ExportDefaultDeclaration(_) => {
+                self.symbols.exports.push("default".to_string());
+            }
+            Statement::ExportAllDeclaration(decl) => {
+                match &decl.exported {
+                    Some(name) => self.symbols.exports.push(name.name().to_string()),
+                    None => self
+                        .symbols
+                        .star_exports
+                        .push(decl.source.value.to_string()),
+                }
+                self.import(&decl.source.value, None);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Names bound by an exported declaration
+fn declared_names(declaration: &Declaration, names: &mut Vec<String>) {
+    match declaration {
+        Declaration::VariableDeclaration(decl) => {
+            for declarator in &decl.declarations {
+                binding_names(&declarator.id, names);
+            }
+        }
+        Declaration::TSModuleDeclaration(decl) => {
+            if let TSModuleDeclarationName::Identifier(id) = &decl.id {
+                names.push(id.name.to_string());
+            }
+        }
+        other => names.extend(other.id().map(|id| id.name.to_string())),
+    }
+}
+
+/// `const { a, b: [c] } = ...` binds a and c
+fn binding_names(pattern: &BindingPattern, names: &mut Vec<String>) {
+    match &pattern.kind {
+        BindingPatternKind::BindingIdentifier(id) => names.push(id.name.to_string()),
+        BindingPatternKind::ObjectPattern(object) => {
+            for property in &object.properties {
+                binding_names(&property.value, names);
+            }
+            if let Some(rest) = &object.rest {
+                binding_names(&rest.argument, names);
+            }
+        }
+        BindingPatternKind::ArrayPattern(array) => {
+            for element in array.elements.iter().flatten() {
+                binding_names(element, names);
+            }
+            if let Some(rest) = &array.rest {
+                binding_names(&rest.argument, names);
+            }
+        }
+        BindingPatternKind::AssignmentPattern(assignment) => {
+            binding_names(&assignment.left, names);
+        }
+    }
+}
+
+impl<'a> Visit<'a> for SymbolVisitor {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let Expression::Identifier(ident) = &call.callee {
+            if ident.name == "require" {
+                if let Some(Argument::StringLiteral(lit)) = call.arguments.first() {
+                    self.import(&lit.value, None);
+                }
+            }
+        }
+        walk::walk_call_expression(self, call);
+    }
+
+    fn visit_import_expression(&mut self, import_expr: &ImportExpression<'a>) {
+        if let Expression::StringLiteral(lit) = &import_expr.source {
+            self.import(&lit.value, None);
+        }
+        walk::walk_import_expression(self, import_expr);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_symbols() {
+        let source = r#"
+import def, { parse as p, format } from 'my-lib';
+import * as all from 'my-lib/utils';
+import './setup';
+export const VERSION = '1', { a, b: [c] } = obj;
+export function helper() {}
+export class Parser {}
+export interface Options {}
+export type Mode = 'a' | 'b';
+export { p as parse, format };
+export { tokenize } from './tokenize';
+export * from './nodes';
+export * as visitors from './visitors';
+export default helper;
+const lazy = () => require('lazy-lib');
+"#;
+        let symbols = ModuleSymbols::extract(Path::new("index.ts"), source);
+
+        assert_eq!(
+            symbols.exports,
+            vec![
+                "VERSION", "a", "c", "helper", "Parser", "Options", "Mode", "parse", "format",
+                "tokenize", "visitors", "default"
+            ]
+        );
+        assert_eq!(symbols.star_exports, vec!["./nodes"]);
+
+        let imports: Vec<(&str, Option<Vec<&str>>)> = symbols
+            .imports
+            .iter()
+            .map(|i| {
+                (
+                    i.specifier.as_str(),
+                    i.names
+                        .as_ref()
+                        .map(|n| n.iter().map(|s| s.as_str()).collect()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            imports,
+            vec![
+                ("my-lib", Some(vec!["default", "parse", "format"])),
+                ("my-lib/utils", None),
+                ("./setup", Some(vec![])),
+                ("./tokenize", Some(vec!["tokenize"])),
+                ("./nodes", None),
+                ("./visitors", None),
+                ("lazy-lib", None),
+            ]
+        );
+    }
+}
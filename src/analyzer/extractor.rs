@@ -1,14 +1,23 @@
+use std::collections::HashSet;
 use std::path::Path;
 
 use miette::Result;
 use oxc_allocator::Allocator;
-use oxc_ast::ast::{Argument, Expression, Statement};
+use oxc_ast::ast::{
+    Argument, BinaryOperator, BindingPatternKind, CallExpression, ExportAllDeclaration,
+    ExportNamedDeclaration, Expression, ImportDeclaration, ImportDeclarationSpecifier,
+    ImportExpression, TSImportType, TSLiteral, TSType, TaggedTemplateExpression, TemplateLiteral,
+    VariableDeclarator,
+};
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
 use oxc_parser::Parser;
-use oxc_span::SourceType;
+use oxc_span::{GetSpan, SourceType, Span};
 
-use crate::types::{Import, ImportKind};
+use crate::types::{CommandInvocation, DynamicImport, Import, ImportKind, LocalImport};
 
-use super::extract_package_name;
+use super::commands::{invoked_binaries, BINARY_CALLS, COMMAND_LINE_CALLS};
+use super::{extract_package_name, script_source, styles};
 
 /// Extracts imports from a single JavaScript/TypeScript file
 pub struct ImportExtractor<'a> {
@@ -21,12 +30,36 @@ impl<'a> ImportExtractor<'a> {
         Self { path, source }
     }
 
+    #[allow(dead_code)]
     pub fn extract(&self) -> Result<Vec<Import>> {
-        let allocator = Allocator::default();
+        Ok(self.extract_all()?.imports)
+    }
 
-        let source_type = SourceType::from_path(self.path).unwrap_or_default();
+    /// Extract package imports, command-line tools the file runs through
+    /// child_process or execa, and relative imports of other project files
+    pub fn extract_all(&self) -> Result<Extraction> {
+        // Stylesheets only have package imports, and aren't JavaScript
+        if let Some(imports) = styles::style_imports(self.path, self.source) {
+            return Ok(Extraction {
+                imports,
+                ..Extraction::default()
+            });
+        }
 
-        let parser = Parser::new(&allocator, self.source, source_type);
+        let allocator = Allocator::default();
+
+        // Components and Markdown are parsed by their scripts alone, which
+        // keep the file's offsets
+        let script = script_source(self.path, self.source);
+        let (source, source_type) = match &script {
+            Some(script) => (script.as_str(), SourceType::ts()),
+            None => (
+                self.source,
+                SourceType::from_path(self.path).unwrap_or_default(),
+            ),
+        };
+
+        let parser = Parser::new(&allocator, source, source_type);
         let parsed = parser.parse();
 
         // We continue even if there are parse errors - partial results are better than none
@@ -34,168 +67,541 @@ impl<'a> ImportExtractor<'a> {
             // Could log warnings here if needed
         }
 
-        let mut imports = Vec::new();
+        // Walk the whole tree so imports nested in functions, callbacks and
+        // class bodies are found too (lazy `import()` calls usually live there)
+        let mut visitor = ImportVisitor {
+            extractor: self,
+            extraction: Extraction::default(),
+            declarations: source_type.is_typescript_definition(),
+            require_aliases: HashSet::new(),
+        };
+        visitor.visit_program(&parsed.program);
+
+        Ok(visitor.extraction)
+    }
 
-        for stmt in &parsed.program.body {
-            self.extract_from_statement(stmt, &mut imports);
+    fn line_number(&self, offset: u32) -> usize {
+        self.source[..offset as usize]
+            .chars()
+            .filter(|c| *c == '\n')
+            .count()
+            + 1
+    }
+}
+
+/// Everything found in a single file
+#[derive(Debug, Default)]
+pub struct Extraction {
+    pub imports: Vec<Import>,
+    pub commands: Vec<CommandInvocation>,
+
+    /// Relative imports of other project files, not yet resolved
+    pub local_imports: Vec<LocalImport>,
+
+    /// Every import specifier's string literal, quotes included, for
+    /// rewriting it in place
+    pub specifiers: Vec<SpecifierSpan>,
+
+    /// `import()` and `require()` calls whose specifier is built at runtime
+    /// without a static prefix naming a package
+    pub dynamic_imports: Vec<DynamicImport>,
+}
+
+/// Where an import specifier's string literal sits in the source
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpecifierSpan {
+    pub specifier: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+struct ImportVisitor<'e, 's> {
+    extractor: &'e ImportExtractor<'s>,
+    extraction: Extraction,
+
+    /// Whether the file is a `.d.ts`, where every import is a type import
+    declarations: bool,
+
+    /// Names bound to `createRequire(import.meta.url)`, which work like
+    /// `require`
+    require_aliases: HashSet<String>,
+}
+
+impl ImportVisitor<'_, '_> {
+    /// Record an import; `literal` is the span of the string literal it's
+    /// written as, if it's one that can be rewritten
+    fn push(&mut self, specifier: &str, literal: Option<Span>, offset: u32, kind: ImportKind) {
+        let kind = if self.declarations {
+            ImportKind::Type
+        } else {
+            kind
+        };
+        if let Some(literal) = literal {
+            self.extraction.specifiers.push(SpecifierSpan {
+                specifier: specifier.to_string(),
+                start: literal.start,
+                end: literal.end,
+            });
+        }
+        if specifier.starts_with("./") || specifier.starts_with("../") {
+            self.extraction.local_imports.push(LocalImport {
+                line: self.extractor.line_number(offset),
+                specifier: specifier.to_string(),
+                target: None,
+            });
+        }
+        if let Some(package_name) = extract_package_name(specifier) {
+            self.extraction.imports.push(Import {
+                file_path: self.extractor.path.to_path_buf(),
+                line: self.extractor.line_number(offset),
+                specifier: specifier.to_string(),
+                kind,
+                resolved_package: Some(package_name),
+            });
+        }
+    }
+
+    /// An import whose specifier is an expression: a template literal or a
+    /// concatenation (`import(`./locales/${lang}.json`)`, `require('pkg/' + name)`)
+    ///
+    /// A static prefix that spells out a package name counts as importing
+    /// that package. Paths into the project, `node:` modules and paths built
+    /// with `path.join()` or `__dirname` can't name a package and are left
+    /// out; the rest are kept as unresolved dynamic imports.
+    fn push_expression(&mut self, argument: &Expression, offset: u32, kind: ImportKind) {
+        let (prefix, complete) = specifier_prefix(argument);
+        if complete {
+            self.push(&prefix, None, offset, kind);
+            return;
+        }
+        if prefix.starts_with(['.', '/']) || prefix.starts_with("node:") {
+            return;
+        }
+        if let Some(specifier) = package_prefix(&prefix) {
+            self.push(specifier, None, offset, kind);
+            return;
+        }
+        if is_path_expression(leftmost_operand(argument)) {
+            return;
         }
 
-        Ok(imports)
+        let span = argument.span();
+        let text = &self.extractor.source[span.start as usize..span.end as usize];
+        let mut expression: String = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if expression.chars().count() > MAX_EXPRESSION_LENGTH {
+            expression = expression.chars().take(MAX_EXPRESSION_LENGTH).collect();
+            expression.push_str("...");
+        }
+        self.extraction.dynamic_imports.push(DynamicImport {
+            file_path: self.extractor.path.to_path_buf(),
+            line: self.extractor.line_number(offset),
+            expression,
+        });
     }
 
-    fn extract_from_statement(&self, stmt: &Statement, imports: &mut Vec<Import>) {
-        match stmt {
-            // ES6 imports: import x from 'package'
-            Statement::ImportDeclaration(decl) => {
-                let specifier = decl.source.value.as_str();
-                let line = self.line_number(decl.span.start);
+    fn is_require(&self, name: &str) -> bool {
+        name == "require" || self.require_aliases.contains(name)
+    }
 
-                if let Some(package_name) = extract_package_name(specifier) {
-                    imports.push(Import {
-                        file_path: self.path.to_path_buf(),
-                        line,
-                        specifier: specifier.to_string(),
-                        kind: ImportKind::EsModule,
-                        resolved_package: Some(package_name),
-                    });
-                }
+    /// The kind of import a call is, if it loads, resolves or mocks a
+    /// module: `require('pkg')`, `createRequire(import.meta.url)('pkg')`,
+    /// `require.resolve('pkg')`, `import.meta.resolve('pkg')` or
+    /// `jest.mock('pkg')`
+    fn module_call_kind(&self, callee: &Expression) -> Option<ImportKind> {
+        match callee {
+            Expression::Identifier(ident) if self.is_require(&ident.name) => {
+                Some(ImportKind::CommonJs)
             }
-
-            // Re-exports: export { x } from 'package'
-            Statement::ExportNamedDeclaration(decl) => {
-                if let Some(source) = &decl.source {
-                    let specifier = source.value.as_str();
-                    let line = self.line_number(decl.span.start);
-
-                    if let Some(package_name) = extract_package_name(specifier) {
-                        imports.push(Import {
-                            file_path: self.path.to_path_buf(),
-                            line,
-                            specifier: specifier.to_string(),
-                            kind: ImportKind::ReExport,
-                            resolved_package: Some(package_name),
-                        });
+            callee if is_create_require(callee) => Some(ImportKind::CommonJs),
+            Expression::StaticMemberExpression(member) if member.property.name == "resolve" => {
+                match &member.object {
+                    Expression::Identifier(ident) if self.is_require(&ident.name) => {
+                        Some(ImportKind::Resolve)
+                    }
+                    Expression::MetaProperty(meta)
+                        if meta.meta.name == "import" && meta.property.name == "meta" =>
+                    {
+                        Some(ImportKind::Resolve)
                     }
+                    _ => None,
                 }
             }
+            Expression::StaticMemberExpression(member) => {
+                let Expression::Identifier(object) = &member.object else {
+                    return None;
+                };
+                let mocks = match object.name.as_str() {
+                    "jest" => JEST_MOCK_CALLS,
+                    "vi" => VITEST_MOCK_CALLS,
+                    name if self.is_require(name) => REQUIRE_MOCK_CALLS,
+                    _ => return None,
+                };
+                mocks
+                    .contains(&member.property.name.as_str())
+                    .then_some(ImportKind::Mock)
+            }
+            _ => None,
+        }
+    }
+
+    fn push_commands(&mut self, command_line: &str, offset: u32) {
+        let line = self.extractor.line_number(offset);
+        for binary in invoked_binaries(command_line) {
+            self.extraction.commands.push(CommandInvocation {
+                file_path: self.extractor.path.to_path_buf(),
+                line,
+                binary,
+            });
+        }
+    }
+
+    fn imports_execa(&self) -> bool {
+        self.extraction
+            .imports
+            .iter()
+            .any(|import| import.resolved_package.as_deref() == Some("execa"))
+    }
 
-            // export * from 'package'
-            Statement::ExportAllDeclaration(decl) => {
-                let specifier = decl.source.value.as_str();
-                let line = self.line_number(decl.span.start);
-
-                if let Some(package_name) = extract_package_name(specifier) {
-                    imports.push(Import {
-                        file_path: self.path.to_path_buf(),
-                        line,
-                        specifier: specifier.to_string(),
-                        kind: ImportKind::ReExport,
-                        resolved_package: Some(package_name),
-                    });
+    /// `exec('eslint .')`, `spawn('npx', ['prettier'])`, `child_process.execSync(...)`
+    fn extract_command(&mut self, call: &CallExpression) {
+        let callee = match &call.callee {
+            Expression::Identifier(ident) => {
+                let name = ident.name.as_str();
+                // A bare `sync()` or `command()` is glob's, commander's or
+                // anyone's; it's execa's only in a file importing execa
+                if matches!(name, "command" | "commandSync" | "sync") && !self.imports_execa() {
+                    return;
                 }
+                name
             }
-
-            // Look for require() calls and dynamic imports in expression statements
-            Statement::ExpressionStatement(expr_stmt) => {
-                self.extract_from_expression(&expr_stmt.expression, imports);
+            Expression::StaticMemberExpression(member) => {
+                let method = member.property.name.as_str();
+                // `re.exec(str)` and `execa.sync()` share names with other APIs, so
+                // only trust them on a known receiver
+                let receiver = match &member.object {
+                    Expression::Identifier(ident) => ident.name.as_str(),
+                    _ => "",
+                };
+                let known_receiver =
+                    matches!(receiver, "child_process" | "childProcess" | "cp" | "execa");
+                if matches!(method, "exec" | "command" | "commandSync" | "sync") && !known_receiver
+                {
+                    return;
+                }
+                method
             }
-
-            // Variable declarations might contain require() or import()
-            Statement::VariableDeclaration(var_decl) => {
-                for declarator in &var_decl.declarations {
-                    if let Some(init) = &declarator.init {
-                        self.extract_from_expression(init, imports);
+            _ => return,
+        };
+
+        let Some(first) = call.arguments.first().and_then(|a| a.as_expression()) else {
+            return;
+        };
+        let Some(command) = static_string(first) else {
+            return;
+        };
+
+        if COMMAND_LINE_CALLS.contains(&callee) {
+            self.push_commands(&command, call.span.start);
+        } else if BINARY_CALLS.contains(&callee) {
+            // Rebuild the command line so runners like `npx` see their arguments
+            let mut command_line = command;
+            if let Some(Argument::ArrayExpression(args)) = call.arguments.get(1) {
+                for element in &args.elements {
+                    match element.as_expression().and_then(static_string) {
+                        Some(arg) => {
+                            command_line.push(' ');
+                            command_line.push_str(&arg);
+                        }
+                        None => break,
                     }
                 }
             }
-
-            _ => {}
+            // Arguments are passed verbatim, never through a shell
+            let command_line = command_line.replace(['&', '|', ';'], " ");
+            self.push_commands(&command_line, call.span.start);
         }
     }
+}
 
-    fn extract_from_expression(&self, expr: &Expression, imports: &mut Vec<Import>) {
-        match expr {
-            // require('package')
-            Expression::CallExpression(call) => {
-                // Check for require()
-                if let Expression::Identifier(ident) = &call.callee {
-                    if ident.name == "require" {
-                        if let Some(first_arg) = call.arguments.first() {
-                            if let Argument::StringLiteral(lit) = first_arg {
-                                let specifier = lit.value.as_str();
-                                let line = self.line_number(call.span.start);
-
-                                if let Some(package_name) = extract_package_name(specifier) {
-                                    imports.push(Import {
-                                        file_path: self.path.to_path_buf(),
-                                        line,
-                                        specifier: specifier.to_string(),
-                                        kind: ImportKind::CommonJs,
-                                        resolved_package: Some(package_name),
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
+/// Jest's `jest` methods taking a module name
+const JEST_MOCK_CALLS: &[&str] = &[
+    "mock",
+    "doMock",
+    "unmock",
+    "dontMock",
+    "setMock",
+    "requireActual",
+    "requireMock",
+    "createMockFromModule",
+];
+
+/// Vitest's `vi` methods taking a module name
+const VITEST_MOCK_CALLS: &[&str] = &[
+    "mock",
+    "doMock",
+    "unmock",
+    "doUnmock",
+    "importActual",
+    "importMock",
+];
+
+/// What Jest adds to `require` in test files
+const REQUIRE_MOCK_CALLS: &[&str] = &["requireActual", "requireMock"];
+
+/// How much of an unresolved specifier expression is kept for reports
+const MAX_EXPRESSION_LENGTH: usize = 60;
+
+/// `createRequire(import.meta.url)` or `module.createRequire(__filename)`
+fn is_create_require(expr: &Expression) -> bool {
+    let Expression::CallExpression(call) = expr else {
+        return false;
+    };
+    match &call.callee {
+        Expression::Identifier(ident) => ident.name == "createRequire",
+        Expression::StaticMemberExpression(member) => member.property.name == "createRequire",
+        _ => false,
+    }
+}
 
-                // Recursively check arguments for nested requires/imports
-                for arg in &call.arguments {
-                    if let Argument::SpreadElement(spread) = arg {
-                        self.extract_from_expression(&spread.argument, imports);
-                    } else if let Some(expr) = arg.as_expression() {
-                        self.extract_from_expression(expr, imports);
-                    }
-                }
-            }
+/// The value of a string literal, or the static prefix of a template literal
+fn static_string(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::StringLiteral(lit) => Some(lit.value.to_string()),
+        Expression::TemplateLiteral(template) => Some(template_prefix(template)),
+        _ => None,
+    }
+}
 
-            // Dynamic import: import('package')
-            Expression::ImportExpression(import_expr) => {
-                if let Expression::StringLiteral(lit) = &import_expr.source {
-                    let specifier = lit.value.as_str();
-                    let line = self.line_number(import_expr.span.start);
-
-                    if let Some(package_name) = extract_package_name(specifier) {
-                        imports.push(Import {
-                            file_path: self.path.to_path_buf(),
-                            line,
-                            specifier: specifier.to_string(),
-                            kind: ImportKind::Dynamic,
-                            resolved_package: Some(package_name),
-                        });
-                    }
-                }
+/// The static start of a specifier expression, and whether that's all of it
+///
+/// Follows `+` concatenations and template literals left to right until the
+/// first part only known at runtime.
+fn specifier_prefix(expr: &Expression) -> (String, bool) {
+    match expr {
+        Expression::StringLiteral(lit) => (lit.value.to_string(), true),
+        Expression::TemplateLiteral(template) => {
+            let text = template
+                .quasis
+                .first()
+                .and_then(|quasi| quasi.value.cooked.as_ref())
+                .map_or("", |cooked| cooked.as_str());
+            (text.to_string(), template.expressions.is_empty())
+        }
+        Expression::BinaryExpression(binary) if binary.operator == BinaryOperator::Addition => {
+            let (mut prefix, complete) = specifier_prefix(&binary.left);
+            if !complete {
+                return (prefix, false);
             }
+            let (rest, complete) = specifier_prefix(&binary.right);
+            prefix.push_str(&rest);
+            (prefix, complete)
+        }
+        Expression::ParenthesizedExpression(inner) => specifier_prefix(&inner.expression),
+        _ => (String::new(), false),
+    }
+}
 
-            // Recurse into other expressions
-            Expression::AwaitExpression(await_expr) => {
-                self.extract_from_expression(&await_expr.argument, imports);
-            }
+/// The part of a specifier prefix up to its last `/`, when that holds a whole
+/// package name ("lodash/" -> "lodash", "@scope/pkg/locale/" ->
+/// "@scope/pkg/locale"), not just part of one ("@scope/", "lodash.")
+fn package_prefix(prefix: &str) -> Option<&str> {
+    let end = prefix.rfind('/')?;
+    let slashes_needed = if prefix.starts_with('@') { 2 } else { 1 };
+    (prefix.matches('/').count() >= slashes_needed).then_some(&prefix[..end])
+}
+
+/// The first part of a concatenation or template literal that isn't a string
+fn leftmost_operand<'e, 'a>(expr: &'e Expression<'a>) -> &'e Expression<'a> {
+    match expr {
+        Expression::BinaryExpression(binary) if binary.operator == BinaryOperator::Addition => {
+            leftmost_operand(&binary.left)
+        }
+        Expression::TemplateLiteral(template)
+            if template
+                .quasis
+                .first()
+                .is_some_and(|q| q.value.raw.is_empty()) =>
+        {
+            template.expressions.first().map_or(expr, leftmost_operand)
+        }
+        Expression::ParenthesizedExpression(inner) => leftmost_operand(&inner.expression),
+        expr => expr,
+    }
+}
+
+/// A file path built at runtime: `__dirname`, `path.join(...)`,
+/// `process.cwd()`, `pathToFileURL(...).href`
+fn is_path_expression(expr: &Expression) -> bool {
+    match expr {
+        Expression::Identifier(ident) => matches!(ident.name.as_str(), "__dirname" | "__filename"),
+        Expression::CallExpression(call) => match &call.callee {
+            Expression::Identifier(ident) => matches!(
+                ident.name.as_str(),
+                "join" | "resolve" | "pathToFileURL" | "fileURLToPath"
+            ),
+            Expression::StaticMemberExpression(member) => matches!(
+                (&member.object, member.property.name.as_str()),
+                (Expression::Identifier(object), "join" | "resolve" | "cwd")
+                    if matches!(object.name.as_str(), "path" | "process" | "url")
+            ),
+            _ => false,
+        },
+        Expression::StaticMemberExpression(member) => {
+            member.property.name == "href" && is_path_expression(&member.object)
+        }
+        Expression::NewExpression(new) => {
+            matches!(&new.callee, Expression::Identifier(ident) if ident.name == "URL")
+        }
+        _ => false,
+    }
+}
+
+/// The text of a template literal up to its first interpolation, dropping a
+/// word cut short by it (`eslint ${dir}` -> "eslint ", `${bin} .` -> "")
+fn template_prefix(template: &TemplateLiteral) -> String {
+    let Some(first) = template.quasis.first() else {
+        return String::new();
+    };
+    let text = first.value.raw.as_str();
+    if template.expressions.is_empty() {
+        return text.to_string();
+    }
+    match text.rfind(char::is_whitespace) {
+        Some(i) => text[..i].to_string(),
+        None => String::new(),
+    }
+}
 
-            Expression::ConditionalExpression(cond) => {
-                self.extract_from_expression(&cond.consequent, imports);
-                self.extract_from_expression(&cond.alternate, imports);
+impl<'a> Visit<'a> for ImportVisitor<'_, '_> {
+    // ES6 imports: import x from 'package', import type { X } from 'package',
+    // import { type X, type Y } from 'package'
+    fn visit_import_declaration(&mut self, decl: &ImportDeclaration<'a>) {
+        let type_specifiers = decl.specifiers.as_ref().is_some_and(|specifiers| {
+            !specifiers.is_empty()
+                && specifiers.iter().all(|specifier| match specifier {
+                    ImportDeclarationSpecifier::ImportSpecifier(s) => s.import_kind.is_type(),
+                    _ => false,
+                })
+        });
+        let kind = if decl.import_kind.is_type() || type_specifiers {
+            ImportKind::Type
+        } else {
+            ImportKind::EsModule
+        };
+        self.push(
+            decl.source.value.as_str(),
+            Some(decl.source.span),
+            decl.span.start,
+            kind,
+        );
+    }
+
+    // Re-exports: export { x } from 'package', export type { X } from 'package'
+    fn visit_export_named_declaration(&mut self, decl: &ExportNamedDeclaration<'a>) {
+        if let Some(source) = &decl.source {
+            let type_only = decl.export_kind.is_type()
+                || (!decl.specifiers.is_empty()
+                    && decl.specifiers.iter().all(|s| s.export_kind.is_type()));
+            let kind = if type_only {
+                ImportKind::Type
+            } else {
+                ImportKind::ReExport
+            };
+            self.push(
+                source.value.as_str(),
+                Some(source.span),
+                decl.span.start,
+                kind,
+            );
+        }
+        walk::walk_export_named_declaration(self, decl);
+    }
+
+    // export * from 'package', export type * from 'package'
+    fn visit_export_all_declaration(&mut self, decl: &ExportAllDeclaration<'a>) {
+        let kind = if decl.export_kind.is_type() {
+            ImportKind::Type
+        } else {
+            ImportKind::ReExport
+        };
+        self.push(
+            decl.source.value.as_str(),
+            Some(decl.source.span),
+            decl.span.start,
+            kind,
+        );
+    }
+
+    // Import types: let x: import('package').X, typeof import('package')
+    fn visit_ts_import_type(&mut self, import_type: &TSImportType<'a>) {
+        if let TSType::TSLiteralType(literal) = &import_type.parameter {
+            if let TSLiteral::StringLiteral(lit) = &literal.literal {
+                self.push(
+                    lit.value.as_str(),
+                    Some(lit.span),
+                    import_type.span.start,
+                    ImportKind::Type,
+                );
             }
+        }
+        walk::walk_ts_import_type(self, import_type);
+    }
 
-            Expression::LogicalExpression(logical) => {
-                self.extract_from_expression(&logical.left, imports);
-                self.extract_from_expression(&logical.right, imports);
+    // const require = createRequire(import.meta.url)
+    fn visit_variable_declarator(&mut self, decl: &VariableDeclarator<'a>) {
+        if let (BindingPatternKind::BindingIdentifier(id), Some(init)) = (&decl.id.kind, &decl.init)
+        {
+            if is_create_require(init) {
+                self.require_aliases.insert(id.name.to_string());
             }
+        }
+        walk::walk_variable_declarator(self, decl);
+    }
 
-            Expression::AssignmentExpression(assign) => {
-                self.extract_from_expression(&assign.right, imports);
+    // require('package'), require.resolve('package/bin'),
+    // import.meta.resolve('package'), jest.mock('package')
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let Some(kind) = self.module_call_kind(&call.callee) {
+            if let Some(Argument::StringLiteral(lit)) = call.arguments.first() {
+                self.push(lit.value.as_str(), Some(lit.span), call.span.start, kind);
+            } else if let Some(argument) = call.arguments.first().and_then(|a| a.as_expression()) {
+                self.push_expression(argument, call.span.start, kind);
             }
+        }
+        self.extract_command(call);
+
+        // Keep going for nested requires/imports in arguments and callbacks
+        walk::walk_call_expression(self, call);
+    }
 
-            _ => {}
+    // Dynamic import: import('package'), import(`package/${name}`)
+    fn visit_import_expression(&mut self, import_expr: &ImportExpression<'a>) {
+        if let Expression::StringLiteral(lit) = &import_expr.source {
+            self.push(
+                lit.value.as_str(),
+                Some(lit.span),
+                import_expr.span.start,
+                ImportKind::Dynamic,
+            );
+        } else {
+            self.push_expression(
+                &import_expr.source,
+                import_expr.span.start,
+                ImportKind::Dynamic,
+            );
         }
+        walk::walk_import_expression(self, import_expr);
     }
 
-    fn line_number(&self, offset: u32) -> usize {
-        self.source[..offset as usize]
-            .chars()
-            .filter(|c| *c == '\n')
-            .count()
-            + 1
+    // execa's tagged template: $`eslint .`
+    fn visit_tagged_template_expression(&mut self, tagged: &TaggedTemplateExpression<'a>) {
+        if let Expression::Identifier(ident) = &tagged.tag {
+            if matches!(ident.name.as_str(), "$" | "execa") {
+                let command_line = template_prefix(&tagged.quasi);
+                self.push_commands(&command_line, tagged.span.start);
+            }
+        }
+        walk::walk_tagged_template_expression(self, tagged);
     }
 }
 
@@ -272,4 +678,295 @@ const mod = await import('lodash');
         assert_eq!(imports.len(), 1);
         assert_eq!(imports[0].kind, ImportKind::Dynamic);
     }
+
+    #[test]
+    fn test_dynamic_specifiers() {
+        let source = r#"
+const messages = await import(`./locales/${lang}.json`);
+const theme = require('@acme/themes/' + name);
+const parser = require("prettier/parser-" + lang);
+const icon = import(`@fortawesome/free-solid-svg-icons/${icon}`);
+const plugin = require(`eslint-plugin-${name}`);
+const loader = require(name);
+const scoped = import('@acme/' + pkg);
+const local = require(path.join(__dirname, 'plugins', name));
+const config = await import(pathToFileURL(file).href);
+const fs = require(`node:${module}`);
+const plain = import(`chalk`);
+"#;
+        let path = PathBuf::from("test.ts");
+        let extraction = ImportExtractor::new(&path, source).extract_all().unwrap();
+
+        let packages: Vec<(&str, &str)> = extraction
+            .imports
+            .iter()
+            .map(|i| (i.specifier.as_str(), i.resolved_package.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            packages,
+            vec![
+                ("@acme/themes", "@acme/themes"),
+                ("prettier", "prettier"),
+                (
+                    "@fortawesome/free-solid-svg-icons",
+                    "@fortawesome/free-solid-svg-icons"
+                ),
+                ("chalk", "chalk"),
+            ]
+        );
+        assert_eq!(extraction.imports[1].kind, ImportKind::CommonJs);
+        assert_eq!(extraction.imports[2].kind, ImportKind::Dynamic);
+        // Built specifiers can't be rewritten in place
+        assert!(extraction.specifiers.is_empty());
+
+        let dynamic: Vec<(usize, &str)> = extraction
+            .dynamic_imports
+            .iter()
+            .map(|d| (d.line, d.expression.as_str()))
+            .collect();
+        assert_eq!(
+            dynamic,
+            vec![
+                (6, "`eslint-plugin-${name}`"),
+                (7, "name"),
+                (8, "'@acme/' + pkg"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_type_imports() {
+        let source = r#"
+import type { UserConfig } from 'vite';
+import { defineConfig } from 'vite';
+"#;
+        let imports = extract_imports(source);
+        assert_eq!(imports[0].kind, ImportKind::Type);
+        assert_eq!(imports[1].kind, ImportKind::EsModule);
+
+        let source = r#"
+import { type Plugin, type ResolvedConfig } from 'vite';
+import { type Options, transform } from 'esbuild';
+export type { Node } from 'estree';
+export { type Program, parse } from 'acorn';
+export type * from 'unist';
+let server: import('express').Application;
+type Ms = typeof import('ms');
+"#;
+        let kinds: Vec<(String, ImportKind)> = extract_imports(source)
+            .into_iter()
+            .map(|i| (i.specifier, i.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("vite".to_string(), ImportKind::Type),
+                ("esbuild".to_string(), ImportKind::EsModule),
+                ("estree".to_string(), ImportKind::Type),
+                ("acorn".to_string(), ImportKind::ReExport),
+                ("unist".to_string(), ImportKind::Type),
+                ("express".to_string(), ImportKind::Type),
+                ("ms".to_string(), ImportKind::Type),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resolve_calls() {
+        let source = r#"
+import { createRequire } from 'node:module';
+const cli = require.resolve('typescript/bin/tsc');
+const worker = import.meta.resolve('@scope/worker');
+const pkg = createRequire(import.meta.url)('left-pad/package.json');
+const load = createRequire(import.meta.url);
+const chalk = load('chalk');
+const eslint = load.resolve('eslint');
+const m = module.createRequire(__filename)('ms');
+const match = str.resolve('not-a-package');
+"#;
+        let kinds: Vec<(Option<String>, ImportKind)> = extract_imports(source)
+            .into_iter()
+            .map(|i| (i.resolved_package, i.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some("typescript".to_string()), ImportKind::Resolve),
+                (Some("@scope/worker".to_string()), ImportKind::Resolve),
+                (Some("left-pad".to_string()), ImportKind::CommonJs),
+                (Some("chalk".to_string()), ImportKind::CommonJs),
+                (Some("eslint".to_string()), ImportKind::Resolve),
+                (Some("ms".to_string()), ImportKind::CommonJs),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mock_calls() {
+        let source = r#"
+jest.mock('axios');
+jest.mock('@sentry/node', () => ({ init: jest.fn() }));
+const { format } = jest.requireActual('date-fns');
+const uuid = require.requireMock('uuid');
+vi.mock('msw', async () => ({ ...(await vi.importActual('msw')) }));
+mock.mock('not-a-package');
+jest.fn('not-a-package');
+"#;
+        let mocked: Vec<(String, ImportKind)> = extract_imports(source)
+            .into_iter()
+            .map(|i| (i.specifier, i.kind))
+            .collect();
+        assert_eq!(
+            mocked,
+            ["axios", "@sentry/node", "date-fns", "uuid", "msw", "msw"]
+                .map(|package| (package.to_string(), ImportKind::Mock))
+        );
+    }
+
+    #[test]
+    fn test_declaration_file_imports() {
+        let path = PathBuf::from("types/globals.d.ts");
+        let source = "import { Request } from 'express';\nexport * from 'ms';\n";
+        let imports = ImportExtractor::new(&path, source).extract().unwrap();
+        assert_eq!(imports.len(), 2);
+        assert!(imports.iter().all(|i| i.kind == ImportKind::Type));
+    }
+
+    #[test]
+    fn test_nested_imports() {
+        let source = r#"
+export async function load() {
+    const chart = await import('chart.js');
+    return items.map(() => require('lodash'));
+}
+
+class Widget {
+    render() {
+        return import('@scope/widget');
+    }
+}
+"#;
+        let imports = extract_imports(source);
+        assert_eq!(imports.len(), 3);
+        assert_eq!(imports[0].resolved_package, Some("chart.js".to_string()));
+        assert_eq!(imports[0].kind, ImportKind::Dynamic);
+        assert_eq!(imports[0].line, 3);
+        assert_eq!(imports[1].kind, ImportKind::CommonJs);
+        assert_eq!(
+            imports[2].resolved_package,
+            Some("@scope/widget".to_string())
+        );
+    }
+
+    #[test]
+    fn test_imports_in_blocks() {
+        let source = r#"
+if (process.env.SENTRY_DSN) {
+    require('@sentry/node').init();
+}
+
+let fetch;
+try {
+    fetch = require('node-fetch');
+} catch {
+    fetch = require('cross-fetch');
+} finally {
+    require('debug')('app')('loaded');
+}
+
+switch (process.platform) {
+    case 'darwin':
+        module.exports = require('fsevents');
+        break;
+}
+
+const adapters = {
+    redis: () => import('ioredis'),
+    get pg() { return require('pg'); },
+    [computed()]: { nested: [require('mysql2')] },
+};
+
+const store = process.env.CI ? require('memorystore') : new (require('connect-redis'))();
+label: for (const name of names) { while (true) { require('chalk'); break label; } }
+"#;
+        let packages: Vec<String> = extract_imports(source)
+            .into_iter()
+            .filter_map(|i| i.resolved_package)
+            .collect();
+        assert_eq!(
+            packages,
+            [
+                "@sentry/node",
+                "node-fetch",
+                "cross-fetch",
+                "debug",
+                "fsevents",
+                "ioredis",
+                "pg",
+                "mysql2",
+                "memorystore",
+                "connect-redis",
+                "chalk",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_invocations() {
+        let source = r#"
+import { execa, $ } from 'execa';
+import { spawn, execSync } from 'child_process';
+
+await execa('eslint', ['--fix', '.']);
+spawn('npx', ['prettier', '--write', '.']);
+execSync(`tsc -p ${project}`);
+await $`vitest run`;
+const match = pattern.exec('not a command');
+"#;
+        let path = PathBuf::from("build.ts");
+        let extractor = ImportExtractor::new(&path, source);
+        let commands = extractor.extract_all().unwrap().commands;
+
+        let binaries: Vec<&str> = commands.iter().map(|c| c.binary.as_str()).collect();
+        assert_eq!(binaries, vec!["eslint", "prettier", "tsc", "vitest"]);
+        assert_eq!(commands[0].line, 5);
+
+        let source = r#"
+import { sync } from 'glob';
+import { command } from 'commander';
+
+const files = sync('src/**/*.ts');
+command('build');
+"#;
+        let extractor = ImportExtractor::new(&path, source);
+        assert!(extractor.extract_all().unwrap().commands.is_empty());
+    }
+
+    #[test]
+    fn test_component_imports() {
+        let source = r#"<script lang="ts">
+  import { writable } from 'svelte/store';
+  import Card from './Card.svelte';
+</script>
+
+<p>import fake from 'not-an-import';</p>
+<button on:click={() => import('confetti')}>Go</button>
+"#;
+        let path = PathBuf::from("Counter.svelte");
+        let extraction = ImportExtractor::new(&path, source).extract_all().unwrap();
+
+        let packages: Vec<(&str, usize)> = extraction
+            .imports
+            .iter()
+            .map(|i| (i.specifier.as_str(), i.line))
+            .collect();
+        assert_eq!(packages, vec![("svelte/store", 2)]);
+        assert_eq!(extraction.local_imports[0].specifier, "./Card.svelte");
+        // Spans point into the component itself
+        let span = &extraction.specifiers[0];
+        assert_eq!(
+            &source[span.start as usize..span.end as usize],
+            "'svelte/store'"
+        );
+    }
 }
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use miette::{Context, IntoDiagnostic, Result};
+
+use super::resolve;
+use crate::types::{Import, ImportKind, ImportMap, Package};
+
+/// Directories the go tool ignores, plus vendored copies of dependencies
+const SKIPPED_DIRS: &[&str] = &["vendor", "testdata", "node_modules"];
+
+/// Finds the modules a Go module's packages import
+///
+/// Import declarations are read from the top of each `.go` file, up to
+/// the first other declaration. An import path belongs to the module with
+/// the longest path it starts with, so `github.com/aws/aws-sdk-go-v2/service/s3`
+/// resolves to that module even when `github.com/aws/aws-sdk-go-v2` is
+/// locked too.
+pub struct GoImportScanner<'a> {
+    root: &'a Path,
+}
+
+impl<'a> GoImportScanner<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn scan(&self, packages: &HashMap<String, Package>) -> Result<ImportMap> {
+        let mut modules: Vec<&str> = packages.values().map(|p| p.name.as_str()).collect();
+        modules.sort_by_key(|m| std::cmp::Reverse(m.len()));
+
+        let mut imports = ImportMap::new();
+        for file in self.sources()? {
+            let source = std::fs::read_to_string(&file)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read file: {}", file.display()))?;
+
+            for (line, path) in go_imports(&source) {
+                let module = modules.iter().find(|module| {
+                    path == **module
+                        || path
+                            .strip_prefix(**module)
+                            .is_some_and(|rest| rest.starts_with('/'))
+                });
+                if let Some(module) = module {
+                    imports.add_import(Import {
+                        file_path: file.clone(),
+                        line,
+                        specifier: path,
+                        kind: ImportKind::Go,
+                        resolved_package: Some(module.to_string()),
+                    });
+                }
+            }
+            imports.mark_file_analyzed(file);
+        }
+
+        Ok(imports)
+    }
+
+    /// The module's `.go` files, skipping the directories `go build` does
+    fn sources(&self) -> Result<Vec<PathBuf>> {
+        let walker = WalkBuilder::new(self.root)
+            .hidden(true)
+            .git_ignore(true)
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                !SKIPPED_DIRS.contains(&name.as_ref()) && !name.starts_with('_')
+            })
+            .build();
+
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = entry
+                .into_diagnostic()
+                .context("Failed to read directory entry")?;
+            let path = resolve::normalize(entry.path());
+            if path.is_file() && path.extension().is_some_and(|e| e == "go") {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+}
+
+/// Import paths declared by a Go file, with their line numbers
+///
+/// Handles single imports, named and dot imports (`import f "fmt"`) and
+/// parenthesized blocks. Imports must come before any other declaration,
+/// so reading stops at the first `func`, `type`, `var` or `const`.
+fn go_imports(source: &str) -> Vec<(usize, String)> {
+    let mut imports = Vec::new();
+    let mut in_block = false;
+    let mut in_comment = false;
+
+    for (index, line) in source.lines().enumerate() {
+        let mut line = line.trim();
+        if in_comment {
+            match line.find("*/") {
+                Some(end) => {
+                    line = line[end + 2..].trim();
+                    in_comment = false;
+                }
+                None => continue,
+            }
+        }
+        if let Some(start) = line.find("/*") {
+            if !line[start..].contains("*/") {
+                in_comment = true;
+            }
+            line = line[..start].trim();
+        }
+
+        if in_block {
+            if line.starts_with(')') {
+                in_block = false;
+            } else if let Some(path) = quoted(line) {
+                imports.push((index + 1, path));
+            }
+            continue;
+        }
+
+        let Some(rest) = line.strip_prefix("import") else {
+            let first = line.split_whitespace().next().unwrap_or("");
+            if matches!(first, "func" | "type" | "var" | "const") {
+                break;
+            }
+            continue;
+        };
+        let rest = rest.trim_start();
+        if let Some(block) = rest.strip_prefix('(') {
+            // `import ("fmt")` on one line
+            match block.split_once(')') {
+                Some((inner, _)) => imports.extend(quoted(inner).map(|p| (index + 1, p))),
+                None => {
+                    in_block = true;
+                    imports.extend(quoted(block).map(|p| (index + 1, p)));
+                }
+            }
+        } else if let Some(path) = quoted(rest) {
+            imports.push((index + 1, path));
+        }
+    }
+
+    imports
+}
+
+/// The first "interpreted" or `raw` string literal in a line
+fn quoted(line: &str) -> Option<String> {
+    let start = line.find(['"', '`'])?;
+    let delimiter = line[start..].chars().next()?;
+    let rest = &line[start + 1..];
+    let end = rest.find(delimiter)?;
+    Some(rest[..end].to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_go_imports() {
+        let source = r#"// Package main is the server.
+package main
+
+/* import "commented/out"
+*/
+import "fmt"
+import f "github.com/gin-gonic/gin"
+
+import (
+	"net/http"
+	_ "github.com/lib/pq" // driver
+	jwt `github.com/golang-jwt/jwt/v4`
+)
+
+func main() {
+	s := `
+import "not/an/import"
+`
+}
+"#;
+        let paths: Vec<(usize, String)> = go_imports(source);
+        assert_eq!(
+            paths,
+            vec![
+                (6, "fmt".to_string()),
+                (7, "github.com/gin-gonic/gin".to_string()),
+                (10, "net/http".to_string()),
+                (11, "github.com/lib/pq".to_string()),
+                (12, "github.com/golang-jwt/jwt/v4".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_module() {
+        let root = TempDir::new("go-scan");
+        std::fs::create_dir_all(root.join("cmd/server")).unwrap();
+        std::fs::create_dir_all(root.join("vendor/github.com/lib/pq")).unwrap();
+        std::fs::write(
+            root.join("cmd/server/main.go"),
+            "package main\n\nimport (\n\t\"github.com/aws/aws-sdk-go-v2/service/s3\"\n\t\"github.com/gin-gonic/gin/binding\"\n)\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("vendor/github.com/lib/pq/conn.go"),
+            "package pq\n\nimport \"golang.org/x/net/context\"\n",
+        )
+        .unwrap();
+
+        let packages: HashMap<String, Package> = [
+            "github.com/aws/aws-sdk-go-v2",
+            "github.com/aws/aws-sdk-go-v2/service/s3",
+            "github.com/gin-gonic/gin",
+            "golang.org/x/net",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), Package::new(*name, "v1.0.0")))
+        .collect();
+        let imports = GoImportScanner::new(&root).scan(&packages).unwrap();
+
+        let mut used: Vec<String> = imports.packages_used().into_iter().collect();
+        used.sort();
+        assert_eq!(
+            used,
+            vec![
+                "github.com/aws/aws-sdk-go-v2/service/s3",
+                "github.com/gin-gonic/gin"
+            ]
+        );
+    }
+}
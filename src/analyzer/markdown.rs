@@ -0,0 +1,180 @@
+use std::ops::Range;
+
+use super::keep_sections;
+
+/// Info strings of fenced code blocks read as JavaScript or TypeScript
+const SCRIPT_LANGUAGES: &[&str] = &[
+    "js",
+    "jsx",
+    "ts",
+    "tsx",
+    "mjs",
+    "cjs",
+    "javascript",
+    "typescript",
+];
+
+/// The ESM blocks of an MDX document
+///
+/// As in MDX, a paragraph starting with `import` or `export` at the start
+/// of a line is ESM, up to the next blank line. Code blocks, JSX and `{}`
+/// expressions are left out.
+pub(super) fn mdx_script(source: &str) -> String {
+    let mut sections = Vec::new();
+    let mut fence: Option<Fence> = None;
+    let mut in_esm = false;
+    for (range, line) in lines(source) {
+        if let Some(open) = &fence {
+            if open.is_closed_by(line) {
+                fence = None;
+            }
+            continue;
+        }
+        if line.trim().is_empty() {
+            in_esm = false;
+            continue;
+        }
+        if !in_esm {
+            if let Some(open) = Fence::open(line) {
+                fence = Some(open);
+                continue;
+            }
+            in_esm = ["import", "export"].iter().any(|keyword| {
+                line.strip_prefix(keyword)
+                    .is_some_and(|rest| rest.starts_with([' ', '{', '*', '\t']) || rest.is_empty())
+            });
+        }
+        if in_esm {
+            sections.push(range);
+        }
+    }
+    keep_sections(source, &sections)
+}
+
+/// The JavaScript and TypeScript code blocks of a Markdown document
+pub(super) fn code_block_script(source: &str) -> String {
+    let mut sections = Vec::new();
+    let mut fence: Option<(Fence, bool)> = None;
+    for (range, line) in lines(source) {
+        match &fence {
+            Some((open, _)) if open.is_closed_by(line) => fence = None,
+            Some((_, true)) => sections.push(range),
+            Some((_, false)) => {}
+            None => {
+                fence = Fence::open(line).map(|open| {
+                    let language = open.info.split_whitespace().next().unwrap_or("");
+                    let is_script = SCRIPT_LANGUAGES.contains(&language.to_lowercase().as_str());
+                    (open, is_script)
+                });
+            }
+        }
+    }
+    keep_sections(source, &sections)
+}
+
+/// Each line with its byte range, newline excluded
+fn lines(source: &str) -> impl Iterator<Item = (Range<usize>, &str)> {
+    let mut offset = 0;
+    source.split_inclusive('\n').map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        (start..start + line.len(), line)
+    })
+}
+
+/// An open code fence: three or more backticks or tildes
+struct Fence {
+    marker: char,
+    len: usize,
+    info: String,
+}
+
+impl Fence {
+    fn open(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start_matches(' ');
+        if line.len() - trimmed.len() > 3 {
+            return None;
+        }
+        let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+        let len = trimmed.chars().take_while(|c| *c == marker).count();
+        let info = trimmed[len..].trim();
+        // A backtick fence's info string can't hold backticks
+        if len < 3 || (marker == '`' && info.contains('`')) {
+            return None;
+        }
+        Some(Self {
+            marker,
+            len,
+            info: info.to_string(),
+        })
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        trimmed.len() >= self.len && trimmed.chars().all(|c| c == self.marker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mdx_script() {
+        let source = r#"---
+title: Getting started
+---
+import Tabs from '@theme/Tabs';
+import {
+  Chart,
+} from '../components/Chart';
+export const meta = { sidebar: true };
+
+# Importing
+
+Write `import x from 'y'` in the docs, or:
+
+```js
+import notUsed from 'example-only';
+```
+
+<Tabs>{importantValue}</Tabs>
+"#;
+        let script = mdx_script(source);
+        assert_eq!(script.len(), source.len());
+        assert!(script.contains("import Tabs from '@theme/Tabs';"));
+        assert!(script.contains("} from '../components/Chart';"));
+        assert!(script.contains("export const meta"));
+        assert!(!script.contains("example-only") && !script.contains("Write"));
+        assert!(!script.contains("importantValue"));
+    }
+
+    #[test]
+    fn test_code_block_script() {
+        let source = r#"# Usage
+
+```ts title="app.ts"
+import { z } from 'zod';
+```
+
+~~~~bash
+npm install zod
+~~~~
+
+```json
+{ "import": "not-code" }
+```
+
+````jsx
+```
+import Foo from 'still-inside';
+````
+"#;
+        let script = code_block_script(source);
+        assert_eq!(script.len(), source.len());
+        assert!(script.contains("import { z } from 'zod';"));
+        assert!(script.contains("import Foo from 'still-inside';"));
+        assert!(!script.contains("npm install") && !script.contains("not-code"));
+    }
+}
@@ -1,13 +1,47 @@
+mod bundler;
+pub mod commands;
+mod components;
+mod configs;
+pub mod deno;
+pub mod exports;
 mod extractor;
-
+pub mod go;
+mod markdown;
+pub mod python;
+pub mod resolve;
+pub mod rust;
+mod styles;
+mod tsconfig;
+
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
 use ignore::WalkBuilder;
 use miette::{Context, IntoDiagnostic, Result};
 
-use crate::types::ImportMap;
+use crate::config::Config;
+use crate::lockfile::jsr_npm_name;
+use crate::range::npm_alias;
+use crate::types::{FileContext, ImportMap, LocalImport};
+use crate::workspace;
 
+use bundler::BundlerAliases;
+use deno::DenoImports;
 pub use extractor::ImportExtractor;
+use resolve::AliasTarget;
+use tsconfig::PathAliases;
+
+/// Directories never walked for imports (dependencies and build output)
+const SKIPPED_DIRS: &[&str] = &["node_modules", "dist", "build", ".git", "coverage", ".next"];
+
+/// Directories holding only tests and their fixtures
+const TEST_DIRS: &[&str] = &["test", "tests", "__tests__", "__mocks__", "e2e", "cypress"];
+
+/// Directories of build and maintenance scripts
+const SCRIPT_DIRS: &[&str] = &["scripts", "tools"];
+
+/// Task runner files, which are scripts rather than configuration
+const SCRIPT_FILES: &[&str] = &["gulpfile", "gruntfile", "jakefile"];
 
 /// Analyzes JavaScript/TypeScript source files to extract imports
 pub struct ImportAnalyzer {
@@ -24,6 +58,13 @@ impl ImportAnalyzer {
     /// Analyze all JS/TS files in the project and extract imports
     pub fn analyze(&self) -> Result<ImportMap> {
         let mut import_map = ImportMap::new();
+        let markdown_imports = Config::load(&self.root)?.markdown_imports;
+        let maps = SpecifierMaps {
+            packages: package_aliases(&self.root)?,
+            deno: DenoImports::load(&self.root)?,
+            tsconfig: PathAliases::load(&self.root)?,
+            bundler: BundlerAliases::load(&self.root)?,
+        };
 
         // Walk the directory, respecting .gitignore
         let walker = WalkBuilder::new(&self.root)
@@ -36,10 +77,7 @@ impl ImportAnalyzer {
                 // Skip node_modules, dist, build directories
                 if path.is_dir() {
                     let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
-                    return !matches!(
-                        name,
-                        "node_modules" | "dist" | "build" | ".git" | "coverage" | ".next"
-                    );
+                    return !SKIPPED_DIRS.contains(&name);
                 }
 
                 true
@@ -50,46 +88,183 @@ impl ImportAnalyzer {
             let entry = entry
                 .into_diagnostic()
                 .context("Failed to read directory entry")?;
-            let path = entry.path();
+            let path = &resolve::normalize(entry.path());
 
             if !path.is_file() {
                 continue;
             }
 
-            // Check if it's a JS/TS file
-            if !is_js_ts_file(path) {
+            // Check if it's a JS/TS file, one with scripts embedded, or a stylesheet
+            let is_markdown = path.extension().is_some_and(|e| e == "md");
+            if !(is_js_ts_file(path) || is_stylesheet(path) || (markdown_imports && is_markdown)) {
                 continue;
             }
 
-            // Skip test files for production analysis
-            // (we might want to make this configurable later)
-            let is_test = is_test_file(path);
+            let context = file_context(&self.root, path);
+
+            self.analyze_file(path, context, &maps, &mut import_map)?;
+        }
+
+        // Tools run from package.json scripts count as used too
+        for command in commands::script_invocations(&self.root)? {
+            import_map.add_command(command);
+        }
 
-            self.analyze_file(path, is_test, &mut import_map)?;
+        // And plugins and presets named in tool configuration
+        for reference in configs::config_references(&self.root)? {
+            import_map.set_file_context(reference.file_path.clone(), FileContext::Config);
+            import_map.add_import(reference);
         }
 
         Ok(import_map)
     }
 
-    fn analyze_file(&self, path: &Path, _is_test: bool, import_map: &mut ImportMap) -> Result<()> {
+    fn analyze_file(
+        &self,
+        path: &Path,
+        context: FileContext,
+        maps: &SpecifierMaps,
+        import_map: &mut ImportMap,
+    ) -> Result<()> {
         let source = std::fs::read_to_string(path)
             .into_diagnostic()
             .with_context(|| format!("Failed to read file: {}", path.display()))?;
 
         let extractor = ImportExtractor::new(path, &source);
-        let imports = extractor.extract()?;
-
-        for import in imports {
+        let extraction = extractor.extract_all()?;
+
+        for mut import in extraction.imports {
+            // tsconfig `paths` and bundler aliases turn some bare specifiers
+            // into project files
+            let aliased = maps
+                .tsconfig
+                .resolve(path, &import.specifier)
+                .or_else(|| maps.bundler.resolve(path, &import.specifier));
+            match aliased {
+                Some(target @ (AliasTarget::File(_) | AliasTarget::Missing)) => {
+                    let local = LocalImport {
+                        line: import.line,
+                        specifier: import.specifier,
+                        target: match target {
+                            AliasTarget::File(file) => Some(file),
+                            _ => None,
+                        },
+                    };
+                    import_map.add_local_import(path.to_path_buf(), local);
+                    continue;
+                }
+                Some(AliasTarget::Package(package)) => import.resolved_package = Some(package),
+                None => {}
+            }
+            // Deno import maps decide which package a bare specifier names
+            if let Some(target) = maps.deno.resolve(&import.specifier) {
+                import.resolved_package = extract_package_name(&target);
+            }
+            // Aliased packages are imported by the alias but installed under their own name
+            if let Some(real) = import
+                .resolved_package
+                .as_ref()
+                .and_then(|package| maps.packages.get(package))
+            {
+                import.resolved_package = Some(real.clone());
+            }
             import_map.add_import(import);
         }
+        for command in extraction.commands {
+            import_map.add_command(command);
+        }
+        for dynamic in extraction.dynamic_imports {
+            import_map.add_dynamic_import(dynamic);
+        }
+        for mut local in extraction.local_imports {
+            local.target = resolve::resolve_relative(path, &local.specifier);
+            import_map.add_local_import(path.to_path_buf(), local);
+        }
 
-        import_map.mark_file_analyzed();
+        import_map.mark_file_analyzed(path.to_path_buf());
+        import_map.set_file_context(path.to_path_buf(), context);
 
         Ok(())
     }
 }
 
-/// Check if a path is a JavaScript/TypeScript file
+/// The project's maps from bare specifiers to packages or files
+struct SpecifierMaps {
+    /// npm aliases, alias -> package it installs
+    packages: HashMap<String, String>,
+    deno: DenoImports,
+    tsconfig: PathAliases,
+    bundler: BundlerAliases,
+}
+
+/// Dependencies declared as npm aliases, alias -> package it installs
+///
+/// Covers the root package.json and those of workspace members, e.g.
+/// `"string-width-cjs": "npm:string-width@^4"` maps "string-width-cjs"
+/// to "string-width".
+fn package_aliases(root: &Path) -> Result<HashMap<String, String>> {
+    let mut dirs = vec![root.to_path_buf()];
+    dirs.extend(workspace::member_dirs(root)?);
+
+    let mut aliases = HashMap::new();
+    for dir in dirs {
+        let Ok(content) = std::fs::read_to_string(dir.join("package.json")) else {
+            continue;
+        };
+        let Ok(manifest) = serde_json::from_str::<serde_json::Value>(&content) else {
+            continue;
+        };
+        for section in [
+            "dependencies",
+            "devDependencies",
+            "optionalDependencies",
+            "peerDependencies",
+        ] {
+            let Some(deps) = manifest.get(section).and_then(|deps| deps.as_object()) else {
+                continue;
+            };
+            for (alias, spec) in deps {
+                let Some(spec) = spec.as_str() else {
+                    continue;
+                };
+                if let Some((real, _)) = npm_alias(spec) {
+                    aliases.insert(alias.clone(), real.to_string());
+                } else if let Some(real) = jsr_dependency(alias, spec) {
+                    aliases.insert(alias.clone(), real);
+                }
+            }
+        }
+    }
+    Ok(aliases)
+}
+
+/// The npm name a `jsr:` dependency installs under
+///
+/// pnpm and yarn install `jsr:` dependencies from JSR's npm compatibility
+/// registry: `"@std/path": "jsr:^1.0.8"` and `"path": "jsr:@std/path@^1"`
+/// both install "@jsr/std__path".
+fn jsr_dependency(alias: &str, spec: &str) -> Option<String> {
+    let target = spec.strip_prefix("jsr:")?;
+    let name = if target.starts_with('@') {
+        npm_alias(&format!("npm:{}", target)).map(|(name, _)| name.to_string())?
+    } else {
+        alias.to_string()
+    };
+    jsr_npm_name(&name)
+}
+
+/// Whether `analyze` would pick up a file under `root`
+pub fn is_analyzed_path(root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let in_skipped_dir = relative.components().any(|c| {
+        let name = c.as_os_str().to_str().unwrap_or("");
+        SKIPPED_DIRS.contains(&name) || (name.starts_with('.') && name.len() > 1 && name != "..")
+    });
+
+    is_js_ts_file(path) && !in_skipped_dir
+}
+
+/// Check if a path is a JavaScript/TypeScript file, a component or MDX
 fn is_js_ts_file(path: &Path) -> bool {
     let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
         return false;
@@ -97,12 +272,44 @@ fn is_js_ts_file(path: &Path) -> bool {
 
     matches!(
         ext,
-        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "mts" | "cts"
-    )
+        "js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs" | "mts" | "cts" | "mdx"
+    ) || components::COMPONENT_EXTENSIONS.contains(&ext)
+}
+
+/// Check if a path is a CSS, Sass or Less stylesheet
+fn is_stylesheet(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| styles::STYLE_EXTENSIONS.contains(&ext))
+}
+
+/// The JS/TS embedded in a component or Markdown file, as source of the
+/// same length; `None` for plain modules
+fn script_source(path: &Path, source: &str) -> Option<String> {
+    match path.extension().and_then(|e| e.to_str())? {
+        "mdx" => Some(markdown::mdx_script(source)),
+        "md" => Some(markdown::code_block_script(source)),
+        _ => components::component_script(path, source),
+    }
+}
+
+/// `source` with everything outside `sections` blanked out, keeping
+/// newlines, so offsets and line numbers hold in either
+///
+/// Sections start and end at ASCII characters.
+fn keep_sections(source: &str, sections: &[std::ops::Range<usize>]) -> String {
+    let mut script: Vec<u8> = source
+        .bytes()
+        .map(|b| if b == b'\n' { b'\n' } else { b' ' })
+        .collect();
+    for section in sections {
+        script[section.clone()].copy_from_slice(&source.as_bytes()[section.clone()]);
+    }
+    String::from_utf8(script).unwrap_or_default()
 }
 
 /// Check if a file is likely a test file
-fn is_test_file(path: &Path) -> bool {
+pub fn is_test_file(path: &Path) -> bool {
     let path_str = path.to_string_lossy();
 
     // Common test file patterns
@@ -116,6 +323,57 @@ fn is_test_file(path: &Path) -> bool {
         || path_str.ends_with(".spec.js")
 }
 
+/// What a file under `root` is for, from its name and the directories
+/// it's in
+pub fn file_context(root: &Path, path: &Path) -> FileContext {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let dirs: Vec<&str> = relative
+        .parent()
+        .into_iter()
+        .flat_map(|parent| parent.components())
+        .filter_map(|c| c.as_os_str().to_str())
+        .collect();
+    let stem = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .and_then(|n| n.split('.').next())
+        .unwrap_or("")
+        .to_lowercase();
+
+    if is_test_file(path)
+        || path.to_string_lossy().contains(".stories.")
+        || dirs.iter().any(|d| TEST_DIRS.contains(d))
+    {
+        FileContext::Test
+    } else if SCRIPT_FILES.contains(&stem.as_str()) || dirs.iter().any(|d| SCRIPT_DIRS.contains(d))
+    {
+        FileContext::Script
+    } else if is_config_file(path) {
+        FileContext::Config
+    } else {
+        FileContext::Source
+    }
+}
+
+/// Files a framework or bundler loads on its own: MDX pages, Svelte and
+/// Astro components (routes, more often than not) and stylesheets
+///
+/// They're read for the packages they import, but nothing has to import
+/// them for them to run.
+pub fn is_framework_file(path: &Path) -> bool {
+    is_stylesheet(path)
+        || path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|ext| ext == "mdx" || components::COMPONENT_EXTENSIONS.contains(&ext))
+}
+
+/// Tool configuration files like `vite.config.ts` or `.eslintrc.js`
+pub fn is_config_file(path: &Path) -> bool {
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+    name.contains(".config.") || name.starts_with(".eslintrc") || name.starts_with(".prettierrc")
+}
+
 /// Extract the package name from an import specifier
 ///
 /// Examples:
@@ -125,12 +383,20 @@ fn is_test_file(path: &Path) -> bool {
 /// - "@scope/package/sub" -> "@scope/package"
 /// - "./local" -> None (relative import)
 /// - "../utils" -> None (relative import)
+/// - "npm:chalk@5" -> "chalk" (Deno specifiers, see `deno::specifier_package`)
 pub fn extract_package_name(specifier: &str) -> Option<String> {
     // Skip relative imports
     if specifier.starts_with('.') || specifier.starts_with('/') {
         return None;
     }
 
+    if ["npm:", "jsr:", "https://", "http://"]
+        .iter()
+        .any(|prefix| specifier.starts_with(prefix))
+    {
+        return deno::specifier_package(specifier);
+    }
+
     // Skip Node.js built-in modules
     if is_node_builtin(specifier) {
         return None;
@@ -203,6 +469,8 @@ fn is_node_builtin(specifier: &str) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::TempDir;
+    use crate::types::ImportKind;
 
     #[test]
     fn test_extract_package_name() {
@@ -223,5 +491,238 @@ mod tests {
         assert_eq!(extract_package_name("../utils"), None);
         assert_eq!(extract_package_name("fs"), None);
         assert_eq!(extract_package_name("node:fs"), None);
+        assert_eq!(
+            extract_package_name("jsr:@std/path@1/join"),
+            Some("@std/path".to_string())
+        );
+    }
+
+    #[test]
+    fn test_file_context() {
+        let root = Path::new("/app");
+        let context = |path: &str| file_context(root, &root.join(path));
+        assert_eq!(context("src/index.ts"), FileContext::Source);
+        assert_eq!(context("src/index.test.ts"), FileContext::Test);
+        assert_eq!(context("src/Button.stories.tsx"), FileContext::Test);
+        assert_eq!(context("packages/ui/test/setup.ts"), FileContext::Test);
+        assert_eq!(context("vite.config.ts"), FileContext::Config);
+        assert_eq!(context("scripts/release.mjs"), FileContext::Script);
+        assert_eq!(context("gulpfile.js"), FileContext::Script);
+        // The root's own directories don't count
+        assert_eq!(
+            file_context(
+                Path::new("/home/me/tools/app"),
+                Path::new("/home/me/tools/app/src/a.ts")
+            ),
+            FileContext::Source
+        );
+    }
+
+    #[test]
+    fn test_aliased_imports() {
+        let root = TempDir::new("alias-imports");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"dependencies": {"string-width-cjs": "npm:string-width@^4", "react": "^18", "@std/path": "jsr:^1.0.8", "cases": "jsr:@luca/cases@1"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/index.js"),
+            "const sw = require('string-width-cjs');\nimport React from 'react';\nimport { join } from '@std/path/join';\nimport { camelCase } from 'cases';\n",
+        )
+        .unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+
+        let used = imports.packages_used();
+        assert!(used.contains("string-width"));
+        assert!(used.contains("react"));
+        assert!(!used.contains("string-width-cjs"));
+        assert!(used.contains("@jsr/std__path"));
+        assert!(used.contains("@jsr/luca__cases"));
+    }
+
+    #[test]
+    fn test_deno_imports() {
+        let root = TempDir::new("deno-analyze");
+        std::fs::write(
+            root.join("deno.json"),
+            r#"{"imports": {"asserts": "jsr:@std/assert@1", "@/": "./src/"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("main.ts"),
+            concat!(
+                "import { assert } from 'asserts';\n",
+                "import chalk from 'npm:chalk@5';\n",
+                "import { Application } from 'https://deno.land/x/oak@v12.6.1/mod.ts';\n",
+                "import { util } from '@/util.ts';\n",
+            ),
+        )
+        .unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+
+        let used = imports.packages_used();
+        let mut used: Vec<&str> = used.iter().map(|name| name.as_str()).collect();
+        used.sort();
+        assert_eq!(used, vec!["@std/assert", "chalk", "deno.land/x/oak"]);
+    }
+
+    #[test]
+    fn test_local_imports() {
+        let root = TempDir::new("local");
+        std::fs::create_dir_all(root.join("src/utils")).unwrap();
+
+        std::fs::write(
+            root.join("src/index.ts"),
+            "import { a } from './utils';\nimport b from '../src/./b.js';\nimport c from './missing';\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/utils/index.ts"), "export const a = 1;\n").unwrap();
+        std::fs::write(root.join("src/b.ts"), "export default 2;\n").unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+
+        let index = root.join("src/index.ts");
+        let local = imports.local_imports_of(&index);
+        assert_eq!(local.len(), 3);
+        assert_eq!(local[0].target, Some(root.join("src/utils/index.ts")));
+        assert_eq!(local[1].target, Some(root.join("src/b.ts")));
+        assert_eq!(local[1].line, 2);
+
+        let unresolved = imports.unresolved_local_imports();
+        assert_eq!(unresolved.len(), 1);
+        assert_eq!(unresolved[0].1.specifier, "./missing");
+    }
+
+    #[test]
+    fn test_bundler_alias_imports() {
+        let root = TempDir::new("vite-alias");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("vite.config.js"),
+            "export default { resolve: { alias: { ui: '@acme/design-system', '@': '/src' } } };\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/main.js"),
+            "import { Button } from 'ui/button';\nimport { api } from '@/api';\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/api.js"), "export const api = 1;\n").unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+
+        let used = imports.packages_used();
+        let mut used: Vec<&str> = used.iter().map(|name| name.as_str()).collect();
+        used.sort();
+        assert_eq!(used, vec!["@acme/design-system"]);
+        let local = imports.local_imports_of(&root.join("src/main.js"));
+        assert_eq!(local[0].target, Some(root.join("src/api.js")));
+    }
+
+    #[test]
+    fn test_markdown_imports() {
+        let root = TempDir::new("markdown");
+        std::fs::create_dir_all(root.join("docs")).unwrap();
+        std::fs::write(
+            root.join("docs/intro.mdx"),
+            "import { Chart } from 'recharts';\n\n# Intro\n\n<Chart />\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("README.md"),
+            "# Usage\n\n```ts\nimport { z } from 'zod';\n```\n",
+        )
+        .unwrap();
+
+        let used = |root: &Path| -> Vec<String> {
+            let mut used: Vec<String> = ImportAnalyzer::new(root)
+                .analyze()
+                .unwrap()
+                .packages_used()
+                .into_iter()
+                .collect();
+            used.sort();
+            used
+        };
+        let by_default = used(&root);
+        std::fs::write(root.join("depx.toml"), "markdown-imports = true\n").unwrap();
+        let with_markdown = used(&root);
+
+        assert_eq!(by_default, vec!["recharts"]);
+        assert_eq!(with_markdown, vec!["recharts", "zod"]);
+    }
+
+    #[test]
+    fn test_style_imports() {
+        let root = TempDir::new("styles");
+        std::fs::create_dir_all(root.join("src/styles")).unwrap();
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write("src/styles/_variables.scss", "$primary: teal;\n");
+        write(
+            "src/styles/main.scss",
+            "@use \"variables\";\n@import \"~bootstrap/scss/bootstrap\";\n@use \"normalize.css\";\n",
+        );
+        write(
+            "src/app.less",
+            "@import (css) \"~@fontsource/inter/index.css\";\n@import \"./theme\";\n",
+        );
+        write("src/reset.css", "@import \"modern-normalize\";\n");
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+
+        let mut used: Vec<String> = imports.packages_used().into_iter().collect();
+        used.sort();
+        assert_eq!(
+            used,
+            vec![
+                "@fontsource/inter",
+                "bootstrap",
+                "modern-normalize",
+                "normalize.css"
+            ]
+        );
+        let bootstrap = &imports.get_package_usages("bootstrap").unwrap()[0];
+        assert_eq!(bootstrap.specifier, "bootstrap/scss/bootstrap");
+        assert_eq!(bootstrap.kind, ImportKind::Style);
+        assert_eq!(bootstrap.line, 2);
+    }
+
+    #[test]
+    fn test_tsconfig_path_imports() {
+        let root = TempDir::new("ts-paths");
+        std::fs::create_dir_all(root.join("src/components")).unwrap();
+        std::fs::write(
+            root.join("tsconfig.json"),
+            r#"{"compilerOptions": {"baseUrl": ".", "paths": {"@components/*": ["src/components/*"]}}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/index.ts"),
+            "import { Button } from '@components/Button';\nimport React from 'react';\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/components/Button.tsx"),
+            "export const Button = 1;\n",
+        )
+        .unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+
+        let used = imports.packages_used();
+        assert!(used.contains("react"));
+        assert!(!used.contains("@components/Button"));
+
+        let local = imports.local_imports_of(&root.join("src/index.ts"));
+        assert_eq!(local.len(), 1);
+        assert_eq!(local[0].specifier, "@components/Button");
+        assert_eq!(
+            local[0].target,
+            Some(root.join("src/components/Button.tsx"))
+        );
     }
 }
@@ -0,0 +1,392 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use miette::{Context, IntoDiagnostic, Result};
+
+use super::resolve;
+use crate::lockfile::normalize_name;
+use crate::types::{Import, ImportKind, ImportMap, Package};
+
+/// Directories never walked for Python sources
+const SKIPPED_DIRS: &[&str] = &[
+    "__pycache__",
+    "site-packages",
+    "node_modules",
+    "build",
+    "dist",
+];
+
+/// Virtual environment directories searched for installed distributions
+const VENV_DIRS: &[&str] = &[".venv", "venv", "env"];
+
+/// Distributions whose import name doesn't follow from the project name
+const MODULE_ALIASES: &[(&str, &str)] = &[
+    ("yaml", "pyyaml"),
+    ("PIL", "pillow"),
+    ("sklearn", "scikit-learn"),
+    ("skimage", "scikit-image"),
+    ("cv2", "opencv-python"),
+    ("bs4", "beautifulsoup4"),
+    ("dateutil", "python-dateutil"),
+    ("jwt", "pyjwt"),
+    ("dotenv", "python-dotenv"),
+    ("multipart", "python-multipart"),
+    ("attr", "attrs"),
+    ("Crypto", "pycryptodome"),
+    ("serial", "pyserial"),
+    ("OpenSSL", "pyopenssl"),
+    ("zmq", "pyzmq"),
+    ("magic", "python-magic"),
+    ("docx", "python-docx"),
+    ("git", "gitpython"),
+    ("google.protobuf", "protobuf"),
+    ("pkg_resources", "setuptools"),
+];
+
+/// Finds the distributions a Python project's source code imports
+///
+/// `import` and `from ... import` statements are read textually, skipping
+/// relative imports and string contents. Module names map to packages
+/// through the `top_level.txt` of distributions installed in a local
+/// virtualenv, a table of well-known renames (`yaml` is PyYAML), and
+/// otherwise the normalized project name (`flask_cors` is flask-cors,
+/// `google.cloud.storage` is google-cloud-storage).
+pub struct PythonImportScanner<'a> {
+    root: &'a Path,
+}
+
+impl<'a> PythonImportScanner<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn scan(&self, packages: &HashMap<String, Package>) -> Result<ImportMap> {
+        let modules = self.module_names(packages);
+
+        let mut imports = ImportMap::new();
+        for file in self.sources()? {
+            let source = std::fs::read_to_string(&file)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read file: {}", file.display()))?;
+
+            for (line, module, names) in python_imports(&source) {
+                // `from google.cloud import storage` may name a distribution
+                let submodule = names.iter().find_map(|name| {
+                    modules
+                        .get(&format!("{}.{}", module, name).to_lowercase())
+                        .cloned()
+                });
+                if let Some(name) = submodule.or_else(|| resolve_module(&module, &modules)) {
+                    imports.add_import(Import {
+                        file_path: file.clone(),
+                        line,
+                        specifier: module,
+                        kind: ImportKind::Python,
+                        resolved_package: Some(name),
+                    });
+                }
+            }
+            imports.mark_file_analyzed(file);
+        }
+
+        Ok(imports)
+    }
+
+    /// The project's `.py` and `.pyi` files, outside virtualenvs
+    fn sources(&self) -> Result<Vec<PathBuf>> {
+        let walker = WalkBuilder::new(self.root)
+            .hidden(true)
+            .git_ignore(true)
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                !SKIPPED_DIRS.contains(&name.as_ref()) && !entry.path().join("pyvenv.cfg").exists()
+            })
+            .build();
+
+        let mut files = Vec::new();
+        for entry in walker {
+            let entry = entry
+                .into_diagnostic()
+                .context("Failed to read directory entry")?;
+            let path = resolve::normalize(entry.path());
+            if path.is_file() && path.extension().is_some_and(|e| e == "py" || e == "pyi") {
+                files.push(path);
+            }
+        }
+        Ok(files)
+    }
+
+    /// Lowercased module path -> package name, for every known package
+    fn module_names(&self, packages: &HashMap<String, Package>) -> HashMap<String, String> {
+        let mut modules = HashMap::new();
+        for package in packages.values() {
+            let name = &package.name;
+            modules.insert(name.replace('-', "_"), name.clone());
+            // Namespace packages: zope-interface is imported as zope.interface
+            if name.contains('-') {
+                modules.insert(name.replace('-', "."), name.clone());
+            }
+        }
+
+        let is_locked = |name: &str| packages.values().any(|p| p.name == name);
+        for (module, name) in MODULE_ALIASES {
+            if is_locked(name) {
+                modules.insert(module.to_lowercase(), name.to_string());
+            }
+        }
+        for (module, name) in self.installed_top_levels() {
+            if is_locked(&name) {
+                modules.insert(module.to_lowercase(), name);
+            }
+        }
+        modules
+    }
+
+    /// Top-level modules of the distributions in a local virtualenv, as
+    /// (module, normalized distribution name)
+    fn installed_top_levels(&self) -> Vec<(String, String)> {
+        let mut site_packages = Vec::new();
+        for venv in VENV_DIRS {
+            let venv = self.root.join(venv);
+            site_packages.push(venv.join("Lib").join("site-packages"));
+            let Ok(entries) = std::fs::read_dir(venv.join("lib")) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                if entry.file_name().to_string_lossy().starts_with("python") {
+                    site_packages.push(entry.path().join("site-packages"));
+                }
+            }
+        }
+
+        let mut top_levels = Vec::new();
+        for dir in site_packages {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for entry in entries.flatten() {
+                let file_name = entry.file_name().to_string_lossy().to_string();
+                // "python_dateutil-2.8.2.dist-info"
+                let Some(stem) = file_name.strip_suffix(".dist-info") else {
+                    continue;
+                };
+                let name = normalize_name(stem.split('-').next().unwrap_or(stem));
+                let Ok(content) = std::fs::read_to_string(entry.path().join("top_level.txt"))
+                else {
+                    continue;
+                };
+                top_levels.extend(
+                    content
+                        .lines()
+                        .map(str::trim)
+                        .filter(|module| !module.is_empty())
+                        .map(|module| (module.replace('/', "."), name.clone())),
+                );
+            }
+        }
+        top_levels
+    }
+}
+
+/// The package a module path belongs to, trying its longest prefix first
+fn resolve_module(module: &str, modules: &HashMap<String, String>) -> Option<String> {
+    let module = module.to_lowercase();
+    let mut prefix = module.as_str();
+    loop {
+        if let Some(name) = modules.get(prefix) {
+            return Some(name.clone());
+        }
+        prefix = &prefix[..prefix.rfind('.')?];
+    }
+}
+
+/// Absolute modules imported by `import` and `from ... import`, with
+/// their line numbers and the names a `from` import takes from them
+fn python_imports(source: &str) -> Vec<(usize, String, Vec<String>)> {
+    let mut imports = Vec::new();
+    let mut open_string: Option<&str> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let code = strip_strings(line, &mut open_string);
+        for statement in code.split(';') {
+            let statement = statement.trim();
+            if let Some(rest) = statement.strip_prefix("import ") {
+                for part in rest.split(',') {
+                    // "a.b as c"
+                    let module = part.split_whitespace().next().unwrap_or("");
+                    let module = module.trim_matches(|c| c == '(' || c == ')');
+                    if is_module(module) {
+                        imports.push((index + 1, module.to_string(), Vec::new()));
+                    }
+                }
+            } else if let Some(rest) = statement.strip_prefix("from ") {
+                let mut words = rest.split_whitespace();
+                let module = words.next().unwrap_or("");
+                if words.next() == Some("import") && is_module(module) && module != "__future__" {
+                    let names = words
+                        .flat_map(|word| word.split(','))
+                        .map(|name| name.trim_matches(|c| c == '(' || c == ')'))
+                        .filter(|name| is_module(name) && *name != "as")
+                        .map(str::to_string)
+                        .collect();
+                    imports.push((index + 1, module.to_string(), names));
+                }
+            }
+        }
+    }
+
+    imports
+}
+
+/// A line's code with comments and string contents removed
+///
+/// `open_string` carries a triple-quoted string across lines.
+fn strip_strings(line: &str, open_string: &mut Option<&'static str>) -> String {
+    let mut code = String::new();
+    let mut rest = line;
+
+    loop {
+        if let Some(delimiter) = *open_string {
+            match rest.find(delimiter) {
+                Some(end) => {
+                    rest = &rest[end + delimiter.len()..];
+                    *open_string = None;
+                }
+                None => return code,
+            }
+        }
+
+        let Some(start) = rest.find(['#', '"', '\'']) else {
+            code.push_str(rest);
+            return code;
+        };
+        code.push_str(&rest[..start]);
+        let quote = &rest[start..];
+        if quote.starts_with('#') {
+            return code;
+        }
+
+        if quote.starts_with("\"\"\"") || quote.starts_with("'''") {
+            *open_string = Some(if quote.starts_with('"') {
+                "\"\"\""
+            } else {
+                "'''"
+            });
+            rest = &quote[3..];
+            continue;
+        }
+
+        // A single-quoted string ends at its unescaped closing quote
+        let delimiter = quote.chars().next().unwrap_or('"');
+        let mut escaped = false;
+        let mut end = None;
+        for (i, c) in quote.char_indices().skip(1) {
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, c) if c == delimiter => {
+                    end = Some(i + 1);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        match end {
+            Some(end) => rest = &quote[end..],
+            None => return code,
+        }
+    }
+}
+
+fn is_module(token: &str) -> bool {
+    token.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && token
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_python_imports() {
+        let source = r#"
+"""Module docstring.
+
+import fake
+"""
+from __future__ import annotations
+import os, sys
+import numpy as np, yaml
+from flask import (
+    Flask,
+)
+from . import views
+from .models import User
+from google.cloud import storage  # import comment
+x = "import quoted"; import requests
+def f():
+    import PIL.Image
+    s = 'from x import y'
+"#;
+        let modules = python_imports(source);
+        let names: Vec<&str> = modules.iter().map(|(_, m, _)| m.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "os",
+                "sys",
+                "numpy",
+                "yaml",
+                "flask",
+                "google.cloud",
+                "requests",
+                "PIL.Image"
+            ]
+        );
+        assert_eq!(modules[4].0, 9);
+        assert_eq!(modules[5].2, vec!["storage"]);
+    }
+
+    #[test]
+    fn test_scan_project() {
+        let root = TempDir::new("python");
+        std::fs::create_dir_all(root.join("app")).unwrap();
+        let site_packages = root.join(".venv/lib/python3.12/site-packages");
+        std::fs::create_dir_all(site_packages.join("attrs-23.2.0.dist-info")).unwrap();
+        std::fs::write(root.join(".venv/pyvenv.cfg"), "home = /usr/bin\n").unwrap();
+        std::fs::write(
+            site_packages.join("attrs-23.2.0.dist-info/top_level.txt"),
+            "attr\nattrs\n",
+        )
+        .unwrap();
+        std::fs::write(site_packages.join("six.py"), "import requests\n").unwrap();
+        std::fs::write(
+            root.join("app/main.py"),
+            "import yaml\nfrom flask_cors import CORS\nfrom google.cloud import storage\nimport attr\n",
+        )
+        .unwrap();
+
+        let packages: HashMap<String, Package> = [
+            "pyyaml",
+            "flask-cors",
+            "google-cloud-storage",
+            "attrs",
+            "requests",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), Package::new(*name, "1.0.0")))
+        .collect();
+        let imports = PythonImportScanner::new(&root).scan(&packages).unwrap();
+
+        let mut used: Vec<String> = imports.packages_used().into_iter().collect();
+        used.sort();
+        assert_eq!(
+            used,
+            vec!["attrs", "flask-cors", "google-cloud-storage", "pyyaml"]
+        );
+    }
+}
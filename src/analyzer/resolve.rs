@@ -0,0 +1,116 @@
+use std::path::{Component, Path, PathBuf};
+
+use super::extract_package_name;
+
+/// Extensions tried when resolving a relative import without one
+const RESOLVE_EXTENSIONS: &[&str] = &["js", "mjs", "cjs", "ts", "mts", "cts", "jsx", "tsx"];
+
+/// Resolve a relative specifier the way Node does for files and directory indexes
+///
+/// TypeScript sources often import `./utils.js` while the file on disk is
+/// `utils.ts`, so a missing `.js` target is also tried with TS extensions.
+pub fn resolve_relative(from: &Path, specifier: &str) -> Option<PathBuf> {
+    resolve_file(&from.parent()?.join(specifier))
+}
+
+/// Resolve a path that may omit its extension or point at a directory
+pub fn resolve_file(path: &Path) -> Option<PathBuf> {
+    let base = normalize(path);
+
+    if base.is_file() {
+        return Some(base);
+    }
+
+    for ext in RESOLVE_EXTENSIONS {
+        let candidate = PathBuf::from(format!("{}.{}", base.display(), ext));
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+
+    if let Some(ext) = base.extension().and_then(|e| e.to_str()) {
+        let ts_exts: &[&str] = match ext {
+            "js" | "jsx" => &["ts", "tsx"],
+            "mjs" => &["mts"],
+            "cjs" => &["cts"],
+            _ => &[],
+        };
+        for ts_ext in ts_exts {
+            let candidate = base.with_extension(ts_ext);
+            if candidate.is_file() {
+                return Some(candidate);
+            }
+        }
+    }
+
+    for ext in RESOLVE_EXTENSIONS {
+        let candidate = base.join(format!("index.{}", ext));
+        if candidate.is_file() {
+            return Some(candidate);
+        }
+    }
+
+    None
+}
+
+/// Collapse `.` and `..` components without touching the filesystem
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// What an aliased import points at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AliasTarget {
+    /// A file of the project
+    File(PathBuf),
+
+    /// A project path nothing on disk matches
+    Missing,
+
+    /// A file inside an installed package
+    Package(String),
+}
+
+/// A resolved file, attributed to its package when it's in node_modules
+pub fn target_of(file: PathBuf) -> AliasTarget {
+    match installed_package(&file.to_string_lossy()) {
+        Some(package) => AliasTarget::Package(package),
+        None => AliasTarget::File(file),
+    }
+}
+
+/// The package a path inside node_modules belongs to
+///
+/// The last `node_modules` counts, for pnpm's `.pnpm/<id>/node_modules/<name>`.
+pub fn installed_package(path: &str) -> Option<String> {
+    let path = path.replace('\\', "/");
+    let index = path.rfind("/node_modules/")?;
+    extract_package_name(&path[index + "/node_modules/".len()..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize(Path::new("./src/../lib/a.js")),
+            PathBuf::from("lib/a.js")
+        );
+        assert_eq!(normalize(Path::new("../x")), PathBuf::from("../x"));
+        assert_eq!(normalize(Path::new("/a/./b/../c")), PathBuf::from("/a/c"));
+    }
+}
@@ -0,0 +1,838 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use miette::{Context, IntoDiagnostic, Result};
+use toml::Value as TomlValue;
+
+use super::resolve;
+use crate::types::{Import, ImportKind, ImportMap, Package};
+
+/// Cargo.toml tables that declare dependencies
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Proc-macro crates usually reached through a facade crate that
+/// re-exports their macros, with the facade and the macros
+///
+/// `#[derive(Serialize)]` expands serde_derive's code, even though the
+/// source only names serde.
+const PROC_MACROS: &[(&str, &str, &[&str])] = &[
+    ("serde_derive", "serde", &["Serialize", "Deserialize"]),
+    ("thiserror-impl", "thiserror", &["Error"]),
+    ("miette-derive", "miette", &["Diagnostic"]),
+    (
+        "clap_derive",
+        "clap",
+        &["Parser", "Subcommand", "Args", "ValueEnum"],
+    ),
+    ("tokio-macros", "tokio", &["main", "test"]),
+    ("tracing-attributes", "tracing", &["instrument"]),
+    (
+        "prost-derive",
+        "prost",
+        &["Message", "Enumeration", "Oneof"],
+    ),
+    (
+        "strum_macros",
+        "strum",
+        &[
+            "EnumString",
+            "EnumIter",
+            "EnumCount",
+            "EnumDiscriminants",
+            "AsRefStr",
+            "IntoStaticStr",
+            "VariantNames",
+            "FromRepr",
+            "Display",
+        ],
+    ),
+    (
+        "zerocopy-derive",
+        "zerocopy",
+        &[
+            "FromBytes",
+            "FromZeros",
+            "IntoBytes",
+            "TryFromBytes",
+            "KnownLayout",
+            "Immutable",
+            "Unaligned",
+        ],
+    ),
+    ("bytemuck_derive", "bytemuck", &["Pod", "Zeroable"]),
+    (
+        "pin-project-internal",
+        "pin-project",
+        &["pin_project", "pinned_drop"],
+    ),
+];
+
+/// Build-script code generators, and the crates the code they generate
+/// uses
+///
+/// The generated code is `include!`d from `OUT_DIR`, outside the sources
+/// scanned, so those crates may never be named anywhere else.
+const CODE_GENERATORS: &[(&str, &[&str])] = &[
+    ("prost-build", &["prost", "prost-types"]),
+    ("tonic-build", &["tonic", "prost", "prost-types"]),
+    ("protobuf-codegen", &["protobuf"]),
+    ("capnpc", &["capnp"]),
+    ("lalrpop", &["lalrpop-util"]),
+    ("cxx-build", &["cxx"]),
+];
+
+/// Finds the crates a Rust workspace's source code refers to
+///
+/// Source is scanned textually for paths rooted at a known crate
+/// (`serde::Deserialize`, `#[tokio::main]`), `use` and `extern crate`
+/// items. Crates renamed in Cargo.toml (`foo = { package = "bar" }`) are
+/// matched by their local name.
+///
+/// Direct dependencies that are only expanded or generated are counted
+/// too: proc-macro crates behind a facade (`PROC_MACROS`), and the
+/// runtimes of build-script code generators (`CODE_GENERATORS`).
+pub struct RustUseScanner<'a> {
+    root: &'a Path,
+}
+
+impl<'a> RustUseScanner<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn scan(&self, packages: &HashMap<String, Package>) -> Result<ImportMap> {
+        let (files, crates) = self.sources(packages)?;
+        let direct: HashSet<&str> = packages
+            .values()
+            .filter(|pkg| pkg.is_direct)
+            .map(|pkg| pkg.name.as_str())
+            .collect();
+
+        let mut imports = ImportMap::new();
+        for file in files {
+            let source = std::fs::read_to_string(&file)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read file: {}", file.display()))?;
+            let mut add = |line: usize, specifier: &str, kind: ImportKind, package: &str| {
+                imports.add_import(Import {
+                    file_path: file.clone(),
+                    line,
+                    specifier: specifier.to_string(),
+                    kind,
+                    resolved_package: Some(package.to_string()),
+                });
+            };
+
+            for (line, ident) in crate_references(&source) {
+                let Some(name) = crates.get(&ident) else {
+                    continue;
+                };
+                add(line, &ident, ImportKind::Use, name);
+                let runtimes = CODE_GENERATORS
+                    .iter()
+                    .filter(|(generator, _)| generator == name)
+                    .flat_map(|(_, runtimes)| runtimes.iter());
+                for runtime in runtimes.filter(|runtime| direct.contains(**runtime)) {
+                    add(line, &ident, ImportKind::Generated, runtime);
+                }
+            }
+
+            for (line, root, name) in macro_uses(&source) {
+                let facade = root.as_ref().map(|root| crates.get(root));
+                let owners = PROC_MACROS.iter().filter(|(owner, crate_facade, macros)| {
+                    direct.contains(owner)
+                        && macros.contains(&name.as_str())
+                        && match facade {
+                            Some(facade) => facade.is_some_and(|facade| facade == crate_facade),
+                            // `#[test]` is the built-in one
+                            None => name != "test",
+                        }
+                });
+                for (owner, _, _) in owners {
+                    add(line, &name, ImportKind::Macro, owner);
+                }
+            }
+            imports.mark_file_analyzed(file);
+        }
+
+        Ok(imports)
+    }
+
+    /// Crates the workspace re-exports with `pub use` or `pub extern crate`
+    ///
+    /// Their types are part of the workspace's public API, so two versions
+    /// of one of them can't be mixed by callers.
+    pub fn public_crates(&self, packages: &HashMap<String, Package>) -> Result<HashSet<String>> {
+        let (files, crates) = self.sources(packages)?;
+
+        let mut public = HashSet::new();
+        for file in files {
+            let source = std::fs::read_to_string(&file)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read file: {}", file.display()))?;
+            for ident in reexported_idents(&source) {
+                if let Some(name) = crates.get(&ident) {
+                    public.insert(name.clone());
+                }
+            }
+        }
+        Ok(public)
+    }
+
+    /// Names the workspace's paths take from each crate, by crate name
+    ///
+    /// Every segment after the crate counts, as do the names of `use`
+    /// groups: `use serde::{de::Visitor, Serialize}` uses "de", "Visitor"
+    /// and "Serialize". A glob import uses "*".
+    pub fn used_names(
+        &self,
+        packages: &HashMap<String, Package>,
+    ) -> Result<HashMap<String, HashSet<String>>> {
+        let (files, crates) = self.sources(packages)?;
+
+        let mut used: HashMap<String, HashSet<String>> = HashMap::new();
+        for file in files {
+            let source = std::fs::read_to_string(&file)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read file: {}", file.display()))?;
+            for (ident, names) in path_names(&tokenize_source(&source)) {
+                if let Some(name) = crates.get(&ident) {
+                    used.entry(name.clone()).or_default().extend(names);
+                }
+            }
+        }
+        Ok(used)
+    }
+
+    /// The workspace's `.rs` files, and a map of local identifier -> crate name
+    fn sources(
+        &self,
+        packages: &HashMap<String, Package>,
+    ) -> Result<(Vec<PathBuf>, HashMap<String, String>)> {
+        let mut files = Vec::new();
+        let mut manifests = Vec::new();
+
+        let walker = WalkBuilder::new(self.root)
+            .hidden(true)
+            .git_ignore(true)
+            .filter_entry(|entry| entry.file_name() != "target")
+            .build();
+        for entry in walker {
+            let entry = entry
+                .into_diagnostic()
+                .context("Failed to read directory entry")?;
+            let path = resolve::normalize(entry.path());
+            if !path.is_file() {
+                continue;
+            }
+            if path.extension().is_some_and(|e| e == "rs") {
+                files.push(path);
+            } else if path.file_name().is_some_and(|n| n == "Cargo.toml") {
+                manifests.push(path);
+            }
+        }
+
+        let mut crates: HashMap<String, String> = packages
+            .values()
+            .map(|pkg| (pkg.name.replace('-', "_"), pkg.name.clone()))
+            .collect();
+        for manifest in &manifests {
+            crates.extend(renamed_dependencies(manifest)?);
+        }
+
+        Ok((files, crates))
+    }
+}
+
+/// Dependencies a Cargo.toml renames, as local identifier -> crate name
+fn renamed_dependencies(manifest: &PathBuf) -> Result<HashMap<String, String>> {
+    let content = std::fs::read_to_string(manifest)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", manifest.display()))?;
+    let Ok(value) = toml::from_str::<TomlValue>(&content) else {
+        return Ok(HashMap::new());
+    };
+
+    let mut tables: Vec<&TomlValue> = DEPENDENCY_TABLES
+        .iter()
+        .filter_map(|t| value.get(t))
+        .collect();
+    if let Some(workspace) = value.get("workspace") {
+        tables.extend(workspace.get("dependencies"));
+    }
+    if let Some(targets) = value.get("target").and_then(|t| t.as_table()) {
+        for target in targets.values() {
+            tables.extend(DEPENDENCY_TABLES.iter().filter_map(|t| target.get(t)));
+        }
+    }
+
+    Ok(tables
+        .into_iter()
+        .filter_map(|table| table.as_table())
+        .flat_map(|table| table.iter())
+        .filter_map(|(alias, spec)| {
+            let package = spec.get("package")?.as_str()?;
+            Some((alias.replace('-', "_"), package.to_string()))
+        })
+        .collect())
+}
+
+/// Identifiers that may name a crate, with their line numbers
+///
+/// Yields the first segment of every `a::b` path and the names in
+/// `use a;`/`extern crate a;`. Modules declared in the file (`mod a;`)
+/// are skipped, since they shadow crates of the same name.
+fn crate_references(source: &str) -> Vec<(usize, String)> {
+    let mut local_modules = HashSet::new();
+    let mut references = Vec::new();
+
+    let tokens = tokenize(source);
+    for (i, (line, token)) in tokens.iter().enumerate() {
+        let next = tokens.get(i + 1).map(|(_, t)| t.as_str());
+        let previous = i.checked_sub(1).map(|p| tokens[p].1.as_str());
+
+        if token == "mod" {
+            if let Some(name) = next.filter(|n| is_ident(n)) {
+                local_modules.insert(name.to_string());
+            }
+            continue;
+        }
+        if !is_ident(token) {
+            continue;
+        }
+
+        let is_path_root = next == Some("::")
+            && !matches!(previous, Some("::"))
+            && !matches!(token.as_str(), "crate" | "self" | "super" | "Self");
+        let is_item = matches!(previous, Some("use") | Some("crate"))
+            && matches!(next, Some(";") | Some("as"));
+
+        if is_path_root || is_item {
+            references.push((*line, token.clone()));
+        }
+    }
+
+    references.retain(|(_, ident)| !local_modules.contains(ident));
+    references
+}
+
+/// Derive and attribute macros a file applies, as line, path root (for
+/// `tokio::main`, `None` for a bare `instrument`) and macro name
+///
+/// Covers the attribute's own path and every entry of a `derive(...)`
+/// list, including one inside `cfg_attr`.
+fn macro_uses(source: &str) -> Vec<(usize, Option<String>, String)> {
+    let tokens = tokenize(source);
+
+    let mut uses = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let inner = usize::from(tokens.get(i + 1).is_some_and(|(_, t)| t == "!"));
+        if tokens[i].1 != "#" || tokens.get(i + 1 + inner).is_none_or(|(_, t)| t != "[") {
+            i += 1;
+            continue;
+        }
+
+        let start = i + 2 + inner;
+        let mut depth = 1;
+        let mut end = start;
+        while let Some((_, token)) = tokens.get(end) {
+            match token.as_str() {
+                "[" => depth += 1,
+                "]" => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            end += 1;
+        }
+        let attribute = &tokens[start..end];
+
+        uses.extend(macro_path(attribute, 0).map(|(path, _)| path));
+        for (j, (_, token)) in attribute.iter().enumerate() {
+            if token != "derive" || attribute.get(j + 1).is_none_or(|(_, t)| t != "(") {
+                continue;
+            }
+            let mut k = j + 2;
+            while let Some((_, token)) = attribute.get(k) {
+                if token == ")" {
+                    break;
+                }
+                match macro_path(attribute, k) {
+                    Some((path, next)) => {
+                        uses.push(path);
+                        k = next;
+                    }
+                    None => k += 1,
+                }
+            }
+        }
+        i = end + 1;
+    }
+    uses
+}
+
+/// The path starting at `tokens[start]`, as line, root and last segment,
+/// and the index after it
+fn macro_path(
+    tokens: &[(usize, String)],
+    start: usize,
+) -> Option<((usize, Option<String>, String), usize)> {
+    let (line, first) = tokens.get(start).filter(|(_, t)| is_ident(t))?;
+    let mut name = first;
+    let mut end = start + 1;
+    while tokens.get(end).is_some_and(|(_, t)| t == "::") {
+        match tokens.get(end + 1) {
+            Some((_, segment)) if is_ident(segment) => {
+                name = segment;
+                end += 2;
+            }
+            _ => break,
+        }
+    }
+    let root = (end > start + 1).then(|| first.clone());
+    Some(((*line, root, name.clone()), end))
+}
+
+/// First path segment of every `pub use` and `pub extern crate` item
+///
+/// Restricted visibility (`pub(crate) use`) isn't public and is skipped.
+fn reexported_idents(source: &str) -> Vec<String> {
+    let mut idents = Vec::new();
+    let tokens = tokenize_source(source);
+    for (i, token) in tokens.iter().enumerate() {
+        if token != "pub" {
+            continue;
+        }
+        let rest: Vec<&str> = tokens[i + 1..(i + 4).min(tokens.len())]
+            .iter()
+            .map(String::as_str)
+            .collect();
+        let ident = match rest.as_slice() {
+            ["use", "::", ident, ..] | ["use", ident, ..] => ident,
+            ["extern", "crate", ident, ..] => ident,
+            _ => continue,
+        };
+        if is_ident(ident) && !matches!(*ident, "crate" | "self" | "super") {
+            idents.push(ident.to_string());
+        }
+    }
+    idents
+}
+
+/// Names following each path root, across lines
+///
+/// `a::b::{c, d as e}` yields ("a", ["b", "c", "d"]); the alias is local.
+fn path_names(tokens: &[String]) -> Vec<(String, Vec<String>)> {
+    let mut paths = Vec::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let is_root = is_ident(token)
+            && tokens.get(i + 1).is_some_and(|next| next == "::")
+            && (i == 0 || tokens[i - 1] != "::")
+            && !matches!(token.as_str(), "crate" | "self" | "super" | "Self");
+        if !is_root {
+            i += 1;
+            continue;
+        }
+
+        let mut names = Vec::new();
+        let mut j = i + 1;
+        while tokens.get(j).is_some_and(|t| t == "::") {
+            match tokens.get(j + 1).map(String::as_str) {
+                Some("{") => {
+                    let mut depth = 0;
+                    j += 1;
+                    while let Some(token) = tokens.get(j) {
+                        match token.as_str() {
+                            "{" => depth += 1,
+                            "}" => {
+                                depth -= 1;
+                                if depth == 0 {
+                                    break;
+                                }
+                            }
+                            // Skip the alias
+                            "as" => j += 1,
+                            "*" => names.push("*".to_string()),
+                            name if is_ident(name) && name != "self" => {
+                                names.push(name.to_string())
+                            }
+                            _ => {}
+                        }
+                        j += 1;
+                    }
+                    break;
+                }
+                Some("*") => {
+                    names.push("*".to_string());
+                    break;
+                }
+                Some(name) if is_ident(name) => {
+                    names.push(name.to_string());
+                    j += 2;
+                }
+                _ => break,
+            }
+        }
+        paths.push((token.clone(), names));
+        i = j.max(i + 1);
+    }
+    paths
+}
+
+/// Tokens of a whole file, without comments and literals
+pub fn tokenize_source(source: &str) -> Vec<String> {
+    tokenize(source)
+        .into_iter()
+        .map(|(_, token)| token)
+        .collect()
+}
+
+/// Split source into identifiers, `::` and single punctuation characters,
+/// each with its line number
+///
+/// Line and (nested) block comments, strings, raw strings and char
+/// literals are skipped, so paths in docs and messages don't count.
+/// Lifetimes (`'a`) come out as their name.
+fn tokenize(source: &str) -> Vec<(usize, String)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut line = 1;
+    let mut i = 0;
+
+    // Skips from `chars[start]` to just past `end`, counting lines
+    let skip_until = |start: usize, end: &[char], line: &mut usize| -> usize {
+        let mut i = start;
+        while i < chars.len() && !chars[i..].starts_with(end) {
+            if chars[i] == '\n' {
+                *line += 1;
+            }
+            i += 1;
+        }
+        (i + end.len()).min(chars.len())
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c == '/' && next == Some('/') {
+            i = skip_until(i, &['\n'], &mut line);
+            line += 1;
+        } else if c == '/' && next == Some('*') {
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i..].starts_with(&['/', '*']) {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i..].starts_with(&['*', '/']) {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    if chars[i] == '\n' {
+                        line += 1;
+                    }
+                    i += 1;
+                }
+            }
+        } else if let Some(end) = raw_string_start(&chars, i) {
+            // r"..", r#".."#, br".."
+            let (hashes, quote) = end;
+            let mut closing = vec!['"'];
+            closing.extend(std::iter::repeat_n('#', hashes));
+            i = skip_until(quote + 1, &closing, &mut line);
+        } else if c == '"' || (c == 'b' && next == Some('"')) {
+            i += if c == 'b' { 2 } else { 1 };
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\n' {
+                    line += 1;
+                }
+                i += if chars[i] == '\\' { 2 } else { 1 };
+            }
+            i += 1;
+        } else if c == '\'' || (c == 'b' && next == Some('\'')) {
+            let start = if c == 'b' { i + 1 } else { i };
+            if chars.get(start + 1) == Some(&'\\') {
+                // '\n', '\'', '\u{1F600}'
+                let mut j = start + 3;
+                while j < chars.len() && chars[j] != '\'' {
+                    j += 1;
+                }
+                i = j + 1;
+            } else if chars.get(start + 2) == Some(&'\'') {
+                i = start + 3;
+            } else {
+                // A lifetime; its name follows as an identifier
+                i = start + 1;
+            }
+        } else if c.is_alphanumeric() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let ident: String = chars[start..i].iter().collect();
+            // Raw identifiers: r#type
+            if ident == "r" && chars.get(i) == Some(&'#') {
+                i += 1;
+                continue;
+            }
+            tokens.push((line, ident));
+        } else if c == ':' && next == Some(':') {
+            tokens.push((line, "::".to_string()));
+            i += 2;
+        } else {
+            if !c.is_whitespace() {
+                tokens.push((line, c.to_string()));
+            }
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+/// Whether a raw string starts at `chars[i]`: its `#` count and the index
+/// of its opening quote
+fn raw_string_start(chars: &[char], i: usize) -> Option<(usize, usize)> {
+    // The prefix must start a token, not end an identifier like `bar"`
+    if i > 0 && (chars[i - 1].is_alphanumeric() || chars[i - 1] == '_') {
+        return None;
+    }
+    let mut j = i;
+    if chars.get(j) == Some(&'b') {
+        j += 1;
+    }
+    if chars.get(j) != Some(&'r') {
+        return None;
+    }
+    j += 1;
+    let hashes = chars[j..].iter().take_while(|c| **c == '#').count();
+    (chars.get(j + hashes) == Some(&'"')).then_some((hashes, j + hashes))
+}
+
+pub fn is_ident(token: &str) -> bool {
+    token
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_crate_references() {
+        let source = r#"
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+extern crate libc;
+mod config;
+
+#[tokio::main]
+async fn main() {
+    let x = crate::config::load();
+    let v = serde_json::from_str::<HashMap<String, u8>>("{}"); // regex::Regex
+    println!("see reqwest::get");
+    let quote = '"'; let c = '\\'; let q = '\''; regex::escape("x");
+    config::reload();
+}
+"#;
+        let idents: Vec<(usize, String)> = crate_references(source);
+        let names: Vec<&str> = idents.iter().map(|(_, i)| i.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["serde", "std", "libc", "tokio", "serde_json", "regex"]
+        );
+        assert_eq!(idents[3].0, 7);
+    }
+
+    #[test]
+    fn test_literals_and_comments() {
+        let source = r##"
+const FIXTURE: &str = r#"
+extern crate libc;
+use rand::Rng;
+"#;
+/* block::comment
+   /* nested::comment */ still::comment */
+fn main() {
+    let escaped = "say \"hi\" to regex::Regex";
+    let url = "https://docs.rs"; let _ = serde_json::json!({});
+    let multi = "first::line
+        second::line";
+    let bytes = br"raw::bytes"; let r#type = 1;
+    tracing::info!("{}", 'x');
+}
+"##;
+        let idents = crate_references(source);
+        assert_eq!(
+            idents,
+            vec![(10, "serde_json".to_string()), (14, "tracing".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_macro_uses() {
+        let source = r#"
+#![deny(missing_docs)]
+#[derive(Debug, Clone, serde::Serialize,
+    Deserialize)]
+#[cfg_attr(feature = "cli", derive(clap::Parser))]
+struct Options;
+
+#[tokio::main]
+#[instrument(skip(options))]
+async fn main() {}
+"#;
+        let uses = macro_uses(source);
+        let uses: Vec<(usize, Option<&str>, &str)> = uses
+            .iter()
+            .map(|(line, root, name)| (*line, root.as_deref(), name.as_str()))
+            .collect();
+        assert_eq!(
+            uses,
+            vec![
+                (2, None, "deny"),
+                (3, None, "derive"),
+                (3, None, "Debug"),
+                (3, None, "Clone"),
+                (3, Some("serde"), "Serialize"),
+                (4, None, "Deserialize"),
+                (5, None, "cfg_attr"),
+                (5, Some("clap"), "Parser"),
+                (8, Some("tokio"), "main"),
+                (9, None, "instrument"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reexported_idents() {
+        let source = r#"
+pub use serde_json::Value;
+pub use ::http::{Method, StatusCode};
+pub(crate) use regex::Regex;
+pub use crate::config::Config;
+pub extern crate libc;
+use tokio::sync::Mutex; // pub use rand::Rng
+"#;
+        assert_eq!(
+            reexported_idents(source),
+            vec!["serde_json", "http", "libc"]
+        );
+    }
+
+    #[test]
+    fn test_path_names() {
+        let source = r#"
+use serde::{
+    de::{self, Visitor},
+    Serialize as Ser,
+};
+use tokio::prelude::*;
+fn run() { reqwest::Client::new(); let v = serde_json::from_str::<u8>("1"); }
+"#;
+        let names: Vec<(String, Vec<String>)> = path_names(&tokenize_source(source));
+        let names: Vec<(&str, Vec<&str>)> = names
+            .iter()
+            .map(|(root, names)| (root.as_str(), names.iter().map(String::as_str).collect()))
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                ("serde", vec!["de", "Visitor", "Serialize"]),
+                ("tokio", vec!["prelude", "*"]),
+                ("reqwest", vec!["Client", "new"]),
+                ("serde_json", vec!["from_str"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_workspace() {
+        let root = TempDir::new("rust");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("target/debug")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            "[package]\nname = \"app\"\n\n[dependencies]\nhttp-client = { package = \"reqwest\", version = \"0.12\" }\nserde = \"1\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/main.rs"),
+            "use http_client::Client;\nfn main() { let _ = serde_json::json!({}); }\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("target/debug/build.rs"),
+            "use serde::Serialize;\n",
+        )
+        .unwrap();
+
+        let packages: HashMap<String, Package> = ["reqwest", "serde", "serde_json"]
+            .iter()
+            .map(|name| (format!("{}@1.0.0", name), Package::new(*name, "1.0.0")))
+            .collect();
+        let imports = RustUseScanner::new(&root).scan(&packages).unwrap();
+
+        let mut used: Vec<String> = imports.packages_used().into_iter().collect();
+        used.sort();
+        assert_eq!(used, vec!["reqwest", "serde_json"]);
+    }
+
+    #[test]
+    fn test_scan_macros_and_generated_code() {
+        let root = TempDir::new("rust-macros");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "use serde::Serialize;\n\n#[derive(Serialize)]\nstruct Config;\n\n#[test]\nfn works() {}\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("build.rs"),
+            "fn main() {\n    prost_build::compile_protos(&[\"api.proto\"], &[\".\"]).unwrap();\n}\n",
+        )
+        .unwrap();
+
+        let mut packages: HashMap<String, Package> = [
+            "serde",
+            "serde_derive",
+            "tokio-macros",
+            "prost",
+            "prost-build",
+        ]
+        .iter()
+        .map(|name| (name.to_string(), Package::new(*name, "1.0.0").direct()))
+        .collect();
+        // Not declared, so not attributed: that would make it a phantom
+        packages.insert(
+            "prost-types".to_string(),
+            Package::new("prost-types", "1.0.0"),
+        );
+        let imports = RustUseScanner::new(&root).scan(&packages).unwrap();
+
+        let mut used: Vec<String> = imports.packages_used().into_iter().collect();
+        used.sort();
+        assert_eq!(used, vec!["prost", "prost-build", "serde", "serde_derive"]);
+        let derive = &imports.get_package_usages("serde_derive").unwrap()[0];
+        assert_eq!((derive.line, derive.kind.clone()), (3, ImportKind::Macro));
+        let generated = &imports.get_package_usages("prost").unwrap()[0];
+        assert_eq!(generated.kind, ImportKind::Generated);
+        assert!(generated.file_path.ends_with("build.rs"));
+    }
+}
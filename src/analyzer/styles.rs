@@ -0,0 +1,248 @@
+use std::path::Path;
+
+use crate::types::{Import, ImportKind};
+
+use super::extract_package_name;
+
+/// Stylesheet extensions whose imports are analyzed
+pub(super) const STYLE_EXTENSIONS: &[&str] = &["css", "scss", "sass", "less"];
+
+/// What Sass and Less append to an import path when looking for the file
+const STYLE_SUFFIXES: &[&str] = &["", ".scss", ".sass", ".css", ".less"];
+
+/// Package imports of a stylesheet's `@import`, `@use` and `@forward`
+/// rules, or `None` for other files
+///
+/// A `~` prefix, as webpack's sass-loader and less-loader use it, always
+/// names a package. A bare path does unless it's a file next to the
+/// stylesheet (or a Sass partial or index), since Sass and Less look there
+/// first. `sass:` modules, URLs and interpolated paths are left out.
+pub(super) fn style_imports(path: &Path, source: &str) -> Option<Vec<Import>> {
+    let extension = path.extension().and_then(|e| e.to_str())?;
+    if !STYLE_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let source = strip_comments(source, extension != "css");
+    let mut imports = Vec::new();
+    for (offset, keyword, body) in at_rules(&source, extension == "sass") {
+        for specifier in rule_specifiers(keyword, body) {
+            let Some(specifier) = package_specifier(dir, &specifier) else {
+                continue;
+            };
+            imports.push(Import {
+                file_path: path.to_path_buf(),
+                line: source[..offset].matches('\n').count() + 1,
+                specifier: specifier.to_string(),
+                kind: ImportKind::Style,
+                resolved_package: extract_package_name(specifier),
+            });
+        }
+    }
+    Some(imports)
+}
+
+/// `source` with comments blanked out, keeping offsets; `//` comments only
+/// exist in Sass and Less
+fn strip_comments(source: &str, line_comments: bool) -> String {
+    let bytes = source.as_bytes();
+    let mut stripped = bytes.to_vec();
+    let mut quote = None;
+    let mut i = 0;
+    while i < bytes.len() {
+        let comment_end = match (quote, bytes[i], bytes.get(i + 1)) {
+            (Some(_), b'\\', _) => {
+                i += 2;
+                continue;
+            }
+            (Some(q), c, _) => {
+                if c == q || c == b'\n' {
+                    quote = None;
+                }
+                i += 1;
+                continue;
+            }
+            (None, b'"' | b'\'', _) => {
+                quote = Some(bytes[i]);
+                i += 1;
+                continue;
+            }
+            (None, b'/', Some(b'*')) => source[i + 2..]
+                .find("*/")
+                .map_or(bytes.len(), |end| i + 2 + end + 2),
+            // Not the `//` of an unquoted `url(https://...)` or `url(//...)`
+            (None, b'/', Some(b'/'))
+                if line_comments && (i == 0 || !matches!(bytes[i - 1], b':' | b'(')) =>
+            {
+                source[i..].find('\n').map_or(bytes.len(), |end| i + end)
+            }
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        for byte in &mut stripped[i..comment_end] {
+            if *byte != b'\n' {
+                *byte = b' ';
+            }
+        }
+        i = comment_end;
+    }
+    String::from_utf8(stripped).unwrap_or_default()
+}
+
+/// Offset, keyword and body of each import rule; bodies end at `;`, or at
+/// the end of the line in indented Sass
+fn at_rules(source: &str, indented: bool) -> Vec<(usize, &'static str, &str)> {
+    let mut rules = Vec::new();
+    let mut offset = 0;
+    while let Some(found) = source[offset..].find('@') {
+        let start = offset + found;
+        offset = start + 1;
+        let after = &source[offset..];
+        let Some(keyword) = ["import", "use", "forward"].into_iter().find(|keyword| {
+            after
+                .strip_prefix(keyword)
+                .is_some_and(|rest| rest.starts_with([' ', '\t', '\n', '"', '\'', '(']))
+        }) else {
+            continue;
+        };
+
+        let body_start = offset + keyword.len();
+        let body_end = source[body_start..]
+            .find(|c| matches!(c, ';' | '{' | '}') || (indented && c == '\n'))
+            .map_or(source.len(), |end| body_start + end);
+        rules.push((start, keyword, &source[body_start..body_end]));
+        offset = body_end;
+    }
+    rules
+}
+
+/// The paths a rule imports: every string and `url()` of an `@import`,
+/// the first one of `@use` and `@forward` (the rest configure the module)
+///
+/// Less import options such as `(reference)` are skipped.
+fn rule_specifiers(keyword: &str, body: &str) -> Vec<String> {
+    let mut specifiers = Vec::new();
+    let mut depth = 0usize;
+    let mut chars = body.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' | '\'' if depth == 0 => {
+                specifiers.push(
+                    chars
+                        .by_ref()
+                        .map(|(_, d)| d)
+                        .take_while(|d| *d != c)
+                        .collect(),
+                );
+            }
+            '(' if depth == 0 && body[..i].trim_end().ends_with("url") => {
+                let url: String = chars
+                    .by_ref()
+                    .map(|(_, d)| d)
+                    .take_while(|d| *d != ')')
+                    .collect();
+                specifiers.push(url.trim().trim_matches(['"', '\'']).to_string());
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+        if keyword != "import" && !specifiers.is_empty() {
+            break;
+        }
+    }
+    specifiers
+}
+
+/// The package path an import names, without its `~`
+fn package_specifier<'a>(dir: &Path, specifier: &'a str) -> Option<&'a str> {
+    let specifier = specifier.trim();
+    if let Some(package) = specifier.strip_prefix('~') {
+        // `~/` is Nuxt's alias for the source directory
+        return (!package.is_empty() && !package.starts_with('/')).then_some(package);
+    }
+
+    let is_path = specifier.is_empty()
+        || specifier.starts_with(['.', '/', '#'])
+        || specifier.contains("://")
+        || ["sass:", "data:"].iter().any(|p| specifier.starts_with(p))
+        || specifier.contains("#{")
+        || specifier.contains("@{");
+    (!is_path && !is_local(dir, specifier)).then_some(specifier)
+}
+
+/// Whether a bare import path is a file next to the stylesheet, as Sass
+/// and Less resolve it: with or without an extension, as a `_` partial, or
+/// as a directory's index
+fn is_local(dir: &Path, specifier: &str) -> bool {
+    let path = dir.join(specifier);
+    let mut candidates = vec![path.clone()];
+    if let Some(name) = path.file_name() {
+        let mut partial = std::ffi::OsString::from("_");
+        partial.push(name);
+        candidates.push(path.with_file_name(partial));
+    }
+    for index in ["_index", "index"] {
+        candidates.push(path.join(index));
+    }
+
+    candidates.iter().any(|candidate| {
+        STYLE_SUFFIXES.iter().any(|suffix| {
+            let mut file = candidate.clone().into_os_string();
+            file.push(suffix);
+            Path::new(&file).is_file()
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_specifiers() {
+        let source = r#"/* @import "commented-out"; */
+@use "sass:math";
+@use "~bootstrap/scss/bootstrap" with ($primary: "teal");
+// @import 'also-commented';
+@import (reference) "~@fortawesome/fontawesome-free/less/fontawesome";
+@import url("https://fonts.googleapis.com/css2?family=Inter"), "normalize.css" screen;
+@forward "@angular/material" as mat-*;
+.icon { background: url(//cdn.example.com/icon.svg); }
+"#;
+        let specifiers: Vec<(usize, Vec<String>)> = at_rules(&strip_comments(source, true), false)
+            .into_iter()
+            .map(|(offset, keyword, body)| {
+                (
+                    source[..offset].matches('\n').count() + 1,
+                    rule_specifiers(keyword, body),
+                )
+            })
+            .collect();
+        assert_eq!(
+            specifiers,
+            vec![
+                (2, vec!["sass:math".to_string()]),
+                (3, vec!["~bootstrap/scss/bootstrap".to_string()]),
+                (
+                    5,
+                    vec!["~@fortawesome/fontawesome-free/less/fontawesome".to_string()]
+                ),
+                (
+                    6,
+                    vec![
+                        "https://fonts.googleapis.com/css2?family=Inter".to_string(),
+                        "normalize.css".to_string()
+                    ]
+                ),
+                (7, vec!["@angular/material".to_string()]),
+            ]
+        );
+
+        let indented = "@use \"theme\"\n.a\n  color: red\n";
+        assert_eq!(at_rules(indented, true)[0].2, " \"theme\"");
+    }
+}
@@ -0,0 +1,292 @@
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+
+use super::configs::{parse_json, strings, ConfigValue};
+use super::resolve::{self, installed_package, target_of, AliasTarget};
+use crate::workspace;
+
+/// Project files declaring compiler options, in the order they're looked for
+const PROJECT_FILES: &[&str] = &["tsconfig.json", "jsconfig.json"];
+
+/// Longest `extends` chain followed, which also stops cycles
+const MAX_EXTENDS: usize = 16;
+
+/// `compilerOptions.paths` and `baseUrl` of the project's tsconfig files
+///
+/// The tsconfig.json (or jsconfig.json) of the root and of each workspace
+/// member applies to the files below it. As in TypeScript, `extends`
+/// chains are followed, the nearest `paths` replaces inherited ones, and
+/// targets resolve against `baseUrl`, or else the file declaring `paths`.
+/// A specifier whose targets don't exist falls through to node_modules,
+/// the way the compiler resolves it.
+#[derive(Debug, Default)]
+pub struct PathAliases {
+    /// Deepest directory first, so the nearest project wins
+    projects: Vec<Project>,
+}
+
+#[derive(Debug)]
+struct Project {
+    dir: PathBuf,
+    base_url: Option<PathBuf>,
+
+    /// Pattern -> target templates, resolved against the base directory
+    paths: Vec<(String, Vec<String>)>,
+}
+
+/// `baseUrl` and `paths` once the `extends` chain is applied
+#[derive(Debug, Default)]
+struct CompilerOptions {
+    base_url: Option<PathBuf>,
+
+    /// The directory of the file declaring them, and the mappings
+    paths: Option<(PathBuf, Vec<(String, Vec<String>)>)>,
+}
+
+impl PathAliases {
+    pub fn load(root: &Path) -> Result<Self> {
+        let mut dirs = vec![root.to_path_buf()];
+        dirs.extend(workspace::member_dirs(root)?);
+
+        let mut projects = Vec::new();
+        for dir in dirs {
+            let Some(path) = PROJECT_FILES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.is_file())
+            else {
+                continue;
+            };
+            let options = compiler_options(&path, 0)?;
+            if options.base_url.is_none() && options.paths.is_none() {
+                continue;
+            }
+
+            let (paths_dir, paths) = options.paths.unwrap_or_default();
+            let base = options.base_url.clone().unwrap_or(paths_dir);
+            let paths = paths
+                .into_iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets
+                        .iter()
+                        .map(|target| base.join(target).to_string_lossy().into_owned())
+                        .collect();
+                    (pattern, targets)
+                })
+                .collect();
+            projects.push(Project {
+                dir,
+                base_url: options.base_url,
+                paths,
+            });
+        }
+
+        projects.sort_by_key(|project| std::cmp::Reverse(project.dir.components().count()));
+        Ok(Self { projects })
+    }
+
+    /// Where a bare specifier imported by `file` points, when an alias or
+    /// `baseUrl` resolves it
+    pub fn resolve(&self, file: &Path, specifier: &str) -> Option<AliasTarget> {
+        self.projects
+            .iter()
+            .find(|project| file.starts_with(&project.dir))?
+            .resolve(specifier)
+    }
+}
+
+impl Project {
+    fn resolve(&self, specifier: &str) -> Option<AliasTarget> {
+        if let Some((targets, matched)) = self.matching(specifier) {
+            let substituted: Vec<String> = targets
+                .iter()
+                .map(|target| target.replacen('*', matched, 1))
+                .collect();
+            if let Some(file) = substituted
+                .iter()
+                .find_map(|target| resolve::resolve_file(Path::new(target)))
+            {
+                return Some(target_of(file));
+            }
+            // A package that isn't installed yet still owns the import
+            if let Some(package) = substituted.iter().find_map(|t| installed_package(t)) {
+                return Some(AliasTarget::Package(package));
+            }
+        }
+
+        let file = resolve::resolve_file(&self.base_url.as_ref()?.join(specifier))?;
+        Some(target_of(file))
+    }
+
+    /// Targets of the pattern a specifier matches, and what its `*` stands for
+    ///
+    /// An exact pattern wins, then the wildcard with the longest prefix.
+    fn matching<'s>(&self, specifier: &'s str) -> Option<(&[String], &'s str)> {
+        let mut best: Option<(usize, &[String], &str)> = None;
+        for (pattern, targets) in &self.paths {
+            let Some((prefix, suffix)) = pattern.split_once('*') else {
+                if pattern == specifier {
+                    return Some((targets, ""));
+                }
+                continue;
+            };
+            if specifier.len() >= prefix.len() + suffix.len()
+                && specifier.starts_with(prefix)
+                && specifier.ends_with(suffix)
+                && best.is_none_or(|(longest, _, _)| prefix.len() > longest)
+            {
+                let matched = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                best = Some((prefix.len(), targets, matched));
+            }
+        }
+        best.map(|(_, targets, matched)| (targets, matched))
+    }
+}
+
+/// Compiler options of a tsconfig file, after the configs it extends
+///
+/// A later `extends` entry overrides an earlier one, and the file's own
+/// options override both. A file that doesn't parse contributes nothing.
+fn compiler_options(path: &Path, depth: usize) -> Result<CompilerOptions> {
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let Some(config) = parse_json(path, &content) else {
+        return Ok(CompilerOptions::default());
+    };
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut options = CompilerOptions::default();
+    if depth < MAX_EXTENDS {
+        for (extends, _) in config.get("extends").into_iter().flat_map(strings) {
+            let Some(base) = extended_config(dir, extends) else {
+                continue;
+            };
+            let inherited = compiler_options(&base, depth + 1)?;
+            options.base_url = inherited.base_url.or(options.base_url);
+            options.paths = inherited.paths.or(options.paths);
+        }
+    }
+
+    if let Some(compiler) = config.get("compilerOptions") {
+        if let Some((base_url, _)) = compiler.get("baseUrl").and_then(|v| strings(v).next()) {
+            options.base_url = Some(resolve::normalize(&dir.join(base_url)));
+        }
+        if let Some(ConfigValue::Map(entries)) = compiler.get("paths") {
+            let paths = entries
+                .iter()
+                .map(|(pattern, _, targets)| {
+                    let targets = strings(targets).map(|(t, _)| t.to_string()).collect();
+                    (pattern.clone(), targets)
+                })
+                .collect();
+            options.paths = Some((dir.to_path_buf(), paths));
+        }
+    }
+
+    Ok(options)
+}
+
+/// The file an `extends` entry names: a path relative to the config, or a
+/// package's config found in node_modules
+///
+/// Examples:
+/// - "./tsconfig.base" -> ./tsconfig.base.json
+/// - "@tsconfig/node20" -> node_modules/@tsconfig/node20/tsconfig.json
+/// - "@acme/config/tsconfig.web.json" -> node_modules/@acme/config/tsconfig.web.json
+fn extended_config(dir: &Path, extends: &str) -> Option<PathBuf> {
+    let config_file = |path: PathBuf| -> Option<PathBuf> {
+        let candidates = [
+            path.clone(),
+            PathBuf::from(format!("{}.json", path.display())),
+            path.join("tsconfig.json"),
+        ];
+        candidates.into_iter().find(|candidate| candidate.is_file())
+    };
+
+    if extends.starts_with('.') || Path::new(extends).is_absolute() {
+        return config_file(dir.join(extends));
+    }
+    dir.ancestors()
+        .find_map(|ancestor| config_file(ancestor.join("node_modules").join(extends)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_path_aliases() {
+        let root = TempDir::new("tsconfig");
+        for dir in [
+            "src/utils",
+            "packages/web/app",
+            "node_modules/@tsconfig/strictest",
+            "node_modules/real-lib",
+            "types",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write("package.json", r#"{ "workspaces": ["packages/*"] }"#);
+        write(
+            "tsconfig.base.json",
+            r#"{
+  // Shared by every package
+  "extends": "@tsconfig/strictest",
+  "compilerOptions": {
+    "paths": {
+      "@app/*": ["./src/*"],
+      "@lib": ["./node_modules/real-lib"],
+      "@vendor/*": ["./node_modules/vendor-*"],
+      "*": ["./types/*"],
+    },
+  },
+}"#,
+        );
+        write("tsconfig.json", r#"{ "extends": "./tsconfig.base" }"#);
+        write(
+            "node_modules/@tsconfig/strictest/tsconfig.json",
+            r#"{ "compilerOptions": { "strict": true } }"#,
+        );
+        write("node_modules/real-lib/index.js", "");
+        write("src/utils/format.ts", "");
+        write("types/globals.d.ts", "");
+        write(
+            "packages/web/tsconfig.json",
+            r#"{ "compilerOptions": { "baseUrl": "." } }"#,
+        );
+        write("packages/web/package.json", r#"{ "name": "web" }"#);
+        write("packages/web/app/page.tsx", "");
+
+        let aliases = PathAliases::load(&root).unwrap();
+        let from_root = root.join("src/index.ts");
+        let from_web = root.join("packages/web/app/layout.tsx");
+        let resolve = |file: &Path, specifier: &str| aliases.resolve(file, specifier);
+        let results = [
+            resolve(&from_root, "@app/utils/format"),
+            resolve(&from_root, "@lib"),
+            resolve(&from_root, "@vendor/ui"),
+            resolve(&from_root, "globals.d.ts"),
+            resolve(&from_root, "react"),
+            resolve(&from_web, "app/page"),
+            resolve(&from_web, "@app/utils/format"),
+        ];
+
+        assert_eq!(
+            results,
+            [
+                Some(AliasTarget::File(root.join("src/utils/format.ts"))),
+                Some(AliasTarget::Package("real-lib".to_string())),
+                Some(AliasTarget::Package("vendor-ui".to_string())),
+                Some(AliasTarget::File(root.join("types/globals.d.ts"))),
+                None,
+                Some(AliasTarget::File(root.join("packages/web/app/page.tsx"))),
+                // The member's own tsconfig doesn't extend the root's
+                None,
+            ]
+        );
+    }
+}
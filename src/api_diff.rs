@@ -0,0 +1,596 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use ring::digest;
+use serde::Deserialize;
+use walkdir::WalkDir;
+
+use crate::analyzer::rust::{is_ident, tokenize_source, RustUseScanner};
+use crate::integrity::home_dir;
+use crate::lockfile::registry_source_dir;
+use crate::types::{ApiChange, ApiChangeKind, ApiImpact, Package, UpdateCandidate};
+
+const CRATES_IO_DOWNLOADS: &str = "https://static.crates.io/crates";
+
+/// crates.io's sparse index, which records the checksum of every version
+const CRATES_IO_INDEX: &str = "https://index.crates.io";
+
+/// Crates compared at once, each downloading up to two versions
+const MAX_CONCURRENT: usize = 4;
+
+/// Unpacks started by this process, keeping their staging dirs apart
+static UNPACKS: AtomicUsize = AtomicUsize::new(0);
+
+/// Signature recorded for a re-export, whose item is declared elsewhere
+const REEXPORT: &str = "use";
+
+/// A crate's public items, mapped to the signatures declared under the name
+type PublicItems = BTreeMap<String, BTreeSet<String>>;
+
+/// Public API changes between the current and latest version of each crate
+///
+/// Sources come from Cargo's registry cache when it has them, or else are
+/// downloaded from crates.io. Items are read textually from `src/`, the
+/// way cargo-semver-checks would compare rustdoc output but without
+/// building: public functions, types, traits, constants, macros and
+/// re-exports, and the public methods of inherent impls. Module paths are
+/// not tracked, so an item moved to another module isn't reported. Crates
+/// whose sources can't be had get no annotation.
+///
+/// `checksums` are Cargo.lock's, keyed by "name@version"; the latest
+/// versions' come from the crates.io index.
+pub async fn api_impact(
+    client: &reqwest::Client,
+    root: &Path,
+    candidates: &[UpdateCandidate],
+    packages: &HashMap<String, Package>,
+    checksums: &HashMap<String, String>,
+) -> Result<HashMap<String, ApiImpact>> {
+    use tokio::task::JoinSet;
+
+    let used = RustUseScanner::new(root).used_names(packages)?;
+    let cache = Arc::new(
+        cache_dir()
+            .ok_or_else(|| miette::miette!("Couldn't find a cache directory for crate sources"))?,
+    );
+
+    let mut impacts = HashMap::new();
+    let nothing = HashSet::new();
+    let mut join_set = JoinSet::new();
+    for chunk in candidates.chunks(MAX_CONCURRENT) {
+        for candidate in chunk {
+            let client = client.clone();
+            let cache = cache.clone();
+            let candidate = candidate.clone();
+            let current_checksum = checksums
+                .get(&format!("{}@{}", candidate.package, candidate.current))
+                .cloned();
+            join_set.spawn(async move {
+                let current = crate_sources(
+                    &client,
+                    &cache,
+                    &candidate.package,
+                    &candidate.current,
+                    current_checksum,
+                )
+                .await;
+                let latest =
+                    crate_sources(&client, &cache, &candidate.package, &candidate.latest, None)
+                        .await;
+                let items = match (current, latest) {
+                    (Ok(current), Ok(latest)) => public_items(&current)
+                        .and_then(|current| public_items(&latest).map(|latest| (current, latest))),
+                    (Err(e), _) | (_, Err(e)) => Err(e),
+                };
+                (candidate.package, items)
+            });
+        }
+
+        // Wait for this batch to complete before starting next
+        while let Some(result) = join_set.join_next().await {
+            if let Ok((package, Ok((current, latest)))) = result {
+                let used = used.get(&package).unwrap_or(&nothing);
+                let changes = api_changes(&current, &latest, used);
+                impacts.insert(package, ApiImpact { changes });
+            }
+        }
+    }
+    Ok(impacts)
+}
+
+/// The user's cache for downloaded crate sources
+fn cache_dir() -> Option<PathBuf> {
+    let base = if let Some(cache) = std::env::var_os("XDG_CACHE_HOME") {
+        PathBuf::from(cache)
+    } else if cfg!(windows) {
+        PathBuf::from(std::env::var_os("LOCALAPPDATA")?)
+    } else if cfg!(target_os = "macos") {
+        home_dir()?.join("Library/Caches")
+    } else {
+        home_dir()?.join(".cache")
+    };
+    Some(base.join("depx").join("crates"))
+}
+
+/// Unpacked sources of a crate version
+///
+/// Downloads go to a per-user cache, since Cargo's own is only filled by
+/// builds. The `.crate` file is checked against `checksum`, or the one the
+/// crates.io index records, then unpacked with the system's `tar` into a
+/// staging directory renamed into place, so the cache only ever holds
+/// complete, verified sources.
+async fn crate_sources(
+    client: &reqwest::Client,
+    cache: &Path,
+    name: &str,
+    version: &str,
+    checksum: Option<String>,
+) -> Result<PathBuf> {
+    if let Some(dir) = registry_source_dir(name, version) {
+        return Ok(dir);
+    }
+
+    let dir = cache.join(format!("{}-{}", name, version));
+    if dir.is_dir() {
+        return Ok(dir);
+    }
+
+    let checksum = match checksum {
+        Some(checksum) => checksum,
+        None => index_checksum(client, name, version).await?,
+    };
+
+    let url = format!(
+        "{}/{}/{}-{}.crate",
+        CRATES_IO_DOWNLOADS, name, name, version
+    );
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to download {}@{}", name, version))?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to download {}@{}: HTTP {}",
+            name,
+            version,
+            response.status()
+        );
+    }
+    let bytes = response.bytes().await.into_diagnostic()?;
+    let actual: String = digest::digest(&digest::SHA256, &bytes)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if !actual.eq_ignore_ascii_case(&checksum) {
+        bail!(
+            "{}@{} from crates.io doesn't match its checksum {}",
+            name,
+            version,
+            checksum
+        );
+    }
+
+    let staging = cache.join(format!(
+        ".{}-{}.{}-{}",
+        name,
+        version,
+        std::process::id(),
+        UNPACKS.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::create_dir_all(&staging)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to create {}", staging.display()))?;
+    let unpacked = unpack(&staging, name, version, &bytes).and_then(|unpacked| {
+        match std::fs::rename(&unpacked, &dir) {
+            Ok(()) => Ok(()),
+            // Another run unpacked the same version first
+            Err(_) if dir.is_dir() => Ok(()),
+            Err(e) => Err(e)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to move {} into the cache", unpacked.display())),
+        }
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    unpacked.map(|()| dir)
+}
+
+/// Unpack a `.crate` archive in `staging`, returning its top directory
+fn unpack(staging: &Path, name: &str, version: &str, bytes: &[u8]) -> Result<PathBuf> {
+    let archive = staging.join(format!("{}-{}.crate", name, version));
+    std::fs::write(&archive, bytes).into_diagnostic()?;
+    let status = std::process::Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(staging)
+        .status()
+        .into_diagnostic()
+        .context("Failed to run tar")?;
+    let unpacked = staging.join(format!("{}-{}", name, version));
+    if !status.success() || !unpacked.is_dir() {
+        bail!("Failed to unpack {}@{}", name, version);
+    }
+    Ok(unpacked)
+}
+
+/// A line of the crates.io index: one published version
+#[derive(Deserialize)]
+struct IndexEntry {
+    vers: String,
+    cksum: String,
+}
+
+/// The `.crate` checksum the crates.io index records for a version
+async fn index_checksum(client: &reqwest::Client, name: &str, version: &str) -> Result<String> {
+    let url = format!("{}/{}", CRATES_IO_INDEX, index_path(name));
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to look up {} in the crates.io index", name))?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to look up {} in the crates.io index: HTTP {}",
+            name,
+            response.status()
+        );
+    }
+    let text = response.text().await.into_diagnostic()?;
+    text.lines()
+        .filter_map(|line| serde_json::from_str::<IndexEntry>(line).ok())
+        .find(|entry| entry.vers == version)
+        .map(|entry| entry.cksum)
+        .ok_or_else(|| miette::miette!("The crates.io index has no {}@{}", name, version))
+}
+
+/// Where the index keeps a crate: `1/a`, `2/ab`, `3/a/abc`, `ab/cd/abcd...`
+fn index_path(name: &str) -> String {
+    let name = name.to_lowercase();
+    match name.len() {
+        1 => format!("1/{}", name),
+        2 => format!("2/{}", name),
+        3 => format!("3/{}/{}", &name[..1], name),
+        _ => format!("{}/{}/{}", &name[..2], &name[2..4], name),
+    }
+}
+
+/// Public items declared in a crate's `src/`
+fn public_items(dir: &Path) -> Result<PublicItems> {
+    let mut items = PublicItems::new();
+    for entry in WalkDir::new(dir.join("src")) {
+        let entry = entry.into_diagnostic()?;
+        let path = entry.path();
+        if !path.is_file() || path.extension().is_none_or(|ext| ext != "rs") {
+            continue;
+        }
+        let source = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        declared_items(&tokenize_source(&source), &mut items);
+    }
+    Ok(items)
+}
+
+/// Collect the public items of a file's tokens
+///
+/// Functions, constants and type aliases keep their signature, so a
+/// changed parameter or return type shows; other items only their kind.
+/// Restricted visibility (`pub(crate)`) and `#[doc(hidden)]` items aren't
+/// public API.
+fn declared_items(tokens: &[String], items: &mut PublicItems) {
+    let token = |i: usize| tokens.get(i).map(String::as_str);
+
+    // Brace depth each open `impl` block was entered at, with its type
+    let mut impls: Vec<(usize, String)> = Vec::new();
+    let mut depth: usize = 0;
+    let mut macro_export = false;
+    let mut hidden = false;
+
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i].as_str() {
+            "{" => depth += 1,
+            "}" => {
+                depth = depth.saturating_sub(1);
+                if impls.last().is_some_and(|(entered, _)| *entered == depth) {
+                    impls.pop();
+                }
+            }
+            "macro_export" => macro_export = true,
+            "doc" if token(i + 1) == Some("(") && token(i + 2) == Some("hidden") => hidden = true,
+            "macro_rules" => {
+                if let Some(name) = token(i + 2).filter(|_| macro_export && !hidden) {
+                    items
+                        .entry(name.to_string())
+                        .or_default()
+                        .insert("macro".to_string());
+                }
+                macro_export = false;
+                hidden = false;
+            }
+            // An impl block, rather than `impl Trait` in a signature
+            "impl"
+                if matches!(
+                    i.checked_sub(1).and_then(token),
+                    None | Some("}" | ";" | "]" | "{" | "unsafe")
+                ) =>
+            {
+                if let Some(ty) = impl_type(&tokens[i + 1..]) {
+                    impls.push((depth, ty));
+                }
+            }
+            "pub" if token(i + 1) != Some("(") => {
+                let mut j = i + 1;
+                while matches!(token(j), Some("unsafe" | "async" | "extern" | "default"))
+                    || (token(j) == Some("const") && token(j + 1) == Some("fn"))
+                {
+                    j += 1;
+                }
+                let Some(kind) = token(j) else {
+                    break;
+                };
+                if std::mem::take(&mut hidden) {
+                    i = j;
+                    continue;
+                }
+
+                if kind == "use" {
+                    for name in reexported_names(&tokens[j + 1..]) {
+                        items.entry(name).or_default().insert(REEXPORT.to_string());
+                    }
+                } else if matches!(
+                    kind,
+                    "fn" | "struct"
+                        | "enum"
+                        | "trait"
+                        | "type"
+                        | "const"
+                        | "static"
+                        | "union"
+                        | "mod"
+                ) {
+                    let name_at = if token(j + 1) == Some("mut") {
+                        j + 2
+                    } else {
+                        j + 1
+                    };
+                    if let Some(name) = token(name_at).filter(|name| is_ident(name)) {
+                        let within = impls
+                            .last()
+                            .filter(|(entered, _)| *entered + 1 == depth)
+                            .map(|(_, ty)| ty);
+                        let name = match within {
+                            Some(ty) => format!("{}::{}", ty, name),
+                            None => name.to_string(),
+                        };
+                        let signature = match kind {
+                            "fn" | "const" | "type" | "static" => signature(&tokens[j..]),
+                            _ => kind.to_string(),
+                        };
+                        items.entry(name).or_default().insert(signature);
+                    }
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
+/// The type an impl block is for: `impl<T> Display for Wrapper<T> {` -> "Wrapper"
+fn impl_type(tokens: &[String]) -> Option<String> {
+    let mut angle = 0;
+    let mut ty = None;
+    for token in tokens {
+        match token.as_str() {
+            "<" => angle += 1,
+            ">" => angle -= 1,
+            "{" if angle <= 0 => return ty,
+            ";" => return None,
+            "for" if angle <= 0 => ty = None,
+            name if angle <= 0 && is_ident(name) && name != "dyn" && name != "mut" => {
+                ty = Some(name.to_string())
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// An item's tokens up to its body or value
+fn signature(tokens: &[String]) -> String {
+    let mut nesting = 0;
+    let mut end = tokens.len();
+    for (i, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            "(" | "[" => nesting += 1,
+            ")" | "]" => nesting -= 1,
+            "{" | ";" | "=" if nesting == 0 => {
+                end = i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    tokens[..end].join(" ")
+}
+
+/// Names a `pub use` makes public: the last segment, or its alias
+///
+/// `pub use inner::{Client, Error as ClientError};` -> ["Client", "ClientError"]
+fn reexported_names(tokens: &[String]) -> Vec<String> {
+    let mut names = Vec::new();
+    for (i, token) in tokens.iter().enumerate() {
+        if token == ";" {
+            break;
+        }
+        let next = tokens.get(i + 1).map(String::as_str);
+        if is_ident(token)
+            && !matches!(token.as_str(), "self" | "crate" | "super" | "as")
+            && matches!(next, Some("," | "}" | ";"))
+        {
+            names.push(token.clone());
+        }
+    }
+    names
+}
+
+/// Items of the current version the latest removes or changes
+///
+/// An item is used when the workspace names it, or the type it's a
+/// method of, or glob-imports from the crate. Used items come first.
+fn api_changes(
+    current: &PublicItems,
+    latest: &PublicItems,
+    used: &HashSet<String>,
+) -> Vec<ApiChange> {
+    let mut changes: Vec<ApiChange> = current
+        .iter()
+        .filter_map(|(item, signatures)| {
+            let kind = match latest.get(item) {
+                None => ApiChangeKind::Removed,
+                Some(latest)
+                    if !signatures.contains(REEXPORT)
+                        && !latest.contains(REEXPORT)
+                        && signatures.is_disjoint(latest) =>
+                {
+                    ApiChangeKind::Changed
+                }
+                Some(_) => return None,
+            };
+            let owner = item.split("::").next().unwrap_or(item);
+            Some(ApiChange {
+                item: item.clone(),
+                kind,
+                used: used.contains("*") || used.contains(owner),
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| b.used.cmp(&a.used).then_with(|| a.item.cmp(&b.item)));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_path() {
+        assert_eq!(index_path("a"), "1/a");
+        assert_eq!(index_path("cc"), "2/cc");
+        assert_eq!(index_path("syn"), "3/s/syn");
+        assert_eq!(index_path("Serde_JSON"), "se/rd/serde_json");
+    }
+
+    fn items(source: &str) -> PublicItems {
+        let mut items = PublicItems::new();
+        declared_items(&tokenize_source(source), &mut items);
+        items
+    }
+
+    #[test]
+    fn test_declared_items() {
+        let items = items(
+            r#"
+pub mod error;
+pub use error::{Error, Kind as ErrorKind};
+pub(crate) fn helper() {}
+
+/// Sends requests
+pub struct Client<'a> { inner: &'a str }
+
+impl<'a> Client<'a> {
+    pub fn new(url: &str) -> Self { todo!() }
+    pub async fn get(&self, path: impl AsRef<str>) -> Result<String, Error> { todo!() }
+    fn private(&self) {}
+}
+
+impl std::fmt::Display for Client<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result { Ok(()) }
+}
+
+pub const TIMEOUT: u64 = 30;
+
+#[doc(hidden)]
+pub fn __private_helper() {}
+
+#[macro_export]
+macro_rules! request { () => {}; }
+macro_rules! internal { () => {}; }
+"#,
+        );
+        let names: Vec<&str> = items.keys().map(String::as_str).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Client",
+                "Client::get",
+                "Client::new",
+                "Error",
+                "ErrorKind",
+                "TIMEOUT",
+                "error",
+                "request"
+            ]
+        );
+        assert!(items["Client::new"].contains("fn new ( url : & str ) - > Self"));
+        assert!(items["TIMEOUT"].contains("const TIMEOUT : u64"));
+    }
+
+    #[test]
+    fn test_api_changes() {
+        let current = items(
+            r#"
+pub struct Client;
+impl Client {
+    pub fn new() -> Self { Client }
+    pub fn get(&self, url: &str) {}
+}
+pub fn connect(addr: &str) {}
+pub fn unused() {}
+pub struct Config;
+pub const RETRIES: u8 = 3;
+"#,
+        );
+        let latest = items(
+            r#"
+pub struct Client;
+impl Client {
+    pub fn new() -> Self { Client }
+    pub fn get(&self, url: &str, timeout: u64) {}
+}
+pub fn connect(addr: &str) {}
+mod config { pub struct Config; }
+pub use config::Config;
+pub const RETRIES: u8 = 5;
+"#,
+        );
+        let used: HashSet<String> = ["Client", "new", "connect"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+
+        let changes = api_changes(&current, &latest, &used);
+        let changes: Vec<(&str, ApiChangeKind, bool)> = changes
+            .iter()
+            .map(|change| (change.item.as_str(), change.kind, change.used))
+            .collect();
+        assert_eq!(
+            changes,
+            vec![
+                ("Client::get", ApiChangeKind::Changed, true),
+                ("unused", ApiChangeKind::Removed, false),
+            ]
+        );
+
+        let glob: HashSet<String> = ["prelude", "*"].iter().map(|n| n.to_string()).collect();
+        assert!(api_changes(&current, &latest, &glob).iter().all(|c| c.used));
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashSet;
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use ring::digest;
+
+use crate::date;
+use crate::types::{AuditCache, AuditChanges, Vulnerability};
+
+/// Where the last audit is kept, relative to the project root
+pub const AUDIT_CACHE: &str = ".depx/audit.json";
+
+/// Current cache format
+const CACHE_VERSION: u32 = 2;
+
+impl AuditCache {
+    /// A cache of `vulnerabilities`, fetched today for the lockfiles hashing
+    /// to `lockfile_hash`, with the sorted `used_packages` of a `--used-only`
+    /// audit
+    pub fn new(
+        lockfile_hash: String,
+        used_packages: Option<Vec<String>>,
+        vulnerabilities: &[Vulnerability],
+    ) -> Self {
+        Self {
+            version: CACHE_VERSION,
+            lockfile_hash,
+            advisory_date: date::format_days(date::today()),
+            used_packages,
+            vulnerabilities: vulnerabilities.to_vec(),
+        }
+    }
+
+    /// Load the last audit, or `None` if there wasn't one
+    ///
+    /// A cache in another format is treated as missing, since it's rebuilt
+    /// from OSV anyway.
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let path = root.join(AUDIT_CACHE);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read audit cache {}", path.display()))?;
+        Ok(serde_json::from_str::<Self>(&content)
+            .ok()
+            .filter(|cache| cache.version == CACHE_VERSION))
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let path = root.join(AUDIT_CACHE);
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let output = serde_json::to_string_pretty(self)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        std::fs::write(&path, output + "\n")
+            .into_diagnostic()
+            .with_context(|| format!("Failed to write audit cache {}", path.display()))
+    }
+
+    /// Whether the cached result still holds: same lockfiles, same kind of
+    /// audit over the same used packages, and advisories fetched today
+    pub fn is_current(&self, lockfile_hash: &str, used_packages: Option<&[String]>) -> bool {
+        self.lockfile_hash == lockfile_hash
+            && self.used_packages.as_deref() == used_packages
+            && self.advisory_date == date::format_days(date::today())
+    }
+}
+
+impl AuditChanges {
+    /// What changed between the `previous` audit and the `current` one
+    pub fn between(previous: Option<&AuditCache>, current: &[Vulnerability]) -> Self {
+        let before: &[Vulnerability] = previous.map_or(&[], |cache| &cache.vulnerabilities);
+        let keys = |vulnerabilities: &[Vulnerability]| -> HashSet<String> {
+            vulnerabilities.iter().map(finding_key).collect()
+        };
+        let (before_keys, current_keys) = (keys(before), keys(current));
+
+        Self {
+            since: previous.map(|cache| cache.advisory_date.clone()),
+            new: current
+                .iter()
+                .filter(|vuln| !before_keys.contains(&finding_key(vuln)))
+                .cloned()
+                .collect(),
+            resolved: before
+                .iter()
+                .filter(|vuln| !current_keys.contains(&finding_key(vuln)))
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// SHA-256 over the contents of `lockfiles`, as hex
+pub fn lockfile_hash(lockfiles: &[&Path]) -> Result<String> {
+    let mut context = digest::Context::new(&digest::SHA256);
+    for lockfile in lockfiles {
+        let content = std::fs::read(lockfile)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", lockfile.display()))?;
+        if let Some(name) = lockfile.file_name() {
+            context.update(name.to_string_lossy().as_bytes());
+        }
+        context.update(&content);
+    }
+    Ok(context
+        .finish()
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// One advisory against one installed version
+fn finding_key(vuln: &Vulnerability) -> String {
+    format!(
+        "{} {}@{}",
+        vuln.id, vuln.package_name, vuln.installed_version
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::types::Severity;
+
+    fn vulnerability(id: &str, package: &str, version: &str) -> Vulnerability {
+        Vulnerability {
+            id: id.to_string(),
+            title: "Prototype pollution".to_string(),
+            severity: Severity::High,
+            package_name: package.to_string(),
+            vulnerable_range: "<4.17.21".to_string(),
+            patched_version: Some("4.17.21".to_string()),
+            url: None,
+            affects_used_code: true,
+            installed_version: version.to_string(),
+            purl: None,
+            cpe: None,
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_audit_changes() {
+        let root = TempDir::new("audit-cache");
+        assert!(AuditCache::load(&root).unwrap().is_none());
+
+        let previous = AuditCache::new(
+            "abc".to_string(),
+            None,
+            &[
+                vulnerability("GHSA-1", "lodash", "4.17.20"),
+                vulnerability("GHSA-2", "minimist", "1.2.5"),
+            ],
+        );
+        previous.save(&root).unwrap();
+        let loaded = AuditCache::load(&root).unwrap().unwrap();
+
+        assert!(loaded.is_current("abc", None));
+        assert!(!loaded.is_current("abc", Some(&[])));
+        assert!(!loaded.is_current("def", None));
+
+        // A --used-only audit only holds while the source uses the same packages
+        let used = vec!["lodash".to_string()];
+        let used_only = AuditCache::new("abc".to_string(), Some(used.clone()), &[]);
+        assert!(used_only.is_current("abc", Some(&used)));
+        assert!(!used_only.is_current("abc", Some(&["lodash".to_string(), "ms".to_string()])));
+
+        // Same advisory, other version: resolved in one, new in the other
+        let current = [
+            vulnerability("GHSA-1", "lodash", "4.17.20"),
+            vulnerability("GHSA-2", "minimist", "1.2.6"),
+            vulnerability("GHSA-3", "semver", "7.5.1"),
+        ];
+        let changes = AuditChanges::between(Some(&loaded), &current);
+        assert_eq!(changes.since, Some(loaded.advisory_date.clone()));
+        let ids = |vulns: &[Vulnerability]| -> Vec<String> {
+            vulns
+                .iter()
+                .map(|v| format!("{} {}", v.id, v.installed_version))
+                .collect()
+        };
+        assert_eq!(ids(&changes.new), ["GHSA-2 1.2.6", "GHSA-3 7.5.1"]);
+        assert_eq!(ids(&changes.resolved), ["GHSA-2 1.2.5"]);
+
+        let first = AuditChanges::between(None, &current);
+        assert_eq!(first.since, None);
+        assert_eq!(first.new.len(), 3);
+    }
+}
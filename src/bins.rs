@@ -0,0 +1,222 @@
+use std::collections::{HashSet, VecDeque};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::resolve::{normalize, resolve_relative};
+use crate::analyzer::{self, ImportExtractor};
+use crate::types::{BinEntry, BinStatus, UndeclaredImport};
+
+/// Checks the executables a project exposes through its package.json `bin` field
+pub struct BinAnalyzer<'a> {
+    root: &'a Path,
+}
+
+impl<'a> BinAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    /// Verify every bin entry points at an existing, analyzed file whose
+    /// imports are all declared as runtime dependencies
+    pub fn analyze(&self) -> Result<Vec<BinEntry>> {
+        let package_json_path = self.root.join("package.json");
+        if !package_json_path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = std::fs::read_to_string(&package_json_path)
+            .into_diagnostic()
+            .with_context(|| "Failed to read package.json")?;
+        let manifest: JsonValue = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package.json")?;
+
+        let own_name = manifest.get("name").and_then(|v| v.as_str()).unwrap_or("");
+        let runtime = dependency_names(
+            &manifest,
+            &["dependencies", "optionalDependencies", "peerDependencies"],
+        );
+        let dev = dependency_names(&manifest, &["devDependencies"]);
+
+        let mut entries = Vec::new();
+        for (name, path) in bin_entries(&manifest) {
+            let file = normalize(&self.root.join(&path));
+
+            if !file.is_file() {
+                entries.push(BinEntry {
+                    name,
+                    path,
+                    status: BinStatus::Missing,
+                    undeclared_imports: Vec::new(),
+                });
+                continue;
+            }
+
+            let status = if analyzer::is_analyzed_path(self.root, &file) {
+                BinStatus::Ok
+            } else {
+                BinStatus::NotAnalyzed
+            };
+
+            let undeclared_imports = self
+                .reachable_imports(&file)?
+                .into_iter()
+                .filter(|import| import.package != own_name && !runtime.contains(&import.package))
+                .map(|mut import| {
+                    import.dev_only = dev.contains(&import.package);
+                    import
+                })
+                .collect();
+
+            entries.push(BinEntry {
+                name,
+                path,
+                status,
+                undeclared_imports,
+            });
+        }
+
+        entries.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(entries)
+    }
+
+    /// Package imports of a bin's entry file and every project file it
+    /// reaches through relative imports
+    fn reachable_imports(&self, entry: &Path) -> Result<Vec<UndeclaredImport>> {
+        let mut imports: Vec<UndeclaredImport> = Vec::new();
+        let mut seen_packages = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([entry.to_path_buf()]);
+        let root = normalize(self.root);
+
+        while let Some(file) = queue.pop_front() {
+            if !visited.insert(file.clone()) {
+                continue;
+            }
+
+            let source = std::fs::read_to_string(&file)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read file: {}", file.display()))?;
+            // Shebang-only bins usually have no extension; parse them as JS
+            let parse_path = if file.extension().is_some() {
+                file.clone()
+            } else {
+                file.with_extension("js")
+            };
+            let extraction = ImportExtractor::new(&parse_path, &source).extract_all()?;
+
+            for import in extraction.imports {
+                if let Some(package) = import.resolved_package {
+                    if seen_packages.insert(package.clone()) {
+                        imports.push(UndeclaredImport {
+                            package,
+                            file: file.clone(),
+                            line: import.line,
+                            dev_only: false,
+                        });
+                    }
+                }
+            }
+
+            for local in &extraction.local_imports {
+                if let Some(target) = resolve_relative(&file, &local.specifier) {
+                    if target.starts_with(&root) {
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+
+        Ok(imports)
+    }
+}
+
+/// `bin` as a map of command -> path; a string bin is named after the package
+fn bin_entries(manifest: &JsonValue) -> Vec<(String, String)> {
+    match manifest.get("bin") {
+        Some(JsonValue::String(path)) => {
+            let name = manifest.get("name").and_then(|v| v.as_str()).unwrap_or("");
+            // Scoped packages expose the unscoped part as the command
+            let command = name.rsplit('/').next().unwrap_or(name);
+            vec![(command.to_string(), path.clone())]
+        }
+        Some(JsonValue::Object(map)) => map
+            .iter()
+            .filter_map(|(name, path)| Some((name.clone(), path.as_str()?.to_string())))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn dependency_names(manifest: &JsonValue, fields: &[&str]) -> HashSet<String> {
+    fields
+        .iter()
+        .filter_map(|field| manifest.get(field).and_then(|v| v.as_object()))
+        .flat_map(|deps| deps.keys().cloned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_bin_entries() {
+        let manifest: JsonValue =
+            serde_json::from_str(r#"{"name": "@scope/tool", "bin": "./cli.js"}"#).unwrap();
+        assert_eq!(
+            bin_entries(&manifest),
+            vec![("tool".to_string(), "./cli.js".to_string())]
+        );
+
+        let manifest: JsonValue =
+            serde_json::from_str(r#"{"bin": {"a": "bin/a.js", "b": "bin/b"}}"#).unwrap();
+        assert_eq!(bin_entries(&manifest).len(), 2);
+    }
+
+    #[test]
+    fn test_analyze_bins() {
+        let root = TempDir::new("bins");
+        std::fs::create_dir_all(root.join("bin")).unwrap();
+        std::fs::create_dir_all(root.join("lib")).unwrap();
+
+        std::fs::write(
+            root.join("package.json"),
+            r#"{
+                "name": "tool",
+                "bin": {"tool": "bin/tool", "gone": "bin/gone.js"},
+                "dependencies": {"commander": "^11.0.0"},
+                "devDependencies": {"chalk": "^5.0.0"}
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("bin/tool"),
+            "#!/usr/bin/env node\nconst { program } = require('commander');\nrequire('../lib/run');\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("lib/run.js"),
+            "import chalk from 'chalk';\nimport ora from 'ora';\nimport fs from 'fs';\n",
+        )
+        .unwrap();
+
+        let entries = BinAnalyzer::new(&root).analyze().unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].name, "gone");
+        assert_eq!(entries[0].status, BinStatus::Missing);
+
+        let tool = &entries[1];
+        assert_eq!(tool.status, BinStatus::NotAnalyzed);
+        let undeclared: Vec<(&str, bool)> = tool
+            .undeclared_imports
+            .iter()
+            .map(|i| (i.package.as_str(), i.dev_only))
+            .collect();
+        assert_eq!(undeclared, vec![("chalk", true), ("ora", false)]);
+    }
+}
@@ -0,0 +1,380 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::graph::DependencyGraph;
+use crate::types::{
+    BundleFormat, BundleImpact, BundleReport, BundledPackage, SharedBundledPackage,
+};
+
+/// Attributes the bytes a bundler emitted to the project's direct
+/// dependencies
+///
+/// Sizes come from the bundler, not from what's installed: webpack-bundle-analyzer's
+/// parsed sizes, esbuild's `bytesInOutput`, or for a Vite manifest the
+/// emitted chunk files, read next to it. A module belongs to the package
+/// of the last `node_modules` in its path. A transitive package counts
+/// towards the one direct dependency that pulls it in, or is shared when
+/// several do.
+pub struct BundleAnalyzer<'a> {
+    stats: &'a Path,
+}
+
+/// A module of the bundle, or for Vite a chunk, and the bytes it emitted
+struct BundledModule {
+    /// The module's path, `None` when the report doesn't name one
+    source: Option<String>,
+    bytes: u64,
+}
+
+impl<'a> BundleAnalyzer<'a> {
+    pub fn new(stats: &'a Path) -> Self {
+        Self { stats }
+    }
+
+    pub fn analyze(&self, graph: &DependencyGraph) -> Result<BundleReport> {
+        let content = std::fs::read_to_string(self.stats)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.stats.display()))?;
+        let stats: JsonValue = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to parse {}", self.stats.display()))?;
+
+        let mut modules = Vec::new();
+        let format = match &stats {
+            JsonValue::Array(chunks) => {
+                webpack_modules(chunks, &mut modules);
+                BundleFormat::WebpackBundleAnalyzer
+            }
+            JsonValue::Object(metafile)
+                if metafile.get("outputs").is_some_and(|o| o.is_object()) =>
+            {
+                modules = esbuild_modules(&metafile["outputs"]);
+                BundleFormat::Esbuild
+            }
+            JsonValue::Object(manifest) if is_vite_manifest(manifest) => {
+                modules = self.vite_modules(manifest);
+                BundleFormat::Vite
+            }
+            _ => bail!(
+                "{} isn't a webpack-bundle-analyzer report, esbuild metafile or Vite manifest",
+                self.stats.display()
+            ),
+        };
+
+        Ok(attribute(format, modules, graph))
+    }
+
+    /// Every chunk, CSS file and asset a Vite manifest lists, sized on disk
+    ///
+    /// Packages bundled into a chunk of the project's own code count as
+    /// first-party; only those split into chunks of their own are told apart.
+    fn vite_modules(&self, manifest: &Map<String, JsonValue>) -> Vec<BundledModule> {
+        // dist/.vite/manifest.json since Vite 5, dist/manifest.json before
+        let mut out_dir = self.stats.parent().unwrap_or(Path::new("."));
+        if out_dir.file_name().is_some_and(|name| name == ".vite") {
+            out_dir = out_dir.parent().unwrap_or(out_dir);
+        }
+
+        let mut seen = HashSet::new();
+        let mut modules = Vec::new();
+        for (key, chunk) in manifest {
+            // Keys starting with "_" are chunks shared by several modules
+            let source = chunk
+                .get("src")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .or_else(|| (!key.starts_with('_')).then(|| key.clone()));
+
+            let files = chunk.get("file").into_iter().chain(
+                ["css", "assets"]
+                    .iter()
+                    .filter_map(|field| chunk.get(*field)?.as_array())
+                    .flatten(),
+            );
+            for file in files.filter_map(|f| f.as_str()) {
+                if !seen.insert(file) {
+                    continue;
+                }
+                let Ok(metadata) = std::fs::metadata(out_dir.join(file)) else {
+                    continue;
+                };
+                modules.push(BundledModule {
+                    source: source.clone(),
+                    bytes: metadata.len(),
+                });
+            }
+        }
+        modules
+    }
+}
+
+/// The leaf modules of webpack-bundle-analyzer's chunk tree
+fn webpack_modules(groups: &[JsonValue], modules: &mut Vec<BundledModule>) {
+    for group in groups {
+        match group.get("groups").and_then(|g| g.as_array()) {
+            Some(children) if !children.is_empty() => webpack_modules(children, modules),
+            _ => {
+                let source = ["path", "label"]
+                    .iter()
+                    .find_map(|field| group.get(*field)?.as_str())
+                    .map(String::from);
+                // Modules inside concatenated ones may only have a stat size
+                let bytes = ["parsedSize", "statSize"]
+                    .iter()
+                    .find_map(|field| group.get(*field)?.as_u64())
+                    .unwrap_or(0);
+                modules.push(BundledModule { source, bytes });
+            }
+        }
+    }
+}
+
+/// The inputs of every output file but source maps
+fn esbuild_modules(outputs: &JsonValue) -> Vec<BundledModule> {
+    outputs
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(file, _)| !file.ends_with(".map"))
+        .filter_map(|(_, output)| output.get("inputs")?.as_object())
+        .flatten()
+        .map(|(path, input)| BundledModule {
+            source: Some(path.clone()),
+            bytes: input
+                .get("bytesInOutput")
+                .and_then(|b| b.as_u64())
+                .unwrap_or(0),
+        })
+        .collect()
+}
+
+/// Vite manifests map source paths to chunks that each name a `file`
+fn is_vite_manifest(manifest: &Map<String, JsonValue>) -> bool {
+    !manifest.is_empty()
+        && manifest
+            .values()
+            .all(|chunk| chunk.get("file").is_some_and(|f| f.is_string()))
+}
+
+/// The package a bundled module belongs to
+///
+/// webpack prefixes loaders (`./node_modules/css-loader/index.js!./src/app.css`),
+/// so only what follows the last `!` is the module itself.
+fn bundled_package(source: &str) -> Option<String> {
+    let module = source
+        .rsplit('!')
+        .next()
+        .unwrap_or(source)
+        .replace('\\', "/");
+    let (_, rest) = module.rsplit_once("node_modules/")?;
+    let mut segments = rest.split('/');
+    let first = segments.next()?;
+    let name = if first.starts_with('@') {
+        format!("{}/{}", first, segments.next()?)
+    } else {
+        first.to_string()
+    };
+    (!name.is_empty() && !name.starts_with('.')).then_some(name)
+}
+
+/// Sum the modules' bytes per package and hand each package to the direct
+/// dependency it's bundled for
+fn attribute(
+    format: BundleFormat,
+    modules: Vec<BundledModule>,
+    graph: &DependencyGraph,
+) -> BundleReport {
+    let mut total_bytes = 0;
+    let mut first_party_bytes = 0;
+    let mut unknown_bytes = 0;
+    let mut by_package: BTreeMap<String, u64> = BTreeMap::new();
+    for module in modules {
+        total_bytes += module.bytes;
+        match module.source.as_deref() {
+            None => unknown_bytes += module.bytes,
+            Some(source) => match bundled_package(source) {
+                Some(name) => *by_package.entry(name).or_default() += module.bytes,
+                None => first_party_bytes += module.bytes,
+            },
+        }
+    }
+
+    let owners = graph.direct_owners();
+    let mut dependencies: BTreeMap<String, BundleImpact> = BTreeMap::new();
+    let mut shared = Vec::new();
+    let mut unattributed = Vec::new();
+    for (name, bytes) in by_package {
+        let package_owners = owners.get(&name);
+        let owner = match package_owners {
+            Some(o) if o.contains(&name) => name.clone(),
+            Some(o) if o.len() == 1 => o.iter().next().cloned().unwrap_or_default(),
+            Some(o) => {
+                shared.push(SharedBundledPackage {
+                    name,
+                    bytes,
+                    dependants: o.iter().cloned().collect(),
+                });
+                continue;
+            }
+            None => {
+                unattributed.push(BundledPackage { name, bytes });
+                continue;
+            }
+        };
+        let impact = dependencies
+            .entry(owner.clone())
+            .or_insert_with(|| BundleImpact {
+                version: graph
+                    .packages()
+                    .values()
+                    .find(|pkg| pkg.is_direct && pkg.name == owner)
+                    .map(|pkg| pkg.version.clone())
+                    .unwrap_or_default(),
+                name: owner.clone(),
+                own_bytes: 0,
+                transitive: Vec::new(),
+            });
+        if owner == name {
+            impact.own_bytes += bytes;
+        } else {
+            impact.transitive.push(BundledPackage { name, bytes });
+        }
+    }
+
+    let mut dependencies: Vec<BundleImpact> = dependencies.into_values().collect();
+    for impact in &mut dependencies {
+        impact.transitive.sort_by_key(|p| Reverse(p.bytes));
+    }
+    dependencies.sort_by_key(|d| Reverse(d.total_bytes()));
+    shared.sort_by_key(|p| Reverse(p.bytes));
+    unattributed.sort_by_key(|p| Reverse(p.bytes));
+
+    BundleReport {
+        format,
+        total_bytes,
+        first_party_bytes,
+        unknown_bytes,
+        dependencies,
+        shared,
+        unattributed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::types::Package;
+    use std::collections::HashMap;
+
+    fn graph() -> DependencyGraph {
+        let mut packages = HashMap::new();
+        let mut add = |name: &str, is_direct: bool, deps: &[&str]| {
+            let mut pkg = Package::new(name, "1.0.0");
+            pkg.is_direct = is_direct;
+            pkg.dependencies = deps.iter().map(|d| d.to_string()).collect();
+            packages.insert(name.to_string(), pkg);
+        };
+        add("react", true, &["loose-envify"]);
+        add("react-dom", true, &["loose-envify", "scheduler"]);
+        add("loose-envify", false, &[]);
+        add("scheduler", false, &[]);
+        DependencyGraph::new(&packages)
+    }
+
+    #[test]
+    fn test_bundle_attribution() {
+        let root = TempDir::new("bundle");
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "meta.json",
+            r#"{
+                "inputs": {},
+                "outputs": {
+                    "dist/app.js": {"bytes": 4200, "inputs": {
+                        "src/main.tsx": {"bytesInOutput": 200},
+                        "node_modules/react/index.js": {"bytesInOutput": 1000},
+                        "node_modules/react-dom/client.js": {"bytesInOutput": 2000},
+                        "node_modules/.pnpm/scheduler@1.0.0/node_modules/scheduler/index.js": {"bytesInOutput": 500},
+                        "node_modules/loose-envify/index.js": {"bytesInOutput": 300},
+                        "node_modules/buffer/index.js": {"bytesInOutput": 100}
+                    }},
+                    "dist/app.js.map": {"bytes": 9000, "inputs": {}}
+                }
+            }"#,
+        );
+        write(
+            "stats.json",
+            r#"[{"label": "app.js", "parsedSize": 1200, "groups": [
+                {"label": "src", "path": "./src", "groups": [
+                    {"label": "index.js", "path": "./src/index.js", "parsedSize": 200}
+                ]},
+                {"label": "react", "path": "./node_modules/react/index.js", "statSize": 4000, "parsedSize": 1000}
+            ]}]"#,
+        );
+        write(
+            "dist/.vite/manifest.json",
+            r#"{
+                "index.html": {"file": "assets/index.js", "css": ["assets/index.css"], "isEntry": true},
+                "node_modules/react-dom/client.js": {"file": "assets/client.js", "isDynamicEntry": true},
+                "_shared.js": {"file": "assets/shared.js"}
+            }"#,
+        );
+        write("dist/assets/index.js", "1234567890");
+        write("dist/assets/index.css", "12345");
+        write("dist/assets/client.js", "12345678901234567890");
+        write("dist/assets/shared.js", "123");
+        write("vite.json", r#"{"name": "not-a-report"}"#);
+
+        let graph = graph();
+        let analyze = |file: &str| BundleAnalyzer::new(&root.join(file)).analyze(&graph);
+        let esbuild = analyze("meta.json").unwrap();
+        let webpack = analyze("stats.json").unwrap();
+        let vite = analyze("dist/.vite/manifest.json").unwrap();
+        let invalid = analyze("vite.json");
+
+        assert_eq!(esbuild.format, BundleFormat::Esbuild);
+        assert_eq!(esbuild.total_bytes, 4100);
+        assert_eq!(esbuild.first_party_bytes, 200);
+        let found: Vec<(&str, u64, u64)> = esbuild
+            .dependencies
+            .iter()
+            .map(|d| (d.name.as_str(), d.own_bytes, d.total_bytes()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("react-dom", 2000, 2500), ("react", 1000, 1000)]
+        );
+        assert_eq!(esbuild.dependencies[0].transitive[0].name, "scheduler");
+        assert_eq!(esbuild.shared[0].name, "loose-envify");
+        assert_eq!(esbuild.shared[0].dependants, vec!["react", "react-dom"]);
+        assert_eq!(
+            esbuild.unattributed,
+            vec![BundledPackage {
+                name: "buffer".to_string(),
+                bytes: 100
+            }]
+        );
+
+        assert_eq!(webpack.format, BundleFormat::WebpackBundleAnalyzer);
+        assert_eq!(webpack.total_bytes, 1200);
+        assert_eq!(webpack.dependencies[0].own_bytes, 1000);
+
+        assert_eq!(vite.format, BundleFormat::Vite);
+        assert_eq!(vite.total_bytes, 38);
+        assert_eq!(vite.first_party_bytes, 15);
+        assert_eq!(vite.unknown_bytes, 3);
+        assert_eq!(vite.dependencies[0].name, "react-dom");
+        assert_eq!(vite.dependencies[0].own_bytes, 20);
+
+        assert!(invalid.is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::{Argument, CallExpression, Expression, ForOfStatement, ObjectPropertyKind};
+use oxc_ast::visit::walk;
+use oxc_ast::Visit;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+use serde::Deserialize;
+
+/// Name pnpm gives the top-level `catalog` of pnpm-workspace.yaml
+pub const DEFAULT_CATALOG: &str = "default";
+
+/// Ranges a workspace manages centrally instead of in each package.json
+///
+/// pnpm keeps them in pnpm-workspace.yaml (`catalog` and named `catalogs`),
+/// which members reference as `catalog:` or `catalog:<name>`. Yarn enforces
+/// them through constraints: `gen_enforced_dependency` facts in
+/// constraints.pro, or `dep.update(range)` on `Yarn.dependencies({ ident })`
+/// in yarn.config.cjs. Only literal names and ranges are picked up.
+#[derive(Debug, Default)]
+pub struct Catalogs {
+    /// Whether the project is a pnpm workspace, where catalogs are available
+    pub pnpm_workspace: bool,
+
+    /// Catalog name -> package -> range
+    pnpm: BTreeMap<String, BTreeMap<String, String>>,
+
+    /// Package -> the range yarn constraints enforce
+    constraints: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PnpmWorkspace {
+    #[serde(default)]
+    catalog: BTreeMap<String, String>,
+
+    #[serde(default)]
+    catalogs: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl Catalogs {
+    pub fn load(root: &Path) -> Result<Self> {
+        let mut catalogs = Self::default();
+
+        let workspace_path = root.join("pnpm-workspace.yaml");
+        if workspace_path.exists() {
+            let content = std::fs::read_to_string(&workspace_path)
+                .into_diagnostic()
+                .with_context(|| "Failed to read pnpm-workspace.yaml")?;
+            let workspace: PnpmWorkspace = serde_yaml::from_str(&content)
+                .into_diagnostic()
+                .with_context(|| "Failed to parse pnpm-workspace.yaml")?;
+            catalogs.pnpm_workspace = true;
+            catalogs.pnpm = workspace.catalogs;
+            if !workspace.catalog.is_empty() {
+                catalogs
+                    .pnpm
+                    .entry(DEFAULT_CATALOG.to_string())
+                    .or_default()
+                    .extend(workspace.catalog);
+            }
+        }
+
+        for (name, parse) in [
+            (
+                "constraints.pro",
+                prolog_constraints as fn(&str) -> Vec<(String, String)>,
+            ),
+            ("yarn.config.cjs", script_constraints),
+        ] {
+            let path = root.join(name);
+            if !path.exists() {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read {}", name))?;
+            for (package, range) in parse(&content) {
+                catalogs.constraints.entry(package).or_insert(range);
+            }
+        }
+
+        Ok(catalogs)
+    }
+
+    /// The range a `catalog:` spec of `package` stands for
+    pub fn resolve(&self, package: &str, spec: &str) -> Option<&str> {
+        self.entry(catalog_name(spec)?, package)
+    }
+
+    /// The range a catalog holds for `package`
+    pub fn entry(&self, catalog: &str, package: &str) -> Option<&str> {
+        self.pnpm.get(catalog)?.get(package).map(String::as_str)
+    }
+
+    /// The range yarn constraints enforce for `package`
+    pub fn enforced(&self, package: &str) -> Option<&str> {
+        self.constraints.get(package).map(String::as_str)
+    }
+}
+
+/// The catalog a `catalog:` spec names; a bare `catalog:` is the default one
+///
+/// Examples:
+/// - "catalog:" -> Some("default")
+/// - "catalog:react17" -> Some("react17")
+/// - "^18.2.0" -> None
+pub fn catalog_name(spec: &str) -> Option<&str> {
+    let name = spec.trim().strip_prefix("catalog:")?.trim();
+    Some(if name.is_empty() {
+        DEFAULT_CATALOG
+    } else {
+        name
+    })
+}
+
+/// `gen_enforced_dependency(WorkspaceCwd, 'react', '^18.2.0', DependencyType)`
+/// facts and rule heads of a constraints.pro
+///
+/// Ranges that are variables, or `null` (forbidding the dependency), don't count.
+fn prolog_constraints(content: &str) -> Vec<(String, String)> {
+    let content: String = content
+        .lines()
+        .map(|line| line.split('%').next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut constraints = Vec::new();
+    let mut rest = content.as_str();
+    while let Some(start) = rest.find("gen_enforced_dependency(") {
+        rest = &rest[start + "gen_enforced_dependency(".len()..];
+        let mut args = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+        let mut depth = 0;
+        for c in rest.chars() {
+            match c {
+                '\'' => quoted = !quoted,
+                '(' | '[' if !quoted => depth += 1,
+                ')' | ']' if !quoted && depth > 0 => depth -= 1,
+                ')' if !quoted => break,
+                ',' if !quoted && depth == 0 => {
+                    args.push(std::mem::take(&mut current));
+                    continue;
+                }
+                _ => {}
+            }
+            current.push(c);
+        }
+        args.push(current);
+
+        let atom = |arg: &str| -> Option<String> {
+            let arg = arg.trim();
+            let inner = arg.strip_prefix('\'')?.strip_suffix('\'')?;
+            Some(inner.to_string())
+        };
+        if let [_, package, range, _] = args.as_slice() {
+            if let (Some(package), Some(range)) = (atom(package), atom(range)) {
+                constraints.push((package, range));
+            }
+        }
+    }
+    constraints
+}
+
+/// `dep.update(range)` calls inside `for (const dep of Yarn.dependencies({ ident }))`
+/// loops of a yarn.config.cjs
+fn script_constraints(content: &str) -> Vec<(String, String)> {
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, content, SourceType::cjs()).parse();
+
+    let mut visitor = ConstraintVisitor::default();
+    visitor.visit_program(&parsed.program);
+    visitor.constraints
+}
+
+#[derive(Default)]
+struct ConstraintVisitor {
+    /// Packages of the enclosing `Yarn.dependencies` loops
+    idents: Vec<String>,
+    constraints: Vec<(String, String)>,
+}
+
+impl<'a> Visit<'a> for ConstraintVisitor {
+    fn visit_for_of_statement(&mut self, stmt: &ForOfStatement<'a>) {
+        let ident = match stmt.right.get_inner_expression() {
+            Expression::CallExpression(call) if callee_method(call) == Some("dependencies") => {
+                dependency_ident(call)
+            }
+            _ => None,
+        };
+        let scoped = ident.is_some();
+        self.idents.extend(ident);
+        walk::walk_for_of_statement(self, stmt);
+        if scoped {
+            self.idents.pop();
+        }
+    }
+
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if callee_method(call) == Some("update") {
+            let range = call
+                .arguments
+                .first()
+                .and_then(Argument::as_expression)
+                .and_then(literal);
+            if let (Some(ident), Some(range)) = (self.idents.last(), range) {
+                self.constraints.push((ident.clone(), range));
+            }
+        }
+        walk::walk_call_expression(self, call);
+    }
+}
+
+/// The method a call invokes, e.g. "update" for `dep.update(...)`
+fn callee_method<'a>(call: &'a CallExpression) -> Option<&'a str> {
+    match &call.callee {
+        Expression::StaticMemberExpression(member) => Some(member.property.name.as_str()),
+        _ => None,
+    }
+}
+
+/// The `ident` a `Yarn.dependencies({ ident: "react" })` query selects
+fn dependency_ident(call: &CallExpression) -> Option<String> {
+    let Expression::ObjectExpression(query) = call.arguments.first()?.as_expression()? else {
+        return None;
+    };
+    query.properties.iter().find_map(|property| match property {
+        ObjectPropertyKind::ObjectProperty(property)
+            if property.key.static_name().as_deref() == Some("ident") =>
+        {
+            literal(&property.value)
+        }
+        _ => None,
+    })
+}
+
+fn literal(expr: &Expression) -> Option<String> {
+    match expr.get_inner_expression() {
+        Expression::StringLiteral(lit) => Some(lit.value.to_string()),
+        Expression::TemplateLiteral(tpl) if tpl.expressions.is_empty() => {
+            Some(tpl.quasis.first()?.value.raw.to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_catalogs() {
+        let root = TempDir::new("catalog");
+        let write = |name: &str, content: &str| std::fs::write(root.join(name), content).unwrap();
+        write(
+            "pnpm-workspace.yaml",
+            r#"packages:
+  - "packages/*"
+catalog:
+  react: ^18.2.0
+catalogs:
+  legacy:
+    react: ^17.0.2
+"#,
+        );
+        write(
+            "constraints.pro",
+            r#"% Every workspace uses the same TypeScript
+gen_enforced_dependency(WorkspaceCwd, 'typescript', '~5.4.0', DependencyType) :-
+  workspace_has_dependency(WorkspaceCwd, 'typescript', _, DependencyType).
+gen_enforced_dependency(WorkspaceCwd, DependencyIdent, DependencyRange2, DependencyType) :-
+  workspace_has_dependency(WorkspaceCwd, DependencyIdent, DependencyRange, DependencyType).
+gen_enforced_dependency(WorkspaceCwd, 'left-pad', null, _).
+"#,
+        );
+        write(
+            "yarn.config.cjs",
+            r#"const { defineConfig } = require('@yarnpkg/types');
+
+module.exports = defineConfig({
+  async constraints({ Yarn }) {
+    for (const dep of Yarn.dependencies({ ident: 'lodash' })) {
+      dep.update(`^4.17.21`);
+    }
+    for (const dep of Yarn.dependencies({ ident: 'typescript' })) {
+      dep.update('^5.0.0');
+    }
+    for (const workspace of Yarn.workspaces()) {
+      workspace.set('license', 'MIT');
+    }
+  },
+});
+"#,
+        );
+
+        let catalogs = Catalogs::load(&root).unwrap();
+
+        assert!(catalogs.pnpm_workspace);
+        assert_eq!(catalogs.resolve("react", "catalog:"), Some("^18.2.0"));
+        assert_eq!(
+            catalogs.resolve("react", "catalog:default"),
+            Some("^18.2.0")
+        );
+        assert_eq!(catalogs.resolve("react", "catalog:legacy"), Some("^17.0.2"));
+        assert_eq!(catalogs.resolve("react", "^18.2.0"), None);
+        assert_eq!(catalogs.resolve("vue", "catalog:"), None);
+
+        // constraints.pro is read first
+        assert_eq!(catalogs.enforced("typescript"), Some("~5.4.0"));
+        assert_eq!(catalogs.enforced("lodash"), Some("^4.17.21"));
+        assert_eq!(catalogs.enforced("left-pad"), None);
+        assert_eq!(catalogs.constraints.len(), 2);
+    }
+}
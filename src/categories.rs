@@ -0,0 +1,182 @@
+use std::collections::BTreeMap;
+
+use crate::types::{CategoryOverlap, Package};
+
+/// Packages that do the same job; depending on several from one category
+/// usually means the project can consolidate on one of them
+const CATEGORIES: &[(&str, &[&str])] = &[
+    (
+        "HTTP clients",
+        &[
+            "axios",
+            "node-fetch",
+            "got",
+            "superagent",
+            "request",
+            "ky",
+            "undici",
+            "cross-fetch",
+            "isomorphic-fetch",
+            "needle",
+            "ofetch",
+            "wretch",
+            // Rust
+            "reqwest",
+            "ureq",
+            "isahc",
+            "surf",
+            "attohttpc",
+        ],
+    ),
+    (
+        "Date libraries",
+        &[
+            "moment",
+            "dayjs",
+            "date-fns",
+            "luxon",
+            "@js-joda/core",
+            "spacetime",
+            // Rust
+            "chrono",
+            "time",
+            "jiff",
+        ],
+    ),
+    (
+        "Utility belts",
+        &[
+            "lodash",
+            "lodash-es",
+            "underscore",
+            "ramda",
+            "remeda",
+            "radash",
+            "es-toolkit",
+        ],
+    ),
+    (
+        "Test runners",
+        &["jest", "vitest", "mocha", "ava", "tap", "jasmine", "uvu"],
+    ),
+    (
+        "Schema validators",
+        &[
+            "zod",
+            "yup",
+            "joi",
+            "ajv",
+            "superstruct",
+            "valibot",
+            "io-ts",
+            "@sinclair/typebox",
+            "class-validator",
+            "arktype",
+        ],
+    ),
+    (
+        "CLI argument parsers",
+        &[
+            "commander",
+            "yargs",
+            "minimist",
+            "meow",
+            "cac",
+            "arg",
+            "sade",
+            // Rust
+            "clap",
+            "structopt",
+            "argh",
+            "pico-args",
+        ],
+    ),
+    (
+        "Loggers",
+        &["winston", "pino", "bunyan", "loglevel", "log4js", "signale"],
+    ),
+    (
+        "Terminal colors",
+        &[
+            "chalk",
+            "kleur",
+            "picocolors",
+            "colors",
+            "ansi-colors",
+            "colorette",
+            // Rust
+            "colored",
+            "owo-colors",
+            "yansi",
+        ],
+    ),
+    (
+        "ID generators",
+        &[
+            "uuid",
+            "nanoid",
+            "cuid",
+            "@paralleldrive/cuid2",
+            "shortid",
+            "ulid",
+        ],
+    ),
+];
+
+/// Find categories where the project depends on more than one package
+pub fn find_overlaps<'a>(direct: impl IntoIterator<Item = &'a Package>) -> Vec<CategoryOverlap> {
+    let mut by_category: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+
+    for pkg in direct {
+        if let Some(category) = category_of(&pkg.name) {
+            by_category
+                .entry(category)
+                .or_default()
+                .push(pkg.name.clone());
+        }
+    }
+
+    by_category
+        .into_iter()
+        .filter(|(_, packages)| packages.len() > 1)
+        .map(|(category, mut packages)| {
+            packages.sort();
+            packages.dedup();
+            CategoryOverlap {
+                category: category.to_string(),
+                packages,
+            }
+        })
+        .filter(|overlap| overlap.packages.len() > 1)
+        .collect()
+}
+
+/// The category a package belongs to, if it's in the curated map
+pub fn category_of(name: &str) -> Option<&'static str> {
+    CATEGORIES
+        .iter()
+        .find(|(_, packages)| packages.contains(&name))
+        .map(|(category, _)| *category)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_overlaps() {
+        let packages = vec![
+            Package::new("axios", "1.6.0").direct(),
+            Package::new("node-fetch", "3.3.0").direct(),
+            Package::new("got", "14.0.0").direct(),
+            Package::new("zod", "3.22.0").direct(),
+            Package::new("react", "18.2.0").direct(),
+        ];
+
+        let overlaps = find_overlaps(&packages);
+
+        assert_eq!(overlaps.len(), 1);
+        assert_eq!(overlaps[0].category, "HTTP clients");
+        assert_eq!(overlaps[0].packages, vec!["axios", "got", "node-fetch"]);
+    }
+}
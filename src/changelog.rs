@@ -0,0 +1,557 @@
+use std::collections::HashMap;
+
+use miette::{Context, IntoDiagnostic, Result};
+use semver::Version;
+use serde::Deserialize;
+
+use crate::range::parse_version;
+use crate::registry::{PackageMetadata, MAX_CONCURRENT};
+use crate::types::{BreakingChange, ReleaseNotes, UpdateCandidate};
+
+const GITHUB_API: &str = "https://api.github.com";
+const GITHUB_RAW: &str = "https://raw.githubusercontent.com";
+
+/// Environment variable holding a GitHub token, which raises the API's
+/// rate limit of 60 requests an hour
+const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
+
+/// Changelog files read when a repository publishes no GitHub releases
+const CHANGELOG_FILES: &[&str] = &["CHANGELOG.md", "HISTORY.md", "CHANGES.md"];
+
+/// GitHub releases read per repository, 100 to a page, before giving up
+/// on reaching the installed version
+const MAX_RELEASE_PAGES: usize = 10;
+
+/// Longest summary kept for a breaking change, in characters
+const MAX_SUMMARY: usize = 100;
+
+/// Release notes between the current and latest version of each update
+///
+/// Notes come from the GitHub releases of the repository the registry
+/// lists for the package, or else from a changelog file at its root.
+/// Packages hosted elsewhere, and lookups that fail, get no notes.
+/// `MAX_CONCURRENT` repositories are looked up at a time, with
+/// `GITHUB_TOKEN` when it's set.
+pub async fn fetch_release_notes(
+    client: &reqwest::Client,
+    candidates: &[UpdateCandidate],
+    metadata: &HashMap<String, PackageMetadata>,
+) -> HashMap<String, ReleaseNotes> {
+    use tokio::task::JoinSet;
+
+    let token = std::env::var(GITHUB_TOKEN_ENV).ok();
+    let hosted: Vec<(GithubRepo, &UpdateCandidate)> = candidates
+        .iter()
+        .filter_map(|candidate| {
+            let repo = metadata
+                .get(&candidate.package)
+                .and_then(|meta| meta.repository.as_deref())
+                .and_then(github_repo)?;
+            Some((repo, candidate))
+        })
+        .collect();
+
+    let mut notes = HashMap::new();
+    for chunk in hosted.chunks(MAX_CONCURRENT) {
+        let mut join_set = JoinSet::new();
+        for (repo, candidate) in chunk {
+            let client = client.clone();
+            let token = token.clone();
+            let repo = repo.clone();
+            let candidate = (*candidate).clone();
+            join_set.spawn(async move {
+                let notes = fetch_notes(&client, &repo, token.as_deref(), &candidate).await;
+                (candidate.package, notes)
+            });
+        }
+        while let Some(result) = join_set.join_next().await {
+            if let Ok((package, Ok(Some(found)))) = result {
+                notes.insert(package, found);
+            }
+        }
+    }
+    notes
+}
+
+async fn fetch_notes(
+    client: &reqwest::Client,
+    repo: &GithubRepo,
+    token: Option<&str>,
+    candidate: &UpdateCandidate,
+) -> Result<Option<ReleaseNotes>> {
+    let (Some(from), Some(to)) = (
+        parse_version(&candidate.current),
+        parse_version(&candidate.latest),
+    ) else {
+        return Ok(None);
+    };
+
+    // Releases come newest first, so pages are read until one reaches the
+    // installed version
+    let mut sections: Vec<(Version, String)> = Vec::new();
+    let mut truncated = true;
+    for page in 1..=MAX_RELEASE_PAGES {
+        let url = format!(
+            "{}/repos/{}/{}/releases?per_page=100&page={}",
+            GITHUB_API, repo.owner, repo.name, page
+        );
+        let releases: Vec<GithubRelease> = get(client, &url, token)
+            .await?
+            .json()
+            .await
+            .into_diagnostic()
+            .with_context(|| format!("Failed to parse GitHub releases of {}", candidate.package))?;
+        let last_page = releases.len() < 100;
+        sections.extend(
+            releases
+                .into_iter()
+                .filter(|release| !release.draft)
+                .filter_map(|release| {
+                    let version = tag_version(&release.tag_name, &candidate.package)?;
+                    Some((version, release.body.unwrap_or_default()))
+                }),
+        );
+        if last_page || sections.iter().any(|(version, _)| *version <= from) {
+            truncated = false;
+            break;
+        }
+    }
+    let source = format!("https://github.com/{}/{}/releases", repo.owner, repo.name);
+    if let Some(mut notes) = notes_between(sections, &from, &to, source) {
+        notes.truncated = truncated;
+        return Ok(Some(notes));
+    }
+
+    // Repositories without releases, or whose releases cover other packages
+    for file in CHANGELOG_FILES {
+        let url = format!("{}/{}/{}/HEAD/{}", GITHUB_RAW, repo.owner, repo.name, file);
+        let Ok(response) = get(client, &url, None).await else {
+            continue;
+        };
+        let text = response.text().await.into_diagnostic()?;
+        let source = format!(
+            "https://github.com/{}/{}/blob/HEAD/{}",
+            repo.owner, repo.name, file
+        );
+        let sections = changelog_sections(&text, &candidate.package);
+        if let Some(notes) = notes_between(sections, &from, &to, source) {
+            return Ok(Some(notes));
+        }
+    }
+
+    Ok(None)
+}
+
+async fn get(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+) -> Result<reqwest::Response> {
+    let mut request = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json");
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to fetch {}", url))?;
+
+    if !response.status().is_success() {
+        miette::bail!("GitHub returned {} for {}", response.status(), url);
+    }
+    Ok(response)
+}
+
+/// A GitHub repository named by a package's repository URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GithubRepo {
+    owner: String,
+    name: String,
+}
+
+/// Examples:
+/// - "git+https://github.com/expressjs/express.git" -> expressjs/express
+/// - "git@github.com:user/repo.git", "github:user/repo", "user/repo" -> user/repo
+/// - "https://github.com/user/repo/tree/main/packages/x" -> user/repo
+/// - "https://gitlab.com/user/repo" -> None
+fn github_repo(url: &str) -> Option<GithubRepo> {
+    let url = url.trim();
+    let path = if let Some(path) = url.strip_prefix("github:") {
+        path
+    } else if let Some(index) = url.find("github.com") {
+        url[index + "github.com".len()..].trim_start_matches([':', '/'])
+    } else if !url.contains(':') && url.matches('/').count() == 1 {
+        // npm's "user/repo" shorthand
+        url
+    } else {
+        return None;
+    };
+
+    let mut parts = path.split('/');
+    let owner = parts.next()?;
+    let name = parts.next()?.split(['#', '?']).next()?;
+    let name = name.strip_suffix(".git").unwrap_or(name);
+    if owner.is_empty() || name.is_empty() {
+        return None;
+    }
+    Some(GithubRepo {
+        owner: owner.to_string(),
+        name: name.to_string(),
+    })
+}
+
+/// The version a release tag or changelog heading names for a package
+///
+/// Monorepos tag each package's releases ("@scope/pkg@1.2.3",
+/// "pkg-v1.2.3"), so tags naming another package are ignored.
+fn tag_version(tag: &str, package: &str) -> Option<Version> {
+    let short = package.rsplit('/').next().unwrap_or(package);
+    let version = match tag.rsplit_once('@') {
+        Some((prefix, version)) if !prefix.is_empty() => {
+            if prefix != package {
+                return None;
+            }
+            version
+        }
+        _ => tag
+            .strip_prefix(&format!("{}-", short))
+            .unwrap_or(tag.trim_start_matches('@')),
+    };
+    parse_version(version)
+}
+
+/// Split a changelog into releases, each starting at a heading naming a version
+///
+/// Headings look like "## 1.2.3", "## [1.2.3](compare-url) (2024-01-01)",
+/// "# v1.2.3" or "## Version 1.2.3".
+fn changelog_sections(text: &str, package: &str) -> Vec<(Version, String)> {
+    let mut sections = Vec::new();
+    let mut current: Option<(Version, String)> = None;
+
+    for line in text.lines() {
+        let version = heading(line).and_then(|(_, title)| {
+            title.split_whitespace().take(2).find_map(|word| {
+                let word = word.trim_start_matches('[').split(']').next()?;
+                tag_version(word.trim_end_matches(':'), package)
+            })
+        });
+        if let Some(version) = version {
+            sections.extend(current.take());
+            current = Some((version, String::new()));
+        } else if let Some((_, body)) = &mut current {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+
+    sections.extend(current);
+    sections
+}
+
+/// Notes of the releases after `from` up to `to`
+///
+/// Prereleases along the way are skipped, as their notes repeat in the
+/// final release. Returns `None` when no release falls in between.
+fn notes_between(
+    sections: Vec<(Version, String)>,
+    from: &Version,
+    to: &Version,
+    source: String,
+) -> Option<ReleaseNotes> {
+    let mut sections: Vec<(Version, String)> = sections
+        .into_iter()
+        .filter(|(version, _)| version > from && version <= to)
+        .filter(|(version, _)| version.pre.is_empty() || version == to)
+        .collect();
+    if sections.is_empty() {
+        return None;
+    }
+    sections.sort_by(|a, b| a.0.cmp(&b.0));
+    sections.dedup_by(|a, b| a.0 == b.0);
+
+    let mut breaking: Vec<BreakingChange> = Vec::new();
+    for (version, body) in &sections {
+        for summary in breaking_changes(body) {
+            if !breaking.iter().any(|change| change.summary == summary) {
+                breaking.push(BreakingChange {
+                    version: version.to_string(),
+                    summary,
+                });
+            }
+        }
+    }
+
+    Some(ReleaseNotes {
+        source,
+        versions: sections.iter().map(|(v, _)| v.to_string()).collect(),
+        breaking,
+        truncated: false,
+    })
+}
+
+/// Breaking changes called out in one release's notes
+///
+/// Items listed under a heading (or a bold line) mentioning "breaking",
+/// and lines flagged "BREAKING CHANGE:", "Breaking:" or with a
+/// conventional commit's "!:".
+fn breaking_changes(body: &str) -> Vec<String> {
+    // Heading level of the breaking changes section being read
+    let mut section: Option<usize> = None;
+    let mut changes = Vec::new();
+
+    for line in body.lines() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+        let mentions_breaking = lower.contains("breaking")
+            && !lower.contains("no breaking")
+            && !lower.contains("non-breaking");
+
+        if let Some((level, _)) = heading(trimmed) {
+            if section.is_some_and(|open| level <= open) {
+                section = None;
+            }
+            if mentions_breaking {
+                section = Some(level);
+            }
+            continue;
+        }
+        // "**Breaking changes**" on a line of its own acts as a heading
+        let bold = trimmed.trim_end_matches(':');
+        if bold.len() > 4 && bold.starts_with("**") && bold.ends_with("**") {
+            section = mentions_breaking.then_some(usize::MAX);
+            continue;
+        }
+
+        let item = ["- ", "* ", "+ "]
+            .iter()
+            .find_map(|bullet| trimmed.strip_prefix(bullet));
+        let flagged = (mentions_breaking
+            && (lower.contains("breaking change") || lower.contains("breaking:")))
+            || is_breaking_commit(item.unwrap_or(trimmed));
+
+        let note = match (section, item) {
+            (Some(_), Some(item)) => item,
+            _ if flagged => item.unwrap_or(trimmed),
+            _ => continue,
+        };
+        let summary = summarize(note);
+        if !summary.is_empty() && !changes.contains(&summary) {
+            changes.push(summary);
+        }
+    }
+
+    changes
+}
+
+/// A markdown ATX heading's level and title
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim();
+    let level = line.bytes().take_while(|b| *b == b'#').count();
+    let title = &line[level..];
+    if level == 0 || level > 6 || !(title.is_empty() || title.starts_with(' ')) {
+        return None;
+    }
+    Some((level, title.trim()))
+}
+
+/// "feat!: drop Node 16" or "fix(parser)!: reject trailing commas"
+fn is_breaking_commit(text: &str) -> bool {
+    let text = text.trim_start_matches('*');
+    let Some((kind, _)) = text.split_once("!:") else {
+        return false;
+    };
+    let kind = match kind.split_once('(') {
+        Some((kind, scope)) if scope.ends_with(')') => kind,
+        Some(_) => return false,
+        None => kind,
+    };
+    !kind.is_empty() && kind.bytes().all(|b| b.is_ascii_lowercase())
+}
+
+/// A note's first line, without markdown, flags and trailing references
+///
+/// Examples:
+/// - "**BREAKING CHANGE:** drop `req.param()` ([#123](https://...))" -> "drop `req.param()`"
+/// - "feat(router)!: remove app.del() by @user in https://..." -> "remove app.del()"
+fn summarize(note: &str) -> String {
+    let mut text = strip_links(note.lines().next().unwrap_or("")).replace("**", "");
+
+    if is_breaking_commit(&text) {
+        if let Some((_, rest)) = text.split_once("!:") {
+            text = rest.to_string();
+        }
+    }
+    for flag in ["breaking changes:", "breaking change:", "breaking:"] {
+        if text.trim_start().to_lowercase().starts_with(flag) {
+            text = text.trim_start()[flag.len()..].to_string();
+            break;
+        }
+    }
+    for reference in [" (#", " ([", " by @", " in https://"] {
+        if let Some(index) = text.find(reference) {
+            text.truncate(index);
+        }
+    }
+
+    let text = text.trim().trim_end_matches(['.', ',']).trim();
+    if text.chars().count() > MAX_SUMMARY {
+        let cut: String = text.chars().take(MAX_SUMMARY - 1).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        text.to_string()
+    }
+}
+
+/// Replace markdown links `[text](url)` with their text
+fn strip_links(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(open) = rest.find('[') {
+        let Some(close) = rest[open..].find("](").map(|i| open + i) else {
+            break;
+        };
+        let Some(end) = rest[close..].find(')').map(|i| close + i) else {
+            break;
+        };
+        output.push_str(&rest[..open]);
+        output.push_str(&rest[open + 1..close]);
+        rest = &rest[end + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    body: Option<String>,
+    #[serde(default)]
+    draft: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_github_repo() {
+        let repo = |owner: &str, name: &str| {
+            Some(GithubRepo {
+                owner: owner.to_string(),
+                name: name.to_string(),
+            })
+        };
+        assert_eq!(
+            github_repo("git+https://github.com/expressjs/express.git"),
+            repo("expressjs", "express")
+        );
+        assert_eq!(
+            github_repo("git@github.com:user/repo.git"),
+            repo("user", "repo")
+        );
+        assert_eq!(github_repo("github:user/repo"), repo("user", "repo"));
+        assert_eq!(github_repo("user/repo#main"), repo("user", "repo"));
+        assert_eq!(
+            github_repo("https://github.com/vercel/next.js/tree/canary/packages/next"),
+            repo("vercel", "next.js")
+        );
+        assert_eq!(github_repo("https://gitlab.com/user/repo"), None);
+    }
+
+    #[test]
+    fn test_tag_version() {
+        let v = |s: &str| Version::parse(s).ok();
+        assert_eq!(tag_version("v5.0.0", "express"), v("5.0.0"));
+        assert_eq!(tag_version("5.0.0", "express"), v("5.0.0"));
+        assert_eq!(
+            tag_version("@vue/reactivity@3.4.0", "@vue/reactivity"),
+            v("3.4.0")
+        );
+        assert_eq!(tag_version("@vue/shared@3.4.0", "@vue/reactivity"), None);
+        assert_eq!(tag_version("next-v14.0.0", "next"), v("14.0.0"));
+        assert_eq!(tag_version("nightly", "next"), None);
+    }
+
+    #[test]
+    fn test_release_notes_between() {
+        let changelog = r#"# Changelog
+
+## [3.0.0](https://github.com/acme/lib/compare/v2.1.0...v3.0.0) (2024-03-01)
+
+### ⚠ BREAKING CHANGES
+
+* drop support for Node.js 16 ([#412](https://github.com/acme/lib/issues/412)) ([abc1234](https://github.com/acme/lib/commit/abc1234))
+* **parser:** `parse()` now throws on invalid input
+
+### Features
+
+* add `stringify()` ([#400](https://github.com/acme/lib/issues/400))
+
+## 3.0.0-rc.1
+
+* feat!: something that got reverted
+
+## v2.1.0
+
+- fix!: rename the `strict` option to `mode`
+- Non-breaking: faster parsing
+
+## 2.0.1
+
+**Breaking changes**
+- Remove the deprecated `legacy` export, which nobody should have been using anymore since the 1.x line was retired
+"#;
+        let sections = changelog_sections(changelog, "lib");
+        let versions: Vec<String> = sections.iter().map(|(v, _)| v.to_string()).collect();
+        assert_eq!(versions, vec!["3.0.0", "3.0.0-rc.1", "2.1.0", "2.0.1"]);
+
+        let from = Version::parse("2.0.1").unwrap();
+        let to = Version::parse("3.0.0").unwrap();
+        let notes =
+            notes_between(sections.clone(), &from, &to, "CHANGELOG.md".to_string()).unwrap();
+        assert_eq!(notes.versions, vec!["2.1.0", "3.0.0"]);
+        let breaking: Vec<(&str, &str)> = notes
+            .breaking
+            .iter()
+            .map(|change| (change.version.as_str(), change.summary.as_str()))
+            .collect();
+        assert_eq!(
+            breaking,
+            vec![
+                ("2.1.0", "rename the `strict` option to `mode`"),
+                ("3.0.0", "drop support for Node.js 16"),
+                ("3.0.0", "parser: `parse()` now throws on invalid input"),
+            ]
+        );
+
+        let older = notes_between(
+            sections.clone(),
+            &Version::parse("2.0.0").unwrap(),
+            &Version::parse("2.0.1").unwrap(),
+            String::new(),
+        )
+        .unwrap();
+        assert_eq!(older.breaking[0].summary.chars().count(), MAX_SUMMARY);
+        assert!(older.breaking[0].summary.ends_with('…'));
+
+        assert!(notes_between(sections, &to, &to, String::new()).is_none());
+    }
+
+    #[test]
+    fn test_breaking_changes() {
+        let body = "## What's Changed\n* Drop Node 16 (BREAKING CHANGE) by @alice in https://github.com/a/b/pull/1\n* BREAKING: `app.del()` is removed\n* Fix typo by @bob in https://github.com/a/b/pull/2\n\nThere are no breaking changes to the CLI.\n";
+        assert_eq!(
+            breaking_changes(body),
+            vec!["Drop Node 16 (BREAKING CHANGE)", "`app.del()` is removed"]
+        );
+        assert!(is_breaking_commit("feat(api)!: x"));
+        assert!(!is_breaking_commit("Wow!: x"));
+        assert_eq!(
+            strip_links("see [docs](https://x.dev) and [#1](u)"),
+            "see docs and #1"
+        );
+    }
+}
@@ -0,0 +1,431 @@
+use std::fmt;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use clap::ValueEnum;
+use miette::{Context, IntoDiagnostic, Result};
+
+use crate::policy::AnalyzePolicy;
+use crate::rules::Rule;
+use crate::types::{AnalysisSnapshot, UsageAnalysis};
+
+/// Default path of the GitLab Code Quality report
+pub const GITLAB_REPORT: &str = "gl-code-quality-report.json";
+
+/// CI service `depx ci` reports to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CiProvider {
+    /// Workflow commands (`::error`) and the job summary
+    Github,
+    /// A Code Quality report artifact
+    Gitlab,
+    /// `buildkite-agent annotate`
+    Buildkite,
+    /// Plain report, no annotations
+    Local,
+}
+
+impl CiProvider {
+    /// Detect the provider from the variables each CI service sets
+    pub fn detect() -> Self {
+        Self::detect_from(|name| std::env::var(name).ok())
+    }
+
+    fn detect_from(env: impl Fn(&str) -> Option<String>) -> Self {
+        let is_set = |name: &str| env(name).is_some_and(|v| v == "true");
+
+        if is_set("GITHUB_ACTIONS") {
+            CiProvider::Github
+        } else if is_set("GITLAB_CI") {
+            CiProvider::Gitlab
+        } else if is_set("BUILDKITE") {
+            CiProvider::Buildkite
+        } else {
+            CiProvider::Local
+        }
+    }
+}
+
+impl fmt::Display for CiProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CiProvider::Github => "GitHub Actions",
+            CiProvider::Gitlab => "GitLab CI",
+            CiProvider::Buildkite => "Buildkite",
+            CiProvider::Local => "local",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnnotationLevel {
+    Error,
+    Warning,
+}
+
+/// A finding attached to a file and line in the CI interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Annotation {
+    pub level: AnnotationLevel,
+    pub rule: Rule,
+    pub package: String,
+    /// Relative to the directory depx runs in
+    pub file: PathBuf,
+    pub line: usize,
+    pub message: String,
+}
+
+/// Drop findings already recorded in the baseline; returns how many were dropped
+pub fn apply_baseline(analysis: &mut UsageAnalysis, baseline: &AnalysisSnapshot) -> usize {
+    let before = analysis.unused_direct.len() + analysis.phantom.len();
+    analysis
+        .unused_direct
+        .retain(|p| !baseline.unused.contains(&p.name));
+    analysis
+        .phantom
+        .retain(|p| !baseline.phantom.contains(&p.name));
+    before - analysis.unused_direct.len() - analysis.phantom.len()
+}
+
+/// Annotations for the analysis findings
+///
+/// Findings the policy fails on are errors, the rest warnings. Unused
+/// dependencies point at their line in package.json.
+pub fn annotations(
+    root: &Path,
+    analysis: &UsageAnalysis,
+    policy: &AnalyzePolicy,
+) -> Result<Vec<Annotation>> {
+    let manifest_path = root.join("package.json");
+    let manifest = if manifest_path.exists() {
+        std::fs::read_to_string(&manifest_path)
+            .into_diagnostic()
+            .with_context(|| "Failed to read package.json")?
+    } else {
+        String::new()
+    };
+    let level = |rule: Rule| {
+        if policy.fails_on(rule, analysis) {
+            AnnotationLevel::Error
+        } else {
+            AnnotationLevel::Warning
+        }
+    };
+    // CI services resolve paths against the checkout, where depx runs
+    let located = |path: &Path| -> PathBuf {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        root.join(relative)
+            .components()
+            .filter(|c| *c != Component::CurDir)
+            .collect()
+    };
+    let mut annotations = Vec::new();
+    for package in &analysis.unused_direct {
+        annotations.push(Annotation {
+            level: level(Rule::UnusedDependency),
+            rule: Rule::UnusedDependency,
+            package: package.name.clone(),
+            file: located(Path::new("package.json")),
+            line: declaration_line(&manifest, &package.name).unwrap_or(1),
+            message: format!(
+                "'{}' is declared but never imported; remove it from package.json",
+                package.name
+            ),
+        });
+    }
+    for phantom in &analysis.phantom {
+        annotations.push(Annotation {
+            level: level(Rule::PhantomDependency),
+            rule: Rule::PhantomDependency,
+            package: phantom.name.clone(),
+            file: located(&phantom.file_path),
+            line: phantom.line,
+            message: format!(
+                "'{}' is imported but not declared in package.json",
+                phantom.name
+            ),
+        });
+    }
+
+    Ok(annotations)
+}
+
+/// Line of a dependency's `"name":` key in package.json
+fn declaration_line(manifest: &str, name: &str) -> Option<usize> {
+    let key = format!("\"{}\"", name);
+    manifest
+        .lines()
+        .position(|line| {
+            line.trim_start()
+                .strip_prefix(&key)
+                .is_some_and(|rest| rest.trim_start().starts_with(':'))
+        })
+        .map(|index| index + 1)
+}
+
+/// Hand the annotations to the provider's annotation mechanism
+///
+/// `report` overrides where the GitLab Code Quality report is written.
+pub fn publish(
+    provider: CiProvider,
+    annotations: &[Annotation],
+    baselined: usize,
+    report: Option<&Path>,
+) -> Result<()> {
+    match provider {
+        CiProvider::Github => {
+            for annotation in annotations {
+                println!("{}", workflow_command(annotation));
+            }
+            // The job summary is only available inside a step
+            if let Ok(summary) = std::env::var("GITHUB_STEP_SUMMARY") {
+                let mut file = std::fs::OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&summary)
+                    .into_diagnostic()
+                    .with_context(|| format!("Failed to open {}", summary))?;
+                file.write_all(markdown_summary(annotations, baselined).as_bytes())
+                    .into_diagnostic()
+                    .with_context(|| format!("Failed to write {}", summary))?;
+            }
+        }
+        CiProvider::Gitlab => {
+            let path = report.unwrap_or(Path::new(GITLAB_REPORT));
+            let output = serde_json::to_string_pretty(&code_quality_report(annotations))
+                .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+            std::fs::write(path, output + "\n")
+                .into_diagnostic()
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        CiProvider::Buildkite => {
+            if annotations.is_empty() {
+                return Ok(());
+            }
+            let style = if annotations
+                .iter()
+                .any(|a| a.level == AnnotationLevel::Error)
+            {
+                "error"
+            } else {
+                "warning"
+            };
+            let mut agent = Command::new("buildkite-agent")
+                .args(["annotate", "--style", style, "--context", "depx"])
+                .stdin(Stdio::piped())
+                .spawn()
+                .into_diagnostic()
+                .with_context(|| "Failed to run buildkite-agent")?;
+            if let Some(mut stdin) = agent.stdin.take() {
+                stdin
+                    .write_all(markdown_summary(annotations, baselined).as_bytes())
+                    .into_diagnostic()
+                    .with_context(|| "Failed to write to buildkite-agent")?;
+            }
+            let status = agent
+                .wait()
+                .into_diagnostic()
+                .with_context(|| "Failed to run buildkite-agent")?;
+            if !status.success() {
+                return Err(miette::miette!(
+                    "buildkite-agent annotate exited with {}",
+                    status
+                ));
+            }
+        }
+        CiProvider::Local => {}
+    }
+
+    Ok(())
+}
+
+/// GitHub Actions workflow command, e.g. `::error file=a.js,line=3,title=...::message`,
+/// with the rule's docs on a line of their own
+fn workflow_command(annotation: &Annotation) -> String {
+    let command = match annotation.level {
+        AnnotationLevel::Error => "error",
+        AnnotationLevel::Warning => "warning",
+    };
+    format!(
+        "::{} file={},line={},title={}::{}",
+        command,
+        escape_property(&annotation.file.display().to_string()),
+        annotation.line,
+        escape_property(&format!(
+            "depx: {} {}",
+            annotation.rule,
+            annotation.rule.name()
+        )),
+        escape_data(&format!(
+            "{}\nSee {}",
+            annotation.message,
+            annotation.rule.docs_url()
+        ))
+    )
+}
+
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// GitLab Code Quality entries (a subset of the Code Climate format)
+///
+/// Fingerprints hash the rule's name rather than its ID, as they did
+/// before rules had IDs, so existing findings aren't reported as new.
+fn code_quality_report(annotations: &[Annotation]) -> serde_json::Value {
+    annotations
+        .iter()
+        .map(|annotation| {
+            let fingerprint = ring::digest::digest(
+                &ring::digest::SHA256,
+                format!("depx:{}:{}", annotation.rule.name(), annotation.package).as_bytes(),
+            );
+            serde_json::json!({
+                "description": annotation.message,
+                "check_name": annotation.rule.id(),
+                "content": {
+                    "body": format!("[{} {}]({})", annotation.rule, annotation.rule.name(), annotation.rule.docs_url()),
+                },
+                "fingerprint": fingerprint
+                    .as_ref()
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>(),
+                "severity": match annotation.level {
+                    AnnotationLevel::Error => "major",
+                    AnnotationLevel::Warning => "minor",
+                },
+                "location": {
+                    "path": annotation.file.display().to_string(),
+                    "lines": { "begin": annotation.line },
+                },
+            })
+        })
+        .collect()
+}
+
+/// Markdown table of the findings for job summaries and annotations
+fn markdown_summary(annotations: &[Annotation], baselined: usize) -> String {
+    let mut summary = String::from("### depx\n\n");
+
+    if annotations.is_empty() {
+        summary.push_str("No new dependency findings.\n");
+    } else {
+        summary.push_str("| Level | Rule | Package | Location |\n");
+        summary.push_str("|-------|------|---------|----------|\n");
+        for annotation in annotations {
+            let level = match annotation.level {
+                AnnotationLevel::Error => "error",
+                AnnotationLevel::Warning => "warning",
+            };
+            summary.push_str(&format!(
+                "| {} | [{}]({}) {} | `{}` | `{}:{}` |\n",
+                level,
+                annotation.rule,
+                annotation.rule.docs_url(),
+                annotation.rule.name(),
+                annotation.package,
+                annotation.file.display(),
+                annotation.line
+            ));
+        }
+    }
+
+    if baselined > 0 {
+        summary.push_str(&format!(
+            "\n{} findings already in the baseline were not reported.\n",
+            baselined
+        ));
+    }
+    summary.push('\n');
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_provider() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+
+        assert_eq!(
+            CiProvider::detect_from(env(&[("GITHUB_ACTIONS", "true")])),
+            CiProvider::Github
+        );
+        assert_eq!(
+            CiProvider::detect_from(env(&[("GITLAB_CI", "true"), ("CI", "true")])),
+            CiProvider::Gitlab
+        );
+        assert_eq!(
+            CiProvider::detect_from(env(&[("BUILDKITE", "true")])),
+            CiProvider::Buildkite
+        );
+        assert_eq!(
+            CiProvider::detect_from(env(&[("CI", "true")])),
+            CiProvider::Local
+        );
+    }
+
+    #[test]
+    fn test_workflow_command() {
+        let annotation = Annotation {
+            level: AnnotationLevel::Error,
+            rule: Rule::PhantomDependency,
+            package: "lodash".to_string(),
+            file: PathBuf::from("src/a,b.js"),
+            line: 3,
+            message: "100% broken\nreally".to_string(),
+        };
+        assert_eq!(
+            workflow_command(&annotation),
+            "::error file=src/a%2Cb.js,line=3,title=depx%3A DEPX010 phantom-dependency::100%25 broken%0Areally%0ASee https://github.com/ruidosujeira/depx/blob/main/docs/rules.md#depx010"
+        );
+    }
+
+    #[test]
+    fn test_code_quality_report() {
+        let annotation = Annotation {
+            level: AnnotationLevel::Warning,
+            rule: Rule::UnusedDependency,
+            package: "lodash".to_string(),
+            file: PathBuf::from("package.json"),
+            line: 4,
+            message: "unused".to_string(),
+        };
+        let report = code_quality_report(&[annotation]);
+        assert_eq!(report[0]["check_name"], "DEPX001");
+        assert_eq!(report[0]["severity"], "minor");
+        // Same fingerprint as before rules had IDs
+        let fingerprint: String =
+            ring::digest::digest(&ring::digest::SHA256, b"depx:unused-dependency:lodash")
+                .as_ref()
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect();
+        assert_eq!(report[0]["fingerprint"], fingerprint);
+    }
+
+    #[test]
+    fn test_declaration_line() {
+        let manifest = "{\n  \"name\": \"app\",\n  \"dependencies\": {\n    \"lodash\": \"^4.0.0\",\n    \"lodash.merge\" : \"^4.0.0\"\n  }\n}\n";
+        assert_eq!(declaration_line(manifest, "lodash"), Some(4));
+        assert_eq!(declaration_line(manifest, "lodash.merge"), Some(5));
+        assert_eq!(declaration_line(manifest, "react"), None);
+    }
+}
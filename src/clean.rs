@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::Command;
+
+use miette::{IntoDiagnostic, Result};
+
+use crate::types::{CleanCandidate, LastUsage, Package, UsageAnalysis};
+
+/// What to do with the candidate being shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// Remove this one
+    Remove,
+    /// Keep this one
+    Keep,
+    /// Remove this one and every remaining candidate
+    RemoveRest,
+    /// Stop asking; keep this one and the rest
+    Quit,
+}
+
+/// Unused direct dependencies with what removing each would change
+pub fn candidates(
+    root: &Path,
+    analysis: &UsageAnalysis,
+    packages: &HashMap<String, Package>,
+) -> Vec<CleanCandidate> {
+    analysis
+        .unused_direct
+        .iter()
+        .map(|pkg| {
+            let mut required_by: Vec<String> = packages
+                .values()
+                .filter(|other| other.dependencies.contains(&pkg.name))
+                .map(|other| other.name.clone())
+                .collect();
+            required_by.sort();
+
+            CleanCandidate {
+                package: pkg.clone(),
+                removes: analysis
+                    .removable_transitive
+                    .get(&pkg.name)
+                    .copied()
+                    .unwrap_or(0),
+                disk_size: disk_size(&root.join("node_modules").join(&pkg.name)),
+                required_by,
+                last_usage: last_usage(root, &pkg.name),
+            }
+        })
+        .collect()
+}
+
+/// Total size of the files under `dir`
+pub fn disk_size(dir: &Path) -> Option<u64> {
+    if !dir.is_dir() {
+        return None;
+    }
+    Some(
+        walkdir::WalkDir::new(dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| entry.metadata().ok())
+            .map(|metadata| metadata.len())
+            .sum(),
+    )
+}
+
+/// Most recent commit adding or removing a quoted `name` outside manifests
+/// and lockfiles, i.e. the last time an import of it changed
+fn last_usage(root: &Path, name: &str) -> Option<LastUsage> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "log",
+            "-1",
+            "--date=short",
+            "--format=%h%x09%ad%x09%s",
+            "-G",
+        ])
+        .arg(format!("['\"]{}['\"/]", escape_basic_regex(name)))
+        .args([
+            "--",
+            ".",
+            ":!package.json",
+            ":!package-lock.json",
+            ":!pnpm-lock.yaml",
+            ":!yarn.lock",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut fields = stdout.trim_end().splitn(3, '\t');
+    Some(LastUsage {
+        commit: fields.next().filter(|c| !c.is_empty())?.to_string(),
+        date: fields.next()?.to_string(),
+        subject: fields.next().unwrap_or("").to_string(),
+    })
+}
+
+fn escape_basic_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '.' | '*' | '[' | ']' | '^' | '$' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Parse a reply to the removal prompt; an empty reply keeps the package
+pub fn parse_answer(reply: &str) -> Option<Answer> {
+    match reply.trim().to_ascii_lowercase().as_str() {
+        "y" | "yes" => Some(Answer::Remove),
+        "" | "n" | "no" => Some(Answer::Keep),
+        "a" | "all" => Some(Answer::RemoveRest),
+        "q" | "quit" => Some(Answer::Quit),
+        _ => None,
+    }
+}
+
+/// Ask until the reply parses; end of input counts as quitting
+pub fn ask(question: &str) -> Result<Answer> {
+    let stdin = std::io::stdin();
+    loop {
+        print!("{} [y/N/a/q] ", question);
+        std::io::stdout().flush().into_diagnostic()?;
+
+        let mut reply = String::new();
+        if stdin.lock().read_line(&mut reply).into_diagnostic()? == 0 {
+            println!();
+            return Ok(Answer::Quit);
+        }
+        match parse_answer(&reply) {
+            Some(answer) => return Ok(answer),
+            None => println!("  y = remove, n = keep, a = remove this and the rest, q = stop"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_answer() {
+        assert_eq!(parse_answer("y\n"), Some(Answer::Remove));
+        assert_eq!(parse_answer(" YES "), Some(Answer::Remove));
+        assert_eq!(parse_answer("\n"), Some(Answer::Keep));
+        assert_eq!(parse_answer("a"), Some(Answer::RemoveRest));
+        assert_eq!(parse_answer("q"), Some(Answer::Quit));
+        assert_eq!(parse_answer("maybe"), None);
+    }
+
+    #[test]
+    fn test_escape_basic_regex() {
+        assert_eq!(escape_basic_regex("lodash.merge"), "lodash\\.merge");
+        assert_eq!(escape_basic_regex("@scope/pkg"), "@scope/pkg");
+    }
+}
@@ -0,0 +1,285 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+
+use crate::policy::AnalyzePolicy;
+use crate::registry::RegistryKind;
+use crate::rules::{Rule, RuleLevel};
+use crate::types::{AlignPreference, RangeStyle, RulePack};
+
+/// Name of the per-project configuration file
+pub const CONFIG_FILE: &str = "depx.toml";
+
+/// Project configuration read from `depx.toml`
+///
+/// ```toml
+/// # Published entry points: "main", "exports" and "bin" expand from
+/// # package.json, anything else is a source file
+/// entry-points = ["exports", "bin", "src/index.ts"]
+///
+/// # Where `depx check` accepts lockfile entries from: hosts, "*.domain"
+/// # wildcards, or URL prefixes. Replaces the default npm/yarn registries
+/// allowed-registries = ["registry.npmjs.org", "https://npm.pkg.github.com/acme/"]
+///
+/// # Framework rule packs `depx check` lints with: "react", "nextjs",
+/// # "nestjs", "express"
+/// lint = ["react", "nextjs"]
+///
+/// # Count imports in the js/ts code blocks of Markdown files, for docs
+/// # whose examples are built and run. MDX imports always count
+/// markdown-imports = true
+///
+/// # The range style `depx pinning` holds dependencies to: "exact",
+/// # "caret" or "tilde". Defaults to the most common one
+/// [pinning]
+/// policy = "caret"
+///
+/// # How `depx align` harmonizes ranges across workspace members: the
+/// # "highest" (default) or "lowest" declared range wins, `ignore` leaves
+/// # dependencies out, and `pin` sets their range outright
+/// [align]
+/// prefer = "highest"
+/// ignore = ["@types/*"]
+/// pin = { react = "^18.2.0", "@babel/*" = "^7.24.0" }
+///
+/// # What `depx ci` fails on
+/// [ci]
+/// baseline = "depx-baseline.json"
+/// fail-if-unused = true
+/// fail-if-phantom = true
+/// max-unused = 3
+///
+/// # Rule levels by ID or name: "off" leaves its findings out of reports,
+/// # "warn" reports them without failing, "error" fails on any of them
+/// [rules]
+/// DEPX004 = "off"
+/// DEPX010 = "error"
+/// duplicate-version = "warn"
+///
+/// # Advisories `depx audit` leaves out, with the reason; `--vex` records them
+/// [audit.ignore]
+/// "GHSA-xvch-5gv4-984h" = "Only parses our own build arguments"
+///
+/// # Teams owning packages, ahead of CODEOWNERS; "*" matches any run of characters
+/// [owners]
+/// "@acme/ui-*" = "@acme/design-system"
+/// "react*" = ["@acme/web", "@alice"]
+///
+/// # Registries serving packages by name, for update, health, deprecated
+/// # and audit; "type" defaults to the project's ecosystem
+/// [registries."@acme"]
+/// url = "https://npm.acme.internal"
+/// token-env = "ACME_NPM_TOKEN"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    pub entry_points: Vec<String>,
+    pub allowed_registries: Vec<String>,
+    pub lint: Vec<RulePack>,
+    pub markdown_imports: bool,
+    pub pinning: PinningConfig,
+    pub align: AlignConfig,
+    pub ci: CiConfig,
+    pub rules: BTreeMap<Rule, RuleLevel>,
+    pub audit: AuditConfig,
+    pub owners: BTreeMap<String, OwnerList>,
+    pub registries: BTreeMap<String, RegistryConfig>,
+}
+
+/// One owner or several
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum OwnerList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OwnerList {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            OwnerList::One(owner) => vec![owner.clone()],
+            OwnerList::Many(owners) => owners.clone(),
+        }
+    }
+}
+
+/// `[pinning]` section: how dependency ranges are written
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct PinningConfig {
+    pub policy: Option<RangeStyle>,
+}
+
+/// `[align]` section: the range each dependency is aligned to
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AlignConfig {
+    pub prefer: AlignPreference,
+
+    /// Dependency names left alone; "*" matches any run of characters
+    pub ignore: Vec<String>,
+
+    /// Dependency name or pattern -> the range to declare it with
+    pub pin: BTreeMap<String, String>,
+}
+
+/// `[ci]` section: the baseline and policy `depx ci` applies
+#[derive(Debug, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct CiConfig {
+    /// Snapshot from `depx analyze --save`; findings in it don't fail the build
+    pub baseline: Option<PathBuf>,
+    pub fail_if_unused: bool,
+    pub fail_if_phantom: bool,
+    pub max_unused: Option<usize>,
+}
+
+impl Default for CiConfig {
+    fn default() -> Self {
+        Self {
+            baseline: None,
+            fail_if_unused: true,
+            fail_if_phantom: true,
+            max_unused: None,
+        }
+    }
+}
+
+/// `[audit]` section: advisories triaged as not affecting the project
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct AuditConfig {
+    /// Advisory ID -> why it doesn't apply
+    pub ignore: BTreeMap<String, String>,
+}
+
+/// `[registries]` entry: the registry serving packages matching its key
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct RegistryConfig {
+    pub url: String,
+    /// Protocol the registry speaks
+    #[serde(rename = "type")]
+    pub kind: Option<RegistryKind>,
+    /// Environment variable holding a bearer token
+    pub token_env: Option<String>,
+    /// Proxies the public registry, so public advisories apply to it
+    #[serde(default)]
+    pub mirror: bool,
+}
+
+impl CiConfig {
+    pub fn policy(&self) -> AnalyzePolicy {
+        AnalyzePolicy {
+            fail_if_unused: self.fail_if_unused,
+            fail_if_phantom: self.fail_if_phantom,
+            max_unused: self.max_unused,
+            rules: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Load `depx.toml` from the project root, or defaults when there is none
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        toml::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse {}: {}", CONFIG_FILE, e))
+    }
+
+    /// Rules set to "off" in `[rules]`
+    pub fn disabled_rules(&self) -> HashSet<Rule> {
+        self.rules
+            .iter()
+            .filter(|(_, level)| **level == RuleLevel::Off)
+            .map(|(rule, _)| *rule)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config: Config = toml::from_str(r#"entry-points = ["main", "src/cli.ts"]"#).unwrap();
+        assert_eq!(config.entry_points, vec!["main", "src/cli.ts"]);
+
+        let config: Config = toml::from_str("").unwrap();
+        assert!(config.entry_points.is_empty());
+        assert!(!config.markdown_imports);
+        let config: Config = toml::from_str("markdown-imports = true").unwrap();
+        assert!(config.markdown_imports);
+
+        assert!(toml::from_str::<Config>("entry_point = []").is_err());
+
+        let config: Config =
+            toml::from_str(r#"allowed-registries = ["registry.npmjs.org"]"#).unwrap();
+        assert_eq!(config.allowed_registries, vec!["registry.npmjs.org"]);
+
+        let config: Config = toml::from_str(r#"lint = ["react", "nestjs"]"#).unwrap();
+        assert_eq!(config.lint, vec![RulePack::React, RulePack::Nestjs]);
+        assert!(toml::from_str::<Config>(r#"lint = ["angular"]"#).is_err());
+
+        let config: Config = toml::from_str("[pinning]\npolicy = \"exact\"\n").unwrap();
+        assert_eq!(config.pinning.policy, Some(RangeStyle::Exact));
+        assert!(toml::from_str::<Config>("[pinning]\npolicy = \"loose\"\n").is_err());
+
+        let config: Config = toml::from_str(
+            "[align]\nprefer = \"lowest\"\nignore = [\"@types/*\"]\npin = { react = \"^18.2.0\" }\n",
+        )
+        .unwrap();
+        assert_eq!(config.align.prefer, AlignPreference::Lowest);
+        assert_eq!(config.align.ignore, vec!["@types/*"]);
+        assert_eq!(config.align.pin["react"], "^18.2.0");
+        assert_eq!(Config::default().align.prefer, AlignPreference::Highest);
+
+        let config: Config = toml::from_str("[ci]\nfail-if-unused = false\n").unwrap();
+        assert!(!config.ci.fail_if_unused);
+        assert!(config.ci.fail_if_phantom);
+        assert!(config.ci.baseline.is_none());
+
+        let config: Config =
+            toml::from_str("[rules]\nDEPX004 = \"off\"\nphantom-dependency = \"error\"\n").unwrap();
+        assert_eq!(
+            config.disabled_rules(),
+            HashSet::from([Rule::OverlappingDependencies])
+        );
+        assert_eq!(config.rules[&Rule::PhantomDependency], RuleLevel::Error);
+        assert!(toml::from_str::<Config>("[rules]\nDEPX999 = \"off\"\n").is_err());
+        assert!(toml::from_str::<Config>("[rules]\nDEPX001 = \"on\"\n").is_err());
+
+        let config: Config =
+            toml::from_str("[owners]\n\"react*\" = \"@web\"\nlodash = [\"@a\", \"@b\"]\n").unwrap();
+        assert_eq!(config.owners["react*"].to_vec(), vec!["@web"]);
+        assert_eq!(config.owners["lodash"].to_vec(), vec!["@a", "@b"]);
+
+        let config: Config =
+            toml::from_str("[audit.ignore]\n\"GHSA-1\" = \"not reachable\"\n").unwrap();
+        assert_eq!(config.audit.ignore["GHSA-1"], "not reachable");
+
+        let config: Config = toml::from_str(
+            "[registries.\"@acme\"]\nurl = \"https://npm.acme.internal\"\ntype = \"npm\"\ntoken-env = \"ACME_TOKEN\"\n",
+        )
+        .unwrap();
+        let acme = &config.registries["@acme"];
+        assert_eq!(acme.kind, Some(RegistryKind::Npm));
+        assert_eq!(acme.token_env.as_deref(), Some("ACME_TOKEN"));
+        assert!(!acme.mirror);
+        assert!(
+            toml::from_str::<Config>("[registries.x]\nurl = \"u\"\ntype = \"maven\"\n").is_err()
+        );
+    }
+}
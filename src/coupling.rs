@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::DiGraph;
+
+use crate::types::{CouplingReport, ImportMap, ModuleCoupling};
+
+/// A module importing this many project files is doing too much
+const GOD_MODULE_FAN_OUT: usize = 15;
+
+/// A module this heavily imported and importing is a change hotspot
+const HUB_FAN_IN: usize = 10;
+const HUB_FAN_OUT: usize = 10;
+
+/// Compute fan-in/fan-out per module and find import cycles
+pub fn analyze(imports: &ImportMap) -> CouplingReport {
+    let files: HashSet<&PathBuf> = imports.analyzed_files().collect();
+
+    // Only edges between analyzed source files count; JSON, CSS and the like
+    // don't take part in coupling
+    let mut edges: HashMap<&PathBuf, HashSet<&PathBuf>> = HashMap::new();
+    for file in &files {
+        let targets = imports
+            .local_imports_of(file)
+            .iter()
+            .filter_map(|i| i.target.as_ref())
+            .filter(|target| files.contains(target) && *target != *file)
+            .collect();
+        edges.insert(file, targets);
+    }
+
+    let mut fan_in: HashMap<&PathBuf, usize> = HashMap::new();
+    for targets in edges.values() {
+        for target in targets {
+            *fan_in.entry(target).or_default() += 1;
+        }
+    }
+
+    let mut modules: Vec<ModuleCoupling> = files
+        .iter()
+        .map(|file| {
+            let fan_in = fan_in.get(file).copied().unwrap_or(0);
+            let fan_out = edges.get(file).map(|t| t.len()).unwrap_or(0);
+            let total = fan_in + fan_out;
+
+            ModuleCoupling {
+                file: (*file).clone(),
+                fan_in,
+                fan_out,
+                instability: if total == 0 {
+                    0.0
+                } else {
+                    fan_out as f64 / total as f64
+                },
+                is_god_module: fan_out >= GOD_MODULE_FAN_OUT
+                    || (fan_in >= HUB_FAN_IN && fan_out >= HUB_FAN_OUT),
+            }
+        })
+        .collect();
+
+    modules.sort_by(|a, b| {
+        (b.fan_in + b.fan_out)
+            .cmp(&(a.fan_in + a.fan_out))
+            .then_with(|| a.file.cmp(&b.file))
+    });
+
+    CouplingReport {
+        modules,
+        clusters: import_cycles(&edges),
+    }
+}
+
+/// Strongly connected groups of two or more files
+fn import_cycles(edges: &HashMap<&PathBuf, HashSet<&PathBuf>>) -> Vec<Vec<PathBuf>> {
+    let mut graph: DiGraph<&PathBuf, ()> = DiGraph::new();
+    let mut indices = HashMap::new();
+
+    for file in edges.keys() {
+        indices.insert(*file, graph.add_node(*file));
+    }
+    for (from, targets) in edges {
+        for to in targets {
+            if let (Some(&a), Some(&b)) = (indices.get(from), indices.get(to)) {
+                graph.add_edge(a, b, ());
+            }
+        }
+    }
+
+    let mut clusters: Vec<Vec<PathBuf>> = tarjan_scc(&graph)
+        .into_iter()
+        .filter(|component| component.len() > 1)
+        .map(|component| {
+            let mut files: Vec<PathBuf> = component.iter().map(|&i| graph[i].clone()).collect();
+            files.sort();
+            files
+        })
+        .collect();
+
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    clusters
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::ImportAnalyzer;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_coupling() {
+        let root = TempDir::new("coupling");
+
+        std::fs::write(root.join("a.ts"), "import './b';\nimport './c';\n").unwrap();
+        std::fs::write(root.join("b.ts"), "import './c';\n").unwrap();
+        std::fs::write(root.join("c.ts"), "import './b';\n").unwrap();
+        std::fs::write(root.join("d.ts"), "export const d = 1;\n").unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+        let report = analyze(&imports);
+
+        let a = report
+            .modules
+            .iter()
+            .find(|m| m.file.ends_with("a.ts"))
+            .unwrap();
+        assert_eq!((a.fan_in, a.fan_out), (0, 2));
+        assert_eq!(a.instability, 1.0);
+
+        let c = report
+            .modules
+            .iter()
+            .find(|m| m.file.ends_with("c.ts"))
+            .unwrap();
+        assert_eq!((c.fan_in, c.fan_out), (2, 1));
+
+        assert_eq!(
+            report.clusters,
+            vec![vec![root.join("b.ts"), root.join("c.ts")]]
+        );
+        assert!(report.modules.iter().all(|m| !m.is_god_module));
+    }
+}
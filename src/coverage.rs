@@ -0,0 +1,270 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::{is_test_file, resolve};
+use crate::types::{
+    CoverageFormat, CoverageReport, CoveredImport, DependencyCoverage, ImportMap, Package,
+};
+
+/// Correlates a test coverage report with where production dependencies
+/// are imported
+///
+/// Reads an lcov tracefile or istanbul's `coverage-final.json`. An import
+/// is covered when the innermost statement on its line ran; imports the
+/// report has no statement for (istanbul doesn't instrument `import`
+/// declarations) are covered when anything in their file ran. A dependency
+/// only imported from code the tests never reach is either dead weight or
+/// a vulnerability nobody has exercised, so it's worth looking at first.
+/// Imports from test files, which coverage reports leave out, don't count.
+pub struct CoverageAnalyzer<'a> {
+    root: &'a Path,
+    report: &'a Path,
+}
+
+/// Hit counts of the statements (lcov: lines) of one file
+#[derive(Default)]
+struct FileCoverage {
+    /// First line, last line and hits of each statement
+    statements: Vec<(usize, usize, u64)>,
+}
+
+impl FileCoverage {
+    fn ran(&self) -> bool {
+        self.statements.iter().any(|(_, _, hits)| *hits > 0)
+    }
+
+    /// Hits of the smallest statement spanning `line`, so a `require()`
+    /// inside a function body isn't covered by the declaration around it
+    fn line_hits(&self, line: usize) -> Option<u64> {
+        self.statements
+            .iter()
+            .filter(|(start, end, _)| (*start..=*end).contains(&line))
+            .min_by_key(|(start, end, _)| end - start)
+            .map(|(_, _, hits)| *hits)
+    }
+}
+
+impl<'a> CoverageAnalyzer<'a> {
+    pub fn new(root: &'a Path, report: &'a Path) -> Self {
+        Self { root, report }
+    }
+
+    pub fn analyze(
+        &self,
+        packages: &HashMap<String, Package>,
+        imports: &ImportMap,
+    ) -> Result<CoverageReport> {
+        let content = std::fs::read_to_string(self.report)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.report.display()))?;
+
+        let (format, files) = match serde_json::from_str::<JsonValue>(&content) {
+            Ok(JsonValue::Object(report))
+                if report
+                    .values()
+                    .any(|file| file.get("statementMap").is_some()) =>
+            {
+                (CoverageFormat::Istanbul, istanbul_files(&report))
+            }
+            _ if content.lines().any(|line| line.starts_with("SF:")) => {
+                (CoverageFormat::Lcov, lcov_files(&content))
+            }
+            _ => bail!(
+                "{} isn't an lcov tracefile or istanbul coverage JSON",
+                self.report.display()
+            ),
+        };
+        let files: HashMap<PathBuf, FileCoverage> = files
+            .into_iter()
+            .map(|(path, coverage)| (file_key(&self.root.join(path)), coverage))
+            .collect();
+
+        let mut direct: Vec<&Package> = packages
+            .values()
+            .filter(|pkg| pkg.is_direct && !pkg.is_dev)
+            .collect();
+        direct.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut covered = Vec::new();
+        let mut uncovered = Vec::new();
+        let mut untracked = BTreeSet::new();
+        for package in direct {
+            let Some(usages) = imports.get_package_usages(&package.name) else {
+                continue;
+            };
+            let mut dependency = DependencyCoverage {
+                name: package.name.clone(),
+                version: package.version.clone(),
+                imports: Vec::new(),
+            };
+            for usage in usages.iter().filter(|u| !is_test_file(&u.file_path)) {
+                let Some(file) = files.get(&file_key(&usage.file_path)) else {
+                    continue;
+                };
+                dependency.imports.push(CoveredImport {
+                    file_path: usage.file_path.clone(),
+                    line: usage.line,
+                    covered: file
+                        .line_hits(usage.line)
+                        .map_or_else(|| file.ran(), |hits| hits > 0),
+                });
+            }
+            dependency
+                .imports
+                .sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+
+            if dependency.imports.is_empty() {
+                untracked.insert(dependency.name);
+            } else if dependency.is_covered() {
+                covered.push(dependency);
+            } else {
+                uncovered.push(dependency);
+            }
+        }
+
+        Ok(CoverageReport {
+            format,
+            files: files.len(),
+            covered,
+            uncovered,
+            untracked: untracked.into_iter().collect(),
+        })
+    }
+}
+
+/// Report paths are absolute, or relative to where the tests ran
+fn file_key(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| resolve::normalize(path))
+}
+
+/// `SF:` starts a file's record, `DA:<line>,<hits>` gives a line's hits
+fn lcov_files(content: &str) -> Vec<(PathBuf, FileCoverage)> {
+    let mut files = Vec::new();
+    let mut current: Option<(PathBuf, FileCoverage)> = None;
+    for line in content.lines().map(str::trim) {
+        if let Some(path) = line.strip_prefix("SF:") {
+            files.extend(current.take());
+            current = Some((PathBuf::from(path), FileCoverage::default()));
+        } else if let Some(data) = line.strip_prefix("DA:") {
+            let mut fields = data.split(',');
+            let (Some(number), Some(hits)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let (Ok(number), Ok(hits)) = (number.parse::<usize>(), hits.parse::<u64>()) else {
+                continue;
+            };
+            if let Some((_, file)) = &mut current {
+                file.statements.push((number, number, hits));
+            }
+        } else if line == "end_of_record" {
+            files.extend(current.take());
+        }
+    }
+    files.extend(current);
+    files
+}
+
+/// Each file's `statementMap` gives statement spans, `s` their hits
+fn istanbul_files(report: &serde_json::Map<String, JsonValue>) -> Vec<(PathBuf, FileCoverage)> {
+    report
+        .iter()
+        .map(|(path, file)| {
+            let hits = file.get("s").and_then(|s| s.as_object());
+            let statements = file
+                .get("statementMap")
+                .and_then(|m| m.as_object())
+                .into_iter()
+                .flatten()
+                .filter_map(|(id, span)| {
+                    let line = |at: &str| span.get(at)?.get("line")?.as_u64();
+                    let hits = hits?.get(id)?.as_u64()?;
+                    Some((line("start")? as usize, line("end")? as usize, hits))
+                })
+                .collect();
+            (PathBuf::from(path), FileCoverage { statements })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::types::{Import, ImportKind};
+
+    #[test]
+    fn test_dependency_coverage() {
+        let root = TempDir::new("coverage");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        for file in ["app.js", "export.js", "legacy.js", "app.test.js"] {
+            std::fs::write(root.join("src").join(file), "").unwrap();
+        }
+        std::fs::write(
+            root.join("lcov.info"),
+            "TN:\nSF:src/app.js\nDA:1,4\nDA:8,0\nDA:9,0\nend_of_record\n\
+             SF:src/export.js\nDA:3,2\nend_of_record\n\
+             SF:src/legacy.js\nDA:2,0\nDA:5,0\nend_of_record\n",
+        )
+        .unwrap();
+        let istanbul = format!(
+            r#"{{"{}": {{
+                "statementMap": {{
+                    "0": {{"start": {{"line": 4, "column": 0}}, "end": {{"line": 10, "column": 1}}}},
+                    "1": {{"start": {{"line": 8, "column": 2}}, "end": {{"line": 8, "column": 30}}}}
+                }},
+                "s": {{"0": 1, "1": 0}}
+            }}}}"#,
+            root.join("src/app.js").display()
+        );
+        std::fs::write(root.join("coverage-final.json"), istanbul).unwrap();
+
+        let mut packages = HashMap::new();
+        for name in ["express", "csv-stringify", "moment", "left-pad", "vitest"] {
+            packages.insert(name.to_string(), Package::new(name, "1.0.0").direct());
+        }
+        packages.get_mut("vitest").unwrap().is_dev = true;
+
+        let mut imports = ImportMap::new();
+        for (file, line, package) in [
+            ("src/app.js", 1, "express"),
+            ("src/app.js", 8, "csv-stringify"),
+            ("src/export.js", 1, "csv-stringify"),
+            ("src/legacy.js", 1, "moment"),
+            ("src/app.test.js", 1, "left-pad"),
+            ("src/app.test.js", 2, "vitest"),
+        ] {
+            imports.add_import(Import {
+                file_path: root.join(file),
+                line,
+                specifier: package.to_string(),
+                kind: ImportKind::EsModule,
+                resolved_package: Some(package.to_string()),
+            });
+        }
+
+        let lcov = CoverageAnalyzer::new(&root, &root.join("lcov.info"))
+            .analyze(&packages, &imports)
+            .unwrap();
+        let istanbul = CoverageAnalyzer::new(&root, &root.join("coverage-final.json"))
+            .analyze(&packages, &imports)
+            .unwrap();
+
+        assert_eq!(lcov.format, CoverageFormat::Lcov);
+        assert_eq!(lcov.files, 3);
+        let names = |deps: &[DependencyCoverage]| -> Vec<String> {
+            deps.iter().map(|d| d.name.clone()).collect()
+        };
+        assert_eq!(names(&lcov.covered), vec!["csv-stringify", "express"]);
+        assert_eq!(lcov.covered[0].imports.len(), 2);
+        assert!(!lcov.covered[0].imports[0].covered);
+        assert_eq!(names(&lcov.uncovered), vec!["moment"]);
+        assert_eq!(lcov.untracked, vec!["left-pad"]);
+
+        assert_eq!(istanbul.format, CoverageFormat::Istanbul);
+        assert_eq!(names(&istanbul.covered), vec!["express"]);
+        assert_eq!(names(&istanbul.uncovered), vec!["csv-stringify"]);
+    }
+}
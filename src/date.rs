@@ -0,0 +1,121 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Days since the Unix epoch for today (UTC)
+pub fn today() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| (d.as_secs() / 86_400) as i64)
+        .unwrap_or(0)
+}
+
+/// Parse the date part of an RFC 3339 timestamp into days since the Unix epoch
+///
+/// Examples:
+/// - "1970-01-02T00:00:00.000Z" -> Some(1)
+/// - "2024-03-01" -> Some(19783)
+/// - "yesterday" -> None
+pub fn parse_days(timestamp: &str) -> Option<i64> {
+    let date = timestamp.get(..10)?;
+    let mut parts = date.split('-');
+
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    Some(days_from_civil(year, month, day))
+}
+
+/// Format days since the Unix epoch as YYYY-MM-DD
+pub fn format_days(days: i64) -> String {
+    let (year, month, day) = civil_from_days(days);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// The current time as an RFC 3339 UTC timestamp, to the second
+pub fn now_timestamp() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0);
+    format_timestamp(secs)
+}
+
+fn format_timestamp(secs: i64) -> String {
+    let time = secs.rem_euclid(86_400);
+    format!(
+        "{}T{:02}:{:02}:{:02}Z",
+        format_days(secs.div_euclid(86_400)),
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+/// Human-friendly age ("today", "12d ago", "5mo ago", "3y ago")
+pub fn format_age(days: i64) -> String {
+    match days {
+        d if d <= 0 => "today".to_string(),
+        d if d < 60 => format!("{}d ago", d),
+        d if d < 730 => format!("{}mo ago", d / 30),
+        d => format!("{}y ago", d / 365),
+    }
+}
+
+// Howard Hinnant's civil calendar algorithms (proleptic Gregorian)
+
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(parse_days("1970-01-01T00:00:00.000Z"), Some(0));
+        assert_eq!(parse_days("1970-01-02T00:00:00.000Z"), Some(1));
+        assert_eq!(parse_days("2024-03-01"), Some(19783));
+        assert_eq!(parse_days("yesterday"), None);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for days in [0, 59, 60, 365, 19783, 20000] {
+            assert_eq!(parse_days(&format_days(days)), Some(days));
+        }
+        assert_eq!(format_days(19783), "2024-03-01");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            format_timestamp(19783 * 86_400 + 3723),
+            "2024-03-01T01:02:03Z"
+        );
+    }
+}
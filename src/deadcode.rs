@@ -0,0 +1,194 @@
+use std::path::{Path, PathBuf};
+
+use miette::Result;
+
+use crate::analyzer::{is_config_file, is_framework_file, is_test_file, resolve};
+use crate::entry_points::EntryPointAnalyzer;
+use crate::types::{DeadCodeReport, ImportMap, UnresolvedImport};
+
+/// Entry points used when the config doesn't declare any
+const DEFAULT_ENTRY_POINTS: &[&str] = &["main", "exports", "bin"];
+
+/// Conventional entry files checked in addition to the package.json fields
+const CONVENTIONAL_ENTRIES: &[&str] = &["index", "src/index", "src/main", "src/cli", "src/app"];
+
+/// Finds source files that no entry point imports, directly or transitively
+pub struct DeadCodeAnalyzer<'a> {
+    root: &'a Path,
+    entry_points: &'a [String],
+}
+
+impl<'a> DeadCodeAnalyzer<'a> {
+    /// `entry_points` come from the config; an empty list falls back to
+    /// package.json `main`/`exports`/`bin` and conventional entry files
+    pub fn new(root: &'a Path, entry_points: &'a [String]) -> Self {
+        Self { root, entry_points }
+    }
+
+    pub fn analyze(&self, imports: &ImportMap) -> Result<DeadCodeReport> {
+        let default_specs: Vec<String>;
+        let specs = if self.entry_points.is_empty() {
+            default_specs = DEFAULT_ENTRY_POINTS.iter().map(|s| s.to_string()).collect();
+            &default_specs
+        } else {
+            self.entry_points
+        };
+
+        let mut roots: Vec<PathBuf> = EntryPointAnalyzer::new(self.root, specs)
+            .resolve()?
+            .into_iter()
+            .flat_map(|(_, files)| files)
+            .filter(|f| imports.is_file_analyzed(f))
+            .collect();
+
+        if self.entry_points.is_empty() {
+            roots.extend(
+                CONVENTIONAL_ENTRIES
+                    .iter()
+                    .filter_map(|entry| resolve::resolve_file(&self.root.join(entry)))
+                    .filter(|f| imports.is_file_analyzed(f)),
+            );
+        }
+
+        // Directories in the config (e.g. "pages") make every file in them an
+        // entry, as do files frameworks load without an import
+        let dirs: Vec<PathBuf> = specs
+            .iter()
+            .map(|spec| self.root.join(spec))
+            .filter(|path| path.is_dir())
+            .map(|path| resolve::normalize(&path))
+            .collect();
+
+        roots.extend(
+            imports
+                .analyzed_files()
+                .filter(|f| {
+                    is_config_file(f)
+                        || is_framework_file(f)
+                        || dirs.iter().any(|d| f.starts_with(d))
+                })
+                .cloned(),
+        );
+        roots.sort();
+        roots.dedup();
+
+        // Tests are loaded by their runner rather than imported; files only
+        // they reach are reported separately from fully unreachable ones
+        let tests: Vec<PathBuf> = imports
+            .analyzed_files()
+            .filter(|f| is_test_file(f))
+            .cloned()
+            .collect();
+
+        let reachable = imports.reachable_files(&roots);
+        let reachable_from_tests = imports.reachable_files(&tests);
+
+        let mut unreachable = Vec::new();
+        let mut test_only = Vec::new();
+        for file in imports.analyzed_files() {
+            // Declaration files are picked up by the compiler, not imported
+            if reachable.contains(file)
+                || is_test_file(file)
+                || file.to_string_lossy().ends_with(".d.ts")
+            {
+                continue;
+            }
+            if reachable_from_tests.contains(file) {
+                test_only.push(file.clone());
+            } else {
+                unreachable.push(file.clone());
+            }
+        }
+        unreachable.sort();
+        test_only.sort();
+
+        Ok(DeadCodeReport {
+            entry_points: roots
+                .into_iter()
+                .filter(|f| !is_config_file(f) && !is_framework_file(f))
+                .collect(),
+            unreachable,
+            test_only,
+            unresolved: imports
+                .unresolved_local_imports()
+                .into_iter()
+                .map(|(file, import)| UnresolvedImport {
+                    file: file.clone(),
+                    line: import.line,
+                    specifier: import.specifier.clone(),
+                })
+                .collect(),
+            total_files: imports.files_analyzed(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::ImportAnalyzer;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_unreachable_files() {
+        let root = TempDir::new("deadcode");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+
+        std::fs::write(root.join("package.json"), r#"{"main": "src/index.ts"}"#).unwrap();
+        std::fs::write(root.join("src/index.ts"), "export * from './used';\n").unwrap();
+        std::fs::write(
+            root.join("src/used.ts"),
+            "import './nested/index.js';\nimport './gone';\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.join("src/nested")).unwrap();
+        std::fs::write(root.join("src/nested/index.ts"), "export const x = 1;\n").unwrap();
+        std::fs::write(root.join("src/old.ts"), "export const y = 2;\n").unwrap();
+        std::fs::write(root.join("src/old.test.ts"), "import { y } from './old';\n").unwrap();
+        std::fs::write(root.join("src/orphan.ts"), "export const z = 3;\n").unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+        let report = DeadCodeAnalyzer::new(&root, &[]).analyze(&imports).unwrap();
+
+        assert_eq!(report.total_files, 6);
+        assert_eq!(report.unreachable, vec![root.join("src/orphan.ts")]);
+        assert_eq!(report.test_only, vec![root.join("src/old.ts")]);
+        assert_eq!(report.entry_points, vec![root.join("src/index.ts")]);
+        assert_eq!(
+            report.unresolved,
+            vec![UnresolvedImport {
+                file: root.join("src/used.ts"),
+                line: 2,
+                specifier: "./gone".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_framework_files_are_roots() {
+        let root = TempDir::new("deadcode-routes");
+        std::fs::create_dir_all(root.join("src/routes")).unwrap();
+
+        std::fs::write(root.join("package.json"), r#"{"main": "src/index.ts"}"#).unwrap();
+        std::fs::write(root.join("src/index.ts"), "export const x = 1;\n").unwrap();
+        std::fs::write(
+            root.join("src/routes/+page.svelte"),
+            "<script>\nimport { format } from '../format';\n</script>\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/format.ts"),
+            "export const format = String;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/about.mdx"), "# About\n").unwrap();
+        std::fs::write(root.join("src/global.css"), "body { margin: 0; }\n").unwrap();
+        std::fs::write(root.join("src/orphan.ts"), "export const z = 3;\n").unwrap();
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+        let report = DeadCodeAnalyzer::new(&root, &[]).analyze(&imports).unwrap();
+
+        assert_eq!(report.unreachable, vec![root.join("src/orphan.ts")]);
+        assert_eq!(report.entry_points, vec![root.join("src/index.ts")]);
+    }
+}
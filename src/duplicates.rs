@@ -1,11 +1,21 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
 
 use miette::{bail, Result};
-use semver::Version;
 
-use crate::lockfile::{CargoLockfileParser, LockfileParser, LockfileType};
+use crate::analyzer::rust::RustUseScanner;
+use crate::clean::disk_size;
+use crate::integrity::home_dir;
+use crate::lockfile::{
+    module_cache, module_path, module_source_dir, registry_source_dir, BunLockfileParser,
+    CargoLockfileParser, DenoLockfileParser, GoModParser, LockfileParser, LockfileType,
+    PnpmLockfileParser,
+};
+use crate::range::{parse_version, satisfies};
+use crate::rules::Rule;
 use crate::types::{
-    DuplicateAnalysis, DuplicateGroup, DuplicateSeverity, DuplicateStats, DuplicateVersion,
+    DuplicateAnalysis, DuplicateCluster, DuplicateGroup, DuplicateSeverity, DuplicateStats,
+    DuplicateVersion, ScoreFactor, SeverityScore, TypesSkew,
 };
 
 /// Analyzer for detecting duplicate dependencies
@@ -19,14 +29,30 @@ impl<'a> DuplicateAnalyzer<'a> {
     }
 
     /// Analyze the project for duplicate dependencies
+    #[allow(dead_code)]
     pub fn analyze(&self) -> Result<DuplicateAnalysis> {
-        let lockfile_parser = LockfileParser::new(self.root)?;
+        self.analyze_lockfile(&LockfileParser::new(self.root)?)
+    }
+
+    /// Analyze one of the project's lockfiles for duplicate dependencies
+    ///
+    /// Manifests and installed packages are read next to the lockfile,
+    /// which may be a workspace root above the project.
+    pub fn analyze_lockfile(&self, lockfile_parser: &LockfileParser) -> Result<DuplicateAnalysis> {
+        let analyzer = DuplicateAnalyzer::new(lockfile_parser.root());
+        analyzer.analyze_detected(lockfile_parser)
+    }
 
+    fn analyze_detected(&self, lockfile_parser: &LockfileParser) -> Result<DuplicateAnalysis> {
         match lockfile_parser.lockfile_type() {
             LockfileType::Cargo => self.analyze_cargo(lockfile_parser.lockfile_path()),
             LockfileType::Npm => self.analyze_npm(lockfile_parser.lockfile_path()),
+            LockfileType::Pnpm => self.analyze_pnpm(lockfile_parser.lockfile_path()),
+            LockfileType::Bun => self.analyze_bun(lockfile_parser.lockfile_path()),
+            LockfileType::Deno => self.analyze_deno(lockfile_parser.lockfile_path()),
+            LockfileType::Go => self.analyze_go(lockfile_parser.lockfile_path()),
             _ => {
-                bail!("Duplicate analysis currently only supports Cargo.lock and package-lock.json")
+                bail!("Duplicate analysis currently only supports Cargo.lock, package-lock.json, pnpm-lock.yaml, bun.lock, deno.lock and go.mod")
             }
         }
     }
@@ -35,23 +61,97 @@ impl<'a> DuplicateAnalyzer<'a> {
     fn analyze_cargo(&self, lockfile_path: &Path) -> Result<DuplicateAnalysis> {
         let parser = CargoLockfileParser::new(lockfile_path);
         let packages_by_name = parser.parse_for_duplicates()?;
-        self.analyze_generic(packages_by_name)
+        let context = ScoreContext {
+            public_api: Some(RustUseScanner::new(self.root).public_crates(&parser.parse()?)?),
+            layout: InstallLayout::CargoRegistry,
+            root: self.root,
+        };
+        self.analyze_generic(packages_by_name, &context)
     }
 
     /// Analyze package-lock.json for duplicates
     fn analyze_npm(&self, lockfile_path: &Path) -> Result<DuplicateAnalysis> {
         let parser = crate::lockfile::NpmLockfileParser::new(self.root, lockfile_path);
         let packages_by_name = parser.parse_for_duplicates()?;
-        self.analyze_generic(packages_by_name)
+        let types_skew = find_types_skew(&packages_by_name);
+
+        let context = ScoreContext::node_modules(self.root);
+        let mut analysis = self.analyze_generic(packages_by_name, &context)?;
+        analysis.types_skew = types_skew;
+        Ok(analysis)
+    }
+
+    /// Analyze pnpm-lock.yaml for duplicates and peer-dependency splits
+    fn analyze_pnpm(&self, lockfile_path: &Path) -> Result<DuplicateAnalysis> {
+        let parser = PnpmLockfileParser::new(self.root, lockfile_path);
+        let packages_by_name = parser.parse_for_duplicates()?;
+        let types_skew = find_types_skew(&packages_by_name);
+
+        let context = ScoreContext {
+            public_api: None,
+            layout: InstallLayout::PnpmStore,
+            root: self.root,
+        };
+        let mut analysis = self.analyze_generic(packages_by_name, &context)?;
+        analysis.types_skew = types_skew;
+        analysis.peer_splits = parser.parse_peer_splits()?;
+        Ok(analysis)
+    }
+
+    /// Analyze bun.lock for duplicates
+    fn analyze_bun(&self, lockfile_path: &Path) -> Result<DuplicateAnalysis> {
+        let parser = BunLockfileParser::new(self.root, lockfile_path);
+        let packages_by_name = parser.parse_for_duplicates()?;
+        let types_skew = find_types_skew(&packages_by_name);
+
+        let context = ScoreContext::node_modules(self.root);
+        let mut analysis = self.analyze_generic(packages_by_name, &context)?;
+        analysis.types_skew = types_skew;
+        Ok(analysis)
+    }
+
+    /// Analyze deno.lock for duplicates
+    fn analyze_deno(&self, lockfile_path: &Path) -> Result<DuplicateAnalysis> {
+        let parser = DenoLockfileParser::new(self.root, lockfile_path);
+        let packages_by_name = parser.parse_for_duplicates()?;
+        let types_skew = find_types_skew(&packages_by_name);
+
+        // npm packages live in Deno's cache, not in the project
+        let context = ScoreContext {
+            public_api: None,
+            layout: InstallLayout::DenoCache,
+            root: self.root,
+        };
+        let mut analysis = self.analyze_generic(packages_by_name, &context)?;
+        analysis.types_skew = types_skew;
+        Ok(analysis)
+    }
+
+    /// Analyze go.mod for modules built at several major versions
+    fn analyze_go(&self, lockfile_path: &Path) -> Result<DuplicateAnalysis> {
+        let parser = GoModParser::new(self.root, lockfile_path);
+        let packages_by_name = parser.parse_for_duplicates()?;
+        let context = ScoreContext {
+            public_api: None,
+            layout: InstallLayout::GoModCache,
+            root: self.root,
+        };
+        self.analyze_generic(packages_by_name, &context)
     }
 
     fn analyze_generic(
         &self,
         packages_by_name: std::collections::HashMap<String, Vec<crate::lockfile::CargoPackageInfo>>,
+        context: &ScoreContext,
     ) -> Result<DuplicateAnalysis> {
         let mut duplicates = Vec::new();
 
         for (name, versions) in &packages_by_name {
+            // Path dependencies and workspace members are local code, even
+            // when a published copy of the same name is installed too
+            let versions: Vec<&crate::lockfile::CargoPackageInfo> =
+                versions.iter().filter(|v| !v.is_path_dep).collect();
+
             // Skip if only one version exists
             if versions.len() <= 1 {
                 continue;
@@ -69,6 +169,7 @@ impl<'a> DuplicateAnalyzer<'a> {
                         version: v.version.clone(),
                         dependents: v.dependents.clone(),
                         transitive_count,
+                        requirements: v.requirements.clone().into_iter().collect(),
                     }
                 })
                 .collect();
@@ -76,45 +177,260 @@ impl<'a> DuplicateAnalyzer<'a> {
             // Sort versions for consistent output
             version_infos.sort_by(|a, b| compare_versions(&a.version, &b.version));
 
-            // Calculate severity
-            let severity = calculate_severity(&version_infos);
+            let score = score_duplicate(name, &version_infos, context);
+            let singleton = context
+                .layout
+                .is_npm()
+                .then(|| singleton_symptoms(name))
+                .flatten();
 
             duplicates.push(DuplicateGroup {
                 name: name.clone(),
+                rule: duplicate_rule(&version_infos, singleton.is_some()),
                 versions: version_infos,
-                severity,
+                severity: if singleton.is_some() {
+                    DuplicateSeverity::Critical
+                } else {
+                    severity_for(score.points)
+                },
+                score,
+                singleton: singleton.map(str::to_string),
+                owners: Vec::new(),
             });
         }
 
-        // Sort by severity (high first), then by name
-        duplicates.sort_by(|a, b| {
-            b.severity
-                .cmp(&a.severity)
-                .then_with(|| a.name.cmp(&b.name))
-        });
+        Ok(summarize(duplicates))
+    }
+}
 
-        // Calculate stats
-        let stats = DuplicateStats {
-            total_duplicates: duplicates.len(),
-            high_severity: duplicates
-                .iter()
-                .filter(|d| d.severity == DuplicateSeverity::High)
-                .count(),
-            medium_severity: duplicates
-                .iter()
-                .filter(|d| d.severity == DuplicateSeverity::Medium)
-                .count(),
-            low_severity: duplicates
-                .iter()
-                .filter(|d| d.severity == DuplicateSeverity::Low)
-                .count(),
-            extra_compile_units: duplicates.iter().map(|d| d.versions.len() - 1).sum(),
+/// Combine the analyses of several lockfiles, e.g. Cargo.lock and
+/// package-lock.json, into one report
+pub fn merge(analyses: Vec<DuplicateAnalysis>) -> DuplicateAnalysis {
+    let mut duplicates = Vec::new();
+    let mut types_skew = Vec::new();
+    let mut peer_splits = Vec::new();
+    for analysis in analyses {
+        duplicates.extend(analysis.duplicates);
+        types_skew.extend(analysis.types_skew);
+        peer_splits.extend(analysis.peer_splits);
+    }
+
+    let mut merged = summarize(duplicates);
+    merged.types_skew = types_skew;
+    merged.peer_splits = peer_splits;
+    merged
+}
+
+/// Drop the findings of `disabled` rules, recounting what's left
+pub fn suppress(analysis: DuplicateAnalysis, disabled: &HashSet<Rule>) -> DuplicateAnalysis {
+    let mut suppressed = summarize(
+        analysis
+            .duplicates
+            .into_iter()
+            .filter(|group| !disabled.contains(&group.rule))
+            .collect(),
+    );
+    if !disabled.contains(&Rule::TypesSkew) {
+        suppressed.types_skew = analysis.types_skew;
+    }
+    if !disabled.contains(&Rule::PeerSplit) {
+        suppressed.peer_splits = analysis.peer_splits;
+    }
+    suppressed
+}
+
+/// Sort duplicate groups and count them, with clusters
+fn summarize(mut duplicates: Vec<DuplicateGroup>) -> DuplicateAnalysis {
+    // Sort by severity (critical first), then by name
+    duplicates.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    // Calculate stats
+    let stats = DuplicateStats {
+        total_duplicates: duplicates.len(),
+        critical_severity: duplicates
+            .iter()
+            .filter(|d| d.severity == DuplicateSeverity::Critical)
+            .count(),
+        high_severity: duplicates
+            .iter()
+            .filter(|d| d.severity == DuplicateSeverity::High)
+            .count(),
+        medium_severity: duplicates
+            .iter()
+            .filter(|d| d.severity == DuplicateSeverity::Medium)
+            .count(),
+        low_severity: duplicates
+            .iter()
+            .filter(|d| d.severity == DuplicateSeverity::Low)
+            .count(),
+        extra_compile_units: duplicates.iter().map(|d| d.versions.len() - 1).sum(),
+    };
+
+    DuplicateAnalysis {
+        clusters: find_clusters(&duplicates),
+        duplicates,
+        stats,
+        types_skew: Vec::new(),
+        peer_splits: Vec::new(),
+    }
+}
+
+/// Groups sharing a scope or name prefix with fewer members stay on their own line
+const MIN_CLUSTER_SIZE: usize = 3;
+
+/// Cluster duplicate groups by npm scope or by the name prefix before the
+/// first `-`/`_` (e.g. `@babel/*`, `babel-*`, `windows-*`)
+///
+/// `-` and `_` are the same in crate names, so `windows_x86_64_msvc` and
+/// `windows-sys` share a cluster.
+///
+/// Toolchains release their packages together, so their duplicates tend to
+/// come and go as a set and read better as one line.
+pub fn find_clusters(duplicates: &[DuplicateGroup]) -> Vec<DuplicateCluster> {
+    let mut by_key: std::collections::BTreeMap<String, Vec<&DuplicateGroup>> =
+        std::collections::BTreeMap::new();
+    for group in duplicates {
+        if let Some(key) = cluster_key(&group.name) {
+            by_key.entry(key).or_default().push(group);
+        }
+    }
+
+    by_key
+        .into_iter()
+        .filter(|(_, groups)| groups.len() >= MIN_CLUSTER_SIZE)
+        .map(|(name, groups)| {
+            let mut packages: Vec<String> = groups.iter().map(|g| g.name.clone()).collect();
+            packages.sort();
+            DuplicateCluster {
+                name,
+                packages,
+                severity: groups
+                    .iter()
+                    .map(|g| g.severity)
+                    .max()
+                    .unwrap_or(DuplicateSeverity::Low),
+                version_count: groups.iter().map(|g| g.versions.len()).sum(),
+                transitive_count: groups
+                    .iter()
+                    .flat_map(|g| &g.versions)
+                    .map(|v| v.transitive_count)
+                    .sum(),
+            }
+        })
+        .collect()
+}
+
+/// "@babel/core" -> "@babel/*", "babel_runtime" -> "babel-*", "serde" -> None,
+/// "golang.org/x/net" -> "golang.org/x/*"
+fn cluster_key(name: &str) -> Option<String> {
+    if let Some(scope) = name.strip_prefix('@') {
+        let (scope, _) = scope.split_once('/')?;
+        return Some(format!("@{}/*", scope));
+    }
+    // Go modules cluster by host and owner
+    let mut segments = name.splitn(3, '/');
+    if let (Some(host), Some(owner), Some(_)) = (segments.next(), segments.next(), segments.next())
+    {
+        return Some(format!("{}/{}/*", host, owner));
+    }
+    let end = name.find(['-', '_'])?;
+    (end > 0).then(|| format!("{}-*", &name[..end]))
+}
+
+/// Find `@types/*` packages built for a different release line than the
+/// installed runtime package
+///
+/// DefinitelyTyped versions track the library's major version (and minor for
+/// 0.x libraries), so a mismatch there usually means stale or too-new types.
+fn find_types_skew(
+    packages_by_name: &std::collections::HashMap<String, Vec<crate::lockfile::CargoPackageInfo>>,
+) -> Vec<TypesSkew> {
+    let mut skew = Vec::new();
+
+    for (types_name, types_versions) in packages_by_name {
+        let Some(runtime_name) = runtime_package_for_types(types_name) else {
+            continue;
+        };
+        let Some(runtime_versions) = packages_by_name.get(&runtime_name) else {
+            continue;
         };
 
-        Ok(DuplicateAnalysis { duplicates, stats })
+        for runtime in runtime_versions {
+            let Some(runtime_line) = release_line(&runtime.version) else {
+                continue;
+            };
+
+            // Any @types copy matching this runtime version is enough
+            let lines: Vec<_> = types_versions
+                .iter()
+                .filter_map(|t| Some((t, release_line(&t.version)?)))
+                .collect();
+            if lines.is_empty() || lines.iter().any(|(_, line)| *line == runtime_line) {
+                continue;
+            }
+
+            for (types, _) in lines {
+                skew.push(TypesSkew {
+                    package: runtime_name.clone(),
+                    package_version: runtime.version.clone(),
+                    types_package: types_name.clone(),
+                    types_version: types.version.clone(),
+                    suggested_range: match runtime_line {
+                        (0, minor) => format!("~0.{}", minor),
+                        (major, _) => format!("^{}", major),
+                    },
+                });
+            }
+        }
+    }
+
+    skew.sort_by(|a, b| {
+        a.package
+            .cmp(&b.package)
+            .then_with(|| compare_versions(&a.package_version, &b.package_version))
+    });
+    skew
+}
+
+/// "@types/lodash" -> "lodash", "@types/babel__core" -> "@babel/core"
+fn runtime_package_for_types(name: &str) -> Option<String> {
+    let bare = name.strip_prefix("@types/")?;
+    Some(match bare.split_once("__") {
+        Some((scope, pkg)) => format!("@{}/{}", scope, pkg),
+        None => bare.to_string(),
+    })
+}
+
+/// The rule a duplicate falls under: a singleton's copies, releases that
+/// aren't semver-compatible, or several versions of one release line
+fn duplicate_rule(versions: &[DuplicateVersion], singleton: bool) -> Rule {
+    let lines: HashSet<(u64, u64)> = versions
+        .iter()
+        .filter_map(|v| release_line(&v.version))
+        .collect();
+    if singleton {
+        Rule::DuplicateSingleton
+    } else if lines.len() > 1 {
+        Rule::DuplicateMajor
+    } else {
+        Rule::DuplicateVersion
     }
 }
 
+/// Major version, plus the minor for 0.x releases where minors break
+pub fn release_line(version: &str) -> Option<(u64, u64)> {
+    let parsed = parse_version(version)?;
+    Some(if parsed.major == 0 {
+        (0, parsed.minor)
+    } else {
+        (parsed.major, 0)
+    })
+}
+
 /// Calculate the number of transitive dependents for a package version
 fn calculate_transitive_dependents(
     package_key: &str,
@@ -162,73 +478,343 @@ fn calculate_transitive_dependents(
 
 /// Compare two version strings, handling semver and non-semver
 fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
-    match (Version::parse(a), Version::parse(b)) {
-        (Ok(va), Ok(vb)) => va.cmp(&vb),
+    match (parse_version(a), parse_version(b)) {
+        (Some(va), Some(vb)) => va.cmp(&vb),
         _ => a.cmp(b),
     }
 }
 
-/// Calculate severity based on version differences
-fn calculate_severity(versions: &[DuplicateVersion]) -> DuplicateSeverity {
-    if versions.len() >= 3 {
-        // 3+ versions is always high severity
-        return DuplicateSeverity::High;
+/// Points at which a duplicate becomes medium or high severity
+const MEDIUM_SEVERITY_POINTS: u32 = 3;
+const HIGH_SEVERITY_POINTS: u32 = 6;
+
+/// Points per major version beyond the first, and for a crate in the public API
+const MAJOR_VERSION_POINTS: u32 = 3;
+const PUBLIC_API_POINTS: u32 = 3;
+
+/// Transitive dependents at which a duplicate scores 1 and 2 points
+const DEPENDENT_THRESHOLDS: (usize, usize) = (5, 20);
+
+/// Bytes taken by the extra copies at which a duplicate scores 1 and 2 points
+const SIZE_THRESHOLDS: (u64, u64) = (1024 * 1024, 5 * 1024 * 1024);
+
+/// What scoring knows beyond the lockfile
+struct ScoreContext<'a> {
+    /// Crates the workspace re-exports (Cargo); `None` where it doesn't apply
+    public_api: Option<HashSet<String>>,
+
+    /// Where installed copies live, to measure their size
+    layout: InstallLayout,
+
+    root: &'a Path,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InstallLayout {
+    /// `node_modules/<name>` (npm, bun); copies of other versions are nested
+    /// wherever their dependents are, so the hoisted copy stands in for all
+    NodeModules,
+    /// `node_modules/.pnpm/<name>@<version>/node_modules/<name>`
+    PnpmStore,
+    /// Unpacked sources under `$CARGO_HOME/registry/src/<index>/<name>-<version>`
+    CargoRegistry,
+    /// `$DENO_DIR/npm/registry.npmjs.org/<name>/<version>`
+    DenoCache,
+    /// `$GOMODCACHE/<module path>@<version>`, the path carrying the major
+    /// version suffix the duplicate group drops
+    GoModCache,
+}
+
+impl InstallLayout {
+    /// Layouts of npm packages, as opposed to crates and Go modules
+    fn is_npm(self) -> bool {
+        matches!(
+            self,
+            InstallLayout::NodeModules | InstallLayout::PnpmStore | InstallLayout::DenoCache
+        )
+    }
+}
+
+impl<'a> ScoreContext<'a> {
+    fn node_modules(root: &'a Path) -> Self {
+        Self {
+            public_api: None,
+            layout: InstallLayout::NodeModules,
+            root,
+        }
+    }
+
+    /// Size of one installed copy, when it's on disk
+    fn copy_size(&self, name: &str, version: &str) -> Option<u64> {
+        match self.layout {
+            InstallLayout::NodeModules => disk_size(&self.root.join("node_modules").join(name)),
+            InstallLayout::PnpmStore => disk_size(
+                &self
+                    .root
+                    .join("node_modules/.pnpm")
+                    .join(format!("{}@{}", name.replace('/', "+"), version))
+                    .join("node_modules")
+                    .join(name),
+            ),
+            InstallLayout::CargoRegistry => disk_size(&registry_source_dir(name, version)?),
+            InstallLayout::DenoCache => disk_size(
+                &deno_dir()?
+                    .join("npm/registry.npmjs.org")
+                    .join(name)
+                    .join(version),
+            ),
+            InstallLayout::GoModCache => disk_size(&module_source_dir(
+                &module_cache()?,
+                &module_path(name, version),
+                version,
+            )),
+        }
     }
+}
 
-    // Parse major versions
-    let major_versions: Vec<u64> = versions
+/// Deno's cache directory: `$DENO_DIR`, else the platform cache directory
+fn deno_dir() -> Option<PathBuf> {
+    if let Some(dir) = std::env::var_os("DENO_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+    let cache = if cfg!(target_os = "macos") {
+        "Library/Caches"
+    } else {
+        ".cache"
+    };
+    Some(home_dir()?.join(cache).join("deno"))
+}
+
+/// Score a duplicate on how likely it is to cause trouble and how much it costs
+///
+/// - major versions: 3 points for every major version past the first, each
+///   0.x minor counting as its own, as in `release_line`
+/// - versions: 1 point for 3 or more copies
+/// - public API (Cargo): 3 points when the workspace re-exports a crate
+///   installed with several major versions, since callers can't mix its types
+/// - dependents: 1 point from 5 transitive dependents, 2 from 20
+/// - size: 1 point when the extra copies take 1 MB on disk, 2 from 5 MB
+fn score_duplicate(
+    name: &str,
+    versions: &[DuplicateVersion],
+    context: &ScoreContext,
+) -> SeverityScore {
+    let mut factors = Vec::new();
+    let mut add = |factor: &str, detail: String, points: u32| {
+        factors.push(ScoreFactor {
+            factor: factor.to_string(),
+            detail,
+            points,
+        });
+    };
+
+    let mut lines: Vec<(u64, u64)> = versions
         .iter()
-        .filter_map(|v| Version::parse(&v.version).ok())
-        .map(|v| v.major)
+        .filter_map(|v| release_line(&v.version))
         .collect();
+    lines.sort_unstable();
+    lines.dedup();
+    let listed: Vec<String> = lines
+        .iter()
+        .map(|(major, minor)| match major {
+            0 => format!("0.{}", minor),
+            _ => major.to_string(),
+        })
+        .collect();
+    let extra_majors = lines.len().saturating_sub(1) as u32;
+    add(
+        "major_versions",
+        match lines.len() {
+            0 => "no semver versions".to_string(),
+            1 => format!("1 major version ({})", listed[0]),
+            n => format!("{} major versions ({})", n, listed.join(", ")),
+        },
+        extra_majors * MAJOR_VERSION_POINTS,
+    );
+
+    add(
+        "versions",
+        format!("{} versions", versions.len()),
+        u32::from(versions.len() >= 3),
+    );
 
-    if major_versions.is_empty() {
-        return DuplicateSeverity::Low;
+    if let Some(public_api) = &context.public_api {
+        let public = public_api.contains(name);
+        add(
+            "public_api",
+            if public {
+                "re-exported by the workspace".to_string()
+            } else {
+                "not re-exported".to_string()
+            },
+            if public && extra_majors > 0 {
+                PUBLIC_API_POINTS
+            } else {
+                0
+            },
+        );
     }
 
-    // Check if all major versions are the same
-    let first_major = major_versions[0];
-    let all_same_major = major_versions.iter().all(|&m| m == first_major);
+    let dependents: usize = versions.iter().map(|v| v.transitive_count).sum();
+    add(
+        "dependents",
+        format!("{} transitive dependents", dependents),
+        match dependents {
+            n if n >= DEPENDENT_THRESHOLDS.1 => 2,
+            n if n >= DEPENDENT_THRESHOLDS.0 => 1,
+            _ => 0,
+        },
+    );
 
-    if all_same_major {
-        DuplicateSeverity::Low
-    } else {
-        DuplicateSeverity::Medium
+    let sizes: Vec<u64> = versions
+        .iter()
+        .filter_map(|v| context.copy_size(name, &v.version))
+        .collect();
+    if !sizes.is_empty() {
+        let per_copy = sizes.iter().sum::<u64>() / sizes.len() as u64;
+        let extra = per_copy * (versions.len() as u64 - 1);
+        add(
+            "size",
+            format!("~{} KB in extra copies", extra / 1024),
+            match extra {
+                n if n >= SIZE_THRESHOLDS.1 => 2,
+                n if n >= SIZE_THRESHOLDS.0 => 1,
+                _ => 0,
+            },
+        );
     }
+
+    SeverityScore {
+        points: factors.iter().map(|f| f.points).sum(),
+        factors,
+    }
+}
+
+/// npm packages that keep module-level state or check `instanceof`
+/// against their own classes, so two copies in one app break it, with what
+/// that looks like
+const SINGLETON_PACKAGES: &[(&str, &str)] = &[
+    (
+        "react",
+        "hooks throw \"Invalid hook call\" and context providers don't reach consumers rendered by the other copy",
+    ),
+    (
+        "react-dom",
+        "hooks throw \"Invalid hook call\" and each copy renders its own root, ignoring the other's updates",
+    ),
+    (
+        "vue",
+        "components built with one copy don't see plugins, provide/inject or reactivity of the other",
+    ),
+    (
+        "@angular/core",
+        "dependency injection fails with \"No provider for ...\" across copies",
+    ),
+    (
+        "graphql",
+        "schema `instanceof` checks fail with \"Cannot use GraphQLSchema from another module or realm\"",
+    ),
+    (
+        "rxjs",
+        "Observables from one copy fail `instanceof Observable` in the other, and operators or type checks reject them",
+    ),
+    (
+        "styled-components",
+        "themes and the style sheet aren't shared, so components render unstyled or out of order",
+    ),
+    (
+        "mobx",
+        "observables of one copy aren't tracked by reactions of the other, so views stop updating",
+    ),
+];
+
+/// Runtime symptoms of duplicating `name`, for packages that must be singletons
+fn singleton_symptoms(name: &str) -> Option<&'static str> {
+    SINGLETON_PACKAGES
+        .iter()
+        .find(|(package, _)| *package == name)
+        .map(|(_, symptoms)| *symptoms)
 }
 
-/// Suggest which version to upgrade to
-pub fn suggest_resolution(group: &DuplicateGroup) -> Option<String> {
-    if group.versions.is_empty() {
-        return None;
+fn severity_for(points: u32) -> DuplicateSeverity {
+    if points >= HIGH_SEVERITY_POINTS {
+        DuplicateSeverity::High
+    } else if points >= MEDIUM_SEVERITY_POINTS {
+        DuplicateSeverity::Medium
+    } else {
+        DuplicateSeverity::Low
     }
+}
 
-    // Find the newest version
-    let newest = group.versions.last()?;
+/// Suggest how to bring the older copies onto the newest version
+///
+/// Dependents whose declared range already allows the newest version are
+/// only held back by the lockfile. Ranges that exclude it can't be unified
+/// by refreshing; those need an override or a release of the dependent that
+/// widens its range. Dependents with no known range get the plain hint.
+pub fn suggest_resolution(group: &DuplicateGroup) -> Vec<String> {
+    let Some(newest) = group.versions.last() else {
+        return Vec::new();
+    };
 
-    // Find dependents that are using older versions
-    let outdated_dependents: Vec<&str> = group
+    let mut feasible = Vec::new();
+    let mut blocked = Vec::new();
+    let mut unknown = Vec::new();
+    for version in group
         .versions
         .iter()
         .filter(|v| v.version != newest.version)
-        .flat_map(|v| v.dependents.iter().map(|s| s.as_str()))
-        .collect();
-
-    if outdated_dependents.is_empty() {
-        return None;
+    {
+        for dependent in &version.dependents {
+            let allows = version
+                .requirements
+                .get(dependent)
+                .and_then(|range| Some((range, satisfies(range, &newest.version)?)));
+            match allows {
+                Some((_, true)) => feasible.push(dependent.as_str()),
+                Some((range, false)) => blocked.push(format!("{} ({})", dependent, range)),
+                None => unknown.push(dependent.as_str()),
+            }
+        }
     }
 
-    Some(format!(
-        "Update {} to use {} {}",
-        outdated_dependents.join(", "),
-        group.name,
-        newest.version
-    ))
+    let mut suggestions = Vec::new();
+    if !feasible.is_empty() {
+        suggestions.push(format!(
+            "Refresh the lockfile to move {} onto {} {}, already in range",
+            feasible.join(", "),
+            group.name,
+            newest.version
+        ));
+    }
+    if !blocked.is_empty() {
+        suggestions.push(format!(
+            "{} can't take {} {}: add an override or ask upstream to widen the range",
+            blocked.join(", "),
+            group.name,
+            newest.version
+        ));
+    }
+    if !unknown.is_empty() {
+        suggestions.push(format!(
+            "Update {} to use {} {}",
+            unknown.join(", "),
+            group.name,
+            newest.version
+        ));
+    }
+    suggestions
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn severity(versions: &[DuplicateVersion]) -> DuplicateSeverity {
+        let context = ScoreContext::node_modules(Path::new("/nonexistent"));
+        severity_for(score_duplicate("pkg", versions, &context).points)
+    }
 
     #[test]
     fn test_severity_same_major() {
@@ -237,15 +823,19 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
             DuplicateVersion {
                 version: "1.2.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
         ];
 
-        assert_eq!(calculate_severity(&versions), DuplicateSeverity::Low);
+        assert_eq!(severity(&versions), DuplicateSeverity::Low);
+        assert_eq!(duplicate_rule(&versions, false), Rule::DuplicateVersion);
+        assert_eq!(duplicate_rule(&versions, true), Rule::DuplicateSingleton);
     }
 
     #[test]
@@ -255,15 +845,24 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
             DuplicateVersion {
                 version: "2.0.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
         ];
 
-        assert_eq!(calculate_severity(&versions), DuplicateSeverity::Medium);
+        assert_eq!(severity(&versions), DuplicateSeverity::Medium);
+        assert_eq!(duplicate_rule(&versions, false), Rule::DuplicateMajor);
+
+        // 0.x minors aren't semver-compatible either
+        let mut zero = versions.clone();
+        zero[0].version = "0.3.1".to_string();
+        zero[1].version = "0.4.0".to_string();
+        assert_eq!(duplicate_rule(&zero, false), Rule::DuplicateMajor);
     }
 
     #[test]
@@ -273,20 +872,75 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
             DuplicateVersion {
                 version: "1.1.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
             DuplicateVersion {
                 version: "1.2.0".to_string(),
                 dependents: vec![],
                 transitive_count: 0,
+                requirements: BTreeMap::new(),
             },
         ];
 
-        assert_eq!(calculate_severity(&versions), DuplicateSeverity::High);
+        // A patch or minor spread is cheap however many copies there are
+        assert_eq!(severity(&versions), DuplicateSeverity::Low);
+    }
+
+    #[test]
+    fn test_severity_score() {
+        let version = |version: &str, transitive_count: usize| DuplicateVersion {
+            version: version.to_string(),
+            dependents: vec![],
+            transitive_count,
+            requirements: BTreeMap::new(),
+        };
+
+        let versions = vec![
+            version("1.3.2", 4),
+            version("2.6.0", 4),
+            version("3.0.0", 4),
+        ];
+        let context = ScoreContext::node_modules(Path::new("/nonexistent"));
+        let score = score_duplicate("bitflags", &versions, &context);
+        let points: Vec<(&str, u32)> = score
+            .factors
+            .iter()
+            .map(|f| (f.factor.as_str(), f.points))
+            .collect();
+        assert_eq!(
+            points,
+            vec![("major_versions", 6), ("versions", 1), ("dependents", 1)]
+        );
+        assert_eq!(score.factors[0].detail, "3 major versions (1, 2, 3)");
+        assert_eq!(severity_for(score.points), DuplicateSeverity::High);
+
+        // Two majors are medium, unless the workspace re-exports the crate
+        let versions = vec![version("1.0.69", 1), version("2.0.17", 1)];
+        let mut context = ScoreContext {
+            public_api: Some(HashSet::new()),
+            ..ScoreContext::node_modules(Path::new("/nonexistent"))
+        };
+        let score = score_duplicate("thiserror", &versions, &context);
+        assert_eq!(severity_for(score.points), DuplicateSeverity::Medium);
+        context.public_api = Some(HashSet::from(["thiserror".to_string()]));
+        let score = score_duplicate("thiserror", &versions, &context);
+        assert_eq!(severity_for(score.points), DuplicateSeverity::High);
+
+        // 0.x minors are breaking releases of their own
+        let versions = vec![
+            version("0.3.1", 1),
+            version("0.4.0", 1),
+            version("0.4.2", 1),
+        ];
+        let score = score_duplicate("rand", &versions, &context);
+        assert_eq!(score.factors[0].points, MAJOR_VERSION_POINTS);
+        assert_eq!(score.factors[0].detail, "2 major versions (0.3, 0.4)");
     }
 
     #[test]
@@ -306,6 +960,7 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec!["root".to_string()],
                 is_path_dep: false,
+                requirements: HashMap::new(),
             }],
         );
 
@@ -315,6 +970,7 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec!["A@1.0.0".to_string(), "D@1.0.0".to_string()],
                 is_path_dep: false,
+                requirements: HashMap::new(),
             }],
         );
 
@@ -324,6 +980,7 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec!["B@1.0.0".to_string()],
                 is_path_dep: false,
+                requirements: HashMap::new(),
             }],
         );
 
@@ -333,6 +990,7 @@ mod tests {
                 version: "1.0.0".to_string(),
                 dependents: vec!["root".to_string()],
                 is_path_dep: false,
+                requirements: HashMap::new(),
             }],
         );
 
@@ -357,5 +1015,216 @@ mod tests {
             compare_versions("1.0.0", "1.0.0"),
             std::cmp::Ordering::Equal
         );
+        // Go module versions
+        assert_eq!(
+            compare_versions("v1.10.0", "v1.9.1"),
+            std::cmp::Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn test_path_dependencies_excluded() {
+        use crate::lockfile::CargoPackageInfo;
+
+        let info = |version: &str, is_path_dep: bool| CargoPackageInfo {
+            version: version.to_string(),
+            dependents: vec![],
+            is_path_dep,
+            requirements: HashMap::new(),
+        };
+        let packages: std::collections::HashMap<String, Vec<CargoPackageInfo>> = [
+            // The workspace crate next to the published release it replaces
+            ("app-core", vec![info("0.4.0", true), info("0.3.1", false)]),
+            ("syn", vec![info("1.0.109", false), info("2.0.48", false)]),
+        ]
+        .into_iter()
+        .map(|(name, versions)| (name.to_string(), versions))
+        .collect();
+
+        let root = Path::new("/nonexistent");
+        let analysis = DuplicateAnalyzer::new(root)
+            .analyze_generic(packages, &ScoreContext::node_modules(root))
+            .unwrap();
+        let names: Vec<&str> = analysis
+            .duplicates
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["syn"]);
+    }
+
+    #[test]
+    fn test_singleton_duplicates() {
+        use crate::lockfile::CargoPackageInfo;
+
+        let info = |version: &str| CargoPackageInfo {
+            version: version.to_string(),
+            dependents: vec![],
+            is_path_dep: false,
+            requirements: HashMap::new(),
+        };
+        let packages = || -> std::collections::HashMap<String, Vec<CargoPackageInfo>> {
+            [
+                ("graphql", vec![info("16.8.1"), info("16.9.0")]),
+                ("lodash", vec![info("3.10.1"), info("4.17.21")]),
+            ]
+            .into_iter()
+            .map(|(name, versions)| (name.to_string(), versions))
+            .collect()
+        };
+
+        let root = Path::new("/nonexistent");
+        let analysis = DuplicateAnalyzer::new(root)
+            .analyze_generic(packages(), &ScoreContext::node_modules(root))
+            .unwrap();
+        let graphql = &analysis.duplicates[0];
+        assert_eq!(graphql.name, "graphql");
+        assert_eq!(graphql.severity, DuplicateSeverity::Critical);
+        assert!(graphql.singleton.as_ref().unwrap().contains("instanceof"));
+        assert_eq!(analysis.duplicates[1].severity, DuplicateSeverity::Medium);
+        assert!(analysis.duplicates[1].singleton.is_none());
+        assert_eq!(analysis.stats.critical_severity, 1);
+
+        // Crates and Go modules don't share names with npm singletons
+        let context = ScoreContext {
+            public_api: None,
+            layout: InstallLayout::GoModCache,
+            root,
+        };
+        let analysis = DuplicateAnalyzer::new(root)
+            .analyze_generic(packages(), &context)
+            .unwrap();
+        assert!(analysis.duplicates.iter().all(|d| d.singleton.is_none()));
+        assert_eq!(analysis.stats.critical_severity, 0);
+    }
+
+    #[test]
+    fn test_suggest_resolution() {
+        let version = |version: &str, requirements: &[(&str, &str)]| DuplicateVersion {
+            version: version.to_string(),
+            dependents: requirements.iter().map(|(d, _)| d.to_string()).collect(),
+            transitive_count: 0,
+            requirements: requirements
+                .iter()
+                .filter(|(_, range)| !range.is_empty())
+                .map(|(d, range)| (d.to_string(), range.to_string()))
+                .collect(),
+        };
+        let group = DuplicateGroup {
+            name: "semver".to_string(),
+            versions: vec![
+                version(
+                    "5.7.2",
+                    &[
+                        ("loose@1.0.0", ">=5"),
+                        ("old@2.0.0", "^5.0.0"),
+                        ("bare@1.0.0", ""),
+                    ],
+                ),
+                version("7.6.0", &[("app@1.0.0", "^7.0.0")]),
+            ],
+            severity: DuplicateSeverity::Medium,
+            rule: Rule::DuplicateMajor,
+            score: SeverityScore::default(),
+            singleton: None,
+            owners: vec![],
+        };
+
+        let suggestions = suggest_resolution(&group);
+        assert_eq!(suggestions.len(), 3);
+        assert!(suggestions[0].starts_with("Refresh the lockfile to move loose@1.0.0 onto"));
+        assert!(suggestions[1].starts_with("old@2.0.0 (^5.0.0) can't take semver 7.6.0"));
+        assert_eq!(suggestions[2], "Update bare@1.0.0 to use semver 7.6.0");
+    }
+
+    #[test]
+    fn test_types_skew() {
+        use crate::lockfile::CargoPackageInfo;
+
+        let info = |version: &str| CargoPackageInfo {
+            version: version.to_string(),
+            dependents: vec![],
+            is_path_dep: false,
+            requirements: HashMap::new(),
+        };
+        let packages: std::collections::HashMap<String, Vec<CargoPackageInfo>> = [
+            ("react", vec![info("18.2.0")]),
+            ("@types/react", vec![info("17.0.80")]),
+            ("lodash", vec![info("4.17.21")]),
+            ("@types/lodash", vec![info("4.14.200")]),
+            ("@babel/core", vec![info("7.23.0")]),
+            ("@types/babel__core", vec![info("7.20.5")]),
+            ("@types/node", vec![info("20.10.0")]),
+        ]
+        .into_iter()
+        .map(|(name, versions)| (name.to_string(), versions))
+        .collect();
+
+        let skew = find_types_skew(&packages);
+        assert_eq!(skew.len(), 1);
+        assert_eq!(skew[0].package, "react");
+        assert_eq!(skew[0].types_version, "17.0.80");
+        assert_eq!(skew[0].suggested_range, "^18");
+    }
+
+    #[test]
+    fn test_find_clusters() {
+        let group = |name: &str, severity: DuplicateSeverity, transitive: usize| DuplicateGroup {
+            name: name.to_string(),
+            versions: ["7.0.0", "7.1.0"]
+                .iter()
+                .map(|v| DuplicateVersion {
+                    version: v.to_string(),
+                    dependents: vec![],
+                    transitive_count: transitive,
+                    requirements: BTreeMap::new(),
+                })
+                .collect(),
+            severity,
+            rule: Rule::DuplicateVersion,
+            score: SeverityScore::default(),
+            singleton: None,
+            owners: vec![],
+        };
+        let duplicates = vec![
+            group("@babel/core", DuplicateSeverity::Low, 1),
+            group("@babel/types", DuplicateSeverity::Medium, 0),
+            group("@babel/parser", DuplicateSeverity::Low, 2),
+            group("@esbuild/linux-x64", DuplicateSeverity::Low, 0),
+            group("windows-sys", DuplicateSeverity::Low, 0),
+            group("windows-targets", DuplicateSeverity::High, 0),
+            group("windows_x86_64_msvc", DuplicateSeverity::Low, 0),
+            group("serde", DuplicateSeverity::Low, 0),
+            group("golang.org/x/net", DuplicateSeverity::Low, 0),
+        ];
+
+        let clusters = find_clusters(&duplicates);
+        let summary: Vec<(&str, usize, DuplicateSeverity, usize, usize)> = clusters
+            .iter()
+            .map(|c| {
+                (
+                    c.name.as_str(),
+                    c.packages.len(),
+                    c.severity,
+                    c.version_count,
+                    c.transitive_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("@babel/*", 3, DuplicateSeverity::Medium, 6, 6),
+                ("windows-*", 3, DuplicateSeverity::High, 6, 0),
+            ]
+        );
+        assert_eq!(
+            clusters[0].packages,
+            vec!["@babel/core", "@babel/parser", "@babel/types"]
+        );
+        assert_eq!(
+            cluster_key("golang.org/x/net").as_deref(),
+            Some("golang.org/x/*")
+        );
     }
 }
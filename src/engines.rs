@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::lockfile::{installed_dir, LockfileParser, LockfileType};
+use crate::range;
+use crate::types::{DeclaredEngine, EngineConflict, EnginesReport, ManagerMismatch, Package};
+
+/// Engines whose versions the project can declare
+const ENGINES: &[&str] = &["node", "npm", "pnpm", "yarn", "bun"];
+
+/// Files version managers (nvm, fnm, nodenv) read the Node.js version from
+const NODE_VERSION_FILES: &[&str] = &[".nvmrc", ".node-version"];
+
+/// Checks installed packages' `engines` fields against the runtime and
+/// package manager the project declares
+///
+/// The project declares them in its own `engines`, a .nvmrc or
+/// .node-version, and `packageManager`. A package conflicts when its
+/// range rejects the oldest version a declaration allows. A dependency's
+/// own `packageManager` only describes how it's developed, so just the
+/// project's is read.
+pub struct EnginesAnalyzer<'a> {
+    root: &'a Path,
+}
+
+impl<'a> EnginesAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn analyze(&self, lockfile: &LockfileParser) -> Result<EnginesReport> {
+        let packages = lockfile.parse()?;
+        let manifest_path = self.root.join("package.json");
+        let manifest: JsonValue = match std::fs::read_to_string(&manifest_path) {
+            Ok(content) => serde_json::from_str(&content)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to parse {}", manifest_path.display()))?,
+            Err(_) => JsonValue::Null,
+        };
+        let project_engines = engines(&manifest);
+
+        let mut declared: Vec<DeclaredEngine> = project_engines
+            .iter()
+            .filter(|(engine, _)| ENGINES.contains(&engine.as_str()))
+            .map(|(engine, range)| DeclaredEngine {
+                engine: engine.clone(),
+                constraint: range.clone(),
+                source: format!("engines.{}", engine),
+            })
+            .collect();
+        for file in NODE_VERSION_FILES {
+            let Ok(content) = std::fs::read_to_string(self.root.join(file)) else {
+                continue;
+            };
+            let version = content.trim().trim_start_matches('v');
+            // Aliases such as "lts/*" or "node" name no particular version
+            if range::lower_bounds(version).is_some_and(|b| !b.is_empty()) {
+                declared.push(DeclaredEngine {
+                    engine: "node".to_string(),
+                    constraint: version.to_string(),
+                    source: file.to_string(),
+                });
+            }
+        }
+        let package_manager = manifest
+            .get("packageManager")
+            .and_then(|v| v.as_str())
+            .and_then(|spec| spec.split_once('@'));
+        if let Some((name, version)) = package_manager {
+            // "pnpm@9.1.0+sha512.abc..." pins a hash after the version
+            let version = version.split('+').next().unwrap_or(version);
+            declared.push(DeclaredEngine {
+                engine: name.to_string(),
+                constraint: version.to_string(),
+                source: "packageManager".to_string(),
+            });
+        }
+
+        // The project's exact versions must fit its own ranges too
+        let exact: Vec<DeclaredEngine> = declared
+            .iter()
+            .filter(|d| !d.source.starts_with("engines."))
+            .cloned()
+            .collect();
+        let mut conflicts = check_engines("package.json", "", &project_engines, &exact);
+
+        let mut checked = 0;
+        let mut installed: Vec<&Package> = packages.values().collect();
+        installed.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+        for package in installed {
+            let Some(engines) = self.installed_engines(package) else {
+                continue;
+            };
+            checked += 1;
+            conflicts.extend(check_engines(
+                &package.name,
+                &package.version,
+                &engines,
+                &declared,
+            ));
+        }
+
+        let manager_mismatch = package_manager.and_then(|(name, _)| {
+            let expected = manager_lockfile(name)?;
+            (expected != lockfile.lockfile_type()).then(|| ManagerMismatch {
+                package_manager: name.to_string(),
+                lockfile: lockfile.file_name(),
+            })
+        });
+
+        Ok(EnginesReport {
+            declared,
+            conflicts,
+            manager_mismatch,
+            checked,
+        })
+    }
+
+    /// The `engines` of the installed copy of `package`, when it's on disk
+    fn installed_engines(&self, package: &Package) -> Option<BTreeMap<String, String>> {
+        let dir = installed_dir(self.root, package)?;
+        let content = std::fs::read_to_string(dir.join("package.json")).ok()?;
+        let manifest: JsonValue = serde_json::from_str(&content).ok()?;
+        Some(engines(&manifest))
+    }
+}
+
+/// A manifest's `engines`, ignoring the array and string forms very old
+/// packages used
+fn engines(manifest: &JsonValue) -> BTreeMap<String, String> {
+    manifest
+        .get("engines")
+        .and_then(|v| v.as_object())
+        .map(|engines| {
+            engines
+                .iter()
+                .filter_map(|(engine, range)| Some((engine.clone(), range.as_str()?.to_string())))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The lockfile a package manager named in `packageManager` writes
+fn manager_lockfile(name: &str) -> Option<LockfileType> {
+    match name {
+        "npm" => Some(LockfileType::Npm),
+        "pnpm" => Some(LockfileType::Pnpm),
+        "yarn" => Some(LockfileType::Yarn),
+        "bun" => Some(LockfileType::Bun),
+        _ => None,
+    }
+}
+
+/// One conflict per engine whose range rejects a declared version
+fn check_engines(
+    package: &str,
+    version: &str,
+    engines: &BTreeMap<String, String>,
+    declared: &[DeclaredEngine],
+) -> Vec<EngineConflict> {
+    engines
+        .iter()
+        .filter_map(|(engine, required)| {
+            declared
+                .iter()
+                .filter(|d| d.engine == *engine)
+                .find_map(|d| {
+                    let oldest = range::lower_bounds(&d.constraint)?
+                        .into_iter()
+                        .find(|v| range::satisfies(required, &v.to_string()) == Some(false))?;
+                    Some(EngineConflict {
+                        package: package.to_string(),
+                        version: version.to_string(),
+                        engine: engine.clone(),
+                        required: required.clone(),
+                        declared: d.clone(),
+                        oldest: oldest.to_string(),
+                    })
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_engine_conflicts() {
+        let root = TempDir::new("engines");
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "package.json",
+            r#"{
+                "name": "app",
+                "engines": {"node": ">=18", "pnpm": ">=9"},
+                "packageManager": "pnpm@8.15.1+sha512.abc"
+            }"#,
+        );
+        write(".nvmrc", "v20.11.0\n");
+        write(
+            "node_modules/vite/package.json",
+            r#"{"name": "vite", "version": "6.0.0", "engines": {"node": "^18.0.0 || >=20.0.0"}}"#,
+        );
+        write(
+            "node_modules/undici/package.json",
+            r#"{"name": "undici", "version": "7.0.0", "engines": {"node": ">=20.18.1"}}"#,
+        );
+        write(
+            "node_modules/.pnpm/old@1.0.0/node_modules/old/package.json",
+            r#"{"name": "old", "version": "1.0.0", "engines": ["node >= 0.4"]}"#,
+        );
+
+        write(
+            "package-lock.json",
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {"name": "app"},
+                    "node_modules/vite": {"version": "6.0.0"},
+                    "node_modules/undici": {"version": "7.0.0"},
+                    "node_modules/old": {"version": "1.0.0"}
+                }
+            }"#,
+        );
+
+        let lockfile = LockfileParser::new(&root).unwrap();
+        let report = EnginesAnalyzer::new(&root).analyze(&lockfile).unwrap();
+
+        assert_eq!(report.checked, 3);
+        let found: Vec<(&str, &str, &str, &str)> = report
+            .conflicts
+            .iter()
+            .map(|c| {
+                (
+                    c.package.as_str(),
+                    c.engine.as_str(),
+                    c.declared.source.as_str(),
+                    c.oldest.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("package.json", "pnpm", "packageManager", "8.15.1"),
+                ("undici", "node", "engines.node", "18.0.0"),
+            ]
+        );
+        let mismatch = report.manager_mismatch.unwrap();
+        assert_eq!(mismatch.package_manager, "pnpm");
+        assert_eq!(mismatch.lockfile, "package-lock.json");
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::resolve;
+use crate::types::{EntryPointAnalysis, EntryPointUsage, ImportMap, Package, PartialDependency};
+
+/// Computes which runtime dependencies each published entry point needs
+pub struct EntryPointAnalyzer<'a> {
+    root: &'a Path,
+    specs: &'a [String],
+}
+
+impl<'a> EntryPointAnalyzer<'a> {
+    /// `specs` are entry points from the config: "main", "exports" and "bin"
+    /// expand from package.json, anything else is a path relative to the root
+    pub fn new(root: &'a Path, specs: &'a [String]) -> Self {
+        Self { root, specs }
+    }
+
+    pub fn analyze(
+        &self,
+        imports: &ImportMap,
+        packages: &HashMap<String, Package>,
+    ) -> Result<EntryPointAnalysis> {
+        let mut runtime: Vec<&str> = packages
+            .values()
+            .filter(|p| p.is_direct && !p.is_dev)
+            .map(|p| p.name.as_str())
+            .collect();
+        runtime.sort();
+
+        let mut entry_points = Vec::new();
+        for (name, files) in self.resolve()? {
+            let analyzed = !files.is_empty() && files.iter().all(|f| imports.is_file_analyzed(f));
+            let reachable = imports.reachable_files(&files);
+            let used = imports.packages_imported_by(&reachable);
+
+            entry_points.push(EntryPointUsage {
+                name,
+                files,
+                analyzed,
+                packages: runtime
+                    .iter()
+                    .filter(|p| used.contains(**p))
+                    .map(|p| p.to_string())
+                    .collect(),
+            });
+        }
+
+        // Entry points we couldn't analyze say nothing about what they need
+        let analyzed: Vec<&EntryPointUsage> = entry_points.iter().filter(|e| e.analyzed).collect();
+
+        let mut partial = Vec::new();
+        let mut unreachable = Vec::new();
+        for package in runtime.iter().filter(|_| !analyzed.is_empty()) {
+            let needed_by: Vec<String> = analyzed
+                .iter()
+                .filter(|e| e.packages.iter().any(|p| p == package))
+                .map(|e| e.name.clone())
+                .collect();
+
+            if needed_by.is_empty() {
+                unreachable.push(package.to_string());
+            } else if needed_by.len() < analyzed.len() {
+                partial.push(PartialDependency {
+                    package: package.to_string(),
+                    entry_points: needed_by,
+                });
+            }
+        }
+
+        Ok(EntryPointAnalysis {
+            entry_points,
+            partial,
+            unreachable,
+        })
+    }
+
+    /// Expand the configured specs into named entry points and their files
+    pub fn resolve(&self) -> Result<Vec<(String, Vec<PathBuf>)>> {
+        let manifest_path = self.root.join("package.json");
+        let manifest: JsonValue = if manifest_path.exists() {
+            let content = std::fs::read_to_string(&manifest_path)
+                .into_diagnostic()
+                .with_context(|| "Failed to read package.json")?;
+            serde_json::from_str(&content)
+                .into_diagnostic()
+                .with_context(|| "Failed to parse package.json")?
+        } else {
+            JsonValue::Null
+        };
+
+        let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+        for spec in self.specs {
+            match spec.as_str() {
+                "main" => {
+                    let targets: Vec<String> = ["main", "module"]
+                        .iter()
+                        .filter_map(|field| manifest.get(field).and_then(|v| v.as_str()))
+                        .map(|s| s.to_string())
+                        .collect();
+                    let targets = if targets.is_empty() {
+                        vec!["index.js".to_string()]
+                    } else {
+                        targets
+                    };
+                    entries.push(("main".to_string(), targets));
+                }
+                "exports" => {
+                    for (subpath, targets) in export_subpaths(manifest.get("exports")) {
+                        entries.push((format!("exports {}", subpath), targets));
+                    }
+                }
+                "bin" => match manifest.get("bin") {
+                    Some(JsonValue::String(path)) => {
+                        entries.push(("bin".to_string(), vec![path.clone()]));
+                    }
+                    Some(JsonValue::Object(bins)) => {
+                        for (name, path) in bins {
+                            if let Some(path) = path.as_str() {
+                                entries.push((format!("bin {}", name), vec![path.to_string()]));
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+                path => entries.push((path.to_string(), vec![path.to_string()])),
+            }
+        }
+
+        Ok(entries
+            .into_iter()
+            .map(|(name, targets)| {
+                let files = targets
+                    .iter()
+                    .map(|t| {
+                        let path = self.root.join(t);
+                        resolve::resolve_file(&path).unwrap_or_else(|| resolve::normalize(&path))
+                    })
+                    .collect();
+                (name, files)
+            })
+            .collect())
+    }
+}
+
+/// Subpath -> target files of a package.json `exports` field
+///
+/// Conditions (`import`, `require`, `default`, ...) are flattened into one
+/// entry point per subpath; `types` targets are declaration files and skipped.
+fn export_subpaths(exports: Option<&JsonValue>) -> Vec<(String, Vec<String>)> {
+    let Some(exports) = exports else {
+        return Vec::new();
+    };
+
+    let is_subpath_map = exports
+        .as_object()
+        .is_some_and(|map| map.keys().any(|k| k.starts_with('.')));
+
+    if !is_subpath_map {
+        return vec![(".".to_string(), condition_targets(exports))];
+    }
+
+    let mut subpaths: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    if let Some(map) = exports.as_object() {
+        for (subpath, target) in map {
+            // Patterns ("./features/*") can't be resolved to single files
+            if subpath.contains('*') {
+                continue;
+            }
+            let targets = condition_targets(target);
+            if !targets.is_empty() {
+                subpaths.insert(subpath.clone(), targets);
+            }
+        }
+    }
+    subpaths.into_iter().collect()
+}
+
+fn condition_targets(value: &JsonValue) -> Vec<String> {
+    let mut targets = Vec::new();
+    let mut seen = HashSet::new();
+    collect_targets(value, &mut targets, &mut seen);
+    targets
+}
+
+fn collect_targets(value: &JsonValue, targets: &mut Vec<String>, seen: &mut HashSet<String>) {
+    match value {
+        JsonValue::String(target) => {
+            if !target.ends_with(".d.ts") && seen.insert(target.clone()) {
+                targets.push(target.clone());
+            }
+        }
+        JsonValue::Object(conditions) => {
+            for (condition, nested) in conditions {
+                if condition != "types" {
+                    collect_targets(nested, targets, seen);
+                }
+            }
+        }
+        JsonValue::Array(fallbacks) => {
+            for nested in fallbacks {
+                collect_targets(nested, targets, seen);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_export_subpaths() {
+        let exports: JsonValue = serde_json::from_str(
+            r#"{
+                ".": {"types": "./index.d.ts", "import": "./index.mjs", "require": "./index.cjs"},
+                "./cli": "./cli.js",
+                "./features/*": "./features/*.js"
+            }"#,
+        )
+        .unwrap();
+
+        let subpaths = export_subpaths(Some(&exports));
+        assert_eq!(
+            subpaths,
+            vec![
+                (
+                    ".".to_string(),
+                    vec!["./index.mjs".to_string(), "./index.cjs".to_string()]
+                ),
+                ("./cli".to_string(), vec!["./cli.js".to_string()]),
+            ]
+        );
+
+        let exports: JsonValue = serde_json::from_str(r#""./main.js""#).unwrap();
+        assert_eq!(
+            export_subpaths(Some(&exports)),
+            vec![(".".to_string(), vec!["./main.js".to_string()])]
+        );
+    }
+}
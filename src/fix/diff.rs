@@ -0,0 +1,104 @@
+/// Unchanged lines shown around each change
+const CONTEXT_LINES: usize = 2;
+
+/// One line of a diff between two versions of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffLine<'a> {
+    Context(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+    /// Unchanged lines left out between two hunks
+    Gap,
+}
+
+/// Line diff of `old` and `new`, trimmed to the changes and their context
+///
+/// Uses a plain LCS table; package.json files are small enough that the
+/// quadratic cost doesn't matter.
+pub fn line_diff<'a>(old: &'a str, new: &'a str) -> Vec<DiffLine<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut full = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            full.push(DiffLine::Context(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            full.push(DiffLine::Removed(old[i]));
+            i += 1;
+        } else {
+            full.push(DiffLine::Added(new[j]));
+            j += 1;
+        }
+    }
+
+    let changed: Vec<usize> = full
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, DiffLine::Context(_)))
+        .map(|(i, _)| i)
+        .collect();
+
+    let near_change = |index: usize| {
+        changed
+            .iter()
+            .any(|&c| index + CONTEXT_LINES >= c && index <= c + CONTEXT_LINES)
+    };
+
+    let mut trimmed = Vec::new();
+    let mut skipped = false;
+    for (index, line) in full.into_iter().enumerate() {
+        if near_change(index) {
+            if skipped && !trimmed.is_empty() {
+                trimmed.push(DiffLine::Gap);
+            }
+            skipped = false;
+            trimmed.push(line);
+        } else {
+            skipped = true;
+        }
+    }
+    trimmed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_diff() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nB\nc\nd\ne\nf\ng\nh\ni\n";
+
+        assert_eq!(
+            line_diff(old, new),
+            vec![
+                DiffLine::Context("a"),
+                DiffLine::Removed("b"),
+                DiffLine::Added("B"),
+                DiffLine::Context("c"),
+                DiffLine::Context("d"),
+                DiffLine::Gap,
+                DiffLine::Context("g"),
+                DiffLine::Context("h"),
+                DiffLine::Added("i"),
+            ]
+        );
+        assert!(line_diff(old, old).is_empty());
+    }
+}
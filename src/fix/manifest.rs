@@ -0,0 +1,376 @@
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Serialize;
+use serde_json::ser::PrettyFormatter;
+use serde_json::{Map, Value as JsonValue};
+
+/// Sections of package.json that declare dependencies
+pub const DEPENDENCY_SECTIONS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "optionalDependencies",
+    "peerDependencies",
+];
+
+/// A package.json loaded for editing
+///
+/// Key order is kept as written. Only the values that changed are
+/// rewritten, in the file's indentation; everything else, inline objects
+/// included, is saved back byte for byte.
+pub struct Manifest {
+    path: PathBuf,
+    pub value: JsonValue,
+    indent: String,
+    trailing_newline: bool,
+
+    /// The file as last read or written
+    original: String,
+}
+
+impl Manifest {
+    pub fn load(root: &Path) -> Result<Self> {
+        let path = root.join("package.json");
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(path, &content)
+    }
+
+    pub fn parse(path: PathBuf, content: &str) -> Result<Self> {
+        let value: JsonValue = serde_json::from_str(content)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        Ok(Self {
+            path,
+            value,
+            indent: detect_indent(content),
+            trailing_newline: content.ends_with('\n'),
+            original: content.to_string(),
+        })
+    }
+
+    /// The file's content before any edits, byte for byte
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    /// The file with the edits made so far
+    pub fn render(&self) -> Result<String> {
+        let original: JsonValue = serde_json::from_str(&self.original).into_diagnostic()?;
+        if original == self.value {
+            return Ok(self.original.clone());
+        }
+        let Some(root) = JsonNode::scan(self.original.as_bytes()) else {
+            return self.render_all();
+        };
+
+        let mut output = String::new();
+        output.push_str(&self.original[..root.start]);
+        output.push_str(&self.render_value(&root, &original, &self.value, 0)?);
+        output.push_str(&self.original[root.end..]);
+        Ok(output)
+    }
+
+    /// The whole file serialized in its indentation
+    fn render_all(&self) -> Result<String> {
+        let mut output = self.serialize(&self.value, 0)?;
+        if self.trailing_newline {
+            output.push('\n');
+        }
+        Ok(output)
+    }
+
+    /// `value` where `old` was written at `node`, keeping the text of
+    /// whatever didn't change
+    fn render_value(
+        &self,
+        node: &JsonNode,
+        old: &JsonValue,
+        value: &JsonValue,
+        depth: usize,
+    ) -> Result<String> {
+        if old == value {
+            return Ok(self.original[node.start..node.end].to_string());
+        }
+        let (JsonValue::Object(old_members), JsonValue::Object(members)) = (old, value) else {
+            return self.serialize(value, depth);
+        };
+        if members.is_empty() {
+            return Ok("{}".to_string());
+        }
+
+        let mut rendered = Vec::new();
+        for (key, member) in members {
+            let key_json = serde_json::to_string(key).into_diagnostic()?;
+            let text = match (node.member(key), old_members.get(key)) {
+                (Some(child), Some(old)) => self.render_value(child, old, member, depth + 1)?,
+                _ => self.serialize(member, depth + 1)?,
+            };
+            rendered.push(format!("{}: {}", key_json, text));
+        }
+
+        // An object written on one line stays on one line
+        let text = &self.original[node.start..node.end];
+        if !old_members.is_empty() && !text.contains('\n') {
+            let pad = if text.starts_with("{ ") { " " } else { "" };
+            return Ok(format!("{{{}{}{}}}", pad, rendered.join(", "), pad));
+        }
+        let inner = self.indent.repeat(depth + 1);
+        Ok(format!(
+            "{{\n{}{}\n{}}}",
+            inner,
+            rendered.join(&format!(",\n{}", inner)),
+            self.indent.repeat(depth)
+        ))
+    }
+
+    /// A value serialized in the file's indentation, nested `depth` deep
+    fn serialize(&self, value: &JsonValue, depth: usize) -> Result<String> {
+        let mut buf = Vec::new();
+        let formatter = PrettyFormatter::with_indent(self.indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
+        value
+            .serialize(&mut serializer)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+
+        let output = String::from_utf8(buf).into_diagnostic()?;
+        Ok(output.replace('\n', &format!("\n{}", self.indent.repeat(depth))))
+    }
+
+    pub fn save(&mut self) -> Result<()> {
+        let content = self.render()?;
+        std::fs::write(&self.path, &content)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+        self.original = content;
+        Ok(())
+    }
+
+    /// The dependency section declaring `package`, if any
+    pub fn section_of(&self, package: &str) -> Option<&'static str> {
+        DEPENDENCY_SECTIONS.iter().copied().find(|section| {
+            self.value
+                .get(section)
+                .and_then(|deps| deps.get(package))
+                .is_some()
+        })
+    }
+
+    /// Move a dependency to another section, keeping its declared range
+    ///
+    /// A missing target section is created next to the other dependency
+    /// sections, and the entry is inserted in order if the section is sorted.
+    pub fn move_dependency(&mut self, package: &str, from: &str, to: &str) -> bool {
+        let Some(range) = self
+            .value
+            .get_mut(from)
+            .and_then(|deps| deps.as_object_mut())
+            .and_then(|deps| deps.shift_remove(package))
+        else {
+            return false;
+        };
+
+        let Some(root) = self.value.as_object_mut() else {
+            return false;
+        };
+        if !root.contains_key(to) {
+            let index = root
+                .keys()
+                .position(|k| k == from)
+                .map(|i| if to == "devDependencies" { i + 1 } else { i })
+                .unwrap_or(root.len());
+            root.shift_insert(index, to.to_string(), JsonValue::Object(Map::new()));
+        }
+
+        let Some(deps) = root.get_mut(to).and_then(|d| d.as_object_mut()) else {
+            return false;
+        };
+        let sorted = deps.keys().zip(deps.keys().skip(1)).all(|(a, b)| a <= b);
+        let index = if sorted {
+            deps.keys()
+                .position(|k| k.as_str() > package)
+                .unwrap_or(deps.len())
+        } else {
+            deps.len()
+        };
+        deps.shift_insert(index, package.to_string(), range);
+        true
+    }
+
+    /// A top-level (or nested, for `pnpm.overrides`) object, created if missing
+    pub fn object_mut(&mut self, keys: &[&str]) -> Option<&mut Map<String, JsonValue>> {
+        let mut current = self.value.as_object_mut()?;
+        for key in keys {
+            current = current
+                .entry(key.to_string())
+                .or_insert_with(|| JsonValue::Object(Map::new()))
+                .as_object_mut()?;
+        }
+        Some(current)
+    }
+}
+
+/// Where a JSON value sits in the text, with the members of an object
+struct JsonNode {
+    start: usize,
+    end: usize,
+    members: Vec<(String, JsonNode)>,
+}
+
+impl JsonNode {
+    /// The document's top-level value; `None` if it isn't valid JSON
+    fn scan(text: &[u8]) -> Option<Self> {
+        let start = skip_whitespace(text, 0);
+        let (node, _) = Self::scan_value(text, start)?;
+        Some(node)
+    }
+
+    fn member(&self, key: &str) -> Option<&JsonNode> {
+        self.members
+            .iter()
+            .rev()
+            .find(|(name, _)| name == key)
+            .map(|(_, node)| node)
+    }
+
+    /// The value starting at `start` and the index just past it
+    fn scan_value(text: &[u8], start: usize) -> Option<(Self, usize)> {
+        let mut members = Vec::new();
+        let end = match *text.get(start)? {
+            b'"' => scan_string(text, start)?,
+            b'{' => {
+                let mut i = skip_whitespace(text, start + 1);
+                if text.get(i) == Some(&b'}') {
+                    i + 1
+                } else {
+                    loop {
+                        let key_end = scan_string(text, i)?;
+                        let key: String = serde_json::from_slice(&text[i..key_end]).ok()?;
+                        i = skip_whitespace(text, key_end);
+                        if text.get(i) != Some(&b':') {
+                            return None;
+                        }
+                        let (value, value_end) =
+                            Self::scan_value(text, skip_whitespace(text, i + 1))?;
+                        members.push((key, value));
+                        i = skip_whitespace(text, value_end);
+                        match text.get(i)? {
+                            b',' => i = skip_whitespace(text, i + 1),
+                            b'}' => break i + 1,
+                            _ => return None,
+                        }
+                    }
+                }
+            }
+            b'[' => {
+                let mut i = skip_whitespace(text, start + 1);
+                if text.get(i) == Some(&b']') {
+                    i + 1
+                } else {
+                    loop {
+                        let (_, value_end) = Self::scan_value(text, i)?;
+                        i = skip_whitespace(text, value_end);
+                        match text.get(i)? {
+                            b',' => i = skip_whitespace(text, i + 1),
+                            b']' => break i + 1,
+                            _ => return None,
+                        }
+                    }
+                }
+            }
+            // Numbers, true, false and null
+            _ => {
+                let len = text[start..]
+                    .iter()
+                    .position(|b| matches!(b, b',' | b'}' | b']') || b.is_ascii_whitespace())
+                    .unwrap_or(text.len() - start);
+                start + len
+            }
+        };
+        Some((
+            Self {
+                start,
+                end,
+                members,
+            },
+            end,
+        ))
+    }
+}
+
+/// The index just past the string literal starting at `start`
+fn scan_string(text: &[u8], start: usize) -> Option<usize> {
+    if text.get(start) != Some(&b'"') {
+        return None;
+    }
+    let mut i = start + 1;
+    loop {
+        match *text.get(i)? {
+            b'\\' => i += 2,
+            b'"' => return Some(i + 1),
+            _ => i += 1,
+        }
+    }
+}
+
+fn skip_whitespace(text: &[u8], mut i: usize) -> usize {
+    while text.get(i).is_some_and(|b| b.is_ascii_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+/// Leading whitespace of the first indented line, defaulting to two spaces
+fn detect_indent(content: &str) -> String {
+    content
+        .lines()
+        .find_map(|line| {
+            let indent: String = line
+                .chars()
+                .take_while(|c| *c == ' ' || *c == '\t')
+                .collect();
+            (!indent.is_empty() && indent.len() < line.len()).then_some(indent)
+        })
+        .unwrap_or_else(|| "  ".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_preserves_style() {
+        let content = "{\n    \"name\": \"app\",\n    \"version\": \"1.0.0\",\n    \"dependencies\": {\n        \"zod\": \"^3.0.0\",\n        \"axios\": \"^1.0.0\"\n    }\n}\n";
+        let manifest = Manifest::parse(PathBuf::from("package.json"), content).unwrap();
+        assert_eq!(manifest.render().unwrap(), content);
+
+        let tabs = "{\n\t\"name\": \"app\"\n}";
+        let manifest = Manifest::parse(PathBuf::from("package.json"), tabs).unwrap();
+        assert_eq!(manifest.render().unwrap(), tabs);
+    }
+
+    #[test]
+    fn test_edits_keep_untouched_text() {
+        let content = "{\n  \"name\": \"app\",\n  \"engines\": { \"node\": \">=18\" },\n  \"scripts\": {\"test\": \"vitest\"},\n  \"dependencies\": {\n    \"zod\": \"^3.0.0\",\n    \"left-pad\": \"1.3.0\"\n  },\n  \"devDependencies\": {\n    \"vitest\": \"^1.0.0\"\n  }\n}\n";
+        let mut manifest = Manifest::parse(PathBuf::from("package.json"), content).unwrap();
+        manifest.move_dependency("left-pad", "dependencies", "devDependencies");
+
+        assert_eq!(
+            manifest.render().unwrap(),
+            "{\n  \"name\": \"app\",\n  \"engines\": { \"node\": \">=18\" },\n  \"scripts\": {\"test\": \"vitest\"},\n  \"dependencies\": {\n    \"zod\": \"^3.0.0\"\n  },\n  \"devDependencies\": {\n    \"left-pad\": \"1.3.0\",\n    \"vitest\": \"^1.0.0\"\n  }\n}\n"
+        );
+
+        let inline = "{\"name\": \"app\", \"dependencies\": {\"zod\": \"^3.0.0\"}}";
+        let mut manifest = Manifest::parse(PathBuf::from("package.json"), inline).unwrap();
+        manifest
+            .object_mut(&["dependencies"])
+            .unwrap()
+            .insert("ms".to_string(), JsonValue::String("^2.1.3".to_string()));
+        assert_eq!(
+            manifest.render().unwrap(),
+            "{\"name\": \"app\", \"dependencies\": {\"zod\": \"^3.0.0\", \"ms\": \"^2.1.3\"}}"
+        );
+    }
+}
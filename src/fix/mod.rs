@@ -0,0 +1,704 @@
+pub mod diff;
+pub mod manifest;
+pub mod rename;
+pub mod rewrite;
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::duplicates::release_line;
+use crate::lockfile::{Ecosystem, LockfileSelection, LockfileType};
+use crate::range::satisfies;
+use crate::types::{
+    DuplicateAnalysis, FileContext, FixAction, FixOutcome, FixPlan, MisplacedDependency, Package,
+    SkippedFix, UsageAnalysis, Vulnerability,
+};
+use manifest::Manifest;
+
+/// Current fix plan format
+const PLAN_VERSION: u32 = 1;
+
+/// Uninstall every unused direct dependency
+pub fn uninstall_unused(analysis: &UsageAnalysis) -> Vec<FixAction> {
+    analysis
+        .unused_direct
+        .iter()
+        .map(|pkg| FixAction::Uninstall {
+            package: pkg.name.clone(),
+            reason: "not imported anywhere".to_string(),
+        })
+        .collect()
+}
+
+/// Move vulnerable packages to their patched version
+///
+/// Direct dependencies are upgraded; transitive ones get an override, since
+/// their version is chosen by whatever depends on them.
+pub fn patch_vulnerabilities(
+    vulnerabilities: &[Vulnerability],
+    packages: &HashMap<String, Package>,
+) -> Vec<FixAction> {
+    let mut patched: HashMap<&str, (&Vulnerability, Vec<&str>)> = HashMap::new();
+    for vuln in vulnerabilities {
+        let Some(version) = vuln.patched_version.as_deref() else {
+            continue;
+        };
+        let entry = patched
+            .entry(vuln.package_name.as_str())
+            .or_insert((vuln, Vec::new()));
+        // One version has to fix every advisory for the package
+        if newer(version, entry.0.patched_version.as_deref().unwrap_or("")) {
+            entry.0 = vuln;
+        }
+        entry.1.push(vuln.id.as_str());
+    }
+
+    let mut actions: Vec<FixAction> = patched
+        .into_values()
+        .map(|(vuln, ids)| {
+            let version = vuln.patched_version.clone().unwrap_or_default();
+            let reason = format!("fixes {}", ids.join(", "));
+            let is_direct = packages
+                .get(&vuln.package_name)
+                .is_some_and(|pkg| pkg.is_direct);
+
+            if is_direct {
+                FixAction::Upgrade {
+                    package: vuln.package_name.clone(),
+                    from: vuln.installed_version.clone(),
+                    to: version,
+                    reason,
+                }
+            } else {
+                FixAction::Override {
+                    package: vuln.package_name.clone(),
+                    version,
+                    reason,
+                }
+            }
+        })
+        .collect();
+
+    actions.sort_by(|a, b| a.package().cmp(b.package()));
+    actions
+}
+
+/// Collapse duplicates onto the newest installed version every dependent
+/// accepts
+///
+/// An override pins every copy, so all of them have to be on one release
+/// line (a major, or a minor for 0.x) and every dependent's declared range
+/// has to allow the target; `~` and exact ranges often don't. Copies with
+/// a dependent whose range isn't recorded are left alone.
+pub fn dedupe_duplicates(analysis: &DuplicateAnalysis) -> Vec<FixAction> {
+    analysis
+        .duplicates
+        .iter()
+        .filter_map(|group| {
+            let lines: HashSet<Option<(u64, u64)>> = group
+                .versions
+                .iter()
+                .map(|v| release_line(&v.version))
+                .collect();
+            if lines.len() != 1 || lines.contains(&None) {
+                return None;
+            }
+
+            let accepted = |target: &str| {
+                group.versions.iter().all(|v| {
+                    v.version == target
+                        || (!v.dependents.is_empty()
+                            && v.dependents.iter().all(|dependent| {
+                                v.requirements
+                                    .get(dependent)
+                                    .and_then(|range| satisfies(range, target))
+                                    == Some(true)
+                            }))
+                })
+            };
+            let mut versions: Vec<&str> =
+                group.versions.iter().map(|v| v.version.as_str()).collect();
+            versions.sort_by(|a, b| {
+                if newer(a, b) {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Greater
+                }
+            });
+            let target = versions.into_iter().find(|version| accepted(version))?;
+            Some(FixAction::Override {
+                package: group.name.clone(),
+                version: target.to_string(),
+                reason: format!(
+                    "dedupe {} copies, all in range of {}",
+                    group.versions.len(),
+                    target
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Move the dependencies `DependencyGraph::find_misplaced` reports to
+/// the package.json section they belong in
+///
+/// Runtime dependencies only tests, configs and scripts import move to
+/// devDependencies, and dev dependencies shipped code imports move to
+/// dependencies. Ones the manifest doesn't declare in the section the
+/// lockfile has them in are left alone.
+pub fn plan_moves(manifest: &Manifest, misplaced: &[MisplacedDependency]) -> Vec<FixAction> {
+    misplaced
+        .iter()
+        .filter_map(|dependency| {
+            let (from, to) = if dependency.should_be_dev() {
+                ("dependencies", "devDependencies")
+            } else {
+                ("devDependencies", "dependencies")
+            };
+            let package = &dependency.package.name;
+            manifest.value.get(from)?.get(package)?;
+
+            let reason = if dependency.should_be_dev() {
+                let mut importers: Vec<&str> = Vec::new();
+                for (context, name) in [
+                    (FileContext::Test, "tests"),
+                    (FileContext::Config, "config files"),
+                    (FileContext::Script, "scripts"),
+                ] {
+                    if dependency.contexts.contains(&context) {
+                        importers.push(name);
+                    }
+                }
+                let importers = match importers.split_last() {
+                    Some((last, [])) => last.to_string(),
+                    Some((last, rest)) => format!("{} and {}", rest.join(", "), last),
+                    None => return None,
+                };
+                format!("only imported by {}", importers)
+            } else {
+                let first = dependency.imports.first()?;
+                format!("imported by {}:{}", first.file_path.display(), first.line)
+            };
+            Some(FixAction::Move {
+                package: package.clone(),
+                from: from.to_string(),
+                to: to.to_string(),
+                reason,
+            })
+        })
+        .collect()
+}
+
+/// Add actions to the plan file, creating it if needed
+///
+/// Several commands can write to the same plan; a later action for the
+/// same package and kind replaces the earlier one. Returns the action count.
+pub fn write_plan(path: &Path, actions: Vec<FixAction>) -> Result<usize> {
+    let mut plan = if path.exists() {
+        load_plan(path)?
+    } else {
+        FixPlan {
+            version: PLAN_VERSION,
+            actions: Vec::new(),
+        }
+    };
+
+    for action in actions {
+        plan.actions.retain(|existing| {
+            std::mem::discriminant(existing) != std::mem::discriminant(&action)
+                || existing.package() != action.package()
+        });
+        plan.actions.push(action);
+    }
+
+    let output = serde_json::to_string_pretty(&plan)
+        .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+    std::fs::write(path, output + "\n")
+        .into_diagnostic()
+        .with_context(|| format!("Failed to write fix plan {}", path.display()))?;
+
+    Ok(plan.actions.len())
+}
+
+pub fn load_plan(path: &Path) -> Result<FixPlan> {
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read fix plan {}", path.display()))?;
+    let plan: FixPlan = serde_json::from_str(&content)
+        .map_err(|e| miette::miette!("Failed to parse fix plan {}: {}", path.display(), e))?;
+
+    if plan.version > PLAN_VERSION {
+        bail!(
+            "Fix plan {} was written by a newer depx (format {}, expected {})",
+            path.display(),
+            plan.version,
+            PLAN_VERSION
+        );
+    }
+    Ok(plan)
+}
+
+/// Load package.json for `depx fix`
+pub fn load_manifest(root: &Path) -> Result<Manifest> {
+    Manifest::load(root)
+}
+
+/// Apply actions to the project's package.json
+///
+/// Only the manifest is edited; the lockfile catches up on the next install.
+/// With `dry_run` nothing is written, and the outcome carries both versions
+/// of the file for a diff.
+///
+/// `lockfile_type` decides where overrides are written, see [`lockfile_type`].
+pub fn apply(
+    lockfile_type: Option<LockfileType>,
+    manifest: &mut Manifest,
+    actions: &[FixAction],
+    dry_run: bool,
+) -> Result<FixOutcome> {
+    // The diff starts from the file itself, so a rewrite that also changes
+    // its formatting shows up
+    let original = manifest.original().to_string();
+    let mut outcome = apply_actions(manifest, actions, lockfile_type);
+    outcome.original = original;
+    if outcome.applied.is_empty() {
+        outcome.updated = outcome.original.clone();
+        return Ok(outcome);
+    }
+
+    outcome.updated = manifest.render()?;
+    if !dry_run {
+        manifest.save()?;
+    }
+    Ok(outcome)
+}
+
+/// The JS lockfile the project at `root` is installed with, `--lockfile`'s
+/// when given
+///
+/// A project without a lockfile yet can still be fixed, but a `--lockfile`
+/// depx can't use for package.json is an error.
+pub fn lockfile_type(root: &Path, selection: &LockfileSelection) -> Result<Option<LockfileType>> {
+    match selection.lockfile(root, Some(Ecosystem::Js)) {
+        Ok(parser) => Ok(Some(parser.lockfile_type())),
+        Err(_) if selection.lockfile.is_none() => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn apply_actions(
+    manifest: &mut Manifest,
+    actions: &[FixAction],
+    lockfile_type: Option<LockfileType>,
+) -> FixOutcome {
+    let mut outcome = FixOutcome::default();
+
+    for action in actions {
+        match apply_action(manifest, action, lockfile_type) {
+            Ok(()) => outcome.applied.push(action.clone()),
+            Err(reason) => outcome.skipped.push(SkippedFix {
+                action: action.clone(),
+                reason,
+            }),
+        }
+    }
+
+    outcome
+}
+
+fn apply_action(
+    manifest: &mut Manifest,
+    action: &FixAction,
+    lockfile_type: Option<LockfileType>,
+) -> std::result::Result<(), String> {
+    let package = action.package();
+    let section = manifest.section_of(package);
+
+    match action {
+        FixAction::Uninstall { .. } => {
+            let section = section.ok_or("not declared in package.json")?;
+            if let Some(deps) = manifest.object_mut(&[section]) {
+                deps.shift_remove(package);
+            }
+        }
+        FixAction::Upgrade { to, .. } => {
+            let section = section.ok_or("not declared in package.json")?;
+            let deps = manifest
+                .object_mut(&[section])
+                .ok_or("dependency section isn't an object")?;
+            let current = deps.get(package).and_then(|v| v.as_str()).unwrap_or("");
+            // Keep the range style the project already uses
+            let range = match current.chars().next() {
+                Some(op @ ('^' | '~')) => format!("{}{}", op, to),
+                _ => to.clone(),
+            };
+            deps.insert(package.to_string(), JsonValue::String(range));
+        }
+        FixAction::Move { from, to, .. } => {
+            if section != Some(from.as_str()) {
+                return Err(format!("not declared in {}", from));
+            }
+            if !manifest.move_dependency(package, from, to) {
+                return Err(format!("couldn't move it to {}", to));
+            }
+        }
+        FixAction::Restyle {
+            section, from, to, ..
+        }
+        | FixAction::Align {
+            section, from, to, ..
+        } => {
+            let deps = manifest
+                .value
+                .get_mut(section)
+                .and_then(|deps| deps.as_object_mut())
+                .ok_or_else(|| format!("no {} in package.json", section))?;
+            let current = deps
+                .get(package)
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| format!("not declared in {}", section))?;
+            if current != from {
+                return Err(format!("declared as {} now", current));
+            }
+            deps.insert(package.to_string(), JsonValue::String(to.clone()));
+        }
+        FixAction::Override { version, .. } => {
+            if section.is_some() {
+                return Err("declared directly in package.json; upgrade it instead".to_string());
+            }
+            let keys: &[&str] = match lockfile_type {
+                Some(LockfileType::Yarn) => &["resolutions"],
+                Some(LockfileType::Pnpm) => &["pnpm", "overrides"],
+                _ => &["overrides"],
+            };
+            let overrides = manifest
+                .object_mut(keys)
+                .ok_or_else(|| format!("`{}` isn't an object", keys.join(".")))?;
+            overrides.insert(package.to_string(), JsonValue::String(version.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether version `a` is newer than `b`; unparsable versions compare as text
+fn newer(a: &str, b: &str) -> bool {
+    match (semver::Version::parse(a), semver::Version::parse(b)) {
+        (Ok(a), Ok(b)) => a > b,
+        (Ok(_), Err(_)) => true,
+        _ => a > b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DependencyGraph;
+    use crate::types::{Import, ImportKind, ImportMap};
+    use std::path::PathBuf;
+
+    fn manifest() -> Manifest {
+        Manifest::parse(
+            PathBuf::from("package.json"),
+            r#"{
+  "name": "app",
+  "dependencies": {
+    "express": "^4.17.0",
+    "left-pad": "1.3.0"
+  },
+  "devDependencies": {
+    "jest": "29.0.0"
+  }
+}
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_dry_run_diffs_the_file() {
+        let content = "{\"name\": \"app\", \"dependencies\": {\"left-pad\": \"1.3.0\"}}";
+        let mut manifest = Manifest::parse(PathBuf::from("package.json"), content).unwrap();
+        let actions = [FixAction::Uninstall {
+            package: "left-pad".to_string(),
+            reason: String::new(),
+        }];
+
+        let outcome = apply(None, &mut manifest, &actions, true).unwrap();
+        assert_eq!(outcome.original, content);
+        assert_eq!(outcome.updated, "{\"name\": \"app\", \"dependencies\": {}}");
+
+        // Nothing applied leaves the file as it is, formatting included
+        let mut manifest = Manifest::parse(PathBuf::from("package.json"), content).unwrap();
+        let actions = [FixAction::Uninstall {
+            package: "moment".to_string(),
+            reason: String::new(),
+        }];
+        let outcome = apply(None, &mut manifest, &actions, true).unwrap();
+        assert!(outcome.applied.is_empty());
+        assert_eq!(outcome.updated, outcome.original);
+    }
+
+    #[test]
+    fn test_apply_actions() {
+        let mut manifest = manifest();
+        let actions = vec![
+            FixAction::Uninstall {
+                package: "left-pad".to_string(),
+                reason: String::new(),
+            },
+            FixAction::Upgrade {
+                package: "express".to_string(),
+                from: "4.17.0".to_string(),
+                to: "4.19.2".to_string(),
+                reason: String::new(),
+            },
+            FixAction::Override {
+                package: "qs".to_string(),
+                version: "6.11.0".to_string(),
+                reason: String::new(),
+            },
+            FixAction::Override {
+                package: "jest".to_string(),
+                version: "29.7.0".to_string(),
+                reason: String::new(),
+            },
+            FixAction::Uninstall {
+                package: "moment".to_string(),
+                reason: String::new(),
+            },
+            FixAction::Restyle {
+                package: "jest".to_string(),
+                section: "devDependencies".to_string(),
+                from: "29.0.0".to_string(),
+                to: "^29.0.0".to_string(),
+                reason: String::new(),
+            },
+            FixAction::Restyle {
+                package: "express".to_string(),
+                section: "dependencies".to_string(),
+                from: "4.17.0".to_string(),
+                to: "^4.17.0".to_string(),
+                reason: String::new(),
+            },
+        ];
+
+        let outcome = apply_actions(&mut manifest, &actions, Some(LockfileType::Npm));
+        assert_eq!(outcome.applied.len(), 4);
+        assert_eq!(outcome.skipped.len(), 3);
+
+        assert_eq!(
+            manifest.render().unwrap(),
+            r#"{
+  "name": "app",
+  "dependencies": {
+    "express": "^4.19.2"
+  },
+  "devDependencies": {
+    "jest": "^29.0.0"
+  },
+  "overrides": {
+    "qs": "6.11.0"
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_plan_moves() {
+        let mut manifest = Manifest::parse(
+            PathBuf::from("package.json"),
+            r#"{
+  "dependencies": {
+    "msw": "^2.0.0",
+    "zod": "^3.0.0"
+  },
+  "devDependencies": {
+    "axios": "^1.0.0",
+    "react": "^18.0.0",
+    "vitest": "^1.0.0"
+  },
+  "peerDependencies": {
+    "react": ">=17"
+  }
+}
+"#,
+        )
+        .unwrap();
+
+        let mut packages = HashMap::new();
+        for (name, dev) in [
+            ("msw", false),
+            ("zod", false),
+            ("axios", true),
+            ("react", true),
+            ("vitest", true),
+        ] {
+            let mut package = Package::new(name, "1.0.0").direct();
+            package.is_dev = dev;
+            packages.insert(name.to_string(), package);
+        }
+
+        let mut imports = ImportMap::new();
+        imports.set_file_context(PathBuf::from("src/api.test.ts"), FileContext::Test);
+        imports.set_file_context(PathBuf::from("src/api.ts"), FileContext::Source);
+        imports.set_file_context(PathBuf::from("src/App.tsx"), FileContext::Source);
+        for (file, package) in [
+            ("src/api.test.ts", "msw"),
+            ("src/api.test.ts", "vitest"),
+            ("src/api.ts", "zod"),
+            ("src/api.ts", "axios"),
+            ("src/App.tsx", "react"),
+        ] {
+            imports.add_import(Import {
+                file_path: PathBuf::from(file),
+                line: 1,
+                specifier: package.to_string(),
+                kind: ImportKind::EsModule,
+                resolved_package: Some(package.to_string()),
+            });
+        }
+
+        let peers = HashSet::from(["react".to_string()]);
+        let misplaced = DependencyGraph::new(&packages).find_misplaced(&imports, &peers);
+        let moves = plan_moves(&manifest, &misplaced);
+        let described: Vec<String> = moves.iter().map(|m| m.to_string()).collect();
+        assert_eq!(
+            described,
+            vec![
+                "move axios from devDependencies to dependencies",
+                "move msw from dependencies to devDependencies",
+            ]
+        );
+        assert_eq!(moves[1].reason(), "only imported by tests");
+
+        apply_actions(&mut manifest, &moves, Some(LockfileType::Npm));
+        assert_eq!(
+            manifest.render().unwrap(),
+            r#"{
+  "dependencies": {
+    "axios": "^1.0.0",
+    "zod": "^3.0.0"
+  },
+  "devDependencies": {
+    "msw": "^2.0.0",
+    "react": "^18.0.0",
+    "vitest": "^1.0.0"
+  },
+  "peerDependencies": {
+    "react": ">=17"
+  }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_pnpm_overrides() {
+        let mut manifest = manifest();
+        let actions = vec![FixAction::Override {
+            package: "qs".to_string(),
+            version: "6.11.0".to_string(),
+            reason: String::new(),
+        }];
+        apply_actions(&mut manifest, &actions, Some(LockfileType::Pnpm));
+        assert_eq!(manifest.value["pnpm"]["overrides"]["qs"], "6.11.0");
+    }
+
+    #[test]
+    fn test_dedupe_duplicates() {
+        use crate::rules::Rule;
+        use crate::types::{
+            DuplicateGroup, DuplicateSeverity, DuplicateStats, DuplicateVersion, SeverityScore,
+        };
+
+        let version = |version: &str, requirements: &[(&str, &str)]| DuplicateVersion {
+            version: version.to_string(),
+            dependents: requirements.iter().map(|(d, _)| d.to_string()).collect(),
+            transitive_count: 0,
+            requirements: requirements
+                .iter()
+                .map(|(d, range)| (d.to_string(), range.to_string()))
+                .collect(),
+        };
+        let group = |name: &str, versions: Vec<DuplicateVersion>| DuplicateGroup {
+            name: name.to_string(),
+            versions,
+            severity: DuplicateSeverity::Low,
+            rule: Rule::DuplicateVersion,
+            score: SeverityScore::default(),
+            singleton: None,
+            owners: vec![],
+        };
+        let analysis = DuplicateAnalysis {
+            duplicates: vec![
+                // ~4.17.0 rules out the newest copy, but all of them take 4.17.21
+                group(
+                    "lodash",
+                    vec![
+                        version("4.17.15", &[("a@1.0.0", "^4.17.0")]),
+                        version("4.17.21", &[("b@1.0.0", "~4.17.0")]),
+                        version("4.18.0", &[("c@1.0.0", "^4.17.0")]),
+                    ],
+                ),
+                // 0.x minors are separate release lines
+                group(
+                    "zero",
+                    vec![
+                        version("0.1.0", &[("a@1.0.0", "^0.1.0")]),
+                        version("0.2.0", &[("b@1.0.0", "^0.2.0")]),
+                    ],
+                ),
+                // An exact range can't move, but the other copy can move to it
+                group(
+                    "pinned",
+                    vec![
+                        version("1.0.0", &[("a@1.0.0", "1.0.0")]),
+                        version("1.2.0", &[("b@1.0.0", "^1.0.0")]),
+                    ],
+                ),
+                group(
+                    "fine",
+                    vec![
+                        version("2.0.0", &[("a@1.0.0", "^2.0.0")]),
+                        version("2.3.0", &[("b@1.0.0", "^2.1.0")]),
+                    ],
+                ),
+            ],
+            stats: DuplicateStats {
+                total_duplicates: 4,
+                critical_severity: 0,
+                high_severity: 0,
+                medium_severity: 0,
+                low_severity: 4,
+                extra_compile_units: 0,
+            },
+            types_skew: vec![],
+            peer_splits: vec![],
+            clusters: vec![],
+        };
+
+        let actions = dedupe_duplicates(&analysis);
+        let overrides: Vec<(&str, &str)> = actions
+            .iter()
+            .map(|action| match action {
+                FixAction::Override {
+                    package, version, ..
+                } => (package.as_str(), version.as_str()),
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            overrides,
+            vec![
+                ("lodash", "4.17.21"),
+                ("pinned", "1.0.0"),
+                ("fine", "2.3.0")
+            ]
+        );
+    }
+}
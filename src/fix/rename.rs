@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use miette::Result;
+
+use super::rewrite::{self, ImportRewrite};
+use crate::registry::Registries;
+use crate::types::{DeprecatedPackage, ImportMap, Package, PackageRename, RenamedFile};
+
+/// Packages whose maintainers moved them to a new name, for registries and
+/// lockfiles that don't record the deprecation
+const RENAMES: &[(&str, &str)] = &[
+    ("@hapi/joi", "joi"),
+    ("@material-ui/core", "@mui/material"),
+    ("@material-ui/icons", "@mui/icons-material"),
+    ("@material-ui/lab", "@mui/lab"),
+    ("@material-ui/styles", "@mui/styles"),
+    ("@zeit/ncc", "@vercel/ncc"),
+    ("babel-eslint", "@babel/eslint-parser"),
+    (
+        "eslint-plugin-typescript",
+        "@typescript-eslint/eslint-plugin",
+    ),
+    ("node-uuid", "uuid"),
+    ("now", "vercel"),
+    ("react-query", "@tanstack/react-query"),
+    ("typescript-eslint-parser", "@typescript-eslint/parser"),
+    ("vue-cli", "@vue/cli"),
+];
+
+/// Phrases deprecation messages use to point at a successor
+const SUCCESSOR_PHRASES: &[&str] = &[
+    "renamed to",
+    "moved to",
+    "replaced by",
+    "superseded by",
+    "in favor of",
+    "in favour of",
+    "switch to",
+    "migrate to",
+    "published as",
+    "please use",
+    "use",
+];
+
+/// Words that follow those phrases without naming a package
+const NOT_PACKAGES: &[&str] = &[
+    "a", "an", "the", "this", "that", "it", "its", "version", "latest", "native", "newer",
+];
+
+/// The package `name` was renamed to, from its deprecation message or the
+/// known renames
+pub fn successor(name: &str, deprecation: Option<&str>) -> Option<String> {
+    deprecation
+        .and_then(|message| successor_in_message(name, message))
+        .or_else(|| {
+            RENAMES
+                .iter()
+                .find(|(old, _)| *old == name)
+                .map(|(_, new)| new.to_string())
+        })
+}
+
+/// A quoted package name following "renamed to", "use ... instead" and
+/// the like
+///
+/// Unquoted words are prose as often as not ("use separate packages",
+/// "moved to github"), so only `'name'`, `"name"` and `` `name` `` count.
+fn successor_in_message(name: &str, message: &str) -> Option<String> {
+    let raw: Vec<&str> = message.split_whitespace().collect();
+    let words: Vec<String> = raw
+        .iter()
+        .map(|word| {
+            word.trim_matches(|c: char| matches!(c, '\'' | '"' | '`' | ',' | '.' | ';' | ':' | '!'))
+                .to_lowercase()
+        })
+        .collect();
+
+    for phrase in SUCCESSOR_PHRASES {
+        let phrase: Vec<&str> = phrase.split(' ').collect();
+        for start in 0..words.len() {
+            let end = start + phrase.len();
+            if end >= words.len() || words[start..end] != phrase[..] {
+                continue;
+            }
+            let candidate = &words[end];
+            // A bare "use" only names a successor in "use X instead"
+            if phrase == ["use"] && !words[end + 1..].iter().take(3).any(|w| w == "instead") {
+                continue;
+            }
+            if is_quoted(raw[end])
+                && is_package_name(candidate)
+                && candidate != name
+                && !NOT_PACKAGES.contains(&candidate.as_str())
+            {
+                return Some(candidate.clone());
+            }
+        }
+    }
+    None
+}
+
+/// Whether a word is wrapped in matching quotes or backticks, ignoring
+/// the punctuation after it
+fn is_quoted(word: &str) -> bool {
+    let word = word.trim_end_matches([',', '.', ';', ':', '!', ')']);
+    let mut chars = word.chars();
+    match (chars.next(), chars.next_back()) {
+        (Some(open), Some(close)) => open == close && matches!(open, '\'' | '"' | '`'),
+        _ => false,
+    }
+}
+
+/// `name` or `@scope/name` in the characters npm allows
+fn is_package_name(candidate: &str) -> bool {
+    let valid = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            && !part.starts_with('.')
+    };
+    match candidate.strip_prefix('@') {
+        Some(scoped) => scoped
+            .split_once('/')
+            .is_some_and(|(scope, name)| valid(scope) && valid(name)),
+        None => valid(candidate) && candidate.chars().any(|c| c.is_ascii_alphabetic()),
+    }
+}
+
+/// Imported packages with a successor under another name
+///
+/// Successors come from the deprecation message when the registry or
+/// lockfile has one, and from the known renames otherwise.
+pub fn plan_renames(imports: &ImportMap, deprecated: &[DeprecatedPackage]) -> Vec<PackageRename> {
+    let messages: HashMap<&str, &str> = deprecated
+        .iter()
+        .map(|d| (d.package.name.as_str(), d.message.as_str()))
+        .collect();
+
+    let mut used: Vec<&String> = imports
+        .imports_by_file()
+        .values()
+        .flatten()
+        .filter_map(|import| import.resolved_package.as_ref())
+        .collect();
+    used.sort();
+    used.dedup();
+
+    used.into_iter()
+        .filter_map(|name| {
+            let message = messages.get(name.as_str()).copied();
+            let to = successor(name, message)?;
+            Some(PackageRename {
+                from: name.clone(),
+                to,
+                reason: match message {
+                    Some(message) => message.to_string(),
+                    None => "renamed by its maintainers".to_string(),
+                },
+            })
+        })
+        .collect()
+}
+
+/// Split renames into those whose successor the npm registry serves and
+/// those whose successor it doesn't know
+///
+/// A deprecation message can quote something other than a package, so a
+/// successor is looked up before any import is rewritten to it.
+pub async fn check_published(
+    renames: Vec<PackageRename>,
+    registries: &Registries,
+    client: &reqwest::Client,
+) -> (Vec<PackageRename>, Vec<PackageRename>) {
+    let successors: Vec<Package> = renames
+        .iter()
+        .map(|rename| Package::new(&rename.to, ""))
+        .collect();
+    let successors: Vec<&Package> = successors.iter().collect();
+    let published = registries.fetch_all(client, &successors).await;
+    renames
+        .into_iter()
+        .partition(|rename| published.contains_key(&rename.to))
+}
+
+/// Rewrite the import specifiers of renamed packages, `old/sub/path`
+/// becoming `new/sub/path`
+///
+/// See `rewrite::rewrite_imports`; with `dry_run` nothing is written.
+pub fn rewrite_imports(
+    imports: &ImportMap,
+    renames: &[PackageRename],
+    dry_run: bool,
+) -> Result<Vec<RenamedFile>> {
+    let rewrites: Vec<ImportRewrite> = renames
+        .iter()
+        .map(|rename| ImportRewrite::new(&rename.from, &rename.to))
+        .collect::<Result<_>>()?;
+    rewrite::rewrite_imports(imports, &rewrites, dry_run)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::types::{Import, ImportKind};
+
+    #[test]
+    fn test_successor() {
+        assert_eq!(
+            successor("@hapi/joi", Some("Switch to 'joi' package")).as_deref(),
+            Some("joi")
+        );
+        assert_eq!(
+            successor("node-uuid", Some("Use `uuid` module instead")).as_deref(),
+            Some("uuid")
+        );
+        assert_eq!(
+            successor("x", Some("This package has been renamed to `@scope/x`.")).as_deref(),
+            Some("@scope/x")
+        );
+        // No package named in the message; the known renames still apply
+        assert_eq!(
+            successor("vue-cli", Some("Please use the latest version")).as_deref(),
+            Some("@vue/cli")
+        );
+        assert_eq!(
+            successor("request", Some("request has been deprecated")),
+            None
+        );
+        assert_eq!(
+            successor("tool", Some("Moved to https://example.com/tool")),
+            None
+        );
+        // Unquoted words after the phrases are prose, not packages
+        assert_eq!(
+            successor("lodash.pick", Some("Please use separate packages instead")),
+            None
+        );
+        assert_eq!(
+            successor("tool", Some("This project has moved to github")),
+            None
+        );
+        assert_eq!(
+            successor("tool", Some("Deprecated, use \"tool-ng\", instead")).as_deref(),
+            Some("tool-ng")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_imports() {
+        let root = TempDir::new("rename");
+        let file = root.join("app.js");
+        std::fs::write(
+            &file,
+            "import Button from '@material-ui/core/Button';\nconst core = require(\"@material-ui/core\");\nconst label = '@material-ui/core is old';\n",
+        )
+        .unwrap();
+
+        let mut imports = ImportMap::new();
+        for (line, specifier) in [(1, "@material-ui/core/Button"), (2, "@material-ui/core")] {
+            imports.add_import(Import {
+                file_path: file.clone(),
+                line,
+                specifier: specifier.to_string(),
+                kind: ImportKind::EsModule,
+                resolved_package: Some("@material-ui/core".to_string()),
+            });
+        }
+
+        let renames = plan_renames(&imports, &[]);
+        assert_eq!(renames.len(), 1);
+        assert_eq!(renames[0].to, "@mui/material");
+
+        let files = rewrite_imports(&imports, &renames, false).unwrap();
+        let content = std::fs::read_to_string(&file).unwrap();
+
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].rewritten, 2);
+        assert_eq!(
+            content,
+            "import Button from '@mui/material/Button';\nconst core = require(\"@mui/material\");\nconst label = '@material-ui/core is old';\n"
+        );
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+
+use crate::analyzer::{extract_package_name, ImportExtractor};
+use crate::types::{ImportMap, RenamedFile};
+
+/// Point imports of one package, or one of its subpaths, at another
+///
+/// `lodash` -> `lodash-es` turns `lodash/debounce` into
+/// `lodash-es/debounce`; `lodash/fp` -> `ramda` only touches `lodash/fp`
+/// and what's below it.
+#[derive(Debug, Clone)]
+pub struct ImportRewrite {
+    pub from: String,
+    pub to: String,
+}
+
+impl ImportRewrite {
+    pub fn new(from: &str, to: &str) -> Result<Self> {
+        for specifier in [from, to] {
+            if extract_package_name(specifier).is_none() {
+                bail!("{} isn't a package specifier", specifier);
+            }
+        }
+        Ok(Self {
+            from: from.trim_end_matches('/').to_string(),
+            to: to.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// The new specifier, when `specifier` is `from` or below it
+    fn apply(&self, specifier: &str) -> Option<String> {
+        let rest = specifier.strip_prefix(self.from.as_str())?;
+        (rest.is_empty() || rest.starts_with('/')).then(|| format!("{}{}", self.to, rest))
+    }
+}
+
+/// Rewrite matching import, export, `require()` and `import()` specifiers
+/// in every file the analysis found importing the packages
+///
+/// Files are parsed again and only the specifiers' string literals are
+/// replaced, keeping their quotes; strings and comments that merely mention
+/// a package stay untouched. With `dry_run` nothing is written.
+pub fn rewrite_imports(
+    imports: &ImportMap,
+    rewrites: &[ImportRewrite],
+    dry_run: bool,
+) -> Result<Vec<RenamedFile>> {
+    let mut paths: BTreeSet<&PathBuf> = BTreeSet::new();
+    for rewrite in rewrites {
+        let Some(package) = extract_package_name(&rewrite.from) else {
+            continue;
+        };
+        for usage in imports.get_package_usages(&package).into_iter().flatten() {
+            paths.insert(&usage.file_path);
+        }
+    }
+
+    let mut files = Vec::new();
+    for path in paths {
+        let original = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let Some((updated, rewritten)) = rewrite_source(path, &original, rewrites)? else {
+            continue;
+        };
+
+        if !dry_run {
+            std::fs::write(path, &updated)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        files.push(RenamedFile {
+            path: path.clone(),
+            rewritten,
+            original,
+            updated,
+        });
+    }
+
+    Ok(files)
+}
+
+/// The source with matching specifiers replaced and how many were, or
+/// `None` when nothing matched
+fn rewrite_source(
+    path: &Path,
+    source: &str,
+    rewrites: &[ImportRewrite],
+) -> Result<Option<(String, usize)>> {
+    let mut specifiers = ImportExtractor::new(path, source).extract_all()?.specifiers;
+    specifiers.sort_by_key(|s| s.start);
+
+    let mut updated = String::with_capacity(source.len());
+    let mut copied = 0;
+    let mut rewritten = 0;
+    for span in specifiers {
+        let Some(new) = rewrites.iter().find_map(|r| r.apply(&span.specifier)) else {
+            continue;
+        };
+        // Keep the literal's own quotes
+        let (start, end) = (span.start as usize + 1, span.end as usize - 1);
+        if start < copied || end < start {
+            continue;
+        }
+        updated.push_str(&source[copied..start]);
+        updated.push_str(&new);
+        copied = end;
+        rewritten += 1;
+    }
+    if rewritten == 0 {
+        return Ok(None);
+    }
+    updated.push_str(&source[copied..]);
+    Ok(Some((updated, rewritten)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_source() {
+        let source = r#"import debounce from "lodash/debounce";
+import { map } from 'lodash';
+import fp from 'lodash/fp';
+export * from "lodash-es";
+const lazy = () => import('lodash/throttle');
+const _ = require('lodash');
+// require('lodash') in a comment
+const name = 'lodash';
+"#;
+        let rewrites = vec![
+            ImportRewrite::new("lodash/fp", "ramda").unwrap(),
+            ImportRewrite::new("lodash", "lodash-es").unwrap(),
+        ];
+        let (updated, rewritten) = rewrite_source(Path::new("a.js"), source, &rewrites)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(rewritten, 5);
+        assert_eq!(
+            updated,
+            r#"import debounce from "lodash-es/debounce";
+import { map } from 'lodash-es';
+import fp from 'ramda';
+export * from "lodash-es";
+const lazy = () => import('lodash-es/throttle');
+const _ = require('lodash-es');
+// require('lodash') in a comment
+const name = 'lodash';
+"#
+        );
+
+        assert!(ImportRewrite::new("./local", "lodash").is_err());
+    }
+}
@@ -1,9 +1,16 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
 
 use petgraph::graph::{DiGraph, NodeIndex};
 use petgraph::Direction;
 
-use crate::types::{Package, PackageExplanation, PackageUsage, UsageAnalysis};
+use crate::analyzer::commands;
+use crate::categories;
+use crate::types::{
+    Classification, ClassificationReason, CommandInvocation, ExpectedUnusedRule, FileContext,
+    GraphAdvisory, GraphEdge, GraphExport, GraphNode, Import, ImportKind, ImportMap,
+    MisplacedDependency, Package, PackageExplanation, PackageSource, PackageUsage, PeerDependent,
+    PhantomDependency, TypeOnlyDependency, UsageAnalysis, UsageClass, Vulnerability,
+};
 
 /// Dependency graph for analyzing package relationships
 pub struct DependencyGraph {
@@ -59,6 +66,9 @@ impl DependencyGraph {
         let mut dev_only = Vec::new();
         let mut unused_direct = Vec::new();
         let mut expected_unused_direct = Vec::new();
+        let mut other_platform = Vec::new();
+
+        let used_packages = &self.package_keys(used_packages);
 
         // Get all packages that are transitively required by used packages
         let transitively_used = self.get_transitive_dependencies(used_packages);
@@ -71,25 +81,29 @@ impl DependencyGraph {
 
             let is_used = used_packages.contains(name) || transitively_used.contains(name);
 
-            if is_used {
-                let import_count = if used_packages.contains(name) { 1 } else { 0 };
-                used.push(PackageUsage {
-                    package: pkg.clone(),
-                    import_count,
-                    files: Vec::new(),
-                });
-            } else if is_expected_unused(name) {
-                // This package is not imported but that's expected (build tool, types, etc.)
-                expected_unused.push(pkg.clone());
-                if pkg.is_direct {
-                    expected_unused_direct.push(pkg.clone());
+            match usage_class(pkg, is_used) {
+                UsageClass::OtherPlatform => other_platform.push(pkg.clone()),
+                UsageClass::FollowsParent => continue,
+                UsageClass::Used => {
+                    let import_count = if used_packages.contains(name) { 1 } else { 0 };
+                    used.push(PackageUsage {
+                        package: pkg.clone(),
+                        import_count,
+                        files: Vec::new(),
+                    });
                 }
-            } else if pkg.is_dev && !pkg.is_direct {
-                dev_only.push(pkg.clone());
-            } else {
-                unused.push(pkg.clone());
-                if pkg.is_direct {
-                    unused_direct.push(pkg.clone());
+                UsageClass::ExpectedUnused => {
+                    expected_unused.push(pkg.clone());
+                    if pkg.is_direct {
+                        expected_unused_direct.push(pkg.clone());
+                    }
+                }
+                UsageClass::DevOnly => dev_only.push(pkg.clone()),
+                UsageClass::Unused => {
+                    unused.push(pkg.clone());
+                    if pkg.is_direct {
+                        unused_direct.push(pkg.clone());
+                    }
                 }
             }
         }
@@ -99,8 +113,20 @@ impl DependencyGraph {
         unused_direct.sort_by(|a, b| a.name.cmp(&b.name));
         expected_unused.sort_by(|a, b| a.name.cmp(&b.name));
         expected_unused_direct.sort_by(|a, b| a.name.cmp(&b.name));
+        other_platform.sort_by(|a, b| a.name.cmp(&b.name));
         used.sort_by(|a, b| a.package.name.cmp(&b.package.name));
 
+        let removable_transitive = unused_direct
+            .iter()
+            .map(|pkg| (pkg.name.clone(), self.exclusive_dependencies(&pkg.name)))
+            .collect();
+
+        let overlapping = categories::find_overlaps(
+            self.packages
+                .values()
+                .filter(|pkg| pkg.is_direct && (include_dev || !pkg.is_dev)),
+        );
+
         UsageAnalysis {
             used,
             unused,
@@ -108,9 +134,317 @@ impl DependencyGraph {
             dev_only,
             unused_direct,
             expected_unused_direct,
+            overlapping,
+            phantom: Vec::new(),
+            misplaced: Vec::new(),
+            type_only: Vec::new(),
+            removable_transitive,
+            other_platform,
         }
     }
 
+    /// Classify one package the way `analyze_usage` does, and say why
+    ///
+    /// `used_packages` are the imported and invoked packages; the import
+    /// and command sites are looked up in `imports`. Cargo packages are
+    /// classified once per installed version.
+    pub fn classify(
+        &self,
+        name: &str,
+        used_packages: &HashSet<String>,
+        imports: &ImportMap,
+    ) -> Vec<Classification> {
+        let used_packages = &self.package_keys(used_packages);
+        let transitively_used = self.get_transitive_dependencies(used_packages);
+
+        let mut keys: Vec<String> = self
+            .package_keys(&HashSet::from([name.to_string()]))
+            .into_iter()
+            .collect();
+        keys.sort();
+        keys.iter()
+            .filter_map(|key| self.classify_key(key, used_packages, &transitively_used, imports))
+            .collect()
+    }
+
+    fn classify_key(
+        &self,
+        key: &str,
+        used_packages: &HashSet<String>,
+        transitively_used: &HashSet<String>,
+        imports: &ImportMap,
+    ) -> Option<Classification> {
+        let pkg = self.packages.get(key)?;
+        let name = pkg.name.as_str();
+        let class = usage_class(pkg, transitively_used.contains(key));
+
+        let reason = match class {
+            UsageClass::Used => {
+                let invoked: Vec<CommandInvocation> = imports
+                    .commands()
+                    .iter()
+                    .filter(|cmd| {
+                        commands::resolve_invoked_packages(
+                            std::slice::from_ref(*cmd),
+                            &self.packages,
+                        )
+                        .contains(name)
+                    })
+                    .cloned()
+                    .collect();
+                match imports.get_package_usages(name) {
+                    Some(sites) if !sites.is_empty() => {
+                        let mut sites = sites.clone();
+                        sites.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+                        ClassificationReason::Imported(sites)
+                    }
+                    _ if !invoked.is_empty() => ClassificationReason::Invoked(invoked),
+                    _ => ClassificationReason::RequiredBy(
+                        self.chain_from(used_packages, key).unwrap_or_default(),
+                    ),
+                }
+            }
+            UsageClass::ExpectedUnused => {
+                ClassificationReason::Expected(expected_unused_rule(pkg)?)
+            }
+            UsageClass::DevOnly => ClassificationReason::DevTransitive,
+            UsageClass::Unused => ClassificationReason::NotReferenced,
+            UsageClass::OtherPlatform => ClassificationReason::Platform,
+            UsageClass::FollowsParent => ClassificationReason::Parent(
+                pkg.platform_parent
+                    .clone()
+                    .or_else(|| pkg.bundled_in.clone())
+                    .unwrap_or_default(),
+            ),
+        };
+
+        Some(Classification {
+            package: pkg.clone(),
+            class,
+            reason,
+            suppressed_by: None,
+        })
+    }
+
+    /// Shortest chain from one of `roots` down to `target`
+    fn chain_from(&self, roots: &HashSet<String>, target: &str) -> Option<Vec<String>> {
+        let mut parents: HashMap<NodeIndex, Option<NodeIndex>> = HashMap::new();
+        let mut queue: VecDeque<NodeIndex> = VecDeque::new();
+
+        let mut sorted_roots: Vec<&String> = roots.iter().collect();
+        sorted_roots.sort();
+        for name in sorted_roots {
+            if let Some(&idx) = self.node_indices.get(name) {
+                parents.insert(idx, None);
+                queue.push_back(idx);
+            }
+        }
+
+        while let Some(idx) = queue.pop_front() {
+            if self.graph[idx] == target {
+                let mut chain = vec![self.graph[idx].clone()];
+                let mut current = idx;
+                while let Some(Some(parent)) = parents.get(&current) {
+                    chain.push(self.graph[*parent].clone());
+                    current = *parent;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for neighbor in self.graph.neighbors_directed(idx, Direction::Outgoing) {
+                if let std::collections::hash_map::Entry::Vacant(entry) = parents.entry(neighbor) {
+                    entry.insert(Some(idx));
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Number of packages reachable only through the given direct dependency
+    fn exclusive_dependencies(&self, name: &str) -> usize {
+        let others: HashSet<String> = self
+            .packages
+            .iter()
+            .filter(|(_, pkg)| pkg.is_direct && pkg.name != name)
+            .map(|(key, _)| key.clone())
+            .collect();
+        let kept = self.get_transitive_dependencies(&others);
+
+        let roots = self.package_keys(&HashSet::from([name.to_string()]));
+        let own = self.get_transitive_dependencies(&roots);
+        own.iter()
+            .filter(|dep| !roots.contains(*dep) && !kept.contains(*dep))
+            .count()
+    }
+
+    /// Packages imported in source code that aren't direct dependencies
+    ///
+    /// Remote modules are imported by URL, which pins them, so they never count.
+    pub fn find_phantom(&self, imports: &ImportMap) -> Vec<PhantomDependency> {
+        let mut phantom: Vec<PhantomDependency> = imports
+            .packages_used()
+            .into_iter()
+            .filter(|name| {
+                !self
+                    .packages_named(name)
+                    .any(|pkg| pkg.is_direct || pkg.source == PackageSource::Remote)
+            })
+            .filter_map(|name| {
+                let mut usages = imports.get_package_usages(&name)?.clone();
+                usages.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+                let first = usages.first()?;
+                let installed_version =
+                    self.packages_named(&name).next().map(|p| p.version.clone());
+                Some(PhantomDependency {
+                    installed_version,
+                    file_path: first.file_path.clone(),
+                    line: first.line,
+                    imports: usages,
+                    name,
+                })
+            })
+            .collect();
+
+        phantom.sort_by(|a, b| a.name.cmp(&b.name));
+        phantom
+    }
+
+    /// Direct dependencies declared in the wrong section for where they're
+    /// imported
+    ///
+    /// Only files the JS/TS analysis gave a context count. Type-only imports
+    /// are erased by the compiler, so shipped code may take them from a dev
+    /// dependency. Dev dependencies the project also lists in `peers` are
+    /// what a library develops against and stay put, and optional
+    /// dependencies have a section of their own.
+    pub fn find_misplaced(
+        &self,
+        imports: &ImportMap,
+        peers: &HashSet<String>,
+    ) -> Vec<MisplacedDependency> {
+        let mut misplaced: Vec<MisplacedDependency> = self
+            .packages
+            .values()
+            .filter(|pkg| pkg.is_direct && !pkg.is_optional && !peers.contains(&pkg.name))
+            .filter_map(|pkg| {
+                let mut sites: Vec<(&Import, FileContext)> = imports
+                    .get_package_usages(&pkg.name)?
+                    .iter()
+                    .filter_map(|import| Some((import, imports.file_context(&import.file_path)?)))
+                    .collect();
+                let shipped = |(import, context): &(&Import, FileContext)| {
+                    *context == FileContext::Source && import.kind != ImportKind::Type
+                };
+                if pkg.is_dev {
+                    sites.retain(shipped);
+                } else if sites
+                    .iter()
+                    .any(|(_, context)| *context == FileContext::Source)
+                {
+                    return None;
+                }
+                if sites.is_empty() {
+                    return None;
+                }
+
+                sites.sort_by(|a, b| (&a.0.file_path, a.0.line).cmp(&(&b.0.file_path, b.0.line)));
+                Some(MisplacedDependency {
+                    package: pkg.clone(),
+                    imports: sites.iter().map(|(import, _)| (*import).clone()).collect(),
+                    contexts: sites.iter().map(|(_, context)| *context).collect(),
+                })
+            })
+            .collect();
+
+        misplaced.sort_by(|a, b| a.package.name.cmp(&b.package.name));
+        misplaced
+    }
+
+    /// Runtime dependencies that shipped code imports, but only as types
+    ///
+    /// Ones no shipped code imports at all are left to `find_misplaced`.
+    pub fn find_type_only(
+        &self,
+        imports: &ImportMap,
+        peers: &HashSet<String>,
+    ) -> Vec<TypeOnlyDependency> {
+        let mut type_only: Vec<TypeOnlyDependency> = self
+            .packages
+            .values()
+            .filter(|pkg| {
+                pkg.is_direct && !pkg.is_dev && !pkg.is_optional && !peers.contains(&pkg.name)
+            })
+            .filter_map(|pkg| {
+                let usages = imports.get_package_usages(&pkg.name)?;
+                let shipped = usages.iter().any(|import| {
+                    imports.file_context(&import.file_path) == Some(FileContext::Source)
+                });
+                if !shipped || usages.iter().any(|import| import.kind != ImportKind::Type) {
+                    return None;
+                }
+
+                let mut imports = usages.clone();
+                imports.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+                Some(TypeOnlyDependency {
+                    package: pkg.clone(),
+                    imports,
+                })
+            })
+            .collect();
+
+        type_only.sort_by(|a, b| a.package.name.cmp(&b.package.name));
+        type_only
+    }
+
+    /// Graph keys of the given package names
+    ///
+    /// Imports name a package, while Cargo packages are keyed by
+    /// "name@version", one per installed version.
+    fn package_keys(&self, names: &HashSet<String>) -> HashSet<String> {
+        let mut keys: HashSet<String> = names
+            .iter()
+            .filter(|name| self.packages.contains_key(*name))
+            .cloned()
+            .collect();
+        keys.extend(
+            self.packages
+                .iter()
+                .filter(|(key, pkg)| *key != &pkg.name && names.contains(&pkg.name))
+                .map(|(key, _)| key.clone()),
+        );
+        keys
+    }
+
+    /// The package keyed by `name`, or every installed version of it
+    fn packages_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Package> + 'a {
+        let keyed = self.packages.get(name);
+        keyed.into_iter().chain(
+            self.packages
+                .values()
+                .filter(move |pkg| keyed.is_none() && pkg.name == name),
+        )
+    }
+
+    /// The direct dependencies whose trees include each package, by name
+    ///
+    /// A direct dependency is among its own.
+    pub fn direct_owners(&self) -> HashMap<String, BTreeSet<String>> {
+        let mut owners: HashMap<String, BTreeSet<String>> = HashMap::new();
+        for (key, pkg) in self.packages.iter().filter(|(_, pkg)| pkg.is_direct) {
+            for dep in self.get_transitive_dependencies(&HashSet::from([key.clone()])) {
+                if let Some(dep_pkg) = self.packages.get(&dep) {
+                    owners
+                        .entry(dep_pkg.name.clone())
+                        .or_default()
+                        .insert(pkg.name.clone());
+                }
+            }
+        }
+        owners
+    }
+
     /// Get all packages that are transitive dependencies of the given packages
     fn get_transitive_dependencies(&self, roots: &HashSet<String>) -> HashSet<String> {
         let mut visited = HashSet::new();
@@ -147,15 +481,46 @@ impl DependencyGraph {
         let chains = self.find_dependency_chains(*pkg_idx);
 
         let is_dev_path = chains.iter().any(|chain| {
-            chain.first().is_some_and(|root| {
-                self.packages.get(root).is_some_and(|p| p.is_dev)
-            })
+            chain
+                .first()
+                .is_some_and(|root| self.packages.get(root).is_some_and(|p| p.is_dev))
         });
 
+        let chain_ranges = chains
+            .iter()
+            .map(|chain| {
+                let mut ranges = vec![None];
+                ranges.extend(chain.windows(2).map(|hop| {
+                    self.packages
+                        .get(&hop[0])
+                        .and_then(|parent| parent.dependency_ranges.get(&hop[1]))
+                        .cloned()
+                }));
+                ranges
+            })
+            .collect();
+
+        let mut peer_of: Vec<PeerDependent> = self
+            .packages
+            .values()
+            .filter_map(|dependent| {
+                dependent
+                    .peer_dependencies
+                    .get(package_name)
+                    .map(|range| PeerDependent {
+                        package: dependent.name.clone(),
+                        range: range.clone(),
+                    })
+            })
+            .collect();
+        peer_of.sort_by(|a, b| a.package.cmp(&b.package));
+
         Some(PackageExplanation {
             package: pkg.clone(),
             dependency_chains: chains,
+            chain_ranges,
             is_dev_path,
+            peer_of,
         })
     }
 
@@ -165,11 +530,7 @@ impl DependencyGraph {
         let target_name = &self.graph[target];
 
         // If it's a direct dependency, return a single-element chain
-        if self
-            .packages
-            .get(target_name)
-            .is_some_and(|p| p.is_direct)
-        {
+        if self.packages.get(target_name).is_some_and(|p| p.is_direct) {
             return vec![vec![target_name.clone()]];
         }
 
@@ -217,7 +578,53 @@ impl DependencyGraph {
         chains
     }
 
+    /// Nodes and edges of the graph, with duplicated packages and the
+    /// advisories in `vulnerabilities` marked on their nodes
+    pub fn export(&self, vulnerabilities: &[Vulnerability]) -> GraphExport {
+        let mut versions: HashMap<&str, HashSet<&str>> = HashMap::new();
+        for pkg in self.packages.values() {
+            versions.entry(&pkg.name).or_default().insert(&pkg.version);
+        }
+
+        let mut nodes: Vec<GraphNode> = self
+            .packages
+            .iter()
+            .map(|(id, pkg)| GraphNode {
+                id: id.clone(),
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                direct: pkg.is_direct,
+                dev: pkg.is_dev,
+                duplicate: versions[pkg.name.as_str()].len() > 1,
+                vulnerabilities: vulnerabilities
+                    .iter()
+                    .filter(|v| v.package_name == pkg.name && v.installed_version == pkg.version)
+                    .map(|v| GraphAdvisory {
+                        id: v.id.clone(),
+                        severity: v.severity,
+                    })
+                    .collect(),
+            })
+            .collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut edges: Vec<GraphEdge> = self
+            .graph
+            .edge_indices()
+            .filter_map(|edge| self.graph.edge_endpoints(edge))
+            .map(|(from, to)| GraphEdge {
+                from: self.graph[from].clone(),
+                to: self.graph[to].clone(),
+            })
+            .collect();
+        edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+        edges.dedup();
+
+        GraphExport { nodes, edges }
+    }
+
     /// Get a package by name
+    #[allow(dead_code)]
     pub fn get_package(&self, name: &str) -> Option<&Package> {
         self.packages.get(name)
     }
@@ -228,25 +635,55 @@ impl DependencyGraph {
     }
 
     /// Get count of all packages
+    #[allow(dead_code)]
     pub fn package_count(&self) -> usize {
         self.packages.len()
     }
 
     /// Get count of direct dependencies
+    #[allow(dead_code)]
     pub fn direct_count(&self) -> usize {
         self.packages.values().filter(|p| p.is_direct).count()
     }
 }
 
-/// Check if a package is expected to not be imported directly.
-/// These are dev/build tools, type definitions, and similar packages.
-fn is_expected_unused(name: &str) -> bool {
+/// Group `analyze_usage` puts a package in, given whether anything uses it
+fn usage_class(pkg: &Package, is_used: bool) -> UsageClass {
+    if !is_used && ((pkg.is_optional && !pkg.supports_platform()) || pkg.other_target.is_some()) {
+        // A native fallback for another OS/CPU is never installed here, and
+        // a Cargo dependency for another target never built
+        UsageClass::OtherPlatform
+    } else if !is_used && (pkg.platform_parent.is_some() || pkg.bundled_in.is_some()) {
+        // Platform builds and bundled copies go wherever their parent goes
+        UsageClass::FollowsParent
+    } else if is_used {
+        UsageClass::Used
+    } else if expected_unused_rule(pkg).is_some() {
+        // This package is not imported but that's expected (build tool, types, optional, etc.)
+        UsageClass::ExpectedUnused
+    } else if pkg.is_dev && !pkg.is_direct {
+        UsageClass::DevOnly
+    } else {
+        UsageClass::Unused
+    }
+}
+
+/// Check if a package is expected to not be imported directly, and by which rule.
+/// These are dev/build tools, type definitions, and similar packages, plus
+/// optional dependencies, which may fail to install on some platforms
+/// (e.g. `fsevents`) and so are loaded behind a guard if at all.
+fn expected_unused_rule(pkg: &Package) -> Option<ExpectedUnusedRule> {
+    let name = pkg.name.as_str();
+
     // TypeScript type definitions
     if name.starts_with("@types/") {
-        return true;
+        return Some(ExpectedUnusedRule::TypeDefinitions);
     }
 
-    // Known build tools and dev utilities that are never imported
+    // Known build tools and dev utilities that are never imported. CLIs
+    // only ever run from package.json scripts (rimraf, concurrently,
+    // patch-package) aren't listed: the scripts resolve them through their
+    // `bin`, so one no script runs is unused
     const EXPECTED_UNUSED_EXACT: &[&str] = &[
         // TypeScript
         "typescript",
@@ -291,22 +728,6 @@ fn is_expected_unused(name: &str) -> bool {
         "cypress",
         "@playwright/test",
         "uvu",
-        // Dev servers & watchers
-        "nodemon",
-        "ts-node-dev",
-        "tsnd",
-        "concurrently",
-        "npm-run-all",
-        "npm-run-all2",
-        "cross-env",
-        "wait-on",
-        // File utilities
-        "rimraf",
-        "del-cli",
-        "copyfiles",
-        "cpy-cli",
-        "mkdirp",
-        "shx",
         // Git hooks & commits
         "husky",
         "lint-staged",
@@ -315,17 +736,8 @@ fn is_expected_unused(name: &str) -> bool {
         "lefthook",
         // Versioning & Release
         "semantic-release",
-        "release-it",
-        "standard-version",
-        "bumpp",
-        "changelogithub",
-        "changelogen",
-        "np",
         "lerna",
         "changeset",
-        // Patching
-        "patch-package",
-        "pnpm-patch",
         // Documentation
         "typedoc",
         "jsdoc",
@@ -338,10 +750,24 @@ fn is_expected_unused(name: &str) -> bool {
         "arethetypeswrong",
         "knip",
         "depcheck",
+        // Python tooling
+        "pytest",
+        "black",
+        "ruff",
+        "mypy",
+        "flake8",
+        "pylint",
+        "isort",
+        "pre-commit",
+        "tox",
+        "nox",
+        "coverage",
+        "twine",
+        "wheel",
     ];
 
     if EXPECTED_UNUSED_EXACT.contains(&name) {
-        return true;
+        return Some(ExpectedUnusedRule::KnownTool);
     }
 
     // Patterns - packages that match these prefixes are expected unused
@@ -359,20 +785,27 @@ fn is_expected_unused(name: &str) -> bool {
         "@testing-library/",
         "@vitest/",
         "prettier-plugin-",
+        // Python stubs and plugins
+        "types-",
+        "pytest-",
+        "flake8-",
     ];
 
-    for prefix in EXPECTED_UNUSED_PREFIXES {
-        if name.starts_with(prefix) {
-            return true;
-        }
+    if let Some(prefix) = EXPECTED_UNUSED_PREFIXES
+        .iter()
+        .find(|prefix| name.starts_with(*prefix))
+    {
+        return Some(ExpectedUnusedRule::Prefix(prefix));
     }
 
-    false
+    pkg.is_optional.then_some(ExpectedUnusedRule::Optional)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::{Import, ImportKind, Severity};
+    use std::path::PathBuf;
 
     fn create_test_packages() -> HashMap<String, Package> {
         let mut packages = HashMap::new();
@@ -383,6 +816,11 @@ mod tests {
                 .direct()
                 .with_dependencies(vec!["body-parser".to_string()]),
         );
+        packages
+            .get_mut("express")
+            .unwrap()
+            .dependency_ranges
+            .insert("body-parser".to_string(), "~1.20.0".to_string());
 
         packages.insert(
             "body-parser".to_string(),
@@ -426,5 +864,429 @@ mod tests {
         // The chain should be: express -> body-parser -> raw-body
         let chain = &explanation.dependency_chains[0];
         assert_eq!(chain, &vec!["express", "body-parser", "raw-body"]);
+
+        assert_eq!(
+            explanation.chain_ranges[0],
+            vec![None, Some("~1.20.0".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn test_find_phantom() {
+        let packages = create_test_packages();
+        let graph = DependencyGraph::new(&packages);
+
+        let mut imports = ImportMap::new();
+        for (line, specifier) in ["express", "body-parser", "left-pad"].iter().enumerate() {
+            imports.add_import(Import {
+                file_path: PathBuf::from("src/index.js"),
+                line: line + 1,
+                specifier: specifier.to_string(),
+                kind: ImportKind::EsModule,
+                resolved_package: Some(specifier.to_string()),
+            });
+        }
+        imports.add_import(Import {
+            file_path: PathBuf::from("src/app.js"),
+            line: 7,
+            specifier: "body-parser/json".to_string(),
+            kind: ImportKind::CommonJs,
+            resolved_package: Some("body-parser".to_string()),
+        });
+
+        let phantom = graph.find_phantom(&imports);
+        let found: Vec<(&str, Option<&str>, usize)> = phantom
+            .iter()
+            .map(|p| (p.name.as_str(), p.installed_version.as_deref(), p.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("body-parser", Some("1.20.0"), 7), ("left-pad", None, 3)]
+        );
+        let locations: Vec<(&str, usize)> = phantom[0]
+            .imports
+            .iter()
+            .map(|i| (i.file_path.to_str().unwrap(), i.line))
+            .collect();
+        assert_eq!(locations, vec![("src/app.js", 7), ("src/index.js", 2)]);
+    }
+
+    #[test]
+    fn test_find_misplaced() {
+        let mut packages = create_test_packages();
+        for (name, dev) in [
+            ("supertest", false),
+            ("zod", true),
+            ("vite", true),
+            ("react", true),
+        ] {
+            let mut pkg = Package::new(name, "1.0.0").direct();
+            pkg.is_dev = dev;
+            packages.insert(name.to_string(), pkg);
+        }
+        let graph = DependencyGraph::new(&packages);
+
+        let mut imports = ImportMap::new();
+        for (file, context) in [
+            ("src/app.ts", FileContext::Source),
+            ("src/app.test.ts", FileContext::Test),
+            ("vite.config.ts", FileContext::Config),
+        ] {
+            imports.set_file_context(PathBuf::from(file), context);
+        }
+        for (file, package, kind) in [
+            ("src/app.ts", "express", ImportKind::EsModule),
+            ("src/app.ts", "zod", ImportKind::EsModule),
+            ("src/app.ts", "vite", ImportKind::Type),
+            ("src/app.ts", "react", ImportKind::EsModule),
+            ("src/app.test.ts", "express", ImportKind::EsModule),
+            ("src/app.test.ts", "supertest", ImportKind::EsModule),
+            ("src/app.test.ts", "zod", ImportKind::EsModule),
+            ("vite.config.ts", "vite", ImportKind::EsModule),
+            // Files outside the JS/TS analysis have no context
+            ("build.rs", "unused-pkg", ImportKind::Use),
+        ] {
+            imports.add_import(Import {
+                file_path: PathBuf::from(file),
+                line: 1,
+                specifier: package.to_string(),
+                kind,
+                resolved_package: Some(package.to_string()),
+            });
+        }
+
+        let peers = HashSet::from(["react".to_string()]);
+        let misplaced = graph.find_misplaced(&imports, &peers);
+        let found: Vec<(&str, bool, Vec<FileContext>)> = misplaced
+            .iter()
+            .map(|m| {
+                (
+                    m.package.name.as_str(),
+                    m.should_be_dev(),
+                    m.contexts.clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("supertest", true, vec![FileContext::Test]),
+                ("zod", false, vec![FileContext::Source]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_find_type_only() {
+        let mut packages = create_test_packages();
+        for name in ["type-fest", "test-types", "zod"] {
+            packages.insert(name.to_string(), Package::new(name, "1.0.0").direct());
+        }
+        let graph = DependencyGraph::new(&packages);
+
+        let mut imports = ImportMap::new();
+        imports.set_file_context(PathBuf::from("src/app.ts"), FileContext::Source);
+        imports.set_file_context(PathBuf::from("src/app.test.ts"), FileContext::Test);
+        for (file, package, kind) in [
+            ("src/app.ts", "type-fest", ImportKind::Type),
+            ("src/app.ts", "zod", ImportKind::Type),
+            ("src/app.ts", "zod", ImportKind::EsModule),
+            ("src/app.test.ts", "type-fest", ImportKind::Type),
+            ("src/app.test.ts", "test-types", ImportKind::Type),
+        ] {
+            imports.add_import(Import {
+                file_path: PathBuf::from(file),
+                line: 1,
+                specifier: package.to_string(),
+                kind,
+                resolved_package: Some(package.to_string()),
+            });
+        }
+
+        // test-types is only imported by tests, which find_misplaced reports
+        let type_only = graph.find_type_only(&imports, &HashSet::new());
+        assert_eq!(type_only.len(), 1);
+        assert_eq!(type_only[0].package.name, "type-fest");
+        assert_eq!(type_only[0].imports.len(), 2);
+        assert_eq!(
+            type_only[0].imports[0].file_path,
+            PathBuf::from("src/app.test.ts")
+        );
+    }
+
+    #[test]
+    fn test_classify() {
+        let mut packages = create_test_packages();
+        packages.insert(
+            "@types/node".to_string(),
+            Package::new("@types/node", "20.0.0").direct().dev(),
+        );
+        packages.insert(
+            "eslint-plugin-react".to_string(),
+            Package::new("eslint-plugin-react", "7.0.0").direct().dev(),
+        );
+        let mut del = Package::new("del-cli", "5.0.0").direct().dev();
+        del.bins = vec!["del".to_string()];
+        packages.insert("del-cli".to_string(), del);
+        packages.insert(
+            "concurrently".to_string(),
+            Package::new("concurrently", "8.0.0").direct().dev(),
+        );
+        let graph = DependencyGraph::new(&packages);
+
+        let mut imports = ImportMap::new();
+        imports.add_import(Import {
+            file_path: PathBuf::from("src/index.js"),
+            line: 1,
+            specifier: "express".to_string(),
+            kind: ImportKind::EsModule,
+            resolved_package: Some("express".to_string()),
+        });
+        imports.add_command(CommandInvocation {
+            file_path: PathBuf::from("package.json"),
+            line: 4,
+            binary: "del".to_string(),
+        });
+        let mut used = imports.packages_used();
+        used.extend(commands::resolve_invoked_packages(
+            imports.commands(),
+            &packages,
+        ));
+
+        let express = graph.classify("express", &used, &imports).remove(0);
+        assert_eq!(express.class, UsageClass::Used);
+        assert!(
+            matches!(express.reason, ClassificationReason::Imported(ref sites) if sites.len() == 1)
+        );
+
+        let raw_body = graph.classify("raw-body", &used, &imports).remove(0);
+        assert!(matches!(
+            raw_body.reason,
+            ClassificationReason::RequiredBy(ref chain) if chain == &["express", "body-parser", "raw-body"]
+        ));
+
+        let types = graph.classify("@types/node", &used, &imports).remove(0);
+        assert_eq!(types.class, UsageClass::ExpectedUnused);
+        assert!(matches!(
+            types.reason,
+            ClassificationReason::Expected(ExpectedUnusedRule::TypeDefinitions)
+        ));
+        let plugin = graph
+            .classify("eslint-plugin-react", &used, &imports)
+            .remove(0);
+        assert!(matches!(
+            plugin.reason,
+            ClassificationReason::Expected(ExpectedUnusedRule::Prefix("eslint-plugin-"))
+        ));
+
+        let unused = graph.classify("unused-pkg", &used, &imports).remove(0);
+        assert_eq!(unused.class, UsageClass::Unused);
+
+        // Script-only CLIs count through the scripts running them
+        let del = graph.classify("del-cli", &used, &imports).remove(0);
+        assert!(matches!(del.reason, ClassificationReason::Invoked(ref cmds) if cmds.len() == 1));
+        let concurrently = graph.classify("concurrently", &used, &imports).remove(0);
+        assert_eq!(concurrently.class, UsageClass::Unused);
+        assert!(graph.classify("left-pad", &used, &imports).is_empty());
+    }
+
+    #[test]
+    fn test_removable_transitive() {
+        let mut packages = create_test_packages();
+        packages.insert(
+            "moment-timezone".to_string(),
+            Package::new("moment-timezone", "0.5.0")
+                .direct()
+                .with_dependencies(vec!["moment".to_string(), "raw-body".to_string()]),
+        );
+        packages.insert("moment".to_string(), Package::new("moment", "2.29.0"));
+        let graph = DependencyGraph::new(&packages);
+
+        let used: HashSet<String> = HashSet::from(["express".to_string()]);
+        let analysis = graph.analyze_usage(&used, true);
+
+        // raw-body stays behind for express; moment goes with moment-timezone
+        assert_eq!(analysis.removable_transitive["moment-timezone"], 1);
+        assert_eq!(analysis.removable_transitive["unused-pkg"], 0);
+    }
+
+    #[test]
+    fn test_cargo_keys() {
+        // Cargo.lock packages are keyed by name@version, imports by crate name
+        let mut packages = HashMap::new();
+        packages.insert(
+            "clap@4.5.0".to_string(),
+            Package::new("clap", "4.5.0")
+                .direct()
+                .with_dependencies(vec!["clap_builder@4.5.0".to_string()]),
+        );
+        packages.insert(
+            "clap_builder@4.5.0".to_string(),
+            Package::new("clap_builder", "4.5.0"),
+        );
+        packages.insert(
+            "serde@1.0.0".to_string(),
+            Package::new("serde", "1.0.0").direct(),
+        );
+        packages.insert("libc@0.2.0".to_string(), Package::new("libc", "0.2.0"));
+        let graph = DependencyGraph::new(&packages);
+
+        let used = HashSet::from(["clap".to_string()]);
+        let analysis = graph.analyze_usage(&used, true);
+        let unused: Vec<&str> = analysis
+            .unused_direct
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(unused, ["serde"]);
+        assert_eq!(analysis.used.len(), 2);
+
+        let mut imports = ImportMap::new();
+        for specifier in ["clap", "libc"] {
+            imports.add_import(Import {
+                file_path: PathBuf::from("src/main.rs"),
+                line: 1,
+                specifier: specifier.to_string(),
+                kind: ImportKind::Use,
+                resolved_package: Some(specifier.to_string()),
+            });
+        }
+        let builder = graph.classify("clap_builder", &used, &imports);
+        assert_eq!(builder.len(), 1);
+        assert!(matches!(
+            builder[0].reason,
+            ClassificationReason::RequiredBy(ref chain) if chain == &["clap@4.5.0", "clap_builder@4.5.0"]
+        ));
+        assert_eq!(
+            graph.classify("serde", &used, &imports)[0].class,
+            UsageClass::Unused
+        );
+
+        let phantom = graph.find_phantom(&imports);
+        assert_eq!(phantom.len(), 1);
+        assert_eq!(phantom[0].name, "libc");
+        assert_eq!(phantom[0].installed_version.as_deref(), Some("0.2.0"));
+    }
+
+    #[test]
+    fn test_other_platform_optionals() {
+        let mut packages = create_test_packages();
+        let mut fsevents = Package::new("fsevents", "2.3.3").optional();
+        fsevents.os = vec![format!("!{}", crate::platform::current_os())];
+        packages.insert("fsevents".to_string(), fsevents);
+        packages.insert(
+            "bufferutil".to_string(),
+            Package::new("bufferutil", "4.0.8").direct().optional(),
+        );
+        let mut native = Package::new("@esbuild/native", "0.19.0").optional();
+        native.platform_parent = Some("esbuild".to_string());
+        packages.insert(native.name.clone(), native);
+        let mut abbrev = Package::new("abbrev", "2.0.0");
+        abbrev.bundled_in = Some("npm".to_string());
+        packages.insert(abbrev.name.clone(), abbrev);
+        let graph = DependencyGraph::new(&packages);
+
+        let used: HashSet<String> = HashSet::from(["express".to_string()]);
+        let analysis = graph.analyze_usage(&used, true);
+
+        let other: Vec<&str> = analysis
+            .other_platform
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(other, vec!["fsevents"]);
+        assert!(!analysis.unused.iter().any(|p| p.name == "fsevents"));
+        // Platform builds and bundled copies are represented by their parent
+        assert!(!analysis.unused.iter().any(|p| p.name == "@esbuild/native"));
+        assert!(!analysis.unused.iter().any(|p| p.name == "abbrev"));
+        // Installable optionals nothing imports are expected, not removable
+        assert!(!analysis.unused.iter().any(|p| p.name == "bufferutil"));
+        assert!(analysis
+            .expected_unused_direct
+            .iter()
+            .any(|p| p.name == "bufferutil"));
+        let bufferutil = graph
+            .classify("bufferutil", &used, &ImportMap::new())
+            .remove(0);
+        assert!(matches!(
+            bufferutil.reason,
+            ClassificationReason::Expected(ExpectedUnusedRule::Optional)
+        ));
+    }
+
+    #[test]
+    fn test_explain_peer_only_package() {
+        let mut packages = create_test_packages();
+        let mut react_dom = Package::new("react-dom", "18.2.0").direct();
+        react_dom
+            .peer_dependencies
+            .insert("react".to_string(), "^18.2.0".to_string());
+        packages.insert("react-dom".to_string(), react_dom);
+        packages.insert("react".to_string(), Package::new("react", "18.2.0"));
+        let graph = DependencyGraph::new(&packages);
+
+        let explanation = graph.explain_package("react").unwrap();
+        assert!(explanation.dependency_chains.is_empty());
+        assert_eq!(
+            explanation.peer_of,
+            vec![PeerDependent {
+                package: "react-dom".to_string(),
+                range: "^18.2.0".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let mut packages = create_test_packages();
+        packages.insert(
+            "raw-body@3.0.0".to_string(),
+            Package::new("raw-body", "3.0.0"),
+        );
+        packages
+            .get_mut("unused-pkg")
+            .unwrap()
+            .dependencies
+            .push("raw-body@3.0.0".to_string());
+        let graph = DependencyGraph::new(&packages);
+
+        let vulnerability = Vulnerability {
+            id: "GHSA-0000-0000-0000".to_string(),
+            title: "Denial of service".to_string(),
+            severity: Severity::High,
+            package_name: "raw-body".to_string(),
+            vulnerable_range: ">=3.0.0 <3.0.1".to_string(),
+            patched_version: Some("3.0.1".to_string()),
+            url: None,
+            affects_used_code: false,
+            installed_version: "3.0.0".to_string(),
+            purl: None,
+            cpe: None,
+            owners: Vec::new(),
+        };
+        let export = graph.export(&[vulnerability]);
+
+        let ids: Vec<&str> = export.nodes.iter().map(|n| n.id.as_str()).collect();
+        assert_eq!(
+            ids,
+            [
+                "body-parser",
+                "express",
+                "raw-body",
+                "raw-body@3.0.0",
+                "unused-pkg"
+            ]
+        );
+        assert!(export.nodes[1].direct && !export.nodes[1].duplicate);
+        assert!(export.nodes[2].duplicate && export.nodes[2].vulnerabilities.is_empty());
+        assert_eq!(export.nodes[3].vulnerabilities[0].severity, Severity::High);
+        assert_eq!(export.edges.len(), 3);
+        assert_eq!(
+            export.edges[0],
+            GraphEdge {
+                from: "body-parser".to_string(),
+                to: "raw-body".to_string(),
+            }
+        );
     }
 }
@@ -0,0 +1,404 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use miette::{Context, IntoDiagnostic, Result};
+
+use crate::analyzer::resolve::normalize;
+use crate::config::Config;
+use crate::types::{FindingGroup, ImportMap, UsageAnalysis};
+
+/// Where GitHub and GitLab look for a CODEOWNERS file, in order
+const CODEOWNERS_PATHS: &[&str] = &["CODEOWNERS", ".github/CODEOWNERS", "docs/CODEOWNERS"];
+
+/// Group name for files no CODEOWNERS rule matches
+const UNOWNED: &str = "(unowned)";
+
+/// How to group findings in the analyze report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// Top-level directory of the file
+    Dir,
+    /// Owners from the CODEOWNERS file
+    Owner,
+}
+
+/// Ownership rules from a CODEOWNERS file
+///
+/// Patterns use gitignore syntax and the last matching rule wins.
+pub struct CodeOwners {
+    rules: Vec<(Gitignore, Vec<String>)>,
+}
+
+impl CodeOwners {
+    /// Load the project's CODEOWNERS file, if it has one
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        let Some(path) = CODEOWNERS_PATHS
+            .iter()
+            .map(|p| root.join(p))
+            .find(|p| p.is_file())
+        else {
+            return Ok(None);
+        };
+
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Ok(Some(Self::parse(root, &content)))
+    }
+
+    pub fn parse(root: &Path, content: &str) -> Self {
+        let mut rules = Vec::new();
+
+        for line in content.lines() {
+            let line = line.trim();
+            // GitLab section headers ("[Docs]") carry no pattern
+            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let Some(pattern) = fields.next() else {
+                continue;
+            };
+            let owners: Vec<String> = fields
+                .take_while(|f| !f.starts_with('#'))
+                .map(|f| f.to_string())
+                .collect();
+
+            let mut builder = GitignoreBuilder::new(root);
+            if builder.add_line(None, pattern).is_err() {
+                continue;
+            }
+            if let Ok(matcher) = builder.build() {
+                rules.push((matcher, owners));
+            }
+        }
+
+        Self { rules }
+    }
+
+    /// Owners of a file relative to the project root; empty if unowned
+    pub fn owners_of(&self, path: &Path) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.matched_path_or_any_parents(path, false).is_ignore())
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Owners of a directory relative to the project root, so rules only
+    /// matching directories (`/apps/web/`) apply too
+    pub fn owners_of_dir(&self, path: &Path) -> &[String] {
+        self.rules
+            .iter()
+            .rev()
+            .find(|(matcher, _)| matcher.matched_path_or_any_parents(path, true).is_ignore())
+            .map(|(_, owners)| owners.as_slice())
+            .unwrap_or(&[])
+    }
+}
+
+/// Who owns a finding: `[owners]` package rules from depx.toml, then CODEOWNERS
+pub struct Ownership {
+    packages: Vec<(String, Vec<String>)>,
+    codeowners: Option<CodeOwners>,
+}
+
+impl Ownership {
+    pub fn load(root: &Path, config: &Config) -> Result<Self> {
+        Ok(Self::new(config, CodeOwners::load(root)?))
+    }
+
+    pub fn new(config: &Config, codeowners: Option<CodeOwners>) -> Self {
+        Self {
+            packages: config
+                .owners
+                .iter()
+                .map(|(pattern, owners)| (pattern.clone(), owners.to_vec()))
+                .collect(),
+            codeowners,
+        }
+    }
+
+    /// Whether any source of ownership is configured
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty() && self.codeowners.is_none()
+    }
+
+    /// Owners of a file relative to the project root, from CODEOWNERS
+    pub fn of_file(&self, path: &Path) -> Vec<String> {
+        self.codeowners
+            .as_ref()
+            .map(|o| o.owners_of(path).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Owners of a directory relative to the project root, from CODEOWNERS
+    pub fn of_dir(&self, path: &Path) -> Vec<String> {
+        self.codeowners
+            .as_ref()
+            .map(|o| o.owners_of_dir(path).to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Owners from the `[owners]` rules; an exact name beats patterns, and
+    /// the longest matching pattern wins
+    pub fn package_rule(&self, name: &str) -> Option<&[String]> {
+        self.packages
+            .iter()
+            .filter(|(pattern, _)| matches_pattern(pattern, name))
+            .max_by_key(|(pattern, _)| (!pattern.contains('*'), pattern.len()))
+            .map(|(_, owners)| owners.as_slice())
+    }
+
+    /// Owners of a package: its `[owners]` rule, else whoever owns the
+    /// manifest (relative to the root) that pulls it in
+    pub fn of_package(&self, name: &str, manifest: &Path) -> Vec<String> {
+        match self.package_rule(name) {
+            Some(owners) => owners.to_vec(),
+            None => self.of_file(manifest),
+        }
+    }
+}
+
+/// `*` matches any run of characters, everything else literally
+pub fn matches_pattern(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// Attribute unused and phantom findings to directories or owners
+///
+/// Unused dependencies belong to the manifest that declares them (the root
+/// `package.json`); phantom dependencies belong to every file importing them.
+/// When grouping by owner, a package's `[owners]` rule takes precedence.
+pub fn group_findings(
+    root: &Path,
+    analysis: &UsageAnalysis,
+    imports: &ImportMap,
+    group_by: GroupBy,
+    owners: Option<&Ownership>,
+) -> Vec<FindingGroup> {
+    let root = normalize(root);
+    let group_of = |package: &str, file: &Path| -> String {
+        let relative = file.strip_prefix(&root).unwrap_or(file).to_path_buf();
+        match group_by {
+            GroupBy::Dir => top_level_dir(&relative),
+            GroupBy::Owner => {
+                let owners = owners
+                    .map(|o| o.of_package(package, &relative))
+                    .unwrap_or_default();
+                if owners.is_empty() {
+                    UNOWNED.to_string()
+                } else {
+                    owners.join(" ")
+                }
+            }
+        }
+    };
+
+    let mut groups: BTreeMap<String, FindingGroup> = BTreeMap::new();
+
+    let manifest = root.join("package.json");
+    for pkg in &analysis.unused_direct {
+        group_entry(&mut groups, group_of(&pkg.name, &manifest))
+            .unused
+            .push(pkg.name.clone());
+    }
+
+    for phantom in &analysis.phantom {
+        let Some(usages) = imports.get_package_usages(&phantom.name) else {
+            continue;
+        };
+        let mut usages: Vec<_> = usages.iter().collect();
+        usages.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+
+        for usage in usages {
+            let entry = group_entry(
+                &mut groups,
+                group_of(&phantom.name, &normalize(&usage.file_path)),
+            );
+            if !entry
+                .phantom
+                .iter()
+                .any(|i| i.resolved_package == usage.resolved_package)
+            {
+                entry.phantom.push(usage.clone());
+            }
+        }
+    }
+
+    groups.into_values().collect()
+}
+
+/// Owners of each unused and phantom package, for tools reading the findings
+///
+/// Unused packages go to the root `package.json`'s owners and phantom ones
+/// to the owners of every file importing them, unless an `[owners]` rule
+/// names the package. Packages nobody owns are left out.
+pub fn finding_owners(
+    root: &Path,
+    analysis: &UsageAnalysis,
+    imports: &ImportMap,
+    ownership: &Ownership,
+) -> BTreeMap<String, Vec<String>> {
+    let root = normalize(root);
+    let mut owners = BTreeMap::new();
+
+    for pkg in &analysis.unused_direct {
+        owners.insert(
+            pkg.name.clone(),
+            ownership.of_package(&pkg.name, Path::new("package.json")),
+        );
+    }
+
+    for phantom in &analysis.phantom {
+        let found = match ownership.package_rule(&phantom.name) {
+            Some(rule) => rule.to_vec(),
+            None => {
+                let mut files: Vec<PathBuf> = imports
+                    .get_package_usages(&phantom.name)
+                    .into_iter()
+                    .flatten()
+                    .map(|usage| {
+                        let file = normalize(&usage.file_path);
+                        file.strip_prefix(&root).unwrap_or(&file).to_path_buf()
+                    })
+                    .collect();
+                files.sort();
+                files.dedup();
+
+                let mut found: Vec<String> = Vec::new();
+                for file in files {
+                    for owner in ownership.of_file(&file) {
+                        if !found.contains(&owner) {
+                            found.push(owner);
+                        }
+                    }
+                }
+                found
+            }
+        };
+        owners.insert(phantom.name.clone(), found);
+    }
+
+    owners.retain(|_, found: &mut Vec<String>| !found.is_empty());
+    owners
+}
+
+fn group_entry(groups: &mut BTreeMap<String, FindingGroup>, name: String) -> &mut FindingGroup {
+    groups.entry(name.clone()).or_insert_with(|| FindingGroup {
+        name,
+        unused: Vec::new(),
+        phantom: Vec::new(),
+    })
+}
+
+/// "src/api/user.ts" -> "src", "index.js" -> "."
+fn top_level_dir(relative: &Path) -> String {
+    let mut components = relative.components();
+    match (components.next(), components.next()) {
+        (Some(first), Some(_)) => PathBuf::from(first.as_os_str()).display().to_string(),
+        _ => ".".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeowners() {
+        let root = Path::new("/repo");
+        let owners = CodeOwners::parse(
+            root,
+            "# Default owners\n\
+             *       @org/core\n\
+             /apps/web/ @org/web @alice\n\
+             *.md    @org/docs # docs team\n\
+             [Infra]\n\
+             infra/** @org/infra\n",
+        );
+
+        assert_eq!(owners.owners_of(Path::new("src/index.ts")), ["@org/core"]);
+        assert_eq!(
+            owners.owners_of(Path::new("apps/web/src/app.tsx")),
+            ["@org/web", "@alice"]
+        );
+        assert_eq!(
+            owners.owners_of(Path::new("apps/web/README.md")),
+            ["@org/docs"]
+        );
+        assert_eq!(
+            owners.owners_of(Path::new("infra/k8s/deploy.js")),
+            ["@org/infra"]
+        );
+        assert_eq!(
+            owners.owners_of_dir(Path::new("apps/web")),
+            ["@org/web", "@alice"]
+        );
+        assert_eq!(owners.owners_of_dir(Path::new(".")), ["@org/core"]);
+    }
+
+    #[test]
+    fn test_package_rules() {
+        let config: Config = toml::from_str(
+            r#"
+            [owners]
+            "@acme/*" = "@acme/platform"
+            "@acme/ui-*" = "@acme/design"
+            "@acme/ui-legacy" = ["@bob"]
+            "#,
+        )
+        .unwrap();
+        let codeowners = CodeOwners::parse(Path::new("/repo"), "package.json @org/core\n");
+        let ownership = Ownership::new(&config, Some(codeowners));
+
+        let manifest = Path::new("package.json");
+        assert_eq!(
+            ownership.of_package("@acme/ui-button", manifest),
+            ["@acme/design"]
+        );
+        assert_eq!(ownership.of_package("@acme/ui-legacy", manifest), ["@bob"]);
+        assert_eq!(
+            ownership.of_package("@acme/api", manifest),
+            ["@acme/platform"]
+        );
+        assert_eq!(ownership.of_package("lodash", manifest), ["@org/core"]);
+    }
+
+    #[test]
+    fn test_matches_pattern() {
+        assert!(matches_pattern("react", "react"));
+        assert!(!matches_pattern("react", "react-dom"));
+        assert!(matches_pattern("react*", "react-dom"));
+        assert!(matches_pattern("*-loader", "css-loader"));
+        assert!(matches_pattern("@babel/*-env", "@babel/preset-env"));
+        assert!(!matches_pattern("@babel/*-env", "@babel/core"));
+        assert!(matches_pattern("*", "anything"));
+    }
+
+    #[test]
+    fn test_top_level_dir() {
+        assert_eq!(top_level_dir(Path::new("src/api/user.ts")), "src");
+        assert_eq!(top_level_dir(Path::new("index.js")), ".");
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::{HashMap, HashSet};
+
+use clap::ValueEnum;
+
+use crate::date;
+use crate::registry::{PackageMetadata, Release};
+use crate::types::{ObscurePackage, Package, PackageHealth, Vulnerability};
+
+/// Packages without a release for this long are flagged as possibly abandoned
+const ABANDONED_AFTER_DAYS: i64 = 730;
+
+/// Maintainer changes older than this are no longer called out
+const MAINTAINER_CHANGE_WINDOW_DAYS: i64 = 365;
+
+/// Packages with fewer weekly downloads than this are worth a second look
+const OBSCURE_WEEKLY_DOWNLOADS: u64 = 1_000;
+
+/// Sort order for the health report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum HealthSort {
+    /// Oldest last release first
+    Age,
+    /// Alphabetical
+    Name,
+    /// Slowest release cadence first
+    Cadence,
+    /// Most advisories first
+    Advisories,
+}
+
+/// Collect the project's direct dependencies from the lockfile packages
+///
+/// Cargo workspace crates aren't direct themselves, only the crates they
+/// depend on, so the same filter fits every lockfile. Git and local
+/// packages are skipped: the registry knows nothing about them.
+pub fn direct_dependencies(packages: &HashMap<String, Package>) -> Vec<Package> {
+    let mut direct: Vec<Package> = packages
+        .values()
+        .filter(|p| p.is_direct && p.has_registry_releases())
+        .cloned()
+        .collect();
+
+    direct.sort_by(|a, b| a.name.cmp(&b.name));
+    direct
+}
+
+/// Combine registry metadata and advisories into a health entry per package
+pub fn build_report(
+    direct: &[Package],
+    metadata: &HashMap<String, PackageMetadata>,
+    downloads: &HashMap<String, u64>,
+    vulnerabilities: &[Vulnerability],
+    today: i64,
+) -> Vec<PackageHealth> {
+    direct
+        .iter()
+        .map(|pkg| {
+            let meta = metadata.get(&pkg.name);
+            let last_published = meta.and_then(|m| m.last_published());
+            let days_since_publish = last_published.map(|d| today - d);
+
+            let releases_last_year = meta
+                .map(|m| {
+                    m.releases
+                        .iter()
+                        .filter(|r| today - r.published <= 365)
+                        .count()
+                })
+                .unwrap_or(0);
+
+            let release_interval_days = meta.and_then(|m| {
+                let first = m.releases.first()?.published;
+                let last = m.releases.last()?.published;
+                let intervals = m.releases.len().checked_sub(1).filter(|n| *n > 0)?;
+                Some((last - first) / intervals as i64)
+            });
+
+            let open_advisories = vulnerabilities
+                .iter()
+                .filter(|v| v.package_name == pkg.name && v.installed_version == pkg.version)
+                .count();
+
+            let maintainers = meta.map(|m| m.maintainers.clone()).unwrap_or_default();
+            let weekly_downloads = meta
+                .and_then(|m| m.weekly_downloads)
+                .or_else(|| downloads.get(&pkg.name).copied());
+
+            PackageHealth {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                purl: pkg.purl.clone(),
+                is_dev: pkg.is_dev,
+                latest_version: meta.and_then(|m| m.latest_version.clone()),
+                last_publish: last_published.map(date::format_days),
+                days_since_publish,
+                releases_last_year,
+                release_interval_days,
+                open_advisories,
+                is_abandoned: days_since_publish.is_some_and(|d| d >= ABANDONED_AFTER_DAYS),
+                single_maintainer: maintainers.len() == 1,
+                maintainers,
+                maintainer_change: meta.and_then(|m| recent_maintainer_change(&m.releases, today)),
+                weekly_downloads,
+                is_obscure: weekly_downloads.is_some_and(|d| d < OBSCURE_WEEKLY_DOWNLOADS),
+            }
+        })
+        .collect()
+}
+
+/// Registry packages that aren't direct dependencies (or workspace crates)
+pub fn transitive_packages<'p>(
+    packages: &'p HashMap<String, Package>,
+    direct: &[Package],
+) -> Vec<&'p Package> {
+    let direct: HashSet<(&str, &str)> = direct
+        .iter()
+        .map(|p| (p.name.as_str(), p.version.as_str()))
+        .collect();
+
+    let mut transitive: Vec<&Package> = packages
+        .values()
+        .filter(|p| !p.is_direct && p.has_registry_releases())
+        .filter(|p| !direct.contains(&(p.name.as_str(), p.version.as_str())))
+        .collect();
+    transitive.sort_by(|a, b| a.name.cmp(&b.name));
+    transitive
+}
+
+/// Transitive packages downloaded so rarely that they may be typosquats or abandoned forks
+pub fn obscure_transitive(
+    packages: &HashMap<String, Package>,
+    direct: &[Package],
+    downloads: &HashMap<String, u64>,
+) -> Vec<ObscurePackage> {
+    let mut obscure: Vec<ObscurePackage> = transitive_packages(packages, direct)
+        .into_iter()
+        .filter_map(|pkg| {
+            let weekly_downloads = *downloads.get(&pkg.name)?;
+            if weekly_downloads >= OBSCURE_WEEKLY_DOWNLOADS {
+                return None;
+            }
+
+            let mut dependents: Vec<String> = packages
+                .iter()
+                .filter(|(_, p)| {
+                    p.dependencies.iter().any(|d| {
+                        packages
+                            .get(d)
+                            .is_some_and(|dep| dep.name == pkg.name && dep.version == pkg.version)
+                    })
+                })
+                .map(|(_, p)| p.name.clone())
+                .collect();
+            dependents.sort();
+            dependents.dedup();
+
+            Some(ObscurePackage {
+                name: pkg.name.clone(),
+                version: pkg.version.clone(),
+                purl: pkg.purl.clone(),
+                weekly_downloads,
+                dependents,
+            })
+        })
+        .collect();
+
+    obscure.sort_by_key(|p| p.weekly_downloads);
+    obscure
+}
+
+/// Describe a recent release published or maintained by a previously unseen account
+///
+/// A new account taking over publishing is a common precursor to
+/// supply-chain compromises, so it's surfaced even when nothing else changed.
+fn recent_maintainer_change(releases: &[Release], today: i64) -> Option<String> {
+    let (latest, earlier) = releases.split_last()?;
+    if today - latest.published > MAINTAINER_CHANGE_WINDOW_DAYS {
+        return None;
+    }
+
+    let known: HashSet<&str> = earlier
+        .iter()
+        .flat_map(|r| {
+            r.maintainers
+                .iter()
+                .map(|m| m.as_str())
+                .chain(r.publisher.as_deref())
+        })
+        .collect();
+    if known.is_empty() {
+        return None;
+    }
+
+    let mut new_accounts: Vec<&str> = latest
+        .maintainers
+        .iter()
+        .map(|m| m.as_str())
+        .chain(latest.publisher.as_deref())
+        .filter(|account| !known.contains(account))
+        .collect();
+    new_accounts.sort();
+    new_accounts.dedup();
+
+    if new_accounts.is_empty() {
+        return None;
+    }
+
+    Some(format!(
+        "{} first appeared in {}",
+        new_accounts.join(", "),
+        latest.version
+    ))
+}
+
+/// Sort the report so the most concerning packages come first
+pub fn sort_report(report: &mut [PackageHealth], sort: HealthSort) {
+    match sort {
+        HealthSort::Age => report.sort_by(|a, b| {
+            b.days_since_publish
+                .cmp(&a.days_since_publish)
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        HealthSort::Name => report.sort_by(|a, b| a.name.cmp(&b.name)),
+        HealthSort::Cadence => report.sort_by(|a, b| {
+            b.release_interval_days
+                .cmp(&a.release_interval_days)
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+        HealthSort::Advisories => report.sort_by(|a, b| {
+            b.open_advisories
+                .cmp(&a.open_advisories)
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(published: &[i64]) -> PackageMetadata {
+        PackageMetadata {
+            latest_version: Some("2.0.0".to_string()),
+            releases: published
+                .iter()
+                .enumerate()
+                .map(|(i, d)| Release {
+                    version: format!("1.0.{}", i),
+                    published: *d,
+                    publisher: Some("alice".to_string()),
+                    maintainers: vec!["alice".to_string()],
+                })
+                .collect(),
+            maintainers: vec!["alice".to_string()],
+            weekly_downloads: None,
+            deprecated: HashMap::new(),
+            repository: None,
+        }
+    }
+
+    #[test]
+    fn test_build_report() {
+        let today = 10_000;
+        let direct = vec![
+            Package::new("fresh", "1.0.0").direct(),
+            Package::new("old", "1.0.0").direct(),
+        ];
+        let metadata = HashMap::from([
+            ("fresh".to_string(), metadata(&[9_000, 9_800, 9_990])),
+            ("old".to_string(), metadata(&[6_000, 7_000])),
+        ]);
+
+        let downloads = HashMap::from([("fresh".to_string(), 50)]);
+
+        let mut report = build_report(&direct, &metadata, &downloads, &[], today);
+        sort_report(&mut report, HealthSort::Age);
+
+        assert_eq!(report[0].name, "old");
+        assert!(report[0].is_abandoned);
+        assert_eq!(report[0].release_interval_days, Some(1000));
+        assert_eq!(report[1].releases_last_year, 2);
+        assert_eq!(report[1].days_since_publish, Some(10));
+        assert!(!report[1].is_abandoned);
+        assert!(report[1].single_maintainer);
+        assert_eq!(report[1].maintainer_change, None);
+        assert!(report[1].is_obscure);
+        assert_eq!(report[0].weekly_downloads, None);
+    }
+
+    #[test]
+    fn test_obscure_transitive() {
+        let mut packages = HashMap::new();
+        packages.insert(
+            "app".to_string(),
+            Package::new("app", "1.0.0")
+                .direct()
+                .with_dependencies(vec!["lodahs".to_string(), "react".to_string()]),
+        );
+        packages.insert("lodahs".to_string(), Package::new("lodahs", "0.0.1"));
+        packages.insert("react".to_string(), Package::new("react", "18.0.0"));
+
+        let downloads = HashMap::from([
+            ("lodahs".to_string(), 12),
+            ("react".to_string(), 20_000_000),
+        ]);
+
+        let direct = direct_dependencies(&packages);
+        let obscure = obscure_transitive(&packages, &direct, &downloads);
+
+        assert_eq!(obscure.len(), 1);
+        assert_eq!(obscure[0].name, "lodahs");
+        assert_eq!(obscure[0].dependents, vec!["app"]);
+    }
+
+    #[test]
+    fn test_recent_maintainer_change() {
+        let mut meta = metadata(&[9_000, 9_900]);
+        meta.releases[1].publisher = Some("mallory".to_string());
+
+        assert_eq!(
+            recent_maintainer_change(&meta.releases, 10_000),
+            Some("mallory first appeared in 1.0.1".to_string())
+        );
+
+        // Old changes aren't news anymore
+        assert_eq!(recent_maintainer_change(&meta.releases, 11_000), None);
+    }
+
+    #[test]
+    fn test_cargo_direct_dependencies() {
+        let mut packages = HashMap::new();
+        packages.insert(
+            "app@0.1.0".to_string(),
+            Package::new("app", "0.1.0").with_dependencies(vec!["serde@1.0.0".to_string()]),
+        );
+        packages.insert(
+            "serde@1.0.0".to_string(),
+            Package::new("serde", "1.0.0").direct(),
+        );
+        packages.insert("itoa@1.0.0".to_string(), Package::new("itoa", "1.0.0"));
+
+        let direct = direct_dependencies(&packages);
+        assert_eq!(direct.len(), 1);
+        assert_eq!(direct[0].name, "serde");
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+
+use crate::date;
+use crate::types::{FindingHistory, FirstSeen, IntegrityIssue, Vulnerability};
+
+/// Current history format
+const HISTORY_VERSION: u32 = 1;
+
+/// Fingerprint categories, each pruned only when it was checked
+pub const INTEGRITY: &str = "integrity";
+pub const VULNERABILITY: &str = "vulnerability";
+
+impl FindingHistory {
+    /// Load the history, or an empty one on the first run
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self {
+                version: HISTORY_VERSION,
+                ..Default::default()
+            });
+        }
+
+        let content = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read history {}", path.display()))?;
+        let history: Self = serde_json::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse history {}: {}", path.display(), e))?;
+
+        if history.version > HISTORY_VERSION {
+            return Err(miette::miette!(
+                "History {} was written by a newer depx (format {}, expected {})",
+                path.display(),
+                history.version,
+                HISTORY_VERSION
+            ));
+        }
+        Ok(history)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let output = serde_json::to_string_pretty(self)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        std::fs::write(path, output + "\n")
+            .into_diagnostic()
+            .with_context(|| format!("Failed to write history {}", path.display()))
+    }
+
+    /// Record this run's findings of a category: new ones are first seen
+    /// `today`, and ones that are gone are forgotten so a regression starts over
+    pub fn observe(&mut self, category: &str, keys: &[String], today: i64) {
+        let prefix = format!("{}/", category);
+        let current: Vec<String> = keys.iter().map(|k| format!("{}{}", prefix, k)).collect();
+
+        self.first_seen.retain(|fingerprint, _| {
+            !fingerprint.starts_with(&prefix) || current.contains(fingerprint)
+        });
+        for fingerprint in current {
+            self.first_seen
+                .entry(fingerprint)
+                .or_insert_with(|| date::format_days(today));
+        }
+    }
+
+    pub fn first_seen(&self, category: &str, key: &str, today: i64) -> Option<FirstSeen> {
+        let date = self.first_seen.get(&format!("{}/{}", category, key))?;
+        Some(FirstSeen {
+            date: date.clone(),
+            days_open: date::parse_days(date).map_or(0, |days| today - days),
+        })
+    }
+}
+
+/// The same issue on the same package version is the same finding
+pub fn integrity_key(issue: &IntegrityIssue) -> String {
+    format!("{}/{}@{}", issue.kind, issue.package, issue.version)
+}
+
+/// An advisory stays open across upgrades that are still affected
+pub fn vulnerability_key(vuln: &Vulnerability) -> String {
+    format!("{}/{}", vuln.id, vuln.package_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_observe() {
+        let mut history = FindingHistory::default();
+        let day = date::parse_days("2024-03-01").unwrap();
+
+        history.observe(VULNERABILITY, &["GHSA-1/lodash".to_string()], day);
+        history.observe(INTEGRITY, &["insecure-url/a@1.0.0".to_string()], day);
+        history.observe(
+            VULNERABILITY,
+            &["GHSA-1/lodash".to_string(), "GHSA-2/minimist".to_string()],
+            day + 10,
+        );
+
+        let first = history
+            .first_seen(VULNERABILITY, "GHSA-1/lodash", day + 30)
+            .unwrap();
+        assert_eq!(first.date, "2024-03-01");
+        assert_eq!(first.days_open, 30);
+        assert_eq!(
+            history
+                .first_seen(VULNERABILITY, "GHSA-2/minimist", day + 30)
+                .unwrap()
+                .days_open,
+            20
+        );
+
+        // Fixed findings are forgotten; other categories are untouched
+        history.observe(VULNERABILITY, &[], day + 31);
+        assert!(history
+            .first_seen(VULNERABILITY, "GHSA-1/lodash", day + 31)
+            .is_none());
+        assert!(history
+            .first_seen(INTEGRITY, "insecure-url/a@1.0.0", day + 31)
+            .is_some());
+    }
+}
@@ -0,0 +1,484 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use base64::Engine;
+use miette::{bail, Result};
+use ring::digest;
+
+use crate::lockfile::{Ecosystem, LockfileParser, LockfileType, NpmLockfileParser};
+use crate::types::{IntegrityIssue, IntegrityIssueKind, IntegrityReport};
+
+/// Registries trusted without any configuration
+const DEFAULT_REGISTRIES: &[&str] = &["registry.npmjs.org", "registry.yarnpkg.com"];
+
+/// Checks that every lockfile entry is hash-pinned and fetched from an
+/// expected registry over https
+pub struct IntegrityChecker<'a> {
+    root: &'a Path,
+    lockfile: Option<&'a Path>,
+    verify_tarballs: bool,
+    allowed_registries: &'a [String],
+}
+
+impl<'a> IntegrityChecker<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile: None,
+            verify_tarballs: false,
+            allowed_registries: &[],
+        }
+    }
+
+    /// Only accept entries from these hosts, "*.domain" wildcards or URL
+    /// prefixes (`allowed-registries` in depx.toml) instead of the defaults
+    pub fn allowed_registries(mut self, allowed: &'a [String]) -> Self {
+        self.allowed_registries = allowed;
+        self
+    }
+
+    /// Check this lockfile instead of the project's own (`--lockfile`)
+    pub fn lockfile(mut self, lockfile: Option<&'a Path>) -> Self {
+        self.lockfile = lockfile;
+        self
+    }
+
+    /// Also hash each entry's tarball in the npm cache against its integrity
+    pub fn verify_tarballs(mut self, verify: bool) -> Self {
+        self.verify_tarballs = verify;
+        self
+    }
+
+    pub fn check(&self) -> Result<IntegrityReport> {
+        let lockfile = match self.lockfile {
+            Some(lockfile) => LockfileParser::from_file(lockfile, self.root)?,
+            None => LockfileParser::for_ecosystem(self.root, Some(Ecosystem::Js))?,
+        };
+        if lockfile.lockfile_type() != LockfileType::Npm {
+            bail!("Integrity checks currently only support package-lock.json");
+        }
+
+        let parser = NpmLockfileParser::new(lockfile.root(), lockfile.lockfile_path());
+        let entries = parser.parse_resolutions()?;
+        let registries = self.registry_hosts();
+        let cache = self.verify_tarballs.then(npm_cache_dir).flatten();
+
+        let mut report = IntegrityReport {
+            entries_checked: entries.len(),
+            ..Default::default()
+        };
+
+        for entry in &entries {
+            let mut issue = |kind: IntegrityIssueKind, detail: &str| {
+                report.issues.push(IntegrityIssue {
+                    package: entry.name.clone(),
+                    version: entry.version.clone(),
+                    path: entry.path.clone(),
+                    kind,
+                    detail: detail.to_string(),
+                    owners: Vec::new(),
+                    first_seen: None,
+                });
+            };
+
+            let source = entry.resolved.as_deref().map(classify_source);
+            if let (Some(source), Some(url)) = (&source, entry.resolved.as_deref()) {
+                if *source == Source::Insecure {
+                    issue(IntegrityIssueKind::InsecureUrl, url);
+                }
+                if self.allowed_registries.is_empty() {
+                    let known = match source {
+                        Source::Registry(host) => registries.contains(host),
+                        Source::Git => false,
+                        Source::Insecure | Source::Local => true,
+                    };
+                    if !known {
+                        issue(IntegrityIssueKind::UnexpectedRegistry, url);
+                    }
+                } else if *source != Source::Local && !is_allowed(self.allowed_registries, url) {
+                    issue(IntegrityIssueKind::DisallowedRegistry, url);
+                }
+            }
+
+            // Git dependencies are pinned by commit and local files by path
+            if matches!(source, Some(Source::Git | Source::Local)) {
+                continue;
+            }
+
+            let hashes = entry
+                .integrity
+                .as_deref()
+                .map(parse_integrity)
+                .unwrap_or_default();
+            let Some(strongest) = hashes.iter().max_by_key(|h| h.algorithm) else {
+                issue(
+                    IntegrityIssueKind::MissingIntegrity,
+                    entry.resolved.as_deref().unwrap_or(""),
+                );
+                continue;
+            };
+            if strongest.algorithm == Algorithm::Sha1 {
+                issue(
+                    IntegrityIssueKind::WeakIntegrity,
+                    entry.integrity.as_deref().unwrap_or(""),
+                );
+            }
+
+            if let Some(cache) = &cache {
+                match verify_cached_tarball(cache, strongest) {
+                    Some(true) => report.tarballs_verified += 1,
+                    Some(false) => {
+                        report.tarballs_verified += 1;
+                        issue(
+                            IntegrityIssueKind::HashMismatch,
+                            entry.integrity.as_deref().unwrap_or(""),
+                        );
+                    }
+                    None => report.tarballs_not_cached += 1,
+                }
+            }
+        }
+
+        for divergence in parser.install_divergence()? {
+            report.issues.push(IntegrityIssue {
+                package: divergence.name,
+                version: divergence
+                    .installed
+                    .clone()
+                    .or_else(|| divergence.locked.clone())
+                    .unwrap_or_default(),
+                path: divergence.path,
+                kind: IntegrityIssueKind::StaleInstall,
+                detail: stale_install_detail(
+                    divergence.locked.as_deref(),
+                    divergence.installed.as_deref(),
+                ),
+                owners: Vec::new(),
+                first_seen: None,
+            });
+        }
+
+        report
+            .issues
+            .sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.path.cmp(&b.path)));
+        Ok(report)
+    }
+
+    /// Hosts of the default registries plus any configured in .npmrc
+    fn registry_hosts(&self) -> HashSet<String> {
+        let mut hosts: HashSet<String> = DEFAULT_REGISTRIES.iter().map(|h| h.to_string()).collect();
+
+        let mut npmrcs = vec![self.root.join(".npmrc")];
+        if let Some(home) = home_dir() {
+            npmrcs.push(home.join(".npmrc"));
+        }
+        let mut urls: Vec<String> = npmrcs
+            .iter()
+            .filter_map(|path| std::fs::read_to_string(path).ok())
+            .flat_map(|content| npmrc_registries(&content))
+            .collect();
+        urls.extend(std::env::var("npm_config_registry").ok());
+
+        hosts.extend(urls.iter().filter_map(|url| url_host(url)));
+        hosts
+    }
+}
+
+/// How node_modules differs from the lockfile at one install path
+fn stale_install_detail(locked: Option<&str>, installed: Option<&str>) -> String {
+    match (locked, installed) {
+        (Some(locked), Some(installed)) => {
+            format!(
+                "{} is installed, package-lock.json has {}",
+                installed, locked
+            )
+        }
+        (Some(locked), None) => format!("not installed, package-lock.json has {}", locked),
+        (None, _) => "installed, but not in package-lock.json".to_string(),
+    }
+}
+
+/// Where a `resolved` URL points
+#[derive(Debug, PartialEq, Eq)]
+enum Source {
+    /// An https registry or tarball host
+    Registry(String),
+    /// Plain http://
+    Insecure,
+    /// git+ssh://, git+https://, github:user/repo, ...
+    Git,
+    /// file: tarballs and directories
+    Local,
+}
+
+fn classify_source(resolved: &str) -> Source {
+    if resolved.starts_with("file:") {
+        return Source::Local;
+    }
+    if resolved.starts_with("git")
+        || resolved.starts_with("github:")
+        || resolved.starts_with("gitlab:")
+        || resolved.starts_with("bitbucket:")
+    {
+        return Source::Git;
+    }
+    if resolved.starts_with("http://") {
+        return Source::Insecure;
+    }
+    match url_host(resolved) {
+        Some(host) => Source::Registry(host),
+        // Not a URL at all; treat the whole value as the host so it's flagged
+        None => Source::Registry(resolved.to_string()),
+    }
+}
+
+/// Whether `resolved` matches an `allowed-registries` entry: a URL prefix,
+/// a "*.domain" wildcard, or an exact host
+fn is_allowed(allowed: &[String], resolved: &str) -> bool {
+    let host = source_host(resolved);
+    allowed.iter().any(|entry| {
+        if entry.contains("://") {
+            resolved.starts_with(entry.as_str())
+        } else if let Some(domain) = entry.strip_prefix("*.") {
+            host.as_deref()
+                .is_some_and(|h| h.strip_suffix(domain).is_some_and(|sub| sub.ends_with('.')))
+        } else {
+            host.as_deref() == Some(entry.as_str())
+        }
+    })
+}
+
+/// Host a resolved URL or git shorthand ("github:user/repo") fetches from
+fn source_host(resolved: &str) -> Option<String> {
+    for (shorthand, host) in [
+        ("github:", "github.com"),
+        ("gitlab:", "gitlab.com"),
+        ("bitbucket:", "bitbucket.org"),
+    ] {
+        if resolved.starts_with(shorthand) {
+            return Some(host.to_string());
+        }
+    }
+    url_host(resolved.strip_prefix("git+").unwrap_or(resolved))
+}
+
+fn url_host(url: &str) -> Option<String> {
+    reqwest::Url::parse(url)
+        .ok()?
+        .host_str()
+        .map(|h| h.to_string())
+}
+
+/// Registry URLs set in an .npmrc (`registry=` and `@scope:registry=`)
+fn npmrc_registries(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#') && !line.starts_with(';'))
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| {
+            let key = key.trim();
+            key == "registry" || key.ends_with(":registry")
+        })
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .collect()
+}
+
+/// Hash algorithms allowed in integrity strings, weakest first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Algorithm {
+    Sha1,
+    Sha256,
+    Sha384,
+    Sha512,
+}
+
+impl Algorithm {
+    fn name(&self) -> &'static str {
+        match self {
+            Algorithm::Sha1 => "sha1",
+            Algorithm::Sha256 => "sha256",
+            Algorithm::Sha384 => "sha384",
+            Algorithm::Sha512 => "sha512",
+        }
+    }
+
+    fn digest(&self) -> &'static digest::Algorithm {
+        match self {
+            Algorithm::Sha1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+            Algorithm::Sha256 => &digest::SHA256,
+            Algorithm::Sha384 => &digest::SHA384,
+            Algorithm::Sha512 => &digest::SHA512,
+        }
+    }
+}
+
+/// One `<algorithm>-<base64>` entry of an integrity string
+#[derive(Debug)]
+struct Hash {
+    algorithm: Algorithm,
+    digest: Vec<u8>,
+}
+
+/// Parse a Subresource Integrity string ("sha512-... sha1-..."), skipping
+/// unknown algorithms and malformed entries
+fn parse_integrity(integrity: &str) -> Vec<Hash> {
+    integrity
+        .split_whitespace()
+        .filter_map(|entry| {
+            let (algorithm, encoded) = entry.split_once('-')?;
+            let algorithm = match algorithm {
+                "sha1" => Algorithm::Sha1,
+                "sha256" => Algorithm::Sha256,
+                "sha384" => Algorithm::Sha384,
+                "sha512" => Algorithm::Sha512,
+                _ => return None,
+            };
+            // Options after '?' are reserved by the SRI spec
+            let encoded = encoded.split('?').next().unwrap_or(encoded);
+            let digest = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok()?;
+            (digest.len() == algorithm.digest().output_len()).then_some(Hash { algorithm, digest })
+        })
+        .collect()
+}
+
+/// Hash the tarball npm cached under this integrity; `None` when it isn't cached
+///
+/// npm's cache (cacache) stores content at a path derived from its hash, so a
+/// file there that hashes differently has been modified after download.
+fn verify_cached_tarball(cache: &Path, hash: &Hash) -> Option<bool> {
+    let hex: String = hash.digest.iter().map(|b| format!("{:02x}", b)).collect();
+    let path = cache
+        .join("_cacache")
+        .join("content-v2")
+        .join(hash.algorithm.name())
+        .join(&hex[..2])
+        .join(&hex[2..4])
+        .join(&hex[4..]);
+
+    let content = std::fs::read(&path).ok()?;
+    let actual = digest::digest(hash.algorithm.digest(), &content);
+    Some(actual.as_ref() == hash.digest.as_slice())
+}
+
+/// npm's cache directory (`npm config get cache`)
+fn npm_cache_dir() -> Option<PathBuf> {
+    if let Ok(cache) = std::env::var("npm_config_cache") {
+        return Some(PathBuf::from(cache));
+    }
+    if cfg!(windows) {
+        return std::env::var("LOCALAPPDATA")
+            .ok()
+            .map(|dir| PathBuf::from(dir).join("npm-cache"));
+    }
+    home_dir().map(|home| home.join(".npm"))
+}
+
+pub fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_classify_source() {
+        assert_eq!(
+            classify_source("https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"),
+            Source::Registry("registry.npmjs.org".to_string())
+        );
+        assert_eq!(
+            classify_source("http://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"),
+            Source::Insecure
+        );
+        assert_eq!(
+            classify_source("git+ssh://git@github.com/user/repo.git#abc123"),
+            Source::Git
+        );
+        assert_eq!(classify_source("file:../local.tgz"), Source::Local);
+    }
+
+    #[test]
+    fn test_parse_integrity() {
+        let content = b"hello";
+        let sha512 = digest::digest(&digest::SHA512, content);
+        let encoded = base64::engine::general_purpose::STANDARD.encode(sha512.as_ref());
+        let integrity = format!("sha512-{} sha1-bogus md5-xyz", encoded);
+
+        let hashes = parse_integrity(&integrity);
+        assert_eq!(hashes.len(), 1);
+        assert_eq!(hashes[0].algorithm, Algorithm::Sha512);
+        assert_eq!(hashes[0].digest, sha512.as_ref());
+    }
+
+    #[test]
+    fn test_verify_cached_tarball() {
+        let cache = TempDir::new("cacache");
+        let content = b"tarball bytes";
+        let digest = digest::digest(&digest::SHA512, content).as_ref().to_vec();
+        let hash = Hash {
+            algorithm: Algorithm::Sha512,
+            digest,
+        };
+        assert_eq!(verify_cached_tarball(&cache, &hash), None);
+
+        let hex: String = hash.digest.iter().map(|b| format!("{:02x}", b)).collect();
+        let dir = cache
+            .join("_cacache/content-v2/sha512")
+            .join(&hex[..2])
+            .join(&hex[2..4]);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(&hex[4..]), content).unwrap();
+        assert_eq!(verify_cached_tarball(&cache, &hash), Some(true));
+
+        std::fs::write(dir.join(&hex[4..]), b"tampered").unwrap();
+        assert_eq!(verify_cached_tarball(&cache, &hash), Some(false));
+    }
+
+    #[test]
+    fn test_is_allowed() {
+        let allowed: Vec<String> = [
+            "registry.npmjs.org",
+            "*.corp.example",
+            "https://npm.pkg.github.com/acme/",
+        ]
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+
+        assert!(is_allowed(
+            &allowed,
+            "https://registry.npmjs.org/a/-/a-1.0.0.tgz"
+        ));
+        assert!(is_allowed(&allowed, "https://npm.corp.example/b.tgz"));
+        assert!(!is_allowed(&allowed, "https://corp.example/b.tgz"));
+        assert!(is_allowed(
+            &allowed,
+            "https://npm.pkg.github.com/acme/c/-/c-1.0.0.tgz"
+        ));
+        assert!(!is_allowed(
+            &allowed,
+            "https://npm.pkg.github.com/other/c/-/c-1.0.0.tgz"
+        ));
+        assert!(!is_allowed(&allowed, "github:user/repo#abc"));
+        assert!(!is_allowed(
+            &allowed,
+            "https://codeload.github.com/user/repo/tar.gz/abc"
+        ));
+    }
+
+    #[test]
+    fn test_npmrc_registries() {
+        let content = "registry=https://npm.corp.example/\n@acme:registry = \"https://npm.acme.dev\"\n; registry=https://ignored\nalways-auth=true\n";
+        assert_eq!(
+            npmrc_registries(content),
+            vec!["https://npm.corp.example/", "https://npm.acme.dev"]
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! Which license each installed package is published under
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use serde_json::Value as JsonValue;
+
+use crate::lockfile::installed_dir;
+use crate::types::{LicenseReport, LicenseSource, Package, PackageLicense};
+
+/// Files a license text is kept in: LICENSE, LICENSE.md, LICENSE-MIT,
+/// LICENCE.txt, COPYING, COPYING.LESSER...
+const LICENSE_FILE_PREFIXES: &[&str] = &["LICENSE", "LICENCE", "COPYING"];
+
+/// Phrases that identify a license's text, all of which must appear,
+/// checked in order so the more specific texts come first
+///
+/// Texts are compared lowercased with their whitespace collapsed.
+const LICENSE_TEXTS: &[(&str, &[&str])] = &[
+    ("AGPL-3.0", &["gnu affero general public license", "version 3"]),
+    ("LGPL-3.0", &["gnu lesser general public license", "version 3"]),
+    ("LGPL-2.1", &["gnu lesser general public license", "version 2.1"]),
+    ("GPL-3.0", &["gnu general public license", "version 3"]),
+    ("GPL-2.0", &["gnu general public license", "version 2"]),
+    ("Apache-2.0", &["apache license", "version 2.0"]),
+    ("MPL-2.0", &["mozilla public license", "2.0"]),
+    (
+        "Unlicense",
+        &["this is free and unencumbered software released into the public domain"],
+    ),
+    ("CC0-1.0", &["cc0 1.0 universal"]),
+    (
+        "ISC",
+        &[
+            "permission to use, copy, modify, and/or distribute this software for any purpose",
+            "provided that the above copyright notice and this permission notice appear in all copies",
+        ],
+    ),
+    (
+        "0BSD",
+        &["permission to use, copy, modify, and/or distribute this software for any purpose"],
+    ),
+    (
+        "MIT",
+        &[
+            "permission is hereby granted, free of charge",
+            "the above copyright notice and this permission notice shall be included",
+        ],
+    ),
+    (
+        "BSD-3-Clause",
+        &[
+            "redistribution and use in source and binary forms",
+            "neither the name of",
+        ],
+    ),
+    (
+        "BSD-2-Clause",
+        &["redistribution and use in source and binary forms"],
+    ),
+];
+
+/// Reads the license of every installed npm, pnpm or bun package
+///
+/// The package.json `license` field is trusted when it names one. When
+/// it's missing or "UNLICENSED", the package's license files are read
+/// and matched against the common licenses' texts instead.
+pub struct LicenseAnalyzer<'a> {
+    root: &'a Path,
+}
+
+impl<'a> LicenseAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn analyze(&self, packages: &HashMap<String, Package>) -> LicenseReport {
+        let mut installed: Vec<&Package> = packages
+            .values()
+            .filter(|package| package.bundled_in.is_none())
+            .collect();
+        installed.sort_by(|a, b| (&a.name, &a.version).cmp(&(&b.name, &b.version)));
+
+        LicenseReport {
+            packages: installed
+                .into_iter()
+                .map(|package| self.package_license(package))
+                .collect(),
+        }
+    }
+
+    fn package_license(&self, package: &Package) -> PackageLicense {
+        let mut license = PackageLicense {
+            name: package.name.clone(),
+            version: package.version.clone(),
+            license: None,
+            source: LicenseSource::NotInstalled,
+            files: Vec::new(),
+        };
+        let Some(dir) = installed_dir(self.root, package) else {
+            return license;
+        };
+
+        let manifest: JsonValue = std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or(JsonValue::Null);
+        if let Some(declared) = manifest_license(&manifest) {
+            license.license = Some(declared);
+            license.source = LicenseSource::Manifest;
+            return license;
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|name| {
+                let upper = name.to_uppercase();
+                LICENSE_FILE_PREFIXES
+                    .iter()
+                    .any(|prefix| upper.starts_with(prefix))
+            })
+            .collect();
+        names.sort();
+
+        // Dual-licensed packages ship one file per license
+        // (LICENSE-MIT, LICENSE-APACHE)
+        let mut licenses = BTreeSet::new();
+        for name in names {
+            let Ok(bytes) = std::fs::read(dir.join(&name)) else {
+                continue;
+            };
+            if let Some(id) = classify_text(&String::from_utf8_lossy(&bytes)) {
+                licenses.insert(id);
+                license.files.push(name);
+            }
+        }
+        if licenses.is_empty() {
+            license.source = LicenseSource::Unknown;
+        } else {
+            license.license = Some(licenses.into_iter().collect::<Vec<_>>().join(" OR "));
+            license.source = LicenseSource::LicenseFile;
+        }
+        license
+    }
+}
+
+/// The license a package.json declares, unless it's "UNLICENSED"
+///
+/// Old packages used `{ "type": "MIT" }` or a `licenses` array instead of
+/// an SPDX string.
+fn manifest_license(manifest: &JsonValue) -> Option<String> {
+    let declared = match &manifest["license"] {
+        JsonValue::String(license) => Some(license.clone()),
+        JsonValue::Object(license) => license
+            .get("type")
+            .and_then(|t| t.as_str())
+            .map(str::to_string),
+        _ => {
+            let types: Vec<&str> = manifest["licenses"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|license| license["type"].as_str())
+                .collect();
+            (!types.is_empty()).then(|| types.join(" OR "))
+        }
+    }?;
+    let declared = declared.trim();
+    (!declared.is_empty() && !declared.eq_ignore_ascii_case("UNLICENSED"))
+        .then(|| declared.to_string())
+}
+
+/// The SPDX identifier of a license text, when it's one of the common ones
+pub fn classify_text(text: &str) -> Option<&'static str> {
+    let text = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    LICENSE_TEXTS
+        .iter()
+        .find(|(_, phrases)| phrases.iter().all(|phrase| text.contains(phrase)))
+        .map(|(id, _)| *id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const MIT: &str = "MIT License\n\nCopyright (c) 2024 Someone\n\nPermission is hereby granted, free of charge, to any person obtaining a copy\nof this software...\n\nThe above copyright notice and this permission notice shall be included in\nall copies or substantial portions of the Software.\n";
+
+    #[test]
+    fn test_classify_text() {
+        assert_eq!(classify_text(MIT), Some("MIT"));
+        assert_eq!(
+            classify_text(
+                "Permission to use, copy, modify, and/or distribute this software for any\npurpose with or without fee is hereby granted, provided that the above\ncopyright notice and this permission notice appear in all copies."
+            ),
+            Some("ISC")
+        );
+        assert_eq!(
+            classify_text("Apache License\n                           Version 2.0, January 2004"),
+            Some("Apache-2.0")
+        );
+        assert_eq!(
+            classify_text("GNU LESSER GENERAL PUBLIC LICENSE\n Version 2.1, February 1999"),
+            Some("LGPL-2.1")
+        );
+        assert_eq!(classify_text("All rights reserved."), None);
+    }
+
+    #[test]
+    fn test_license_file_fallback() {
+        let root = TempDir::new("licenses");
+        let install = |name: &str, manifest: &str, files: &[(&str, &str)]| {
+            let dir = root.join("node_modules").join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join("package.json"), manifest).unwrap();
+            for (file, text) in files {
+                std::fs::write(dir.join(file), text).unwrap();
+            }
+        };
+        install(
+            "no-field",
+            r#"{"name": "no-field", "version": "1.0.0"}"#,
+            &[("LICENSE.md", MIT)],
+        );
+        install(
+            "proprietary",
+            r#"{"name": "proprietary", "version": "2.0.0", "license": "UNLICENSED"}"#,
+            &[("README.md", MIT)],
+        );
+        install(
+            "declared",
+            r#"{"name": "declared", "version": "3.0.0", "license": "Apache-2.0"}"#,
+            &[("LICENSE", MIT)],
+        );
+
+        let packages: HashMap<String, Package> = [
+            Package::new("no-field", "1.0.0"),
+            Package::new("proprietary", "2.0.0"),
+            Package::new("declared", "3.0.0"),
+            Package::new("missing", "4.0.0"),
+        ]
+        .into_iter()
+        .map(|package| (package.name.clone(), package))
+        .collect();
+        let report = LicenseAnalyzer::new(&root).analyze(&packages);
+
+        let licenses: Vec<(&str, Option<&str>, LicenseSource)> = report
+            .packages
+            .iter()
+            .map(|p| (p.name.as_str(), p.license.as_deref(), p.source))
+            .collect();
+        assert_eq!(
+            licenses,
+            vec![
+                ("declared", Some("Apache-2.0"), LicenseSource::Manifest),
+                ("missing", None, LicenseSource::NotInstalled),
+                ("no-field", Some("MIT"), LicenseSource::LicenseFile),
+                ("proprietary", None, LicenseSource::Unknown),
+            ]
+        );
+        assert_eq!(report.packages[2].files, ["LICENSE.md"]);
+    }
+}
@@ -0,0 +1,438 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use miette::Result;
+use oxc_allocator::Allocator;
+use oxc_parser::Parser;
+use oxc_span::SourceType;
+
+use crate::analyzer::ImportAnalyzer;
+use crate::duplicates::DuplicateAnalyzer;
+use crate::lockfile::{LockfileParser, LockfileType};
+use crate::range::parse_version;
+use crate::types::{ImportMap, LintFinding, LintSite, Package, RulePack, Severity};
+
+/// Packages that only work on the server: database drivers, native
+/// modules, and secrets-handling SDKs
+const SERVER_ONLY_PACKAGES: &[&str] = &[
+    "server-only",
+    "@prisma/client",
+    "pg",
+    "mysql2",
+    "mongodb",
+    "mongoose",
+    "ioredis",
+    "redis",
+    "bcrypt",
+    "sharp",
+    "nodemailer",
+    "firebase-admin",
+    "fs-extra",
+    "@aws-sdk/client-s3",
+];
+
+/// NestJS packages released together, which must share a major version
+const NEST_CORE_PACKAGES: &[&str] = &[
+    "@nestjs/common",
+    "@nestjs/core",
+    "@nestjs/microservices",
+    "@nestjs/websockets",
+    "@nestjs/testing",
+    "@nestjs/platform-express",
+    "@nestjs/platform-fastify",
+    "@nestjs/platform-socket.io",
+    "@nestjs/platform-ws",
+];
+
+/// Lints the dependencies with the framework rule packs the project opts into
+///
+/// Rules look at what the lockfile installs, and the Next.js pack also at
+/// which packages client components (files starting with `"use client"`)
+/// import. Copies of a package are counted where `depx duplicates` reads
+/// the lockfile (npm, pnpm, bun, deno).
+pub struct Linter<'a> {
+    root: &'a Path,
+    packs: &'a [RulePack],
+}
+
+/// What the rules look at
+struct LintContext<'a> {
+    packages: &'a HashMap<String, Package>,
+
+    /// Every installed version of each package installed more than once
+    copies: BTreeMap<String, Vec<String>>,
+
+    imports: Option<&'a ImportMap>,
+}
+
+impl LintContext<'_> {
+    fn installed(&self, name: &str) -> Option<&Package> {
+        self.packages.values().find(|pkg| pkg.name == name)
+    }
+
+    fn direct(&self, name: &str) -> Option<&Package> {
+        self.packages
+            .values()
+            .find(|pkg| pkg.is_direct && pkg.name == name)
+    }
+}
+
+impl<'a> Linter<'a> {
+    pub fn new(root: &'a Path, packs: &'a [RulePack]) -> Self {
+        Self { root, packs }
+    }
+
+    pub fn lint(&self, lockfile: &LockfileParser) -> Result<Vec<LintFinding>> {
+        if self.packs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let packages = lockfile.parse()?;
+
+        let copies = match lockfile.lockfile_type() {
+            LockfileType::Npm | LockfileType::Pnpm | LockfileType::Bun | LockfileType::Deno => {
+                DuplicateAnalyzer::new(self.root)
+                    .analyze_lockfile(lockfile)?
+                    .duplicates
+                    .into_iter()
+                    .map(|group| {
+                        let versions = group.versions.into_iter().map(|v| v.version).collect();
+                        (group.name, versions)
+                    })
+                    .collect()
+            }
+            _ => BTreeMap::new(),
+        };
+        let imports = if self.packs.contains(&RulePack::Nextjs) {
+            Some(ImportAnalyzer::new(self.root).analyze()?)
+        } else {
+            None
+        };
+
+        let context = LintContext {
+            packages: &packages,
+            copies,
+            imports: imports.as_ref(),
+        };
+        Ok(lint_packs(self.packs, &context))
+    }
+}
+
+fn lint_packs(packs: &[RulePack], context: &LintContext) -> Vec<LintFinding> {
+    let mut packs = packs.to_vec();
+    packs.sort();
+    packs.dedup();
+
+    let mut findings = Vec::new();
+    for pack in packs {
+        let mut finding = |rule: &str, severity: Severity, packages: &[&str], message: String| {
+            findings.push(LintFinding {
+                pack,
+                rule: rule.to_string(),
+                severity,
+                packages: packages.iter().map(|p| p.to_string()).collect(),
+                message,
+                sites: Vec::new(),
+            });
+        };
+        match pack {
+            RulePack::React => {
+                for name in ["react", "react-dom"] {
+                    if let Some(versions) = context.copies.get(name) {
+                        finding(
+                            "react/multiple-copies",
+                            Severity::High,
+                            &[name],
+                            format!(
+                                "{} copies of {} are installed ({}): hooks fail with \"Invalid hook call\" when a component renders with another copy than the one it imports",
+                                versions.len(),
+                                name,
+                                versions.join(", ")
+                            ),
+                        );
+                    }
+                }
+                if let (Some(react), Some(dom)) =
+                    (context.direct("react"), context.direct("react-dom"))
+                {
+                    if react.version != dom.version {
+                        finding(
+                            "react/dom-version-mismatch",
+                            Severity::Medium,
+                            &["react", "react-dom"],
+                            format!(
+                                "react {} and react-dom {} are released together and must be the same version",
+                                react.version, dom.version
+                            ),
+                        );
+                    }
+                }
+            }
+            RulePack::Nextjs => {
+                if let Some(imports) = context.imports {
+                    findings.extend(server_only_in_client(imports));
+                }
+            }
+            RulePack::Nestjs => {
+                // Microservices and standalone apps (createApplicationContext)
+                // run without an HTTP adapter, so this is only a hint
+                if context.installed("@nestjs/core").is_some()
+                    && context.installed("@nestjs/platform-express").is_none()
+                    && context.installed("@nestjs/platform-fastify").is_none()
+                    && context.installed("@nestjs/microservices").is_none()
+                {
+                    finding(
+                        "nestjs/missing-platform",
+                        Severity::Low,
+                        &["@nestjs/core"],
+                        "@nestjs/core is installed without @nestjs/platform-express or @nestjs/platform-fastify, so NestFactory.create has no HTTP adapter to start; standalone apps using createApplicationContext don't need one".to_string(),
+                    );
+                }
+                if context.installed("@nestjs/websockets").is_some()
+                    && context.installed("@nestjs/platform-socket.io").is_none()
+                    && context.installed("@nestjs/platform-ws").is_none()
+                {
+                    finding(
+                        "nestjs/missing-websocket-adapter",
+                        Severity::High,
+                        &["@nestjs/websockets"],
+                        "@nestjs/websockets is installed without @nestjs/platform-socket.io or @nestjs/platform-ws, so gateways have no adapter".to_string(),
+                    );
+                }
+                let majors: BTreeMap<&str, u64> = NEST_CORE_PACKAGES
+                    .iter()
+                    .filter_map(|name| {
+                        let version = parse_version(&context.direct(name)?.version)?;
+                        Some((*name, version.major))
+                    })
+                    .collect();
+                let mut distinct: Vec<u64> = majors.values().copied().collect();
+                distinct.sort();
+                distinct.dedup();
+                if distinct.len() > 1 {
+                    let names: Vec<&str> = majors.keys().copied().collect();
+                    let listed: Vec<String> = majors
+                        .iter()
+                        .map(|(name, major)| format!("{}@{}", name, major))
+                        .collect();
+                    finding(
+                        "nestjs/major-mismatch",
+                        Severity::High,
+                        &names,
+                        format!(
+                            "NestJS packages are released together, but {} are on different major versions",
+                            listed.join(", ")
+                        ),
+                    );
+                }
+            }
+            RulePack::Express => {
+                let express = context
+                    .direct("express")
+                    .and_then(|pkg| parse_version(&pkg.version));
+                let types = context
+                    .direct("@types/express")
+                    .and_then(|pkg| parse_version(&pkg.version));
+                if let (Some(express), Some(types)) = (&express, &types) {
+                    if express.major != types.major {
+                        finding(
+                            "express/types-mismatch",
+                            Severity::Low,
+                            &["express", "@types/express"],
+                            format!(
+                                "@types/express {} describes Express {}, but Express {} is installed",
+                                types, types.major, express
+                            ),
+                        );
+                    }
+                }
+                if express.is_some_and(|v| (v.major, v.minor) >= (4, 16))
+                    && context.direct("body-parser").is_some()
+                {
+                    finding(
+                        "express/redundant-body-parser",
+                        Severity::Low,
+                        &["body-parser"],
+                        "Express 4.16+ includes body-parser as express.json() and express.urlencoded()".to_string(),
+                    );
+                }
+            }
+        }
+    }
+    findings
+}
+
+/// Server-only packages imported from client components
+fn server_only_in_client(imports: &ImportMap) -> Vec<LintFinding> {
+    let mut client_files: HashMap<&Path, bool> = HashMap::new();
+    let mut findings = Vec::new();
+    for name in SERVER_ONLY_PACKAGES {
+        let Some(usages) = imports.get_package_usages(name) else {
+            continue;
+        };
+        let mut sites: Vec<LintSite> = usages
+            .iter()
+            .filter(|usage| {
+                *client_files
+                    .entry(usage.file_path.as_path())
+                    .or_insert_with(|| is_client_component(&usage.file_path))
+            })
+            .map(|usage| LintSite {
+                file_path: usage.file_path.clone(),
+                line: usage.line,
+            })
+            .collect();
+        if sites.is_empty() {
+            continue;
+        }
+        sites.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+        findings.push(LintFinding {
+            pack: RulePack::Nextjs,
+            rule: "nextjs/server-only-in-client".to_string(),
+            severity: Severity::High,
+            packages: vec![name.to_string()],
+            message: format!(
+                "{} only runs on the server, but client components import it, which fails the build or ships it to the browser",
+                name
+            ),
+            sites,
+        });
+    }
+    findings
+}
+
+/// Whether a file opens with the `"use client"` directive
+fn is_client_component(path: &Path) -> bool {
+    let Ok(source) = std::fs::read_to_string(path) else {
+        return false;
+    };
+    let allocator = Allocator::default();
+    let source_type = SourceType::from_path(path).unwrap_or_default();
+    let parsed = Parser::new(&allocator, &source, source_type).parse();
+    parsed
+        .program
+        .directives
+        .iter()
+        .any(|d| d.directive == "use client")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use crate::types::{Import, ImportKind};
+
+    #[test]
+    fn test_rule_packs() {
+        let root = TempDir::new("lint");
+        std::fs::create_dir_all(root.join("app")).unwrap();
+        std::fs::write(
+            root.join("app/button.tsx"),
+            "// A button\n'use client';\nimport { db } from '@prisma/client';\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("app/page.tsx"),
+            "import { db } from '@prisma/client';\n",
+        )
+        .unwrap();
+
+        let mut packages = HashMap::new();
+        for (name, version) in [
+            ("react", "18.2.0"),
+            ("react-dom", "18.3.1"),
+            ("@nestjs/core", "10.3.0"),
+            ("@nestjs/common", "9.4.0"),
+            ("@nestjs/platform-fastify", "10.3.0"),
+            ("@nestjs/websockets", "10.3.0"),
+            ("express", "4.18.2"),
+            ("@types/express", "5.0.0"),
+            ("body-parser", "1.20.2"),
+        ] {
+            packages.insert(name.to_string(), Package::new(name, version).direct());
+        }
+        let mut imports = ImportMap::new();
+        for file in ["app/button.tsx", "app/page.tsx"] {
+            imports.add_import(Import {
+                file_path: root.join(file),
+                line: if file == "app/button.tsx" { 3 } else { 1 },
+                specifier: "@prisma/client".to_string(),
+                kind: ImportKind::EsModule,
+                resolved_package: Some("@prisma/client".to_string()),
+            });
+        }
+        let context = LintContext {
+            packages: &packages,
+            copies: BTreeMap::from([(
+                "react".to_string(),
+                vec!["17.0.2".to_string(), "18.2.0".to_string()],
+            )]),
+            imports: Some(&imports),
+        };
+
+        let findings = lint_packs(
+            &[
+                RulePack::Express,
+                RulePack::React,
+                RulePack::Nextjs,
+                RulePack::Nestjs,
+            ],
+            &context,
+        );
+
+        let rules: Vec<&str> = findings.iter().map(|f| f.rule.as_str()).collect();
+        assert_eq!(
+            rules,
+            vec![
+                "react/multiple-copies",
+                "react/dom-version-mismatch",
+                "nextjs/server-only-in-client",
+                "nestjs/missing-websocket-adapter",
+                "nestjs/major-mismatch",
+                "express/types-mismatch",
+                "express/redundant-body-parser",
+            ]
+        );
+        assert_eq!(
+            findings[2].sites,
+            vec![LintSite {
+                file_path: root.join("app/button.tsx"),
+                line: 3
+            }]
+        );
+        assert_eq!(
+            findings[4].packages,
+            vec![
+                "@nestjs/common",
+                "@nestjs/core",
+                "@nestjs/platform-fastify",
+                "@nestjs/websockets"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nest_platform() {
+        let lint = |names: &[&str]| -> Vec<(String, Severity)> {
+            let packages: HashMap<String, Package> = names
+                .iter()
+                .map(|name| (name.to_string(), Package::new(*name, "10.3.0").direct()))
+                .collect();
+            let context = LintContext {
+                packages: &packages,
+                copies: BTreeMap::new(),
+                imports: None,
+            };
+            lint_packs(&[RulePack::Nestjs], &context)
+                .into_iter()
+                .map(|f| (f.rule, f.severity))
+                .collect()
+        };
+
+        assert_eq!(
+            lint(&["@nestjs/core"]),
+            vec![("nestjs/missing-platform".to_string(), Severity::Low)]
+        );
+        // A microservice listens on its transport instead
+        assert!(lint(&["@nestjs/core", "@nestjs/microservices"]).is_empty());
+    }
+}
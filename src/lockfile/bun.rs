@@ -0,0 +1,606 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::lockfile::CargoPackageInfo;
+use crate::types::{Package, PackageSource};
+
+/// What bun.lockb starts with
+const BINARY_LOCKFILE_HEADER: &[u8] = b"#!/usr/bin/env bun";
+
+/// Parser for Bun's text lockfile, bun.lock
+///
+/// The binary bun.lockb predecessor isn't documented or stable across Bun
+/// versions, so it is detected but not read: parsing it fails with a hint
+/// to write bun.lock instead.
+pub struct BunLockfileParser<'a> {
+    root: &'a Path,
+    lockfile_path: &'a Path,
+}
+
+/// One `packages` entry: `[ "name@version", registry, { metadata }, integrity ]`
+#[derive(Debug, Clone, Default)]
+struct BunEntry {
+    name: String,
+    version: String,
+    /// `workspace:` path for workspace projects
+    workspace: Option<String>,
+    dependencies: BTreeMap<String, String>,
+    optional_dependencies: BTreeMap<String, String>,
+    peer_dependencies: HashMap<String, String>,
+    optional_peers: Vec<String>,
+    bins: Vec<String>,
+    os: Vec<String>,
+    cpu: Vec<String>,
+    libc: Vec<String>,
+}
+
+impl BunEntry {
+    fn is_platform_specific(&self) -> bool {
+        !self.os.is_empty() || !self.cpu.is_empty() || !self.libc.is_empty()
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct BunLockfile {
+    #[serde(default)]
+    workspaces: BTreeMap<String, BunWorkspace>,
+    #[serde(default)]
+    packages: BTreeMap<String, JsonValue>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BunWorkspace {
+    #[serde(default)]
+    dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    dev_dependencies: BTreeMap<String, String>,
+    #[serde(default)]
+    optional_dependencies: BTreeMap<String, String>,
+}
+
+impl<'a> BunLockfileParser<'a> {
+    pub fn new(root: &'a Path, lockfile_path: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile_path,
+        }
+    }
+
+    /// Parse every installed package
+    ///
+    /// Dependencies declared by any workspace are direct; they're dev
+    /// dependencies when no workspace needs them at runtime. Packages only
+    /// reachable through dev dependencies are dev packages. Keys like
+    /// "parent/child" are copies nested under another package; the hoisted
+    /// copy (keyed by its own name) is the one reported.
+    pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let entries = self.load()?;
+        let workspaces = &entries.workspaces;
+
+        let mut direct_prod: HashSet<&str> = HashSet::new();
+        let mut direct_dev: HashSet<&str> = HashSet::new();
+        let mut required: HashSet<&str> = HashSet::new();
+        let mut optional_refs: HashSet<&str> = HashSet::new();
+        for workspace in workspaces.values() {
+            direct_prod.extend(workspace.dependencies.keys().map(|k| k.as_str()));
+            direct_prod.extend(workspace.optional_dependencies.keys().map(|k| k.as_str()));
+            direct_dev.extend(workspace.dev_dependencies.keys().map(|k| k.as_str()));
+            required.extend(workspace.dependencies.keys().map(|k| k.as_str()));
+            required.extend(workspace.dev_dependencies.keys().map(|k| k.as_str()));
+            optional_refs.extend(workspace.optional_dependencies.keys().map(|k| k.as_str()));
+        }
+
+        // Aliases are hoisted under the alias, and reported under the name
+        // they install unless that package is installed too
+        let hoisted: BTreeMap<&str, &BunEntry> = entries
+            .packages
+            .iter()
+            .filter(|(key, entry)| {
+                key.as_str() == entry.name
+                    || (is_hoisted_key(key) && !entries.packages.contains_key(&entry.name))
+            })
+            .map(|(key, entry)| (key.as_str(), entry))
+            .collect();
+        for entry in entries.packages.values() {
+            required.extend(entry.dependencies.keys().map(|k| k.as_str()));
+            optional_refs.extend(entry.optional_dependencies.keys().map(|k| k.as_str()));
+        }
+
+        // Packages runtime code can reach
+        let mut prod: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<&str> = direct_prod.iter().copied().collect();
+        while let Some(name) = queue.pop_front() {
+            if !prod.insert(name) {
+                continue;
+            }
+            if let Some(entry) = hoisted.get(name) {
+                queue.extend(
+                    entry
+                        .dependencies
+                        .keys()
+                        .chain(entry.optional_dependencies.keys())
+                        .map(|k| k.as_str()),
+                );
+            }
+        }
+
+        let mut platform_parents: HashMap<&str, &str> = HashMap::new();
+        for (name, entry) in &hoisted {
+            for child in entry.optional_dependencies.keys() {
+                if hoisted
+                    .get(child.as_str())
+                    .is_some_and(|c| c.is_platform_specific())
+                {
+                    // BTreeMap order keeps the first parent alphabetically
+                    platform_parents.entry(child.as_str()).or_insert(name);
+                }
+            }
+        }
+
+        let mut packages = HashMap::new();
+        for (name, entry) in &hoisted {
+            let is_direct = direct_prod.contains(name) || direct_dev.contains(name);
+            let is_dev = if is_direct {
+                !direct_prod.contains(name)
+            } else {
+                !prod.contains(name)
+            };
+
+            let (version, dependencies) = match &entry.workspace {
+                Some(path) => {
+                    let dir = self.root.join(path);
+                    // The workspace's own section lists what it depends on
+                    let dependencies = workspaces
+                        .get(path.as_str())
+                        .map(|w| {
+                            w.dependencies
+                                .keys()
+                                .chain(w.optional_dependencies.keys())
+                                .cloned()
+                                .collect()
+                        })
+                        .unwrap_or_default();
+                    (manifest_version(&dir), dependencies)
+                }
+                None => (
+                    entry.version.clone(),
+                    entry
+                        .dependencies
+                        .keys()
+                        .chain(entry.optional_dependencies.keys())
+                        .cloned()
+                        .collect(),
+                ),
+            };
+
+            packages.insert(
+                entry.name.clone(),
+                Package {
+                    name: entry.name.clone(),
+                    version,
+                    is_direct,
+                    is_dev,
+                    is_build: false,
+                    dependencies,
+                    dependency_ranges: entry
+                        .dependencies
+                        .iter()
+                        .chain(entry.optional_dependencies.iter())
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect(),
+                    peer_dependencies: entry.peer_dependencies.clone(),
+                    optional_peers: entry.optional_peers.clone(),
+                    deprecated: None,
+                    bins: entry.bins.clone(),
+                    is_optional: optional_refs.contains(name) && !required.contains(name),
+                    os: entry.os.clone(),
+                    cpu: entry.cpu.clone(),
+                    libc: entry.libc.clone(),
+                    platform_parent: platform_parents.get(name).map(|p| p.to_string()),
+                    bundled_in: None,
+                    other_target: None,
+                    source: if entry.workspace.is_some() {
+                        PackageSource::Link
+                    } else {
+                        PackageSource::of(&entry.version)
+                    },
+                    purl: None,
+                    cpe: None,
+                },
+            );
+        }
+
+        Ok(packages)
+    }
+
+    /// Distinct versions of every package with the packages depending on them
+    pub fn parse_for_duplicates(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        let entries = self.load()?;
+
+        let mut by_name: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+        for entry in entries.packages.values() {
+            if entry.workspace.is_some() {
+                continue;
+            }
+            let versions = by_name.entry(entry.name.clone()).or_default();
+            if !versions.iter().any(|v| v.version == entry.version) {
+                versions.push(CargoPackageInfo {
+                    version: entry.version.clone(),
+                    dependents: Vec::new(),
+                    is_path_dep: entry.version.starts_with("file:")
+                        || entry.version.starts_with("link:"),
+                    requirements: HashMap::new(),
+                });
+            }
+        }
+
+        // A dependency resolves to the copy nested under the dependent, else the hoisted one
+        for (key, entry) in &entries.packages {
+            let dependent = format!("{}@{}", entry.name, entry.version);
+            for (child, range) in entry
+                .dependencies
+                .iter()
+                .chain(entry.optional_dependencies.iter())
+            {
+                let resolved = entries
+                    .packages
+                    .get(&format!("{}/{}", key, child))
+                    .or_else(|| entries.packages.get(child));
+                let Some(resolved) = resolved else {
+                    continue;
+                };
+                let Some(version) = by_name.get_mut(&resolved.name).and_then(|versions| {
+                    versions.iter_mut().find(|v| v.version == resolved.version)
+                }) else {
+                    continue;
+                };
+                if !version.dependents.contains(&dependent) {
+                    version.dependents.push(dependent.clone());
+                }
+                version
+                    .requirements
+                    .insert(dependent.clone(), range.clone());
+            }
+        }
+
+        for versions in by_name.values_mut() {
+            for version in versions.iter_mut() {
+                version.dependents.sort();
+            }
+        }
+
+        Ok(by_name)
+    }
+
+    fn load(&self) -> Result<Entries> {
+        let binary = || {
+            miette::miette!(
+                "{} is Bun's binary lockfile, which depx can't read. Run `bun install --save-text-lockfile` (Bun 1.1.39+) to write bun.lock next to it",
+                self.lockfile_path.display()
+            )
+        };
+        if self
+            .lockfile_path
+            .extension()
+            .is_some_and(|ext| ext == "lockb")
+        {
+            return Err(binary());
+        }
+
+        let bytes = std::fs::read(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+        // A bun.lockb renamed or passed through --lockfile
+        if bytes.starts_with(BINARY_LOCKFILE_HEADER) {
+            return Err(binary());
+        }
+        let content = String::from_utf8(bytes).map_err(|_| binary())?;
+        let lockfile: BunLockfile = serde_json::from_str(&strip_trailing_commas(&content))
+            .into_diagnostic()
+            .with_context(|| "Failed to parse bun.lock")?;
+
+        let packages = lockfile
+            .packages
+            .iter()
+            .filter_map(|(key, value)| Some((key.clone(), parse_entry(value)?)))
+            .collect();
+        Ok(Entries {
+            workspaces: lockfile.workspaces,
+            packages,
+        })
+    }
+}
+
+/// Whether a `packages` key is a package's own ("chalk", "@scope/pkg")
+/// rather than a copy nested under another ("parent/chalk")
+fn is_hoisted_key(key: &str) -> bool {
+    key.matches('/').count() == usize::from(key.starts_with('@'))
+}
+
+/// bun.lock with its package entries decoded
+struct Entries {
+    workspaces: BTreeMap<String, BunWorkspace>,
+    packages: BTreeMap<String, BunEntry>,
+}
+
+fn parse_entry(value: &JsonValue) -> Option<BunEntry> {
+    let fields = value.as_array()?;
+    let ident = fields.first()?.as_str()?;
+    // The name can be scoped, so split at the first '@' after the scope's
+    let (scope, unscoped) = match ident.strip_prefix('@') {
+        Some(unscoped) => ("@", unscoped),
+        None => ("", ident),
+    };
+    let (name, reference) = unscoped.split_once('@')?;
+
+    let mut entry = BunEntry {
+        name: format!("{}{}", scope, name),
+        version: reference.to_string(),
+        ..BunEntry::default()
+    };
+    if let Some(path) = reference.strip_prefix("workspace:") {
+        entry.workspace = Some(path.to_string());
+        return Some(entry);
+    }
+
+    let Some(metadata) = fields.iter().find_map(|f| f.as_object()) else {
+        return Some(entry);
+    };
+    let map = |key: &str| -> BTreeMap<String, String> {
+        metadata
+            .get(key)
+            .and_then(|v| v.as_object())
+            .map(|deps| {
+                deps.iter()
+                    .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let list = |key: &str| -> Vec<String> {
+        match metadata.get(key) {
+            Some(JsonValue::String(value)) if value != "none" => vec![value.clone()],
+            Some(JsonValue::Array(values)) => values
+                .iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect(),
+            _ => Vec::new(),
+        }
+    };
+
+    entry.dependencies = map("dependencies");
+    entry.optional_dependencies = map("optionalDependencies");
+    entry.peer_dependencies = map("peerDependencies").into_iter().collect();
+    entry.optional_peers = list("optionalPeers");
+    entry.bins = match metadata.get("bin") {
+        // Scoped packages expose the unscoped part as the command
+        Some(JsonValue::String(_)) => vec![name.rsplit('/').next().unwrap_or(name).to_string()],
+        Some(JsonValue::Object(bins)) => bins.keys().cloned().collect(),
+        _ => Vec::new(),
+    };
+    entry.os = list("os");
+    entry.cpu = list("cpu");
+    entry.libc = list("libc");
+    Some(entry)
+}
+
+/// Drop the trailing commas bun.lock allows before `}` and `]`
+fn strip_trailing_commas(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut pending_comma: Option<String> = None;
+
+    for c in content.chars() {
+        if in_string {
+            output.push(c);
+            match (escaped, c) {
+                (true, _) => escaped = false,
+                (false, '\\') => escaped = true,
+                (false, '"') => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        if let Some(pending) = pending_comma.as_mut() {
+            if c.is_whitespace() {
+                pending.push(c);
+                continue;
+            }
+            let pending = pending_comma.take().unwrap_or_default();
+            if c == '}' || c == ']' {
+                output.push_str(&pending[1..]);
+            } else {
+                output.push_str(&pending);
+            }
+        }
+
+        match c {
+            ',' => pending_comma = Some(",".to_string()),
+            '"' => {
+                in_string = true;
+                output.push(c);
+            }
+            _ => output.push(c),
+        }
+    }
+    if let Some(pending) = pending_comma {
+        output.push_str(&pending);
+    }
+
+    output
+}
+
+/// Version from a workspace project's package.json
+fn manifest_version(dir: &Path) -> String {
+    std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<JsonValue>(&content).ok())
+        .and_then(|manifest| manifest.get("version")?.as_str().map(String::from))
+        .unwrap_or_else(|| "0.0.0".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const LOCKFILE: &str = r#"{
+  "lockfileVersion": 1,
+  "workspaces": {
+    "": {
+      "name": "app",
+      "dependencies": {
+        "@acme/ui": "workspace:*",
+        "@std/path": "npm:@jsr/std__path@^1.0.8",
+        "esbuild": "^0.19.0",
+        "express": "^4.18.0",
+      },
+      "devDependencies": {
+        "typescript": "^5.3.0",
+      },
+    },
+    "packages/ui": {
+      "name": "@acme/ui",
+      "dependencies": {
+        "debug": "^4.0.0",
+      },
+    },
+  },
+  "packages": {
+    "@acme/ui": ["@acme/ui@workspace:packages/ui"],
+
+    "@esbuild/linux-x64": ["@esbuild/linux-x64@0.19.0", "", { "os": "linux", "cpu": "x64" }, "sha512-a"],
+
+    "@std/path": ["@jsr/std__path@1.0.8", "https://npm.jsr.io/~/11/@jsr/std__path/1.0.8.tgz", {}, "sha512-i"],
+
+    "debug": ["debug@4.3.4", "", { "dependencies": { "ms": "2.1.2" } }, "sha512-b"],
+
+    "esbuild": ["esbuild@0.19.0", "", { "optionalDependencies": { "@esbuild/linux-x64": "0.19.0" }, "bin": { "esbuild": "bin/esbuild" } }, "sha512-c"],
+
+    "express": ["express@4.18.2", "", { "dependencies": { "debug": "2.6.9" } }, "sha512-d"],
+
+    "ms": ["ms@2.1.2", "", {}, "sha512-e"],
+
+    "typescript": ["typescript@5.3.3", "", { "bin": { "tsc": "bin/tsc", "tsserver": "bin/tsserver" } }, "sha512-f"],
+
+    "express/debug": ["debug@2.6.9", "", { "dependencies": { "ms": "2.0.0" } }, "sha512-g"],
+
+    "express/debug/ms": ["ms@2.0.0", "", {}, "sha512-h"],
+  }
+}
+"#;
+
+    fn write_fixture(name: &str) -> TempDir {
+        let root = TempDir::new(&format!("bun-{}", name));
+        std::fs::create_dir_all(root.join("packages/ui")).unwrap();
+        std::fs::write(
+            root.join("packages/ui/package.json"),
+            r#"{"name": "@acme/ui", "version": "1.2.0"}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("bun.lock"), LOCKFILE).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_parse() {
+        let root = write_fixture("parse");
+        let lockfile = root.join("bun.lock");
+        let packages = BunLockfileParser::new(&root, &lockfile).parse().unwrap();
+
+        assert_eq!(packages.len(), 8);
+        let express = &packages["express"];
+        assert!(express.is_direct && !express.is_dev);
+        assert_eq!(express.version, "4.18.2");
+        assert_eq!(express.dependencies, vec!["debug"]);
+
+        assert!(packages["typescript"].is_direct && packages["typescript"].is_dev);
+        assert_eq!(packages["typescript"].bins, vec!["tsc", "tsserver"]);
+
+        let ui = &packages["@acme/ui"];
+        assert!(ui.is_direct);
+        assert_eq!(ui.version, "1.2.0");
+        assert_eq!(ui.dependencies, vec!["debug"]);
+        assert!(!packages["debug"].is_dev && !packages["ms"].is_dev);
+
+        let native = &packages["@esbuild/linux-x64"];
+        assert!(native.is_optional);
+        assert_eq!(native.os, vec!["linux"]);
+        assert_eq!(native.platform_parent.as_deref(), Some("esbuild"));
+
+        // Installed from JSR's npm registry under an alias
+        let path = &packages["@jsr/std__path"];
+        assert!(path.is_direct && !path.is_dev);
+        assert_eq!(path.version, "1.0.8");
+    }
+
+    #[test]
+    fn test_parse_for_duplicates() {
+        let root = write_fixture("dupes");
+        let lockfile = root.join("bun.lock");
+        let by_name = BunLockfileParser::new(&root, &lockfile)
+            .parse_for_duplicates()
+            .unwrap();
+
+        let mut debug: Vec<(&str, &[String])> = by_name["debug"]
+            .iter()
+            .map(|v| (v.version.as_str(), v.dependents.as_slice()))
+            .collect();
+        debug.sort();
+        assert_eq!(
+            debug,
+            vec![
+                ("2.6.9", &["express@4.18.2".to_string()][..]),
+                ("4.3.4", &[][..]),
+            ]
+        );
+        assert_eq!(by_name["ms"].len(), 2);
+        assert!(!by_name.contains_key("@acme/ui"));
+    }
+
+    #[test]
+    fn test_strip_trailing_commas() {
+        assert_eq!(
+            strip_trailing_commas("{\"a\": [1, 2,],\n \"b,\": \"},\",\n}"),
+            "{\"a\": [1, 2],\n \"b,\": \"},\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_binary_lockfile() {
+        let path = Path::new("bun.lockb");
+        let error = BunLockfileParser::new(Path::new("."), path)
+            .parse()
+            .unwrap_err();
+        assert!(error.to_string().contains("--save-text-lockfile"));
+
+        let root = TempDir::new("bun-binary");
+        let path = root.join("bun.lock");
+        std::fs::write(
+            &path,
+            b"#!/usr/bin/env bun\nbun-lockfile-format-v0\n\x02\x00\xff",
+        )
+        .unwrap();
+        let error = BunLockfileParser::new(&root, &path).parse().unwrap_err();
+        assert!(error.to_string().contains("--save-text-lockfile"));
+    }
+
+    #[test]
+    fn test_malformed_idents() {
+        for ident in ["", "@", "lodash", "@scope/pkg"] {
+            assert!(parse_entry(&serde_json::json!([ident])).is_none());
+        }
+        let entry = parse_entry(&serde_json::json!(["@scope/pkg@1.0.0"])).unwrap();
+        assert_eq!(
+            (entry.name.as_str(), entry.version.as_str()),
+            ("@scope/pkg", "1.0.0")
+        );
+    }
+}
@@ -1,24 +1,44 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use miette::Result;
 use serde::Deserialize;
+use toml::Value as TomlValue;
 
+use crate::integrity::home_dir;
+use crate::target::Target;
 use crate::types::Package;
+use crate::workspace;
 
 /// Parser for Cargo.lock files (Rust projects)
 pub struct CargoLockfileParser<'a> {
     lockfile_path: &'a Path,
+
+    /// The platform whose `[target.'cfg(...)']` dependencies apply
+    target: Target,
+
+    /// The one workspace member being analyzed, by crate name
+    member: Option<String>,
 }
 
 /// Cargo.lock format (TOML)
 #[derive(Debug, Deserialize)]
 struct CargoLockfile {
     #[serde(default)]
+    #[allow(dead_code)]
     version: Option<u32>,
     #[serde(default)]
     package: Vec<CargoPackage>,
+    #[serde(default)]
+    patch: CargoLockfilePatch,
+}
+
+/// `[[patch.unused]]` entries Cargo records for patches that matched nothing
+#[derive(Debug, Deserialize, Default)]
+struct CargoLockfilePatch {
+    #[serde(default)]
+    unused: Vec<CargoPackage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,11 +49,31 @@ struct CargoPackage {
     source: Option<String>,
     #[serde(default)]
     dependencies: Option<Vec<String>>,
+    /// SHA-256 of the registry's `.crate` file, as hex
+    #[serde(default)]
+    checksum: Option<String>,
 }
 
 impl<'a> CargoLockfileParser<'a> {
     pub fn new(lockfile_path: &'a Path) -> Self {
-        Self { lockfile_path }
+        Self {
+            lockfile_path,
+            target: Target::host(),
+            member: None,
+        }
+    }
+
+    /// Analyze for `target` instead of the host
+    pub fn with_target(mut self, target: Target) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Analyze one workspace member: only its dependencies are direct, and
+    /// dev and build packages are those of its manifest
+    pub fn with_member(mut self, name: String) -> Self {
+        self.member = Some(name);
+        self
     }
 
     pub fn parse(&self) -> Result<HashMap<String, Package>> {
@@ -46,11 +86,31 @@ impl<'a> CargoLockfileParser<'a> {
         self.build_package_map(&lockfile)
     }
 
+    /// Build the package map, keyed by "name@version"
+    ///
+    /// Workspace members are the project itself: the registry and git
+    /// crates they depend on are the direct dependencies. Crates the
+    /// members can't reach without `[dev-dependencies]` are dev packages,
+    /// and ones only reached through `[build-dependencies]` (the members'
+    /// or a dependency's) are build packages. Direct dependencies every
+    /// member only declares for other targets are marked `other_target`.
+    /// Without a Cargo.toml next to the lockfile, every crate without a
+    /// `source` is taken to be a member. With `member` set, that member
+    /// alone is the project, and the others are followed like any crate.
     fn build_package_map(&self, lockfile: &CargoLockfile) -> Result<HashMap<String, Package>> {
         let mut packages = HashMap::new();
+        let versions_by_name = versions_by_name(lockfile);
+
+        let root = self.lockfile_path.parent().unwrap_or(Path::new("."));
+        let manifests = member_manifests(root, &self.target)?;
+        let is_member = |pkg: &CargoPackage| {
+            pkg.source.is_none()
+                && (manifests.is_empty() || manifests.iter().any(|m| m.name == pkg.name))
+        };
 
         // First pass: collect all packages with their versions
         // Use name@version as key since same crate can have multiple versions
+        let mut member_keys: HashSet<String> = HashSet::new();
         for pkg in &lockfile.package {
             let key = format!("{}@{}", pkg.name, pkg.version);
 
@@ -60,30 +120,137 @@ impl<'a> CargoLockfileParser<'a> {
                 .as_ref()
                 .map(|deps| {
                     deps.iter()
-                        .map(|d| {
-                            // Dependencies are in format "name version" or just "name"
-                            let parts: Vec<&str> = d.split_whitespace().collect();
-                            if parts.len() >= 2 {
-                                format!("{}@{}", parts[0], parts[1])
-                            } else {
-                                parts[0].to_string()
-                            }
-                        })
+                        .map(|d| dependency_key(d, &versions_by_name))
                         .collect()
                 })
                 .unwrap_or_default();
 
-            let package = Package::new(&pkg.name, &pkg.version).with_dependencies(deps);
+            if is_member(pkg) {
+                member_keys.insert(key.clone());
+            }
+            packages.insert(
+                key,
+                Package::new(&pkg.name, &pkg.version).with_dependencies(deps),
+            );
+        }
+
+        let kinds_by_member: HashMap<&str, &HashMap<String, DependencyKind>> = manifests
+            .iter()
+            .map(|m| (m.name.as_str(), &m.dependencies))
+            .collect();
+
+        let local: HashSet<String> = lockfile
+            .package
+            .iter()
+            .filter(|pkg| pkg.source.is_none())
+            .map(|pkg| format!("{}@{}", pkg.name, pkg.version))
+            .collect();
 
-            // Mark path dependencies (no source) as "direct" for now
-            // In Cargo, the root crate has no source field
-            let package = if pkg.source.is_none() {
-                package.direct()
+        // How each dependency is declared. Cargo.lock doesn't say, so members
+        // use their manifests and registry crates their unpacked Cargo.toml;
+        // anything unknown counts as a normal dependency.
+        let mut edges: HashMap<String, Vec<(String, DependencyKind)>> = HashMap::new();
+        for pkg in &lockfile.package {
+            let key = format!("{}@{}", pkg.name, pkg.version);
+            let declared = if is_member(pkg) {
+                kinds_by_member.get(pkg.name.as_str()).map(|k| (*k).clone())
+            } else if pkg.source.is_some() {
+                registry_source_dir(&pkg.name, &pkg.version)
+                    .and_then(|dir| fs::read_to_string(dir.join("Cargo.toml")).ok())
+                    .and_then(|content| toml::from_str::<TomlValue>(&content).ok())
+                    .map(|manifest| declared_dependencies(&manifest, None, &self.target).kinds)
             } else {
-                package
+                None
             };
+            let package_edges = packages[&key]
+                .dependencies
+                .iter()
+                .map(|dep| {
+                    let name = packages.get(dep).map_or(dep.as_str(), |d| d.name.as_str());
+                    let kind = declared
+                        .as_ref()
+                        .and_then(|kinds| kinds.get(name).copied())
+                        .unwrap_or(DependencyKind::Normal);
+                    (dep.clone(), kind)
+                })
+                .collect();
+            edges.insert(key, package_edges);
+        }
+
+        let roots: HashSet<String> = match &self.member {
+            Some(name) => member_keys
+                .iter()
+                .filter(|key| packages[*key].name == *name)
+                .cloned()
+                .collect(),
+            None => member_keys,
+        };
+
+        for key in &roots {
+            for (dep, _) in edges.get(key).into_iter().flatten() {
+                if let Some(package) = packages.get_mut(dep) {
+                    if !local.contains(dep) {
+                        package.is_direct = true;
+                    }
+                }
+            }
+        }
 
-            packages.insert(key, package);
+        // One member building it for this target is enough
+        let mut other_targets: HashMap<&str, Option<&String>> = HashMap::new();
+        for manifest in manifests
+            .iter()
+            .filter(|m| self.member.as_ref().is_none_or(|name| m.name == *name))
+        {
+            for name in manifest.dependencies.keys() {
+                let other = manifest.other_targets.get(name);
+                other_targets
+                    .entry(name.as_str())
+                    .and_modify(|current| *current = other.and(*current))
+                    .or_insert(other);
+            }
+        }
+        for package in packages.values_mut().filter(|pkg| pkg.is_direct) {
+            if let Some(Some(cfg)) = other_targets.get(package.name.as_str()) {
+                package.other_target = Some((*cfg).clone());
+            }
+        }
+
+        // Crates needed at runtime follow normal dependencies from the
+        // members; those needed to build also follow build-dependencies
+        let reachable = |kinds: &[DependencyKind]| -> HashSet<String> {
+            let mut queue: VecDeque<&String> = roots
+                .iter()
+                .flat_map(|key| edges.get(key).into_iter().flatten())
+                .filter(|(_, kind)| kinds.contains(kind))
+                .map(|(dep, _)| dep)
+                .collect();
+            let mut seen: HashSet<String> = HashSet::new();
+            while let Some(key) = queue.pop_front() {
+                // Members' own dependencies are queued above
+                if roots.contains(key) || !seen.insert(key.clone()) {
+                    continue;
+                }
+                queue.extend(
+                    edges
+                        .get(key.as_str())
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, kind)| kinds.contains(kind))
+                        .map(|(dep, _)| dep),
+                );
+            }
+            seen
+        };
+        let runtime = reachable(&[DependencyKind::Normal]);
+        let build = reachable(&[DependencyKind::Normal, DependencyKind::Build]);
+
+        for (key, package) in &mut packages {
+            if local.contains(key) {
+                continue;
+            }
+            package.is_dev = !build.contains(key);
+            package.is_build = build.contains(key) && !runtime.contains(key);
         }
 
         Ok(packages)
@@ -103,19 +270,43 @@ impl<'a> CargoLockfileParser<'a> {
         // Build a reverse dependency map
         let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
 
+        let versions_by_name = versions_by_name(&lockfile);
+
+        // Requirements come from the members' manifests and the unpacked
+        // sources of registry crates; Cargo.lock only has what was picked
+        let root = self.lockfile_path.parent().unwrap_or(Path::new("."));
+        let member_requirements: HashMap<String, HashMap<String, String>> =
+            member_manifests(root, &Target::host())?
+                .into_iter()
+                .map(|m| (m.name, m.requirements))
+                .collect();
+        let mut requirements: HashMap<String, HashMap<String, String>> = HashMap::new();
+
         for pkg in &lockfile.package {
+            let pkg_key = format!("{}@{}", pkg.name, pkg.version);
+            let declared = if pkg.source.is_none() {
+                member_requirements.get(&pkg.name).cloned()
+            } else {
+                registry_source_dir(&pkg.name, &pkg.version)
+                    .and_then(|dir| fs::read_to_string(dir.join("Cargo.toml")).ok())
+                    .and_then(|content| toml::from_str::<TomlValue>(&content).ok())
+                    .map(|manifest| {
+                        declared_dependencies(&manifest, None, &Target::host()).requirements
+                    })
+            };
+
             if let Some(deps) = &pkg.dependencies {
                 for dep in deps {
-                    let parts: Vec<&str> = dep.split_whitespace().collect();
-                    let dep_key = if parts.len() >= 2 {
-                        format!("{}@{}", parts[0], parts[1])
-                    } else {
-                        parts[0].to_string()
-                    };
-
-                    let pkg_key = format!("{}@{}", pkg.name, pkg.version);
+                    let dep_key = dependency_key(dep, &versions_by_name);
+                    let dep_name = dep.split_whitespace().next().unwrap_or(dep);
+                    if let Some(req) = declared.as_ref().and_then(|d| d.get(dep_name)) {
+                        requirements
+                            .entry(dep_key.clone())
+                            .or_default()
+                            .insert(pkg_key.clone(), req.clone());
+                    }
 
-                    dependents.entry(dep_key).or_default().push(pkg_key);
+                    dependents.entry(dep_key).or_default().push(pkg_key.clone());
                 }
             }
         }
@@ -132,17 +323,529 @@ impl<'a> CargoLockfileParser<'a> {
                     version: pkg.version.clone(),
                     dependents: pkg_dependents,
                     is_path_dep: pkg.source.is_none(),
+                    requirements: requirements.remove(&key).unwrap_or_default(),
                 });
         }
 
         Ok(by_name)
     }
+
+    /// The `.crate` checksum of each registry crate, keyed by "name@version"
+    pub fn checksums(&self) -> Result<HashMap<String, String>> {
+        let content = fs::read_to_string(self.lockfile_path)
+            .map_err(|e| miette::miette!("Failed to read Cargo.lock: {}", e))?;
+
+        let lockfile: CargoLockfile = toml::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse Cargo.lock: {}", e))?;
+
+        Ok(lockfile
+            .package
+            .into_iter()
+            .filter_map(|pkg| Some((format!("{}@{}", pkg.name, pkg.version), pkg.checksum?)))
+            .collect())
+    }
+
+    /// Workspace members and path dependencies, which have no `source`, as
+    /// (name, version)
+    pub fn local_packages(&self) -> Result<HashSet<(String, String)>> {
+        let content = fs::read_to_string(self.lockfile_path)
+            .map_err(|e| miette::miette!("Failed to read Cargo.lock: {}", e))?;
+
+        let lockfile: CargoLockfile = toml::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse Cargo.lock: {}", e))?;
+
+        Ok(lockfile
+            .package
+            .into_iter()
+            .filter(|pkg| pkg.source.is_none())
+            .map(|pkg| (pkg.name, pkg.version))
+            .collect())
+    }
+
+    /// Names of `[patch]` entries that Cargo reports as unused
+    pub fn unused_patches(&self) -> Result<Vec<String>> {
+        let content = fs::read_to_string(self.lockfile_path)
+            .map_err(|e| miette::miette!("Failed to read Cargo.lock: {}", e))?;
+
+        let lockfile: CargoLockfile = toml::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse Cargo.lock: {}", e))?;
+
+        Ok(lockfile
+            .patch
+            .unused
+            .into_iter()
+            .map(|pkg| pkg.name)
+            .collect())
+    }
+}
+
+/// How a manifest declares a dependency, from weakest to strongest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum DependencyKind {
+    Dev,
+    Build,
+    Normal,
+}
+
+/// A workspace member's name and how it declares each of its dependencies
+struct MemberManifest {
+    name: String,
+    dependencies: HashMap<String, DependencyKind>,
+
+    /// Version requirement per dependency, as an npm-style range
+    requirements: HashMap<String, String>,
+
+    /// Dependencies only declared for other targets
+    other_targets: HashMap<String, String>,
+}
+
+/// Dependencies a Cargo.toml declares, by crate name
+#[derive(Debug, Default)]
+struct DeclaredDependencies {
+    /// The strongest kind each crate is declared as
+    kinds: HashMap<String, DependencyKind>,
+
+    /// Version requirement per crate, as an npm-style range
+    requirements: HashMap<String, String>,
+
+    /// Crates only declared in `[target.<cfg>]` tables that don't apply to
+    /// the target, with the first such `<cfg>`
+    other_targets: HashMap<String, String>,
+}
+
+/// Read the Cargo.toml of every workspace member under `root`, for `target`
+fn member_manifests(root: &Path, target: &Target) -> Result<Vec<MemberManifest>> {
+    let read = |path: &Path| -> Result<TomlValue> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| miette::miette!("Failed to read {}: {}", path.display(), e))?;
+        toml::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse {}: {}", path.display(), e))
+    };
+
+    let dirs = workspace::cargo_member_dirs(root)?;
+    if dirs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let root_manifest = read(&root.join("Cargo.toml"))?;
+    let inherited = root_manifest
+        .get("workspace")
+        .and_then(|w| w.get("dependencies"));
+
+    let mut manifests = Vec::new();
+    for dir in dirs {
+        let manifest = read(&dir.join("Cargo.toml"))?;
+        let Some(name) = manifest
+            .get("package")
+            .and_then(|p| p.get("name"))
+            .and_then(|n| n.as_str())
+        else {
+            continue;
+        };
+        let declared = declared_dependencies(&manifest, inherited, target);
+        manifests.push(MemberManifest {
+            name: name.to_string(),
+            dependencies: declared.kinds,
+            requirements: declared.requirements,
+            other_targets: declared.other_targets,
+        });
+    }
+    Ok(manifests)
+}
+
+/// Crates a Cargo.toml depends on, with how and at which versions
+///
+/// Renamed dependencies (`foo = { package = "bar" }`) are recorded under
+/// the crate's real name, including ones inherited with `workspace = true`
+/// from the `inherited` `[workspace.dependencies]` table. `[target.<cfg>]`
+/// tables count whether or not they apply to `target`.
+fn declared_dependencies(
+    manifest: &TomlValue,
+    inherited: Option<&TomlValue>,
+    target: &Target,
+) -> DeclaredDependencies {
+    let kinds = [
+        ("dependencies", DependencyKind::Normal),
+        ("build-dependencies", DependencyKind::Build),
+        ("dev-dependencies", DependencyKind::Dev),
+    ];
+
+    let mut declared = DeclaredDependencies::default();
+    let mut this_target: HashSet<String> = HashSet::new();
+    for (table, kind) in kinds {
+        // Each table with the `<cfg>` it's for, `None` for every target
+        let mut tables: Vec<(&TomlValue, Option<&String>)> =
+            manifest.get(table).map(|t| (t, None)).into_iter().collect();
+        if let Some(targets) = manifest.get("target").and_then(|t| t.as_table()) {
+            tables.extend(
+                targets
+                    .iter()
+                    .filter_map(|(cfg, deps)| Some((deps.get(table)?, Some(cfg)))),
+            );
+        }
+
+        for (alias, spec, cfg) in tables
+            .into_iter()
+            .filter_map(|(table, cfg)| Some((table.as_table()?, cfg)))
+            .flat_map(|(table, cfg)| table.iter().map(move |(alias, spec)| (alias, spec, cfg)))
+        {
+            let spec = if spec.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+                inherited.and_then(|deps| deps.get(alias)).unwrap_or(spec)
+            } else {
+                spec
+            };
+            let name = spec
+                .get("package")
+                .and_then(|p| p.as_str())
+                .unwrap_or(alias)
+                .to_string();
+
+            let requirement = spec
+                .as_str()
+                .or_else(|| spec.get("version").and_then(|v| v.as_str()));
+            if let Some(requirement) = requirement {
+                declared
+                    .requirements
+                    .entry(name.clone())
+                    .or_insert_with(|| requirement_as_range(requirement));
+            }
+
+            match cfg {
+                Some(cfg) if !target.matches(cfg) => {
+                    declared
+                        .other_targets
+                        .entry(name.clone())
+                        .or_insert_with(|| cfg.clone());
+                }
+                _ => {
+                    this_target.insert(name.clone());
+                }
+            }
+
+            let entry = declared.kinds.entry(name).or_insert(kind);
+            *entry = (*entry).max(kind);
+        }
+    }
+    declared
+        .other_targets
+        .retain(|name, _| !this_target.contains(name));
+    declared
+}
+
+/// Write a Cargo version requirement as an npm-style range
+///
+/// Cargo separates comparators with commas and reads a bare version as a
+/// caret requirement; npm uses spaces and reads it as exact.
+/// "1.2" -> "^1.2", ">=1.0, <2" -> ">=1.0 <2"
+fn requirement_as_range(requirement: &str) -> String {
+    requirement
+        .split(',')
+        .map(|comparator| {
+            let comparator = comparator.trim();
+            if comparator.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("^{}", comparator)
+            } else {
+                comparator.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Unpacked sources of a registry crate, when Cargo has downloaded it
+///
+/// Cargo keeps them under `$CARGO_HOME/registry/src/<index>/<name>-<version>`.
+pub fn registry_source_dir(name: &str, version: &str) -> Option<PathBuf> {
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".cargo")))?;
+    fs::read_dir(cargo_home.join("registry/src"))
+        .ok()?
+        .filter_map(|index| index.ok())
+        .map(|index| index.path().join(format!("{}-{}", name, version)))
+        .find(|dir| dir.is_dir())
+}
+
+/// Map each crate name to the versions present in the lockfile
+fn versions_by_name(lockfile: &CargoLockfile) -> HashMap<&str, Vec<&str>> {
+    let mut versions: HashMap<&str, Vec<&str>> = HashMap::new();
+    for pkg in &lockfile.package {
+        versions.entry(&pkg.name).or_default().push(&pkg.version);
+    }
+    versions
+}
+
+/// Turn a lockfile dependency entry into a "name@version" key
+///
+/// Entries are "name version [(source)]", or just "name" when only one
+/// version of the crate is in the lockfile.
+fn dependency_key(dep: &str, versions_by_name: &HashMap<&str, Vec<&str>>) -> String {
+    let parts: Vec<&str> = dep.split_whitespace().collect();
+    if parts.len() >= 2 {
+        return format!("{}@{}", parts[0], parts[1]);
+    }
+
+    match versions_by_name.get(parts[0]).map(|v| v.as_slice()) {
+        Some([version]) => format!("{}@{}", parts[0], version),
+        _ => parts[0].to_string(),
+    }
 }
 
 /// Package info for duplicate analysis
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct CargoPackageInfo {
     pub version: String,
     pub dependents: Vec<String>,
     pub is_path_dep: bool,
+
+    /// Requirement each dependent declares on this package, by the
+    /// dependent's "name@version", written as an npm-style range
+    pub requirements: HashMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_workspace_direct_dependencies() {
+        let root = TempDir::new("cargo-workspace");
+        std::fs::create_dir_all(root.join("crates/core")).unwrap();
+        std::fs::create_dir_all(root.join("vendor/shim")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            r#"[workspace]
+members = ["crates/*"]
+
+[workspace.dependencies]
+json = { package = "serde_json", version = "1" }
+
+[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+core = { path = "crates/core" }
+shim = { path = "vendor/shim" }
+json = { workspace = true }
+
+[build-dependencies]
+cc = "1"
+
+[dev-dependencies]
+insta = "1"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("crates/core/Cargo.toml"),
+            "[package]\nname = \"core\"\n\n[dev-dependencies]\nserde_json = \"1\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("Cargo.lock"),
+            r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["core", "shim", "serde_json", "insta", "cc"]
+
+[[package]]
+name = "core"
+version = "0.1.0"
+dependencies = ["serde_json"]
+
+[[package]]
+name = "shim"
+version = "0.1.0"
+dependencies = ["libc"]
+
+[[package]]
+name = "serde_json"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "insta"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["similar"]
+
+[[package]]
+name = "similar"
+version = "2.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "libc"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "cc"
+version = "1.0.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+dependencies = ["shlex"]
+
+[[package]]
+name = "shlex"
+version = "1.3.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+        )
+        .unwrap();
+
+        let lockfile_path = root.join("Cargo.lock");
+        let packages = CargoLockfileParser::new(&lockfile_path).parse().unwrap();
+        let core = CargoLockfileParser::new(&lockfile_path)
+            .with_member("core".to_string())
+            .parse()
+            .unwrap();
+
+        // core only needs serde_json for its tests, and nothing of app's
+        let json = &core["serde_json@1.0.0"];
+        assert!(json.is_direct && json.is_dev);
+        assert!(!core["insta@1.0.0"].is_direct && !core["cc@1.0.0"].is_direct);
+
+        // Workspace crates and path dependencies are local code
+        assert!(!packages["app@0.1.0"].is_direct);
+        assert!(!packages["shim@0.1.0"].is_direct);
+        // Only a dev dependency in core, but app needs it (renamed) at runtime
+        let json = &packages["serde_json@1.0.0"];
+        assert!(json.is_direct && !json.is_dev);
+        assert!(packages["insta@1.0.0"].is_direct && packages["insta@1.0.0"].is_dev);
+        assert!(!packages["similar@2.0.0"].is_direct && packages["similar@2.0.0"].is_dev);
+        // Pulled in by a path dependency, not declared by a member
+        let libc = &packages["libc@0.2.0"];
+        assert!(!libc.is_direct && !libc.is_dev && !libc.is_build);
+        // Build-only crates and what they pull in
+        let cc = &packages["cc@1.0.0"];
+        assert!(cc.is_direct && cc.is_build && !cc.is_dev);
+        assert!(packages["shlex@1.3.0"].is_build);
+        assert!(!json.is_build && !packages["insta@1.0.0"].is_build);
+    }
+
+    #[test]
+    fn test_other_target_dependencies() {
+        let root = TempDir::new("cargo-target");
+        std::fs::create_dir_all(root.join("crates/cli")).unwrap();
+        std::fs::write(
+            root.join("Cargo.toml"),
+            r#"[workspace]
+members = ["crates/*"]
+
+[package]
+name = "app"
+version = "0.1.0"
+
+[target.'cfg(windows)'.dependencies]
+windows-sys = "0.59"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("crates/cli/Cargo.toml"),
+            "[package]\nname = \"cli\"\n\n[target.'cfg(target_os = \"macos\")'.dependencies]\nlibc = \"0.2\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("Cargo.lock"),
+            r#"version = 3
+
+[[package]]
+name = "app"
+version = "0.1.0"
+dependencies = ["libc", "windows-sys"]
+
+[[package]]
+name = "cli"
+version = "0.1.0"
+dependencies = ["libc"]
+
+[[package]]
+name = "libc"
+version = "0.2.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+
+[[package]]
+name = "windows-sys"
+version = "0.59.0"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+"#,
+        )
+        .unwrap();
+
+        let lockfile_path = root.join("Cargo.lock");
+        let parse = |triple: &str| {
+            CargoLockfileParser::new(&lockfile_path)
+                .with_target(Target::from_triple(triple).unwrap())
+                .parse()
+                .unwrap()
+        };
+        let linux = parse("x86_64-unknown-linux-gnu");
+        let windows = parse("x86_64-pc-windows-msvc");
+
+        assert_eq!(
+            linux["windows-sys@0.59.0"].other_target.as_deref(),
+            Some("cfg(windows)")
+        );
+        // app needs it on Linux even though cli only wants it on macOS
+        assert_eq!(linux["libc@0.2.0"].other_target, None);
+        assert_eq!(windows["windows-sys@0.59.0"].other_target, None);
+        assert_eq!(
+            windows["libc@0.2.0"].other_target.as_deref(),
+            Some("cfg(unix)")
+        );
+    }
+
+    #[test]
+    fn test_declared_kinds() {
+        let manifest: TomlValue = toml::from_str(
+            r#"
+[dependencies]
+cfg-if = "1"
+
+[build-dependencies]
+cc = "1"
+cfg-if = "1"
+
+[target.'cfg(windows)'.build-dependencies]
+winres = "0.1"
+
+[dev-dependencies]
+rng = { package = "rand", version = "0.8" }
+"#,
+        )
+        .unwrap();
+
+        let linux = Target::from_triple("x86_64-unknown-linux-gnu").unwrap();
+        let declared = declared_dependencies(&manifest, None, &linux);
+        let kinds = &declared.kinds;
+        assert_eq!(kinds["cfg-if"], DependencyKind::Normal);
+        assert_eq!(kinds["cc"], DependencyKind::Build);
+        assert_eq!(kinds["winres"], DependencyKind::Build);
+        assert_eq!(kinds["rand"], DependencyKind::Dev);
+        assert_eq!(declared.requirements["rand"], "^0.8");
+        assert_eq!(
+            declared.other_targets,
+            HashMap::from([("winres".to_string(), "cfg(windows)".to_string())])
+        );
+
+        let windows = Target::from_triple("x86_64-pc-windows-msvc").unwrap();
+        assert!(declared_dependencies(&manifest, None, &windows)
+            .other_targets
+            .is_empty());
+    }
+
+    #[test]
+    fn test_requirement_as_range() {
+        assert_eq!(requirement_as_range("1.2"), "^1.2");
+        assert_eq!(requirement_as_range("~0.4"), "~0.4");
+        assert_eq!(requirement_as_range(">=1.0, <2"), ">=1.0 <2");
+        assert_eq!(requirement_as_range("=0.2.100"), "=0.2.100");
+    }
 }
@@ -0,0 +1,626 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::deno::{remote_package, specifier_package, split_spec, DenoImports};
+use crate::lockfile::CargoPackageInfo;
+use crate::types::{Package, PackageSource};
+
+/// Parser for Deno's deno.lock, versions 3 to 5
+///
+/// npm packages keep their npm names and JSR packages their scoped names,
+/// with the `jsr` source. Remote modules are grouped into one package per
+/// module (see `remote_package`); the lockfile doesn't record who imports
+/// them, so only those the import map points to are direct.
+pub struct DenoLockfileParser<'a> {
+    root: &'a Path,
+    lockfile_path: &'a Path,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DenoLockfile {
+    version: String,
+    /// Version 3 nests the resolved packages under `packages`
+    packages: Option<Resolved>,
+    #[serde(flatten)]
+    resolved: Resolved,
+    #[serde(default)]
+    remote: BTreeMap<String, String>,
+    #[serde(default)]
+    workspace: DenoWorkspace,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Resolved {
+    /// Requested specifier -> resolved version ("npm:chalk@5" -> "5.3.0")
+    #[serde(default)]
+    specifiers: BTreeMap<String, String>,
+    /// "name@version" -> entry
+    #[serde(default)]
+    jsr: BTreeMap<String, DenoEntry>,
+    /// "name@version", with a "_peer@version" suffix for peer variants
+    #[serde(default)]
+    npm: BTreeMap<String, DenoEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DenoEntry {
+    #[serde(default)]
+    dependencies: DenoDependencies,
+    #[serde(default)]
+    optional_dependencies: DenoDependencies,
+    #[serde(default)]
+    os: Vec<String>,
+    #[serde(default)]
+    cpu: Vec<String>,
+}
+
+/// A list of references from version 4 on; version 3 maps names to "name@version"
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum DenoDependencies {
+    List(Vec<String>),
+    Map(BTreeMap<String, String>),
+}
+
+impl Default for DenoDependencies {
+    fn default() -> Self {
+        DenoDependencies::List(Vec::new())
+    }
+}
+
+impl DenoDependencies {
+    fn references(&self) -> Vec<&str> {
+        match self {
+            DenoDependencies::List(references) => references.iter().map(|r| r.as_str()).collect(),
+            DenoDependencies::Map(references) => references.values().map(|r| r.as_str()).collect(),
+        }
+    }
+}
+
+/// Specifiers requested by the workspace and its members
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DenoWorkspace {
+    #[serde(default)]
+    dependencies: Vec<String>,
+    #[serde(default)]
+    package_json: DenoPackageJson,
+    #[serde(default)]
+    members: BTreeMap<String, DenoWorkspace>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DenoPackageJson {
+    #[serde(default)]
+    dependencies: Vec<String>,
+}
+
+/// One resolved npm or JSR package version, keyed by "npm:<key>" or "jsr:<key>"
+#[derive(Debug)]
+struct Node {
+    name: String,
+    version: String,
+    source: PackageSource,
+    /// Node IDs with the requested range when the lockfile has it
+    dependencies: Vec<(String, Option<String>)>,
+    optional_dependencies: Vec<String>,
+    os: Vec<String>,
+    cpu: Vec<String>,
+}
+
+impl<'a> DenoLockfileParser<'a> {
+    pub fn new(root: &'a Path, lockfile_path: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile_path,
+        }
+    }
+
+    /// Parse every package the lockfile resolves
+    ///
+    /// Specifiers in the workspace section are direct dependencies; those
+    /// coming from a package.json `devDependencies` are dev dependencies.
+    /// When a package resolves to several versions, the one a direct
+    /// specifier asks for is reported, else one runtime code reaches, else
+    /// the highest.
+    pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let lockfile = self.load()?;
+        let resolved = lockfile.packages.as_ref().unwrap_or(&lockfile.resolved);
+        let nodes = build_nodes(resolved);
+
+        let dev_names = self.dev_dependency_names(&lockfile.workspace);
+        let mut direct_prod: HashSet<String> = HashSet::new();
+        let mut direct_dev: HashSet<String> = HashSet::new();
+        for specifier in workspace_specifiers(&lockfile.workspace) {
+            let Some(id) = resolve_specifier(specifier, &resolved.specifiers) else {
+                continue;
+            };
+            let is_dev = specifier
+                .strip_prefix("npm:")
+                .and_then(split_spec)
+                .is_some_and(|(name, _)| dev_names.contains(&name));
+            if is_dev {
+                direct_dev.insert(id);
+            } else {
+                direct_prod.insert(id);
+            }
+        }
+
+        // Packages runtime code can reach
+        let mut prod: HashSet<&str> = HashSet::new();
+        let mut queue: VecDeque<&str> = direct_prod.iter().map(|id| id.as_str()).collect();
+        while let Some(id) = queue.pop_front() {
+            if !prod.insert(id) {
+                continue;
+            }
+            if let Some(node) = nodes.get(id) {
+                queue.extend(node.dependencies.iter().map(|(dep, _)| dep.as_str()));
+                queue.extend(node.optional_dependencies.iter().map(|dep| dep.as_str()));
+            }
+        }
+
+        let mut required: HashSet<&str> = HashSet::new();
+        let mut optional_refs: HashSet<&str> = HashSet::new();
+        for node in nodes.values() {
+            required.extend(node.dependencies.iter().map(|(dep, _)| dep.as_str()));
+            optional_refs.extend(node.optional_dependencies.iter().map(|dep| dep.as_str()));
+        }
+
+        // One version per name: a direct one, else one runtime code reaches, else the highest
+        let rank = |id: &str| {
+            (
+                direct_prod.contains(id) || direct_dev.contains(id),
+                prod.contains(id),
+            )
+        };
+        let mut chosen: BTreeMap<&str, &str> = BTreeMap::new();
+        for (id, node) in &nodes {
+            let better = chosen.get(node.name.as_str()).is_none_or(|current| {
+                rank(id)
+                    .cmp(&rank(current))
+                    .then_with(|| compare_versions(&node.version, &nodes[*current].version))
+                    .is_gt()
+            });
+            if better {
+                chosen.insert(&node.name, id);
+            }
+        }
+
+        let mut packages = HashMap::new();
+        for (name, id) in chosen {
+            let node = &nodes[id];
+            let is_direct = direct_prod.contains(id) || direct_dev.contains(id);
+            let is_dev = if is_direct {
+                !direct_prod.contains(id)
+            } else {
+                !prod.contains(id)
+            };
+
+            let mut package = Package::new(name, node.version.clone());
+            package.is_direct = is_direct;
+            package.is_dev = is_dev;
+            package.source = node.source;
+            for (dep, range) in &node.dependencies {
+                let Some(child) = nodes.get(dep) else {
+                    continue;
+                };
+                if !package.dependencies.contains(&child.name) {
+                    package.dependencies.push(child.name.clone());
+                }
+                if let Some(range) = range {
+                    package
+                        .dependency_ranges
+                        .insert(child.name.clone(), range.clone());
+                }
+            }
+            for dep in &node.optional_dependencies {
+                if let Some(child) = nodes.get(dep) {
+                    if !package.dependencies.contains(&child.name) {
+                        package.dependencies.push(child.name.clone());
+                    }
+                }
+            }
+            package.is_optional = optional_refs.contains(id) && !required.contains(id);
+            package.os = node.os.clone();
+            package.cpu = node.cpu.clone();
+            packages.insert(name.to_string(), package);
+        }
+
+        // Remote modules the import map points to count as declared
+        let imports = DenoImports::load(self.root)?;
+        let mapped: HashSet<String> = imports
+            .targets()
+            .filter(|target| target.starts_with("http"))
+            .filter_map(specifier_package)
+            .collect();
+        for url in lockfile.remote.keys() {
+            let Some((name, version)) = remote_package(url) else {
+                continue;
+            };
+            if packages.contains_key(&name) {
+                continue;
+            }
+            let mut package = Package::new(name.clone(), version);
+            package.is_direct = mapped.contains(&name);
+            package.source = PackageSource::Remote;
+            packages.insert(name, package);
+        }
+
+        Ok(packages)
+    }
+
+    /// Distinct versions of every npm and JSR package with the packages depending on them
+    pub fn parse_for_duplicates(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        let lockfile = self.load()?;
+        let resolved = lockfile.packages.as_ref().unwrap_or(&lockfile.resolved);
+        let nodes = build_nodes(resolved);
+
+        let mut by_name: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+        for node in nodes.values() {
+            let versions = by_name.entry(node.name.clone()).or_default();
+            if !versions.iter().any(|v| v.version == node.version) {
+                versions.push(CargoPackageInfo {
+                    version: node.version.clone(),
+                    ..CargoPackageInfo::default()
+                });
+            }
+        }
+
+        // Only JSR dependencies carry the range they asked for
+        for node in nodes.values() {
+            let dependent = format!("{}@{}", node.name, node.version);
+            let children = node
+                .dependencies
+                .iter()
+                .map(|(dep, range)| (dep, range.as_ref()))
+                .chain(node.optional_dependencies.iter().map(|dep| (dep, None)));
+            for (dep, range) in children {
+                let Some(child) = nodes.get(dep) else {
+                    continue;
+                };
+                let Some(version) = by_name
+                    .get_mut(&child.name)
+                    .and_then(|versions| versions.iter_mut().find(|v| v.version == child.version))
+                else {
+                    continue;
+                };
+                if !version.dependents.contains(&dependent) {
+                    version.dependents.push(dependent.clone());
+                }
+                if let Some(range) = range {
+                    version
+                        .requirements
+                        .insert(dependent.clone(), range.clone());
+                }
+            }
+        }
+
+        for versions in by_name.values_mut() {
+            for version in versions.iter_mut() {
+                version.dependents.sort();
+            }
+        }
+
+        Ok(by_name)
+    }
+
+    fn load(&self) -> Result<DenoLockfile> {
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+        let lockfile: DenoLockfile = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse deno.lock")?;
+
+        if !matches!(lockfile.version.as_str(), "3" | "4" | "5") {
+            bail!(
+                "deno.lock version {} isn't supported. Run `deno install` with Deno 1.40 or later to update it",
+                lockfile.version
+            );
+        }
+        Ok(lockfile)
+    }
+
+    /// Names listed under `devDependencies` in the workspace's package.json files
+    fn dev_dependency_names(&self, workspace: &DenoWorkspace) -> HashSet<String> {
+        let dirs = std::iter::once(self.root.to_path_buf()).chain(
+            workspace
+                .members
+                .keys()
+                .map(|member| self.root.join(member)),
+        );
+
+        let mut names = HashSet::new();
+        for dir in dirs {
+            let manifest = std::fs::read_to_string(dir.join("package.json"))
+                .ok()
+                .and_then(|content| serde_json::from_str::<JsonValue>(&content).ok());
+            if let Some(dev) = manifest
+                .as_ref()
+                .and_then(|manifest| manifest.get("devDependencies")?.as_object())
+            {
+                names.extend(dev.keys().cloned());
+            }
+        }
+        names
+    }
+}
+
+fn workspace_specifiers(workspace: &DenoWorkspace) -> Vec<&str> {
+    let mut specifiers: Vec<&str> = workspace
+        .dependencies
+        .iter()
+        .chain(workspace.package_json.dependencies.iter())
+        .map(|s| s.as_str())
+        .collect();
+    for member in workspace.members.values() {
+        specifiers.extend(workspace_specifiers(member));
+    }
+    specifiers
+}
+
+/// Node ID a requested `npm:` or `jsr:` specifier resolved to
+///
+/// Version 3 maps "npm:chalk@5" to "npm:chalk@5.3.0"; later versions map
+/// it to "5.3.0".
+fn resolve_specifier(specifier: &str, specifiers: &BTreeMap<String, String>) -> Option<String> {
+    let resolved = specifiers.get(specifier)?;
+    let kind = ["npm:", "jsr:"]
+        .into_iter()
+        .find(|kind| specifier.starts_with(kind))?;
+    if let Some(key) = resolved.strip_prefix(kind) {
+        return Some(format!("{}{}", kind, key));
+    }
+    let (name, _) = split_spec(&specifier[kind.len()..])?;
+    Some(format!("{}{}@{}", kind, name, resolved))
+}
+
+/// Name and version in an npm or JSR key, without any peer suffix
+///
+/// "@types/node@20.11.0" -> ("@types/node", "20.11.0")
+/// "react-dom@18.2.0_react@18.2.0" -> ("react-dom", "18.2.0")
+fn split_key(key: &str) -> Option<(&str, &str)> {
+    let at = key.get(1..)?.find('@')? + 1;
+    let version = &key[at + 1..];
+    Some((&key[..at], version.split('_').next().unwrap_or(version)))
+}
+
+fn build_nodes(resolved: &Resolved) -> BTreeMap<String, Node> {
+    let mut npm_by_name: HashMap<&str, &str> = HashMap::new();
+    for key in resolved.npm.keys() {
+        if let Some((name, _)) = split_key(key) {
+            npm_by_name.entry(name).or_insert(key);
+        }
+    }
+
+    // npm entries list "name" when one version is installed, else "name@version"
+    let npm_reference = |reference: &str| -> Option<String> {
+        if resolved.npm.contains_key(reference) {
+            return Some(format!("npm:{}", reference));
+        }
+        npm_by_name.get(reference).map(|key| format!("npm:{}", key))
+    };
+
+    let mut nodes = BTreeMap::new();
+    for (key, entry) in &resolved.npm {
+        let Some((name, version)) = split_key(key) else {
+            continue;
+        };
+        nodes.insert(
+            format!("npm:{}", key),
+            Node {
+                name: name.to_string(),
+                version: version.to_string(),
+                source: PackageSource::Registry,
+                dependencies: entry
+                    .dependencies
+                    .references()
+                    .into_iter()
+                    .filter_map(|reference| Some((npm_reference(reference)?, None)))
+                    .collect(),
+                optional_dependencies: entry
+                    .optional_dependencies
+                    .references()
+                    .into_iter()
+                    .filter_map(npm_reference)
+                    .collect(),
+                os: entry.os.clone(),
+                cpu: entry.cpu.clone(),
+            },
+        );
+    }
+
+    // JSR entries list the specifiers they request
+    for (key, entry) in &resolved.jsr {
+        let Some((name, version)) = split_key(key) else {
+            continue;
+        };
+        let dependencies = entry
+            .dependencies
+            .references()
+            .into_iter()
+            .filter_map(|specifier| {
+                let id = resolve_specifier(specifier, &resolved.specifiers)?;
+                let range = specifier
+                    .strip_prefix("jsr:")
+                    .or_else(|| specifier.strip_prefix("npm:"))
+                    .and_then(split_spec)
+                    .map(|(_, range)| range)
+                    .filter(|range| !range.is_empty());
+                Some((id, range))
+            })
+            .collect();
+        nodes.insert(
+            format!("jsr:{}", key),
+            Node {
+                name: name.to_string(),
+                version: version.to_string(),
+                source: PackageSource::Jsr,
+                dependencies,
+                optional_dependencies: Vec::new(),
+                os: Vec::new(),
+                cpu: Vec::new(),
+            },
+        );
+    }
+
+    nodes
+}
+
+fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    match (semver::Version::parse(a), semver::Version::parse(b)) {
+        (Ok(a), Ok(b)) => a.cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const LOCKFILE_V4: &str = r#"{
+  "version": "4",
+  "specifiers": {
+    "jsr:@std/assert@1": "1.0.8",
+    "jsr:@std/internal@^1.0.5": "1.0.5",
+    "npm:chalk@5": "5.3.0",
+    "npm:express@^4.18": "4.21.1",
+    "npm:typescript@^5.3": "5.3.3"
+  },
+  "jsr": {
+    "@std/assert@1.0.8": {
+      "integrity": "sha256-a",
+      "dependencies": ["jsr:@std/internal@^1.0.5"]
+    },
+    "@std/internal@1.0.5": { "integrity": "sha256-b" }
+  },
+  "npm": {
+    "chalk@5.3.0": { "integrity": "sha512-c" },
+    "debug@2.6.9": { "integrity": "sha512-d", "dependencies": ["ms@2.0.0"] },
+    "debug@4.3.4": { "integrity": "sha512-e", "dependencies": ["ms@2.1.2"] },
+    "express@4.21.1": { "integrity": "sha512-f", "dependencies": ["debug@2.6.9"] },
+    "ms@2.0.0": { "integrity": "sha512-g" },
+    "ms@2.1.2": { "integrity": "sha512-h" },
+    "typescript@5.3.3": { "integrity": "sha512-i" }
+  },
+  "remote": {
+    "https://deno.land/x/oak@v12.6.1/mod.ts": "hash-a",
+    "https://deno.land/x/oak@v12.6.1/router.ts": "hash-b",
+    "https://deno.land/std@0.200.0/path/mod.ts": "hash-c"
+  },
+  "workspace": {
+    "dependencies": ["jsr:@std/assert@1", "npm:chalk@5"],
+    "packageJson": {
+      "dependencies": ["npm:express@^4.18", "npm:typescript@^5.3"]
+    }
+  }
+}"#;
+
+    fn write_fixture(name: &str, lockfile: &str) -> TempDir {
+        let root = TempDir::new(&format!("deno-{}", name));
+        std::fs::write(
+            root.join("deno.json"),
+            r#"{"imports": {"oak/": "https://deno.land/x/oak@v12.6.1/"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"dependencies": {"express": "^4.18"}, "devDependencies": {"typescript": "^5.3"}}"#,
+        )
+        .unwrap();
+        std::fs::write(root.join("deno.lock"), lockfile).unwrap();
+        root
+    }
+
+    #[test]
+    fn test_parse() {
+        let root = write_fixture("parse", LOCKFILE_V4);
+        let lockfile = root.join("deno.lock");
+        let packages = DenoLockfileParser::new(&root, &lockfile).parse().unwrap();
+
+        let assert = &packages["@std/assert"];
+        assert!(assert.is_direct && !assert.is_dev);
+        assert_eq!(assert.source, PackageSource::Jsr);
+        assert_eq!(assert.dependencies, vec!["@std/internal"]);
+        assert_eq!(assert.dependency_ranges["@std/internal"], "^1.0.5");
+        assert!(!packages["@std/internal"].is_direct);
+
+        assert!(packages["chalk"].is_direct);
+        assert!(packages["typescript"].is_direct && packages["typescript"].is_dev);
+
+        // express reaches debug 2, which wins over the higher, unreachable 4.3.4
+        let express = &packages["express"];
+        assert_eq!(express.dependencies, vec!["debug"]);
+        assert_eq!(packages["debug"].version, "2.6.9");
+        assert!(!packages["debug"].is_dev);
+
+        let oak = &packages["deno.land/x/oak"];
+        assert_eq!(oak.version, "v12.6.1");
+        assert_eq!(oak.source, PackageSource::Remote);
+        assert!(oak.is_direct);
+        assert!(!packages["deno.land/std"].is_direct);
+    }
+
+    #[test]
+    fn test_parse_for_duplicates() {
+        let root = write_fixture("dupes", LOCKFILE_V4);
+        let lockfile = root.join("deno.lock");
+        let by_name = DenoLockfileParser::new(&root, &lockfile)
+            .parse_for_duplicates()
+            .unwrap();
+
+        let mut debug: Vec<(&str, &[String])> = by_name["debug"]
+            .iter()
+            .map(|v| (v.version.as_str(), v.dependents.as_slice()))
+            .collect();
+        debug.sort();
+        assert_eq!(
+            debug,
+            vec![
+                ("2.6.9", &["express@4.21.1".to_string()][..]),
+                ("4.3.4", &[][..]),
+            ]
+        );
+        assert_eq!(
+            by_name["@std/internal"][0].requirements["@std/assert@1.0.8"],
+            "^1.0.5"
+        );
+    }
+
+    #[test]
+    fn test_parse_v3() {
+        let lockfile = r#"{
+  "version": "3",
+  "packages": {
+    "specifiers": {
+      "npm:react-dom@18": "npm:react-dom@18.2.0_react@18.2.0",
+      "npm:react@18": "npm:react@18.2.0"
+    },
+    "npm": {
+      "react-dom@18.2.0_react@18.2.0": {
+        "integrity": "sha512-a",
+        "dependencies": { "react": "react@18.2.0" }
+      },
+      "react@18.2.0": { "integrity": "sha512-b", "dependencies": {} }
+    }
+  },
+  "remote": {},
+  "workspace": { "dependencies": ["npm:react-dom@18", "npm:react@18"] }
+}"#;
+        let root = write_fixture("v3", lockfile);
+        let path = root.join("deno.lock");
+        let packages = DenoLockfileParser::new(&root, &path).parse().unwrap();
+
+        let react_dom = &packages["react-dom"];
+        assert!(react_dom.is_direct);
+        assert_eq!(react_dom.version, "18.2.0");
+        assert_eq!(react_dom.dependencies, vec!["react"]);
+        assert!(packages["react"].is_direct);
+    }
+}
@@ -0,0 +1,543 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+
+use super::CargoPackageInfo;
+use crate::integrity::home_dir;
+use crate::range::parse_version;
+use crate::types::{Package, PackageSource};
+
+/// Parser for go.mod and go.sum (Go modules)
+///
+/// go.mod pins the selected version of every module the build needs
+/// (since Go 1.17, indirect ones too); for older go.mod files, go.sum adds
+/// the modules they leave out. Neither records which module requires which,
+/// so the edges are read from the dependencies' own go.mod files in the
+/// module cache, when `go mod download` has filled it.
+pub struct GoModParser<'a> {
+    root: &'a Path,
+    lockfile_path: &'a Path,
+    module_cache: Option<PathBuf>,
+}
+
+/// The directives of a go.mod file depx cares about
+#[derive(Debug, Default)]
+struct GoMod {
+    /// The `go` directive's language version, e.g. "1.21"
+    go: Option<String>,
+    requires: Vec<GoRequire>,
+    replaces: Vec<GoReplace>,
+}
+
+#[derive(Debug)]
+struct GoRequire {
+    path: String,
+    version: String,
+    /// Marked `// indirect`: no package of the main module imports it
+    indirect: bool,
+}
+
+/// `replace old [version] => new [version]`
+#[derive(Debug)]
+struct GoReplace {
+    old: String,
+    old_version: Option<String>,
+    new: String,
+    new_version: Option<String>,
+}
+
+impl GoReplace {
+    /// Replacements with a directory instead of a module
+    fn is_local(&self) -> bool {
+        self.new.starts_with("./") || self.new.starts_with("../") || self.new.starts_with('/')
+    }
+}
+
+impl GoMod {
+    /// Whether go.mod lists indirect requirements too, as Go 1.17 and
+    /// later write it; a go.mod without a `go` directive is older
+    fn lists_indirect(&self) -> bool {
+        let Some(go) = &self.go else {
+            return false;
+        };
+        let mut parts = go.split('.').map(|part| {
+            part.chars()
+                .take_while(char::is_ascii_digit)
+                .collect::<String>()
+                .parse::<u64>()
+                .unwrap_or(0)
+        });
+        let (major, minor) = (parts.next().unwrap_or(0), parts.next().unwrap_or(0));
+        (major, minor) >= (1, 17)
+    }
+}
+
+impl<'a> GoModParser<'a> {
+    pub fn new(root: &'a Path, lockfile_path: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile_path,
+            module_cache: module_cache(),
+        }
+    }
+
+    /// Read dependencies' go.mod files from this module cache instead of
+    /// the one `GOMODCACHE`/`GOPATH` point at
+    #[allow(dead_code)]
+    pub fn with_module_cache(mut self, cache: PathBuf) -> Self {
+        self.module_cache = Some(cache);
+        self
+    }
+
+    /// Parse the build list, keyed by module path
+    ///
+    /// Modules required without `// indirect` are direct. Go has no dev
+    /// dependencies: test-only imports are requirements like any other.
+    pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let go_mod = self.load()?;
+
+        let mut packages: HashMap<String, Package> = HashMap::new();
+        for require in &go_mod.requires {
+            let mut package = Package::new(&require.path, &require.version);
+            package.is_direct = !require.indirect;
+            if let Some(replace) = replacement(&go_mod, &require.path, &require.version) {
+                if replace.is_local() {
+                    package.source = PackageSource::Link;
+                } else if let Some(version) = &replace.new_version {
+                    package.version = version.clone();
+                }
+            }
+            packages.insert(require.path.clone(), package);
+        }
+
+        // Modules older go.mod files don't list, at the version go.sum has
+        // their source for. Newer ones list the whole build, while go.sum
+        // also keeps modules only needed to check it.
+        if !go_mod.lists_indirect() {
+            for (path, version) in self.checksummed_modules()? {
+                packages
+                    .entry(path.clone())
+                    .or_insert_with(|| Package::new(&path, &version));
+            }
+        }
+
+        let cache = &self.module_cache;
+        let keys: Vec<String> = packages.keys().cloned().collect();
+        for key in &keys {
+            let package = &packages[key];
+            if package.source != PackageSource::Registry {
+                continue;
+            }
+            let (path, version) = match replacement(&go_mod, key, &package.version) {
+                Some(replace) => (
+                    replace.new.clone(),
+                    replace
+                        .new_version
+                        .clone()
+                        .unwrap_or_else(|| package.version.clone()),
+                ),
+                None => (key.clone(), package.version.clone()),
+            };
+            let Some(content) = cache
+                .as_ref()
+                .and_then(|cache| std::fs::read_to_string(mod_file(cache, &path, &version)).ok())
+            else {
+                continue;
+            };
+
+            let requires = parse_go_mod(&content).requires;
+            let package = packages.get_mut(key).unwrap();
+            for require in requires {
+                if keys.contains(&require.path) && !package.dependencies.contains(&require.path) {
+                    package.dependencies.push(require.path.clone());
+                    package
+                        .dependency_ranges
+                        .insert(require.path, require.version);
+                }
+            }
+            package.dependencies.sort();
+        }
+
+        Ok(packages)
+    }
+
+    /// Modules on several major versions, grouped by path without the
+    /// major suffix (github.com/golang-jwt/jwt and .../jwt/v4)
+    ///
+    /// Minimal version selection builds one version of each module path,
+    /// so a major suffix is the only way two copies end up in a binary.
+    pub fn parse_for_duplicates(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        let packages = self.parse()?;
+        let label = |path: &str, version: &str| format!("{}@{}", module_base(path), version);
+
+        let mut dependents: HashMap<&str, Vec<String>> = HashMap::new();
+        for (path, package) in &packages {
+            for dep in &package.dependencies {
+                dependents
+                    .entry(dep)
+                    .or_default()
+                    .push(label(path, &package.version));
+            }
+        }
+
+        let mut by_name: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+        for (path, package) in &packages {
+            let mut package_dependents = dependents.remove(path.as_str()).unwrap_or_default();
+            package_dependents.sort();
+            by_name
+                .entry(module_base(path).to_string())
+                .or_default()
+                .push(CargoPackageInfo {
+                    version: package.version.clone(),
+                    dependents: package_dependents,
+                    is_path_dep: package.source == PackageSource::Link,
+                    requirements: HashMap::new(),
+                });
+        }
+
+        Ok(by_name)
+    }
+
+    fn load(&self) -> Result<GoMod> {
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+        Ok(parse_go_mod(&content))
+    }
+
+    /// Modules whose source go.sum has a hash for, at the highest version
+    ///
+    /// Lines ending in "/go.mod" only checksum a module's go.mod, read
+    /// while resolving versions; those modules aren't built.
+    fn checksummed_modules(&self) -> Result<HashMap<String, String>> {
+        let path = self.root.join("go.sum");
+        if !path.is_file() {
+            return Ok(HashMap::new());
+        }
+        let content = std::fs::read_to_string(&path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        let mut modules: HashMap<String, String> = HashMap::new();
+        for line in content.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(module), Some(version)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            if version.ends_with("/go.mod") {
+                continue;
+            }
+            let newer = modules.get(module).is_none_or(|current| {
+                match (parse_version(version), parse_version(current)) {
+                    (Some(version), Some(current)) => version > current,
+                    _ => version > current.as_str(),
+                }
+            });
+            if newer {
+                modules.insert(module.to_string(), version.to_string());
+            }
+        }
+        Ok(modules)
+    }
+}
+
+/// The replace directive that applies to a module version, if any
+fn replacement<'g>(go_mod: &'g GoMod, path: &str, version: &str) -> Option<&'g GoReplace> {
+    go_mod.replaces.iter().find(|replace| {
+        replace.old == path
+            && replace
+                .old_version
+                .as_ref()
+                .is_none_or(|old| old == version)
+    })
+}
+
+/// Parse the `require` and `replace` directives, alone or in blocks
+fn parse_go_mod(content: &str) -> GoMod {
+    let mut go_mod = GoMod::default();
+    let mut block: Option<&str> = None;
+
+    for line in content.lines() {
+        let (code, comment) = match line.split_once("//") {
+            Some((code, comment)) => (code, Some(comment.trim())),
+            None => (line, None),
+        };
+        let tokens: Vec<&str> = code
+            .split_whitespace()
+            .map(|t| t.trim_matches('"'))
+            .collect();
+
+        let (directive, args) = match (block, tokens.as_slice()) {
+            (_, []) => continue,
+            (Some(_), [")"]) => {
+                block = None;
+                continue;
+            }
+            (Some(directive), args) => (directive, args),
+            (None, [directive, "("]) => {
+                block = Some(match *directive {
+                    "require" => "require",
+                    "replace" => "replace",
+                    _ => "other",
+                });
+                continue;
+            }
+            (None, [directive, args @ ..]) => (*directive, args),
+        };
+
+        match (directive, args) {
+            ("go", [version]) => go_mod.go = Some(version.to_string()),
+            ("require", [path, version]) => go_mod.requires.push(GoRequire {
+                path: path.to_string(),
+                version: version.to_string(),
+                indirect: comment.is_some_and(|c| c.split(';').any(|c| c.trim() == "indirect")),
+            }),
+            ("replace", args) => {
+                let Some(arrow) = args.iter().position(|t| *t == "=>") else {
+                    continue;
+                };
+                let (old, new) = (&args[..arrow], &args[arrow + 1..]);
+                let (Some(old_path), Some(new_path)) = (old.first(), new.first()) else {
+                    continue;
+                };
+                go_mod.replaces.push(GoReplace {
+                    old: old_path.to_string(),
+                    old_version: old.get(1).map(|v| v.to_string()),
+                    new: new_path.to_string(),
+                    new_version: new.get(1).map(|v| v.to_string()),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    go_mod
+}
+
+/// A module path without its major version suffix
+///
+/// "github.com/golang-jwt/jwt/v4" -> "github.com/golang-jwt/jwt",
+/// "gopkg.in/yaml.v3" -> "gopkg.in/yaml"
+pub fn module_base(path: &str) -> &str {
+    let is_major = |suffix: &str| !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit());
+    if let Some((base, suffix)) = path.rsplit_once("/v") {
+        if is_major(suffix) {
+            return base;
+        }
+    }
+    if path.starts_with("gopkg.in/") {
+        if let Some((base, suffix)) = path.rsplit_once(".v") {
+            if is_major(suffix) {
+                return base;
+            }
+        }
+    }
+    path
+}
+
+/// The module path a version of `module_base` is published under
+///
+/// ("github.com/golang-jwt/jwt", "v4.5.0") -> "github.com/golang-jwt/jwt/v4";
+/// v0, v1 and `+incompatible` versions keep the bare path.
+pub fn module_path(base: &str, version: &str) -> String {
+    let Some(parsed) = parse_version(version) else {
+        return base.to_string();
+    };
+    // gopkg.in paths always carry their major version
+    if base.starts_with("gopkg.in/") {
+        return format!("{}.v{}", base, parsed.major);
+    }
+    if parsed.major >= 2 && !parsed.build.as_str().contains("incompatible") {
+        format!("{}/v{}", base, parsed.major)
+    } else {
+        base.to_string()
+    }
+}
+
+/// The module cache: `$GOMODCACHE`, else `$GOPATH/pkg/mod`, else `~/go/pkg/mod`
+pub fn module_cache() -> Option<PathBuf> {
+    if let Some(cache) = std::env::var_os("GOMODCACHE").filter(|c| !c.is_empty()) {
+        return Some(PathBuf::from(cache));
+    }
+    let gopath = match std::env::var_os("GOPATH").filter(|p| !p.is_empty()) {
+        Some(gopath) => std::env::split_paths(&gopath).next()?,
+        None => home_dir()?.join("go"),
+    };
+    Some(gopath.join("pkg/mod"))
+}
+
+/// Where the module cache unpacks a module version's source
+pub fn module_source_dir(cache: &Path, path: &str, version: &str) -> PathBuf {
+    cache.join(format!("{}@{}", escape_path(path), escape_path(version)))
+}
+
+fn mod_file(cache: &Path, path: &str, version: &str) -> PathBuf {
+    cache
+        .join("cache/download")
+        .join(escape_path(path))
+        .join("@v")
+        .join(format!("{}.mod", escape_path(version)))
+}
+
+/// The module cache's case-insensitive encoding: "BurntSushi" -> "!burnt!sushi"
+pub fn escape_path(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if c.is_ascii_uppercase() {
+            escaped.push('!');
+            escaped.push(c.to_ascii_lowercase());
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const GO_MOD: &str = r#"module example.com/app
+
+go 1.21
+
+require (
+	github.com/gin-gonic/gin v1.9.1
+	github.com/golang-jwt/jwt v3.2.2+incompatible
+	github.com/golang-jwt/jwt/v4 v4.5.0 // indirect
+	example.com/shared v0.0.0 // indirect; used by tests
+)
+
+require golang.org/x/net v0.17.0 // indirect
+
+replace example.com/shared => ../shared
+
+replace (
+	golang.org/x/net v0.17.0 => golang.org/x/net v0.18.0
+)
+"#;
+
+    #[test]
+    fn test_parse_go_mod() {
+        let go_mod = parse_go_mod(GO_MOD);
+        let requires: Vec<(&str, &str, bool)> = go_mod
+            .requires
+            .iter()
+            .map(|r| (r.path.as_str(), r.version.as_str(), r.indirect))
+            .collect();
+        assert_eq!(
+            requires,
+            vec![
+                ("github.com/gin-gonic/gin", "v1.9.1", false),
+                ("github.com/golang-jwt/jwt", "v3.2.2+incompatible", false),
+                ("github.com/golang-jwt/jwt/v4", "v4.5.0", true),
+                ("example.com/shared", "v0.0.0", true),
+                ("golang.org/x/net", "v0.17.0", true),
+            ]
+        );
+        assert_eq!(go_mod.go.as_deref(), Some("1.21"));
+        assert!(go_mod.lists_indirect());
+        assert!(!parse_go_mod("module a\n\ngo 1.16\n").lists_indirect());
+        assert!(!parse_go_mod("module a\n").lists_indirect());
+        assert_eq!(go_mod.replaces.len(), 2);
+        assert!(go_mod.replaces[0].is_local());
+        assert_eq!(go_mod.replaces[1].new_version.as_deref(), Some("v0.18.0"));
+
+        assert_eq!(
+            module_base("github.com/golang-jwt/jwt/v4"),
+            "github.com/golang-jwt/jwt"
+        );
+        assert_eq!(module_base("gopkg.in/yaml.v3"), "gopkg.in/yaml");
+        assert_eq!(
+            module_base("github.com/vmihailenco/msgpack"),
+            "github.com/vmihailenco/msgpack"
+        );
+        assert_eq!(
+            module_path("github.com/golang-jwt/jwt", "v4.5.0"),
+            "github.com/golang-jwt/jwt/v4"
+        );
+        assert_eq!(
+            module_path("github.com/golang-jwt/jwt", "v3.2.2+incompatible"),
+            "github.com/golang-jwt/jwt"
+        );
+        assert_eq!(module_path("gopkg.in/yaml", "v2.4.0"), "gopkg.in/yaml.v2");
+        assert_eq!(
+            escape_path("github.com/BurntSushi/toml"),
+            "github.com/!burnt!sushi/toml"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let root = TempDir::new("go");
+        let cache = root.join("modcache");
+        // Before Go 1.17, go.mod leaves indirect requirements to go.sum
+        std::fs::write(root.join("go.mod"), GO_MOD.replace("go 1.21", "go 1.16")).unwrap();
+        std::fs::write(
+            root.join("go.sum"),
+            concat!(
+                "github.com/gin-gonic/gin v1.9.1 h1:abc=\n",
+                "github.com/gin-gonic/gin v1.9.1/go.mod h1:def=\n",
+                "github.com/mattn/go-isatty v0.0.19 h1:ghi=\n",
+                "github.com/mattn/go-isatty v0.0.20 h1:jkl=\n",
+                "github.com/ugorji/go v1.1.7/go.mod h1:mno=\n",
+            ),
+        )
+        .unwrap();
+        let gin_mod = mod_file(&cache, "github.com/gin-gonic/gin", "v1.9.1");
+        std::fs::create_dir_all(gin_mod.parent().unwrap()).unwrap();
+        std::fs::write(
+            &gin_mod,
+            "module github.com/gin-gonic/gin\n\nrequire (\n\tgithub.com/mattn/go-isatty v0.0.19\n\tgithub.com/golang-jwt/jwt/v4 v4.4.0\n)\n",
+        )
+        .unwrap();
+
+        let lockfile = root.join("go.mod");
+        let parser = GoModParser::new(&root, &lockfile).with_module_cache(cache.clone());
+        let packages = parser.parse().unwrap();
+        let by_name = parser.parse_for_duplicates().unwrap();
+        std::fs::write(root.join("go.mod"), GO_MOD).unwrap();
+        let complete = GoModParser::new(&root, &lockfile)
+            .with_module_cache(cache)
+            .parse()
+            .unwrap();
+
+        assert_eq!(complete.len(), 5);
+        assert!(!complete.contains_key("github.com/mattn/go-isatty"));
+
+        assert_eq!(packages.len(), 6);
+        let gin = &packages["github.com/gin-gonic/gin"];
+        assert!(gin.is_direct);
+        assert_eq!(
+            gin.dependencies,
+            vec!["github.com/golang-jwt/jwt/v4", "github.com/mattn/go-isatty"]
+        );
+        assert_eq!(
+            gin.dependency_ranges["github.com/mattn/go-isatty"],
+            "v0.0.19"
+        );
+        assert_eq!(packages["github.com/mattn/go-isatty"].version, "v0.0.20");
+        assert!(!packages["github.com/mattn/go-isatty"].is_direct);
+        assert!(!packages.contains_key("github.com/ugorji/go"));
+        assert_eq!(packages["example.com/shared"].source, PackageSource::Link);
+        assert_eq!(packages["golang.org/x/net"].version, "v0.18.0");
+
+        let mut jwt: Vec<(&str, &[String])> = by_name["github.com/golang-jwt/jwt"]
+            .iter()
+            .map(|v| (v.version.as_str(), v.dependents.as_slice()))
+            .collect();
+        jwt.sort();
+        assert_eq!(
+            jwt,
+            vec![
+                ("v3.2.2+incompatible", &[][..]),
+                (
+                    "v4.5.0",
+                    &["github.com/gin-gonic/gin@v1.9.1".to_string()][..]
+                ),
+            ]
+        );
+    }
+}
@@ -1,21 +1,86 @@
+mod bun;
 mod cargo;
+mod deno;
+mod go;
 mod npm;
+mod pnpm;
+mod poetry;
+mod requirements;
 
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
-use miette::{bail, Result};
+use clap::ValueEnum;
+use miette::{bail, Context, IntoDiagnostic, Result};
 
-use crate::types::Package;
+use crate::sbom::{cpe, package_url};
+use crate::target::Target;
+use crate::types::{Package, PackageSource};
 
-pub use cargo::{CargoLockfileParser, CargoPackageInfo};
-pub use npm::NpmLockfileParser;
+pub use bun::BunLockfileParser;
+pub use cargo::{registry_source_dir, CargoLockfileParser, CargoPackageInfo};
+pub use deno::DenoLockfileParser;
+pub use go::{escape_path, module_cache, module_path, module_source_dir, GoModParser};
+pub use npm::{declared_dependencies, declared_peers, NpmLockfileParser};
+pub use pnpm::PnpmLockfileParser;
+pub use poetry::PoetryLockfileParser;
+pub use requirements::{normalize_name, RequirementsParser};
 
 /// Unified lockfile parser that auto-detects the lockfile type
 pub struct LockfileParser {
     root: PathBuf,
     lockfile_path: PathBuf,
     lockfile_type: LockfileType,
+
+    /// The platform Cargo dependencies are evaluated for, the host if `None`
+    target: Option<Target>,
+
+    /// The workspace member being analyzed with its workspace's lockfile,
+    /// whose manifest decides which packages are direct
+    member: Option<PathBuf>,
+}
+
+/// Which lockfiles a command reads: the one `--lockfile` names, or those
+/// found in the project, only `--ecosystem`'s if given, and the `--target`
+/// they're read for
+#[derive(Debug, Clone, Default)]
+pub struct LockfileSelection {
+    pub lockfile: Option<PathBuf>,
+    pub ecosystem: Option<Ecosystem>,
+    pub target: Option<Target>,
+}
+
+impl LockfileSelection {
+    /// Every selected lockfile of the project at `root`
+    pub fn lockfiles(&self, root: &Path) -> Result<Vec<LockfileParser>> {
+        let parsers = match &self.lockfile {
+            Some(lockfile) => vec![LockfileParser::from_file(lockfile, root)?],
+            None => LockfileParser::detect_all(root, self.ecosystem)?,
+        };
+        Ok(parsers
+            .into_iter()
+            .map(|parser| parser.with_target(self.target.clone()))
+            .collect())
+    }
+
+    /// The single lockfile a command reads, `ecosystem`'s when it only
+    /// understands one
+    pub fn lockfile(&self, root: &Path, ecosystem: Option<Ecosystem>) -> Result<LockfileParser> {
+        let ecosystem = ecosystem.or(self.ecosystem);
+        let Some(lockfile) = &self.lockfile else {
+            return Ok(
+                LockfileParser::for_ecosystem(root, ecosystem)?.with_target(self.target.clone())
+            );
+        };
+
+        let parser = LockfileParser::from_file(lockfile, root)?.with_target(self.target.clone());
+        match ecosystem {
+            Some(ecosystem) if parser.lockfile_type().ecosystem() != ecosystem => {
+                bail!("{} is not a {} lockfile", lockfile.display(), ecosystem)
+            }
+            _ => Ok(parser),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,43 +88,271 @@ pub enum LockfileType {
     Npm,
     Pnpm,
     Yarn,
+    Bun,
+    Deno,
     Cargo,
+    Poetry,
+    /// A pip requirements.txt, standing in for a lockfile
+    Pip,
+    /// go.mod, with go.sum
+    Go,
+}
+
+/// A language's package ecosystem, which may have several lockfile formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum)]
+pub enum Ecosystem {
+    Rust,
+    /// npm, pnpm, yarn, bun and deno
+    Js,
+    Python,
+    Go,
+}
+
+impl std::fmt::Display for Ecosystem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Ecosystem::Rust => "Rust",
+            Ecosystem::Js => "JavaScript",
+            Ecosystem::Python => "Python",
+            Ecosystem::Go => "Go",
+        })
+    }
+}
+
+impl LockfileType {
+    pub fn ecosystem(&self) -> Ecosystem {
+        match self {
+            LockfileType::Cargo => Ecosystem::Rust,
+            LockfileType::Poetry | LockfileType::Pip => Ecosystem::Python,
+            LockfileType::Go => Ecosystem::Go,
+            LockfileType::Npm
+            | LockfileType::Pnpm
+            | LockfileType::Yarn
+            | LockfileType::Bun
+            | LockfileType::Deno => Ecosystem::Js,
+        }
+    }
+
+    /// The package URL type for packages from this lockfile ("pkg:npm/...")
+    pub fn purl_type(&self) -> &'static str {
+        match self {
+            LockfileType::Cargo => "cargo",
+            LockfileType::Poetry | LockfileType::Pip => "pypi",
+            LockfileType::Go => "golang",
+            LockfileType::Npm
+            | LockfileType::Pnpm
+            | LockfileType::Yarn
+            | LockfileType::Bun
+            | LockfileType::Deno => "npm",
+        }
+    }
+
+    /// Whether the project is a Python one, whose imports are read by the
+    /// Python scanner
+    pub fn is_python(&self) -> bool {
+        matches!(self, LockfileType::Poetry | LockfileType::Pip)
+    }
+
+    /// The OSV ecosystem name for packages from this lockfile
+    pub fn osv_ecosystem(&self) -> &'static str {
+        match self {
+            LockfileType::Cargo => "crates.io",
+            LockfileType::Poetry | LockfileType::Pip => "PyPI",
+            LockfileType::Go => "Go",
+            LockfileType::Npm
+            | LockfileType::Pnpm
+            | LockfileType::Yarn
+            | LockfileType::Bun
+            | LockfileType::Deno => "npm",
+        }
+    }
 }
 
 impl LockfileParser {
+    /// The project's lockfile, or the first of several by `LOCKFILES` order
+    #[allow(dead_code)]
     pub fn new(root: impl AsRef<Path>) -> Result<Self> {
-        let root = root.as_ref().to_path_buf();
+        Self::for_ecosystem(root, None)
+    }
+
+    /// The project's lockfile for one ecosystem, or for any when `None`
+    pub fn for_ecosystem(root: impl AsRef<Path>, ecosystem: Option<Ecosystem>) -> Result<Self> {
+        let root = root.as_ref();
+        Self::detect_all(root, ecosystem)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| no_lockfile(root, ecosystem))
+    }
+
+    /// One parser per ecosystem with a lockfile, e.g. Cargo.lock and
+    /// package-lock.json for a Tauri app, or just `ecosystem`'s
+    ///
+    /// Errors when no lockfile is found.
+    pub fn detect_all(root: impl AsRef<Path>, ecosystem: Option<Ecosystem>) -> Result<Vec<Self>> {
+        let root = root.as_ref();
+        let parsers: Vec<Self> = detect_lockfiles(root)
+            .into_iter()
+            .filter(|(_, t)| ecosystem.is_none_or(|e| t.ecosystem() == e))
+            .map(|(lockfile_path, lockfile_type)| Self {
+                root: root.to_path_buf(),
+                lockfile_path,
+                lockfile_type,
+                target: None,
+                member: None,
+            })
+            .collect();
+        if parsers.is_empty() {
+            return Err(no_lockfile(root, ecosystem));
+        }
+        Ok(parsers)
+    }
 
-        // Auto-detect lockfile
-        let (lockfile_path, lockfile_type) = detect_lockfile(&root)?;
+    /// A lockfile named explicitly, such as a monorepo's root lockfile
+    /// for one of its packages at `project`
+    ///
+    /// When the lockfile is outside `project`, the packages `project`
+    /// declares are its direct dependencies.
+    pub fn from_file(lockfile: impl AsRef<Path>, project: impl AsRef<Path>) -> Result<Self> {
+        let lockfile = lockfile.as_ref();
+        let name = lockfile.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let Some((_, lockfile_type)) = LOCKFILES.iter().find(|(file, _)| *file == name) else {
+            bail!(
+                "{} is not a lockfile depx reads. Expected one of: {}",
+                lockfile.display(),
+                lockfile_names(None)
+            );
+        };
+        if !lockfile.is_file() {
+            bail!("Lockfile {} not found", lockfile.display());
+        }
+
+        let root = match lockfile.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let project = project.as_ref();
+        let same_dir = match (root.canonicalize(), project.canonicalize()) {
+            (Ok(root), Ok(project)) => root == project,
+            _ => false,
+        };
 
         Ok(Self {
             root,
-            lockfile_path,
-            lockfile_type,
+            lockfile_path: lockfile.to_path_buf(),
+            lockfile_type: *lockfile_type,
+            target: None,
+            member: (!same_dir).then(|| project.to_path_buf()),
         })
     }
 
+    /// Evaluate Cargo's `[target.'cfg(...)']` dependencies for `target`
+    /// instead of the host
+    pub fn with_target(mut self, target: Option<Target>) -> Self {
+        self.target = target;
+        self
+    }
+
     /// Parse the lockfile and return all packages
     pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let mut packages = self.parse_packages()?;
+        if let Some(member) = &self.member {
+            scope_to_member(&mut packages, member, self.lockfile_type.ecosystem())?;
+        }
+        let kind = self.lockfile_type.purl_type();
+        for package in packages.values_mut() {
+            if package.is_from_registry() && jsr_package(&package.name).is_some() {
+                package.source = PackageSource::Jsr;
+            }
+            // Git, local, JSR and remote packages have no registry identity
+            if package.is_from_registry() {
+                package.purl = Some(package_url(kind, &package.name, &package.version));
+                package.cpe = Some(cpe(kind, &package.name, &package.version));
+            }
+        }
+        Ok(packages)
+    }
+
+    fn parse_packages(&self) -> Result<HashMap<String, Package>> {
         match self.lockfile_type {
             LockfileType::Npm => {
                 let parser = NpmLockfileParser::new(&self.root, &self.lockfile_path);
                 parser.parse()
             }
             LockfileType::Pnpm => {
-                bail!("pnpm lockfile support coming soon")
+                let parser = PnpmLockfileParser::new(&self.root, &self.lockfile_path);
+                parser.parse()
             }
             LockfileType::Yarn => {
                 bail!("yarn lockfile support coming soon")
             }
+            LockfileType::Bun => {
+                let parser = BunLockfileParser::new(&self.root, &self.lockfile_path);
+                parser.parse()
+            }
+            LockfileType::Deno => {
+                let parser = DenoLockfileParser::new(&self.root, &self.lockfile_path);
+                parser.parse()
+            }
             LockfileType::Cargo => {
-                let parser = CargoLockfileParser::new(&self.lockfile_path);
+                let mut parser = CargoLockfileParser::new(&self.lockfile_path);
+                if let Some(target) = &self.target {
+                    parser = parser.with_target(target.clone());
+                }
+                if let Some(member) = &self.member {
+                    if let Some(name) = cargo_member_name(member)? {
+                        parser = parser.with_member(name);
+                    }
+                }
+                parser.parse()
+            }
+            LockfileType::Poetry => {
+                let parser = PoetryLockfileParser::new(&self.root, &self.lockfile_path);
+                parser.parse()
+            }
+            LockfileType::Pip => {
+                let parser = RequirementsParser::new(&self.root, &self.lockfile_path);
+                parser.parse()
+            }
+            LockfileType::Go => {
+                let parser = GoModParser::new(&self.root, &self.lockfile_path);
                 parser.parse()
             }
         }
     }
 
+    /// Every installed version of every package, with what depends on it
+    pub fn parse_versions(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        match self.lockfile_type {
+            LockfileType::Npm => {
+                NpmLockfileParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Pnpm => {
+                PnpmLockfileParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Yarn => {
+                bail!("yarn lockfile support coming soon")
+            }
+            LockfileType::Bun => {
+                BunLockfileParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Deno => {
+                DenoLockfileParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Cargo => {
+                CargoLockfileParser::new(&self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Poetry => {
+                PoetryLockfileParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Pip => {
+                RequirementsParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+            LockfileType::Go => {
+                GoModParser::new(&self.root, &self.lockfile_path).parse_for_duplicates()
+            }
+        }
+    }
+
     pub fn lockfile_type(&self) -> LockfileType {
         self.lockfile_type
     }
@@ -67,35 +360,294 @@ impl LockfileParser {
     pub fn lockfile_path(&self) -> &Path {
         &self.lockfile_path
     }
-}
 
-fn detect_lockfile(root: &Path) -> Result<(PathBuf, LockfileType)> {
-    // Check for Cargo.lock (Rust projects)
-    let cargo_lock = root.join("Cargo.lock");
-    if cargo_lock.exists() {
-        return Ok((cargo_lock, LockfileType::Cargo));
+    /// The directory the lockfile belongs to, which manifests and installed
+    /// packages are relative to
+    pub fn root(&self) -> &Path {
+        &self.root
     }
 
-    // Check for npm
-    let npm_lock = root.join("package-lock.json");
-    if npm_lock.exists() {
-        return Ok((npm_lock, LockfileType::Npm));
+    /// The lockfile read
+    pub fn path(&self) -> &Path {
+        &self.lockfile_path
     }
 
-    // Check for pnpm
-    let pnpm_lock = root.join("pnpm-lock.yaml");
-    if pnpm_lock.exists() {
-        return Ok((pnpm_lock, LockfileType::Pnpm));
+    /// "Cargo.lock", "package-lock.json", ...
+    pub fn file_name(&self) -> String {
+        self.lockfile_path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default()
     }
+}
 
-    // Check for yarn
-    let yarn_lock = root.join("yarn.lock");
-    if yarn_lock.exists() {
-        return Ok((yarn_lock, LockfileType::Yarn));
+/// Lockfiles depx reads, in order of preference within an ecosystem
+///
+/// bun's text lockfile wins over its binary one when both exist, and
+/// requirements.txt only counts when nothing else locks the Python
+/// dependencies.
+const LOCKFILES: &[(&str, LockfileType)] = &[
+    ("Cargo.lock", LockfileType::Cargo),
+    ("package-lock.json", LockfileType::Npm),
+    ("pnpm-lock.yaml", LockfileType::Pnpm),
+    ("bun.lock", LockfileType::Bun),
+    ("bun.lockb", LockfileType::Bun),
+    ("deno.lock", LockfileType::Deno),
+    ("yarn.lock", LockfileType::Yarn),
+    ("poetry.lock", LockfileType::Poetry),
+    ("requirements.txt", LockfileType::Pip),
+    ("go.mod", LockfileType::Go),
+];
+
+/// The preferred lockfile of each ecosystem present, in `LOCKFILES` order
+fn detect_lockfiles(root: &Path) -> Vec<(PathBuf, LockfileType)> {
+    let mut found: Vec<(PathBuf, LockfileType)> = Vec::new();
+    for (file, lockfile_type) in LOCKFILES {
+        let path = root.join(file);
+        let ecosystem = lockfile_type.ecosystem();
+        if path.exists() && !found.iter().any(|(_, t)| t.ecosystem() == ecosystem) {
+            found.push((path, *lockfile_type));
+        }
     }
+    found
+}
 
-    bail!(
-        "No lockfile found in {}. Expected one of: Cargo.lock, package-lock.json, pnpm-lock.yaml, yarn.lock",
-        root.display()
+/// The lockfile names of an ecosystem, or of all, for error messages
+fn lockfile_names(ecosystem: Option<Ecosystem>) -> String {
+    let names: Vec<&str> = LOCKFILES
+        .iter()
+        .filter(|(_, t)| ecosystem.is_none_or(|e| t.ecosystem() == e))
+        .map(|(file, _)| *file)
+        .collect();
+    names.join(", ")
+}
+
+fn no_lockfile(root: &Path, ecosystem: Option<Ecosystem>) -> miette::Report {
+    let found = match ecosystem {
+        Some(ecosystem) => format!("No {} lockfile", ecosystem),
+        None => "No lockfile".to_string(),
+    };
+    miette::miette!(
+        "{} found in {}. Expected one of: {}",
+        found,
+        root.display(),
+        lockfile_names(ecosystem)
     )
 }
+
+/// Make direct dependencies those of one workspace member, read from its
+/// package.json
+///
+/// Workspace lockfiles mark every member's dependencies as direct, which
+/// would report the other members' dependencies as unused. Ecosystems
+/// without workspaces, or a member without a manifest, are left alone.
+/// Cargo members are scoped by the Cargo.lock parser, see
+/// [`CargoLockfileParser::with_member`].
+fn scope_to_member(
+    packages: &mut HashMap<String, Package>,
+    member: &Path,
+    ecosystem: Ecosystem,
+) -> Result<()> {
+    match ecosystem {
+        Ecosystem::Js if member.join("package.json").exists() => {
+            let (runtime, dev) = declared_dependencies(&member.join("package.json"))?;
+            for package in packages.values_mut() {
+                package.is_direct = runtime.contains(&package.name) || dev.contains(&package.name);
+                if package.is_direct {
+                    package.is_dev = dev.contains(&package.name);
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// The crate name in a Cargo workspace member's Cargo.toml, if it has one
+fn cargo_member_name(member: &Path) -> Result<Option<String>> {
+    let manifest_path = member.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&manifest_path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: toml::Value = toml::from_str(&content)
+        .map_err(|e| miette::miette!("Failed to parse {}: {}", manifest_path.display(), e))?;
+    Ok(manifest
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(str::to_string))
+}
+
+/// The directory an npm, pnpm or bun package is installed in, when its
+/// package.json there is the locked version
+///
+/// npm and bun hoist packages to `node_modules/<name>`, pnpm keeps them
+/// in `node_modules/.pnpm/<name>@<version>/node_modules/<name>`.
+pub fn installed_dir(root: &Path, package: &Package) -> Option<PathBuf> {
+    let node_modules = root.join("node_modules");
+    let candidates: [PathBuf; 2] = [
+        node_modules.join(&package.name),
+        node_modules
+            .join(".pnpm")
+            .join(format!(
+                "{}@{}",
+                package.name.replace('/', "+"),
+                package.version
+            ))
+            .join("node_modules")
+            .join(&package.name),
+    ];
+    candidates.into_iter().find(|dir| {
+        std::fs::read_to_string(dir.join("package.json"))
+            .ok()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).ok())
+            .is_some_and(|manifest| manifest["version"].as_str() == Some(&package.version))
+    })
+}
+
+/// The JSR package published on JSR's npm compatibility registry
+/// (npm.jsr.io) under an npm name
+///
+/// "@jsr/std__path" -> "@std/path"
+pub fn jsr_package(npm_name: &str) -> Option<String> {
+    let (scope, name) = npm_name.strip_prefix("@jsr/")?.split_once("__")?;
+    Some(format!("@{}/{}", scope, name))
+}
+
+/// The npm name JSR's npm compatibility registry publishes a JSR package under
+///
+/// "@std/path" -> "@jsr/std__path"
+pub fn jsr_npm_name(name: &str) -> Option<String> {
+    let (scope, name) = name.strip_prefix('@')?.split_once('/')?;
+    Some(format!("@jsr/{}__{}", scope, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_jsr_names() {
+        assert_eq!(jsr_package("@jsr/std__path").as_deref(), Some("@std/path"));
+        assert_eq!(
+            jsr_package("@jsr/luca__cases").as_deref(),
+            Some("@luca/cases")
+        );
+        assert_eq!(jsr_package("@std/path"), None);
+        assert_eq!(jsr_package("@jsr/unscoped"), None);
+        assert_eq!(jsr_npm_name("@std/path").as_deref(), Some("@jsr/std__path"));
+        assert_eq!(jsr_npm_name("chalk"), None);
+    }
+
+    #[test]
+    fn test_detect_lockfiles() {
+        let root = TempDir::new("detect");
+        for file in [
+            "Cargo.lock",
+            "package-lock.json",
+            "yarn.lock",
+            "poetry.lock",
+            "requirements.txt",
+        ] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        // One lockfile per ecosystem, the preferred one within each
+        let found: Vec<LockfileType> = LockfileParser::detect_all(&root, None)
+            .unwrap()
+            .iter()
+            .map(|p| p.lockfile_type())
+            .collect();
+        assert_eq!(
+            found,
+            [LockfileType::Cargo, LockfileType::Npm, LockfileType::Poetry]
+        );
+
+        let js = LockfileParser::for_ecosystem(&root, Some(Ecosystem::Js)).unwrap();
+        assert_eq!(js.lockfile_path(), root.join("package-lock.json"));
+        assert_eq!(
+            LockfileParser::new(&root).unwrap().lockfile_type(),
+            LockfileType::Cargo
+        );
+
+        let error = LockfileParser::for_ecosystem(&root, Some(Ecosystem::Go))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.starts_with("No Go lockfile found"), "{}", error);
+        assert!(error.ends_with("Expected one of: go.mod"), "{}", error);
+    }
+
+    #[test]
+    fn test_lockfile_for_member() {
+        let root = TempDir::new("member-lockfile");
+        let app = root.join("packages/app");
+        std::fs::create_dir_all(&app).unwrap();
+        std::fs::write(
+            app.join("package.json"),
+            r#"{"dependencies": {"react": "^18.2.0"}, "devDependencies": {"vitest": "^1.0.0"}}"#,
+        )
+        .unwrap();
+        let lockfile = root.join("pnpm-lock.yaml");
+        std::fs::write(
+            &lockfile,
+            r#"
+lockfileVersion: '9.0'
+importers:
+  .:
+    devDependencies:
+      typescript:
+        specifier: ^5.0.0
+        version: 5.3.3
+  packages/app:
+    dependencies:
+      react:
+        specifier: ^18.2.0
+        version: 18.2.0
+    devDependencies:
+      vitest:
+        specifier: ^1.0.0
+        version: 1.0.0
+packages:
+  typescript@5.3.3:
+    resolution: {integrity: sha512-a}
+  react@18.2.0:
+    resolution: {integrity: sha512-b}
+  vitest@1.0.0:
+    resolution: {integrity: sha512-c}
+snapshots:
+  typescript@5.3.3: {}
+  react@18.2.0: {}
+  vitest@1.0.0: {}
+"#,
+        )
+        .unwrap();
+
+        let parser = LockfileParser::from_file(&lockfile, &app).unwrap();
+        assert_eq!(parser.lockfile_type(), LockfileType::Pnpm);
+        assert_eq!(parser.root(), &*root);
+        let packages = parser.parse().unwrap();
+
+        // Only what the member declares is direct
+        assert!(packages["react"].is_direct && !packages["react"].is_dev);
+        assert!(packages["vitest"].is_direct && packages["vitest"].is_dev);
+        assert!(!packages["typescript"].is_direct);
+
+        // The workspace root itself keeps every importer's dependencies
+        let packages = LockfileParser::from_file(&lockfile, &root)
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(packages["typescript"].is_direct);
+
+        let error = LockfileParser::from_file(root.join("deps.lock"), &root)
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(error.contains("is not a lockfile depx reads"), "{}", error);
+    }
+}
@@ -1,10 +1,17 @@
+use std::collections::hash_map::Entry;
 use std::collections::{HashMap, HashSet};
 use std::path::Path;
 
 use miette::{Context, IntoDiagnostic, Result};
 use serde::Deserialize;
+use serde_json::Value as JsonValue;
 
-use crate::types::Package;
+use crate::range::npm_alias;
+use crate::types::{Package, PackageSource};
+use crate::workspace;
+
+/// npm's record of the last install, kept inside node_modules
+const HIDDEN_LOCKFILE: &str = "node_modules/.package-lock.json";
 
 /// Parser for npm's package-lock.json
 pub struct NpmLockfileParser<'a> {
@@ -29,29 +36,40 @@ impl<'a> NpmLockfileParser<'a> {
             .into_diagnostic()
             .with_context(|| "Failed to parse package-lock.json")?;
 
-        // Also read package.json to know which are direct dependencies
-        let package_json_path = self.root.join("package.json");
-        let package_json: PackageJson = if package_json_path.exists() {
-            let content = std::fs::read_to_string(&package_json_path)
-                .into_diagnostic()
-                .with_context(|| "Failed to read package.json")?;
-            serde_json::from_str(&content)
-                .into_diagnostic()
-                .with_context(|| "Failed to parse package.json")?
-        } else {
-            PackageJson::default()
-        };
+        // The root package.json and those of any workspace members tell
+        // which packages are direct dependencies
+        let mut manifests = vec![read_package_json(&self.root.join("package.json"))?];
+        for member in workspace::member_dirs(self.root)? {
+            manifests.push(read_package_json(&member.join("package.json"))?);
+        }
 
-        let direct_deps: HashSet<String> = package_json
-            .dependencies
-            .keys()
-            .chain(package_json.dev_dependencies.keys())
-            .cloned()
+        let mut direct_deps: HashSet<String> = HashSet::new();
+        let mut prod_deps: HashSet<String> = HashSet::new();
+        let mut optional_deps: HashSet<String> = HashSet::new();
+        for manifest in &manifests {
+            direct_deps.extend(real_names(&manifest.dependencies));
+            direct_deps.extend(real_names(&manifest.dev_dependencies));
+            direct_deps.extend(real_names(&manifest.optional_dependencies));
+            prod_deps.extend(real_names(&manifest.dependencies));
+            prod_deps.extend(real_names(&manifest.optional_dependencies));
+            optional_deps.extend(real_names(&manifest.optional_dependencies));
+        }
+
+        // Dev dependencies of one member that another needs at runtime aren't dev-only
+        let dev_deps: HashSet<String> = manifests
+            .iter()
+            .flat_map(|manifest| real_names(&manifest.dev_dependencies))
+            .filter(|name| !prod_deps.contains(name))
             .collect();
 
-        let dev_deps: HashSet<String> = package_json.dev_dependencies.keys().cloned().collect();
+        let mut packages = self.parse_lockfile_v3(&lockfile, &direct_deps, &dev_deps)?;
+        for name in &optional_deps {
+            if let Some(pkg) = packages.get_mut(name) {
+                pkg.is_optional = true;
+            }
+        }
 
-        self.parse_lockfile_v3(&lockfile, &direct_deps, &dev_deps)
+        Ok(packages)
     }
 
     /// Parse lockfile format v2/v3 (npm 7+)
@@ -65,43 +83,89 @@ impl<'a> NpmLockfileParser<'a> {
 
         // In v2/v3, packages are under the "packages" key
         // The keys are paths like "" (root), "node_modules/lodash", etc.
-        for (path, pkg_info) in &lockfile.packages {
-            // Skip the root package
-            if path.is_empty() {
+        // Workspace members appear twice: their sources under their own
+        // directory ("packages/app") and a link to them ("node_modules/app").
+        for (path, link_info) in &lockfile.packages {
+            // Skip the root package and workspace sources; members are
+            // included through their links
+            if path.is_empty() || is_workspace_source(path) {
                 continue;
             }
+            let pkg_info = link_target(lockfile, link_info).unwrap_or(link_info);
 
             // Extract package name from path
             // "node_modules/lodash" -> "lodash"
             // "node_modules/@scope/pkg" -> "@scope/pkg"
             // "node_modules/foo/node_modules/bar" -> "bar"
-            let name = extract_package_name_from_path(path);
+            // Aliases record the package they install under "name"
+            let name = entry_name(path, link_info);
             if name.is_empty() {
                 continue;
             }
 
-            let version = pkg_info.version.clone().unwrap_or_default();
+            let source = entry_source(link_info);
+            let version = match &pkg_info.version {
+                Some(version) => version.clone(),
+                // Git checkouts without a version in their package.json
+                // are identified by the commit they were installed from
+                None => link_info
+                    .resolved
+                    .as_deref()
+                    .filter(|_| source == PackageSource::Git)
+                    .and_then(git_commit)
+                    .unwrap_or_default()
+                    .to_string(),
+            };
             let is_direct = direct_deps.contains(&name);
-            let is_dev = pkg_info.dev.unwrap_or(false) || dev_deps.contains(&name);
+            let is_dev = link_info.dev.unwrap_or(false) || dev_deps.contains(&name);
 
-            let dependencies: Vec<String> = pkg_info
-                .dependencies
-                .keys()
-                .chain(pkg_info.optional_dependencies.keys())
-                .cloned()
+            let dependency_ranges: HashMap<String, String> = real_ranges(&pkg_info.dependencies)
+                .chain(real_ranges(&pkg_info.optional_dependencies))
                 .collect();
+            let dependencies: Vec<String> = dependency_ranges.keys().cloned().collect();
 
             let package = Package {
                 name: name.clone(),
                 version,
                 is_direct,
                 is_dev,
+                is_build: false,
                 dependencies,
+                dependency_ranges,
+                peer_dependencies: pkg_info.peer_dependencies.clone(),
+                optional_peers: optional_peers(&pkg_info.peer_dependencies_meta),
                 deprecated: pkg_info.deprecated.clone(),
+                bins: pkg_info.bin.keys().cloned().collect(),
+                is_optional: link_info.optional.unwrap_or(false),
+                os: pkg_info.os.clone(),
+                cpu: pkg_info.cpu.clone(),
+                libc: pkg_info.libc.clone(),
+                platform_parent: None,
+                bundled_in: bundled_parent(lockfile, path),
+                other_target: None,
+                source,
+                purl: None,
+                cpe: None,
             };
 
-            // Use the name as key (this will keep the first occurrence for duplicates)
-            packages.entry(name).or_insert(package);
+            // Use the name as key (this will keep the first occurrence for
+            // duplicates, preferring a real install over a bundled copy)
+            match packages.entry(name) {
+                Entry::Vacant(entry) => {
+                    entry.insert(package);
+                }
+                Entry::Occupied(mut entry) => {
+                    if entry.get().bundled_in.is_some() && package.bundled_in.is_none() {
+                        entry.insert(package);
+                    }
+                }
+            }
+        }
+
+        for (child, parent) in platform_parents(lockfile) {
+            if let Some(pkg) = packages.get_mut(&child) {
+                pkg.platform_parent = Some(parent);
+            }
         }
 
         // Fallback to v1 format if packages map is empty
@@ -128,21 +192,48 @@ impl<'a> NpmLockfileParser<'a> {
             dev_deps: &HashSet<String>,
         ) {
             for (name, dep) in deps {
+                // Aliases are keyed by the alias, with "npm:<name>@<version>"
+                let (name, version) =
+                    npm_alias(&dep.version).unwrap_or((name.as_str(), dep.version.as_str()));
+                // Git and local dependencies keep their source where the version goes
+                let source = PackageSource::of(version);
+                let version = match source {
+                    PackageSource::Registry | PackageSource::Jsr | PackageSource::Remote => version,
+                    PackageSource::Git => git_commit(version).unwrap_or_default(),
+                    PackageSource::File | PackageSource::Link => "",
+                };
                 let is_direct = direct_deps.contains(name);
                 let is_dev = dep.dev.unwrap_or(false) || dev_deps.contains(name);
 
-                let dependencies: Vec<String> = dep.requires.keys().cloned().collect();
+                let dependency_ranges: HashMap<String, String> =
+                    real_ranges(&dep.requires).collect();
+                let dependencies: Vec<String> = dependency_ranges.keys().cloned().collect();
 
                 let package = Package {
-                    name: name.clone(),
-                    version: dep.version.clone(),
+                    name: name.to_string(),
+                    version: version.to_string(),
                     is_direct,
                     is_dev,
+                    is_build: false,
                     dependencies,
+                    dependency_ranges,
+                    peer_dependencies: HashMap::new(),
+                    optional_peers: Vec::new(),
                     deprecated: None,
+                    bins: Vec::new(),
+                    is_optional: dep.optional.unwrap_or(false),
+                    os: Vec::new(),
+                    cpu: Vec::new(),
+                    libc: Vec::new(),
+                    platform_parent: None,
+                    bundled_in: None,
+                    other_target: None,
+                    source,
+                    purl: None,
+                    cpe: None,
                 };
 
-                packages.entry(name.clone()).or_insert(package);
+                packages.entry(name.to_string()).or_insert(package);
 
                 // Recurse into nested dependencies
                 collect_deps(&dep.dependencies, packages, direct_deps, dev_deps);
@@ -151,9 +242,186 @@ impl<'a> NpmLockfileParser<'a> {
 
         collect_deps(&lockfile.dependencies, &mut packages, direct_deps, dev_deps);
 
+        // v1 lockfiles don't record `bin`
+        for (name, pkg) in packages.iter_mut() {
+            pkg.bins = installed_bins(self.root, name);
+        }
+
         Ok(packages)
     }
 
+    /// Where node_modules/.package-lock.json, npm's record of what the
+    /// last install put in node_modules, disagrees with package-lock.json
+    ///
+    /// A partial or interrupted install, or pulling a lockfile change
+    /// without reinstalling, leaves the two apart. Optional packages for
+    /// other platforms, and dev dependencies of an `--omit=dev` install,
+    /// aren't installed by design. Empty when nothing is installed or the
+    /// lockfile is v1, which lists no install paths.
+    pub fn install_divergence(&self) -> Result<Vec<InstallDivergence>> {
+        let hidden_path = self.root.join(HIDDEN_LOCKFILE);
+        if !hidden_path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+        let lockfile: NpmLockfile = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package-lock.json")?;
+        let content = std::fs::read_to_string(&hidden_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", hidden_path.display()))?;
+        let installed: NpmLockfile = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to parse {}", HIDDEN_LOCKFILE))?;
+
+        // Workspace links and bundled packages aren't installed from the registry
+        let compared = |path: &str, info: &NpmPackageInfo| {
+            path.contains("node_modules/")
+                && !info.link.unwrap_or(false)
+                && !info.in_bundle.unwrap_or(false)
+        };
+        let dev_installed = installed
+            .packages
+            .values()
+            .any(|info| info.dev.unwrap_or(false));
+
+        let mut divergence = Vec::new();
+        for (path, info) in &lockfile.packages {
+            if !compared(path, info) {
+                continue;
+            }
+            match installed.packages.get(path) {
+                Some(copy) if copy.version == info.version => {}
+                None if info.optional.unwrap_or(false)
+                    || (info.dev.unwrap_or(false) && !dev_installed) => {}
+                copy => divergence.push(InstallDivergence {
+                    name: entry_name(path, info),
+                    path: path.clone(),
+                    locked: info.version.clone(),
+                    installed: copy.and_then(|c| c.version.clone()),
+                }),
+            }
+        }
+        for (path, info) in &installed.packages {
+            if compared(path, info) && !lockfile.packages.contains_key(path) {
+                divergence.push(InstallDivergence {
+                    name: entry_name(path, info),
+                    path: path.clone(),
+                    locked: None,
+                    installed: info.version.clone(),
+                });
+            }
+        }
+
+        divergence.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(divergence)
+    }
+
+    /// Installed packages keyed by their directory, with their peers
+    ///
+    /// Unlike `parse`, every copy is kept: `node_modules/a/node_modules/react`
+    /// is the one `a` resolves, ahead of a hoisted `node_modules/react`.
+    /// Workspace members are keyed by their sources' directory, where Node
+    /// resolves their imports from. Empty for v1 lockfiles.
+    pub fn parse_install_paths(&self) -> Result<HashMap<String, Package>> {
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+
+        let lockfile: NpmLockfile = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package-lock.json")?;
+
+        let mut installs = HashMap::new();
+        for (path, info) in &lockfile.packages {
+            if path.is_empty() || is_workspace_source(path) {
+                continue;
+            }
+            let (dir, pkg_info) = match link_target(&lockfile, info) {
+                Some(target) => (info.resolved.clone().unwrap_or_default(), target),
+                None => (path.clone(), info),
+            };
+            let mut package = Package::new(
+                entry_name(path, info),
+                pkg_info.version.clone().unwrap_or_default(),
+            );
+            package.peer_dependencies = pkg_info.peer_dependencies.clone();
+            package.optional_peers = optional_peers(&pkg_info.peer_dependencies_meta);
+            installs.insert(dir, package);
+        }
+        Ok(installs)
+    }
+
+    /// Where every installed entry was downloaded from and the hash it's pinned to
+    ///
+    /// Workspace links and the member sources they point at, and copies
+    /// bundled inside another package's tarball, are never downloaded on
+    /// their own and are left out. v1 lockfiles keep the git URL or `file:`
+    /// path of a non-registry dependency in its version, which stands in
+    /// for `resolved`.
+    pub fn parse_resolutions(&self) -> Result<Vec<ResolvedEntry>> {
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+
+        let lockfile: NpmLockfile = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package-lock.json")?;
+
+        let mut entries = Vec::new();
+        for (path, info) in &lockfile.packages {
+            if path.is_empty()
+                || is_workspace_source(path)
+                || info.link.unwrap_or(false)
+                || bundled_parent(&lockfile, path).is_some()
+            {
+                continue;
+            }
+            entries.push(ResolvedEntry {
+                name: entry_name(path, info),
+                version: info.version.clone().unwrap_or_default(),
+                path: path.clone(),
+                resolved: info.resolved.clone(),
+                integrity: info.integrity.clone(),
+            });
+        }
+
+        // v1 lockfiles nest dependencies instead of listing paths
+        fn collect_v1(
+            deps: &HashMap<String, NpmDependency>,
+            prefix: &str,
+            entries: &mut Vec<ResolvedEntry>,
+        ) {
+            for (name, dep) in deps {
+                let path = format!("{}node_modules/{}", prefix, name);
+                if !dep.bundled.unwrap_or(false) {
+                    let (real, version) =
+                        npm_alias(&dep.version).unwrap_or((name.as_str(), dep.version.as_str()));
+                    let resolved = dep.resolved.clone().or_else(|| {
+                        (PackageSource::of(&dep.version) != PackageSource::Registry)
+                            .then(|| dep.version.clone())
+                    });
+                    entries.push(ResolvedEntry {
+                        name: real.to_string(),
+                        version: version.to_string(),
+                        path: path.clone(),
+                        resolved,
+                        integrity: dep.integrity.clone(),
+                    });
+                }
+                collect_v1(&dep.dependencies, &format!("{}/", path), entries);
+            }
+        }
+        if lockfile.packages.is_empty() {
+            collect_v1(&lockfile.dependencies, "", &mut entries);
+        }
+
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(entries)
+    }
+
     pub fn parse_for_duplicates(
         &self,
     ) -> Result<HashMap<String, Vec<crate::lockfile::CargoPackageInfo>>> {
@@ -167,20 +435,25 @@ impl<'a> NpmLockfileParser<'a> {
 
         let mut by_name: HashMap<String, Vec<crate::lockfile::CargoPackageInfo>> = HashMap::new();
         let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+        let mut requirements: HashMap<String, HashMap<String, String>> = HashMap::new();
+
+        let platform_children = platform_parents(&lockfile);
 
         // Build reverse dependency map for npm (v3)
         for (path, pkg_info) in &lockfile.packages {
             let pkg_name = if path.is_empty() {
                 // For root, try to get name from pkg_info or use "root"
                 pkg_info.name.clone().unwrap_or_else(|| "root".to_string())
+            } else if is_workspace_source(path) {
+                pkg_info.name.clone().unwrap_or_else(|| path.clone())
             } else {
-                extract_package_name_from_path(path)
+                entry_name(path, pkg_info)
             };
 
             let pkg_version = pkg_info.version.clone().unwrap_or_default();
             let pkg_key = format!("{}@{}", pkg_name, pkg_version);
 
-            for dep_name in pkg_info.dependencies.keys() {
+            for (dep_name, range) in &pkg_info.dependencies {
                 // In npm, we don't always know the exact version of the dependency from the packages map alone
                 // without resolving it. For simplicity in duplicate analysis, we'll map to the name for now,
                 // or try to find the resolved path if possible.
@@ -203,8 +476,15 @@ impl<'a> NpmLockfileParser<'a> {
 
                 if let Some(target_pkg) = lockfile.packages.get(&actual_dep_path) {
                     let target_version = target_pkg.version.clone().unwrap_or_default();
-                    let target_key = format!("{}@{}", dep_name, target_version);
-
+                    let target_name = entry_name(&actual_dep_path, target_pkg);
+                    let target_key = format!("{}@{}", target_name, target_version);
+
+                    requirements.entry(target_key.clone()).or_default().insert(
+                        pkg_key.clone(),
+                        npm_alias(range)
+                            .map_or(range.as_str(), |(_, r)| r)
+                            .to_string(),
+                    );
                     dependents
                         .entry(target_key)
                         .or_default()
@@ -220,12 +500,19 @@ impl<'a> NpmLockfileParser<'a> {
         }
 
         // Group by name
-        for (path, pkg_info) in &lockfile.packages {
-            if path.is_empty() {
+        for (path, link_info) in &lockfile.packages {
+            // Workspace members are counted once, through their links
+            if path.is_empty() || is_workspace_source(path) {
                 continue;
             }
+            let pkg_info = link_target(&lockfile, link_info).unwrap_or(link_info);
 
-            let name = extract_package_name_from_path(path);
+            let name = entry_name(path, link_info);
+            // Platform builds follow their parent, and bundled copies ship
+            // inside their parent's tarball where they can't be deduped
+            if platform_children.contains_key(&name) || bundled_parent(&lockfile, path).is_some() {
+                continue;
+            }
             let version = pkg_info.version.clone().unwrap_or_default();
             let key = format!("{}@{}", name, version);
             let pkg_dependents = dependents.get(&key).cloned().unwrap_or_default();
@@ -235,7 +522,9 @@ impl<'a> NpmLockfileParser<'a> {
                 versions.push(crate::lockfile::CargoPackageInfo {
                     version,
                     dependents: pkg_dependents,
-                    is_path_dep: false, // npm doesn't have a direct equivalent here easily
+                    // Workspace members and `file:` directories are linked
+                    is_path_dep: link_info.link.unwrap_or(false),
+                    requirements: requirements.remove(&key).unwrap_or_default(),
                 });
             }
         }
@@ -244,6 +533,227 @@ impl<'a> NpmLockfileParser<'a> {
     }
 }
 
+/// An install path where node_modules doesn't hold what package-lock.json
+/// records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstallDivergence {
+    pub name: String,
+
+    /// Install path (e.g. "node_modules/foo/node_modules/bar")
+    pub path: String,
+
+    /// Version package-lock.json records, `None` if it doesn't list the path
+    pub locked: Option<String>,
+
+    /// Version the last install put there, `None` if it isn't installed
+    pub installed: Option<String>,
+}
+
+/// A downloaded lockfile entry with its source and integrity hash
+#[derive(Debug, Clone)]
+pub struct ResolvedEntry {
+    pub name: String,
+    pub version: String,
+
+    /// Install path in the lockfile (e.g. "node_modules/foo/node_modules/bar")
+    pub path: String,
+
+    /// URL the tarball was fetched from (`resolved`)
+    pub resolved: Option<String>,
+
+    /// Subresource Integrity hash(es) of the tarball (`integrity`)
+    pub integrity: Option<String>,
+}
+
+/// Platform-specific packages mapped to the package listing them as an
+/// optional dependency (e.g. "@esbuild/linux-x64" -> "esbuild")
+fn platform_parents(lockfile: &NpmLockfile) -> HashMap<String, String> {
+    let platform_specific: HashSet<String> = lockfile
+        .packages
+        .iter()
+        .filter(|(path, info)| !path.is_empty() && info.is_platform_specific())
+        .map(|(path, _)| extract_package_name_from_path(path))
+        .collect();
+
+    let mut parents: HashMap<String, String> = HashMap::new();
+    for (path, info) in &lockfile.packages {
+        if path.is_empty() {
+            continue;
+        }
+        let parent = extract_package_name_from_path(path);
+        for child in info.optional_dependencies.keys() {
+            if platform_specific.contains(child) {
+                let entry = parents
+                    .entry(child.clone())
+                    .or_insert_with(|| parent.clone());
+                // Keep the choice stable when several packages share a build
+                if parent < *entry {
+                    *entry = parent.clone();
+                }
+            }
+        }
+    }
+    parents
+}
+
+/// Name of the package whose tarball ships the entry at `path`, if any
+///
+/// npm marks bundled entries with `inBundle`; older lockfiles only list them
+/// under the parent's `bundleDependencies` (an array, or `true` for all).
+fn bundled_parent(lockfile: &NpmLockfile, path: &str) -> Option<String> {
+    let (parent_path, _) = path.rsplit_once("/node_modules/")?;
+    let parent = lockfile.packages.get(parent_path)?;
+    let name = extract_package_name_from_path(path);
+
+    let in_bundle = lockfile
+        .packages
+        .get(path)
+        .is_some_and(|info| info.in_bundle.unwrap_or(false));
+    let listed = match &parent.bundle_dependencies {
+        Some(JsonValue::Bool(all)) => *all,
+        Some(JsonValue::Array(names)) => names.iter().any(|n| n.as_str() == Some(name.as_str())),
+        _ => false,
+    };
+
+    (in_bundle || listed).then(|| extract_package_name_from_path(parent_path))
+}
+
+/// Whether `path` holds a workspace member's sources rather than an install
+fn is_workspace_source(path: &str) -> bool {
+    !path.starts_with("node_modules/") && !path.contains("/node_modules/")
+}
+
+/// Entry a `link` entry points at (e.g. "node_modules/app" -> "packages/app")
+fn link_target<'l>(lockfile: &'l NpmLockfile, info: &NpmPackageInfo) -> Option<&'l NpmPackageInfo> {
+    if !info.link.unwrap_or(false) {
+        return None;
+    }
+    lockfile.packages.get(info.resolved.as_deref()?)
+}
+
+/// Name of the package installed at `path`
+///
+/// Aliases ("string-width-cjs": "npm:string-width@^4") are installed under
+/// the alias, with the real name in the entry's `name`.
+fn entry_name(path: &str, info: &NpmPackageInfo) -> String {
+    match &info.name {
+        Some(name) if !info.link.unwrap_or(false) => name.clone(),
+        _ => extract_package_name_from_path(path),
+    }
+}
+
+/// Where an entry was installed from: a link, or what its `resolved` points at
+fn entry_source(info: &NpmPackageInfo) -> PackageSource {
+    if info.link.unwrap_or(false) {
+        return PackageSource::Link;
+    }
+    info.resolved
+        .as_deref()
+        .map(PackageSource::of)
+        .unwrap_or_default()
+}
+
+/// Commit a git reference is pinned to ("git+ssh://host/repo.git#abc123" -> "abc123")
+fn git_commit(reference: &str) -> Option<&str> {
+    reference
+        .rsplit_once('#')
+        .map(|(_, commit)| commit)
+        .filter(|commit| !commit.is_empty())
+}
+
+/// Names of the packages a dependency map installs, looking through aliases
+fn real_names(deps: &HashMap<String, String>) -> impl Iterator<Item = String> + '_ {
+    real_ranges(deps).map(|(name, _)| name)
+}
+
+/// A dependency map keyed by the packages it installs, with alias ranges unwrapped
+fn real_ranges(deps: &HashMap<String, String>) -> impl Iterator<Item = (String, String)> + '_ {
+    deps.iter().map(|(name, range)| match npm_alias(range) {
+        Some((real, range)) => (real.to_string(), range.to_string()),
+        None => (name.clone(), range.clone()),
+    })
+}
+
+/// Packages a package.json declares, as (runtime, dev-only), looking
+/// through aliases
+pub fn declared_dependencies(manifest_path: &Path) -> Result<(HashSet<String>, HashSet<String>)> {
+    let manifest = read_package_json(manifest_path)?;
+    let runtime: HashSet<String> = real_names(&manifest.dependencies)
+        .chain(real_names(&manifest.optional_dependencies))
+        .collect();
+    let dev = real_names(&manifest.dev_dependencies)
+        .filter(|name| !runtime.contains(name))
+        .collect();
+    Ok((runtime, dev))
+}
+
+/// Peer dependencies a package.json declares, as (name -> range, names
+/// marked optional in `peerDependenciesMeta`)
+pub fn declared_peers(manifest_path: &Path) -> Result<(HashMap<String, String>, Vec<String>)> {
+    let manifest = read_package_json(manifest_path)?;
+    let optional = optional_peers(&manifest.peer_dependencies_meta);
+    Ok((manifest.peer_dependencies, optional))
+}
+
+/// Peers `peerDependenciesMeta` marks optional, sorted
+pub(super) fn optional_peers(meta: &HashMap<String, PeerMeta>) -> Vec<String> {
+    let mut optional: Vec<String> = meta
+        .iter()
+        .filter(|(_, meta)| meta.optional)
+        .map(|(name, _)| name.clone())
+        .collect();
+    optional.sort();
+    optional
+}
+
+/// Executables of an installed package, from its package.json `bin`, for
+/// lockfiles that don't record them
+///
+/// Reads node_modules; packages that aren't linked at the top level report none.
+pub(super) fn installed_bins(root: &Path, name: &str) -> Vec<String> {
+    let manifest_path = root.join("node_modules").join(name).join("package.json");
+    let Some(manifest) = std::fs::read_to_string(manifest_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<JsonValue>(&content).ok())
+    else {
+        return Vec::new();
+    };
+
+    match manifest.get("bin") {
+        // Scoped packages expose the unscoped part as the command
+        Some(JsonValue::String(_)) => vec![name.rsplit('/').next().unwrap_or(name).to_string()],
+        Some(JsonValue::Object(bins)) => bins.keys().cloned().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The ranges an installed package declares its dependencies with, for
+/// lockfiles that only record what they resolved to
+///
+/// Reads node_modules; packages that aren't installed report none.
+pub(super) fn installed_ranges(root: &Path, package: &Package) -> HashMap<String, String> {
+    let Some(manifest) = super::installed_dir(root, package)
+        .and_then(|dir| read_package_json(&dir.join("package.json")).ok())
+    else {
+        return HashMap::new();
+    };
+    real_ranges(&manifest.dependencies)
+        .chain(real_ranges(&manifest.optional_dependencies))
+        .collect()
+}
+
+fn read_package_json(path: &Path) -> Result<PackageJson> {
+    if !path.exists() {
+        return Ok(PackageJson::default());
+    }
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
 fn extract_package_name_from_path(path: &str) -> String {
     // Find the last "node_modules/" in the path
     let parts: Vec<&str> = path.rsplitn(2, "node_modules/").collect();
@@ -273,6 +783,7 @@ fn extract_package_name_from_path(path: &str) -> String {
 #[serde(rename_all = "camelCase")]
 struct NpmLockfile {
     #[serde(default)]
+    #[allow(dead_code)]
     lockfile_version: u32,
 
     #[serde(default)]
@@ -304,7 +815,44 @@ struct NpmPackageInfo {
     #[serde(default)]
     peer_dependencies: HashMap<String, String>,
 
+    #[serde(default)]
+    peer_dependencies_meta: HashMap<String, PeerMeta>,
+
     deprecated: Option<String>,
+
+    /// Executables installed by the package, normalized to a name -> path map
+    #[serde(default)]
+    bin: HashMap<String, String>,
+
+    #[serde(default)]
+    os: Vec<String>,
+
+    #[serde(default)]
+    cpu: Vec<String>,
+
+    #[serde(default)]
+    libc: Vec<String>,
+
+    #[serde(default)]
+    in_bundle: Option<bool>,
+
+    /// Workspace packages and `file:` directories are symlinked, not downloaded
+    #[serde(default)]
+    link: Option<bool>,
+
+    resolved: Option<String>,
+
+    integrity: Option<String>,
+
+    /// `bundleDependencies` (or the older `bundledDependencies` spelling)
+    #[serde(default, alias = "bundledDependencies")]
+    bundle_dependencies: Option<JsonValue>,
+}
+
+impl NpmPackageInfo {
+    fn is_platform_specific(&self) -> bool {
+        !self.os.is_empty() || !self.cpu.is_empty() || !self.libc.is_empty()
+    }
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -314,6 +862,16 @@ struct NpmDependency {
     #[serde(default)]
     dev: Option<bool>,
 
+    #[serde(default)]
+    optional: Option<bool>,
+
+    #[serde(default)]
+    bundled: Option<bool>,
+
+    resolved: Option<String>,
+
+    integrity: Option<String>,
+
     #[serde(default)]
     requires: HashMap<String, String>,
 
@@ -333,13 +891,24 @@ struct PackageJson {
     #[serde(default)]
     peer_dependencies: HashMap<String, String>,
 
+    #[serde(default)]
+    peer_dependencies_meta: HashMap<String, PeerMeta>,
+
     #[serde(default)]
     optional_dependencies: HashMap<String, String>,
 }
 
+/// One `peerDependenciesMeta` entry
+#[derive(Debug, Deserialize, Default)]
+pub(super) struct PeerMeta {
+    #[serde(default)]
+    optional: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::TempDir;
 
     #[test]
     fn test_extract_package_name() {
@@ -360,4 +929,369 @@ mod tests {
             "dep"
         );
     }
+
+    #[test]
+    fn test_platform_parents() {
+        let lockfile: NpmLockfile = serde_json::from_str(
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {"name": "app"},
+                    "node_modules/esbuild": {
+                        "version": "0.19.0",
+                        "optionalDependencies": {
+                            "@esbuild/linux-x64": "0.19.0",
+                            "@esbuild/darwin-arm64": "0.19.0"
+                        }
+                    },
+                    "node_modules/@esbuild/linux-x64": {
+                        "version": "0.19.0", "optional": true,
+                        "os": ["linux"], "cpu": ["x64"]
+                    },
+                    "node_modules/@esbuild/darwin-arm64": {
+                        "version": "0.19.0", "optional": true,
+                        "os": ["darwin"], "cpu": ["arm64"]
+                    },
+                    "node_modules/@swc/core-linux-x64-musl": {
+                        "version": "1.3.0", "optional": true,
+                        "os": ["linux"], "cpu": ["x64"], "libc": ["musl"]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let parents = platform_parents(&lockfile);
+        assert_eq!(parents.len(), 2);
+        assert_eq!(parents["@esbuild/linux-x64"], "esbuild");
+
+        let parser = NpmLockfileParser::new(Path::new("."), Path::new("package-lock.json"));
+        let packages = parser
+            .parse_lockfile_v3(&lockfile, &HashSet::new(), &HashSet::new())
+            .unwrap();
+        let darwin = &packages["@esbuild/darwin-arm64"];
+        assert!(darwin.is_optional);
+        assert_eq!(darwin.platform_parent.as_deref(), Some("esbuild"));
+        // Orphaned platform builds aren't attributed to anything
+        let swc = &packages["@swc/core-linux-x64-musl"];
+        assert_eq!(swc.libc, vec!["musl"]);
+        assert!(swc.platform_parent.is_none());
+    }
+
+    #[test]
+    fn test_bundled_dependencies() {
+        let lockfile: NpmLockfile = serde_json::from_str(
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {"name": "app"},
+                    "node_modules/npm": {
+                        "version": "10.2.0",
+                        "bundleDependencies": ["abbrev"],
+                        "dependencies": {"abbrev": "^2.0.0", "semver": "^7.5.0"}
+                    },
+                    "node_modules/npm/node_modules/abbrev": {"version": "2.0.0"},
+                    "node_modules/npm/node_modules/semver": {"version": "7.5.4", "inBundle": true},
+                    "node_modules/semver": {"version": "7.6.0"},
+                    "node_modules/other/node_modules/abbrev": {"version": "1.1.1"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            bundled_parent(&lockfile, "node_modules/npm/node_modules/abbrev").as_deref(),
+            Some("npm")
+        );
+        assert!(bundled_parent(&lockfile, "node_modules/other/node_modules/abbrev").is_none());
+
+        let parser = NpmLockfileParser::new(Path::new("."), Path::new("package-lock.json"));
+        let packages = parser
+            .parse_lockfile_v3(&lockfile, &HashSet::new(), &HashSet::new())
+            .unwrap();
+        // The hoisted semver wins over the copy bundled inside npm
+        assert_eq!(packages["semver"].version, "7.6.0");
+        assert!(packages["semver"].bundled_in.is_none());
+    }
+
+    #[test]
+    fn test_workspace_members() {
+        let root = TempDir::new("npm-workspace");
+        std::fs::create_dir_all(root.join("packages/app")).unwrap();
+        std::fs::create_dir_all(root.join("packages/ui")).unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*"], "devDependencies": {"typescript": "^5.0.0"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("packages/app/package.json"),
+            r#"{"name": "app", "dependencies": {"ui": "*", "lodash": "^4.0.0"},
+                "devDependencies": {"vitest": "^1.0.0"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("packages/ui/package.json"),
+            r#"{"name": "ui", "dependencies": {"react": "^18.0.0"},
+                "devDependencies": {"lodash": "^4.0.0"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("package-lock.json"),
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {"workspaces": ["packages/*"]},
+                    "packages/app": {"name": "app", "version": "1.0.0",
+                        "dependencies": {"ui": "*", "lodash": "^4.0.0"}},
+                    "packages/ui": {"name": "ui", "version": "0.3.0",
+                        "dependencies": {"react": "^18.0.0"}},
+                    "packages/ui/node_modules/react": {"version": "18.2.0"},
+                    "node_modules/app": {"resolved": "packages/app", "link": true},
+                    "node_modules/ui": {"resolved": "packages/ui", "link": true},
+                    "node_modules/lodash": {"version": "4.17.21"},
+                    "node_modules/vitest": {"version": "1.6.0", "dev": true},
+                    "node_modules/typescript": {"version": "5.4.0", "dev": true}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let lockfile_path = root.join("package-lock.json");
+        let packages = NpmLockfileParser::new(&root, &lockfile_path)
+            .parse()
+            .unwrap();
+
+        // Declared by a member, not the root
+        assert!(packages["react"].is_direct && !packages["react"].is_dev);
+        assert!(packages["vitest"].is_direct && packages["vitest"].is_dev);
+        assert!(packages["typescript"].is_direct && packages["typescript"].is_dev);
+        // A dev dependency of ui that app needs at runtime
+        assert!(packages["lodash"].is_direct && !packages["lodash"].is_dev);
+
+        // Members come from their links, with their sources' version and deps
+        let ui = &packages["ui"];
+        assert!(ui.is_direct);
+        assert_eq!(ui.version, "0.3.0");
+        assert_eq!(ui.dependencies, vec!["react"]);
+        assert!(!packages["app"].is_direct);
+        assert!(!packages.contains_key("packages"));
+    }
+
+    #[test]
+    fn test_git_and_local_sources() {
+        let root = TempDir::new("npm-sources");
+        std::fs::write(
+            root.join("package-lock.json"),
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {},
+                    "node_modules/forked": {"version": "2.1.0",
+                        "resolved": "git+ssh://git@github.com/acme/forked.git#4f2a9c1"},
+                    "node_modules/bare": {
+                        "resolved": "git+https://github.com/acme/bare.git#9b8e7d6"},
+                    "node_modules/vendored": {"version": "0.1.0",
+                        "resolved": "file:vendor/vendored-0.1.0.tgz"},
+                    "node_modules/local": {"resolved": "../local", "link": true},
+                    "../local": {"name": "local", "version": "0.0.3"},
+                    "node_modules/lodash": {"version": "4.17.21",
+                        "resolved": "https://registry.npmjs.org/lodash/-/lodash-4.17.21.tgz"}
+                }
+            }"#,
+        )
+        .unwrap();
+        let v1_root = root.join("v1");
+        std::fs::create_dir_all(&v1_root).unwrap();
+        std::fs::write(
+            v1_root.join("package-lock.json"),
+            r#"{
+                "lockfileVersion": 1,
+                "dependencies": {
+                    "forked": {"version": "git+https://github.com/acme/forked.git#4f2a9c1"},
+                    "local": {"version": "file:../local"},
+                    "rimraf": {"version": "3.0.2"}
+                }
+            }"#,
+        )
+        .unwrap();
+        // v1 lockfiles leave `bin` to the installed manifest
+        std::fs::create_dir_all(v1_root.join("node_modules/rimraf")).unwrap();
+        std::fs::write(
+            v1_root.join("node_modules/rimraf/package.json"),
+            r#"{"name": "rimraf", "bin": "bin.js"}"#,
+        )
+        .unwrap();
+
+        let lockfile_path = root.join("package-lock.json");
+        let packages = NpmLockfileParser::new(&root, &lockfile_path)
+            .parse()
+            .unwrap();
+        let v1_lockfile_path = v1_root.join("package-lock.json");
+        let v1_packages = NpmLockfileParser::new(&v1_root, &v1_lockfile_path)
+            .parse()
+            .unwrap();
+        let mut resolutions: Vec<(String, Option<String>)> =
+            NpmLockfileParser::new(&root, &lockfile_path)
+                .parse_resolutions()
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.path, entry.resolved))
+                .collect();
+        resolutions.sort();
+        let mut v1_resolutions: Vec<(String, Option<String>)> =
+            NpmLockfileParser::new(&v1_root, &v1_lockfile_path)
+                .parse_resolutions()
+                .unwrap()
+                .into_iter()
+                .map(|entry| (entry.path, entry.resolved))
+                .collect();
+        v1_resolutions.sort();
+
+        // The link and the member sources it points at aren't downloaded
+        let paths: Vec<&str> = resolutions.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(
+            paths,
+            [
+                "node_modules/bare",
+                "node_modules/forked",
+                "node_modules/lodash",
+                "node_modules/vendored"
+            ]
+        );
+        assert_eq!(
+            v1_resolutions,
+            vec![
+                (
+                    "node_modules/forked".to_string(),
+                    Some("git+https://github.com/acme/forked.git#4f2a9c1".to_string())
+                ),
+                (
+                    "node_modules/local".to_string(),
+                    Some("file:../local".to_string())
+                ),
+                ("node_modules/rimraf".to_string(), None),
+            ]
+        );
+
+        assert_eq!(packages["forked"].source, PackageSource::Git);
+        assert_eq!(packages["forked"].version, "2.1.0");
+        assert_eq!(packages["bare"].version, "9b8e7d6");
+        assert_eq!(packages["vendored"].source, PackageSource::File);
+        assert_eq!(packages["local"].source, PackageSource::Link);
+        assert_eq!(packages["local"].label(), "local@0.0.3 (link)");
+        assert!(packages["lodash"].is_from_registry());
+
+        assert_eq!(v1_packages["forked"].source, PackageSource::Git);
+        assert_eq!(v1_packages["forked"].version, "4f2a9c1");
+        assert_eq!(v1_packages["local"].source, PackageSource::File);
+        assert_eq!(v1_packages["local"].label(), "local (file)");
+        assert_eq!(v1_packages["rimraf"].bins, vec!["rimraf"]);
+        assert!(v1_packages["forked"].bins.is_empty());
+    }
+
+    #[test]
+    fn test_aliases() {
+        let root = TempDir::new("npm-alias");
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"dependencies": {"cliui": "^8.0.0", "sw": "npm:string-width@^4.2.0"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("package-lock.json"),
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {"dependencies": {"cliui": "^8.0.0", "sw": "npm:string-width@^4.2.0"}},
+                    "node_modules/cliui": {"version": "8.0.1",
+                        "dependencies": {"string-width-cjs": "npm:string-width@^4.2.0"}},
+                    "node_modules/string-width-cjs": {"name": "string-width", "version": "4.2.3"},
+                    "node_modules/sw": {"name": "string-width", "version": "4.2.3"},
+                    "node_modules/cliui/node_modules/string-width": {"version": "5.1.2"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let lockfile_path = root.join("package-lock.json");
+        let parser = NpmLockfileParser::new(&root, &lockfile_path);
+        let packages = parser.parse().unwrap();
+        let duplicates = parser.parse_for_duplicates().unwrap();
+
+        // Aliases are installed under their real name
+        assert!(packages["string-width"].is_direct);
+        assert!(!packages.contains_key("sw"));
+        assert_eq!(packages["cliui"].dependencies, vec!["string-width"]);
+        assert_eq!(
+            packages["cliui"].dependency_ranges["string-width"],
+            "^4.2.0"
+        );
+
+        let mut versions: Vec<&str> = duplicates["string-width"]
+            .iter()
+            .map(|v| v.version.as_str())
+            .collect();
+        versions.sort();
+        assert_eq!(versions, vec!["4.2.3", "5.1.2"]);
+        let aliased = duplicates["string-width"]
+            .iter()
+            .find(|v| v.version == "4.2.3")
+            .unwrap();
+        // Reached through both aliases
+        assert!(aliased.dependents.contains(&"root@".to_string()));
+        assert!(aliased.dependents.contains(&"cliui@8.0.1".to_string()));
+        assert_eq!(aliased.requirements["cliui@8.0.1"], "^4.2.0");
+    }
+
+    #[test]
+    fn test_install_divergence() {
+        let root = TempDir::new("npm-hidden");
+        std::fs::create_dir_all(root.join("node_modules")).unwrap();
+        let lockfile_path = root.join("package-lock.json");
+        std::fs::write(
+            &lockfile_path,
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "": {"name": "app", "dependencies": {"lodash": "^4.17.0", "chalk": "^5.0.0"}},
+                    "node_modules/lodash": {"version": "4.17.21"},
+                    "node_modules/chalk": {"version": "5.3.0"},
+                    "node_modules/fsevents": {"version": "2.3.3", "optional": true},
+                    "node_modules/react": {"version": "18.2.0"}
+                }
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join(HIDDEN_LOCKFILE),
+            r#"{
+                "lockfileVersion": 3,
+                "packages": {
+                    "node_modules/lodash": {"version": "4.17.20"},
+                    "node_modules/chalk": {"version": "5.3.0"},
+                    "node_modules/left-pad": {"version": "1.3.0"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let divergence = NpmLockfileParser::new(&root, &lockfile_path)
+            .install_divergence()
+            .unwrap();
+
+        // The optional fsevents isn't installed on every platform
+        let found: Vec<(&str, Option<&str>, Option<&str>)> = divergence
+            .iter()
+            .map(|d| (d.name.as_str(), d.locked.as_deref(), d.installed.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("left-pad", None, Some("1.3.0")),
+                ("lodash", Some("4.17.21"), Some("4.17.20")),
+                ("react", Some("18.2.0"), None),
+            ]
+        );
+    }
 }
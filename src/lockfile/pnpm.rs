@@ -0,0 +1,992 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::resolve::normalize;
+use crate::catalog::catalog_name;
+use crate::lockfile::npm::{installed_bins, installed_ranges, optional_peers, PeerMeta};
+use crate::lockfile::CargoPackageInfo;
+use crate::types::{Package, PackageSource, PeerSplit, PeerVariant};
+
+/// Parser for pnpm's pnpm-lock.yaml
+pub struct PnpmLockfileParser<'a> {
+    root: &'a Path,
+    lockfile_path: &'a Path,
+}
+
+/// One installed copy of a package: pnpm keeps a separate instance for every
+/// combination of peer dependencies it's resolved against
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Instance {
+    name: String,
+    version: String,
+    /// Resolved peers, e.g. ["react@18.2.0"]; empty for peer-free packages
+    peers: Vec<String>,
+}
+
+impl<'a> PnpmLockfileParser<'a> {
+    pub fn new(root: &'a Path, lockfile_path: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile_path,
+        }
+    }
+
+    /// Parse every installed package (lockfile v6 and v9)
+    ///
+    /// Dependencies declared by any importer (workspace project) are direct;
+    /// they're dev dependencies when no importer needs them at runtime.
+    /// Packages only reachable through dev dependencies are dev packages.
+    /// Workspace projects linked with `link:` are included under their own
+    /// names, so importing a sibling project isn't reported as phantom.
+    pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let lockfile = self.load()?;
+
+        let mut direct_prod: HashSet<&str> = HashSet::new();
+        let mut direct_dev: HashSet<&str> = HashSet::new();
+        let mut direct_optional: HashSet<&str> = HashSet::new();
+        // Version each name resolves to in the importers, root first
+        let mut direct_versions: HashMap<&str, String> = HashMap::new();
+        let mut prod_roots: Vec<Instance> = Vec::new();
+        let mut links: Vec<(&str, &str, &str)> = Vec::new();
+
+        for (importer_path, importer) in lockfile.importer_entries() {
+            let groups = [
+                (&importer.dependencies, false),
+                (&importer.optional_dependencies, false),
+                (&importer.dev_dependencies, true),
+            ];
+            for (deps, is_dev) in groups {
+                for (name, dep) in deps {
+                    let reference = dep.version();
+                    if is_dev {
+                        direct_dev.insert(name);
+                    } else {
+                        direct_prod.insert(name);
+                    }
+                    if let Some(link) = reference.strip_prefix("link:") {
+                        links.push((importer_path, name, link));
+                        continue;
+                    }
+                    let Some(target) = resolve_reference(name, reference) else {
+                        continue;
+                    };
+                    if !is_dev {
+                        prod_roots.push(target.clone());
+                    }
+                    if importer_path == "." || !direct_versions.contains_key(name.as_str()) {
+                        direct_versions.insert(name, target.version.clone());
+                    }
+                }
+            }
+            direct_optional.extend(importer.optional_dependencies.keys().map(|k| k.as_str()));
+        }
+
+        // Edges between instances, and the instances runtime code can reach
+        let mut edges: HashMap<Instance, Vec<Instance>> = HashMap::new();
+        let mut keys: Vec<(&str, Instance)> = Vec::new();
+        for (key, info) in lockfile.instance_entries() {
+            let Some(instance) = parse_instance_key(key) else {
+                continue;
+            };
+            let children = info
+                .dependencies
+                .iter()
+                .chain(info.optional_dependencies.iter())
+                .filter_map(|(name, reference)| resolve_reference(name, reference))
+                .collect();
+            edges.insert(instance.clone(), children);
+            keys.push((key.as_str(), instance));
+        }
+        keys.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut prod: HashSet<Instance> = HashSet::new();
+        let mut queue: VecDeque<Instance> = prod_roots.into();
+        while let Some(instance) = queue.pop_front() {
+            if prod.insert(instance.clone()) {
+                queue.extend(edges.get(&instance).into_iter().flatten().cloned());
+            }
+        }
+
+        // Merge the peer instances of each version
+        let mut versions: BTreeMap<(&str, &str), VersionEntry> = BTreeMap::new();
+        for (key, instance) in &keys {
+            let info = &lockfile.instance_entries()[*key];
+            let entry = versions
+                .entry((instance.name.as_str(), instance.version.as_str()))
+                .or_insert_with(|| VersionEntry {
+                    metadata: lockfile.metadata(key),
+                    dependencies: BTreeSet::new(),
+                    optional: true,
+                    prod: false,
+                });
+            entry.dependencies.extend(
+                info.dependencies
+                    .keys()
+                    .chain(info.optional_dependencies.keys())
+                    .map(|k| k.as_str()),
+            );
+            let metadata_optional = entry.metadata.is_some_and(|m| m.optional);
+            entry.optional &= info.optional || metadata_optional;
+            entry.prod |= prod.contains(instance);
+        }
+
+        // One package per name: the version the importers use, else the newest
+        let mut chosen: HashMap<&str, (&str, &VersionEntry)> = HashMap::new();
+        for ((name, version), entry) in &versions {
+            let replace = match chosen.get(name) {
+                None => true,
+                Some((current, _)) => {
+                    let wanted = direct_versions.get(*name).map(|v| v.as_str());
+                    if wanted == Some(*current) {
+                        false
+                    } else if wanted == Some(*version) {
+                        true
+                    } else {
+                        is_newer(version, current)
+                    }
+                }
+            };
+            if replace {
+                chosen.insert(name, (version, entry));
+            }
+        }
+
+        let platform_specific: HashSet<&str> = chosen
+            .iter()
+            .filter(|(_, (_, entry))| entry.metadata.is_some_and(|m| m.is_platform_specific()))
+            .map(|(name, _)| *name)
+            .collect();
+        let mut platform_parents: HashMap<&str, &str> = HashMap::new();
+        for (key, instance) in &keys {
+            let info = &lockfile.instance_entries()[*key];
+            for child in info.optional_dependencies.keys() {
+                if platform_specific.contains(child.as_str()) {
+                    let parent = platform_parents
+                        .entry(child.as_str())
+                        .or_insert(instance.name.as_str());
+                    // Keep the choice stable when several packages share a build
+                    if instance.name.as_str() < *parent {
+                        *parent = instance.name.as_str();
+                    }
+                }
+            }
+        }
+
+        let mut packages = HashMap::new();
+        for (name, (version, entry)) in chosen {
+            let is_direct = direct_prod.contains(name) || direct_dev.contains(name);
+            let is_dev = if is_direct {
+                !direct_prod.contains(name)
+            } else {
+                !entry.prod
+            };
+            let metadata = entry.metadata;
+
+            let mut package = Package {
+                name: name.to_string(),
+                version: version.to_string(),
+                is_direct,
+                is_dev,
+                is_build: false,
+                dependencies: entry.dependencies.iter().map(|d| d.to_string()).collect(),
+                dependency_ranges: HashMap::new(),
+                peer_dependencies: metadata
+                    .map(|m| m.peer_dependencies.clone())
+                    .unwrap_or_default(),
+                optional_peers: metadata
+                    .map(|m| optional_peers(&m.peer_dependencies_meta))
+                    .unwrap_or_default(),
+                deprecated: metadata.and_then(|m| m.deprecated.clone()),
+                // pnpm only records `hasBin`
+                bins: if metadata.is_some_and(|m| m.has_bin) {
+                    installed_bins(self.root, name)
+                } else {
+                    Vec::new()
+                },
+                is_optional: entry.optional || direct_optional.contains(name),
+                os: metadata.map(|m| m.os.clone()).unwrap_or_default(),
+                cpu: metadata.map(|m| m.cpu.clone()).unwrap_or_default(),
+                libc: metadata.map(|m| m.libc.clone()).unwrap_or_default(),
+                platform_parent: platform_parents.get(name).map(|p| p.to_string()),
+                bundled_in: None,
+                other_target: None,
+                source: PackageSource::of(version),
+                purl: None,
+                cpe: None,
+            };
+            // pnpm-lock.yaml records resolved versions, not the ranges asked for
+            package.dependency_ranges = installed_ranges(self.root, &package);
+            packages.insert(name.to_string(), package);
+        }
+
+        for (importer_path, name, link) in links {
+            if packages.contains_key(name) {
+                continue;
+            }
+            let target = normalize(&self.root.join(importer_path).join(link));
+            let target_key = target
+                .strip_prefix(normalize(self.root))
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_default();
+            let importer = lockfile.importers.get(&target_key);
+            let declared = importer
+                .into_iter()
+                .flat_map(|importer| {
+                    importer
+                        .dependencies
+                        .iter()
+                        .chain(importer.optional_dependencies.iter())
+                })
+                .collect::<Vec<_>>();
+            let dependencies = declared.iter().map(|(dep, _)| dep.to_string()).collect();
+
+            let mut package = Package::new(name, manifest_version(&target))
+                .direct()
+                .with_dependencies(dependencies);
+            package.dependency_ranges = declared
+                .iter()
+                .filter_map(|(dep, reference)| {
+                    let range = lockfile.specifier_range(dep, reference.specifier()?)?;
+                    Some((dep.to_string(), range.to_string()))
+                })
+                .collect();
+            package.is_dev = !direct_prod.contains(name);
+            packages.insert(name.to_string(), package);
+        }
+
+        Ok(packages)
+    }
+
+    fn load(&self) -> Result<PnpmLockfile> {
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+
+        serde_yaml::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse pnpm-lock.yaml")
+    }
+
+    /// Distinct versions of every package with the packages depending on them
+    ///
+    /// Peer-dependency instances of the same version are merged; see
+    /// [`Self::parse_peer_splits`] for those.
+    pub fn parse_for_duplicates(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        let lockfile = self.load()?;
+        let dependents = instance_dependents(&lockfile);
+
+        let mut by_name: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+        for instance in lockfile.instances() {
+            let versions = by_name.entry(instance.name.clone()).or_default();
+            let pos = match versions.iter().position(|v| v.version == instance.version) {
+                Some(pos) => pos,
+                None => {
+                    versions.push(CargoPackageInfo {
+                        version: instance.version.clone(),
+                        dependents: Vec::new(),
+                        is_path_dep: is_local_reference(&instance.version),
+                        // pnpm-lock.yaml keeps only the resolved versions
+                        requirements: HashMap::new(),
+                    });
+                    versions.len() - 1
+                }
+            };
+
+            for dependent in dependents.get(&instance).into_iter().flatten() {
+                if !versions[pos].dependents.contains(dependent) {
+                    versions[pos].dependents.push(dependent.clone());
+                }
+            }
+        }
+
+        for versions in by_name.values_mut() {
+            for version in versions.iter_mut() {
+                version.dependents.sort();
+            }
+        }
+
+        Ok(by_name)
+    }
+
+    /// Package versions installed more than once because their dependents
+    /// resolve different peer dependencies
+    pub fn parse_peer_splits(&self) -> Result<Vec<PeerSplit>> {
+        let lockfile = self.load()?;
+        let dependents = instance_dependents(&lockfile);
+
+        let mut by_version: BTreeMap<(String, String), Vec<Instance>> = BTreeMap::new();
+        for instance in lockfile.instances() {
+            by_version
+                .entry((instance.name.clone(), instance.version.clone()))
+                .or_default()
+                .push(instance);
+        }
+
+        let mut splits = Vec::new();
+        for ((name, version), mut instances) in by_version {
+            if instances.len() < 2 {
+                continue;
+            }
+            instances.sort_by(|a, b| a.peers.cmp(&b.peers));
+
+            let differing_peers = differing_peers(&instances);
+            let variants = instances
+                .into_iter()
+                .map(|instance| {
+                    let mut dependents = dependents.get(&instance).cloned().unwrap_or_default();
+                    dependents.sort();
+                    dependents.dedup();
+                    PeerVariant {
+                        peers: instance.peers,
+                        dependents,
+                    }
+                })
+                .collect();
+
+            splits.push(PeerSplit {
+                name,
+                version,
+                variants,
+                differing_peers,
+            });
+        }
+
+        Ok(splits)
+    }
+}
+
+/// A package version merged across its peer-dependency instances
+struct VersionEntry<'a> {
+    metadata: Option<&'a PnpmPackageInfo>,
+    dependencies: BTreeSet<&'a str>,
+    /// Every instance is only installed as an optional dependency
+    optional: bool,
+    /// Some instance is reachable from a runtime dependency of an importer
+    prod: bool,
+}
+
+fn is_newer(version: &str, than: &str) -> bool {
+    match (
+        semver::Version::parse(version),
+        semver::Version::parse(than),
+    ) {
+        (Ok(a), Ok(b)) => a > b,
+        _ => version > than,
+    }
+}
+
+/// Version from a linked workspace project's package.json
+fn manifest_version(dir: &Path) -> String {
+    std::fs::read_to_string(dir.join("package.json"))
+        .ok()
+        .and_then(|content| serde_json::from_str::<JsonValue>(&content).ok())
+        .and_then(|manifest| manifest.get("version")?.as_str().map(String::from))
+        .unwrap_or_else(|| "0.0.0".to_string())
+}
+
+/// Peer names resolved to different versions across the instances
+fn differing_peers(instances: &[Instance]) -> Vec<String> {
+    let mut versions: BTreeMap<&str, Vec<Option<&str>>> = BTreeMap::new();
+    for (i, instance) in instances.iter().enumerate() {
+        for peer in &instance.peers {
+            let (name, version) = split_name_version(peer).unwrap_or((peer.as_str(), ""));
+            let slots = versions
+                .entry(name)
+                .or_insert_with(|| vec![None; instances.len()]);
+            slots[i] = Some(version);
+        }
+    }
+
+    versions
+        .into_iter()
+        .filter(|(_, slots)| slots.iter().any(|s| *s != slots[0]))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// Dependents of each instance as "name@version", with "root" (or the
+/// importer path in a workspace) for the project itself
+fn instance_dependents(lockfile: &PnpmLockfile) -> HashMap<Instance, Vec<String>> {
+    let mut dependents: HashMap<Instance, Vec<String>> = HashMap::new();
+
+    for (importer, deps) in lockfile.importer_dependencies() {
+        let label = if importer == "." {
+            "root".to_string()
+        } else {
+            importer.to_string()
+        };
+        for (name, reference) in deps {
+            if let Some(target) = resolve_reference(name, reference) {
+                dependents.entry(target).or_default().push(label.clone());
+            }
+        }
+    }
+
+    for (key, info) in lockfile.instance_entries() {
+        let Some(parent) = parse_instance_key(key) else {
+            continue;
+        };
+        let label = format!("{}@{}", parent.name, parent.version);
+        for (name, reference) in info
+            .dependencies
+            .iter()
+            .chain(info.optional_dependencies.iter())
+        {
+            if let Some(target) = resolve_reference(name, reference) {
+                dependents.entry(target).or_default().push(label.clone());
+            }
+        }
+    }
+
+    dependents
+}
+
+/// Whether a version or reference points at a directory (`link:`, `file:`)
+fn is_local_reference(reference: &str) -> bool {
+    reference.starts_with("link:") || reference.starts_with("file:")
+}
+
+/// Resolve a dependency reference ("1.0.0(react@18.2.0)", or a full key
+/// like "/string-width@4.2.3" for aliases) to the instance it points at
+fn resolve_reference(name: &str, reference: &str) -> Option<Instance> {
+    if is_local_reference(reference) {
+        return None;
+    }
+
+    // An alias points at another package by its full key
+    if !reference.starts_with(|c: char| c.is_ascii_digit()) {
+        return parse_instance_key(reference);
+    }
+
+    let (version, peers) = strip_peers(reference);
+    // v5: "1.0.0_react@18.2.0"
+    let (version, v5_peers) = split_v5_peers(version);
+    Some(Instance {
+        name: name.to_string(),
+        version: version.to_string(),
+        peers: if peers.is_empty() { v5_peers } else { peers },
+    })
+}
+
+/// Parse a pnpm package key in any of the formats pnpm has used:
+///
+/// - v9: "react-dom@18.2.0(react@18.2.0)"
+/// - v6: "/react-dom@18.2.0(react@18.2.0)"
+/// - v5: "/react-dom/18.2.0_react@18.2.0"
+fn parse_instance_key(key: &str) -> Option<Instance> {
+    let key = key.trim_start_matches('/');
+
+    // v5 separates name and version with the last slash (before any peers)
+    let base = key.split('_').next().unwrap_or(key);
+    if let Some((name, version)) = base.rsplit_once('/') {
+        if version.starts_with(|c: char| c.is_ascii_digit()) {
+            let (_, peers) = split_v5_peers(key);
+            return Some(Instance {
+                name: name.to_string(),
+                version: version.to_string(),
+                peers,
+            });
+        }
+    }
+
+    let (head, peers) = strip_peers(key);
+    let (name, version) = split_name_version(head)?;
+    Some(Instance {
+        name: name.to_string(),
+        version: version.to_string(),
+        peers,
+    })
+}
+
+/// Split "foo@1.0.0" or "@scope/foo@1.0.0" into name and version
+fn split_name_version(spec: &str) -> Option<(&str, &str)> {
+    match spec.rfind('@') {
+        Some(0) | None => None,
+        Some(i) => Some((&spec[..i], &spec[i + 1..])),
+    }
+}
+
+/// Separate the peer suffix of a key: "foo@1.0.0(a@1)(b@2(a@1))" ->
+/// ("foo@1.0.0", ["a@1", "b@2(a@1)"])
+fn strip_peers(key: &str) -> (&str, Vec<String>) {
+    let Some(start) = key.find('(') else {
+        return (key, Vec::new());
+    };
+
+    let mut peers = Vec::new();
+    let mut depth = 0;
+    let mut current = String::new();
+    for c in key[start..].chars() {
+        match c {
+            '(' => {
+                if depth > 0 {
+                    current.push(c);
+                }
+                depth += 1;
+            }
+            ')' => {
+                depth -= 1;
+                if depth > 0 {
+                    current.push(c);
+                } else {
+                    peers.push(std::mem::take(&mut current));
+                }
+            }
+            _ if depth > 0 => current.push(c),
+            _ => {}
+        }
+    }
+
+    (&key[..start], peers)
+}
+
+/// "18.2.0_react-dom@18.2.0+react@18.2.0" -> ("18.2.0", ["react-dom@18.2.0", "react@18.2.0"])
+fn split_v5_peers(version: &str) -> (&str, Vec<String>) {
+    match version.split_once('_') {
+        Some((version, peers)) => (version, peers.split('+').map(|p| p.to_string()).collect()),
+        None => (version, Vec::new()),
+    }
+}
+
+// Serde types for pnpm-lock.yaml
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PnpmLockfile {
+    /// Workspace projects (lockfile v6+ always has at least ".")
+    #[serde(default)]
+    importers: HashMap<String, PnpmImporter>,
+
+    /// Root project dependencies of single-project lockfiles before v9
+    #[serde(flatten)]
+    root: PnpmImporter,
+
+    #[serde(default)]
+    packages: HashMap<String, PnpmPackageInfo>,
+
+    /// v9 moved per-instance dependency info here, keeping `packages` for
+    /// per-version metadata
+    #[serde(default)]
+    snapshots: HashMap<String, PnpmPackageInfo>,
+
+    /// Catalog name -> package -> the range `catalog:` specifiers stand for
+    #[serde(default)]
+    catalogs: HashMap<String, HashMap<String, PnpmCatalogEntry>>,
+}
+
+impl PnpmLockfile {
+    /// Entries describing installed instances and their dependencies
+    fn instance_entries(&self) -> &HashMap<String, PnpmPackageInfo> {
+        if self.snapshots.is_empty() {
+            &self.packages
+        } else {
+            &self.snapshots
+        }
+    }
+
+    fn instances(&self) -> Vec<Instance> {
+        let mut instances: Vec<Instance> = self
+            .instance_entries()
+            .keys()
+            .filter_map(|key| parse_instance_key(key))
+            .collect();
+        instances
+            .sort_by(|a, b| (&a.name, &a.version, &a.peers).cmp(&(&b.name, &b.version, &b.peers)));
+        instances.dedup();
+        instances
+    }
+
+    /// Package metadata of an instance: v9 keeps it in `packages` under the
+    /// key without peers, earlier versions in the instance entry itself
+    fn metadata(&self, key: &str) -> Option<&PnpmPackageInfo> {
+        if self.snapshots.is_empty() {
+            self.packages.get(key)
+        } else {
+            self.packages.get(strip_peers(key).0)
+        }
+    }
+
+    /// Workspace projects by path, sorted, with "." for the root
+    fn importer_entries(&self) -> Vec<(&str, &PnpmImporter)> {
+        if self.importers.is_empty() {
+            return vec![(".", &self.root)];
+        }
+
+        let mut importers: Vec<(&str, &PnpmImporter)> = self
+            .importers
+            .iter()
+            .map(|(k, v)| (k.as_str(), v))
+            .collect();
+        importers.sort_by_key(|(path, _)| *path);
+        importers
+    }
+
+    /// The range an importer's specifier declares, looking `catalog:`
+    /// specifiers up in the catalogs
+    fn specifier_range<'s>(&'s self, name: &str, specifier: &'s str) -> Option<&'s str> {
+        match catalog_name(specifier) {
+            Some(catalog) => Some(self.catalogs.get(catalog)?.get(name)?.specifier.as_str()),
+            None => Some(specifier),
+        }
+    }
+
+    /// Each importer with its declared dependencies as name -> reference
+    fn importer_dependencies(&self) -> Vec<(&str, Vec<(&str, &str)>)> {
+        self.importer_entries()
+            .into_iter()
+            .map(|(path, importer)| {
+                let deps = importer
+                    .dependencies
+                    .iter()
+                    .chain(importer.dev_dependencies.iter())
+                    .chain(importer.optional_dependencies.iter())
+                    .map(|(name, dep)| (name.as_str(), dep.version()))
+                    .collect();
+                (path, deps)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PnpmImporter {
+    #[serde(default)]
+    dependencies: HashMap<String, PnpmDependencyRef>,
+
+    #[serde(default)]
+    dev_dependencies: HashMap<String, PnpmDependencyRef>,
+
+    #[serde(default)]
+    optional_dependencies: HashMap<String, PnpmDependencyRef>,
+}
+
+/// A dependency reference: a bare version (v5) or `{specifier, version}` (v6+)
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PnpmDependencyRef {
+    Version(String),
+    Spec {
+        #[serde(default)]
+        specifier: Option<String>,
+        version: String,
+    },
+}
+
+impl PnpmDependencyRef {
+    fn version(&self) -> &str {
+        match self {
+            PnpmDependencyRef::Version(version) => version,
+            PnpmDependencyRef::Spec { version, .. } => version,
+        }
+    }
+
+    /// The range package.json declares, e.g. "^18.2.0" or "catalog:"
+    fn specifier(&self) -> Option<&str> {
+        match self {
+            PnpmDependencyRef::Version(_) => None,
+            PnpmDependencyRef::Spec { specifier, .. } => specifier.as_deref(),
+        }
+    }
+}
+
+/// An entry of the v9 `catalogs` section
+#[derive(Debug, Deserialize)]
+struct PnpmCatalogEntry {
+    specifier: String,
+}
+
+/// An entry of `packages` or `snapshots`; v9 splits these fields between
+/// the two, earlier versions keep them all in `packages`
+#[derive(Debug, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct PnpmPackageInfo {
+    #[serde(default)]
+    dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    optional_dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    peer_dependencies: HashMap<String, String>,
+
+    #[serde(default)]
+    peer_dependencies_meta: HashMap<String, PeerMeta>,
+
+    /// Only installed as an optional dependency
+    #[serde(default)]
+    optional: bool,
+
+    #[serde(default)]
+    deprecated: Option<String>,
+
+    #[serde(default)]
+    has_bin: bool,
+
+    #[serde(default)]
+    os: Vec<String>,
+
+    #[serde(default)]
+    cpu: Vec<String>,
+
+    #[serde(default)]
+    libc: Vec<String>,
+}
+
+impl PnpmPackageInfo {
+    fn is_platform_specific(&self) -> bool {
+        !self.os.is_empty() || !self.cpu.is_empty() || !self.libc.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_parse_instance_key() {
+        let instance = parse_instance_key("/@tanstack/react-query@5.0.0(react@18.2.0)").unwrap();
+        assert_eq!(instance.name, "@tanstack/react-query");
+        assert_eq!(instance.version, "5.0.0");
+        assert_eq!(instance.peers, vec!["react@18.2.0"]);
+
+        let instance =
+            parse_instance_key("react-dom@18.2.0(react@18.2.0)(scheduler@0.23.0(react@18.2.0))")
+                .unwrap();
+        assert_eq!(
+            instance.peers,
+            vec!["react@18.2.0", "scheduler@0.23.0(react@18.2.0)"]
+        );
+
+        let instance =
+            parse_instance_key("/styled-jsx/5.0.0_react-dom@18.2.0+react@18.2.0").unwrap();
+        assert_eq!(instance.name, "styled-jsx");
+        assert_eq!(instance.version, "5.0.0");
+        assert_eq!(instance.peers, vec!["react-dom@18.2.0", "react@18.2.0"]);
+    }
+
+    #[test]
+    fn test_peer_splits() {
+        let root = TempDir::new("pnpm");
+        let path = root.join("pnpm-lock.yaml");
+        std::fs::write(
+            &path,
+            r#"
+lockfileVersion: '9.0'
+importers:
+  .:
+    dependencies:
+      react-query:
+        specifier: ^3.39.0
+        version: 3.39.3(react@18.2.0)
+      legacy-widget:
+        specifier: ^1.0.0
+        version: 1.0.0
+      react:
+        specifier: ^18.2.0
+        version: 18.2.0
+packages:
+  react-query@3.39.3: {}
+  react@18.2.0: {}
+  react@17.0.2: {}
+  legacy-widget@1.0.0: {}
+snapshots:
+  react-query@3.39.3(react@18.2.0): {}
+  react-query@3.39.3(react@17.0.2): {}
+  react@18.2.0: {}
+  react@17.0.2: {}
+  legacy-widget@1.0.0:
+    dependencies:
+      react: 17.0.2
+      react-query: 3.39.3(react@17.0.2)
+"#,
+        )
+        .unwrap();
+
+        let parser = PnpmLockfileParser::new(Path::new("."), &path);
+        let splits = parser.parse_peer_splits().unwrap();
+        let by_name = parser.parse_for_duplicates().unwrap();
+
+        assert_eq!(splits.len(), 1);
+        let split = &splits[0];
+        assert_eq!(split.name, "react-query");
+        assert_eq!(split.differing_peers, vec!["react"]);
+        assert_eq!(split.variants[0].peers, vec!["react@17.0.2"]);
+        assert_eq!(split.variants[0].dependents, vec!["legacy-widget@1.0.0"]);
+        assert_eq!(split.variants[1].dependents, vec!["root"]);
+
+        // Peer instances collapse to one version; react really is duplicated
+        assert_eq!(by_name["react-query"].len(), 1);
+        assert_eq!(by_name["react"].len(), 2);
+    }
+
+    #[test]
+    fn test_parse_v6() {
+        let root = TempDir::new("pnpm-v6");
+        let path = root.join("pnpm-lock.yaml");
+        std::fs::write(
+            &path,
+            r#"
+lockfileVersion: '6.0'
+dependencies:
+  express:
+    specifier: ^4.18.0
+    version: 4.18.2
+devDependencies:
+  vitest:
+    specifier: ^1.0.0
+    version: 1.0.0
+packages:
+  /express@4.18.2:
+    resolution: {integrity: sha512-abc}
+    dependencies:
+      debug: 2.6.9
+    dev: false
+  /debug@2.6.9:
+    resolution: {integrity: sha512-def}
+    dev: false
+  /vitest@1.0.0:
+    resolution: {integrity: sha512-ghi}
+    hasBin: true
+    dependencies:
+      tinypool: 0.8.0
+    dev: true
+  /tinypool@0.8.0:
+    resolution: {integrity: sha512-jkl}
+    deprecated: use something else
+    dev: true
+"#,
+        )
+        .unwrap();
+
+        let packages = PnpmLockfileParser::new(Path::new("."), &path)
+            .parse()
+            .unwrap();
+
+        assert_eq!(packages.len(), 4);
+        let express = &packages["express"];
+        assert!(express.is_direct && !express.is_dev);
+        assert_eq!(express.dependencies, vec!["debug"]);
+        assert!(!packages["debug"].is_direct && !packages["debug"].is_dev);
+        assert!(packages["vitest"].is_direct && packages["vitest"].is_dev);
+        assert!(packages["tinypool"].is_dev);
+        assert_eq!(
+            packages["tinypool"].deprecated.as_deref(),
+            Some("use something else")
+        );
+    }
+
+    #[test]
+    fn test_parse_v9_workspace() {
+        let root = TempDir::new("pnpm-v9");
+        std::fs::create_dir_all(root.join("packages/core")).unwrap();
+        std::fs::write(
+            root.join("packages/core/package.json"),
+            r#"{"name": "@acme/core", "version": "2.1.0"}"#,
+        )
+        .unwrap();
+        let react_dom = root.join("node_modules/.pnpm/react-dom@18.2.0/node_modules/react-dom");
+        std::fs::create_dir_all(&react_dom).unwrap();
+        std::fs::write(
+            react_dom.join("package.json"),
+            r#"{"version": "18.2.0", "dependencies": {"loose-envify": "^1.1.0", "scheduler": "^0.23.0"}}"#,
+        )
+        .unwrap();
+        let path = root.join("pnpm-lock.yaml");
+        std::fs::write(
+            &path,
+            r#"
+lockfileVersion: '9.0'
+catalogs:
+  default:
+    esbuild:
+      specifier: ^0.19.0
+      version: 0.19.0
+importers:
+  .:
+    devDependencies:
+      typescript:
+        specifier: ^5.0.0
+        version: 5.3.3
+  packages/app:
+    dependencies:
+      '@acme/core':
+        specifier: workspace:*
+        version: link:../core
+      react-dom:
+        specifier: ^18.2.0
+        version: 18.2.0(react@18.2.0)
+      react:
+        specifier: ^18.2.0
+        version: 18.2.0
+  packages/core:
+    dependencies:
+      esbuild:
+        specifier: 'catalog:'
+        version: 0.19.0
+packages:
+  typescript@5.3.3:
+    resolution: {integrity: sha512-a}
+    hasBin: true
+  react@18.2.0:
+    resolution: {integrity: sha512-b}
+  react@17.0.2:
+    resolution: {integrity: sha512-c}
+  react-dom@18.2.0:
+    resolution: {integrity: sha512-d}
+    peerDependencies:
+      react: ^18.2.0
+  esbuild@0.19.0:
+    resolution: {integrity: sha512-e}
+  '@esbuild/linux-x64@0.19.0':
+    resolution: {integrity: sha512-f}
+    os: [linux]
+    cpu: [x64]
+snapshots:
+  typescript@5.3.3: {}
+  react@18.2.0: {}
+  react@17.0.2: {}
+  react-dom@18.2.0(react@18.2.0):
+    dependencies:
+      react: 18.2.0
+  esbuild@0.19.0:
+    optionalDependencies:
+      '@esbuild/linux-x64': 0.19.0
+  '@esbuild/linux-x64@0.19.0':
+    optional: true
+"#,
+        )
+        .unwrap();
+
+        let packages = PnpmLockfileParser::new(&root, &path).parse().unwrap();
+
+        // The version the importers use wins over the newer or older copies
+        assert_eq!(packages["react"].version, "18.2.0");
+        assert!(packages["react"].is_direct && !packages["react"].is_dev);
+        assert_eq!(packages["react-dom"].peer_dependencies["react"], "^18.2.0");
+        // Read from the installed package.json
+        assert_eq!(
+            packages["react-dom"].dependency_ranges["scheduler"],
+            "^0.23.0"
+        );
+        assert!(packages["react"].dependency_ranges.is_empty());
+        assert!(packages["typescript"].is_dev);
+
+        let core = &packages["@acme/core"];
+        assert!(core.is_direct);
+        assert_eq!(core.version, "2.1.0");
+        assert_eq!(core.dependencies, vec!["esbuild"]);
+        // Looked up in the catalog the specifier names
+        assert_eq!(core.dependency_ranges["esbuild"], "^0.19.0");
+
+        let native = &packages["@esbuild/linux-x64"];
+        assert!(native.is_optional && !native.is_dev);
+        assert_eq!(native.os, vec!["linux"]);
+        assert_eq!(native.platform_parent.as_deref(), Some("esbuild"));
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use toml::Value as TomlValue;
+
+use super::requirements::{normalize_name, requirement_name};
+use super::CargoPackageInfo;
+use crate::types::{Package, PackageSource};
+
+/// Parser for poetry.lock files (Python projects)
+pub struct PoetryLockfileParser<'a> {
+    root: &'a Path,
+    lockfile_path: &'a Path,
+}
+
+/// poetry.lock format (TOML)
+#[derive(Debug, Deserialize)]
+struct PoetryLockfile {
+    #[serde(default)]
+    package: Vec<PoetryPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PoetryPackage {
+    name: String,
+    version: String,
+
+    /// "main" or "dev", written by Poetry 1.x
+    #[serde(default)]
+    category: Option<String>,
+
+    /// Dependency groups needing the package, written by Poetry 2
+    #[serde(default)]
+    groups: Option<Vec<String>>,
+
+    /// Dependency name -> constraint string or `{ version = ... }` table
+    #[serde(default)]
+    dependencies: BTreeMap<String, TomlValue>,
+
+    #[serde(default)]
+    source: Option<PoetrySource>,
+}
+
+/// `[package.source]`, present for packages not from PyPI
+#[derive(Debug, Deserialize)]
+struct PoetrySource {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+/// Dependencies declared in pyproject.toml, by normalized name
+#[derive(Debug, Default)]
+struct ManifestDependencies {
+    production: HashSet<String>,
+    dev: HashSet<String>,
+}
+
+impl<'a> PoetryLockfileParser<'a> {
+    pub fn new(root: &'a Path, lockfile_path: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile_path,
+        }
+    }
+
+    /// Parse the lockfile, keyed by normalized package name
+    ///
+    /// Direct dependencies come from pyproject.toml; without one, packages
+    /// nothing else depends on are taken to be direct. Dev packages are
+    /// the ones outside the "main" group (or "dev" category before Poetry
+    /// 2), and failing both, the ones production dependencies don't reach.
+    pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let lockfile = self.load()?;
+        let manifest = manifest_dependencies(self.root)?;
+
+        let mut packages: HashMap<String, Package> = HashMap::new();
+        let mut dev_flags: HashMap<String, Option<bool>> = HashMap::new();
+        for pkg in &lockfile.package {
+            let name = normalize_name(&pkg.name);
+            // The same package locked twice, for different environment markers
+            let key = if packages.contains_key(&name) {
+                format!("{}@{}", name, pkg.version)
+            } else {
+                name.clone()
+            };
+
+            let mut package = Package::new(&name, &pkg.version);
+            package.source = pkg.source.as_ref().map_or(PackageSource::Registry, |s| {
+                match s.kind.as_str() {
+                    "git" => PackageSource::Git,
+                    "directory" => PackageSource::Link,
+                    "file" => PackageSource::File,
+                    "url" => PackageSource::Remote,
+                    // "legacy" is a private package index
+                    _ => PackageSource::Registry,
+                }
+            });
+            package.dependency_ranges = pkg
+                .dependencies
+                .iter()
+                .map(|(dep, spec)| (normalize_name(dep), constraint(spec)))
+                .collect();
+
+            let declared_dev = match (&pkg.groups, &pkg.category) {
+                (Some(groups), _) => Some(!groups.iter().any(|g| g == "main")),
+                (None, Some(category)) => Some(category == "dev"),
+                (None, None) => None,
+            };
+            dev_flags.insert(key.clone(), declared_dev);
+            packages.insert(key, package);
+        }
+
+        // Extras that weren't locked have no entry to point to
+        let keys: Vec<String> = packages.keys().cloned().collect();
+        for key in &keys {
+            let mut deps: Vec<String> = packages[key]
+                .dependency_ranges
+                .keys()
+                .filter(|dep| packages.contains_key(*dep))
+                .cloned()
+                .collect();
+            deps.sort();
+            packages.get_mut(key).unwrap().dependencies = deps;
+        }
+
+        let direct: HashSet<String> = match &manifest {
+            Some(manifest) => manifest
+                .production
+                .union(&manifest.dev)
+                .filter(|name| packages.contains_key(*name))
+                .cloned()
+                .collect(),
+            None => {
+                let depended_on: HashSet<&String> =
+                    packages.values().flat_map(|p| &p.dependencies).collect();
+                keys.iter()
+                    .filter(|key| !depended_on.contains(key))
+                    .cloned()
+                    .collect()
+            }
+        };
+
+        let runtime = manifest.as_ref().map(|manifest| {
+            let mut seen: HashSet<String> = HashSet::new();
+            let mut queue: VecDeque<&String> = manifest.production.iter().collect();
+            while let Some(key) = queue.pop_front() {
+                if let Some(package) = packages.get(key) {
+                    if seen.insert(key.clone()) {
+                        queue.extend(&package.dependencies);
+                    }
+                }
+            }
+            seen
+        });
+
+        for (key, package) in &mut packages {
+            package.is_direct = direct.contains(key);
+            package.is_dev = match (dev_flags[key], &runtime) {
+                (Some(is_dev), _) => is_dev,
+                (None, Some(runtime)) => !runtime.contains(key),
+                (None, None) => false,
+            };
+        }
+
+        Ok(packages)
+    }
+
+    /// Every locked version of every package, with what depends on it
+    ///
+    /// Poetry constraints use PEP 440 syntax rather than npm ranges, so no
+    /// requirements are recorded.
+    pub fn parse_for_duplicates(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        let lockfile = self.load()?;
+
+        let mut dependents: HashMap<String, Vec<String>> = HashMap::new();
+        for pkg in &lockfile.package {
+            let dependent = format!("{}@{}", normalize_name(&pkg.name), pkg.version);
+            for dep in pkg.dependencies.keys() {
+                dependents
+                    .entry(normalize_name(dep))
+                    .or_default()
+                    .push(dependent.clone());
+            }
+        }
+
+        let mut by_name: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+        for pkg in &lockfile.package {
+            let name = normalize_name(&pkg.name);
+            let mut pkg_dependents = dependents.get(&name).cloned().unwrap_or_default();
+            pkg_dependents.sort();
+            by_name.entry(name).or_default().push(CargoPackageInfo {
+                version: pkg.version.clone(),
+                dependents: pkg_dependents,
+                is_path_dep: pkg
+                    .source
+                    .as_ref()
+                    .is_some_and(|s| matches!(s.kind.as_str(), "directory" | "file")),
+                requirements: HashMap::new(),
+            });
+        }
+
+        Ok(by_name)
+    }
+
+    fn load(&self) -> Result<PoetryLockfile> {
+        let content = std::fs::read_to_string(self.lockfile_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", self.lockfile_path.display()))?;
+        toml::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse poetry.lock")
+    }
+}
+
+/// The version constraint of a `[package.dependencies]` entry
+///
+/// Entries with several alternatives, one per environment marker, are
+/// joined with " || ".
+fn constraint(spec: &TomlValue) -> String {
+    match spec {
+        TomlValue::String(version) => version.clone(),
+        TomlValue::Table(table) => table
+            .get("version")
+            .and_then(|v| v.as_str())
+            .unwrap_or("*")
+            .to_string(),
+        TomlValue::Array(alternatives) => alternatives
+            .iter()
+            .map(constraint)
+            .collect::<Vec<_>>()
+            .join(" || "),
+        _ => "*".to_string(),
+    }
+}
+
+/// Direct dependencies declared in pyproject.toml, or None without one
+///
+/// Reads Poetry's `[tool.poetry.dependencies]` and groups, PEP 621
+/// `[project]` dependencies and PEP 735 `[dependency-groups]`. Groups
+/// other than "main" hold dev dependencies.
+fn manifest_dependencies(root: &Path) -> Result<Option<ManifestDependencies>> {
+    let path = root.join("pyproject.toml");
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let value: TomlValue = toml::from_str(&content)
+        .into_diagnostic()
+        .with_context(|| "Failed to parse pyproject.toml")?;
+
+    let mut manifest = ManifestDependencies::default();
+    let table_keys = |table: Option<&TomlValue>| -> Vec<String> {
+        table
+            .and_then(|t| t.as_table())
+            .into_iter()
+            .flat_map(|t| t.keys())
+            .filter(|name| name.as_str() != "python")
+            .map(|name| normalize_name(name))
+            .collect()
+    };
+    let requirement_names = |list: Option<&TomlValue>| -> Vec<String> {
+        list.and_then(|l| l.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| requirement_name(r.as_str()?))
+            .collect()
+    };
+
+    if let Some(poetry) = value.get("tool").and_then(|t| t.get("poetry")) {
+        manifest
+            .production
+            .extend(table_keys(poetry.get("dependencies")));
+        manifest
+            .dev
+            .extend(table_keys(poetry.get("dev-dependencies")));
+        if let Some(groups) = poetry.get("group").and_then(|g| g.as_table()) {
+            for (group, table) in groups {
+                let names = table_keys(table.get("dependencies"));
+                if group == "main" {
+                    manifest.production.extend(names);
+                } else {
+                    manifest.dev.extend(names);
+                }
+            }
+        }
+    }
+
+    if let Some(project) = value.get("project") {
+        manifest
+            .production
+            .extend(requirement_names(project.get("dependencies")));
+        if let Some(extras) = project
+            .get("optional-dependencies")
+            .and_then(|e| e.as_table())
+        {
+            for list in extras.values() {
+                manifest.production.extend(requirement_names(Some(list)));
+            }
+        }
+    }
+
+    if let Some(groups) = value.get("dependency-groups").and_then(|g| g.as_table()) {
+        for list in groups.values() {
+            // `{ include-group = "..." }` entries aren't requirements
+            manifest.dev.extend(requirement_names(Some(list)));
+        }
+    }
+
+    Ok(Some(manifest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    const LOCKFILE: &str = r#"
+[[package]]
+name = "Flask"
+version = "3.0.2"
+groups = ["main"]
+
+[package.dependencies]
+click = ">=8.1.3"
+Werkzeug = ">=3.0.0"
+python-dotenv = { version = "*", optional = true }
+
+[[package]]
+name = "click"
+version = "8.1.7"
+groups = ["main", "dev"]
+
+[[package]]
+name = "werkzeug"
+version = "3.0.1"
+groups = ["main"]
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+groups = ["dev"]
+
+[package.dependencies]
+pluggy = [
+    { version = ">=1.3,<2", markers = "python_version >= \"3.8\"" },
+    { version = ">=1.0", markers = "python_version < \"3.8\"" },
+]
+
+[[package]]
+name = "pluggy"
+version = "1.4.0"
+groups = ["dev"]
+
+[[package]]
+name = "mylib"
+version = "0.1.0"
+groups = ["main"]
+
+[package.source]
+type = "git"
+url = "https://github.com/acme/mylib.git"
+reference = "main"
+resolved_reference = "abc123"
+
+[metadata]
+lock-version = "2.1"
+"#;
+
+    fn write_fixture(name: &str, lockfile: &str, pyproject: Option<&str>) -> TempDir {
+        let root = TempDir::new(&format!("poetry-{}", name));
+        std::fs::write(root.join("poetry.lock"), lockfile).unwrap();
+        if let Some(pyproject) = pyproject {
+            std::fs::write(root.join("pyproject.toml"), pyproject).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_parse() {
+        let root = write_fixture(
+            "parse",
+            LOCKFILE,
+            Some(
+                r#"
+[tool.poetry.dependencies]
+python = "^3.11"
+Flask = "^3.0"
+mylib = { git = "https://github.com/acme/mylib.git" }
+
+[tool.poetry.group.dev.dependencies]
+pytest = "^8.0"
+"#,
+            ),
+        );
+        let lockfile = root.join("poetry.lock");
+        let packages = PoetryLockfileParser::new(&root, &lockfile).parse().unwrap();
+
+        assert_eq!(packages.len(), 6);
+        let flask = &packages["flask"];
+        assert!(flask.is_direct && !flask.is_dev);
+        assert_eq!(flask.dependencies, vec!["click", "werkzeug"]);
+        assert_eq!(flask.dependency_ranges["werkzeug"], ">=3.0.0");
+        assert!(!packages["click"].is_direct && !packages["click"].is_dev);
+        assert!(packages["pytest"].is_direct && packages["pytest"].is_dev);
+        assert_eq!(
+            packages["pytest"].dependency_ranges["pluggy"],
+            ">=1.3,<2 || >=1.0"
+        );
+        assert!(packages["pluggy"].is_dev && !packages["pluggy"].is_direct);
+        assert_eq!(packages["mylib"].source, PackageSource::Git);
+    }
+
+    #[test]
+    fn test_parse_legacy() {
+        // Poetry 1.x: categories instead of groups, PEP 621 manifest
+        let lockfile = LOCKFILE
+            .replace("groups = [\"main\", \"dev\"]", "category = \"main\"")
+            .replace("groups = [\"main\"]", "category = \"main\"")
+            .replace("groups = [\"dev\"]", "category = \"dev\"");
+        let root = write_fixture(
+            "legacy",
+            &lockfile,
+            Some(
+                r#"
+[project]
+dependencies = ["flask>=3", "mylib @ git+https://github.com/acme/mylib.git"]
+
+[dependency-groups]
+test = ["pytest>=8"]
+"#,
+            ),
+        );
+        let path = root.join("poetry.lock");
+        let packages = PoetryLockfileParser::new(&root, &path).parse().unwrap();
+
+        assert!(packages["flask"].is_direct && !packages["flask"].is_dev);
+        assert!(packages["pytest"].is_direct && packages["pytest"].is_dev);
+        assert!(packages["pluggy"].is_dev);
+        assert!(!packages["werkzeug"].is_dev);
+
+        // Without pyproject.toml, roots of the dependency graph are direct
+        let root = write_fixture("bare", LOCKFILE, None);
+        let path = root.join("poetry.lock");
+        let packages = PoetryLockfileParser::new(&root, &path).parse().unwrap();
+
+        let mut direct: Vec<&str> = packages
+            .values()
+            .filter(|p| p.is_direct)
+            .map(|p| p.name.as_str())
+            .collect();
+        direct.sort();
+        assert_eq!(direct, vec!["flask", "mylib", "pytest"]);
+    }
+}
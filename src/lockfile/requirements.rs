@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+
+use super::CargoPackageInfo;
+use crate::types::{Package, PackageSource};
+
+/// Requirement files next to requirements.txt that hold development tools
+const DEV_FILES: &[&str] = &[
+    "requirements-dev.txt",
+    "dev-requirements.txt",
+    "requirements-test.txt",
+    "test-requirements.txt",
+];
+
+/// Parser for pip requirements files (Python projects without a lockfile)
+///
+/// Every requirement is direct unless pip-compile marked it `# via` other
+/// packages only, in which case it's a dependency of those. Requirements
+/// pinned with `==` carry their version; unpinned ones have none.
+pub struct RequirementsParser<'a> {
+    root: &'a Path,
+    lockfile_path: &'a Path,
+}
+
+/// One requirement line, with what pip-compile says pulled it in
+#[derive(Debug)]
+struct Requirement {
+    name: String,
+    version: String,
+    source: PackageSource,
+    is_dev: bool,
+    via: Vec<String>,
+}
+
+impl<'a> RequirementsParser<'a> {
+    pub fn new(root: &'a Path, lockfile_path: &'a Path) -> Self {
+        Self {
+            root,
+            lockfile_path,
+        }
+    }
+
+    pub fn parse(&self) -> Result<HashMap<String, Package>> {
+        let mut requirements = Vec::new();
+        let mut visited = HashSet::new();
+        read_file(self.lockfile_path, false, &mut requirements, &mut visited)?;
+        for name in DEV_FILES {
+            let path = self.root.join(name);
+            if path.is_file() {
+                read_file(&path, true, &mut requirements, &mut visited)?;
+            }
+        }
+
+        let mut packages: HashMap<String, Package> = HashMap::new();
+        for requirement in &requirements {
+            let is_direct = requirement.via.is_empty()
+                || requirement.via.iter().any(|via| via.starts_with('-'));
+            match packages.get_mut(&requirement.name) {
+                // Listed by both the main and a dev file
+                Some(package) => {
+                    package.is_dev &= requirement.is_dev;
+                    package.is_direct |= is_direct;
+                }
+                None => {
+                    let mut package = Package::new(&requirement.name, &requirement.version);
+                    package.is_direct = is_direct;
+                    package.is_dev = requirement.is_dev;
+                    package.source = requirement.source;
+                    packages.insert(requirement.name.clone(), package);
+                }
+            }
+        }
+
+        for requirement in &requirements {
+            for parent in &requirement.via {
+                let Some(package) = packages.get_mut(&normalize_name(parent)) else {
+                    continue;
+                };
+                if !package.dependencies.contains(&requirement.name) {
+                    package.dependencies.push(requirement.name.clone());
+                }
+            }
+        }
+
+        Ok(packages)
+    }
+
+    /// The pinned version of every requirement, with the packages
+    /// pip-compile says pulled it in
+    pub fn parse_for_duplicates(&self) -> Result<HashMap<String, Vec<CargoPackageInfo>>> {
+        let packages = self.parse()?;
+
+        let mut dependents: HashMap<&str, Vec<String>> = HashMap::new();
+        for package in packages.values() {
+            for dep in &package.dependencies {
+                dependents
+                    .entry(dep)
+                    .or_default()
+                    .push(format!("{}@{}", package.name, package.version));
+            }
+        }
+
+        Ok(packages
+            .values()
+            .map(|package| {
+                let mut package_dependents =
+                    dependents.remove(package.name.as_str()).unwrap_or_default();
+                package_dependents.sort();
+                let info = CargoPackageInfo {
+                    version: package.version.clone(),
+                    dependents: package_dependents,
+                    is_path_dep: package.source == PackageSource::File,
+                    requirements: HashMap::new(),
+                };
+                (package.name.clone(), vec![info])
+            })
+            .collect())
+    }
+}
+
+/// Read a requirements file and the files it includes with `-r`
+fn read_file(
+    path: &Path,
+    is_dev: bool,
+    requirements: &mut Vec<Requirement>,
+    visited: &mut HashSet<PathBuf>,
+) -> Result<()> {
+    if !visited.insert(path.to_path_buf()) {
+        return Ok(());
+    }
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    // Index of the requirement a multi-line `# via` list belongs to
+    let mut via_list: Option<usize> = None;
+    for line in join_continuations(&content) {
+        let line = line.trim();
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim();
+            if let Some(rest) = comment.strip_prefix("via") {
+                let Some(last) = requirements.len().checked_sub(1) else {
+                    continue;
+                };
+                match rest.trim() {
+                    "" => via_list = Some(last),
+                    parent => requirements[last].via.push(parent.to_string()),
+                }
+            } else if let Some(index) = via_list {
+                if !comment.is_empty() {
+                    requirements[index].via.push(comment.to_string());
+                }
+            }
+            continue;
+        }
+        via_list = None;
+
+        // Inline comments need whitespace before the "#"
+        let line = line.split(" #").next().unwrap_or(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(include) = line
+            .strip_prefix("--requirement")
+            .or_else(|| line.strip_prefix("-r"))
+        {
+            let include = include.trim_start_matches('=').trim();
+            read_file(&dir.join(include), is_dev, requirements, visited)?;
+            continue;
+        }
+        // Editable installs, constraints files and index options
+        if line.starts_with('-') {
+            continue;
+        }
+
+        if let Some(requirement) = parse_requirement(line, is_dev) {
+            requirements.push(requirement);
+        }
+    }
+
+    Ok(())
+}
+
+/// Lines with backslash continuations joined
+fn join_continuations(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for line in content.lines() {
+        match line.strip_suffix('\\') {
+            Some(start) => current.push_str(start),
+            None => {
+                current.push_str(line);
+                lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Parse "requests[socks]==2.31.0 ; python_version >= '3.8' --hash=..."
+fn parse_requirement(line: &str, is_dev: bool) -> Option<Requirement> {
+    let line = line.split(" --").next().unwrap_or(line);
+    let name = requirement_name(line)?;
+
+    let (spec, url) = match line.split_once('@') {
+        Some((spec, url)) => (spec, Some(url.trim())),
+        None => (line, None),
+    };
+    let source = match url {
+        Some(url) if url.starts_with("git+") => PackageSource::Git,
+        Some(url) if url.starts_with("file:") => PackageSource::File,
+        Some(_) => PackageSource::Remote,
+        None => PackageSource::Registry,
+    };
+
+    let spec = spec.split(';').next().unwrap_or(spec);
+    let version = spec
+        .split_once("==")
+        .map(|(_, pin)| {
+            pin.trim_start_matches('=')
+                .split([',', ' '])
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string()
+        })
+        .filter(|pin| !pin.contains('*'))
+        .unwrap_or_default();
+
+    Some(Requirement {
+        name,
+        version,
+        source,
+        is_dev,
+        via: Vec::new(),
+    })
+}
+
+/// The normalized project name a PEP 508 requirement starts with
+///
+/// "Flask-SQLAlchemy[async]>=3.0" -> "flask-sqlalchemy"
+pub fn requirement_name(requirement: &str) -> Option<String> {
+    let requirement = requirement.trim_start();
+    let end = requirement
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+        .unwrap_or(requirement.len());
+    let name = &requirement[..end];
+    if !name.starts_with(|c: char| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(normalize_name(name))
+}
+
+/// PEP 503 name normalization: lowercase, with runs of "-", "_" and "."
+/// collapsed to "-"
+pub fn normalize_name(name: &str) -> String {
+    let mut normalized = String::with_capacity(name.len());
+    for c in name.trim().chars() {
+        if matches!(c, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(c.to_ascii_lowercase());
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_requirement_name() {
+        assert_eq!(
+            requirement_name("Flask-SQLAlchemy[async]>=3.0").as_deref(),
+            Some("flask-sqlalchemy")
+        );
+        assert_eq!(
+            requirement_name("zope.interface ; python_version > '3'").as_deref(),
+            Some("zope-interface")
+        );
+        assert_eq!(
+            requirement_name("ruamel__yaml").as_deref(),
+            Some("ruamel-yaml")
+        );
+        assert_eq!(requirement_name("./local"), None);
+    }
+
+    #[test]
+    fn test_parse() {
+        let root = TempDir::new("requirements");
+        std::fs::write(
+            root.join("requirements.txt"),
+            concat!(
+                "# pip-compile output\n",
+                "certifi==2024.2.2\n",
+                "    # via requests\n",
+                "Flask==3.0.2 \\\n",
+                "    --hash=sha256:abc\n",
+                "    # via -r requirements.in\n",
+                "requests[socks]==2.31.0 ; python_version >= \"3.8\"\n",
+                "    # via\n",
+                "    #   -r requirements.in\n",
+                "    #   flask\n",
+                "mylib @ git+https://github.com/acme/mylib.git\n",
+                "-e ./vendored\n",
+                "-r base.txt\n",
+            ),
+        )
+        .unwrap();
+        std::fs::write(root.join("base.txt"), "click>=8 # cli\n").unwrap();
+        std::fs::write(root.join("requirements-dev.txt"), "pytest==8.0.0\nclick\n").unwrap();
+
+        let lockfile = root.join("requirements.txt");
+        let packages = RequirementsParser::new(&root, &lockfile).parse().unwrap();
+
+        let mut names: Vec<&String> = packages.keys().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec!["certifi", "click", "flask", "mylib", "pytest", "requests"]
+        );
+
+        assert!(!packages["certifi"].is_direct);
+        assert_eq!(packages["certifi"].version, "2024.2.2");
+        assert_eq!(packages["requests"].dependencies, vec!["certifi"]);
+        assert_eq!(packages["flask"].dependencies, vec!["requests"]);
+        assert!(packages["flask"].is_direct && packages["requests"].is_direct);
+        assert_eq!(packages["mylib"].source, PackageSource::Git);
+        assert_eq!(packages["click"].version, "");
+        assert!(!packages["click"].is_dev);
+        assert!(packages["pytest"].is_dev && packages["pytest"].is_direct);
+    }
+}
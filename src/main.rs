@@ -1,22 +1,92 @@
 #![allow(clippy::type_complexity, clippy::collapsible_match)]
 
+mod align;
 mod analyzer;
+mod api_diff;
+mod audit_cache;
+mod bins;
+mod bundle;
+mod catalog;
+mod categories;
+mod changelog;
+mod ci;
+mod clean;
+mod config;
+mod coupling;
+mod coverage;
+mod date;
+mod deadcode;
 mod duplicates;
+mod engines;
+mod entry_points;
+mod fix;
 mod graph;
+mod grouping;
+mod health;
+mod history;
+mod integrity;
+mod licenses;
+mod lint;
 mod lockfile;
+mod metrics;
+mod notify;
+mod overrides;
+mod peers;
+mod pinning;
+mod platform;
+mod policy;
+mod publish;
+mod range;
+mod registry;
 mod reporter;
+mod rules;
+mod sbom;
+mod serve;
+mod snapshot;
+mod target;
+#[cfg(test)]
+mod testing;
 mod types;
+mod typings;
+mod unused_exports;
+mod update;
+mod vendored;
+mod vex;
 mod vulnerability;
+mod workspace;
 
+use std::collections::{HashMap, HashSet};
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Arc;
 
 use clap::{Parser, Subcommand};
-use miette::Result;
+use miette::{Context, IntoDiagnostic, Result};
 
-use crate::analyzer::ImportAnalyzer;
+use crate::analyzer::go::GoImportScanner;
+use crate::analyzer::python::PythonImportScanner;
+use crate::analyzer::rust::RustUseScanner;
+use crate::analyzer::{commands, ImportAnalyzer};
+use crate::config::Config;
+use crate::entry_points::EntryPointAnalyzer;
+use crate::fix::rewrite::ImportRewrite;
 use crate::graph::DependencyGraph;
-use crate::lockfile::LockfileParser;
-use crate::reporter::Reporter;
+use crate::grouping::{GroupBy, Ownership};
+use crate::health::HealthSort;
+use crate::lockfile::{
+    CargoPackageInfo, Ecosystem, LockfileParser, LockfileSelection, LockfileType, NpmLockfileParser,
+};
+use crate::policy::{AnalyzePolicy, ExitStatus};
+use crate::registry::Registries;
+use crate::reporter::{Reporter, TableSort};
+use crate::target::Target;
+use crate::types::{
+    AlignmentReport, AnalysisSnapshot, AuditCache, AuditChanges, DeprecatedPackage, FindingHistory,
+    FixAction, GraphExport, ImportMap, NotifyState, NotifySummary, Package, PinningReport,
+    ProjectSummary, RulePack, Severity, TrackedVulnerability, UsageAnalysis,
+};
+use crate::update::PackageManager;
 
 #[derive(Parser)]
 #[command(name = "depx")]
@@ -28,6 +98,21 @@ use crate::reporter::Reporter;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Only read this ecosystem's lockfile when the project has several
+    /// (e.g. Cargo.lock and package-lock.json)
+    #[arg(long, global = true, value_enum)]
+    ecosystem: Option<Ecosystem>,
+
+    /// Read this lockfile instead of detecting one, e.g. a monorepo's root
+    /// lockfile when analyzing one of its packages
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "ecosystem")]
+    lockfile: Option<PathBuf>,
+
+    /// Evaluate Cargo's `[target.'cfg(...)']` dependencies for this target
+    /// triple (e.g. x86_64-pc-windows-msvc) instead of the host
+    #[arg(long, global = true, value_name = "TRIPLE")]
+    target: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -42,9 +127,54 @@ enum Commands {
         #[arg(long)]
         unused: bool,
 
+        /// Show only phantom dependencies, with every file and line importing them
+        #[arg(long, conflicts_with = "unused")]
+        phantom: bool,
+
         /// Include dev dependencies in analysis
         #[arg(long, default_value = "true")]
         include_dev: bool,
+
+        /// Exit with code 1 if any direct dependency is unused
+        #[arg(long)]
+        fail_if_unused: bool,
+
+        /// Exit with code 1 if source code imports undeclared packages
+        #[arg(long)]
+        fail_if_phantom: bool,
+
+        /// Exit with code 1 if more than N direct dependencies are unused
+        #[arg(long, value_name = "N")]
+        max_unused: Option<usize>,
+
+        /// Sort order for the table: name, size or severity
+        #[arg(long, value_enum)]
+        sort: Option<TableSort>,
+
+        /// Don't truncate the table to the terminal width
+        #[arg(long)]
+        wide: bool,
+
+        /// Group unused and phantom findings by top-level directory or CODEOWNERS owner
+        #[arg(long, value_enum)]
+        group_by: Option<GroupBy>,
+
+        /// Save unused and phantom findings to a snapshot file
+        #[arg(long, value_name = "FILE")]
+        save: Option<PathBuf>,
+
+        /// Compare findings against a snapshot saved with --save
+        #[arg(long, value_name = "FILE")]
+        compare: Option<PathBuf>,
+
+        /// Write proposed fixes to a fix plan for `depx fix --plan`
+        #[arg(long, value_name = "FILE")]
+        fix_plan: Option<PathBuf>,
+
+        /// Analyze every project below the path (each directory with a
+        /// lockfile, or a workspace root) and summarize them together
+        #[arg(long, conflicts_with_all = ["save", "compare", "fix_plan"])]
+        recursive: bool,
     },
 
     /// Explain why a package is installed
@@ -57,6 +187,43 @@ enum Commands {
         path: PathBuf,
     },
 
+    /// Print the dependency graph as JSON: packages, with duplicates and
+    /// advisories marked, and the links between them
+    Graph {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Mark packages with known vulnerabilities (queries OSV)
+        #[arg(long)]
+        audit: bool,
+    },
+
+    /// Browse the dependency graph in an interactive page served locally
+    Serve {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Port to listen on at 127.0.0.1
+        #[arg(long, default_value_t = 7878)]
+        port: u16,
+
+        /// Mark packages with known vulnerabilities (queries OSV)
+        #[arg(long)]
+        audit: bool,
+    },
+
+    /// Explain how analyze classifies a package (used, expected-unused, dev-only, unused)
+    ExplainRule {
+        /// Package name to explain
+        package: String,
+
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+    },
+
     /// Check for known vulnerabilities
     Audit {
         /// Path to the project root
@@ -66,6 +233,27 @@ enum Commands {
         /// Only show vulnerabilities in actually used packages
         #[arg(long)]
         used_only: bool,
+
+        /// Write proposed fixes to a fix plan for `depx fix --plan`
+        #[arg(long, value_name = "FILE")]
+        fix_plan: Option<PathBuf>,
+
+        /// Write a VEX document with the triage of each vulnerability
+        #[arg(long, value_name = "FILE")]
+        vex: Option<PathBuf>,
+
+        /// Format of the --vex document
+        #[arg(long, value_enum, default_value = "openvex")]
+        vex_format: vex::VexFormat,
+
+        /// Only report vulnerabilities that are new or resolved since the
+        /// previous audit
+        #[arg(long)]
+        since_last: bool,
+
+        /// Output as JSON, with package URLs and CPEs
+        #[arg(long)]
+        json: bool,
     },
 
     /// List deprecated packages
@@ -73,6 +261,10 @@ enum Commands {
         /// Path to the project root
         #[arg(default_value = ".")]
         path: PathBuf,
+
+        /// Don't truncate the table to the terminal width
+        #[arg(long)]
+        wide: bool,
     },
 
     /// Detect duplicate dependencies (multiple versions of same crate)
@@ -85,159 +277,2737 @@ enum Commands {
         #[arg(short, long)]
         verbose: bool,
 
+        /// Sort order for the table: name, size or severity
+        #[arg(long, value_enum)]
+        sort: Option<TableSort>,
+
+        /// Don't truncate the table to the terminal width
+        #[arg(long)]
+        wide: bool,
+
+        /// List every package of scope/prefix clusters (e.g. @babel/*) instead of one line each
+        #[arg(long)]
+        expand: bool,
+
+        /// Also list low-severity duplicates (minor or patch spread)
+        #[arg(long)]
+        all: bool,
+
+        /// Write proposed fixes to a fix plan for `depx fix --plan`
+        #[arg(long, value_name = "FILE")]
+        fix_plan: Option<PathBuf>,
+
         /// Output as JSON
         #[arg(long)]
         json: bool,
     },
-}
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+    /// Report release age, cadence, and advisories for direct dependencies
+    Health {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    match cli.command {
-        Commands::Analyze {
-            path,
-            unused,
-            include_dev,
-        } => {
-            run_analyze(&path, unused, include_dev).await?;
-        }
-        Commands::Why { package, path } => {
-            run_why(&path, &package).await?;
-        }
-        Commands::Audit { path, used_only } => {
-            run_audit(&path, used_only).await?;
-        }
-        Commands::Deprecated { path } => {
-            run_deprecated(&path).await?;
-        }
-        Commands::Duplicates {
-            path,
-            verbose,
-            json,
-        } => {
-            run_duplicates(&path, verbose, json).await?;
-        }
-    }
+        /// Sort order for the report
+        #[arg(long, value_enum, default_value = "age")]
+        sort: HealthSort,
 
-    Ok(())
-}
+        /// Also look up downloads for transitive packages and list obscure ones
+        #[arg(long)]
+        transitive: bool,
 
-async fn run_analyze(path: &PathBuf, show_unused_only: bool, include_dev: bool) -> Result<()> {
-    let reporter = Reporter::new();
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    reporter.status("Analyzing", &format!("project at {}", path.display()));
+    /// Count unused, phantom and duplicated packages and vulnerabilities by
+    /// severity, for dashboards
+    Metrics {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    // 1. Parse lockfile to get all installed packages
-    let lockfile_parser = LockfileParser::new(path)?;
-    let installed_packages = lockfile_parser.parse()?;
+        /// Output in the Prometheus text format
+        #[arg(long, conflicts_with = "json")]
+        prometheus: bool,
 
-    reporter.info(&format!(
-        "Found {} installed packages",
-        installed_packages.len()
-    ));
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    // 2. Analyze source code to find actual imports
-    let analyzer = ImportAnalyzer::new(path);
-    let imports = analyzer.analyze()?;
+    /// Audit overrides, resolutions, and patches for stale or vulnerable entries
+    Overrides {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    reporter.info(&format!(
-        "Found {} import statements across {} files",
-        imports.total_imports(),
-        imports.files_analyzed()
-    ));
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    // 3. Build dependency graph
-    let graph = DependencyGraph::new(&installed_packages);
+    /// Find source files no entry point imports
+    Deadcode {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    // 4. Cross-reference to find unused packages
-    let used_packages = imports.packages_used();
-    let analysis = graph.analyze_usage(&used_packages, include_dev);
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    // 5. Report results
-    if show_unused_only {
-        reporter.report_unused(&analysis);
-    } else {
-        reporter.report_full(&analysis, &imports);
-    }
+    /// Find exports of the package's entry points that tests, examples and workspace packages never import
+    UnusedExports {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    Ok(())
-}
+        /// Also count imports from the other packages of the enclosing workspace
+        #[arg(long)]
+        workspace: bool,
 
-async fn run_why(path: &PathBuf, package: &str) -> Result<()> {
-    let reporter = Reporter::new();
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    let lockfile_parser = LockfileParser::new(path)?;
-    let installed_packages = lockfile_parser.parse()?;
+    /// Show fan-in/fan-out per source file and import cycles
+    Coupling {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    let graph = DependencyGraph::new(&installed_packages);
+        /// Number of modules to list
+        #[arg(long, default_value = "10")]
+        top: usize,
 
-    match graph.explain_package(package) {
-        Some(explanation) => reporter.report_why(package, &explanation),
-        None => reporter.error(&format!("Package '{}' not found in dependencies", package)),
-    }
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    Ok(())
-}
+    /// Check the executables exposed by the project's package.json `bin` field
+    Bins {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-async fn run_audit(path: &PathBuf, used_only: bool) -> Result<()> {
-    let reporter = Reporter::new();
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    reporter.status("Auditing", &format!("project at {}", path.display()));
+    /// Check that peer dependencies are installed at compatible versions
+    Peers {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    let lockfile_parser = LockfileParser::new(path)?;
-    let installed_packages = lockfile_parser.parse()?;
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    let used_packages = if used_only {
-        let analyzer = ImportAnalyzer::new(path);
-        let imports = analyzer.analyze()?;
-        Some(imports.packages_used())
-    } else {
-        None
-    };
+    /// Check installed packages' engines against the project's Node.js and package manager
+    Engines {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    let vulnerabilities =
-        vulnerability::check_vulnerabilities(&installed_packages, used_packages.as_ref()).await?;
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    reporter.report_vulnerabilities(&vulnerabilities);
+    /// List the license of every installed package, reading LICENSE files
+    /// when package.json has none
+    Licenses {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    Ok(())
-}
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-async fn run_deprecated(path: &PathBuf) -> Result<()> {
-    let reporter = Reporter::new();
+    /// Check that dependency ranges use one pinning style (exact, ^ or ~)
+    Pinning {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    reporter.status("Checking", "for deprecated packages");
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    let lockfile_parser = LockfileParser::new(path)?;
-    let installed_packages = lockfile_parser.parse()?;
+    /// Check that workspace members declare each dependency with the same range
+    Align {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    let deprecated = vulnerability::check_deprecated(&installed_packages).await?;
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    reporter.report_deprecated(&deprecated);
+    /// Attribute a bundler's emitted bytes to direct dependencies
+    Bundle {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    Ok(())
-}
+        /// webpack-bundle-analyzer JSON report, esbuild metafile or Vite manifest
+        #[arg(long, value_name = "FILE")]
+        stats: PathBuf,
 
-async fn run_duplicates(path: &Path, verbose: bool, json: bool) -> Result<()> {
-    let reporter = if verbose {
-        Reporter::new().verbose()
-    } else {
-        Reporter::new()
-    };
+        /// Number of dependencies to list
+        #[arg(long, default_value = "10")]
+        top: usize,
 
-    reporter.status("Analyzing", &format!("duplicates at {}", path.display()));
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
 
-    let analyzer = duplicates::DuplicateAnalyzer::new(path);
-    let analysis = analyzer.analyze()?;
+    /// Find dependencies only imported from code the tests never run
+    Coverage {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
 
-    if json {
-        let output = serde_json::to_string_pretty(&analysis)
-            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
-        println!("{}", output);
-    } else {
-        reporter.report_duplicates(&analysis);
+        /// lcov tracefile or istanbul coverage-final.json
+        #[arg(long, value_name = "FILE")]
+        report: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Find copies of published packages checked into the source tree
+    Vendored {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Apply fixes to package.json
+    Fix {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Apply a fix plan written by analyze, audit or duplicates with --fix-plan
+        #[arg(long, value_name = "FILE")]
+        plan: Option<PathBuf>,
+
+        /// Move test-only dependencies to devDependencies, and dev dependencies
+        /// used by application code to dependencies
+        #[arg(long)]
+        moves: bool,
+
+        /// Point imports of renamed packages at their successor's name
+        #[arg(long)]
+        rename: bool,
+
+        /// Rewrite dependency ranges in the project's pinning style, in
+        /// every workspace member
+        #[arg(long)]
+        pinning: bool,
+
+        /// Rewrite ranges that differ between workspace members to the one
+        /// `[align]` in depx.toml picks
+        #[arg(long)]
+        align: bool,
+
+        /// Show the diff without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Rewrite import and require specifiers from one package to another
+    Rewrite {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Package or subpath whose imports to rewrite (e.g. lodash, lodash/fp)
+        #[arg(long)]
+        from: String,
+
+        /// Package to point them at
+        #[arg(long)]
+        to: String,
+
+        /// Show the diff without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove unused direct dependencies from package.json
+    Clean {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Review each unused dependency (why it's installed, last use, size) before removing it
+        #[arg(long, short)]
+        interactive: bool,
+
+        /// Show the package.json diff without writing it
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// List newer releases of direct dependencies and write the chosen ones to the manifest
+    Update {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Pick which updates to apply from a numbered list
+        #[arg(long, short)]
+        interactive: bool,
+
+        /// Show the manifest diff without writing it
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Read each update's GitHub release notes or changelog, and list
+        /// the breaking changes they call out
+        #[arg(long)]
+        changelog: bool,
+
+        /// Compare the public API of each crate's current and latest
+        /// version, and flag changes to items the workspace uses (Cargo)
+        #[arg(long)]
+        api_diff: bool,
+    },
+
+    /// Check the lockfile for supply-chain hygiene: integrity hashes and sources
+    Check {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Also hash each package's tarball in the npm cache against the lockfile
+        #[arg(long)]
+        verify_tarballs: bool,
+
+        /// Post new vulnerabilities, deprecations and the package count to a webhook
+        #[arg(long, value_name = "URL")]
+        notify: Option<String>,
+
+        /// Webhook payload (defaults to slack for Slack URLs, json otherwise)
+        #[arg(long, value_enum, requires = "notify")]
+        notify_format: Option<notify::NotifyFormat>,
+
+        /// Findings of the previous run; only what's new since is posted
+        #[arg(long, value_name = "FILE", requires = "notify")]
+        state: Option<PathBuf>,
+
+        /// Record when each finding was first seen, and show how long it's been open
+        #[arg(long, value_name = "FILE")]
+        history: Option<PathBuf>,
+
+        /// Exit with code 1 if a critical vulnerability has been open more than N days
+        #[arg(long, value_name = "DAYS", requires = "history")]
+        max_critical_age: Option<i64>,
+
+        /// Framework rule packs to lint with, on top of the config's `lint`
+        #[arg(long, value_enum, value_delimiter = ',')]
+        lint: Vec<RulePack>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare an SBOM (CycloneDX or SPDX JSON) with what the lockfile installs
+    Diff {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// The SBOM to check, e.g. the one published with the last release
+        #[arg(long, value_name = "FILE")]
+        sbom: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run the analysis with the CI provider's annotations and the config's policy
+    Ci {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// CI provider to report to (detected from the environment by default)
+        #[arg(long, value_enum)]
+        provider: Option<ci::CiProvider>,
+
+        /// Where to write the GitLab Code Quality report
+        #[arg(long, value_name = "FILE")]
+        report: Option<PathBuf>,
+    },
+
+    /// Check publishable packages for problems that break their consumers
+    Prepublish {
+        /// Path to the project (or workspace) root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that dependencies imported from TypeScript have type declarations
+    Types {
+        /// Path to the project root
+        #[arg(default_value = ".")]
+        path: PathBuf,
+
+        /// List typed dependencies and where their declarations come from
+        #[arg(short, long)]
+        verbose: bool,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Cli::parse()).await {
+        Ok(status) => status.into(),
+        Err(report) => {
+            eprintln!("{:?}", report);
+            ExitStatus::Error.into()
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<ExitStatus> {
+    let mut status = ExitStatus::Success;
+    let selection = LockfileSelection {
+        lockfile: cli.lockfile,
+        ecosystem: cli.ecosystem,
+        target: cli.target.as_deref().map(Target::from_triple).transpose()?,
+    };
+
+    match cli.command {
+        Commands::Analyze {
+            path,
+            unused,
+            phantom,
+            include_dev,
+            fail_if_unused,
+            fail_if_phantom,
+            max_unused,
+            sort,
+            wide,
+            group_by,
+            save,
+            compare,
+            fix_plan,
+            recursive,
+        } => {
+            let options = AnalyzeOptions {
+                unused_only: unused,
+                phantom_only: phantom,
+                include_dev,
+                group_by,
+                save,
+                compare,
+                fix_plan,
+            };
+            let policy = AnalyzePolicy {
+                fail_if_unused,
+                fail_if_phantom,
+                max_unused,
+                ..Default::default()
+            };
+            let reporter = Reporter::new().sort(sort).wide(wide);
+            status = if recursive {
+                run_analyze_recursive(&reporter, &path, &selection, &options, &policy).await?
+            } else {
+                run_analyze(&reporter, &path, &selection, &options, &policy)
+                    .await?
+                    .1
+            };
+        }
+        Commands::Why { package, path } => {
+            run_why(&path, &selection, &package).await?;
+        }
+        Commands::Graph { path, audit } => {
+            run_graph(&path, &selection, audit).await?;
+        }
+        Commands::Serve { path, port, audit } => {
+            run_serve(&path, &selection, port, audit).await?;
+        }
+        Commands::ExplainRule { package, path } => {
+            run_explain_rule(&path, &selection, &package)?;
+        }
+        Commands::Audit {
+            path,
+            used_only,
+            fix_plan,
+            vex,
+            vex_format,
+            since_last,
+            json,
+        } => {
+            let options = AuditOptions {
+                used_only,
+                fix_plan,
+                vex: vex.map(|file| (file, vex_format)),
+                since_last,
+            };
+            run_audit(&path, &selection, &options, json).await?;
+        }
+        Commands::Deprecated { path, wide } => {
+            run_deprecated(&path, &selection, wide).await?;
+        }
+        Commands::Duplicates {
+            path,
+            verbose,
+            sort,
+            wide,
+            expand,
+            all,
+            fix_plan,
+            json,
+        } => {
+            let reporter = if verbose {
+                Reporter::new().verbose()
+            } else {
+                Reporter::new()
+            };
+            let reporter = reporter.sort(sort).wide(wide).expand(expand).all(all);
+            status =
+                run_duplicates(&reporter, &path, &selection, fix_plan.as_deref(), json).await?;
+        }
+        Commands::Health {
+            path,
+            sort,
+            transitive,
+            json,
+        } => {
+            run_health(&path, &selection, sort, transitive, json).await?;
+        }
+        Commands::Metrics {
+            path,
+            prometheus,
+            json,
+        } => {
+            run_metrics(&path, &selection, prometheus, json).await?;
+        }
+        Commands::Overrides { path, json } => {
+            if selection.lockfile.is_some() {
+                miette::bail!(
+                    "depx overrides reads each manifest's own lockfile; it can't be used with --lockfile"
+                );
+            }
+            run_overrides(&path, json).await?;
+        }
+        Commands::Deadcode { path, json } => {
+            run_deadcode(&path, json)?;
+        }
+        Commands::UnusedExports {
+            path,
+            workspace,
+            json,
+        } => {
+            run_unused_exports(&path, workspace, json)?;
+        }
+        Commands::Coupling { path, top, json } => {
+            run_coupling(&path, top, json)?;
+        }
+        Commands::Bins { path, json } => {
+            run_bins(&path, json)?;
+        }
+        Commands::Peers { path, json } => {
+            status = run_peers(&path, &selection, json)?;
+        }
+        Commands::Engines { path, json } => {
+            status = run_engines(&path, &selection, json)?;
+        }
+        Commands::Licenses { path, json } => {
+            run_licenses(&path, &selection, json)?;
+        }
+        Commands::Pinning { path, json } => {
+            status = run_pinning(&path, &selection, json)?;
+        }
+        Commands::Align { path, json } => {
+            status = run_align(&path, json)?;
+        }
+        Commands::Bundle {
+            path,
+            stats,
+            top,
+            json,
+        } => {
+            run_bundle(&path, &stats, &selection, top, json)?;
+        }
+        Commands::Coverage { path, report, json } => {
+            run_coverage(&path, &report, &selection, json)?;
+        }
+        Commands::Vendored { path, json } => {
+            run_vendored(&path, json)?;
+        }
+        Commands::Fix {
+            path,
+            plan,
+            moves,
+            rename,
+            pinning,
+            align,
+            dry_run,
+        } => {
+            let fixes = Fixes {
+                plan,
+                moves,
+                rename,
+                pinning,
+                align,
+            };
+            run_fix(&path, &selection, &fixes, dry_run).await?;
+        }
+        Commands::Rewrite {
+            path,
+            from,
+            to,
+            dry_run,
+        } => {
+            run_rewrite(&path, &from, &to, dry_run)?;
+        }
+        Commands::Clean {
+            path,
+            interactive,
+            dry_run,
+        } => {
+            run_clean(&path, &selection, interactive, dry_run)?;
+        }
+        Commands::Update {
+            path,
+            interactive,
+            dry_run,
+            changelog,
+            api_diff,
+        } => {
+            let options = UpdateOptions {
+                interactive,
+                dry_run,
+                changelog,
+                api_diff,
+            };
+            run_update(&path, &selection, &options).await?;
+        }
+        Commands::Check {
+            path,
+            verify_tarballs,
+            notify,
+            notify_format,
+            state,
+            history,
+            max_critical_age,
+            lint,
+            json,
+        } => {
+            let notify = notify.map(|url| NotifyOptions {
+                format: notify_format.unwrap_or_else(|| notify::NotifyFormat::for_url(&url)),
+                url,
+                state,
+            });
+            let tracking = history.map(|history| TrackingOptions {
+                history,
+                max_critical_age,
+            });
+            status = run_check(
+                &path,
+                &selection,
+                verify_tarballs,
+                notify.as_ref(),
+                tracking.as_ref(),
+                &lint,
+                json,
+            )
+            .await?;
+        }
+        Commands::Diff { path, sbom, json } => {
+            status = run_diff(&path, &selection, &sbom, json)?;
+        }
+        Commands::Ci {
+            path,
+            provider,
+            report,
+        } => {
+            status = run_ci(&path, &selection, provider, report.as_deref())?;
+        }
+        Commands::Prepublish { path, json } => {
+            if selection.lockfile.is_some() {
+                miette::bail!(
+                    "depx prepublish reads no lockfile; it can't be used with --lockfile"
+                );
+            }
+            status = run_prepublish(&path, json)?;
+        }
+        Commands::Types {
+            path,
+            verbose,
+            json,
+        } => {
+            run_types(&path, &selection, verbose, json)?;
+        }
+    }
+
+    Ok(status)
+}
+
+/// Report options for `depx analyze`
+struct AnalyzeOptions {
+    unused_only: bool,
+    phantom_only: bool,
+    include_dev: bool,
+    group_by: Option<GroupBy>,
+    save: Option<PathBuf>,
+    compare: Option<PathBuf>,
+    fix_plan: Option<PathBuf>,
+}
+
+async fn run_analyze(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+    options: &AnalyzeOptions,
+    policy: &AnalyzePolicy,
+) -> Result<(UsageAnalysis, ExitStatus)> {
+    reporter.status("Analyzing", &format!("project at {}", path.display()));
+    let config = Config::load(path)?;
+    let policy = policy.clone().with_rules(&config.rules);
+
+    // 1. Parse each ecosystem's lockfile to get all installed packages
+    let lockfiles = parse_lockfiles(reporter, path, selection)?;
+    let several = lockfiles.len() > 1;
+    // Tips name the first lockfile's manifest and commands
+    let package_manager = PackageManager::of(lockfiles[0].0.lockfile_type());
+
+    let mut installed_packages = HashMap::new();
+    let mut imports = ImportMap::new();
+    let mut analyses = Vec::new();
+    for (lockfile_parser, packages) in lockfiles {
+        let bundled = packages.values().filter(|p| p.bundled_in.is_some()).count();
+        let mut found = if bundled > 0 {
+            format!(
+                "Found {} installed packages (plus {} bundled inside other packages)",
+                packages.len() - bundled,
+                bundled
+            )
+        } else {
+            format!("Found {} installed packages", packages.len())
+        };
+        if several {
+            found.push_str(&format!(" in {}", lockfile_parser.file_name()));
+        }
+        reporter.info(&found);
+
+        // npm records what it actually installed in node_modules/.package-lock.json
+        if lockfile_parser.lockfile_type() == LockfileType::Npm {
+            let stale =
+                NpmLockfileParser::new(lockfile_parser.root(), lockfile_parser.lockfile_path())
+                    .install_divergence()?
+                    .len();
+            if stale > 0 {
+                reporter.warn(&format!(
+                    "node_modules doesn't match package-lock.json for {} packages; run `npm ci` (see `depx check`)",
+                    stale
+                ));
+            }
+        }
+
+        // 2. Analyze source code to find actual imports
+        let lockfile_imports = project_imports(path, &lockfile_parser, &packages)?;
+
+        // 3. Cross-reference the dependency graph to find unused packages
+        analyses.push(usage_analysis(
+            path,
+            &packages,
+            &lockfile_imports,
+            options.include_dev,
+        )?);
+        imports.merge(lockfile_imports);
+        installed_packages.extend(packages);
+    }
+    let mut analysis = merge_analyses(analyses);
+    rules::suppress_usage(&mut analysis, &config.disabled_rules());
+
+    reporter.info(&format!(
+        "Found {} import statements across {} files",
+        imports.total_imports(),
+        imports.files_analyzed()
+    ));
+    if !imports.commands().is_empty() {
+        reporter.info(&format!(
+            "Found {} command-line tool invocations",
+            imports.commands().len()
+        ));
+    }
+    let dynamic = imports.unresolved_dynamic_imports().len();
+    if dynamic > 0 {
+        reporter.warn(&format!(
+            "{} dynamic imports build their specifier at runtime and couldn't be tied to a package; unused results may be incomplete",
+            dynamic
+        ));
+    }
+
+    // 4. Report results
+    if options.unused_only {
+        reporter.report_unused(&analysis, package_manager);
+    } else if options.phantom_only {
+        reporter.report_phantom(&analysis, package_manager);
+    } else {
+        reporter.report_full(&analysis, &imports, package_manager);
+    }
+
+    let ownership = Ownership::load(path, &config)?;
+    if let Some(group_by) = options.group_by {
+        let owners = match group_by {
+            GroupBy::Owner => {
+                if ownership.is_empty() {
+                    reporter.warn(
+                        "No CODEOWNERS file or [owners] in depx.toml; every finding is unowned",
+                    );
+                }
+                Some(&ownership)
+            }
+            GroupBy::Dir => None,
+        };
+        let groups = grouping::group_findings(path, &analysis, &imports, group_by, owners);
+        reporter.report_groups(&groups, group_by);
+    }
+
+    let snapshot = AnalysisSnapshot::from_analysis(&analysis).with_owners(
+        grouping::finding_owners(path, &analysis, &imports, &ownership),
+    );
+    if let Some(compare) = &options.compare {
+        let previous = AnalysisSnapshot::load(compare)?;
+        reporter.report_snapshot_diff(&snapshot.diff(&previous), compare);
+    }
+    if let Some(save) = &options.save {
+        snapshot.save(save)?;
+        reporter.info(&format!("Saved findings to {}", save.display()));
+    }
+    if let Some(plan) = &options.fix_plan {
+        write_fix_plan(reporter, plan, fix::uninstall_unused(&analysis))?;
+    }
+
+    // 5. Per-entry-point reachability, when entry points are configured
+    if !config.entry_points.is_empty() {
+        let entry_points = EntryPointAnalyzer::new(path, &config.entry_points)
+            .analyze(&imports, &installed_packages)?;
+        reporter.report_entry_points(&entry_points);
+    }
+
+    for violation in policy.violations(&analysis) {
+        reporter.error(&violation);
+    }
+
+    let status = policy.status(&analysis);
+    Ok((analysis, status))
+}
+
+/// `depx analyze` for each project below `path`, then a summary of them all
+///
+/// A project that can't be analyzed (no lockfile yet, a lockfile depx can't
+/// read) is reported and skipped, and makes the run end with an error.
+async fn run_analyze_recursive(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+    options: &AnalyzeOptions,
+    policy: &AnalyzePolicy,
+) -> Result<ExitStatus> {
+    if selection.lockfile.is_some() {
+        miette::bail!(
+            "--recursive finds each project's lockfiles; it can't be used with --lockfile"
+        );
+    }
+
+    let projects = workspace::project_dirs(path, selection.ecosystem)?;
+    if projects.is_empty() {
+        miette::bail!("No projects with a lockfile found under {}", path.display());
+    }
+    reporter.info(&format!(
+        "Found {} projects under {}",
+        projects.len(),
+        path.display()
+    ));
+
+    let ownership = Ownership::load(path, &Config::load(path)?)?;
+    let mut status = ExitStatus::Success;
+    let mut summaries = Vec::new();
+    for project in projects {
+        let relative = project.strip_prefix(path).unwrap_or(&project);
+        let relative = if relative.as_os_str().is_empty() {
+            PathBuf::from(".")
+        } else {
+            relative.to_path_buf()
+        };
+        let owners = ownership.of_dir(&relative);
+        reporter.project_header(&relative);
+
+        let summary = match run_analyze(reporter, &project, selection, options, policy).await {
+            Ok((analysis, project_status)) => {
+                if project_status != ExitStatus::Success && status == ExitStatus::Success {
+                    status = project_status;
+                }
+                ProjectSummary {
+                    path: relative,
+                    unused: analysis.unused_direct.len(),
+                    phantom: analysis.phantom.len(),
+                    error: None,
+                    owners,
+                }
+            }
+            Err(e) => {
+                reporter.error(&e.to_string());
+                status = ExitStatus::Error;
+                ProjectSummary {
+                    path: relative,
+                    unused: 0,
+                    phantom: 0,
+                    error: Some(e.to_string()),
+                    owners,
+                }
+            }
+        };
+        summaries.push(summary);
+    }
+
+    reporter.report_projects(&summaries);
+    Ok(status)
+}
+
+/// Imports of the project's source, read by the scanner for its language
+fn project_imports(
+    path: &Path,
+    lockfile_parser: &LockfileParser,
+    installed_packages: &HashMap<String, Package>,
+) -> Result<ImportMap> {
+    match lockfile_parser.lockfile_type() {
+        LockfileType::Poetry | LockfileType::Pip => {
+            PythonImportScanner::new(path).scan(installed_packages)
+        }
+        LockfileType::Go => GoImportScanner::new(path).scan(installed_packages),
+        LockfileType::Cargo => RustUseScanner::new(path).scan(installed_packages),
+        _ => ImportAnalyzer::new(path).analyze(),
+    }
+}
+
+/// The selected lockfiles and their packages: each ecosystem's, just
+/// `--ecosystem`'s, or the `--lockfile`
+///
+/// A lockfile depx can't read yet (yarn.lock) is skipped with a warning
+/// when there are others, so a Tauri app using yarn still gets its crates
+/// checked.
+fn parse_lockfiles(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+) -> Result<Vec<(LockfileParser, HashMap<String, Package>)>> {
+    let lockfile_parsers = selection.lockfiles(path)?;
+    let several = lockfile_parsers.len() > 1;
+
+    let mut lockfiles = Vec::new();
+    for lockfile_parser in lockfile_parsers {
+        match lockfile_parser.parse() {
+            Ok(packages) => lockfiles.push((lockfile_parser, packages)),
+            Err(e) if several => {
+                reporter.warn(&format!("Skipping {}: {}", lockfile_parser.file_name(), e));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if lockfiles.is_empty() {
+        return Err(miette::miette!(
+            "None of the lockfiles in {} could be read",
+            path.display()
+        ));
+    }
+    Ok(lockfiles)
+}
+
+/// Usage of one lockfile's packages by its ecosystem's imports
+fn usage_analysis(
+    path: &Path,
+    installed_packages: &HashMap<String, Package>,
+    imports: &ImportMap,
+    include_dev: bool,
+) -> Result<UsageAnalysis> {
+    let (peers, _) = lockfile::declared_peers(&path.join("package.json"))?;
+    let graph = DependencyGraph::new(installed_packages);
+    let mut used_packages = imports.packages_used();
+    used_packages.extend(commands::resolve_invoked_packages(
+        imports.commands(),
+        installed_packages,
+    ));
+    let mut analysis = graph.analyze_usage(&used_packages, include_dev);
+    analysis.phantom = graph.find_phantom(imports);
+    let peers = peers.into_keys().collect();
+    analysis.misplaced = graph.find_misplaced(imports, &peers);
+    analysis.type_only = graph.find_type_only(imports, &peers);
+    Ok(analysis)
+}
+
+/// One report from each lockfile's analysis; `parse_lockfiles` never
+/// returns none
+fn merge_analyses(analyses: Vec<UsageAnalysis>) -> UsageAnalysis {
+    analyses
+        .into_iter()
+        .reduce(|mut merged, analysis| {
+            merged.merge(analysis);
+            merged
+        })
+        .expect("at least one lockfile is analyzed")
+}
+
+fn run_explain_rule(path: &Path, selection: &LockfileSelection, package: &str) -> Result<()> {
+    let reporter = Reporter::new();
+    let disabled = Config::load(path)?.disabled_rules();
+
+    let mut found = false;
+    for (lockfile_parser, installed_packages) in parse_lockfiles(&reporter, path, selection)? {
+        let imports = project_imports(path, &lockfile_parser, &installed_packages)?;
+
+        let graph = DependencyGraph::new(&installed_packages);
+        let mut used_packages = imports.packages_used();
+        used_packages.extend(commands::resolve_invoked_packages(
+            imports.commands(),
+            &installed_packages,
+        ));
+
+        for mut classification in graph.classify(package, &used_packages, &imports) {
+            rules::suppress_classification(&mut classification, &disabled);
+            reporter.report_classification(
+                &classification,
+                PackageManager::of(lockfile_parser.lockfile_type()),
+            );
+            found = true;
+        }
+    }
+    if !found {
+        reporter.error(&format!("Package '{}' not found in dependencies", package));
+    }
+
+    Ok(())
+}
+
+async fn run_why(path: &Path, selection: &LockfileSelection, package: &str) -> Result<()> {
+    let reporter = Reporter::new();
+
+    let mut found = false;
+    for (lockfile_parser, installed_packages) in parse_lockfiles(&reporter, path, selection)? {
+        let graph = DependencyGraph::new(&installed_packages);
+        let manifest = update::manifest_name(lockfile_parser.lockfile_type());
+
+        // Cargo packages are keyed by name@version; explain every installed version
+        let mut keys: Vec<&String> = if installed_packages.contains_key(package) {
+            vec![installed_packages.get_key_value(package).unwrap().0]
+        } else {
+            installed_packages
+                .iter()
+                .filter(|(_, pkg)| pkg.name == package)
+                .map(|(key, _)| key)
+                .collect()
+        };
+        keys.sort();
+
+        for explanation in keys.iter().filter_map(|key| graph.explain_package(key)) {
+            reporter.report_why(&explanation, manifest);
+            found = true;
+        }
+    }
+    if !found {
+        reporter.error(&format!("Package '{}' not found in dependencies", package));
+    }
+
+    Ok(())
+}
+
+async fn run_graph(path: &Path, selection: &LockfileSelection, audit: bool) -> Result<()> {
+    let (_, export) = graph_export(&Reporter::new(), path, selection, audit).await?;
+    let output = serde_json::to_string_pretty(&export)
+        .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+    println!("{}", output);
+
+    Ok(())
+}
+
+async fn run_serve(
+    path: &Path,
+    selection: &LockfileSelection,
+    port: u16,
+    audit: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Loading",
+        &format!("dependency graph at {}", path.display()),
+    );
+
+    let (graphs, export) = graph_export(&reporter, path, selection, audit).await?;
+    let server = Arc::new(serve::GraphServer::new(graphs, &export)?);
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to listen on port {}", port))?;
+    let address = listener.local_addr().into_diagnostic()?;
+
+    reporter.status(
+        "Serving",
+        &format!(
+            "{} packages at http://{}/ (Ctrl+C to stop)",
+            export.nodes.len(),
+            address
+        ),
+    );
+
+    tokio::select! {
+        served = server.serve(listener) => served,
+        _ = tokio::signal::ctrl_c() => Ok(()),
+    }
+}
+
+/// Dependency graphs of the selected lockfiles and their combined export,
+/// with advisories from OSV marked when `audit` is set
+async fn graph_export(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+    audit: bool,
+) -> Result<(Vec<DependencyGraph>, GraphExport)> {
+    let config = Config::load(path)?;
+
+    let mut graphs = Vec::new();
+    let mut export = GraphExport::default();
+    for (lockfile_parser, packages) in parse_lockfiles(reporter, path, selection)? {
+        let vulnerabilities = if audit {
+            let lockfile_type = lockfile_parser.lockfile_type();
+            let registries = Registries::new(lockfile_type, &config.registries)?;
+            let mut found = vulnerability::check_vulnerabilities(
+                &registries.public_packages(&packages),
+                None,
+                lockfile_type.osv_ecosystem(),
+            )
+            .await?
+            .vulnerabilities;
+            found.retain(|vuln| !config.audit.ignore.contains_key(&vuln.id));
+            found
+        } else {
+            Vec::new()
+        };
+
+        let graph = DependencyGraph::new(&packages);
+        let lockfile_export = graph.export(&vulnerabilities);
+        export.nodes.extend(lockfile_export.nodes);
+        export.edges.extend(lockfile_export.edges);
+        graphs.push(graph);
+    }
+
+    Ok((graphs, export))
+}
+
+/// Options for `depx audit`
+struct AuditOptions {
+    used_only: bool,
+    fix_plan: Option<PathBuf>,
+    vex: Option<(PathBuf, vex::VexFormat)>,
+    since_last: bool,
+}
+
+async fn run_audit(
+    path: &Path,
+    selection: &LockfileSelection,
+    options: &AuditOptions,
+    json: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+    let used_only = options.used_only;
+
+    reporter.status("Auditing", &format!("project at {}", path.display()));
+
+    let config = Config::load(path)?;
+    // A lockfile skipped here would have its vulnerabilities go unreported
+    let lockfiles = selection
+        .lockfiles(path)?
+        .into_iter()
+        .map(|lockfile_parser| {
+            let packages = lockfile_parser.parse()?;
+            Ok((lockfile_parser, packages))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    // The same lockfiles audited earlier today give the same result
+    let lockfile_paths: Vec<&Path> = lockfiles.iter().map(|(parser, _)| parser.path()).collect();
+    let lockfile_hash = audit_cache::lockfile_hash(&lockfile_paths)?;
+    // What --used-only finds used decides `affects_used_code`, so it's
+    // part of what a cached result depends on
+    let used: Vec<Option<HashSet<String>>> = lockfiles
+        .iter()
+        .map(|(lockfile_parser, packages)| -> Result<_> {
+            if !used_only {
+                return Ok(None);
+            }
+            let imports = project_imports(path, lockfile_parser, packages)?;
+            let mut used = imports.packages_used();
+            used.extend(commands::resolve_invoked_packages(
+                imports.commands(),
+                packages,
+            ));
+            Ok(Some(used))
+        })
+        .collect::<Result<_>>()?;
+    let used_names: Option<Vec<String>> = used_only.then(|| {
+        let mut names: Vec<String> = used.iter().flatten().flatten().cloned().collect();
+        names.sort();
+        names.dedup();
+        names
+    });
+    let previous = AuditCache::load(path)?;
+    let cached = previous
+        .as_ref()
+        .filter(|cache| cache.is_current(&lockfile_hash, used_names.as_deref()));
+
+    let mut installed_packages = HashMap::new();
+    let mut manifests = HashMap::new();
+    let mut vulnerabilities = Vec::new();
+    let mut complete = true;
+    let mut private = 0;
+    for ((lockfile_parser, packages), used_packages) in lockfiles.into_iter().zip(used) {
+        let manifest = update::manifest_name(lockfile_parser.lockfile_type());
+        for name in packages.keys() {
+            manifests.entry(name.clone()).or_insert(manifest);
+        }
+        // Public advisories don't describe packages from private registries
+        let registries = Registries::new(lockfile_parser.lockfile_type(), &config.registries)?;
+        let audited = registries.public_packages(&packages);
+        private += packages.len() - audited.len();
+
+        if cached.is_none() {
+            let mut scan = vulnerability::check_vulnerabilities(
+                &audited,
+                used_packages.as_ref(),
+                lockfile_parser.lockfile_type().osv_ecosystem(),
+            )
+            .await?;
+            complete &= scan.complete;
+            // Git and local packages have no purl of their own, but the
+            // advisory is about the registry package of their ecosystem
+            let purl_type = lockfile_parser.lockfile_type().purl_type();
+            for vuln in &mut scan.vulnerabilities {
+                vuln.purl.get_or_insert_with(|| {
+                    sbom::package_url(purl_type, &vuln.package_name, &vuln.installed_version)
+                });
+            }
+            vulnerabilities.extend(scan.vulnerabilities);
+        }
+        installed_packages.extend(packages);
+    }
+    vulnerability::sort_vulnerabilities(&mut vulnerabilities);
+
+    if let Some(cache) = cached {
+        vulnerabilities = cache.vulnerabilities.clone();
+        reporter.info(&format!(
+            "Lockfiles unchanged since today's audit; reusing its results from {}",
+            audit_cache::AUDIT_CACHE
+        ));
+    } else if !complete {
+        // A partial result would hide the missing advisories until tomorrow
+        reporter.warn(&format!(
+            "Some OSV requests failed, so vulnerabilities may be missing; not saving the audit to {}",
+            audit_cache::AUDIT_CACHE
+        ));
+    } else if let Err(e) = AuditCache::new(lockfile_hash, used_names, &vulnerabilities).save(path) {
+        reporter.warn(&format!("Couldn't save the audit: {}", e));
+    }
+
+    // Owners are looked up on every run, cached results included, so
+    // CODEOWNERS edits show up the same day
+    let ownership = Ownership::load(path, &config)?;
+    for vuln in &mut vulnerabilities {
+        let manifest = manifests
+            .get(&vuln.package_name)
+            .copied()
+            .unwrap_or("package.json");
+        vuln.owners = ownership.of_package(&vuln.package_name, Path::new(manifest));
+    }
+
+    if private > 0 {
+        reporter.info(&format!(
+            "Not auditing {} served by private registries",
+            if private == 1 {
+                "1 package".to_string()
+            } else {
+                format!("{} packages", private)
+            }
+        ));
+    }
+
+    let ignore = config.audit.ignore;
+
+    if let Some((file, format)) = &options.vex {
+        let document = vex::VexDocument::new(&vulnerabilities, &ignore, used_only);
+        document.write(file, *format)?;
+        reporter.info(&format!(
+            "Wrote {} VEX statements to {}",
+            vulnerabilities.len(),
+            file.display()
+        ));
+    }
+
+    // Advisories triaged in depx.toml stay in the VEX document only
+    let (ignored, vulnerabilities): (Vec<_>, Vec<_>) = vulnerabilities
+        .into_iter()
+        .partition(|vuln| ignore.contains_key(&vuln.id));
+    if !ignored.is_empty() {
+        reporter.info(&format!(
+            "Ignoring {} listed in {}",
+            if ignored.len() == 1 {
+                "1 vulnerability".to_string()
+            } else {
+                format!("{} vulnerabilities", ignored.len())
+            },
+            config::CONFIG_FILE
+        ));
+    }
+
+    if options.since_last {
+        // Findings from the failed requests would show up as resolved
+        if !complete {
+            return Err(miette::miette!(
+                "Some OSV requests failed, so this audit can't be compared with the last one. Try again later"
+            ));
+        }
+        let previous = previous.map(|mut cache| {
+            cache
+                .vulnerabilities
+                .retain(|vuln| !ignore.contains_key(&vuln.id));
+            cache
+        });
+        let changes = AuditChanges::between(previous.as_ref(), &vulnerabilities);
+        if json {
+            let output = serde_json::to_string_pretty(&changes)
+                .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+            println!("{}", output);
+        } else {
+            reporter.report_audit_changes(&changes);
+        }
+    } else if json {
+        let output = serde_json::to_string_pretty(&vulnerabilities)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_vulnerabilities(&vulnerabilities);
+    }
+
+    if let Some(plan) = &options.fix_plan {
+        let actions = fix::patch_vulnerabilities(&vulnerabilities, &installed_packages);
+        write_fix_plan(&reporter, plan, actions)?;
+    }
+
+    Ok(())
+}
+
+async fn run_deprecated(path: &Path, selection: &LockfileSelection, wide: bool) -> Result<()> {
+    let reporter = Reporter::new().wide(wide);
+
+    reporter.status("Checking", "for deprecated packages");
+
+    let deprecated = collect_deprecated(&reporter, path, selection).await?;
+    reporter.report_deprecated(&deprecated);
+
+    Ok(())
+}
+
+/// Deprecated packages of every selected lockfile, from the lockfile when
+/// it records deprecations and the registry otherwise
+async fn collect_deprecated(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+) -> Result<Vec<DeprecatedPackage>> {
+    let config = Config::load(path)?;
+    let mut deprecated = Vec::new();
+    for (lockfile_parser, installed_packages) in parse_lockfiles(reporter, path, selection)? {
+        let lockfile_type = lockfile_parser.lockfile_type();
+
+        // npm and pnpm lockfiles record npm deprecations, though not JSR yanks;
+        // Go modules have none to look up
+        let records_deprecations = matches!(
+            lockfile_type,
+            LockfileType::Npm | LockfileType::Pnpm | LockfileType::Go
+        );
+        let published: Vec<&Package> = installed_packages
+            .values()
+            .filter(|p| {
+                p.source == types::PackageSource::Jsr
+                    || (p.is_from_registry() && !records_deprecations)
+            })
+            .collect();
+        let metadata = if published.is_empty() {
+            HashMap::new()
+        } else {
+            let registries = Registries::new(lockfile_type, &config.registries)?;
+            registries.fetch_all(&registry::client()?, &published).await
+        };
+        deprecated.extend(vulnerability::check_deprecated(&installed_packages, &metadata).await?);
+    }
+    deprecated.sort_by(|a, b| a.package.name.cmp(&b.package.name));
+
+    Ok(deprecated)
+}
+
+async fn run_duplicates(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+    fix_plan: Option<&Path>,
+    json: bool,
+) -> Result<ExitStatus> {
+    reporter.status("Analyzing", &format!("duplicates at {}", path.display()));
+
+    let analyzer = duplicates::DuplicateAnalyzer::new(path);
+    let config = Config::load(path)?;
+    let ownership = Ownership::load(path, &config)?;
+
+    // Lockfiles without duplicate analysis are skipped alongside others
+    let lockfile_parsers = selection.lockfiles(path)?;
+    let several = lockfile_parsers.len() > 1;
+    let mut analyses = Vec::new();
+    for lockfile_parser in &lockfile_parsers {
+        let mut analysis = match analyzer.analyze_lockfile(lockfile_parser) {
+            Ok(analysis) => analysis,
+            Err(e) if several => {
+                reporter.warn(&format!("Skipping {}: {}", lockfile_parser.file_name(), e));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        let manifest = Path::new(update::manifest_name(lockfile_parser.lockfile_type()));
+        for group in &mut analysis.duplicates {
+            group.owners = ownership.of_package(&group.name, manifest);
+        }
+        analyses.push(analysis);
+    }
+    let analysis = duplicates::suppress(duplicates::merge(analyses), &config.disabled_rules());
+
+    if json {
+        let output = serde_json::to_string_pretty(&analysis)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_duplicates(&analysis);
+    }
+
+    if let Some(plan) = fix_plan {
+        write_fix_plan(reporter, plan, fix::dedupe_duplicates(&analysis))?;
+    }
+
+    let violations = policy::duplicate_violations(&config.rules, &analysis);
+    if !json {
+        for violation in &violations {
+            reporter.error(violation);
+        }
+    }
+    Ok(if violations.is_empty() {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+async fn run_metrics(
+    path: &Path,
+    selection: &LockfileSelection,
+    prometheus: bool,
+    json: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+    let config = Config::load(path)?;
+
+    let mut packages = HashMap::new();
+    let mut analyses = Vec::new();
+    let mut duplicates = 0;
+    let mut vulnerabilities = Vec::new();
+    let mut complete = true;
+    for (lockfile_parser, lockfile_packages) in parse_lockfiles(&reporter, path, selection)? {
+        let imports = project_imports(path, &lockfile_parser, &lockfile_packages)?;
+        analyses.push(usage_analysis(path, &lockfile_packages, &imports, true)?);
+
+        // Counted the way `depx duplicates` does, where it can
+        duplicates +=
+            match duplicates::DuplicateAnalyzer::new(path).analyze_lockfile(&lockfile_parser) {
+                Ok(analysis) => analysis.duplicates.len(),
+                Err(_) => metrics::duplicate_names(&lockfile_packages),
+            };
+
+        let lockfile_type = lockfile_parser.lockfile_type();
+        let registries = Registries::new(lockfile_type, &config.registries)?;
+        let scan = vulnerability::check_vulnerabilities(
+            &registries.public_packages(&lockfile_packages),
+            None,
+            lockfile_type.osv_ecosystem(),
+        )
+        .await?;
+        complete &= scan.complete;
+        vulnerabilities.extend(scan.vulnerabilities);
+        packages.extend(lockfile_packages);
+    }
+    vulnerabilities.retain(|vuln| !config.audit.ignore.contains_key(&vuln.id));
+    if !complete {
+        reporter.warn("Some OSV requests failed; leaving out the vulnerability counts");
+    }
+    let mut analysis = merge_analyses(analyses);
+    rules::suppress_usage(&mut analysis, &config.disabled_rules());
+
+    let metrics = types::DependencyMetrics::new(
+        project_name(path)?,
+        &packages,
+        &analysis,
+        duplicates,
+        complete.then_some(vulnerabilities.as_slice()),
+    );
+    if prometheus {
+        print!("{}", metrics.to_prometheus());
+    } else if json {
+        let output = serde_json::to_string_pretty(&metrics)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_metrics(&metrics);
+    }
+
+    Ok(())
+}
+
+async fn run_overrides(path: &Path, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Auditing", &format!("overrides at {}", path.display()));
+
+    let analyzer = overrides::OverrideAnalyzer::new(path);
+    let mut analysis = analyzer.analyze()?;
+
+    overrides::check_override_vulnerabilities(&mut analysis).await?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&analysis)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_overrides(&analysis);
+    }
+
+    Ok(())
+}
+
+/// Where and how `depx check --notify` posts its summary
+struct NotifyOptions {
+    url: String,
+    format: notify::NotifyFormat,
+    state: Option<PathBuf>,
+}
+
+/// Where `depx check --history` keeps first-seen dates, and the SLA
+struct TrackingOptions {
+    history: PathBuf,
+    max_critical_age: Option<i64>,
+}
+
+/// Registry and advisory lookups shared by `--notify` and `--max-critical-age`
+struct Advisories {
+    packages: HashMap<String, types::Package>,
+    vulnerabilities: Vec<types::Vulnerability>,
+    deprecated: Vec<types::DeprecatedPackage>,
+}
+
+async fn lookup_advisories(
+    reporter: &Reporter,
+    path: &Path,
+    selection: &LockfileSelection,
+) -> Result<Advisories> {
+    let config = Config::load(path)?;
+
+    let mut packages = HashMap::new();
+    let mut vulnerabilities = Vec::new();
+    let mut deprecated = Vec::new();
+    for (lockfile_parser, lockfile_packages) in parse_lockfiles(reporter, path, selection)? {
+        let lockfile_type = lockfile_parser.lockfile_type();
+        let registries = Registries::new(lockfile_type, &config.registries)?;
+
+        vulnerabilities.extend(
+            vulnerability::check_vulnerabilities(
+                &registries.public_packages(&lockfile_packages),
+                None,
+                lockfile_type.osv_ecosystem(),
+            )
+            .await?
+            .vulnerabilities,
+        );
+        deprecated
+            .extend(vulnerability::check_deprecated(&lockfile_packages, &HashMap::new()).await?);
+        packages.extend(lockfile_packages);
+    }
+    vulnerability::sort_vulnerabilities(&mut vulnerabilities);
+    deprecated.sort_by(|a, b| a.package.name.cmp(&b.package.name));
+
+    Ok(Advisories {
+        packages,
+        vulnerabilities,
+        deprecated,
+    })
+}
+
+async fn run_check(
+    path: &Path,
+    selection: &LockfileSelection,
+    verify_tarballs: bool,
+    notify: Option<&NotifyOptions>,
+    tracking: Option<&TrackingOptions>,
+    lint: &[RulePack],
+    json: bool,
+) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status("Checking", &format!("lockfile at {}", path.display()));
+
+    let config = Config::load(path)?;
+    let mut report = integrity::IntegrityChecker::new(path)
+        .lockfile(selection.lockfile.as_deref())
+        .verify_tarballs(verify_tarballs)
+        .allowed_registries(&config.allowed_registries)
+        .check()?;
+
+    let ownership = Ownership::load(path, &config)?;
+    for issue in &mut report.issues {
+        // Entries under a workspace member ("packages/ui/node_modules/x") belong to it
+        let member = issue
+            .path
+            .split_once("node_modules/")
+            .map_or(issue.path.as_str(), |(member, _)| member);
+        issue.owners =
+            ownership.of_package(&issue.package, &Path::new(member).join("package.json"));
+    }
+
+    let packs: Vec<RulePack> = config.lint.iter().chain(lint).copied().collect();
+    if !packs.is_empty() {
+        let lockfile = selection.lockfile(path, Some(Ecosystem::Js))?;
+        report.lint = lint::Linter::new(path, &packs).lint(&lockfile)?;
+    }
+
+    let max_critical_age = tracking.and_then(|t| t.max_critical_age);
+    let advisories = if notify.is_some() || max_critical_age.is_some() {
+        Some(lookup_advisories(&reporter, path, selection).await?)
+    } else {
+        None
+    };
+
+    if let Some(tracking) = tracking {
+        let mut history = FindingHistory::load(&tracking.history)?;
+        let today = date::today();
+
+        let keys: Vec<String> = report.issues.iter().map(history::integrity_key).collect();
+        history.observe(history::INTEGRITY, &keys, today);
+        for (issue, key) in report.issues.iter_mut().zip(&keys) {
+            issue.first_seen = history.first_seen(history::INTEGRITY, key, today);
+        }
+
+        if let Some(advisories) = &advisories {
+            let keys: Vec<String> = advisories
+                .vulnerabilities
+                .iter()
+                .map(history::vulnerability_key)
+                .collect();
+            history.observe(history::VULNERABILITY, &keys, today);
+
+            if let Some(max_age) = max_critical_age {
+                report.critical_vulnerabilities = advisories
+                    .vulnerabilities
+                    .iter()
+                    .filter(|v| v.severity == Severity::Critical)
+                    .map(|v| {
+                        let key = history::vulnerability_key(v);
+                        let first_seen = history.first_seen(history::VULNERABILITY, &key, today);
+                        TrackedVulnerability {
+                            vulnerability: v.clone(),
+                            past_sla: first_seen.as_ref().is_some_and(|f| f.days_open > max_age),
+                            first_seen,
+                        }
+                    })
+                    .collect();
+                report
+                    .critical_vulnerabilities
+                    .sort_by(|a, b| a.vulnerability.id.cmp(&b.vulnerability.id));
+            }
+        }
+
+        history.save(&tracking.history)?;
+    }
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_check(&report, verify_tarballs, max_critical_age);
+    }
+
+    if let (Some(options), Some(advisories)) = (notify, &advisories) {
+        notify_findings(&reporter, path, options, advisories, report.issues.len()).await?;
+    }
+
+    let past_sla = report.critical_vulnerabilities.iter().any(|v| v.past_sla);
+    // Low findings are advice
+    let lint_failed = report.lint.iter().any(|f| f.severity >= Severity::Medium);
+    Ok(if report.issues.is_empty() && !past_sla && !lint_failed {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+fn run_diff(
+    path: &Path,
+    selection: &LockfileSelection,
+    sbom_path: &Path,
+    json: bool,
+) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Comparing",
+        &format!(
+            "{} with the lockfile at {}",
+            sbom_path.display(),
+            path.display()
+        ),
+    );
+
+    // SBOM components without a package URL can't be told apart by ecosystem,
+    // so this compares one lockfile
+    let sbom = sbom::Sbom::load(sbom_path)?;
+    let lockfile_parser = selection.lockfile(path, None)?;
+    // Versions come from the full package set, which has the platform builds
+    // and bundled packages `parse_versions` leaves out; `parse_versions` only
+    // tells which packages are the project's own
+    let local: HashSet<String> = lockfile_parser
+        .parse_versions()?
+        .into_iter()
+        .filter(|(_, versions)| versions.iter().any(|v| v.is_path_dep))
+        .map(|(name, _)| name)
+        .collect();
+    let mut installed: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+    for package in lockfile_parser.parse()?.into_values() {
+        let is_path_dep = local.contains(&package.name);
+        installed
+            .entry(package.name)
+            .or_default()
+            .push(CargoPackageInfo {
+                version: package.version,
+                is_path_dep,
+                ..Default::default()
+            });
+    }
+    let diff = sbom.diff(&installed, lockfile_parser.lockfile_type().purl_type());
+
+    if json {
+        let output = serde_json::to_string_pretty(&diff)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_sbom_diff(&diff, sbom_path);
+    }
+
+    Ok(if diff.is_empty() {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+fn run_ci(
+    path: &Path,
+    selection: &LockfileSelection,
+    provider: Option<ci::CiProvider>,
+    report: Option<&Path>,
+) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+    let provider = provider.unwrap_or_else(ci::CiProvider::detect);
+
+    reporter.status(
+        "Analyzing",
+        &format!("project at {} ({})", path.display(), provider),
+    );
+
+    let config = Config::load(path)?;
+    let policy = config.ci.policy().with_rules(&config.rules);
+
+    let lockfiles = parse_lockfiles(&reporter, path, selection)?;
+    let package_manager = PackageManager::of(lockfiles[0].0.lockfile_type());
+    let mut imports = ImportMap::new();
+    let mut analyses = Vec::new();
+    for (lockfile_parser, installed_packages) in lockfiles {
+        let lockfile_imports = project_imports(path, &lockfile_parser, &installed_packages)?;
+        analyses.push(usage_analysis(
+            path,
+            &installed_packages,
+            &lockfile_imports,
+            true,
+        )?);
+        imports.merge(lockfile_imports);
+    }
+    let mut analysis = merge_analyses(analyses);
+    rules::suppress_usage(&mut analysis, &config.disabled_rules());
+
+    let baselined = match &config.ci.baseline {
+        Some(baseline) => {
+            let baseline = AnalysisSnapshot::load(&path.join(baseline))?;
+            ci::apply_baseline(&mut analysis, &baseline)
+        }
+        None => 0,
+    };
+
+    reporter.report_full(&analysis, &imports, package_manager);
+    if baselined > 0 {
+        reporter.info(&format!(
+            "{} findings already in the baseline were not reported",
+            baselined
+        ));
+    }
+
+    let annotations = ci::annotations(path, &analysis, &policy)?;
+    if let Err(e) = ci::publish(provider, &annotations, baselined, report) {
+        reporter.warn(&format!("Couldn't publish annotations: {}", e));
+    }
+
+    for violation in policy.violations(&analysis) {
+        reporter.error(&violation);
+    }
+
+    Ok(policy.status(&analysis))
+}
+
+async fn notify_findings(
+    reporter: &Reporter,
+    path: &Path,
+    options: &NotifyOptions,
+    advisories: &Advisories,
+    integrity_issues: usize,
+) -> Result<()> {
+    let Advisories {
+        packages,
+        vulnerabilities,
+        deprecated,
+    } = advisories;
+
+    let current = NotifyState::new(packages, vulnerabilities, deprecated);
+    let previous = match &options.state {
+        Some(state) => NotifyState::load(state)?,
+        None => None,
+    };
+
+    let summary = NotifySummary::new(
+        project_name(path)?,
+        &current,
+        previous.as_ref(),
+        vulnerabilities,
+        deprecated,
+        integrity_issues,
+    );
+    notify::send(&options.url, &summary.payload(options.format)?).await?;
+    reporter.status("Notified", &summary.headline());
+
+    // Only advance the state once the notification went out
+    if let Some(state) = &options.state {
+        current.save(state)?;
+    }
+
+    Ok(())
+}
+
+/// The package.json name, or the directory name
+fn project_name(path: &Path) -> Result<String> {
+    let manifest_path = path.join("package.json");
+    if manifest_path.exists() {
+        let content = std::fs::read_to_string(&manifest_path)
+            .into_diagnostic()
+            .with_context(|| "Failed to read package.json")?;
+        let manifest: serde_json::Value = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package.json")?;
+        if let Some(name) = manifest.get("name").and_then(|v| v.as_str()) {
+            return Ok(name.to_string());
+        }
+    }
+
+    let dir = analyzer::resolve::normalize(path);
+    Ok(dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| ".".to_string()))
+}
+
+fn run_prepublish(path: &Path, json: bool) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Checking",
+        &format!("publishable packages at {}", path.display()),
+    );
+
+    let mut checks = publish::PublishChecker::new(path).check()?;
+
+    let ownership = Ownership::load(path, &Config::load(path)?)?;
+    for check in &mut checks {
+        let dir = check.dir.strip_prefix(path).unwrap_or(&check.dir);
+        check.owners = ownership.of_package(&check.name, &dir.join("package.json"));
+    }
+
+    if json {
+        let output = serde_json::to_string_pretty(&checks)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_prepublish(path, &checks);
+    }
+
+    Ok(if checks.iter().all(|c| c.issues.is_empty()) {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+fn run_deadcode(path: &Path, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Analyzing", &format!("modules at {}", path.display()));
+
+    let config = Config::load(path)?;
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let report = deadcode::DeadCodeAnalyzer::new(path, &config.entry_points).analyze(&imports)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_deadcode(&report);
+    }
+
+    Ok(())
+}
+
+fn run_unused_exports(path: &Path, workspace: bool, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Analyzing", &format!("exports at {}", path.display()));
+
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let report = unused_exports::UnusedExportsAnalyzer::new(path, workspace).analyze(&imports)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_unused_exports(&report);
+    }
+
+    Ok(())
+}
+
+fn run_coupling(path: &Path, top: usize, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Analyzing", &format!("modules at {}", path.display()));
+
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let report = coupling::analyze(&imports);
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_coupling(&report, top);
+    }
+
+    Ok(())
+}
+
+fn run_bins(path: &Path, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Checking", &format!("bin entries at {}", path.display()));
+
+    let entries = bins::BinAnalyzer::new(path).analyze()?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&entries)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_bins(&entries);
+    }
+
+    Ok(())
+}
+
+fn run_peers(path: &Path, selection: &LockfileSelection, json: bool) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Checking",
+        &format!("peer dependencies at {}", path.display()),
+    );
+
+    let lockfile_parser = selection.lockfile(path, Some(Ecosystem::Js))?;
+    let installed_packages = lockfile_parser.parse()?;
+    let mut analyzer = peers::PeerAnalyzer::new(path);
+    match catalog::Catalogs::load(path) {
+        Ok(catalogs) => analyzer = analyzer.with_catalogs(catalogs),
+        Err(e) => reporter.warn(&format!("Not resolving catalog: peer ranges: {}", e)),
+    }
+    if lockfile_parser.lockfile_type() == LockfileType::Npm {
+        let installs =
+            NpmLockfileParser::new(lockfile_parser.root(), lockfile_parser.lockfile_path())
+                .parse_install_paths()?;
+        if !installs.is_empty() {
+            analyzer = analyzer.with_install_paths(installs);
+        }
+    }
+    let issues = analyzer.analyze(&installed_packages)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&issues)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_peers(&issues);
+    }
+
+    Ok(if issues.is_empty() {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+fn run_engines(path: &Path, selection: &LockfileSelection, json: bool) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Checking",
+        &format!("engine constraints at {}", path.display()),
+    );
+
+    let lockfile = selection.lockfile(path, Some(Ecosystem::Js))?;
+    let report = engines::EnginesAnalyzer::new(path).analyze(&lockfile)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_engines(&report);
+    }
+
+    Ok(
+        if report.conflicts.is_empty() && report.manager_mismatch.is_none() {
+            ExitStatus::Success
+        } else {
+            ExitStatus::Failure
+        },
+    )
+}
+
+fn run_licenses(path: &Path, selection: &LockfileSelection, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Reading", &format!("licenses at {}", path.display()));
+
+    let packages = selection.lockfile(path, Some(Ecosystem::Js))?.parse()?;
+    let report = licenses::LicenseAnalyzer::new(path).analyze(&packages);
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_licenses(&report);
+    }
+
+    Ok(())
+}
+
+fn run_pinning(path: &Path, selection: &LockfileSelection, json: bool) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Checking",
+        &format!("dependency ranges at {}", path.display()),
+    );
+
+    let report = pinning_report(path, selection)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_pinning(&report);
+    }
+
+    Ok(if report.deviations.is_empty() {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+/// Range styles across the project's manifests, held to `[pinning] policy`
+fn pinning_report(path: &Path, selection: &LockfileSelection) -> Result<PinningReport> {
+    let config = Config::load(path)?;
+    let mut analyzer = pinning::PinningAnalyzer::new(path, config.pinning.policy);
+    // Without a lockfile, ranges naming no version just get no suggestion
+    let installed = match selection.lockfile(path, Some(Ecosystem::Js)) {
+        Ok(lockfile) => {
+            if lockfile.lockfile_type() == LockfileType::Npm {
+                let installs = NpmLockfileParser::new(lockfile.root(), lockfile.lockfile_path())
+                    .parse_install_paths()?;
+                if !installs.is_empty() {
+                    analyzer = analyzer.with_install_paths(installs);
+                }
+            }
+            lockfile.parse()?
+        }
+        Err(_) => HashMap::new(),
+    };
+    analyzer.analyze(&installed)
+}
+
+fn run_align(path: &Path, json: bool) -> Result<ExitStatus> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Checking",
+        &format!("workspace dependency ranges at {}", path.display()),
+    );
+
+    let report = alignment_report(path)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_alignment(&report);
+    }
+
+    Ok(if report.mismatches.is_empty() {
+        ExitStatus::Success
+    } else {
+        ExitStatus::Failure
+    })
+}
+
+/// Ranges workspace members disagree on, aligned per `[align]`, yarn
+/// constraints and pnpm catalogs
+fn alignment_report(path: &Path) -> Result<AlignmentReport> {
+    let config = Config::load(path)?;
+    let catalogs = catalog::Catalogs::load(path)?;
+    align::AlignmentAnalyzer::new(path, &config.align, &catalogs).analyze()
+}
+
+fn run_bundle(
+    path: &Path,
+    stats: &Path,
+    selection: &LockfileSelection,
+    top: usize,
+    json: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Reading", &format!("bundle stats from {}", stats.display()));
+
+    let installed_packages = selection.lockfile(path, Some(Ecosystem::Js))?.parse()?;
+    let graph = DependencyGraph::new(&installed_packages);
+    let report = bundle::BundleAnalyzer::new(stats).analyze(&graph)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_bundle(&report, top);
+    }
+
+    Ok(())
+}
+
+fn run_coverage(
+    path: &Path,
+    report: &Path,
+    selection: &LockfileSelection,
+    json: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status("Reading", &format!("coverage from {}", report.display()));
+
+    let installed_packages = selection.lockfile(path, Some(Ecosystem::Js))?.parse()?;
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let coverage =
+        coverage::CoverageAnalyzer::new(path, report).analyze(&installed_packages, &imports)?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&coverage)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_coverage(&coverage);
+    }
+
+    Ok(())
+}
+
+fn run_vendored(path: &Path, json: bool) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Scanning",
+        &format!("for vendored packages at {}", path.display()),
+    );
+
+    let packages = vendored::VendoredAnalyzer::new(path).analyze()?;
+
+    if json {
+        let output = serde_json::to_string_pretty(&packages)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_vendored(&packages);
+    }
+
+    Ok(())
+}
+
+fn write_fix_plan(reporter: &Reporter, path: &Path, actions: Vec<FixAction>) -> Result<()> {
+    let added = actions.len();
+    let total = fix::write_plan(path, actions)?;
+    reporter.info(&format!(
+        "Wrote {} fixes to {} ({} total)",
+        added,
+        path.display(),
+        total
+    ));
+    Ok(())
+}
+
+/// What `depx fix` was asked to do
+struct Fixes {
+    plan: Option<PathBuf>,
+    moves: bool,
+    rename: bool,
+    pinning: bool,
+    align: bool,
+}
+
+async fn run_fix(
+    path: &Path,
+    selection: &LockfileSelection,
+    fixes: &Fixes,
+    dry_run: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    if fixes.plan.is_none() && !fixes.moves && !fixes.rename && !fixes.pinning && !fixes.align {
+        return Err(miette::miette!(
+            "Nothing to fix: pass --plan <file> (written by `depx analyze --fix-plan`), --moves, --rename, --pinning or --align"
+        ));
+    }
+
+    let lockfile_type = fix::lockfile_type(path, selection)?;
+    let mut manifest = fix::load_manifest(path)?;
+    let imports = if fixes.moves || fixes.rename {
+        ImportAnalyzer::new(path).analyze()?
+    } else {
+        ImportMap::new()
+    };
+
+    let pinning = if fixes.pinning {
+        Some(pinning_report(path, selection)?)
+    } else {
+        None
+    };
+    let alignment = if fixes.align {
+        Some(alignment_report(path)?)
+    } else {
+        None
+    };
+    let root_manifest = Path::new("package.json");
+    // A dependency being aligned isn't restyled too, so the ranges members
+    // agree on stay as written
+    let range_actions = |manifest: &Path| -> Vec<FixAction> {
+        let mut actions = match &alignment {
+            Some(report) => align::align_actions(report, manifest),
+            None => Vec::new(),
+        };
+        if let Some(report) = &pinning {
+            let aligned: HashSet<String> =
+                actions.iter().map(|a| a.package().to_string()).collect();
+            actions.extend(
+                pinning::restyle_actions(report, manifest)
+                    .into_iter()
+                    .filter(|action| !aligned.contains(action.package())),
+            );
+        }
+        actions
+    };
+
+    if fixes.plan.is_some() || fixes.moves || fixes.pinning || fixes.align {
+        reporter.status("Fixing", &format!("package.json at {}", path.display()));
+
+        let mut actions = match &fixes.plan {
+            Some(plan) => fix::load_plan(plan)?.actions,
+            None => Vec::new(),
+        };
+        if fixes.moves {
+            let installed_packages = selection.lockfile(path, Some(Ecosystem::Js))?.parse()?;
+            let (peers, _) = lockfile::declared_peers(&path.join("package.json"))?;
+            let misplaced = DependencyGraph::new(&installed_packages)
+                .find_misplaced(&imports, &peers.into_keys().collect());
+            actions.extend(fix::plan_moves(&manifest, &misplaced));
+        }
+        actions.extend(range_actions(root_manifest));
+
+        let outcome = fix::apply(lockfile_type, &mut manifest, &actions, dry_run)?;
+        if dry_run {
+            reporter.report_fix_diff(&outcome, "package.json");
+        } else {
+            reporter.report_fix(&outcome);
+        }
+    }
+
+    // Workspace members only get their ranges restyled and aligned
+    let mut members: Vec<&PathBuf> = pinning
+        .iter()
+        .flat_map(|report| &report.manifests)
+        .chain(alignment.iter().flat_map(|report| &report.manifests))
+        .filter(|m| *m != root_manifest)
+        .collect();
+    members.sort();
+    members.dedup();
+    for member in members {
+        let actions = range_actions(member);
+        if actions.is_empty() {
+            continue;
+        }
+        let dir = path.join(member.parent().unwrap_or(Path::new("")));
+        reporter.status("Fixing", &format!("package.json at {}", dir.display()));
+
+        let mut manifest = fix::load_manifest(&dir)?;
+        let outcome = fix::apply(lockfile_type, &mut manifest, &actions, dry_run)?;
+        if dry_run {
+            reporter.report_fix_diff(&outcome, &member.display().to_string());
+        } else {
+            reporter.report_fix(&outcome);
+        }
+    }
+
+    if fixes.rename {
+        reporter.status("Renaming", &format!("imports at {}", path.display()));
+
+        let selection = LockfileSelection {
+            ecosystem: Some(Ecosystem::Js),
+            ..selection.clone()
+        };
+        let deprecated = collect_deprecated(&reporter, path, &selection).await?;
+        let renames = fix::rename::plan_renames(&imports, &deprecated);
+        let config = Config::load(path)?;
+        let registries = Registries::new(LockfileType::Npm, &config.registries)?;
+        let (renames, unpublished) =
+            fix::rename::check_published(renames, &registries, &registry::client()?).await;
+        for rename in &unpublished {
+            reporter.warn(&format!(
+                "Not renaming {}: the registry has no {}",
+                rename.from, rename.to
+            ));
+        }
+        let files = fix::rename::rewrite_imports(&imports, &renames, dry_run)?;
+        reporter.report_renames(&renames, &files, dry_run);
+    }
+
+    Ok(())
+}
+
+fn run_rewrite(path: &Path, from: &str, to: &str, dry_run: bool) -> Result<()> {
+    let reporter = Reporter::new();
+    let rewrite = ImportRewrite::new(from, to)?;
+
+    reporter.status(
+        "Rewriting",
+        &format!(
+            "imports of {} to {} at {}",
+            rewrite.from,
+            rewrite.to,
+            path.display()
+        ),
+    );
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let files = fix::rewrite::rewrite_imports(&imports, std::slice::from_ref(&rewrite), dry_run)?;
+    reporter.report_rewrite(&rewrite, &files, dry_run);
+
+    Ok(())
+}
+
+fn run_clean(
+    path: &Path,
+    selection: &LockfileSelection,
+    interactive: bool,
+    dry_run: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    if interactive && !std::io::stdin().is_terminal() {
+        return Err(miette::miette!(
+            "depx clean --interactive needs a terminal; drop --interactive to remove every unused dependency"
+        ));
+    }
+
+    reporter.status("Cleaning", &format!("project at {}", path.display()));
+
+    let lockfile_type = fix::lockfile_type(path, selection)?;
+    let mut manifest = fix::load_manifest(path)?;
+    let installed_packages = selection.lockfile(path, Some(Ecosystem::Js))?.parse()?;
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let graph = DependencyGraph::new(&installed_packages);
+
+    let mut used_packages = imports.packages_used();
+    used_packages.extend(commands::resolve_invoked_packages(
+        imports.commands(),
+        &installed_packages,
+    ));
+    let analysis = graph.analyze_usage(&used_packages, true);
+
+    let mut actions = fix::uninstall_unused(&analysis);
+    if interactive && !actions.is_empty() {
+        let candidates = clean::candidates(path, &analysis, &installed_packages);
+        let mut selected = Vec::new();
+        let mut remove_rest = false;
+
+        for (index, (candidate, action)) in candidates.iter().zip(actions).enumerate() {
+            if remove_rest {
+                selected.push(action);
+                continue;
+            }
+            reporter.report_clean_candidate(
+                candidate,
+                index + 1,
+                candidates.len(),
+                PackageManager::of(lockfile_type.unwrap_or(LockfileType::Npm)),
+            );
+            match clean::ask(&format!("Remove {}?", candidate.package.name))? {
+                clean::Answer::Remove => selected.push(action),
+                clean::Answer::Keep => {}
+                clean::Answer::RemoveRest => {
+                    selected.push(action);
+                    remove_rest = true;
+                }
+                clean::Answer::Quit => break,
+            }
+        }
+        actions = selected;
+    }
+
+    let outcome = fix::apply(lockfile_type, &mut manifest, &actions, dry_run)?;
+    if dry_run {
+        reporter.report_fix_diff(&outcome, "package.json");
+    } else {
+        reporter.report_fix(&outcome);
+    }
+
+    Ok(())
+}
+
+/// How `depx update` was asked to run
+struct UpdateOptions {
+    interactive: bool,
+    dry_run: bool,
+    changelog: bool,
+    api_diff: bool,
+}
+
+async fn run_update(
+    path: &Path,
+    selection: &LockfileSelection,
+    options: &UpdateOptions,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    if options.interactive && !std::io::stdin().is_terminal() {
+        return Err(miette::miette!(
+            "depx update --interactive needs a terminal to pick updates"
+        ));
+    }
+
+    reporter.status("Checking", &format!("updates at {}", path.display()));
+
+    // Each ecosystem has its own manifest to write, so updates are picked
+    // one lockfile at a time
+    let config = Config::load(path)?;
+    for (lockfile_parser, packages) in parse_lockfiles(&reporter, path, selection)? {
+        update_lockfile(
+            &reporter,
+            path,
+            &config,
+            &lockfile_parser,
+            &packages,
+            options,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+async fn update_lockfile(
+    reporter: &Reporter,
+    path: &Path,
+    config: &Config,
+    lockfile_parser: &LockfileParser,
+    packages: &HashMap<String, Package>,
+    options: &UpdateOptions,
+) -> Result<()> {
+    let lockfile_type = lockfile_parser.lockfile_type();
+    // Git and local packages have no registry releases to update to
+    let direct: Vec<&Package> = packages
+        .values()
+        .filter(|p| p.is_direct && p.has_registry_releases())
+        .collect();
+    let registries = Registries::new(lockfile_type, &config.registries)?;
+    let client = registry::client()?;
+    let metadata = registries.fetch_all(&client, &direct).await;
+    if metadata.is_empty() && !direct.is_empty() {
+        return Err(miette::miette!(
+            "Couldn't fetch registry metadata for any dependency"
+        ));
+    }
+
+    let direct: std::collections::HashSet<String> = direct.iter().map(|p| p.name.clone()).collect();
+    let vulnerabilities = match vulnerability::check_vulnerabilities(
+        &registries.public_packages(packages),
+        Some(&direct),
+        lockfile_type.osv_ecosystem(),
+    )
+    .await
+    {
+        Ok(scan) => scan.vulnerabilities,
+        Err(e) => {
+            reporter.warn(&format!("Skipping vulnerability annotations: {}", e));
+            Vec::new()
+        }
+    };
+
+    let mut candidates = update::candidates(packages, &metadata, &vulnerabilities);
+    if options.changelog && !candidates.is_empty() {
+        reporter.status("Reading", "release notes");
+        let mut notes = changelog::fetch_release_notes(&client, &candidates, &metadata).await;
+        if notes.is_empty() {
+            reporter.warn(
+                "No release notes found on GitHub; set GITHUB_TOKEN if the API is rate limited",
+            );
+        }
+        for candidate in &mut candidates {
+            candidate.notes = notes.remove(&candidate.package);
+        }
+    }
+    if options.api_diff && !candidates.is_empty() {
+        if lockfile_type == LockfileType::Cargo {
+            reporter.status("Comparing", "public APIs");
+            let checksums =
+                lockfile::CargoLockfileParser::new(lockfile_parser.lockfile_path()).checksums()?;
+            let mut impacts =
+                api_diff::api_impact(&client, path, &candidates, packages, &checksums).await?;
+            if impacts.is_empty() {
+                reporter.warn("Couldn't read the sources of any crate to compare");
+            }
+            for candidate in &mut candidates {
+                candidate.api = impacts.remove(&candidate.package);
+            }
+        } else {
+            reporter.warn(&format!(
+                "--api-diff compares Cargo crates only; skipping {}",
+                update::manifest_name(lockfile_type)
+            ));
+        }
+    }
+    reporter.report_updates(&candidates, options.interactive);
+    if !options.interactive || candidates.is_empty() {
+        return Ok(());
+    }
+
+    let selected: Vec<_> = update::ask_selection(candidates.len())?
+        .into_iter()
+        .map(|index| candidates[index].clone())
+        .collect();
+    if selected.is_empty() {
+        reporter.info("Nothing selected; the manifest was not changed");
+        return Ok(());
+    }
+
+    let outcome = update::apply(path, lockfile_type, &selected, options.dry_run)?;
+    if options.dry_run {
+        reporter.report_fix_diff(&outcome, update::manifest_name(lockfile_type));
+    } else {
+        reporter.report_fix(&outcome);
+    }
+
+    Ok(())
+}
+
+fn run_types(path: &Path, selection: &LockfileSelection, verbose: bool, json: bool) -> Result<()> {
+    let reporter = if verbose {
+        Reporter::new().verbose()
+    } else {
+        Reporter::new()
+    };
+
+    reporter.status(
+        "Checking",
+        &format!("type declarations at {}", path.display()),
+    );
+
+    let imports = ImportAnalyzer::new(path).analyze()?;
+    let installed_packages = selection.lockfile(path, Some(Ecosystem::Js))?.parse()?;
+    let results = typings::TypesAnalyzer::new(path).analyze(&imports, &installed_packages);
+
+    if json {
+        let output = serde_json::to_string_pretty(&results)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_types(&results);
+    }
+
+    Ok(())
+}
+
+async fn run_health(
+    path: &Path,
+    selection: &LockfileSelection,
+    sort: HealthSort,
+    transitive: bool,
+    json: bool,
+) -> Result<()> {
+    let reporter = Reporter::new();
+
+    reporter.status(
+        "Checking",
+        &format!("dependency health at {}", path.display()),
+    );
+
+    let client = registry::client()?;
+    let config = Config::load(path)?;
+    let mut report = types::HealthReport {
+        direct: Vec::new(),
+        obscure_transitive: Vec::new(),
+    };
+    for (lockfile_parser, installed_packages) in parse_lockfiles(&reporter, path, selection)? {
+        let lockfile_type = lockfile_parser.lockfile_type();
+        let direct = health::direct_dependencies(&installed_packages);
+
+        let registries = Registries::new(lockfile_type, &config.registries)?;
+        let direct_refs: Vec<&Package> = direct.iter().collect();
+        let mut metadata = registries.fetch_all(&client, &direct_refs).await;
+        registries
+            .fetch_owners(&client, &direct_refs, &mut metadata)
+            .await;
+
+        // Download counts not already included with the metadata
+        let transitive_packages = if transitive {
+            health::transitive_packages(&installed_packages, &direct)
+        } else {
+            Vec::new()
+        };
+        let download_packages: Vec<&Package> = direct
+            .iter()
+            .filter(|p| {
+                metadata
+                    .get(&p.name)
+                    .is_none_or(|m| m.weekly_downloads.is_none())
+            })
+            .chain(transitive_packages.iter().copied())
+            .collect();
+        let downloads = registries
+            .fetch_weekly_downloads(&client, &download_packages)
+            .await;
+
+        let direct_packages: std::collections::HashMap<String, types::Package> = direct
+            .iter()
+            .filter(|p| registries.is_public(p))
+            .map(|p| (format!("{}@{}", p.name, p.version), p.clone()))
+            .collect();
+        let vulnerabilities = vulnerability::check_vulnerabilities(
+            &direct_packages,
+            None,
+            lockfile_type.osv_ecosystem(),
+        )
+        .await?
+        .vulnerabilities;
+
+        report.direct.extend(health::build_report(
+            &direct,
+            &metadata,
+            &downloads,
+            &vulnerabilities,
+            date::today(),
+        ));
+        if transitive {
+            report.obscure_transitive.extend(health::obscure_transitive(
+                &installed_packages,
+                &direct,
+                &downloads,
+            ));
+        }
+    }
+    health::sort_report(&mut report.direct, sort);
+    report
+        .obscure_transitive
+        .sort_by_key(|p| p.weekly_downloads);
+
+    if json {
+        let output = serde_json::to_string_pretty(&report)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        println!("{}", output);
+    } else {
+        reporter.report_health(&report);
     }
 
     Ok(())
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::Write;
+
+use crate::types::{DependencyMetrics, Package, Severity, UsageAnalysis, Vulnerability};
+
+const SEVERITIES: [Severity; 4] = [
+    Severity::Low,
+    Severity::Medium,
+    Severity::High,
+    Severity::Critical,
+];
+
+impl DependencyMetrics {
+    /// `duplicates` as `depx duplicates` counts them, and `vulnerabilities`
+    /// unless the advisory lookup failed
+    pub fn new(
+        project: String,
+        packages: &HashMap<String, Package>,
+        analysis: &UsageAnalysis,
+        duplicates: usize,
+        vulnerabilities: Option<&[Vulnerability]>,
+    ) -> Self {
+        let vulnerabilities = vulnerabilities.map(|vulnerabilities| {
+            let mut by_severity: BTreeMap<Severity, usize> =
+                SEVERITIES.iter().map(|severity| (*severity, 0)).collect();
+            for vulnerability in vulnerabilities {
+                *by_severity.entry(vulnerability.severity).or_default() += 1;
+            }
+            by_severity
+        });
+
+        Self {
+            project,
+            packages: packages.len(),
+            direct: packages.values().filter(|p| p.is_direct).count(),
+            unused: analysis.unused_direct.len(),
+            phantom: analysis.phantom.len(),
+            duplicates,
+            vulnerabilities,
+        }
+    }
+
+    /// The metrics in the Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let project = format!("project=\"{}\"", escape_label(&self.project));
+        let mut output = String::new();
+        let mut gauge = |name: &str, help: &str, samples: &[(String, usize)]| {
+            let _ = writeln!(output, "# HELP depx_{} {}", name, help);
+            let _ = writeln!(output, "# TYPE depx_{} gauge", name);
+            for (labels, value) in samples {
+                let _ = writeln!(output, "depx_{}{{{}}} {}", name, labels, value);
+            }
+        };
+
+        gauge(
+            "packages",
+            "Installed packages, direct and transitive.",
+            &[
+                (format!("{},kind=\"direct\"", project), self.direct),
+                (
+                    format!("{},kind=\"transitive\"", project),
+                    self.packages - self.direct,
+                ),
+            ],
+        );
+        gauge(
+            "unused_dependencies",
+            "Direct dependencies nothing imports or runs.",
+            &[(project.clone(), self.unused)],
+        );
+        gauge(
+            "phantom_dependencies",
+            "Packages imported without being declared.",
+            &[(project.clone(), self.phantom)],
+        );
+        gauge(
+            "duplicate_packages",
+            "Package names installed in more than one version.",
+            &[(project.clone(), self.duplicates)],
+        );
+        // No gauge beats a row of zeros when the advisories couldn't be read
+        if let Some(vulnerabilities) = &self.vulnerabilities {
+            let vulnerabilities: Vec<(String, usize)> = vulnerabilities
+                .iter()
+                .map(|(severity, count)| (format!("{},severity=\"{}\"", project, severity), *count))
+                .collect();
+            gauge(
+                "vulnerabilities",
+                "Known vulnerabilities in installed packages, by severity.",
+                &vulnerabilities,
+            );
+        }
+        output
+    }
+}
+
+/// Package names installed in more than one version, for lockfiles
+/// `depx duplicates` doesn't analyze
+pub fn duplicate_names(packages: &HashMap<String, Package>) -> usize {
+    let mut versions: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for package in packages.values() {
+        versions
+            .entry(&package.name)
+            .or_default()
+            .insert(&package.version);
+    }
+    versions.values().filter(|v| v.len() > 1).count()
+}
+
+/// Escape a label value: backslashes, double quotes and newlines
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graph::DependencyGraph;
+
+    #[test]
+    fn test_prometheus() {
+        let mut packages = HashMap::new();
+        packages.insert(
+            "react".to_string(),
+            Package::new("react", "18.2.0").direct(),
+        );
+        packages.insert("ms".to_string(), Package::new("ms", "2.1.3").direct());
+        packages.insert("ms@2.0.0".to_string(), Package::new("ms", "2.0.0"));
+        let used = HashSet::from(["react".to_string()]);
+        let analysis = DependencyGraph::new(&packages).analyze_usage(&used, true);
+        let vulnerability = Vulnerability {
+            id: "GHSA-0000-0000-0000".to_string(),
+            title: "ReDoS".to_string(),
+            severity: Severity::High,
+            package_name: "ms".to_string(),
+            vulnerable_range: "<2.0.0".to_string(),
+            patched_version: Some("2.0.0".to_string()),
+            url: None,
+            affects_used_code: false,
+            installed_version: "2.0.0".to_string(),
+            purl: None,
+            cpe: None,
+            owners: Vec::new(),
+        };
+
+        let metrics = DependencyMetrics::new(
+            "acme \"web\"".to_string(),
+            &packages,
+            &analysis,
+            duplicate_names(&packages),
+            Some(&[vulnerability]),
+        );
+        assert_eq!(metrics.duplicates, 1);
+
+        let output = metrics.to_prometheus();
+        assert!(output.contains("# TYPE depx_packages gauge\n"));
+        assert!(output.contains("depx_packages{project=\"acme \\\"web\\\"\",kind=\"direct\"} 2\n"));
+        assert!(
+            output.contains("depx_packages{project=\"acme \\\"web\\\"\",kind=\"transitive\"} 1\n")
+        );
+        assert!(output.contains("depx_unused_dependencies{project=\"acme \\\"web\\\"\"} 1\n"));
+        assert!(output.contains("depx_duplicate_packages{project=\"acme \\\"web\\\"\"} 1\n"));
+        assert!(output
+            .contains("depx_vulnerabilities{project=\"acme \\\"web\\\"\",severity=\"high\"} 1\n"));
+        assert!(output.contains(
+            "depx_vulnerabilities{project=\"acme \\\"web\\\"\",severity=\"critical\"} 0\n"
+        ));
+
+        let unaudited = DependencyMetrics::new("web".to_string(), &packages, &analysis, 0, None)
+            .to_prometheus();
+        assert!(!unaudited.contains("depx_vulnerabilities"));
+    }
+}
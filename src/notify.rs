@@ -0,0 +1,329 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+
+use clap::ValueEnum;
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::types::{
+    DeprecatedPackage, DeprecationNotice, NotifyState, NotifySummary, Package, Vulnerability,
+};
+
+/// Current state format
+const STATE_VERSION: u32 = 1;
+
+/// Payload posted to the webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NotifyFormat {
+    /// The summary as JSON
+    Json,
+    /// A Slack incoming-webhook message
+    Slack,
+}
+
+impl NotifyFormat {
+    /// Slack for Slack webhook URLs, JSON otherwise
+    pub fn for_url(url: &str) -> Self {
+        let host = reqwest::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().map(|h| h.to_string()));
+        if host.as_deref() == Some("hooks.slack.com") {
+            NotifyFormat::Slack
+        } else {
+            NotifyFormat::Json
+        }
+    }
+}
+
+impl NotifyState {
+    pub fn new(
+        packages: &HashMap<String, Package>,
+        vulnerabilities: &[Vulnerability],
+        deprecated: &[DeprecatedPackage],
+    ) -> Self {
+        Self {
+            version: STATE_VERSION,
+            packages: packages.len(),
+            vulnerabilities: vulnerabilities
+                .iter()
+                .map(vulnerability_key)
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+            deprecated: deprecated
+                .iter()
+                .map(|d| format!("{}@{}", d.package.name, d.package.version))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Load the previous state, or `None` on the first run
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read state {}", path.display()))?;
+        let state: Self = serde_json::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse state {}: {}", path.display(), e))?;
+
+        if state.version > STATE_VERSION {
+            return Err(miette::miette!(
+                "State {} was written by a newer depx (format {}, expected {})",
+                path.display(),
+                state.version,
+                STATE_VERSION
+            ));
+        }
+        Ok(Some(state))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let output = serde_json::to_string_pretty(self)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        std::fs::write(path, output + "\n")
+            .into_diagnostic()
+            .with_context(|| format!("Failed to write state {}", path.display()))
+    }
+}
+
+fn vulnerability_key(vuln: &Vulnerability) -> String {
+    format!(
+        "{} {}@{}",
+        vuln.id, vuln.package_name, vuln.installed_version
+    )
+}
+
+impl NotifySummary {
+    /// Findings not in the previous state; everything is new on the first run
+    pub fn new(
+        project: String,
+        current: &NotifyState,
+        previous: Option<&NotifyState>,
+        vulnerabilities: &[Vulnerability],
+        deprecated: &[DeprecatedPackage],
+        integrity_issues: usize,
+    ) -> Self {
+        let is_new_vuln = |v: &&Vulnerability| {
+            previous.is_none_or(|p| !p.vulnerabilities.contains(&vulnerability_key(v)))
+        };
+        let is_new_deprecation = |d: &&DeprecatedPackage| {
+            let key = format!("{}@{}", d.package.name, d.package.version);
+            previous.is_none_or(|p| !p.deprecated.contains(&key))
+        };
+
+        let mut new_vulnerabilities: Vec<Vulnerability> = vulnerabilities
+            .iter()
+            .filter(is_new_vuln)
+            .cloned()
+            .collect();
+        new_vulnerabilities.sort_by(|a, b| {
+            b.severity
+                .cmp(&a.severity)
+                .then_with(|| a.package_name.cmp(&b.package_name))
+        });
+
+        Self {
+            project,
+            packages: current.packages,
+            package_delta: previous.map(|p| current.packages as i64 - p.packages as i64),
+            new_vulnerabilities,
+            new_deprecated: deprecated
+                .iter()
+                .filter(is_new_deprecation)
+                .map(|d| DeprecationNotice {
+                    package: d.package.name.clone(),
+                    version: d.package.version.clone(),
+                    message: d.message.clone(),
+                })
+                .collect(),
+            integrity_issues,
+        }
+    }
+
+    /// One-line headline, e.g. "app: 2 new vulnerabilities, 412 packages (+3)"
+    pub fn headline(&self) -> String {
+        let mut findings = Vec::new();
+        if !self.new_vulnerabilities.is_empty() {
+            findings.push(plural(
+                self.new_vulnerabilities.len(),
+                "new vulnerability",
+                "new vulnerabilities",
+            ));
+        }
+        if !self.new_deprecated.is_empty() {
+            findings.push(plural(
+                self.new_deprecated.len(),
+                "new deprecation",
+                "new deprecations",
+            ));
+        }
+        if self.integrity_issues > 0 {
+            findings.push(plural(
+                self.integrity_issues,
+                "lockfile issue",
+                "lockfile issues",
+            ));
+        }
+        if findings.is_empty() {
+            findings.push("nothing new".to_string());
+        }
+
+        let delta = match self.package_delta {
+            Some(delta) if delta != 0 => format!(" ({:+})", delta),
+            _ => String::new(),
+        };
+        format!(
+            "{}: {}, {} packages{}",
+            self.project,
+            findings.join(", "),
+            self.packages,
+            delta
+        )
+    }
+
+    pub fn payload(&self, format: NotifyFormat) -> Result<JsonValue> {
+        match format {
+            NotifyFormat::Json => serde_json::to_value(self)
+                .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e)),
+            NotifyFormat::Slack => Ok(serde_json::json!({ "text": self.slack_text() })),
+        }
+    }
+
+    /// Slack mrkdwn message: the headline and one line per finding
+    fn slack_text(&self) -> String {
+        let mut lines = vec![format!("*depx* {}", self.headline())];
+
+        for vuln in &self.new_vulnerabilities {
+            let title = match &vuln.url {
+                Some(url) => format!("<{}|{}>", url, vuln.id),
+                None => vuln.id.clone(),
+            };
+            let fix = match &vuln.patched_version {
+                Some(version) => format!(", fixed in {}", version),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "• [{}] {} `{}@{}`: {}{}",
+                vuln.severity, title, vuln.package_name, vuln.installed_version, vuln.title, fix
+            ));
+        }
+        for notice in &self.new_deprecated {
+            lines.push(format!(
+                "• [deprecated] `{}@{}`: {}",
+                notice.package, notice.version, notice.message
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+fn plural(count: usize, one: &str, many: &str) -> String {
+    format!("{} {}", count, if count == 1 { one } else { many })
+}
+
+/// Post the payload; a non-2xx response is an error
+pub async fn send(url: &str, payload: &JsonValue) -> Result<()> {
+    let client = crate::registry::client()?;
+    client
+        .post(url)
+        .json(payload)
+        .send()
+        .await
+        .into_diagnostic()
+        .with_context(|| "Failed to post notification")?
+        .error_for_status()
+        .into_diagnostic()
+        .with_context(|| "Notification webhook rejected the payload")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Severity;
+
+    fn vuln(id: &str, package: &str, severity: Severity) -> Vulnerability {
+        Vulnerability {
+            id: id.to_string(),
+            title: "Prototype pollution".to_string(),
+            severity,
+            package_name: package.to_string(),
+            vulnerable_range: "<4.17.21".to_string(),
+            patched_version: Some("4.17.21".to_string()),
+            url: None,
+            affects_used_code: true,
+            installed_version: "4.17.20".to_string(),
+            purl: None,
+            cpe: None,
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_summary_against_previous_state() {
+        let packages: HashMap<String, Package> = ["lodash", "minimist", "request"]
+            .iter()
+            .map(|n| (n.to_string(), Package::new(*n, "1.0.0")))
+            .collect();
+        let vulns = vec![
+            vuln("GHSA-old", "lodash", Severity::High),
+            vuln("GHSA-new", "minimist", Severity::Critical),
+        ];
+        let deprecated = vec![DeprecatedPackage {
+            package: Package::new("request", "1.0.0"),
+            message: "request has been deprecated".to_string(),
+            is_used: false,
+            successor: None,
+        }];
+
+        let current = NotifyState::new(&packages, &vulns, &deprecated);
+        let previous = NotifyState {
+            version: STATE_VERSION,
+            packages: 5,
+            vulnerabilities: vec!["GHSA-old lodash@4.17.20".to_string()],
+            deprecated: Vec::new(),
+        };
+
+        let summary = NotifySummary::new(
+            "app".to_string(),
+            &current,
+            Some(&previous),
+            &vulns,
+            &deprecated,
+            0,
+        );
+        let ids: Vec<&str> = summary
+            .new_vulnerabilities
+            .iter()
+            .map(|v| v.id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["GHSA-new"]);
+        assert_eq!(summary.new_deprecated.len(), 1);
+        assert_eq!(
+            summary.headline(),
+            "app: 1 new vulnerability, 1 new deprecation, 3 packages (-2)"
+        );
+
+        let first_run = NotifySummary::new("app".to_string(), &current, None, &vulns, &[], 0);
+        assert_eq!(first_run.new_vulnerabilities.len(), 2);
+        assert_eq!(first_run.package_delta, None);
+    }
+
+    #[test]
+    fn test_format_for_url() {
+        assert_eq!(
+            NotifyFormat::for_url("https://hooks.slack.com/services/T/B/X"),
+            NotifyFormat::Slack
+        );
+        assert_eq!(
+            NotifyFormat::for_url("https://example.com/hooks/depx"),
+            NotifyFormat::Json
+        );
+    }
+}
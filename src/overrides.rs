@@ -0,0 +1,635 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+
+use crate::lockfile::{
+    BunLockfileParser, CargoLockfileParser, CargoPackageInfo, NpmLockfileParser, PnpmLockfileParser,
+};
+use crate::range;
+use crate::types::{OverrideAnalysis, OverrideEntry, OverrideSource, OverrideStatus, Package};
+use crate::vulnerability;
+
+/// Analyzer for npm overrides, yarn resolutions, pnpm patches, and Cargo patches
+pub struct OverrideAnalyzer<'a> {
+    root: &'a Path,
+}
+
+/// What the lockfile tells us about the installed tree
+#[derive(Default)]
+struct InstalledTree {
+    /// Package name -> every installed version
+    versions: HashMap<String, Vec<String>>,
+
+    /// Installed packages with their declared dependency ranges
+    packages: HashMap<String, Package>,
+
+    /// Ranges the root manifest declares for its direct dependencies
+    root_ranges: HashMap<String, String>,
+
+    /// `[patch]` entries Cargo reports as unused
+    unused_patches: HashSet<String>,
+}
+
+impl<'a> OverrideAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    /// Collect every override in the project's manifests and classify it
+    pub fn analyze(&self) -> Result<OverrideAnalysis> {
+        let mut entries = Vec::new();
+
+        let package_json_path = self.root.join("package.json");
+        if package_json_path.exists() {
+            let manifest = read_json(&package_json_path)?;
+            let root_deps = manifest_dependencies(&manifest);
+
+            let tree = js_tree(self.root)?.map(|(versions, packages)| InstalledTree {
+                versions: versions
+                    .into_iter()
+                    .map(|(name, infos)| (name, infos.into_iter().map(|i| i.version).collect()))
+                    .collect(),
+                packages,
+                root_ranges: root_deps.clone(),
+                unused_patches: HashSet::new(),
+            });
+
+            let mut npm_entries = collect_js_overrides(&manifest, &root_deps);
+            for entry in &mut npm_entries {
+                if let Some(ref tree) = tree {
+                    classify(entry, tree);
+                }
+            }
+            entries.extend(npm_entries);
+        }
+
+        let cargo_toml_path = self.root.join("Cargo.toml");
+        if cargo_toml_path.exists() {
+            let content = std::fs::read_to_string(&cargo_toml_path)
+                .into_diagnostic()
+                .with_context(|| "Failed to read Cargo.toml")?;
+            let manifest: TomlValue = toml::from_str(&content)
+                .map_err(|e| miette::miette!("Failed to parse Cargo.toml: {}", e))?;
+
+            let lockfile_path = self.root.join("Cargo.lock");
+            let tree = if lockfile_path.exists() {
+                let parser = CargoLockfileParser::new(&lockfile_path);
+                Some(InstalledTree {
+                    versions: parser
+                        .parse_for_duplicates()?
+                        .into_iter()
+                        .map(|(name, infos)| (name, infos.into_iter().map(|i| i.version).collect()))
+                        .collect(),
+                    unused_patches: parser.unused_patches()?.into_iter().collect(),
+                    ..Default::default()
+                })
+            } else {
+                None
+            };
+
+            let mut cargo_entries = collect_cargo_patches(&manifest);
+            for entry in &mut cargo_entries {
+                if let Some(ref tree) = tree {
+                    classify(entry, tree);
+                }
+            }
+            entries.extend(cargo_entries);
+        }
+
+        entries.sort_by(|a, b| {
+            a.package
+                .cmp(&b.package)
+                .then_with(|| a.selector.cmp(&b.selector))
+        });
+
+        Ok(OverrideAnalysis { entries })
+    }
+}
+
+/// Query OSV for the versions each override forces and record any hits
+pub async fn check_override_vulnerabilities(analysis: &mut OverrideAnalysis) -> Result<()> {
+    for (ecosystem, is_cargo) in [("npm", false), ("crates.io", true)] {
+        let mut forced: HashMap<String, Package> = HashMap::new();
+
+        for entry in &analysis.entries {
+            let entry_is_cargo = matches!(
+                entry.source,
+                OverrideSource::CargoPatch | OverrideSource::CargoReplace
+            );
+            if entry_is_cargo != is_cargo {
+                continue;
+            }
+
+            for version in &entry.installed_versions {
+                let key = format!("{}@{}", entry.package, version);
+                forced.insert(key, Package::new(&entry.package, version));
+            }
+        }
+
+        if forced.is_empty() {
+            continue;
+        }
+
+        let vulnerabilities = vulnerability::check_vulnerabilities(&forced, None, ecosystem)
+            .await?
+            .vulnerabilities;
+
+        for entry in &mut analysis.entries {
+            for vuln in &vulnerabilities {
+                if vuln.package_name == entry.package
+                    && entry.installed_versions.contains(&vuln.installed_version)
+                    && !entry.vulnerabilities.contains(&vuln.id)
+                {
+                    entry.vulnerabilities.push(vuln.id.clone());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Installed versions and packages from the project's npm, pnpm or bun lockfile
+fn js_tree(
+    root: &Path,
+) -> Result<
+    Option<(
+        HashMap<String, Vec<CargoPackageInfo>>,
+        HashMap<String, Package>,
+    )>,
+> {
+    let npm_lock = root.join("package-lock.json");
+    if npm_lock.exists() {
+        let parser = NpmLockfileParser::new(root, &npm_lock);
+        return Ok(Some((parser.parse_for_duplicates()?, parser.parse()?)));
+    }
+    let pnpm_lock = root.join("pnpm-lock.yaml");
+    if pnpm_lock.exists() {
+        let parser = PnpmLockfileParser::new(root, &pnpm_lock);
+        return Ok(Some((parser.parse_for_duplicates()?, parser.parse()?)));
+    }
+    let bun_lock = root.join("bun.lock");
+    if bun_lock.exists() {
+        let parser = BunLockfileParser::new(root, &bun_lock);
+        return Ok(Some((parser.parse_for_duplicates()?, parser.parse()?)));
+    }
+    Ok(None)
+}
+
+/// Decide whether an override still has an effect on the installed tree
+fn classify(entry: &mut OverrideEntry, tree: &InstalledTree) {
+    entry.installed_versions = tree
+        .versions
+        .get(&entry.package)
+        .cloned()
+        .unwrap_or_default();
+
+    if tree.unused_patches.contains(&entry.package) || entry.installed_versions.is_empty() {
+        entry.status = OverrideStatus::Unused;
+        return;
+    }
+
+    match entry.source {
+        OverrideSource::PnpmPatch => {
+            // A patch targets one exact version; it stops applying once that version is gone
+            if let Some(version) = entry.selector.rsplit_once('@').map(|(_, v)| v) {
+                if !entry.installed_versions.iter().any(|v| v == version) {
+                    entry.status = OverrideStatus::Unused;
+                }
+            }
+        }
+        OverrideSource::CargoPatch | OverrideSource::CargoReplace => {}
+        _ => {
+            // (dependent, its range), with the root manifest as "package.json"
+            let mut requirements: Vec<(String, &String)> = tree
+                .packages
+                .values()
+                .filter(|p| entry.parent.as_ref().is_none_or(|parent| &p.name == parent))
+                .filter_map(|p| {
+                    let req = p.dependency_ranges.get(&entry.package)?;
+                    Some((format!("{}@{}", p.name, p.version), req))
+                })
+                .collect();
+
+            if entry.parent.is_none() {
+                requirements.extend(
+                    tree.root_ranges
+                        .get(&entry.package)
+                        .map(|req| ("package.json".to_string(), req)),
+                );
+            }
+
+            let accepts = |req: &str| {
+                entry
+                    .installed_versions
+                    .iter()
+                    .all(|v| range::satisfies(req, v) == Some(true))
+            };
+            // Dependents that would resolve to something else without the override
+            entry.overridden = requirements
+                .iter()
+                .filter(|(_, req)| range::satisfies(req, &entry.installed_versions[0]).is_some())
+                .filter(|(_, req)| !accepts(req))
+                .map(|(dependent, req)| format!("{} ({})", dependent, req))
+                .collect();
+            entry.overridden.sort();
+
+            // If every dependent already accepts what's installed, the override does nothing
+            let all_satisfied =
+                !requirements.is_empty() && requirements.iter().all(|(_, req)| accepts(req));
+            if all_satisfied {
+                entry.status = OverrideStatus::Stale;
+            }
+        }
+    }
+}
+
+fn read_json(path: &Path) -> Result<JsonValue> {
+    let content = std::fs::read_to_string(path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Ranges from `dependencies` and `devDependencies` of a package.json
+fn manifest_dependencies(manifest: &JsonValue) -> HashMap<String, String> {
+    ["dependencies", "devDependencies"]
+        .iter()
+        .filter_map(|field| manifest.get(field).and_then(|v| v.as_object()))
+        .flatten()
+        .filter_map(|(name, range)| Some((name.clone(), range.as_str()?.to_string())))
+        .collect()
+}
+
+/// Collect npm overrides, yarn resolutions, and pnpm overrides/patches from package.json
+fn collect_js_overrides(
+    manifest: &JsonValue,
+    root_deps: &HashMap<String, String>,
+) -> Vec<OverrideEntry> {
+    let mut entries = Vec::new();
+
+    if let Some(overrides) = manifest.get("overrides").and_then(|v| v.as_object()) {
+        collect_npm_overrides(overrides, None, root_deps, &mut entries);
+    }
+
+    if let Some(resolutions) = manifest.get("resolutions").and_then(|v| v.as_object()) {
+        for (selector, target) in resolutions {
+            let Some(target) = target.as_str() else {
+                continue;
+            };
+            let (parent, package) = split_yarn_selector(selector);
+            entries.push(new_entry(
+                OverrideSource::YarnResolutions,
+                selector,
+                package,
+                parent,
+                target,
+            ));
+        }
+    }
+
+    if let Some(pnpm) = manifest.get("pnpm") {
+        if let Some(overrides) = pnpm.get("overrides").and_then(|v| v.as_object()) {
+            for (selector, target) in overrides {
+                let Some(target) = target.as_str() else {
+                    continue;
+                };
+
+                // "parent@1>child@2" scopes the override below a parent
+                let (parent, child) = match selector.rsplit_once('>') {
+                    Some((parent, child)) => (Some(strip_version(parent)), child),
+                    None => (None, selector.as_str()),
+                };
+
+                entries.push(new_entry(
+                    OverrideSource::PnpmOverrides,
+                    selector,
+                    strip_version(child),
+                    parent,
+                    target,
+                ));
+            }
+        }
+
+        if let Some(patches) = pnpm.get("patchedDependencies").and_then(|v| v.as_object()) {
+            for (selector, patch_file) in patches {
+                let Some(patch_file) = patch_file.as_str() else {
+                    continue;
+                };
+                entries.push(new_entry(
+                    OverrideSource::PnpmPatch,
+                    selector,
+                    strip_version(selector),
+                    None,
+                    patch_file,
+                ));
+            }
+        }
+    }
+
+    entries
+}
+
+/// Walk npm's nested `overrides` object
+///
+/// `{ "foo": "1.0.0" }` overrides foo everywhere, while
+/// `{ "bar": { ".": "2.0.0", "foo": "1.0.0" } }` overrides bar itself and
+/// foo only below bar. `"$foo"` refers to the root's own range for foo.
+fn collect_npm_overrides(
+    overrides: &serde_json::Map<String, JsonValue>,
+    parent: Option<&str>,
+    root_deps: &HashMap<String, String>,
+    entries: &mut Vec<OverrideEntry>,
+) {
+    for (key, value) in overrides {
+        // "." is the parent's own override, handled by the caller
+        if key == "." {
+            continue;
+        }
+
+        let package = strip_version(key);
+        let selector = match parent {
+            Some(parent) => format!("{} > {}", parent, key),
+            None => key.clone(),
+        };
+
+        match value {
+            JsonValue::String(target) => {
+                let target = resolve_reference(target, root_deps);
+                entries.push(new_entry(
+                    OverrideSource::NpmOverrides,
+                    &selector,
+                    package,
+                    parent,
+                    &target,
+                ));
+            }
+            JsonValue::Object(nested) => {
+                if let Some(target) = nested.get(".").and_then(|v| v.as_str()) {
+                    let target = resolve_reference(target, root_deps);
+                    entries.push(new_entry(
+                        OverrideSource::NpmOverrides,
+                        &selector,
+                        package,
+                        parent,
+                        &target,
+                    ));
+                }
+
+                collect_npm_overrides(nested, Some(package), root_deps, entries);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Collect `[patch.<registry>]` and `[replace]` entries from a Cargo.toml
+fn collect_cargo_patches(manifest: &TomlValue) -> Vec<OverrideEntry> {
+    let mut entries = Vec::new();
+
+    if let Some(registries) = manifest.get("patch").and_then(|v| v.as_table()) {
+        for (registry, patches) in registries {
+            let Some(patches) = patches.as_table() else {
+                continue;
+            };
+
+            for (key, spec) in patches {
+                // `package = "real-name"` renames the patched crate
+                let package = spec.get("package").and_then(|v| v.as_str()).unwrap_or(key);
+
+                entries.push(new_entry(
+                    OverrideSource::CargoPatch,
+                    &format!("{}.{}", registry, key),
+                    package,
+                    None,
+                    &describe_cargo_source(spec),
+                ));
+            }
+        }
+    }
+
+    if let Some(replacements) = manifest.get("replace").and_then(|v| v.as_table()) {
+        for (key, spec) in replacements {
+            // Keys look like "foo:0.1.0"
+            let package = key.split(':').next().unwrap_or(key);
+            entries.push(new_entry(
+                OverrideSource::CargoReplace,
+                key,
+                package,
+                None,
+                &describe_cargo_source(spec),
+            ));
+        }
+    }
+
+    entries
+}
+
+fn describe_cargo_source(spec: &TomlValue) -> String {
+    if let Some(version) = spec.as_str() {
+        return version.to_string();
+    }
+
+    if let Some(git) = spec.get("git").and_then(|v| v.as_str()) {
+        let reference = ["branch", "tag", "rev"]
+            .iter()
+            .find_map(|key| spec.get(key).and_then(|v| v.as_str()).map(|r| (key, r)));
+        return match reference {
+            Some((key, r)) => format!("git {} ({} {})", git, key, r),
+            None => format!("git {}", git),
+        };
+    }
+
+    if let Some(path) = spec.get("path").and_then(|v| v.as_str()) {
+        return format!("path {}", path);
+    }
+
+    spec.get("version")
+        .and_then(|v| v.as_str())
+        .unwrap_or("*")
+        .to_string()
+}
+
+fn new_entry(
+    source: OverrideSource,
+    selector: &str,
+    package: &str,
+    parent: Option<&str>,
+    target: &str,
+) -> OverrideEntry {
+    OverrideEntry {
+        source,
+        selector: selector.to_string(),
+        package: package.to_string(),
+        parent: parent.map(|p| p.to_string()),
+        target: target.to_string(),
+        installed_versions: Vec::new(),
+        status: OverrideStatus::Active,
+        overridden: Vec::new(),
+        vulnerabilities: Vec::new(),
+    }
+}
+
+/// Resolve npm's "$name" references to the root's declared range
+fn resolve_reference(target: &str, root_deps: &HashMap<String, String>) -> String {
+    target
+        .strip_prefix('$')
+        .and_then(|name| root_deps.get(name))
+        .cloned()
+        .unwrap_or_else(|| target.to_string())
+}
+
+/// Strip a version selector from a package key ("foo@^1" -> "foo", "@s/foo@1" -> "@s/foo")
+fn strip_version(key: &str) -> &str {
+    let search_from = if key.starts_with('@') { 1 } else { 0 };
+    match key[search_from..].find('@') {
+        Some(idx) => &key[..idx + search_from],
+        None => key,
+    }
+}
+
+/// Split a yarn resolution path into (parent, package)
+///
+/// - "foo" -> (None, "foo")
+/// - "**/foo" -> (None, "foo")
+/// - "parent/foo" -> (Some("parent"), "foo")
+/// - "@s/parent/@s/foo" -> (Some("@s/parent"), "@s/foo")
+fn split_yarn_selector(selector: &str) -> (Option<&str>, &str) {
+    let mut names: Vec<&str> = Vec::new();
+    let mut rest = selector;
+
+    while !rest.is_empty() {
+        let segment_len = if rest.starts_with('@') {
+            match rest.find('/') {
+                Some(slash) => rest[slash + 1..]
+                    .find('/')
+                    .map(|i| slash + 1 + i)
+                    .unwrap_or(rest.len()),
+                None => rest.len(),
+            }
+        } else {
+            rest.find('/').unwrap_or(rest.len())
+        };
+
+        let segment = &rest[..segment_len];
+        if segment != "**" && !segment.is_empty() {
+            names.push(strip_version(segment));
+        }
+        rest = rest.get(segment_len + 1..).unwrap_or("");
+    }
+
+    match names.as_slice() {
+        [] => (None, selector),
+        [only] => (None, only),
+        [.., parent, last] => (Some(parent), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_version() {
+        assert_eq!(strip_version("foo"), "foo");
+        assert_eq!(strip_version("foo@^1.0.0"), "foo");
+        assert_eq!(strip_version("@scope/foo@1"), "@scope/foo");
+        assert_eq!(strip_version("@scope/foo"), "@scope/foo");
+    }
+
+    #[test]
+    fn test_split_yarn_selector() {
+        assert_eq!(split_yarn_selector("foo"), (None, "foo"));
+        assert_eq!(split_yarn_selector("**/foo"), (None, "foo"));
+        assert_eq!(split_yarn_selector("parent/foo"), (Some("parent"), "foo"));
+        assert_eq!(
+            split_yarn_selector("@s/parent/**/@s/foo"),
+            (Some("@s/parent"), "@s/foo")
+        );
+    }
+
+    #[test]
+    fn test_nested_npm_overrides() {
+        let manifest: JsonValue = serde_json::json!({
+            "dependencies": { "react": "^18.2.0" },
+            "overrides": {
+                "foo": "1.0.0",
+                "bar": { ".": "2.0.0", "baz": "3.0.0" },
+                "react-dom": { "react": "$react" }
+            }
+        });
+
+        let entries = collect_js_overrides(&manifest, &manifest_dependencies(&manifest));
+        let find = |selector: &str| entries.iter().find(|e| e.selector == selector).unwrap();
+
+        assert_eq!(find("foo").target, "1.0.0");
+        assert_eq!(find("bar").target, "2.0.0");
+        assert_eq!(find("bar > baz").parent.as_deref(), Some("bar"));
+        assert!(!entries.iter().any(|e| e.selector == "bar > ."));
+        assert_eq!(find("react-dom > react").target, "^18.2.0");
+    }
+
+    #[test]
+    fn test_stale_override() {
+        let mut packages = HashMap::new();
+        let mut parent = Package::new("parent", "1.0.0");
+        parent
+            .dependency_ranges
+            .insert("foo".to_string(), "^1.2.0".to_string());
+        packages.insert("parent".to_string(), parent);
+
+        let tree = InstalledTree {
+            versions: HashMap::from([("foo".to_string(), vec!["1.3.0".to_string()])]),
+            packages,
+            ..Default::default()
+        };
+
+        let mut entry = new_entry(OverrideSource::NpmOverrides, "foo", "foo", None, "1.3.0");
+        classify(&mut entry, &tree);
+        assert_eq!(entry.status, OverrideStatus::Stale);
+
+        let mut entry = new_entry(
+            OverrideSource::NpmOverrides,
+            "missing",
+            "missing",
+            None,
+            "1.0.0",
+        );
+        classify(&mut entry, &tree);
+        assert_eq!(entry.status, OverrideStatus::Unused);
+    }
+
+    #[test]
+    fn test_overridden_dependents() {
+        let mut packages = HashMap::new();
+        for (name, range) in [
+            ("old", "^1.0.0"),
+            ("new", "^2.0.0"),
+            ("forked", "github:a/b"),
+        ] {
+            let mut dependent = Package::new(name, "1.0.0");
+            dependent
+                .dependency_ranges
+                .insert("foo".to_string(), range.to_string());
+            packages.insert(name.to_string(), dependent);
+        }
+
+        let tree = InstalledTree {
+            versions: HashMap::from([("foo".to_string(), vec!["2.1.0".to_string()])]),
+            packages,
+            root_ranges: HashMap::from([("foo".to_string(), "^2.0.0".to_string())]),
+            ..Default::default()
+        };
+
+        let mut entry = new_entry(OverrideSource::PnpmOverrides, "foo", "foo", None, "2.1.0");
+        classify(&mut entry, &tree);
+        assert_eq!(entry.status, OverrideStatus::Active);
+        // Only the dependent that wanted another major had its resolution changed
+        assert_eq!(entry.overridden, vec!["old@1.0.0 (^1.0.0)"]);
+    }
+}
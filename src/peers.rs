@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use miette::Result;
+
+use crate::catalog::Catalogs;
+use crate::lockfile::declared_peers;
+use crate::range;
+use crate::types::{Package, PeerIssue, PeerIssueKind};
+
+/// Checks that every declared peer dependency is installed at a version
+/// its dependent accepts
+///
+/// Peers come from the lockfile entries of installed packages and from the
+/// project's own package.json. Optional peers (`peerDependenciesMeta`) may
+/// be left out, but must still match when installed. `catalog:` ranges of
+/// the package.json are looked up in the pnpm catalog; other ranges that
+/// aren't semver (`workspace:`, git URLs) are skipped.
+///
+/// With the install paths of an npm lockfile, each peer is the copy Node
+/// resolves from its dependent's directory: a nested `node_modules` before
+/// the hoisted one. Otherwise peers are looked up by name.
+pub struct PeerAnalyzer<'a> {
+    root: &'a Path,
+    installs: Option<HashMap<String, Package>>,
+    catalogs: Catalogs,
+}
+
+impl<'a> PeerAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self {
+            root,
+            installs: None,
+            catalogs: Catalogs::default(),
+        }
+    }
+
+    /// Look up the package.json's `catalog:` peer ranges in these catalogs
+    pub fn with_catalogs(mut self, catalogs: Catalogs) -> Self {
+        self.catalogs = catalogs;
+        self
+    }
+
+    /// Resolve peers through installed packages keyed by their directory,
+    /// as `NpmLockfileParser::parse_install_paths` returns them
+    pub fn with_install_paths(mut self, installs: HashMap<String, Package>) -> Self {
+        self.installs = Some(installs);
+        self
+    }
+
+    pub fn analyze(&self, packages: &HashMap<String, Package>) -> Result<Vec<PeerIssue>> {
+        let mut issues = Vec::new();
+        match &self.installs {
+            Some(installs) => {
+                for (dir, package) in installs {
+                    issues.extend(check_peers(
+                        &package.name,
+                        &package.version,
+                        &package.peer_dependencies,
+                        &package.optional_peers,
+                        |peer| resolve_from(installs, dir, peer),
+                    ));
+                }
+            }
+            None => {
+                for package in packages.values() {
+                    issues.extend(check_peers(
+                        &package.name,
+                        &package.version,
+                        &package.peer_dependencies,
+                        &package.optional_peers,
+                        |peer| packages.get(peer),
+                    ));
+                }
+            }
+        }
+
+        let (mut manifest_peers, optional) = declared_peers(&self.root.join("package.json"))?;
+        for (peer, range) in manifest_peers.iter_mut() {
+            if let Some(resolved) = self.catalogs.resolve(peer, range) {
+                *range = resolved.to_string();
+            }
+        }
+        issues.extend(check_peers(
+            "package.json",
+            "",
+            &manifest_peers,
+            &optional,
+            |peer| match &self.installs {
+                Some(installs) => resolve_from(installs, "", peer),
+                None => packages.get(peer),
+            },
+        ));
+
+        issues.sort_by(|a, b| {
+            (&a.package, &a.version, &a.peer).cmp(&(&b.package, &b.version, &b.peer))
+        });
+        Ok(issues)
+    }
+}
+
+/// The copy of `name` Node resolves from the directory `dir`: the
+/// `node_modules` of `dir`, then of each parent up to the root
+pub fn resolve_from<'p>(
+    installs: &'p HashMap<String, Package>,
+    dir: &str,
+    name: &str,
+) -> Option<&'p Package> {
+    let mut parts: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    loop {
+        // node_modules/node_modules isn't looked in
+        if parts.last() != Some(&"node_modules") {
+            let candidate = if parts.is_empty() {
+                format!("node_modules/{}", name)
+            } else {
+                format!("{}/node_modules/{}", parts.join("/"), name)
+            };
+            if let Some(package) = installs.get(&candidate) {
+                return Some(package);
+            }
+        }
+        parts.pop()?;
+    }
+}
+
+fn check_peers<'p>(
+    package: &str,
+    version: &str,
+    peers: &HashMap<String, String>,
+    optional: &[String],
+    resolve: impl Fn(&str) -> Option<&'p Package>,
+) -> Vec<PeerIssue> {
+    peers
+        .iter()
+        .filter_map(|(peer, peer_range)| {
+            let is_optional = optional.contains(peer);
+            let installed = resolve(peer);
+            let kind = match installed {
+                None if is_optional => return None,
+                None => PeerIssueKind::Missing,
+                Some(installed) => match range::satisfies(peer_range, &installed.version) {
+                    Some(false) => PeerIssueKind::Mismatch,
+                    _ => return None,
+                },
+            };
+            Some(PeerIssue {
+                package: package.to_string(),
+                version: version.to_string(),
+                peer: peer.clone(),
+                range: peer_range.clone(),
+                installed: installed.map(|p| p.version.clone()),
+                optional: is_optional,
+                kind,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_peer_issues() {
+        let root = TempDir::new("peers");
+        std::fs::write(
+            root.join("package.json"),
+            r#"{
+                "name": "ui-kit",
+                "peerDependencies": {
+                    "react": "^17.0.0 || ^18.0.0",
+                    "react-dom": "^17.0.0",
+                    "styled-components": "^6.0.0",
+                    "react-is": "catalog:"
+                },
+                "peerDependenciesMeta": {"styled-components": {"optional": true}}
+            }"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "catalog:\n  react-is: ^17.0.0\n",
+        )
+        .unwrap();
+
+        let mut packages = HashMap::new();
+        packages.insert("react".to_string(), Package::new("react", "18.2.0"));
+        packages.insert("react-is".to_string(), Package::new("react-is", "18.2.0"));
+        let mut react_dom = Package::new("react-dom", "18.2.0");
+        react_dom
+            .peer_dependencies
+            .insert("react".to_string(), "^18.2.0".to_string());
+        packages.insert("react-dom".to_string(), react_dom);
+        let mut query = Package::new("@tanstack/react-query", "5.0.0");
+        query
+            .peer_dependencies
+            .insert("react".to_string(), "^19.0.0".to_string());
+        query
+            .peer_dependencies
+            .insert("react-native".to_string(), "*".to_string());
+        query
+            .peer_dependencies
+            .insert("@tanstack/devtools".to_string(), "^5".to_string());
+        query.optional_peers = vec!["react-native".to_string()];
+        packages.insert("@tanstack/react-query".to_string(), query);
+
+        let issues = PeerAnalyzer::new(&root)
+            .with_catalogs(Catalogs::load(&root).unwrap())
+            .analyze(&packages)
+            .unwrap();
+
+        let found: Vec<(&str, &str, PeerIssueKind)> = issues
+            .iter()
+            .map(|i| (i.package.as_str(), i.peer.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "@tanstack/react-query",
+                    "@tanstack/devtools",
+                    PeerIssueKind::Missing
+                ),
+                ("@tanstack/react-query", "react", PeerIssueKind::Mismatch),
+                ("package.json", "react-dom", PeerIssueKind::Mismatch),
+                ("package.json", "react-is", PeerIssueKind::Mismatch),
+            ]
+        );
+        assert_eq!(issues[1].installed.as_deref(), Some("18.2.0"));
+        assert_eq!(issues[3].range, "^17.0.0");
+    }
+
+    #[test]
+    fn test_nested_peers() {
+        let root = TempDir::new("peers-nested");
+        std::fs::write(root.join("package.json"), r#"{"name": "app"}"#).unwrap();
+
+        // legacy-ui brings its own react 16; the app's is hoisted
+        let with_peer = |name: &str, version: &str, range: &str| {
+            let mut package = Package::new(name, version);
+            package
+                .peer_dependencies
+                .insert("react".to_string(), range.to_string());
+            package
+        };
+        let installs: HashMap<String, Package> = [
+            ("node_modules/react", Package::new("react", "18.2.0")),
+            (
+                "node_modules/react-dom",
+                with_peer("react-dom", "18.2.0", "^18.2.0"),
+            ),
+            ("node_modules/legacy-ui", Package::new("legacy-ui", "1.0.0")),
+            (
+                "node_modules/legacy-ui/node_modules/react",
+                Package::new("react", "16.14.0"),
+            ),
+            (
+                "node_modules/legacy-ui/node_modules/react-dom",
+                with_peer("react-dom", "16.14.0", "^16.14.0"),
+            ),
+            (
+                "node_modules/legacy-ui/node_modules/react-modal",
+                with_peer("react-modal", "3.0.0", "^18.0.0"),
+            ),
+        ]
+        .into_iter()
+        .map(|(dir, package)| (dir.to_string(), package))
+        .collect();
+
+        let issues = PeerAnalyzer::new(&root)
+            .with_install_paths(installs)
+            .analyze(&HashMap::new())
+            .unwrap();
+
+        let found: Vec<(&str, Option<&str>)> = issues
+            .iter()
+            .map(|i| (i.package.as_str(), i.installed.as_deref()))
+            .collect();
+        assert_eq!(found, vec![("react-modal", Some("16.14.0"))]);
+    }
+}
@@ -0,0 +1,486 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use miette::{bail, Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::peers::resolve_from;
+use crate::range::{npm_alias, parse_version, satisfies};
+use crate::types::{FixAction, Package, PinningDeviation, PinningReport, RangeStyle};
+use crate::workspace;
+
+/// Sections whose ranges the project picks for itself; peer ranges are a
+/// promise to consumers and stay as wide as they need to be
+const PINNED_SECTIONS: &[&str] = &["dependencies", "devDependencies", "optionalDependencies"];
+
+/// Checks that the project's manifests declare ranges in one style
+///
+/// Looks at the root package.json and those of workspace members. The
+/// style is `[pinning] policy` from depx.toml, or else whichever of exact,
+/// caret and tilde is most common. `*` and dist-tags always deviate, since
+/// every install may pick another release; complex ranges (">=1.2 <2") are
+/// counted but left alone, as they're written that way on purpose.
+///
+/// The version pinned is the copy the manifest's directory resolves: from
+/// the npm lockfile's install paths when given, else from `node_modules`
+/// on disk, else the newest installed copy the range allows.
+pub struct PinningAnalyzer<'a> {
+    root: &'a Path,
+    policy: Option<RangeStyle>,
+    installs: Option<HashMap<String, Package>>,
+}
+
+impl<'a> PinningAnalyzer<'a> {
+    pub fn new(root: &'a Path, policy: Option<RangeStyle>) -> Self {
+        Self {
+            root,
+            policy,
+            installs: None,
+        }
+    }
+
+    /// Resolve versions through installed packages keyed by their
+    /// directory, as `NpmLockfileParser::parse_install_paths` returns them
+    pub fn with_install_paths(mut self, installs: HashMap<String, Package>) -> Self {
+        self.installs = Some(installs);
+        self
+    }
+
+    /// `installed` supplies the version to pin when a range names none
+    pub fn analyze(&self, installed: &HashMap<String, Package>) -> Result<PinningReport> {
+        if let Some(policy @ (RangeStyle::Any | RangeStyle::Complex)) = self.policy {
+            bail!(
+                "`pinning.policy` must be \"exact\", \"caret\" or \"tilde\", not {}",
+                policy
+            );
+        }
+
+        let mut dirs = vec![self.root.to_path_buf()];
+        dirs.extend(workspace::member_dirs(self.root)?);
+
+        let mut manifests = Vec::new();
+        let mut declared = Vec::new();
+        for dir in dirs {
+            let path = dir.join("package.json");
+            if !path.exists() {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let value: JsonValue = serde_json::from_str(&content)
+                .into_diagnostic()
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            let manifest = path
+                .strip_prefix(self.root)
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|_| path.clone());
+
+            for section in PINNED_SECTIONS {
+                let Some(deps) = value.get(section).and_then(|d| d.as_object()) else {
+                    continue;
+                };
+                for (package, range) in deps {
+                    let Some(range) = range.as_str() else {
+                        continue;
+                    };
+                    let Some(style) = range_style(range) else {
+                        continue;
+                    };
+                    declared.push(PinningDeviation {
+                        manifest: manifest.clone(),
+                        section: section.to_string(),
+                        package: package.clone(),
+                        range: range.to_string(),
+                        style,
+                        suggested: None,
+                    });
+                }
+            }
+            manifests.push(manifest);
+        }
+
+        let mut styles = BTreeMap::new();
+        for range in &declared {
+            *styles.entry(range.style).or_insert(0) += 1;
+        }
+        let policy = self.policy.unwrap_or_else(|| most_common(&styles));
+
+        let deviations = declared
+            .into_iter()
+            .filter(|range| range.style != policy && range.style != RangeStyle::Complex)
+            .map(|mut range| {
+                let version = self.resolved_version(installed, &range);
+                range.suggested = restyle(&range.range, policy, version.as_deref());
+                range
+            })
+            .collect();
+
+        Ok(PinningReport {
+            manifests,
+            styles,
+            policy,
+            configured: self.policy.is_some(),
+            deviations,
+        })
+    }
+
+    /// The installed version the manifest's directory resolves for the
+    /// range's package, when the range allows it
+    fn resolved_version(
+        &self,
+        installed: &HashMap<String, Package>,
+        range: &PinningDeviation,
+    ) -> Option<String> {
+        let dir = range
+            .manifest
+            .parent()
+            .map(|dir| dir.to_string_lossy().replace('\\', "/"))
+            .unwrap_or_default();
+        let version = match &self.installs {
+            Some(installs) => {
+                resolve_from(installs, &dir, &range.package).map(|pkg| pkg.version.clone())
+            }
+            None => resolved_on_disk(self.root, &dir, &range.package),
+        };
+        match version {
+            Some(version) => (satisfies(&range.range, &version) == Some(true)).then_some(version),
+            None => installed_version(installed, &range.package, &range.range).map(str::to_string),
+        }
+    }
+}
+
+/// The version of `name` Node resolves from `dir` (relative to the root):
+/// the `node_modules` of `dir`, then of each parent up to the root
+fn resolved_on_disk(root: &Path, dir: &str, name: &str) -> Option<String> {
+    let mut dir = root.join(dir);
+    loop {
+        let manifest = dir.join("node_modules").join(name).join("package.json");
+        if let Some(version) = std::fs::read_to_string(&manifest)
+            .ok()
+            .and_then(|content| serde_json::from_str::<JsonValue>(&content).ok())
+            .and_then(|value| value["version"].as_str().map(str::to_string))
+        {
+            return Some(version);
+        }
+        if dir == root || !dir.pop() {
+            return None;
+        }
+    }
+}
+
+/// Rewrite the deviating ranges of one manifest in the project's style
+///
+/// Ranges without a suggestion are left out: there's no version to pin.
+pub fn restyle_actions(report: &PinningReport, manifest: &Path) -> Vec<FixAction> {
+    let reason = if report.configured {
+        format!("depx.toml pins {} ranges", report.policy)
+    } else {
+        format!("most ranges are {}", report.policy)
+    };
+
+    report
+        .deviations
+        .iter()
+        .filter(|deviation| deviation.manifest == manifest)
+        .filter_map(|deviation| {
+            Some(FixAction::Restyle {
+                package: deviation.package.clone(),
+                section: deviation.section.clone(),
+                from: deviation.range.clone(),
+                to: deviation.suggested.clone()?,
+                reason: reason.clone(),
+            })
+        })
+        .collect()
+}
+
+/// The style of a registry range
+///
+/// Returns `None` for ranges that don't pick a registry release: protocols
+/// (`workspace:`, `file:`, `npm:` aliases, git URLs) and GitHub shorthands.
+pub fn range_style(range: &str) -> Option<RangeStyle> {
+    let range = range.trim();
+    if npm_alias(range).is_some() || range.contains(':') || range.contains('/') {
+        return None;
+    }
+    if matches!(range, "" | "*" | "x" | "X") {
+        return Some(RangeStyle::Any);
+    }
+
+    let (style, version) = match range.as_bytes()[0] {
+        b'^' => (RangeStyle::Caret, &range[1..]),
+        b'~' => (RangeStyle::Tilde, &range[1..]),
+        b'=' => (RangeStyle::Exact, &range[1..]),
+        _ => (RangeStyle::Exact, range),
+    };
+    if is_version(version.trim(), style != RangeStyle::Exact) {
+        return Some(style);
+    }
+
+    // Dist-tags: "latest", "next", "beta"
+    let is_tag = range.starts_with(|c: char| c.is_ascii_alphabetic())
+        && range
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'.');
+    Some(if is_tag {
+        RangeStyle::Any
+    } else {
+        RangeStyle::Complex
+    })
+}
+
+/// Whether a range names a single version
+///
+/// A bare "1.2" is a range of its own (any 1.2.x), so only caret and tilde
+/// ranges may leave out the minor and patch.
+fn is_version(version: &str, partial: bool) -> bool {
+    if parse_version(version).is_some() {
+        return true;
+    }
+    let parts: Vec<&str> = version.split('.').collect();
+    partial
+        && parts.len() <= 2
+        && parts
+            .iter()
+            .all(|p| !p.is_empty() && p.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Most common of exact, caret and tilde; caret, npm's default, wins ties
+fn most_common(styles: &BTreeMap<RangeStyle, usize>) -> RangeStyle {
+    let count = |style| styles.get(&style).copied().unwrap_or(0);
+    let mut best = RangeStyle::Caret;
+    for style in [RangeStyle::Exact, RangeStyle::Tilde] {
+        if count(style) > count(best) {
+            best = style;
+        }
+    }
+    best
+}
+
+/// Newest installed version of a package that satisfies the range, for
+/// projects without install paths or `node_modules`
+fn installed_version<'p>(
+    installed: &'p HashMap<String, Package>,
+    name: &str,
+    range: &str,
+) -> Option<&'p str> {
+    installed
+        .values()
+        .filter(|pkg| pkg.name == name && satisfies(range, &pkg.version) == Some(true))
+        .filter_map(|pkg| Some((parse_version(&pkg.version)?, pkg.version.as_str())))
+        .max()
+        .map(|(_, version)| version)
+}
+
+/// A range rewritten in another style, keeping the version it names
+///
+/// Exact pins need a full version, so "^1.2" and "*" fall back to the
+/// installed one.
+fn restyle(range: &str, policy: RangeStyle, installed: Option<&str>) -> Option<String> {
+    let range = range.trim();
+    let named = range
+        .trim_start_matches(['^', '~', '='])
+        .trim_start_matches('v')
+        .trim();
+    let named = match range_style(range)? {
+        RangeStyle::Any | RangeStyle::Complex => None,
+        _ if policy == RangeStyle::Exact && parse_version(named).is_none() => None,
+        _ => Some(named),
+    };
+    let version = named.or(installed)?;
+
+    match policy {
+        RangeStyle::Exact => Some(version.to_string()),
+        RangeStyle::Caret => Some(format!("^{}", version)),
+        RangeStyle::Tilde => Some(format!("~{}", version)),
+        RangeStyle::Any | RangeStyle::Complex => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_range_style() {
+        assert_eq!(range_style("1.2.3"), Some(RangeStyle::Exact));
+        assert_eq!(range_style("=1.2.3"), Some(RangeStyle::Exact));
+        assert_eq!(range_style("^1.2.3"), Some(RangeStyle::Caret));
+        assert_eq!(range_style("^1.2"), Some(RangeStyle::Caret));
+        assert_eq!(range_style("~1.2.3-beta.1"), Some(RangeStyle::Tilde));
+        assert_eq!(range_style("*"), Some(RangeStyle::Any));
+        assert_eq!(range_style(""), Some(RangeStyle::Any));
+        assert_eq!(range_style("latest"), Some(RangeStyle::Any));
+        assert_eq!(range_style("1.2"), Some(RangeStyle::Complex));
+        assert_eq!(range_style(">=1.2.0 <2"), Some(RangeStyle::Complex));
+        assert_eq!(range_style("^1 || ^2"), Some(RangeStyle::Complex));
+        assert_eq!(range_style("workspace:*"), None);
+        assert_eq!(range_style("npm:lodash@^4"), None);
+        assert_eq!(range_style("user/repo"), None);
+    }
+
+    #[test]
+    fn test_pinning_report() {
+        let root = TempDir::new("pinning");
+        std::fs::create_dir_all(root.join("packages/ui")).unwrap();
+        std::fs::write(
+            root.join("package.json"),
+            r#"{
+  "workspaces": ["packages/*"],
+  "dependencies": { "react": "^18.2.0", "lodash": "4.17.21", "chalk": "*" },
+  "devDependencies": { "typescript": "~5.4", "ui": "workspace:*" },
+  "peerDependencies": { "vue": "3.4.0" }
+}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("packages/ui/package.json"),
+            r#"{ "dependencies": { "clsx": "^2.1.0", "semver": ">=7 <8", "react": "^18" } }"#,
+        )
+        .unwrap();
+
+        let mut installed = HashMap::new();
+        for (key, name, version) in [
+            ("chalk@4.1.2", "chalk", "4.1.2"),
+            ("chalk@5.3.0", "chalk", "5.3.0"),
+            ("typescript", "typescript", "5.4.5"),
+        ] {
+            installed.insert(key.to_string(), Package::new(name, version));
+        }
+
+        let report = PinningAnalyzer::new(&root, None)
+            .analyze(&installed)
+            .unwrap();
+        let pinned = PinningAnalyzer::new(&root, Some(RangeStyle::Exact))
+            .analyze(&installed)
+            .unwrap();
+        let invalid = PinningAnalyzer::new(&root, Some(RangeStyle::Any)).analyze(&installed);
+
+        assert_eq!(
+            report.manifests,
+            vec![
+                PathBuf::from("package.json"),
+                PathBuf::from("packages/ui/package.json")
+            ]
+        );
+        assert_eq!(report.styles[&RangeStyle::Caret], 3);
+        assert_eq!(report.styles[&RangeStyle::Complex], 1);
+        assert_eq!(report.policy, RangeStyle::Caret);
+        assert!(!report.configured);
+
+        let suggested: Vec<(&str, Option<&str>)> = report
+            .deviations
+            .iter()
+            .map(|d| (d.package.as_str(), d.suggested.as_deref()))
+            .collect();
+        assert_eq!(
+            suggested,
+            vec![
+                ("lodash", Some("^4.17.21")),
+                ("chalk", Some("^5.3.0")),
+                ("typescript", Some("^5.4")),
+            ]
+        );
+
+        let suggested: Vec<(&str, Option<&str>)> = pinned
+            .deviations
+            .iter()
+            .map(|d| (d.package.as_str(), d.suggested.as_deref()))
+            .collect();
+        assert_eq!(
+            suggested,
+            vec![
+                ("react", Some("18.2.0")),
+                ("chalk", Some("5.3.0")),
+                ("typescript", Some("5.4.5")),
+                ("clsx", Some("2.1.0")),
+                ("react", None),
+            ]
+        );
+        let actions = restyle_actions(&pinned, Path::new("packages/ui/package.json"));
+        assert_eq!(
+            actions,
+            vec![FixAction::Restyle {
+                package: "clsx".to_string(),
+                section: "dependencies".to_string(),
+                from: "^2.1.0".to_string(),
+                to: "2.1.0".to_string(),
+                reason: "depx.toml pins exact ranges".to_string(),
+            }]
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_member_resolution() {
+        let root = TempDir::new("pinning-member");
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "package.json",
+            r#"{ "workspaces": ["packages/*"], "dependencies": { "react": "^18.2.0" } }"#,
+        );
+        write(
+            "packages/legacy/package.json",
+            r#"{ "dependencies": { "react": "*" } }"#,
+        );
+        write(
+            "node_modules/react/package.json",
+            r#"{ "name": "react", "version": "18.2.0" }"#,
+        );
+        write(
+            "packages/legacy/node_modules/react/package.json",
+            r#"{ "name": "react", "version": "16.14.0" }"#,
+        );
+
+        let installed: HashMap<String, Package> = [
+            ("react@16.14.0", Package::new("react", "16.14.0")),
+            ("react@18.2.0", Package::new("react", "18.2.0")),
+        ]
+        .into_iter()
+        .map(|(key, package)| (key.to_string(), package))
+        .collect();
+        let suggested = |report: PinningReport| -> Vec<(PathBuf, Option<String>)> {
+            report
+                .deviations
+                .into_iter()
+                .map(|d| (d.manifest, d.suggested))
+                .collect()
+        };
+
+        let report = PinningAnalyzer::new(&root, Some(RangeStyle::Exact))
+            .analyze(&installed)
+            .unwrap();
+        let expected = vec![
+            (PathBuf::from("package.json"), Some("18.2.0".to_string())),
+            (
+                PathBuf::from("packages/legacy/package.json"),
+                Some("16.14.0".to_string()),
+            ),
+        ];
+        assert_eq!(suggested(report), expected);
+
+        let installs: HashMap<String, Package> = [
+            ("node_modules/react", Package::new("react", "18.2.0")),
+            (
+                "packages/legacy/node_modules/react",
+                Package::new("react", "16.14.0"),
+            ),
+        ]
+        .into_iter()
+        .map(|(dir, package)| (dir.to_string(), package))
+        .collect();
+        std::fs::remove_dir_all(root.join("packages/legacy/node_modules")).unwrap();
+        let report = PinningAnalyzer::new(&root, Some(RangeStyle::Exact))
+            .with_install_paths(installs)
+            .analyze(&installed)
+            .unwrap();
+        assert_eq!(suggested(report), expected);
+    }
+}
@@ -0,0 +1,84 @@
+//! Matching npm `os`/`cpu`/`libc` constraints against the machine depx runs on
+
+/// The current OS under the name npm uses in `os` fields
+pub fn current_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "darwin",
+        "windows" => "win32",
+        other => other,
+    }
+}
+
+/// The current CPU architecture under the name npm uses in `cpu` fields
+pub fn current_cpu() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "x86" => "ia32",
+        "aarch64" => "arm64",
+        "powerpc64" => "ppc64",
+        other => other,
+    }
+}
+
+/// The C library npm's `libc` field names for the current Linux target
+pub fn current_libc() -> &'static str {
+    if cfg!(target_env = "musl") {
+        "musl"
+    } else {
+        "glibc"
+    }
+}
+
+/// Whether a package with these constraints installs on the current machine
+///
+/// Like npm, a `libc` restriction only ever matches on Linux.
+pub fn supports(os: &[String], cpu: &[String], libc: &[String]) -> bool {
+    satisfies(os, current_os())
+        && satisfies(cpu, current_cpu())
+        && (libc.is_empty() || (current_os() == "linux" && satisfies(libc, current_libc())))
+}
+
+/// Whether `value` satisfies an npm constraint list such as `["darwin"]`
+/// or `["!win32"]`
+///
+/// An empty list allows everything. Negated entries exclude a value, and a
+/// list made only of negations allows anything not excluded.
+pub fn satisfies(constraints: &[String], value: &str) -> bool {
+    if constraints.is_empty() {
+        return true;
+    }
+
+    let mut has_allow = false;
+    let mut allowed = false;
+    for constraint in constraints {
+        match constraint.strip_prefix('!') {
+            Some(excluded) if excluded == value => return false,
+            Some(_) => {}
+            None => {
+                has_allow = true;
+                allowed |= constraint == value;
+            }
+        }
+    }
+
+    allowed || !has_allow
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_satisfies() {
+        assert!(satisfies(&[], "linux"));
+        assert!(satisfies(&list(&["darwin", "linux"]), "linux"));
+        assert!(!satisfies(&list(&["darwin"]), "linux"));
+        assert!(satisfies(&list(&["!win32"]), "linux"));
+        assert!(!satisfies(&list(&["!win32"]), "win32"));
+        assert!(!satisfies(&list(&["linux", "!linux"]), "linux"));
+    }
+}
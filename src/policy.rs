@@ -0,0 +1,302 @@
+use std::collections::BTreeMap;
+use std::process::ExitCode;
+
+use crate::rules::{Rule, RuleLevel};
+use crate::types::{DuplicateAnalysis, UsageAnalysis};
+
+/// Outcome of a command, mapped to the process exit code
+///
+/// - `0` success: nothing exceeded the configured thresholds
+/// - `1` failure: a `--fail-*` or `--max-*` threshold was exceeded, or a
+///   rule set to "error" has findings
+/// - `2` error: the command couldn't run (bad arguments, unreadable lockfile, ...)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitStatus {
+    Success,
+    Failure,
+    Error,
+}
+
+impl ExitStatus {
+    pub fn code(self) -> u8 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Failure => 1,
+            ExitStatus::Error => 2,
+        }
+    }
+}
+
+impl From<ExitStatus> for ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        ExitCode::from(status.code())
+    }
+}
+
+/// Rules `depx analyze` and `depx ci` report
+const USAGE_RULES: [Rule; 5] = [
+    Rule::UnusedDependency,
+    Rule::MisplacedDependency,
+    Rule::TypeOnlyDependency,
+    Rule::OverlappingDependencies,
+    Rule::PhantomDependency,
+];
+
+/// Thresholds that make `depx analyze` fail
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzePolicy {
+    pub fail_if_unused: bool,
+    pub fail_if_phantom: bool,
+    pub max_unused: Option<usize>,
+
+    /// Levels from `[rules]`; "error" fails on any finding, while "warn"
+    /// never relaxes the thresholds above
+    pub rules: BTreeMap<Rule, RuleLevel>,
+}
+
+impl AnalyzePolicy {
+    /// The policy with the project's `[rules]` levels
+    pub fn with_rules(mut self, rules: &BTreeMap<Rule, RuleLevel>) -> Self {
+        self.rules = rules.clone();
+        self
+    }
+
+    /// Whether the findings of `rule` fail the run
+    pub fn fails_on(&self, rule: Rule, analysis: &UsageAnalysis) -> bool {
+        let count = usage_findings(rule, analysis);
+        if self.rules.get(&rule) == Some(&RuleLevel::Error) && count > 0 {
+            return true;
+        }
+        match rule {
+            Rule::UnusedDependency => {
+                (self.fail_if_unused && count > 0) || self.max_unused.is_some_and(|max| count > max)
+            }
+            Rule::PhantomDependency => self.fail_if_phantom && count > 0,
+            _ => false,
+        }
+    }
+
+    /// Describe every threshold the analysis exceeds, and every rule set to
+    /// "error" that has findings
+    ///
+    /// Thresholds apply whatever `[rules]` says; a rule set to "error" with
+    /// findings is listed once, under its rule id.
+    pub fn violations(&self, analysis: &UsageAnalysis) -> Vec<String> {
+        let mut violations = rule_violations(&self.rules, &USAGE_RULES, |rule| {
+            usage_findings(rule, analysis)
+        });
+        let unused = analysis.unused_direct.len();
+        let phantom = analysis.phantom.len();
+
+        let is_error = |rule| self.rules.get(&rule) == Some(&RuleLevel::Error);
+
+        if !is_error(Rule::UnusedDependency) {
+            if self.fail_if_unused && unused > 0 {
+                violations.push(format!("{} unused dependencies (--fail-if-unused)", unused));
+            }
+            if let Some(max) = self.max_unused {
+                if unused > max {
+                    violations.push(format!(
+                        "{} unused dependencies, more than --max-unused {}",
+                        unused, max
+                    ));
+                }
+            }
+        }
+        if !is_error(Rule::PhantomDependency) && self.fail_if_phantom && phantom > 0 {
+            violations.push(format!(
+                "{} phantom dependencies (--fail-if-phantom)",
+                phantom
+            ));
+        }
+
+        violations
+    }
+
+    pub fn status(&self, analysis: &UsageAnalysis) -> ExitStatus {
+        if self.violations(analysis).is_empty() {
+            ExitStatus::Success
+        } else {
+            ExitStatus::Failure
+        }
+    }
+}
+
+/// Rules set to "error" in `[rules]` whose findings make `depx duplicates` fail
+pub fn duplicate_violations(
+    rules: &BTreeMap<Rule, RuleLevel>,
+    analysis: &DuplicateAnalysis,
+) -> Vec<String> {
+    let reported = [
+        Rule::DuplicateSingleton,
+        Rule::DuplicateMajor,
+        Rule::DuplicateVersion,
+        Rule::TypesSkew,
+        Rule::PeerSplit,
+    ];
+    rule_violations(rules, &reported, |rule| match rule {
+        Rule::TypesSkew => analysis.types_skew.len(),
+        Rule::PeerSplit => analysis.peer_splits.len(),
+        rule => analysis
+            .duplicates
+            .iter()
+            .filter(|group| group.rule == rule)
+            .count(),
+    })
+}
+
+/// "3 phantom dependencies (DEPX010 is an error)" for each of `reported`
+/// set to "error" with findings
+fn rule_violations(
+    rules: &BTreeMap<Rule, RuleLevel>,
+    reported: &[Rule],
+    count: impl Fn(Rule) -> usize,
+) -> Vec<String> {
+    reported
+        .iter()
+        .filter(|rule| rules.get(rule) == Some(&RuleLevel::Error))
+        .filter_map(|rule| {
+            let findings = count(*rule);
+            (findings > 0)
+                .then(|| format!("{} {} ({} is an error)", findings, rule.findings(), rule))
+        })
+        .collect()
+}
+
+/// How many findings of a usage rule the analysis has
+fn usage_findings(rule: Rule, analysis: &UsageAnalysis) -> usize {
+    match rule {
+        Rule::UnusedDependency => analysis.unused_direct.len(),
+        Rule::MisplacedDependency => analysis.misplaced.len(),
+        Rule::TypeOnlyDependency => analysis.type_only.len(),
+        Rule::OverlappingDependencies => analysis.overlapping.len(),
+        Rule::PhantomDependency => analysis.phantom.len(),
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Package, PhantomDependency, TypesSkew};
+    use std::path::PathBuf;
+
+    fn analysis(unused: usize, phantom: usize) -> UsageAnalysis {
+        UsageAnalysis {
+            used: Vec::new(),
+            unused: Vec::new(),
+            expected_unused: Vec::new(),
+            dev_only: Vec::new(),
+            unused_direct: (0..unused)
+                .map(|i| Package::new(format!("pkg-{}", i), "1.0.0").direct())
+                .collect(),
+            expected_unused_direct: Vec::new(),
+            overlapping: Vec::new(),
+            phantom: (0..phantom)
+                .map(|i| PhantomDependency {
+                    name: format!("phantom-{}", i),
+                    installed_version: None,
+                    file_path: PathBuf::from("index.js"),
+                    line: 1,
+                    imports: Vec::new(),
+                })
+                .collect(),
+            misplaced: Vec::new(),
+            type_only: Vec::new(),
+            removable_transitive: Default::default(),
+            other_platform: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_analyze_policy() {
+        let default = AnalyzePolicy::default();
+        assert_eq!(default.status(&analysis(3, 2)), ExitStatus::Success);
+
+        let policy = AnalyzePolicy {
+            max_unused: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(policy.status(&analysis(2, 0)), ExitStatus::Success);
+        assert_eq!(policy.status(&analysis(3, 0)), ExitStatus::Failure);
+
+        let policy = AnalyzePolicy {
+            fail_if_unused: true,
+            fail_if_phantom: true,
+            max_unused: None,
+            rules: BTreeMap::new(),
+        };
+        assert_eq!(policy.status(&analysis(0, 0)), ExitStatus::Success);
+        assert_eq!(policy.violations(&analysis(1, 1)).len(), 2);
+    }
+
+    #[test]
+    fn test_rule_levels() {
+        // "warn" never fails on its own, "error" fails on any finding
+        let policy = AnalyzePolicy::default().with_rules(&BTreeMap::from([
+            (Rule::UnusedDependency, RuleLevel::Warn),
+            (Rule::PhantomDependency, RuleLevel::Error),
+        ]));
+        assert_eq!(policy.status(&analysis(3, 0)), ExitStatus::Success);
+        assert!(!policy.fails_on(Rule::UnusedDependency, &analysis(3, 0)));
+        assert_eq!(
+            policy.violations(&analysis(3, 2)),
+            vec!["2 phantom dependencies (DEPX010 is an error)"]
+        );
+        assert!(policy.fails_on(Rule::PhantomDependency, &analysis(0, 1)));
+
+        let mut duplicates = crate::duplicates::merge(Vec::new());
+        let rules = BTreeMap::from([(Rule::TypesSkew, RuleLevel::Error)]);
+        assert!(duplicate_violations(&rules, &duplicates).is_empty());
+        duplicates.types_skew.push(TypesSkew {
+            package: "react".to_string(),
+            package_version: "18.2.0".to_string(),
+            types_package: "@types/react".to_string(),
+            types_version: "17.0.80".to_string(),
+            suggested_range: "^18".to_string(),
+        });
+        assert_eq!(
+            duplicate_violations(&rules, &duplicates),
+            vec!["1 @types packages for another major version (DEPX110 is an error)"]
+        );
+    }
+
+    #[test]
+    fn test_thresholds_escalate_warn_rules() {
+        // An explicit --fail-if-* or --max-unused fails the run even when
+        // depx.toml sets the rule to "warn"
+        let policy = AnalyzePolicy {
+            fail_if_phantom: true,
+            max_unused: Some(1),
+            ..Default::default()
+        }
+        .with_rules(&BTreeMap::from([
+            (Rule::UnusedDependency, RuleLevel::Warn),
+            (Rule::PhantomDependency, RuleLevel::Warn),
+        ]));
+        assert_eq!(policy.status(&analysis(1, 0)), ExitStatus::Success);
+        assert!(policy.fails_on(Rule::UnusedDependency, &analysis(2, 0)));
+        assert!(policy.fails_on(Rule::PhantomDependency, &analysis(0, 1)));
+        assert_eq!(
+            policy.violations(&analysis(2, 1)),
+            vec![
+                "2 unused dependencies, more than --max-unused 1",
+                "1 phantom dependencies (--fail-if-phantom)",
+            ]
+        );
+
+        // A rule set to "error" is listed once, not again for the threshold
+        let policy = AnalyzePolicy {
+            fail_if_unused: true,
+            ..Default::default()
+        }
+        .with_rules(&BTreeMap::from([(
+            Rule::UnusedDependency,
+            RuleLevel::Error,
+        )]));
+        assert_eq!(
+            policy.violations(&analysis(2, 0)),
+            vec!["2 unused dependencies (DEPX001 is an error)"]
+        );
+    }
+}
@@ -0,0 +1,343 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::{resolve, ImportExtractor};
+use crate::entry_points::EntryPointAnalyzer;
+use crate::types::{PublishCheck, PublishIssue, PublishIssueKind};
+use crate::workspace;
+
+/// Fields consumers install from a published manifest; devDependencies stay
+/// with the author
+const PUBLISHED_DEPENDENCY_FIELDS: &[&str] =
+    &["dependencies", "optionalDependencies", "peerDependencies"];
+
+/// Checks the publishable packages of a project (the root and any workspace
+/// members) for problems that only show up once they're installed elsewhere
+pub struct PublishChecker<'a> {
+    root: &'a Path,
+}
+
+impl<'a> PublishChecker<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    /// One entry per publishable package; private packages are skipped
+    pub fn check(&self) -> Result<Vec<PublishCheck>> {
+        let mut dirs = vec![self.root.to_path_buf()];
+        dirs.extend(workspace::member_dirs(self.root)?);
+
+        let mut checks = Vec::new();
+        for dir in dirs {
+            let Some(manifest) = read_manifest(&dir)? else {
+                continue;
+            };
+            if !is_publishable(&manifest) {
+                continue;
+            }
+
+            let mut issues = local_ranges(&manifest);
+            issues.extend(unpublished_files(&dir, &manifest)?);
+
+            checks.push(PublishCheck {
+                name: manifest["name"].as_str().unwrap_or_default().to_string(),
+                issues,
+                dir,
+                owners: Vec::new(),
+            });
+        }
+
+        Ok(checks)
+    }
+}
+
+fn read_manifest(dir: &Path) -> Result<Option<JsonValue>> {
+    let path: PathBuf = dir.join("package.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to parse {}", path.display()))
+        .map(Some)
+}
+
+/// npm refuses to publish manifests marked private or without a name
+fn is_publishable(manifest: &JsonValue) -> bool {
+    manifest.get("private").and_then(|v| v.as_bool()) != Some(true)
+        && manifest.get("name").and_then(|v| v.as_str()).is_some()
+}
+
+/// Dependency ranges that only resolve inside the author's checkout
+fn local_ranges(manifest: &JsonValue) -> Vec<PublishIssue> {
+    let mut issues = Vec::new();
+
+    for field in PUBLISHED_DEPENDENCY_FIELDS {
+        let Some(deps) = manifest.get(*field).and_then(|v| v.as_object()) else {
+            continue;
+        };
+        for (name, range) in deps {
+            let Some(range) = range.as_str() else {
+                continue;
+            };
+            let kind = if range.starts_with("workspace:") {
+                PublishIssueKind::WorkspaceProtocol
+            } else if ["file:", "link:", "portal:"]
+                .iter()
+                .any(|p| range.starts_with(p))
+            {
+                PublishIssueKind::LocalPath
+            } else {
+                continue;
+            };
+
+            issues.push(PublishIssue {
+                kind,
+                field: field.to_string(),
+                subject: name.clone(),
+                value: range.to_string(),
+            });
+        }
+    }
+
+    issues
+}
+
+/// Entry points whose files are missing or left out of the tarball by the
+/// `files` field, along with project files they import
+fn unpublished_files(dir: &Path, manifest: &JsonValue) -> Result<Vec<PublishIssue>> {
+    let mut specs: Vec<String> = Vec::new();
+    if manifest.get("main").is_some() || manifest.get("module").is_some() {
+        specs.push("main".to_string());
+    }
+    for field in ["exports", "bin"] {
+        if manifest.get(field).is_some() {
+            specs.push(field.to_string());
+        }
+    }
+
+    let mut entries = EntryPointAnalyzer::new(dir, &specs).resolve()?;
+    for field in ["types", "typings"] {
+        if let Some(path) = manifest.get(field).and_then(|v| v.as_str()) {
+            entries.push((field.to_string(), vec![resolve::normalize(&dir.join(path))]));
+        }
+    }
+
+    let root = resolve::normalize(dir);
+    let relative = |path: &Path| {
+        path.strip_prefix(&root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    };
+    let files = files_matcher(dir, manifest);
+    let is_published = |path: &Path| {
+        files.as_ref().is_none_or(|matcher| {
+            matcher
+                .matched_path_or_any_parents(path.strip_prefix(&root).unwrap_or(path), false)
+                .is_ignore()
+        })
+    };
+    // npm always packs the `main` file, whatever `files` says, and `bin`
+    // and `exports` targets are the files the package declares it ships
+    let main = manifest
+        .get("main")
+        .and_then(|v| v.as_str())
+        .and_then(|m| resolve::resolve_file(&dir.join(m)));
+
+    let mut issues = Vec::new();
+    let mut existing = Vec::new();
+    for (name, targets) in &entries {
+        let field = name.split_whitespace().next().unwrap_or(name).to_string();
+        for target in targets {
+            if !target.is_file() {
+                issues.push(PublishIssue {
+                    kind: PublishIssueKind::MissingEntry,
+                    field: field.clone(),
+                    subject: name.clone(),
+                    value: relative(target),
+                });
+            } else if !matches!(field.as_str(), "main" | "bin" | "exports")
+                && !is_published(target)
+                && main.as_ref() != Some(target)
+            {
+                issues.push(PublishIssue {
+                    kind: PublishIssueKind::NotPublished,
+                    field: "files".to_string(),
+                    subject: name.clone(),
+                    value: relative(target),
+                });
+            } else {
+                existing.push((name, target.clone()));
+            }
+        }
+    }
+
+    // Files reached through the entry points' relative imports and re-exports
+    if files.is_some() {
+        let mut reported: Vec<PathBuf> = Vec::new();
+        for (name, entry) in &existing {
+            for file in local_files_reached(&root, entry)? {
+                if file != *entry && !is_published(&file) && !reported.contains(&file) {
+                    issues.push(PublishIssue {
+                        kind: PublishIssueKind::NotPublished,
+                        field: "files".to_string(),
+                        subject: name.to_string(),
+                        value: relative(&file),
+                    });
+                    reported.push(file);
+                }
+            }
+        }
+    }
+
+    Ok(issues)
+}
+
+/// Project files an entry file reaches through relative imports, sorted
+///
+/// Walked directly rather than through the analyzer, which skips build
+/// output directories like `dist/` that are exactly what gets published.
+fn local_files_reached(root: &Path, entry: &Path) -> Result<Vec<PathBuf>> {
+    let mut visited = BTreeSet::new();
+    let mut queue = VecDeque::from([entry.to_path_buf()]);
+
+    while let Some(file) = queue.pop_front() {
+        if !visited.insert(file.clone()) {
+            continue;
+        }
+
+        let bytes = std::fs::read(&file)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read file: {}", file.display()))?;
+        // Images, `.node` addons and other binaries import nothing
+        let Ok(source) = String::from_utf8(bytes) else {
+            continue;
+        };
+        // Shebang-only bins usually have no extension; parse them as JS
+        let parse_path = if file.extension().is_some() {
+            file.clone()
+        } else {
+            file.with_extension("js")
+        };
+        let Ok(extraction) = ImportExtractor::new(&parse_path, &source).extract_all() else {
+            continue;
+        };
+
+        for local in &extraction.local_imports {
+            if let Some(target) = resolve::resolve_relative(&file, &local.specifier) {
+                if target.starts_with(root) {
+                    queue.push_back(target);
+                }
+            }
+        }
+    }
+
+    Ok(visited.into_iter().collect())
+}
+
+/// Matcher for the `files` field, or `None` when it's absent and npm packs
+/// everything not ignored
+fn files_matcher(dir: &Path, manifest: &JsonValue) -> Option<Gitignore> {
+    let files = manifest.get("files")?.as_array()?;
+
+    let mut builder = GitignoreBuilder::new(dir);
+    for pattern in files.iter().filter_map(|f| f.as_str()) {
+        let pattern = pattern.trim_start_matches("./").trim_end_matches('/');
+        // A bad pattern only means fewer files are treated as published
+        let _ = builder.add_line(None, pattern);
+    }
+    builder.build().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_local_ranges() {
+        let manifest: JsonValue = serde_json::from_str(
+            r#"{
+                "name": "@acme/ui",
+                "dependencies": {"@acme/core": "workspace:^", "react-dom": "^18.0.0"},
+                "peerDependencies": {"@acme/theme": "link:../theme"},
+                "devDependencies": {"@acme/test-utils": "workspace:*"}
+            }"#,
+        )
+        .unwrap();
+
+        let issues = local_ranges(&manifest);
+        let found: Vec<(&str, PublishIssueKind)> = issues
+            .iter()
+            .map(|i| (i.subject.as_str(), i.kind))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("@acme/core", PublishIssueKind::WorkspaceProtocol),
+                ("@acme/theme", PublishIssueKind::LocalPath),
+            ]
+        );
+
+        assert!(is_publishable(&manifest));
+        let private: JsonValue =
+            serde_json::from_str(r#"{"name": "app", "private": true}"#).unwrap();
+        assert!(!is_publishable(&private));
+    }
+
+    #[test]
+    fn test_unpublished_files() {
+        let root = TempDir::new("publish");
+        std::fs::create_dir_all(root.join("dist")).unwrap();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("dist/index.js"),
+            "export * from '../src/util.js';\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/util.js"),
+            "import './logo.png';\nexport const x = 1;\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("src/logo.png"), [0x89, b'P', b'N', b'G', 0xff]).unwrap();
+        std::fs::write(root.join("cli.js"), "").unwrap();
+
+        let content = r#"{
+            "name": "lib",
+            "files": ["dist"],
+            "exports": {".": "./dist/index.js", "./extra": "./dist/extra.js"},
+            "bin": {"lib": "./cli.js"}
+        }"#;
+        std::fs::write(root.join("package.json"), content).unwrap();
+        let manifest: JsonValue = serde_json::from_str(content).unwrap();
+
+        let issues = unpublished_files(&root, &manifest).unwrap();
+
+        let found: Vec<(PublishIssueKind, &str, &str)> = issues
+            .iter()
+            .map(|i| (i.kind, i.subject.as_str(), i.value.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    PublishIssueKind::MissingEntry,
+                    "exports ./extra",
+                    "dist/extra.js"
+                ),
+                (PublishIssueKind::NotPublished, "exports .", "src/logo.png"),
+                (PublishIssueKind::NotPublished, "exports .", "src/util.js"),
+            ]
+        );
+    }
+}
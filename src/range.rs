@@ -0,0 +1,220 @@
+use semver::{Op, Version, VersionReq};
+
+/// Check whether a version satisfies an npm-style semver range
+///
+/// Returns `None` when either side can't be interpreted as semver
+/// (git URLs, `file:` paths, dist-tags, ...), so callers can tell
+/// "doesn't match" apart from "can't tell".
+///
+/// Examples:
+/// - "^1.2.0" / "1.4.0" -> Some(true)
+/// - ">=1.0.0 <2.0.0" / "2.0.0" -> Some(false)
+/// - "1.x || 2.x" / "2.3.1" -> Some(true)
+/// - "npm:string-width@^4" / "4.2.3" -> Some(true) (an alias's own range)
+/// - "github:user/repo" / "1.0.0" -> None
+pub fn satisfies(range: &str, version: &str) -> Option<bool> {
+    let range = npm_alias(range).map_or(range, |(_, range)| range);
+    let version = parse_version(version)?;
+
+    for alternative in range.split("||") {
+        let req = to_version_req(alternative)?;
+        if req.matches(&version) {
+            return Some(true);
+        }
+    }
+
+    Some(false)
+}
+
+/// Parse a semver version, allowing the leading "v" of Go modules and git tags
+pub fn parse_version(version: &str) -> Option<Version> {
+    Version::parse(strip_v(version.trim())).ok()
+}
+
+/// Parse an npm-style range into a `VersionReq`
+///
+/// npm separates comparators with spaces and treats a bare version as
+/// an exact match, while the semver crate uses commas and caret
+/// semantics, so the range is normalized first.
+pub fn to_version_req(range: &str) -> Option<VersionReq> {
+    let range = range.trim();
+
+    if range.is_empty() || matches!(range, "*" | "x" | "X" | "latest") {
+        return Some(VersionReq::STAR);
+    }
+
+    let tokens = join_operators(range.split_whitespace().collect());
+
+    // Hyphen range: "1.2.3 - 2.3.4"
+    if tokens.len() == 3 && tokens[1] == "-" {
+        let req = format!(">={}, <={}", strip_v(&tokens[0]), strip_v(&tokens[2]));
+        return VersionReq::parse(&req).ok();
+    }
+
+    let comparators: Vec<String> = tokens.iter().map(|t| normalize_comparator(t)).collect();
+
+    VersionReq::parse(&comparators.join(", ")).ok()
+}
+
+/// The oldest version each alternative of a range allows
+///
+/// Alternatives without a lower bound ("*", "<20") contribute nothing, and
+/// `None` means the range isn't semver at all.
+///
+/// Examples:
+/// - ">=18.17.0" -> [18.17.0]
+/// - "^18 || ^20.9.0" -> [18.0.0, 20.9.0]
+/// - ">16 <21" -> [17.0.0]
+/// - "20" -> [20.0.0]
+pub fn lower_bounds(range: &str) -> Option<Vec<Version>> {
+    let mut bounds = Vec::new();
+    for alternative in range.split("||") {
+        let req = to_version_req(alternative)?;
+        let lowest = req
+            .comparators
+            .iter()
+            .filter_map(|c| {
+                let (minor, patch) = (c.minor.unwrap_or(0), c.patch.unwrap_or(0));
+                match c.op {
+                    Op::Exact | Op::GreaterEq | Op::Tilde | Op::Caret | Op::Wildcard => {
+                        Some(Version::new(c.major, minor, patch))
+                    }
+                    Op::Greater => Some(match (c.minor, c.patch) {
+                        (Some(_), Some(_)) => Version::new(c.major, minor, patch + 1),
+                        (Some(_), None) => Version::new(c.major, minor + 1, 0),
+                        _ => Version::new(c.major + 1, 0, 0),
+                    }),
+                    _ => None,
+                }
+            })
+            .max();
+        bounds.extend(lowest);
+    }
+    Some(bounds)
+}
+
+/// Split an npm alias into the package it installs and the range
+///
+/// Examples:
+/// - "npm:string-width@^4.2.0" -> ("string-width", "^4.2.0")
+/// - "npm:@scope/pkg@1.0.0" -> ("@scope/pkg", "1.0.0")
+/// - "npm:lodash" -> ("lodash", "*")
+/// - "^4.2.0" -> None
+pub fn npm_alias(spec: &str) -> Option<(&str, &str)> {
+    let target = spec.strip_prefix("npm:")?;
+    // A leading '@' starts a scope, not the range
+    match target.get(1..)?.find('@') {
+        Some(at) => Some((&target[..at + 1], &target[at + 2..])),
+        None => Some((target, "*")),
+    }
+}
+
+/// Merge standalone operators with the version that follows (">= 1.0" -> ">=1.0")
+fn join_operators(tokens: Vec<&str>) -> Vec<String> {
+    let mut joined: Vec<String> = Vec::new();
+    let mut pending: Option<&str> = None;
+
+    for token in tokens {
+        if token
+            .chars()
+            .all(|c| matches!(c, '<' | '>' | '=' | '~' | '^'))
+        {
+            pending = Some(token);
+            continue;
+        }
+
+        match pending.take() {
+            Some(op) => joined.push(format!("{}{}", op, token)),
+            None => joined.push(token.to_string()),
+        }
+    }
+
+    joined
+}
+
+fn normalize_comparator(token: &str) -> String {
+    let op_len = token
+        .find(|c: char| !matches!(c, '<' | '>' | '=' | '~' | '^'))
+        .unwrap_or(token.len());
+    let (op, version) = token.split_at(op_len);
+    let version = strip_v(version);
+
+    if !op.is_empty() {
+        return format!("{}{}", op, version);
+    }
+
+    // Bare versions: exact when complete, wildcard when partial ("1.2" -> "1.2.*")
+    let parts: Vec<&str> = version.split('.').collect();
+    let is_wildcard = parts.iter().any(|p| matches!(*p, "x" | "X" | "*"));
+
+    if parts.len() >= 3 && !is_wildcard {
+        format!("={}", version)
+    } else if is_wildcard {
+        version.to_string()
+    } else {
+        format!("{}.*", version)
+    }
+}
+
+fn strip_v(version: &str) -> &str {
+    version.strip_prefix('v').unwrap_or(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_npm_alias() {
+        assert_eq!(
+            npm_alias("npm:string-width@^4.2.0"),
+            Some(("string-width", "^4.2.0"))
+        );
+        assert_eq!(
+            npm_alias("npm:@scope/pkg@1.0.0"),
+            Some(("@scope/pkg", "1.0.0"))
+        );
+        assert_eq!(npm_alias("npm:lodash"), Some(("lodash", "*")));
+        assert_eq!(npm_alias("^4.2.0"), None);
+    }
+
+    #[test]
+    fn test_caret_and_tilde() {
+        assert_eq!(satisfies("^1.2.0", "1.4.0"), Some(true));
+        assert_eq!(satisfies("^1.2.0", "2.0.0"), Some(false));
+        assert_eq!(satisfies("~1.2.0", "1.2.9"), Some(true));
+        assert_eq!(satisfies("~1.2.0", "1.3.0"), Some(false));
+    }
+
+    #[test]
+    fn test_npm_syntax() {
+        assert_eq!(satisfies("1.2.3", "1.2.3"), Some(true));
+        assert_eq!(satisfies("1.2.3", "1.2.4"), Some(false));
+        assert_eq!(satisfies("1.2", "1.2.7"), Some(true));
+        assert_eq!(satisfies("1.x", "1.9.0"), Some(true));
+        assert_eq!(satisfies(">= 1.0.0 < 2.0.0", "1.5.0"), Some(true));
+        assert_eq!(satisfies(">=1.0.0 <2.0.0", "2.0.0"), Some(false));
+        assert_eq!(satisfies("1.0.0 - 1.5.0", "1.5.0"), Some(true));
+        assert_eq!(satisfies("^1.0.0 || ^2.0.0", "2.1.0"), Some(true));
+        assert_eq!(satisfies("*", "3.0.0"), Some(true));
+    }
+
+    #[test]
+    fn test_lower_bounds() {
+        let bounds = |range| {
+            lower_bounds(range).map(|b| b.iter().map(|v| v.to_string()).collect::<Vec<_>>())
+        };
+        assert_eq!(bounds(">=18.17.0").unwrap(), ["18.17.0"]);
+        assert_eq!(bounds("^18 || ^20.9.0").unwrap(), ["18.0.0", "20.9.0"]);
+        assert_eq!(bounds(">16 <21").unwrap(), ["17.0.0"]);
+        assert_eq!(bounds("20").unwrap(), ["20.0.0"]);
+        assert!(bounds("*").unwrap().is_empty());
+        assert_eq!(bounds("lts/*"), None);
+    }
+
+    #[test]
+    fn test_unknown_ranges() {
+        assert_eq!(satisfies("github:user/repo", "1.0.0"), None);
+        assert_eq!(satisfies("^1.0.0", "not-a-version"), None);
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use miette::Result;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use super::{fetch_many, get_json, BoxFuture, PackageMetadata, Registry, Release, Token};
+use crate::date;
+
+const CRATES_IO_API: &str = "https://crates.io/api/v1";
+
+/// crates.io's data access policy asks crawlers for at most one request
+/// a second
+const CRATES_IO_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// crates.io, or an alternative registry serving the same web API
+///
+/// Download counts come with crate metadata, so there's no separate
+/// downloads endpoint.
+#[derive(Clone)]
+pub struct CratesIoRegistry {
+    api: String,
+    token: Token,
+    throttle: Throttle,
+}
+
+/// Spaces out the requests to one API, shared by every clone
+#[derive(Clone, Default)]
+struct Throttle {
+    interval: Duration,
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Throttle {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            ..Self::default()
+        }
+    }
+
+    /// Wait until the next request may be sent
+    async fn wait(&self) {
+        if self.interval.is_zero() {
+            return;
+        }
+        let mut next = self.next.lock().await;
+        if let Some(at) = *next {
+            tokio::time::sleep_until(at).await;
+        }
+        *next = Some(Instant::now() + self.interval);
+    }
+}
+
+impl CratesIoRegistry {
+    pub fn public() -> Self {
+        Self {
+            throttle: Throttle::new(CRATES_IO_REQUEST_INTERVAL),
+            ..Self::new(CRATES_IO_API, Token::default())
+        }
+    }
+
+    /// An alternative registry at `api`, which isn't throttled
+    pub fn new(api: &str, token: Token) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_string(),
+            token,
+            throttle: Throttle::default(),
+        }
+    }
+
+    async fn fetch_crate(&self, client: &reqwest::Client, name: &str) -> Result<PackageMetadata> {
+        let token = self.token.read()?;
+        let token = token.as_deref();
+        let url = format!("{}/crates/{}", self.api, name);
+        self.throttle.wait().await;
+        let doc: CratesIoCrate = get_json(client, &url, token, name).await?;
+
+        let mut releases: Vec<Release> = doc
+            .versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .filter_map(|v| {
+                Some(Release {
+                    version: v.num.clone(),
+                    published: date::parse_days(&v.created_at)?,
+                    publisher: v.published_by.as_ref().map(|u| u.login.clone()),
+                    maintainers: Vec::new(),
+                })
+            })
+            .collect();
+        releases.sort_by_key(|r| r.published);
+
+        let deprecated = doc
+            .versions
+            .iter()
+            .filter(|v| v.yanked)
+            .map(|v| (v.num.clone(), "This version was yanked".to_string()))
+            .collect();
+
+        Ok(PackageMetadata {
+            latest_version: doc.krate.max_stable_version.or(doc.krate.max_version),
+            releases,
+            maintainers: Vec::new(),
+            // crates.io reports the last 90 days
+            weekly_downloads: doc.krate.recent_downloads.map(|d| d * 7 / 90),
+            deprecated,
+            repository: doc.krate.repository,
+        })
+    }
+
+    /// The crate's owners, when the registry serves them
+    async fn fetch_owners(&self, client: &reqwest::Client, name: &str) -> Option<Vec<String>> {
+        let token = self.token.read().ok()?;
+        let url = format!("{}/crates/{}/owners", self.api, name);
+        self.throttle.wait().await;
+        let owners: CratesIoOwners = get_json(client, &url, token.as_deref(), name).await.ok()?;
+        Some(owners.users.into_iter().map(|u| u.login).collect())
+    }
+}
+
+impl Registry for CratesIoRegistry {
+    fn fetch<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PackageMetadata>> {
+        Box::pin(self.fetch_crate(client, name))
+    }
+
+    fn owners<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Option<Vec<String>>> {
+        Box::pin(self.fetch_owners(client, name))
+    }
+
+    fn weekly_downloads<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        names: &'a [String],
+    ) -> BoxFuture<'a, HashMap<String, u64>> {
+        Box::pin(async move {
+            fetch_many(client, Arc::new(self.clone()), names)
+                .await
+                .into_iter()
+                .filter_map(|(name, meta)| Some((name, meta.weekly_downloads?)))
+                .collect()
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct CratesIoCrate {
+    #[serde(rename = "crate")]
+    krate: CratesIoCrateInfo,
+
+    #[serde(default)]
+    versions: Vec<CratesIoVersion>,
+}
+
+#[derive(Deserialize)]
+struct CratesIoCrateInfo {
+    max_version: Option<String>,
+    max_stable_version: Option<String>,
+    recent_downloads: Option<u64>,
+    repository: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CratesIoVersion {
+    num: String,
+    created_at: String,
+    #[serde(default)]
+    yanked: bool,
+    published_by: Option<CratesIoUser>,
+}
+
+#[derive(Deserialize)]
+struct CratesIoOwners {
+    #[serde(default)]
+    users: Vec<CratesIoUser>,
+}
+
+#[derive(Deserialize)]
+struct CratesIoUser {
+    login: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let elapsed = runtime.block_on(async {
+            let throttle = Throttle::new(Duration::from_millis(20));
+            let start = Instant::now();
+            let clone = throttle.clone();
+            throttle.wait().await;
+            clone.wait().await;
+            throttle.wait().await;
+            start.elapsed()
+        });
+        assert!(elapsed >= Duration::from_millis(40));
+        // Only the first request goes out right away
+        runtime.block_on(async {
+            let unthrottled = Throttle::default();
+            let start = Instant::now();
+            unthrottled.wait().await;
+            unthrottled.wait().await;
+            assert!(start.elapsed() < Duration::from_millis(20));
+        });
+    }
+}
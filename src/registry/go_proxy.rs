@@ -0,0 +1,109 @@
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+
+use super::{get, get_json, BoxFuture, PackageMetadata, Registry, Release, Token, MAX_CONCURRENT};
+use crate::date;
+use crate::lockfile::escape_path;
+
+const GO_PROXY: &str = "https://proxy.golang.org";
+
+/// A Go module proxy: proxy.golang.org, or a private GOPROXY such as
+/// Athens or Artifactory
+///
+/// The proxy protocol lists versions without dates, so each version's
+/// `.info` is fetched for its publish time. Modules have no maintainers
+/// beyond their repository's owners, and proxies publish no download
+/// counts.
+pub struct GoProxy {
+    url: String,
+    token: Token,
+}
+
+impl GoProxy {
+    pub fn public() -> Self {
+        Self::new(GO_PROXY, Token::default())
+    }
+
+    pub fn new(url: &str, token: Token) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    async fn fetch_module(&self, client: &reqwest::Client, name: &str) -> Result<PackageMetadata> {
+        use tokio::task::JoinSet;
+
+        let token = self.token.read()?;
+        let token = token.as_deref();
+        let base = format!("{}/{}/@v", self.url, escape_path(name));
+        let list = get(client, &format!("{}/list", base), token, name)
+            .await?
+            .text()
+            .await
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read registry metadata for {}", name))?;
+
+        let versions: Vec<&str> = list
+            .lines()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .collect();
+        let mut releases = Vec::new();
+        for chunk in versions.chunks(MAX_CONCURRENT) {
+            let mut join_set = JoinSet::new();
+            for version in chunk {
+                let client = client.clone();
+                let url = format!("{}/{}.info", base, escape_path(version));
+                let token = token.map(str::to_string);
+                let name = name.to_string();
+                join_set.spawn(async move {
+                    get_json::<GoVersionInfo>(&client, &url, token.as_deref(), &name).await
+                });
+            }
+            while let Some(result) = join_set.join_next().await {
+                if let Ok(Ok(info)) = result {
+                    if let Some(published) = date::parse_days(&info.time) {
+                        releases.push(Release {
+                            version: info.version,
+                            published,
+                            publisher: None,
+                            maintainers: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+        releases.sort_by_key(|r| r.published);
+
+        let latest_url = format!("{}/{}/@latest", self.url, escape_path(name));
+        let latest: GoVersionInfo = get_json(client, &latest_url, token, name).await?;
+
+        Ok(PackageMetadata {
+            latest_version: Some(latest.version),
+            releases,
+            // A module path on GitHub is its repository, "/v2" suffixes aside
+            repository: name
+                .starts_with("github.com/")
+                .then(|| format!("https://{}", name)),
+            ..Default::default()
+        })
+    }
+}
+
+impl Registry for GoProxy {
+    fn fetch<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PackageMetadata>> {
+        Box::pin(self.fetch_module(client, name))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GoVersionInfo {
+    version: String,
+    time: String,
+}
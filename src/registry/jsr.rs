@@ -0,0 +1,106 @@
+use miette::Result;
+use serde::Deserialize;
+
+use super::{get_json, BoxFuture, PackageMetadata, Registry, Release, Token};
+use crate::date;
+use crate::lockfile::jsr_package;
+
+const JSR_API: &str = "https://api.jsr.io";
+
+/// The JSR registry's API, for `jsr:` packages and those installed from
+/// its npm compatibility registry
+///
+/// Packages belong to scopes rather than accounts, so maintainers are
+/// left empty, and JSR publishes no download counts.
+pub struct JsrRegistry {
+    api: String,
+    token: Token,
+}
+
+impl JsrRegistry {
+    pub fn public() -> Self {
+        Self::new(JSR_API, Token::default())
+    }
+
+    pub fn new(api: &str, token: Token) -> Self {
+        Self {
+            api: api.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    async fn fetch_package(&self, client: &reqwest::Client, name: &str) -> Result<PackageMetadata> {
+        // "@std/path", or "@jsr/std__path" when installed through npm
+        let jsr_name = jsr_package(name).unwrap_or_else(|| name.to_string());
+        let Some((scope, package)) = jsr_name.strip_prefix('@').and_then(|n| n.split_once('/'))
+        else {
+            miette::bail!("{} is not a scoped JSR package name", name);
+        };
+        let token = self.token.read()?;
+        let token = token.as_deref();
+        let url = format!("{}/scopes/{}/packages/{}", self.api, scope, package);
+        let info: JsrPackage = get_json(client, &url, token, name).await?;
+        let versions: Vec<JsrVersion> =
+            get_json(client, &format!("{}/versions", url), token, name).await?;
+
+        let mut releases: Vec<Release> = versions
+            .iter()
+            .filter(|v| !v.yanked)
+            .filter_map(|v| {
+                Some(Release {
+                    version: v.version.clone(),
+                    published: date::parse_days(&v.created_at)?,
+                    publisher: None,
+                    maintainers: Vec::new(),
+                })
+            })
+            .collect();
+        releases.sort_by_key(|r| r.published);
+
+        Ok(PackageMetadata {
+            latest_version: info.latest_version,
+            releases,
+            deprecated: versions
+                .iter()
+                .filter(|v| v.yanked)
+                .map(|v| (v.version.clone(), "This version was yanked".to_string()))
+                .collect(),
+            repository: info
+                .github_repository
+                .map(|repo| format!("https://github.com/{}/{}", repo.owner, repo.name)),
+            ..Default::default()
+        })
+    }
+}
+
+impl Registry for JsrRegistry {
+    fn fetch<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PackageMetadata>> {
+        Box::pin(self.fetch_package(client, name))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsrPackage {
+    latest_version: Option<String>,
+    github_repository: Option<JsrGithubRepository>,
+}
+
+#[derive(Deserialize)]
+struct JsrGithubRepository {
+    owner: String,
+    name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsrVersion {
+    version: String,
+    created_at: String,
+    #[serde(default)]
+    yanked: bool,
+}
@@ -0,0 +1,492 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+
+use crate::config::RegistryConfig;
+use crate::grouping::matches_pattern;
+use crate::lockfile::LockfileType;
+use crate::types::{Package, PackageSource};
+
+mod crates_io;
+mod go_proxy;
+mod jsr;
+mod npm;
+mod pypi;
+
+pub use crates_io::CratesIoRegistry;
+pub use go_proxy::GoProxy;
+pub use jsr::JsrRegistry;
+pub use npm::NpmRegistry;
+pub use pypi::PyPiRegistry;
+
+/// crates.io rejects requests without a descriptive User-Agent
+const USER_AGENT: &str = concat!(
+    "depx/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/ruidosujeira/depx)"
+);
+
+/// Limit concurrent requests to avoid overwhelming the registry
+pub(crate) const MAX_CONCURRENT: usize = 20;
+
+/// A future returned by [`Registry`] methods, boxed so registries can be
+/// chosen at runtime
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A package registry depx reads release metadata from
+///
+/// There is one implementation per protocol. Private registries speaking
+/// it (Verdaccio or Artifactory for npm, Athens as a Go module proxy) are
+/// the same implementation at another URL, with a token.
+pub trait Registry: Send + Sync {
+    /// Metadata for a single package
+    fn fetch<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PackageMetadata>>;
+
+    /// Accounts that can publish a package, for registries that list them
+    /// apart from its metadata
+    ///
+    /// Only `depx health` needs them, so `fetch` doesn't spend a request
+    /// on them. `None` means the metadata's maintainers are all there is.
+    fn owners<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+        _name: &'a str,
+    ) -> BoxFuture<'a, Option<Vec<String>>> {
+        Box::pin(async { None })
+    }
+
+    /// Last-week download counts for many packages
+    ///
+    /// Registries that don't publish counts report none.
+    fn weekly_downloads<'a>(
+        &'a self,
+        _client: &'a reqwest::Client,
+        _names: &'a [String],
+    ) -> BoxFuture<'a, HashMap<String, u64>> {
+        Box::pin(async { HashMap::new() })
+    }
+}
+
+/// Registry protocols, as named by `type` in a `[registries]` entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RegistryKind {
+    Npm,
+    CratesIo,
+    #[serde(rename = "pypi")]
+    PyPi,
+    GoProxy,
+    Jsr,
+}
+
+impl RegistryKind {
+    pub fn for_lockfile(lockfile_type: LockfileType) -> Self {
+        match lockfile_type {
+            LockfileType::Cargo => RegistryKind::CratesIo,
+            LockfileType::Poetry | LockfileType::Pip => RegistryKind::PyPi,
+            LockfileType::Go => RegistryKind::GoProxy,
+            LockfileType::Npm
+            | LockfileType::Pnpm
+            | LockfileType::Yarn
+            | LockfileType::Bun
+            | LockfileType::Deno => RegistryKind::Npm,
+        }
+    }
+
+    /// The public registry speaking this protocol
+    pub fn public(self) -> Arc<dyn Registry> {
+        match self {
+            RegistryKind::Npm => Arc::new(NpmRegistry::public()),
+            RegistryKind::CratesIo => Arc::new(CratesIoRegistry::public()),
+            RegistryKind::PyPi => Arc::new(PyPiRegistry::public()),
+            RegistryKind::GoProxy => Arc::new(GoProxy::public()),
+            RegistryKind::Jsr => Arc::new(JsrRegistry::public()),
+        }
+    }
+
+    /// A registry speaking this protocol at `url`
+    pub fn at(self, url: &str, token: Token) -> Arc<dyn Registry> {
+        match self {
+            RegistryKind::Npm => Arc::new(NpmRegistry::new(url, token)),
+            RegistryKind::CratesIo => Arc::new(CratesIoRegistry::new(url, token)),
+            RegistryKind::PyPi => Arc::new(PyPiRegistry::new(url, token)),
+            RegistryKind::GoProxy => Arc::new(GoProxy::new(url, token)),
+            RegistryKind::Jsr => Arc::new(JsrRegistry::new(url, token)),
+        }
+    }
+}
+
+/// The registries a project's packages come from
+///
+/// Packages are fetched from the ecosystem's public registry, and `jsr:`
+/// packages from JSR, unless a `[registries]` entry in depx.toml matches
+/// their name. As with `[owners]`, an exact name beats patterns and the
+/// longest matching pattern wins; a bare scope like "@acme" stands for
+/// "@acme/*".
+pub struct Registries {
+    /// The ecosystem's public registry, JSR, then one per rule
+    registries: Vec<Arc<dyn Registry>>,
+    rules: Vec<Rule>,
+}
+
+/// A `[registries]` entry, serving `registries[index]`
+struct Rule {
+    pattern: String,
+    index: usize,
+    mirror: bool,
+}
+
+/// Index of the ecosystem's public registry in `Registries::registries`
+const DEFAULT: usize = 0;
+
+/// Index of JSR in `Registries::registries`
+const JSR: usize = 1;
+
+impl Registries {
+    pub fn new(
+        lockfile_type: LockfileType,
+        config: &BTreeMap<String, RegistryConfig>,
+    ) -> Result<Self> {
+        let kind = RegistryKind::for_lockfile(lockfile_type);
+        let mut registries = vec![kind.public(), RegistryKind::Jsr.public()];
+        let mut rules = Vec::new();
+
+        for (pattern, entry) in config {
+            let token = match &entry.token_env {
+                Some(var) => Token::from_env(var, pattern),
+                None => Token::default(),
+            };
+            rules.push(Rule {
+                pattern: if pattern.starts_with('@') && !pattern.contains('/') {
+                    format!("{}/*", pattern)
+                } else {
+                    pattern.clone()
+                },
+                index: registries.len(),
+                mirror: entry.mirror,
+            });
+            registries.push(entry.kind.unwrap_or(kind).at(&entry.url, token));
+        }
+
+        Ok(Self { registries, rules })
+    }
+
+    /// The `[registries]` entry serving a package name
+    fn rule(&self, name: &str) -> Option<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| matches_pattern(&rule.pattern, name))
+            .max_by_key(|rule| (!rule.pattern.contains('*'), rule.pattern.len()))
+    }
+
+    fn index(&self, package: &Package) -> usize {
+        match self.rule(&package.name) {
+            Some(rule) => rule.index,
+            None if package.source == PackageSource::Jsr => JSR,
+            None => DEFAULT,
+        }
+    }
+
+    /// Whether public advisory databases cover a package
+    ///
+    /// Packages from a private registry may share their name with an
+    /// unrelated public package, so they're only audited when the registry
+    /// is marked as a `mirror` of the public one.
+    pub fn is_public(&self, package: &Package) -> bool {
+        self.rule(&package.name).is_none_or(|rule| rule.mirror)
+    }
+
+    /// The packages public advisory databases cover
+    pub fn public_packages(&self, packages: &HashMap<String, Package>) -> HashMap<String, Package> {
+        packages
+            .iter()
+            .filter(|(_, package)| self.is_public(package))
+            .map(|(key, package)| (key.clone(), package.clone()))
+            .collect()
+    }
+
+    /// Package names grouped by the registry serving them
+    fn by_registry(&self, packages: &[&Package]) -> BTreeMap<usize, Vec<String>> {
+        let mut groups: BTreeMap<usize, BTreeSet<String>> = BTreeMap::new();
+        for package in packages {
+            groups
+                .entry(self.index(package))
+                .or_default()
+                .insert(package.name.clone());
+        }
+        groups
+            .into_iter()
+            .map(|(index, names)| (index, names.into_iter().collect()))
+            .collect()
+    }
+
+    /// Fetch metadata for many packages concurrently, keyed by name
+    ///
+    /// Packages whose lookup fails are left out of the result rather than
+    /// failing the whole run; the caller reports them as unknown.
+    pub async fn fetch_all(
+        &self,
+        client: &reqwest::Client,
+        packages: &[&Package],
+    ) -> HashMap<String, PackageMetadata> {
+        let mut metadata = HashMap::new();
+        for (index, names) in self.by_registry(packages) {
+            metadata.extend(fetch_many(client, self.registries[index].clone(), &names).await);
+        }
+        metadata
+    }
+
+    /// Fill in the maintainers of registries that list them separately,
+    /// see [`Registry::owners`]
+    pub async fn fetch_owners(
+        &self,
+        client: &reqwest::Client,
+        packages: &[&Package],
+        metadata: &mut HashMap<String, PackageMetadata>,
+    ) {
+        for (index, names) in self.by_registry(packages) {
+            for name in names {
+                let Some(meta) = metadata.get_mut(&name) else {
+                    continue;
+                };
+                if let Some(owners) = self.registries[index].owners(client, &name).await {
+                    meta.maintainers = owners;
+                }
+            }
+        }
+    }
+
+    /// Fetch last-week download counts for many packages, keyed by name
+    pub async fn fetch_weekly_downloads(
+        &self,
+        client: &reqwest::Client,
+        packages: &[&Package],
+    ) -> HashMap<String, u64> {
+        let mut downloads = HashMap::new();
+        for (index, names) in self.by_registry(packages) {
+            downloads.extend(
+                self.registries[index]
+                    .weekly_downloads(client, &names)
+                    .await,
+            );
+        }
+        downloads
+    }
+}
+
+/// Registry metadata for a single package
+#[derive(Debug, Clone, Default)]
+pub struct PackageMetadata {
+    /// Latest published version
+    pub latest_version: Option<String>,
+
+    /// Every published release, oldest first
+    pub releases: Vec<Release>,
+
+    /// Accounts that can currently publish the package
+    pub maintainers: Vec<String>,
+
+    /// Downloads over the last week, when the registry reports them with metadata
+    pub weekly_downloads: Option<u64>,
+
+    /// Deprecation or yank notices, by version
+    pub deprecated: HashMap<String, String>,
+
+    /// Source repository URL, as the package declares it
+    pub repository: Option<String>,
+}
+
+/// A single published release
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub version: String,
+
+    /// Publish date as days since the Unix epoch
+    pub published: i64,
+
+    /// Account that published this release, when the registry records it
+    pub publisher: Option<String>,
+
+    /// Maintainers listed at the time of this release
+    pub maintainers: Vec<String>,
+}
+
+impl PackageMetadata {
+    /// Days since the Unix epoch of the most recent release
+    pub fn last_published(&self) -> Option<i64> {
+        self.releases.iter().map(|r| r.published).max()
+    }
+}
+
+pub fn client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .build()
+        .into_diagnostic()
+        .with_context(|| "Failed to build HTTP client")
+}
+
+/// Fetch metadata for many packages of one registry, `MAX_CONCURRENT` at a time
+async fn fetch_many(
+    client: &reqwest::Client,
+    registry: Arc<dyn Registry>,
+    names: &[String],
+) -> HashMap<String, PackageMetadata> {
+    use tokio::task::JoinSet;
+
+    let mut metadata = HashMap::new();
+    let mut join_set = JoinSet::new();
+
+    for chunk in names.chunks(MAX_CONCURRENT) {
+        for name in chunk {
+            let client = client.clone();
+            let registry = registry.clone();
+            let name = name.clone();
+            join_set.spawn(async move {
+                let result = registry.fetch(&client, &name).await;
+                (name, result)
+            });
+        }
+
+        // Wait for this batch to complete before starting next
+        while let Some(result) = join_set.join_next().await {
+            if let Ok((name, Ok(meta))) = result {
+                metadata.insert(name, meta);
+            }
+        }
+    }
+
+    metadata
+}
+
+/// A registry's bearer token, read from its `token-env` variable when a
+/// request is sent, so runs that never reach the registry don't need it
+#[derive(Debug, Clone, Default)]
+pub struct Token {
+    /// The variable, and the `[registries]` pattern it was given for
+    env: Option<(String, String)>,
+}
+
+impl Token {
+    pub fn from_env(var: &str, pattern: &str) -> Self {
+        Self {
+            env: Some((var.to_string(), pattern.to_string())),
+        }
+    }
+
+    /// The token, failing when its variable isn't set
+    fn read(&self) -> Result<Option<String>> {
+        let Some((var, pattern)) = &self.env else {
+            return Ok(None);
+        };
+        std::env::var(var).map(Some).map_err(|_| {
+            miette::miette!(
+                "{} is not set; [registries.\"{}\"] reads its token from it",
+                var,
+                pattern
+            )
+        })
+    }
+}
+
+/// GET a registry URL, sending the token as a bearer token
+async fn get(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+    name: &str,
+) -> Result<reqwest::Response> {
+    let mut request = client.get(url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to fetch registry metadata for {}", name))?;
+
+    if !response.status().is_success() {
+        miette::bail!("Registry returned {} for {}", response.status(), name);
+    }
+    Ok(response)
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    token: Option<&str>,
+    name: &str,
+) -> Result<T> {
+    get(client, url, token, name)
+        .await?
+        .json()
+        .await
+        .into_diagnostic()
+        .with_context(|| format!("Failed to parse registry metadata for {}", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registries_route_by_name() {
+        let config: crate::config::Config = toml::from_str(
+            r#"
+[registries."@acme"]
+url = "https://npm.acme.internal/"
+
+[registries."@acme/public-*"]
+url = "https://artifactory.acme.internal/api/npm/npm-remote"
+mirror = true
+
+[registries."@std/path"]
+url = "https://npm.acme.internal/"
+"#,
+        )
+        .unwrap();
+        let registries = Registries::new(LockfileType::Deno, &config.registries).unwrap();
+
+        let index = |name: &str, source: PackageSource| {
+            let mut package = Package::new(name, "1.0.0");
+            package.source = source;
+            registries.index(&package)
+        };
+        assert_eq!(index("chalk", PackageSource::Registry), DEFAULT);
+        assert_eq!(index("@std/assert", PackageSource::Jsr), JSR);
+        // Rules are kept in key order: "@acme", "@acme/public-*", "@std/path"
+        assert_eq!(index("@acme/ui", PackageSource::Registry), 2);
+        assert_eq!(index("@acme/public-utils", PackageSource::Registry), 3);
+        assert_eq!(index("@std/path", PackageSource::Jsr), 4);
+
+        assert!(registries.is_public(&Package::new("chalk", "1.0.0")));
+        assert!(!registries.is_public(&Package::new("@acme/ui", "1.0.0")));
+        assert!(registries.is_public(&Package::new("@acme/public-utils", "1.0.0")));
+    }
+
+    #[test]
+    fn test_registries_token_env() {
+        let config: crate::config::Config = toml::from_str(
+            "[registries.\"*\"]\nurl = \"https://goproxy.acme.internal\"\ntype = \"go-proxy\"\ntoken-env = \"DEPX_TEST_UNSET_TOKEN\"\n",
+        )
+        .unwrap();
+        // Only a request needs the token
+        assert!(Registries::new(LockfileType::Go, &config.registries).is_ok());
+        let error = Token::from_env("DEPX_TEST_UNSET_TOKEN", "*")
+            .read()
+            .err()
+            .unwrap();
+        assert!(error
+            .to_string()
+            .contains("DEPX_TEST_UNSET_TOKEN is not set"));
+    }
+}
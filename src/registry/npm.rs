@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use miette::Result;
+use serde::Deserialize;
+
+use super::{get_json, BoxFuture, PackageMetadata, Registry, Release, Token, MAX_CONCURRENT};
+use crate::date;
+
+const NPM_REGISTRY: &str = "https://registry.npmjs.org";
+const NPM_DOWNLOADS_API: &str = "https://api.npmjs.org/downloads/point/last-week";
+
+/// The npm downloads API accepts at most this many packages per bulk query
+const NPM_BULK_DOWNLOADS_LIMIT: usize = 128;
+
+/// An npm-compatible registry: registry.npmjs.org, or Verdaccio,
+/// Artifactory, GitHub Packages and the like at their own URL
+///
+/// Only the public registry has a downloads API.
+pub struct NpmRegistry {
+    url: String,
+    token: Token,
+    downloads_api: Option<&'static str>,
+}
+
+impl NpmRegistry {
+    pub fn public() -> Self {
+        Self {
+            url: NPM_REGISTRY.to_string(),
+            token: Token::default(),
+            downloads_api: Some(NPM_DOWNLOADS_API),
+        }
+    }
+
+    pub fn new(url: &str, token: Token) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            token,
+            downloads_api: None,
+        }
+    }
+
+    async fn fetch_packument(
+        &self,
+        client: &reqwest::Client,
+        name: &str,
+    ) -> Result<PackageMetadata> {
+        // Scoped names keep the "@" but escape the slash
+        let url = format!("{}/{}", self.url, name.replace('/', "%2F"));
+        let doc: NpmPackument = get_json(client, &url, self.token.read()?.as_deref(), name).await?;
+
+        let mut releases: Vec<Release> = doc
+            .time
+            .iter()
+            .filter(|(version, _)| !matches!(version.as_str(), "created" | "modified"))
+            .filter_map(|(version, timestamp)| {
+                let manifest = doc.versions.get(version);
+                Some(Release {
+                    version: version.clone(),
+                    published: date::parse_days(timestamp)?,
+                    publisher: manifest
+                        .and_then(|m| m.npm_user.as_ref())
+                        .map(|u| u.name().to_string()),
+                    maintainers: manifest
+                        .map(|m| m.maintainers.iter().map(|u| u.name().to_string()).collect())
+                        .unwrap_or_default(),
+                })
+            })
+            .collect();
+        releases.sort_by_key(|r| r.published);
+
+        // `npm deprecate` with an empty message lifts a deprecation
+        let deprecated = doc
+            .versions
+            .iter()
+            .filter_map(|(version, manifest)| {
+                let message = manifest.deprecated.as_ref()?.as_str()?;
+                (!message.is_empty()).then(|| (version.clone(), message.to_string()))
+            })
+            .collect();
+
+        Ok(PackageMetadata {
+            latest_version: doc.dist_tags.get("latest").cloned(),
+            releases,
+            maintainers: doc
+                .maintainers
+                .iter()
+                .map(|u| u.name().to_string())
+                .collect(),
+            weekly_downloads: None,
+            deprecated,
+            repository: doc.repository.map(|repository| match repository {
+                NpmRepository::Object { url } => url,
+                NpmRepository::Text(url) => url,
+            }),
+        })
+    }
+}
+
+impl Registry for NpmRegistry {
+    fn fetch<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PackageMetadata>> {
+        Box::pin(self.fetch_packument(client, name))
+    }
+
+    fn weekly_downloads<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        names: &'a [String],
+    ) -> BoxFuture<'a, HashMap<String, u64>> {
+        match self.downloads_api {
+            Some(api) => Box::pin(fetch_downloads(client, api, names)),
+            None => Box::pin(async { HashMap::new() }),
+        }
+    }
+}
+
+/// Last-week downloads from the npm downloads API, with bulk queries for
+/// unscoped packages
+async fn fetch_downloads(
+    client: &reqwest::Client,
+    api: &str,
+    names: &[String],
+) -> HashMap<String, u64> {
+    use tokio::task::JoinSet;
+
+    let mut downloads = HashMap::new();
+    let (scoped, unscoped): (Vec<&String>, Vec<&String>) =
+        names.iter().partition(|n| n.starts_with('@'));
+
+    // Bulk queries don't support scoped packages
+    for chunk in unscoped.chunks(NPM_BULK_DOWNLOADS_LIMIT) {
+        let joined: Vec<&str> = chunk.iter().map(|n| n.as_str()).collect();
+        let url = format!("{}/{}", api, joined.join(","));
+
+        // A single-package query answers with the single-package shape
+        if chunk.len() == 1 {
+            if let Ok(point) = get_json::<NpmDownloadPoint>(client, &url, None, chunk[0]).await {
+                downloads.insert(chunk[0].clone(), point.downloads);
+            }
+            continue;
+        }
+
+        if let Ok(bulk) =
+            get_json::<HashMap<String, Option<NpmDownloadPoint>>>(client, &url, None, "bulk query")
+                .await
+        {
+            for (name, point) in bulk {
+                if let Some(point) = point {
+                    downloads.insert(name, point.downloads);
+                }
+            }
+        }
+    }
+
+    let mut join_set = JoinSet::new();
+    for chunk in scoped.chunks(MAX_CONCURRENT) {
+        for name in chunk {
+            let client = client.clone();
+            let name = (*name).clone();
+            let url = format!("{}/{}", api, name);
+            join_set.spawn(async move {
+                let result = get_json::<NpmDownloadPoint>(&client, &url, None, &name).await;
+                (name, result)
+            });
+        }
+
+        while let Some(result) = join_set.join_next().await {
+            if let Ok((name, Ok(point))) = result {
+                downloads.insert(name, point.downloads);
+            }
+        }
+    }
+
+    downloads
+}
+
+#[derive(Deserialize)]
+struct NpmPackument {
+    #[serde(default, rename = "dist-tags")]
+    dist_tags: HashMap<String, String>,
+
+    #[serde(default)]
+    time: HashMap<String, String>,
+
+    #[serde(default)]
+    maintainers: Vec<NpmPerson>,
+
+    #[serde(default)]
+    versions: HashMap<String, NpmVersionManifest>,
+
+    repository: Option<NpmRepository>,
+}
+
+/// `repository` is an object with a URL, or a "github:user/repo" shorthand
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NpmRepository {
+    Object { url: String },
+    Text(String),
+}
+
+#[derive(Deserialize)]
+struct NpmVersionManifest {
+    #[serde(default)]
+    maintainers: Vec<NpmPerson>,
+
+    #[serde(rename = "_npmUser")]
+    npm_user: Option<NpmPerson>,
+
+    /// A message, though some old packuments have `false`
+    deprecated: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct NpmDownloadPoint {
+    downloads: u64,
+}
+
+/// Old packuments list people as "name <email>" strings instead of objects
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NpmPerson {
+    Object { name: String },
+    Text(String),
+}
+
+impl NpmPerson {
+    fn name(&self) -> &str {
+        match self {
+            NpmPerson::Object { name } => name,
+            NpmPerson::Text(text) => text.split(" <").next().unwrap_or(text),
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+
+use miette::Result;
+use serde::Deserialize;
+
+use super::{get_json, BoxFuture, PackageMetadata, Registry, Release, Token, MAX_CONCURRENT};
+use crate::date;
+
+const PYPI_API: &str = "https://pypi.org/pypi";
+const PYPI_STATS_API: &str = "https://pypistats.org/api/packages";
+
+/// PyPI, or an index serving its JSON API (Artifactory, devpi)
+///
+/// The JSON API doesn't expose the accounts that can publish a project,
+/// so maintainers are left empty. Download counts come from pypistats.org
+/// for the public index only.
+pub struct PyPiRegistry {
+    url: String,
+    token: Token,
+    stats_api: Option<&'static str>,
+}
+
+impl PyPiRegistry {
+    pub fn public() -> Self {
+        Self {
+            url: PYPI_API.to_string(),
+            token: Token::default(),
+            stats_api: Some(PYPI_STATS_API),
+        }
+    }
+
+    pub fn new(url: &str, token: Token) -> Self {
+        Self {
+            url: url.trim_end_matches('/').to_string(),
+            token,
+            stats_api: None,
+        }
+    }
+
+    async fn fetch_project(&self, client: &reqwest::Client, name: &str) -> Result<PackageMetadata> {
+        let url = format!("{}/{}/json", self.url, name);
+        let doc: PyPiProject = get_json(client, &url, self.token.read()?.as_deref(), name).await?;
+
+        // A release is published when its first file is uploaded
+        let mut releases: Vec<Release> = doc
+            .releases
+            .iter()
+            .filter(|(_, files)| !files.is_empty() && !files.iter().all(|f| f.yanked))
+            .filter_map(|(version, files)| {
+                Some(Release {
+                    version: version.clone(),
+                    published: files
+                        .iter()
+                        .filter_map(|f| date::parse_days(&f.upload_time_iso_8601))
+                        .min()?,
+                    publisher: None,
+                    maintainers: Vec::new(),
+                })
+            })
+            .collect();
+        releases.sort_by_key(|r| r.published);
+
+        let deprecated = doc
+            .releases
+            .iter()
+            .filter(|(_, files)| !files.is_empty() && files.iter().all(|f| f.yanked))
+            .map(|(version, files)| {
+                let message = match files.iter().find_map(|f| f.yanked_reason.as_deref()) {
+                    Some(reason) if !reason.is_empty() => {
+                        format!("This version was yanked: {}", reason)
+                    }
+                    _ => "This version was yanked".to_string(),
+                };
+                (version.clone(), message)
+            })
+            .collect();
+
+        // There's no dedicated field; projects label their links freely
+        let project_urls = doc.info.project_urls.unwrap_or_default();
+        let repository = ["Source", "Source Code", "Repository", "Code", "GitHub"]
+            .iter()
+            .find_map(|label| {
+                project_urls
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(label))
+                    .map(|(_, url)| url.clone())
+            })
+            .or(doc.info.home_page.filter(|url| url.contains("github.com")));
+
+        Ok(PackageMetadata {
+            latest_version: Some(doc.info.version),
+            releases,
+            maintainers: Vec::new(),
+            weekly_downloads: None,
+            deprecated,
+            repository,
+        })
+    }
+}
+
+impl Registry for PyPiRegistry {
+    fn fetch<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        name: &'a str,
+    ) -> BoxFuture<'a, Result<PackageMetadata>> {
+        Box::pin(self.fetch_project(client, name))
+    }
+
+    fn weekly_downloads<'a>(
+        &'a self,
+        client: &'a reqwest::Client,
+        names: &'a [String],
+    ) -> BoxFuture<'a, HashMap<String, u64>> {
+        match self.stats_api {
+            Some(api) => Box::pin(fetch_downloads(client, api, names)),
+            None => Box::pin(async { HashMap::new() }),
+        }
+    }
+}
+
+/// Last-week downloads from pypistats.org, one project at a time
+async fn fetch_downloads(
+    client: &reqwest::Client,
+    api: &str,
+    names: &[String],
+) -> HashMap<String, u64> {
+    use tokio::task::JoinSet;
+
+    let mut downloads = HashMap::new();
+    let mut join_set = JoinSet::new();
+    for chunk in names.chunks(MAX_CONCURRENT) {
+        for name in chunk {
+            let client = client.clone();
+            let name = name.clone();
+            let url = format!("{}/{}/recent", api, name);
+            join_set.spawn(async move {
+                let result = get_json::<PyPiStats>(&client, &url, None, &name).await;
+                (name, result)
+            });
+        }
+
+        while let Some(result) = join_set.join_next().await {
+            if let Ok((name, Ok(stats))) = result {
+                downloads.insert(name, stats.data.last_week);
+            }
+        }
+    }
+
+    downloads
+}
+
+#[derive(Deserialize)]
+struct PyPiProject {
+    info: PyPiInfo,
+
+    #[serde(default)]
+    releases: HashMap<String, Vec<PyPiFile>>,
+}
+
+#[derive(Deserialize)]
+struct PyPiInfo {
+    version: String,
+    project_urls: Option<HashMap<String, String>>,
+    home_page: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PyPiFile {
+    upload_time_iso_8601: String,
+    #[serde(default)]
+    yanked: bool,
+    yanked_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PyPiStats {
+    data: PyPiRecentDownloads,
+}
+
+#[derive(Deserialize)]
+struct PyPiRecentDownloads {
+    last_week: u64,
+}
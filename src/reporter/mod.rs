@@ -1,19 +1,51 @@
-use colored::Colorize;
+mod table;
 
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use colored::{Color, ColoredString, Colorize};
+
+use crate::analyzer::extract_package_name;
 use crate::duplicates::suggest_resolution;
+use crate::fix::diff::{line_diff, DiffLine};
+use crate::fix::rewrite::ImportRewrite;
+use crate::grouping::GroupBy;
+use crate::rules::Rule;
 use crate::types::{
-    DeprecatedPackage, DuplicateAnalysis, DuplicateSeverity, ImportMap, PackageExplanation,
-    Severity, UsageAnalysis, Vulnerability,
+    AlignSource, AlignmentReport, AuditChanges, BinEntry, BinStatus, BundleFormat, BundleReport,
+    Classification, ClassificationReason, CleanCandidate, CouplingReport, CoverageReport,
+    DeadCodeReport, DependencyMetrics, DeprecatedPackage, DuplicateAnalysis, DuplicateSeverity,
+    EnginesReport, EntryPointAnalysis, ExpectedUnusedRule, FindingGroup, FirstSeen, FixOutcome,
+    HealthReport, ImportMap, IntegrityIssueKind, IntegrityReport, LicenseReport, LicenseSource,
+    LintFinding, OverrideAnalysis, OverrideStatus, PackageExplanation, PackageRename, PackageTypes,
+    PeerIssue, PeerIssueKind, PinningReport, ProjectSummary, PublishCheck, PublishIssueKind,
+    RangeStyle, RenamedFile, SbomComponent, SbomDiff, Severity, SnapshotDiff, TypesSource,
+    UnusedExportsReport, UpdateCandidate, UsageAnalysis, UsageClass, VendoredPackage,
+    Vulnerability,
 };
+use crate::update::PackageManager;
+
+pub use table::TableSort;
+use table::{Cell, Table};
 
 /// Reporter for formatted terminal output
 pub struct Reporter {
     verbose: bool,
+    sort: Option<TableSort>,
+    wide: bool,
+    expand: bool,
+    all: bool,
 }
 
 impl Reporter {
     pub fn new() -> Self {
-        Self { verbose: false }
+        Self {
+            verbose: false,
+            sort: None,
+            wide: false,
+            expand: false,
+            all: false,
+        }
     }
 
     pub fn verbose(mut self) -> Self {
@@ -21,28 +53,55 @@ impl Reporter {
         self
     }
 
+    /// Sort order for tabular reports (each report has its own default)
+    pub fn sort(mut self, sort: Option<TableSort>) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Don't truncate tables to the terminal width
+    pub fn wide(mut self, wide: bool) -> Self {
+        self.wide = wide;
+        self
+    }
+
+    /// List the members of summarized clusters instead of one line each
+    pub fn expand(mut self, expand: bool) -> Self {
+        self.expand = expand;
+        self
+    }
+
+    /// Also list low-severity findings, which are hidden by default
+    pub fn all(mut self, all: bool) -> Self {
+        self.all = all;
+        self
+    }
+
     /// Print a status message
+    ///
+    /// Status lines, like info, warnings and errors, go to stderr so that
+    /// `--json` output on stdout stays parseable.
     pub fn status(&self, action: &str, message: &str) {
-        println!("{:>12} {}", action.green().bold(), message);
+        eprintln!("{:>12} {}", action.green().bold(), message);
     }
 
     /// Print an info message
     pub fn info(&self, message: &str) {
-        println!("{:>12} {}", "Info".cyan().bold(), message);
+        eprintln!("{:>12} {}", "Info".cyan().bold(), message);
     }
 
     /// Print an error message
     pub fn error(&self, message: &str) {
-        println!("{:>12} {}", "Error".red().bold(), message);
+        eprintln!("{:>12} {}", "Error".red().bold(), message);
     }
 
     /// Print a warning message
     pub fn warn(&self, message: &str) {
-        println!("{:>12} {}", "Warning".yellow().bold(), message);
+        eprintln!("{:>12} {}", "Warning".yellow().bold(), message);
     }
 
     /// Report full analysis results
-    pub fn report_full(&self, analysis: &UsageAnalysis, _imports: &ImportMap) {
+    pub fn report_full(&self, analysis: &UsageAnalysis, imports: &ImportMap, pm: PackageManager) {
         println!();
         println!("{}", "Dependency Analysis Report".bold().underline());
         println!();
@@ -62,27 +121,69 @@ impl Reporter {
         }
         if !analysis.expected_unused_direct.is_empty() {
             println!(
-                "  {} dev/build tools {}",
+                "  {} {} {}",
                 analysis.expected_unused_direct.len().to_string().cyan(),
+                if analysis
+                    .expected_unused_direct
+                    .iter()
+                    .any(|p| p.is_optional)
+                {
+                    "dev/build tools and optional packages"
+                } else {
+                    "dev/build tools"
+                },
                 "(expected, not imported)".dimmed()
             );
         }
+        if !analysis.overlapping.is_empty() {
+            println!(
+                "  {} groups of overlapping dependencies",
+                analysis.overlapping.len().to_string().yellow()
+            );
+        }
+        if !analysis.phantom.is_empty() {
+            println!(
+                "  {} packages imported but not declared {}",
+                analysis.phantom.len().to_string().red(),
+                "(phantom)".red()
+            );
+        }
+        if !analysis.misplaced.is_empty() {
+            println!(
+                "  {} dependencies in the wrong {} section",
+                analysis.misplaced.len().to_string().yellow(),
+                pm.manifest
+            );
+        }
+        if !analysis.type_only.is_empty() {
+            println!(
+                "  {} dependencies only imported as types",
+                analysis.type_only.len().to_string().yellow()
+            );
+        }
+        if !analysis.other_platform.is_empty() {
+            println!(
+                "  {} packages for other platforms {}",
+                analysis.other_platform.len().to_string().dimmed(),
+                "(not installed here, skipped)".dimmed()
+            );
+        }
         println!();
 
+        self.print_phantom(analysis, false, pm);
+        self.print_misplaced(analysis);
+        self.print_type_only(analysis);
+
         // Unused direct dependencies (truly removable)
         if !analysis.unused_direct.is_empty() {
-            println!("{}", "Unused Dependencies (safe to remove):".red().bold());
-            for pkg in &analysis.unused_direct {
-                let dev_marker = if pkg.is_dev { " (dev)" } else { "" };
-                println!(
-                    "  {} {}{}",
-                    "-".red(),
-                    format!("{}@{}", pkg.name, pkg.version).white(),
-                    dev_marker.dimmed()
-                );
-            }
+            self.print_rule_heading(
+                "Unused Dependencies (safe to remove):".red().bold(),
+                Rule::UnusedDependency,
+            );
+            self.unused_table(analysis).print(2);
             println!();
-            println!("  {} {}", "Tip:".dimmed(), "npm uninstall <package>".cyan());
+            println!("  {} {}", "Tip:".dimmed(), pm.remove("<package>").cyan());
+            self.print_rule_docs(Rule::UnusedDependency);
             println!();
         }
 
@@ -93,12 +194,100 @@ impl Reporter {
                 "Dev/Build Tools (not imported, expected):".cyan().bold()
             );
             for pkg in &analysis.expected_unused_direct {
+                if pkg.is_optional {
+                    println!(
+                        "  {} {} {}",
+                        "~".cyan(),
+                        pkg.label().dimmed(),
+                        "(optionalDependencies)".dimmed()
+                    );
+                } else {
+                    println!("  {} {}", "~".cyan(), pkg.label().dimmed());
+                }
+            }
+            println!();
+        }
+
+        // Several packages doing the same job
+        if !analysis.overlapping.is_empty() {
+            self.print_rule_heading(
+                "Overlapping Dependencies (same purpose):".yellow().bold(),
+                Rule::OverlappingDependencies,
+            );
+            for overlap in &analysis.overlapping {
                 println!(
-                    "  {} {}",
-                    "~".cyan(),
-                    format!("{}@{}", pkg.name, pkg.version).dimmed()
+                    "  {} {}: {}",
+                    "~".yellow(),
+                    overlap.category.white(),
+                    overlap.packages.join(", ")
+                );
+            }
+            println!();
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "consolidate on one package per category to shrink the tree".cyan()
+            );
+            self.print_rule_docs(Rule::OverlappingDependencies);
+            println!();
+        }
+
+        // Direct dependencies only ever loaded lazily
+        let dynamic_only: Vec<_> = imports
+            .dynamic_only_packages()
+            .into_iter()
+            .filter(|(name, _)| {
+                analysis
+                    .used
+                    .iter()
+                    .any(|u| u.package.is_direct && u.package.name == *name)
+            })
+            .collect();
+        if !dynamic_only.is_empty() {
+            println!(
+                "{}",
+                "Only Loaded via Dynamic import() (lazy-loading candidates):"
+                    .cyan()
+                    .bold()
+            );
+            for (name, usages) in &dynamic_only {
+                println!("  {} {}", "~".cyan(), name.white());
+                for usage in usages.iter() {
+                    println!(
+                        "      {}",
+                        format!("{}:{}", usage.file_path.display(), usage.line).dimmed()
+                    );
+                }
+            }
+            println!();
+        }
+
+        // Specifiers built at runtime that no package could be read from
+        let dynamic = imports.unresolved_dynamic_imports();
+        if !dynamic.is_empty() {
+            println!(
+                "{}",
+                "Unresolved Dynamic Imports (analysis may be incomplete):"
+                    .yellow()
+                    .bold()
+            );
+            for import in dynamic.iter().take(20) {
+                println!(
+                    "  {} {} {}",
+                    "?".yellow(),
+                    format!("{}:{}", import.file_path.display(), import.line).white(),
+                    import.expression.dimmed()
                 );
             }
+            if dynamic.len() > 20 {
+                println!("  {} ... and {} more", "".dimmed(), dynamic.len() - 20);
+            }
+            println!();
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Packages these load aren't counted as used; check before removing any".cyan()
+            );
             println!();
         }
 
@@ -111,7 +300,7 @@ impl Reporter {
                 println!(
                     "  {} {}{}",
                     "+".green(),
-                    format!("{}@{}", pkg.name, pkg.version).white(),
+                    pkg.label().white(),
                     direct_marker.dimmed()
                 );
             }
@@ -126,11 +315,7 @@ impl Reporter {
             if !unused_transitive.is_empty() {
                 println!("{}", "Unused Transitive Dependencies:".yellow().bold());
                 for pkg in unused_transitive.iter().take(20) {
-                    println!(
-                        "  {} {}",
-                        "?".yellow(),
-                        format!("{}@{}", pkg.name, pkg.version).dimmed()
-                    );
+                    println!("  {} {}", "?".yellow(), pkg.label().dimmed());
                 }
                 if unused_transitive.len() > 20 {
                     println!(
@@ -144,8 +329,69 @@ impl Reporter {
         }
     }
 
+    /// Report which runtime dependencies each configured entry point needs
+    pub fn report_entry_points(&self, analysis: &EntryPointAnalysis) {
+        println!("{}", "Dependencies by Entry Point".bold().underline());
+        println!();
+
+        for entry in &analysis.entry_points {
+            if !entry.analyzed {
+                let files: Vec<String> = entry
+                    .files
+                    .iter()
+                    .map(|f| f.display().to_string())
+                    .collect();
+                println!(
+                    "  {} {} {}",
+                    "?".yellow(),
+                    entry.name.cyan(),
+                    format!("({} not analyzed)", files.join(", ")).yellow()
+                );
+                continue;
+            }
+
+            println!(
+                "  {} {} {}",
+                "-".dimmed(),
+                entry.name.cyan(),
+                format!("({} dependencies)", entry.packages.len()).dimmed()
+            );
+        }
+        println!();
+
+        if !analysis.partial.is_empty() {
+            println!("{}", "Needed by Some Entry Points Only:".yellow().bold());
+            for dep in &analysis.partial {
+                println!(
+                    "  {} {} {}",
+                    "~".yellow(),
+                    dep.package.white(),
+                    format!("<- {}", dep.entry_points.join(", ")).dimmed()
+                );
+            }
+            println!();
+        }
+
+        if !analysis.unreachable.is_empty() {
+            println!("{}", "Not Reachable from Any Entry Point:".red().bold());
+            for package in &analysis.unreachable {
+                println!("  {} {}", "-".red(), package.white());
+            }
+            println!();
+        }
+
+        if analysis.entry_points.iter().any(|e| !e.analyzed) {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Point entry points at source files; build output isn't analyzed".cyan()
+            );
+            println!();
+        }
+    }
+
     /// Report only unused packages
-    pub fn report_unused(&self, analysis: &UsageAnalysis) {
+    pub fn report_unused(&self, analysis: &UsageAnalysis, pm: PackageManager) {
         println!();
 
         if analysis.unused_direct.is_empty() && analysis.unused.is_empty() {
@@ -163,315 +409,3464 @@ impl Reporter {
         println!();
 
         if !analysis.unused_direct.is_empty() {
-            println!("{}", "Direct dependencies (in package.json):".bold());
-            for pkg in &analysis.unused_direct {
-                let dev_marker = if pkg.is_dev { " (dev)" } else { "" };
-                println!(
-                    "  {} {}{}",
-                    "-".red(),
-                    pkg.name.white(),
-                    dev_marker.dimmed()
-                );
-            }
+            self.print_rule_heading(
+                format!("Direct dependencies (in {}):", pm.manifest).bold(),
+                Rule::UnusedDependency,
+            );
+            self.unused_table(analysis).print(2);
             println!();
             println!(
                 "{}",
-                "Tip: Run `npm uninstall <package>` to remove unused packages".dimmed()
+                format!(
+                    "Tip: Run `{}` to remove unused packages",
+                    pm.remove("<package>")
+                )
+                .dimmed()
+            );
+            println!(
+                "{}",
+                format!("Docs: {}", Rule::UnusedDependency.docs_url()).dimmed()
             );
         }
 
         println!();
+        self.print_phantom(analysis, false, pm);
     }
 
-    /// Report why a package is installed
-    pub fn report_why(&self, _package_name: &str, explanation: &PackageExplanation) {
-        println!();
-        println!(
-            "{} {}@{}",
-            "Package:".bold(),
-            explanation.package.name.cyan(),
-            explanation.package.version
-        );
+    /// Report packages imported without being declared, and where
+    pub fn report_phantom(&self, analysis: &UsageAnalysis, pm: PackageManager) {
         println!();
 
-        if explanation.package.is_direct {
+        if analysis.phantom.is_empty() {
             println!(
-                "  {} This is a {} in package.json",
-                "->".green(),
-                if explanation.package.is_dev {
-                    "dev dependency".yellow()
-                } else {
-                    "direct dependency".green()
-                }
+                "{}",
+                format!("Every imported package is declared in {}", pm.manifest)
+                    .green()
+                    .bold()
             );
-        } else {
-            println!("{}", "Dependency chains:".bold());
+            return;
+        }
 
-            for (i, chain) in explanation.dependency_chains.iter().enumerate() {
-                let chain_str = chain.join(" -> ");
+        self.print_phantom(analysis, true, pm);
+    }
 
-                let prefix = if i == 0 { "->" } else { "  " };
-                println!("  {} {}", prefix.green(), chain_str);
+    /// Report unused and phantom findings per directory or owner
+    pub fn report_groups(&self, groups: &[FindingGroup], group_by: GroupBy) {
+        if groups.is_empty() {
+            return;
+        }
+
+        let title = match group_by {
+            GroupBy::Dir => "Findings by Directory",
+            GroupBy::Owner => "Findings by Owner",
+        };
+        println!("{}", title.bold().underline());
+        println!();
+
+        for group in groups {
+            println!(
+                "{} {}",
+                group.name.cyan().bold(),
+                format!("({})", group.unused.len() + group.phantom.len()).dimmed()
+            );
+            for name in &group.unused {
+                println!("  {} {} {}", "-".red(), name.white(), "unused".dimmed());
+            }
+            for import in &group.phantom {
+                println!(
+                    "  {} {} {} {}",
+                    "!".red().bold(),
+                    import
+                        .resolved_package
+                        .as_deref()
+                        .unwrap_or_default()
+                        .white(),
+                    "phantom".dimmed(),
+                    format!("{}:{}", import.file_path.display(), import.line).dimmed()
+                );
             }
+            println!();
+        }
+    }
+
+    /// Separate one project's report from the next in a recursive run
+    pub fn project_header(&self, path: &std::path::Path) {
+        println!();
+        println!(
+            "{}",
+            format!("Project {}", path.display()).bold().underline()
+        );
+    }
+
+    /// Summarize the projects of a recursive run
+    pub fn report_projects(&self, projects: &[ProjectSummary]) {
+        println!();
+        println!("{}", "Projects".bold().underline());
+        println!();
 
-            if explanation.dependency_chains.is_empty() {
+        for project in projects {
+            let path = project.path.display().to_string();
+            let owners = owners_suffix(&project.owners);
+            if let Some(error) = &project.error {
                 println!(
-                    "  {} Could not determine dependency chain (might be orphaned)",
-                    "?".yellow()
+                    "  {} {} {}{}",
+                    "!".red().bold(),
+                    path.white(),
+                    error.red(),
+                    owners
+                );
+            } else if project.unused + project.phantom > 0 {
+                println!(
+                    "  {} {} {}{}",
+                    "~".yellow(),
+                    path.white(),
+                    format!("{} unused, {} phantom", project.unused, project.phantom).yellow(),
+                    owners
+                );
+            } else {
+                println!(
+                    "  {} {} {}{}",
+                    "-".green(),
+                    path.white(),
+                    "clean".dimmed(),
+                    owners
                 );
             }
         }
+        println!();
+    }
 
-        if explanation.is_dev_path {
+    /// Report the import specifiers `depx fix --rename` rewrote, or would
+    /// rewrite with `dry_run`
+    pub fn report_renames(&self, renames: &[PackageRename], files: &[RenamedFile], dry_run: bool) {
+        println!();
+
+        if renames.is_empty() {
+            println!("{}", "No imported package has been renamed".green().bold());
             println!();
+            return;
+        }
+
+        println!("{}", "Renamed Packages".bold());
+        for rename in renames {
             println!(
-                "  {} This package is only required for development",
-                "Note:".dimmed()
+                "  {} {} -> {} {}",
+                "~".yellow(),
+                rename.from.white(),
+                rename.to.cyan(),
+                format!("({})", rename.reason).dimmed()
             );
         }
+        println!();
 
+        self.print_rewritten(files, "renamed", dry_run);
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Install the new packages and uninstall the old ones; their APIs may have changed too"
+                .cyan()
+        );
         println!();
     }
 
-    /// Report vulnerabilities
-    pub fn report_vulnerabilities(&self, vulnerabilities: &[Vulnerability]) {
+    /// Report the import specifiers `depx rewrite` rewrote, or would
+    /// rewrite with `dry_run`
+    pub fn report_rewrite(&self, rewrite: &ImportRewrite, files: &[RenamedFile], dry_run: bool) {
         println!();
 
-        if vulnerabilities.is_empty() {
-            println!("{}", "No known vulnerabilities found!".green().bold());
+        if files.is_empty() {
+            println!(
+                "{}",
+                format!("No imports of {} to rewrite", rewrite.from)
+                    .green()
+                    .bold()
+            );
+            println!();
             return;
         }
 
-        println!(
-            "{} {} found",
-            vulnerabilities.len().to_string().red().bold(),
-            if vulnerabilities.len() == 1 {
-                "vulnerability"
-            } else {
-                "vulnerabilities"
+        self.print_rewritten(files, "rewritten", dry_run);
+        if dry_run {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Run again without --dry-run to write the changes".cyan()
+            );
+        } else if extract_package_name(&rewrite.from) != extract_package_name(&rewrite.to) {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                format!(
+                    "Make sure {} is installed before removing the old package",
+                    extract_package_name(&rewrite.to).unwrap_or_default()
+                )
+                .cyan()
+            );
+        }
+        println!();
+    }
+
+    /// Diffs of rewritten files with `dry_run`, then how many imports changed
+    fn print_rewritten(&self, files: &[RenamedFile], label: &str, dry_run: bool) {
+        if dry_run {
+            for file in files {
+                let name = file.path.display();
+                println!("{}", format!("--- {}", name).red());
+                println!("{}", format!("+++ {} ({})", name, label).green());
+                for line in line_diff(&file.original, &file.updated) {
+                    match line {
+                        DiffLine::Context(text) => println!(" {}", text.dimmed()),
+                        DiffLine::Removed(text) => println!("{}", format!("-{}", text).red()),
+                        DiffLine::Added(text) => println!("{}", format!("+{}", text).green()),
+                        DiffLine::Gap => println!("{}", "@@".cyan()),
+                    }
+                }
+                println!();
             }
+        }
+
+        let rewritten: usize = files.iter().map(|f| f.rewritten).sum();
+        println!(
+            "  {} {} in {} files",
+            if dry_run { "Would rewrite" } else { "Rewrote" },
+            format!("{} imports", rewritten).cyan(),
+            files.len()
         );
-        println!();
+    }
 
-        // Group by severity
-        let critical: Vec<_> = vulnerabilities
-            .iter()
-            .filter(|v| v.severity == Severity::Critical)
-            .collect();
-        let high: Vec<_> = vulnerabilities
-            .iter()
-            .filter(|v| v.severity == Severity::High)
-            .collect();
-        let medium: Vec<_> = vulnerabilities
-            .iter()
-            .filter(|v| v.severity == Severity::Medium)
-            .collect();
-        let low: Vec<_> = vulnerabilities
-            .iter()
-            .filter(|v| v.severity == Severity::Low)
-            .collect();
+    /// Report the changes `depx fix` made to package.json
+    pub fn report_fix(&self, outcome: &FixOutcome) {
+        println!();
 
-        let severity_groups: Vec<(&str, Vec<_>, fn(&str) -> String)> = vec![
-            ("CRITICAL", critical, |s: &str| s.red().bold().to_string()),
-            ("HIGH", high, |s: &str| s.red().to_string()),
-            ("MEDIUM", medium, |s: &str| s.yellow().to_string()),
-            ("LOW", low, |s: &str| s.dimmed().to_string()),
-        ];
+        if outcome.applied.is_empty() && outcome.skipped.is_empty() {
+            println!("{}", "Nothing to fix!".green().bold());
+            return;
+        }
 
-        for (severity_name, vulns, color_fn) in severity_groups {
-            if vulns.is_empty() {
-                continue;
+        if !outcome.applied.is_empty() {
+            println!("{}", "Applied".green().bold());
+            for action in &outcome.applied {
+                println!(
+                    "  {} {} {}",
+                    "+".green(),
+                    action.to_string().white(),
+                    format!("({})", action.reason()).dimmed()
+                );
             }
+            println!();
+        }
 
-            println!("{}", color_fn(severity_name));
+        if !outcome.skipped.is_empty() {
+            println!("{}", "Skipped".yellow().bold());
+            for skipped in &outcome.skipped {
+                println!(
+                    "  {} {} {}",
+                    "~".yellow(),
+                    skipped.action.to_string().white(),
+                    format!("({})", skipped.reason).dimmed()
+                );
+            }
+            println!();
+        }
+
+        if !outcome.applied.is_empty() {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Run your package manager's install to update the lockfile".cyan()
+            );
+            println!();
+        }
+    }
+
+    /// Report what `depx fix --dry-run` would change, as a diff of `manifest`
+    pub fn report_fix_diff(&self, outcome: &FixOutcome, manifest: &str) {
+        println!();
+
+        let diff = line_diff(&outcome.original, &outcome.updated);
+        if diff.is_empty() {
+            println!("{}", "Nothing to fix!".green().bold());
+        } else {
+            println!("{}", format!("--- {}", manifest).red());
+            println!("{}", format!("+++ {} (fixed)", manifest).green());
+            for line in diff {
+                match line {
+                    DiffLine::Context(text) => println!(" {}", text.dimmed()),
+                    DiffLine::Removed(text) => println!("{}", format!("-{}", text).red()),
+                    DiffLine::Added(text) => println!("{}", format!("+{}", text).green()),
+                    DiffLine::Gap => println!("{}", "@@".cyan()),
+                }
+            }
+        }
+        println!();
+
+        for action in &outcome.applied {
+            println!(
+                "  {} {} {}",
+                "+".green(),
+                action.to_string().white(),
+                format!("({})", action.reason()).dimmed()
+            );
+        }
+        for skipped in &outcome.skipped {
+            println!(
+                "  {} {} {}",
+                "~".yellow(),
+                skipped.action.to_string().white(),
+                format!("(skipped: {})", skipped.reason).dimmed()
+            );
+        }
+        if !outcome.applied.is_empty() || !outcome.skipped.is_empty() {
+            println!();
+        }
+
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            format!("Dry run: {} was not modified", manifest).cyan()
+        );
+        println!();
+    }
+
+    /// Report how findings changed since a saved snapshot
+    pub fn report_snapshot_diff(&self, diff: &SnapshotDiff, snapshot: &std::path::Path) {
+        println!(
+            "{} {}",
+            "Changes since".bold().underline(),
+            snapshot.display().to_string().bold().underline()
+        );
+        println!();
+
+        if diff.is_empty() {
+            println!(
+                "  {}",
+                "No change in unused or phantom dependencies".dimmed()
+            );
+            println!();
+            return;
+        }
+
+        let sections = [
+            ("New unused", &diff.new_unused, "+".red().bold()),
+            ("New phantom", &diff.new_phantom, "+".red().bold()),
+            ("Resolved unused", &diff.resolved_unused, "-".green().bold()),
+            (
+                "Resolved phantom",
+                &diff.resolved_phantom,
+                "-".green().bold(),
+            ),
+        ];
+        for (title, names, marker) in sections {
+            if names.is_empty() {
+                continue;
+            }
+            println!("{} {}", title.bold(), format!("({})", names.len()).dimmed());
+            for name in names {
+                println!("  {} {}", marker, name.white());
+            }
+            println!();
+        }
+    }
+
+    /// Report how an SBOM drifted from the lockfile
+    pub fn report_sbom_diff(&self, diff: &SbomDiff, sbom: &std::path::Path) {
+        println!();
+        println!(
+            "{} {}",
+            "SBOM drift in".bold().underline(),
+            sbom.display().to_string().bold().underline()
+        );
+        println!(
+            "  {}",
+            format!(
+                "{} {} components, {} installed packages",
+                diff.sbom_components, diff.format, diff.lockfile_packages
+            )
+            .dimmed()
+        );
+        println!();
+
+        if diff.is_empty() {
+            println!("{}", "The SBOM matches the lockfile!".green().bold());
+            return;
+        }
+
+        let component = |c: &SbomComponent| format!("{}@{}", c.name, c.version);
+        if !diff.added.is_empty() {
+            println!(
+                "{} {}",
+                "Installed but missing from the SBOM".bold(),
+                format!("({})", diff.added.len()).dimmed()
+            );
+            for c in &diff.added {
+                println!("  {} {}", "+".red().bold(), component(c).white());
+            }
+            println!();
+        }
+        if !diff.removed.is_empty() {
+            println!(
+                "{} {}",
+                "In the SBOM but not installed".bold(),
+                format!("({})", diff.removed.len()).dimmed()
+            );
+            for c in &diff.removed {
+                println!("  {} {}", "-".red().bold(), component(c).white());
+            }
+            println!();
+        }
+        if !diff.changed.is_empty() {
+            println!(
+                "{} {}",
+                "Different versions".bold(),
+                format!("({})", diff.changed.len()).dimmed()
+            );
+            for drift in &diff.changed {
+                println!(
+                    "  {} {} {} {} {}",
+                    "~".yellow(),
+                    drift.name.white(),
+                    drift.sbom_versions.join(", ").dimmed(),
+                    "->".green(),
+                    drift.lockfile_versions.join(", ")
+                );
+            }
+            println!();
+        }
+
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "regenerate the SBOM from this lockfile before publishing it".cyan()
+        );
+        println!();
+    }
+
+    /// Unused direct dependencies with what removing each would drop
+    ///
+    /// `--sort severity` lists runtime dependencies before dev ones, since
+    /// those ship to users.
+    fn unused_table(&self, analysis: &UsageAnalysis) -> Table {
+        let removes = |name: &str| {
+            analysis
+                .removable_transitive
+                .get(name)
+                .copied()
+                .unwrap_or(0)
+        };
+
+        let mut packages: Vec<_> = analysis.unused_direct.iter().collect();
+        match self.sort.unwrap_or(TableSort::Name) {
+            TableSort::Name => packages.sort_by(|a, b| a.name.cmp(&b.name)),
+            TableSort::Size => packages.sort_by(|a, b| {
+                removes(&b.name)
+                    .cmp(&removes(&a.name))
+                    .then_with(|| a.name.cmp(&b.name))
+            }),
+            TableSort::Severity => {
+                packages.sort_by(|a, b| a.is_dev.cmp(&b.is_dev).then_with(|| a.name.cmp(&b.name)))
+            }
+        }
+
+        let mut table = Table::new(&["Package", "Version", "Type", "Removes"])
+            .align_right(3)
+            .wide(self.wide);
+        for pkg in packages {
+            let removed = removes(&pkg.name);
+            table.row(vec![
+                Cell::new(&pkg.name).color(Color::White),
+                Cell::new(pkg.version_label()).dimmed(),
+                match (pkg.is_dev, pkg.is_optional) {
+                    (true, _) => Cell::new("dev").dimmed(),
+                    (false, true) => Cell::new("optional").color(Color::Yellow),
+                    (false, false) => Cell::new("prod").color(Color::Red),
+                },
+                if removed > 0 {
+                    Cell::new(format!("+{}", removed)).color(Color::Yellow)
+                } else {
+                    Cell::new("0").dimmed()
+                },
+            ]);
+        }
+        table
+    }
+
+    /// Imports of packages missing from the manifest, either the first of
+    /// each or, with `locations`, all of them
+    fn print_phantom(&self, analysis: &UsageAnalysis, locations: bool, pm: PackageManager) {
+        if analysis.phantom.is_empty() {
+            return;
+        }
+
+        self.print_rule_heading(
+            format!(
+                "Phantom Dependencies (imported but not in {}):",
+                pm.manifest
+            )
+            .red()
+            .bold(),
+            Rule::PhantomDependency,
+        );
+        for phantom in &analysis.phantom {
+            let installed = match &phantom.installed_version {
+                Some(version) => format!("(installed transitively @{})", version),
+                None => "(not installed)".to_string(),
+            };
+            if !locations {
+                println!(
+                    "  {} {} {} {}",
+                    "!".red().bold(),
+                    phantom.name.white(),
+                    installed.dimmed(),
+                    format!("{}:{}", phantom.file_path.display(), phantom.line).dimmed()
+                );
+                continue;
+            }
+            println!(
+                "  {} {} {}",
+                "!".red().bold(),
+                phantom.name.white(),
+                installed.dimmed()
+            );
+            for import in &phantom.imports {
+                println!(
+                    "      {}:{} {}",
+                    import.file_path.display(),
+                    import.line,
+                    import.specifier.dimmed()
+                );
+            }
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            format!("{} to declare what the code imports", pm.add("<package>")).cyan()
+        );
+        self.print_rule_docs(Rule::PhantomDependency);
+        println!();
+    }
+
+    /// Direct dependencies to move between dependencies and devDependencies
+    fn print_misplaced(&self, analysis: &UsageAnalysis) {
+        let (to_dev, to_runtime): (Vec<_>, Vec<_>) = analysis
+            .misplaced
+            .iter()
+            .partition(|misplaced| misplaced.should_be_dev());
+
+        for (heading, misplaced) in [
+            (
+                "Should Be devDependencies (only tests, config and scripts import them):",
+                &to_dev,
+            ),
+            (
+                "Should Be dependencies (shipped code imports them):",
+                &to_runtime,
+            ),
+        ] {
+            if misplaced.is_empty() {
+                continue;
+            }
+            self.print_rule_heading(heading.yellow().bold(), Rule::MisplacedDependency);
+            for dependency in misplaced {
+                println!("  {} {}", "~".yellow(), dependency.package.label().white());
+                for (import, context) in dependency.imports.iter().zip(&dependency.contexts) {
+                    println!(
+                        "      {} {}",
+                        format!("{}:{}", import.file_path.display(), import.line).dimmed(),
+                        format!("({})", context).dimmed()
+                    );
+                }
+            }
+            println!();
+            self.print_rule_docs(Rule::MisplacedDependency);
+            println!();
+        }
+    }
+
+    /// Runtime dependencies shipped code only imports as types
+    fn print_type_only(&self, analysis: &UsageAnalysis) {
+        if analysis.type_only.is_empty() {
+            return;
+        }
+
+        self.print_rule_heading(
+            "Only Used as Types (erased at runtime):".yellow().bold(),
+            Rule::TypeOnlyDependency,
+        );
+        for dependency in &analysis.type_only {
+            println!("  {} {}", "~".yellow(), dependency.package.label().white());
+            for import in &dependency.imports {
+                println!(
+                    "      {}",
+                    format!("{}:{}", import.file_path.display(), import.line).dimmed()
+                );
+            }
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Move them to devDependencies, unless your published .d.ts files import them".cyan()
+        );
+        self.print_rule_docs(Rule::TypeOnlyDependency);
+        println!();
+    }
+
+    /// A finding section's heading, followed by its rule ID
+    fn print_rule_heading(&self, heading: ColoredString, rule: Rule) {
+        println!("{} {}", heading, rule.id().dimmed());
+    }
+
+    /// Where a finding section's rule is documented
+    fn print_rule_docs(&self, rule: Rule) {
+        println!("  {} {}", "Docs:".dimmed(), rule.docs_url().dimmed());
+    }
+
+    /// Report why a package is installed
+    pub fn report_why(&self, explanation: &PackageExplanation, manifest: &str) {
+        println!();
+        println!(
+            "{} {}",
+            "Package:".bold(),
+            explanation.package.label().cyan()
+        );
+        println!();
+
+        if explanation.package.is_direct {
+            println!(
+                "  {} This is a {} in {}",
+                "->".green(),
+                if explanation.package.is_dev {
+                    "dev dependency".yellow()
+                } else if explanation.package.is_build {
+                    "build dependency".yellow()
+                } else {
+                    "direct dependency".green()
+                },
+                manifest
+            );
+        } else {
+            if !explanation.dependency_chains.is_empty() {
+                println!("{}", "Dependency chains:".bold());
+            }
+
+            for (i, chain) in explanation.dependency_chains.iter().enumerate() {
+                let ranges = explanation.chain_ranges.get(i);
+                let chain_str = chain
+                    .iter()
+                    .enumerate()
+                    .map(
+                        |(j, name)| match ranges.and_then(|r| r.get(j)).and_then(|r| r.as_ref()) {
+                            Some(range) => format!("{}{}", name, format!("@{}", range).dimmed()),
+                            None => name.clone(),
+                        },
+                    )
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+
+                let prefix = if i == 0 { "->" } else { "  " };
+                println!("  {} {}", prefix.green(), chain_str);
+            }
+
+            if !explanation.peer_of.is_empty() {
+                if !explanation.dependency_chains.is_empty() {
+                    println!();
+                }
+                println!("{}", "Peer dependency of:".bold());
+                for peer in &explanation.peer_of {
+                    println!(
+                        "  {} peer of {} {}",
+                        "->".green(),
+                        peer.package.white(),
+                        format!("(wants {})", peer.range).dimmed()
+                    );
+                }
+            }
+
+            if let Some(parent) = &explanation.package.bundled_in {
+                println!(
+                    "  {} Shipped inside {} {}",
+                    "->".green(),
+                    parent.white(),
+                    "(bundleDependencies, not installed separately)".dimmed()
+                );
+            }
+
+            if explanation.dependency_chains.is_empty()
+                && explanation.peer_of.is_empty()
+                && explanation.package.bundled_in.is_none()
+            {
+                println!(
+                    "  {} Could not determine dependency chain (might be orphaned)",
+                    "?".yellow()
+                );
+            }
+        }
+
+        if explanation.is_dev_path {
+            println!();
+            println!(
+                "  {} This package is only required for development",
+                "Note:".dimmed()
+            );
+        } else if explanation.package.is_build && !explanation.package.is_direct {
+            println!();
+            println!(
+                "  {} This package is only required to build the project (build-dependencies)",
+                "Note:".dimmed()
+            );
+        }
+
+        println!();
+    }
+
+    /// Report which group `depx analyze` puts a package in, and the rule that decided it
+    pub fn report_classification(&self, classification: &Classification, pm: PackageManager) {
+        const MAX_SITES: usize = 5;
+        let pkg = &classification.package;
+
+        println!();
+        println!("{} {}", "Package:".bold(), pkg.label().cyan());
+        println!();
+
+        let group = match classification.class {
+            UsageClass::Used => "used".green().bold(),
+            UsageClass::ExpectedUnused => "expected unused".cyan().bold(),
+            UsageClass::DevOnly => "dev-only".yellow().bold(),
+            UsageClass::Unused => "unused".red().bold(),
+            UsageClass::OtherPlatform => "built for another platform".dimmed(),
+            UsageClass::FollowsParent => "reported with its parent".dimmed(),
+        };
+        println!("  {} Classified as {}", "->".green(), group);
+
+        match &classification.reason {
+            ClassificationReason::Imported(sites) => {
+                println!(
+                    "  {} Imported {} {}:",
+                    "->".green(),
+                    sites.len(),
+                    if sites.len() == 1 { "time" } else { "times" }
+                );
+                for site in sites.iter().take(MAX_SITES) {
+                    println!(
+                        "      {} {}",
+                        format!("{}:{}", site.file_path.display(), site.line).dimmed(),
+                        site.specifier
+                    );
+                }
+                if sites.len() > MAX_SITES {
+                    println!(
+                        "      {}",
+                        format!("... and {} more", sites.len() - MAX_SITES).dimmed()
+                    );
+                }
+            }
+            ClassificationReason::Invoked(commands) => {
+                println!(
+                    "  {} Never imported, but provides a binary that is run:",
+                    "->".green()
+                );
+                for command in commands.iter().take(MAX_SITES) {
+                    println!(
+                        "      {} {}",
+                        format!("{}:{}", command.file_path.display(), command.line).dimmed(),
+                        command.binary
+                    );
+                }
+                if commands.len() > MAX_SITES {
+                    println!(
+                        "      {}",
+                        format!("... and {} more", commands.len() - MAX_SITES).dimmed()
+                    );
+                }
+            }
+            ClassificationReason::RequiredBy(chain) => {
+                println!(
+                    "  {} Never imported, but required by a used package:",
+                    "->".green()
+                );
+                println!("      {}", chain.join(" -> "));
+            }
+            ClassificationReason::Expected(rule) => {
+                let rule = match rule {
+                    ExpectedUnusedRule::TypeDefinitions => {
+                        "it is an @types/* package (type definitions are never imported)"
+                            .to_string()
+                    }
+                    ExpectedUnusedRule::KnownTool => {
+                        "it is a known build, lint, test or release tool".to_string()
+                    }
+                    ExpectedUnusedRule::Prefix(prefix) => {
+                        format!("its name starts with the tooling prefix \"{}\"", prefix)
+                    }
+                    ExpectedUnusedRule::Optional => {
+                        "it is an optional dependency, which may not install on every platform"
+                            .to_string()
+                    }
+                };
+                println!(
+                    "  {} Never imported, which is expected: {}",
+                    "->".green(),
+                    rule
+                );
+            }
+            ClassificationReason::DevTransitive => {
+                println!(
+                    "  {} Never imported, and only dev dependencies pull it in",
+                    "->".green()
+                );
+            }
+            ClassificationReason::NotReferenced => {
+                println!(
+                    "  {} Not imported, not run from any script, and no used package requires it",
+                    "!".red().bold()
+                );
+            }
+            ClassificationReason::Platform => {
+                println!(
+                    "  {} Optional, and its os/cpu/libc exclude this machine",
+                    "->".green()
+                );
+            }
+            ClassificationReason::Parent(parent) => {
+                println!(
+                    "  {} Platform build or bundled copy of {}",
+                    "->".green(),
+                    parent.white()
+                );
+            }
+        }
+
+        if let Some(rule) = classification.suppressed_by {
+            println!(
+                "  {} {} ({}) is off in depx.toml, so analyze doesn't report it",
+                "->".green(),
+                rule.id(),
+                rule.name()
+            );
+        } else if classification.class == UsageClass::Unused && pkg.is_direct {
+            println!();
+            println!("  {} {}", "Tip:".dimmed(), pm.remove(&pkg.name).cyan());
+        }
+
+        println!();
+    }
+
+    /// Show one unused dependency during `depx clean --interactive`
+    pub fn report_clean_candidate(
+        &self,
+        candidate: &CleanCandidate,
+        index: usize,
+        total: usize,
+        pm: PackageManager,
+    ) {
+        let pkg = &candidate.package;
+
+        println!();
+        println!(
+            "{} {}@{} {}",
+            format!("[{}/{}]", index, total).dimmed(),
+            pkg.name.cyan().bold(),
+            pkg.version,
+            if pkg.is_dev {
+                "(devDependencies)".dimmed()
+            } else {
+                "(dependencies)".dimmed()
+            }
+        );
+
+        if candidate.required_by.is_empty() {
+            println!(
+                "  {} Direct dependency in {}; nothing imports or requires it",
+                "->".green(),
+                pm.manifest
+            );
+        } else {
+            println!(
+                "  {} Also required by {}; it stays installed after removal",
+                "~".yellow(),
+                candidate.required_by.join(", ").white()
+            );
+        }
+
+        match &candidate.last_usage {
+            Some(usage) => println!(
+                "  {} Last import change: {} {} {}",
+                "->".green(),
+                usage.commit.yellow(),
+                usage.date.dimmed(),
+                usage.subject
+            ),
+            None => println!(
+                "  {} {}",
+                "-".dimmed(),
+                "No import of it in git history".dimmed()
+            ),
+        }
+
+        let mut impact = vec![match candidate.removes {
+            0 => "no other packages".to_string(),
+            1 => "1 transitive package".to_string(),
+            n => format!("{} transitive packages", n),
+        }];
+        if let Some(size) = candidate.disk_size {
+            impact.push(format!("{} on disk", format_bytes(size)));
+        }
+        println!("  {} Removes {}", "->".green(), impact.join(", "));
+    }
+
+    /// Report available updates; `numbered` adds the index `depx update -i` selects by
+    pub fn report_updates(&self, candidates: &[UpdateCandidate], numbered: bool) {
+        println!();
+
+        if candidates.is_empty() {
+            println!(
+                "{}",
+                "All direct dependencies are up to date!".green().bold()
+            );
+            println!();
+            return;
+        }
+
+        let breaking = candidates.iter().filter(|c| c.breaking).count();
+        let fixing = candidates.iter().filter(|c| !c.fixes.is_empty()).count();
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} updates available",
+            candidates.len().to_string().cyan()
+        );
+        if breaking > 0 {
+            println!(
+                "  {} cross a major version {}",
+                breaking.to_string().yellow(),
+                "(may break)".yellow()
+            );
+        }
+        if fixing > 0 {
+            println!("  {} fix known vulnerabilities", fixing.to_string().green());
+        }
+        println!();
+
+        let mut headers = vec!["Package", "Current", "Latest", "Type", "Notes"];
+        if numbered {
+            headers.insert(0, "#");
+        }
+        let mut table = Table::new(&headers).wide(self.wide);
+        if numbered {
+            table = table.align_right(0);
+        }
+        for (index, candidate) in candidates.iter().enumerate() {
+            let mut notes = Vec::new();
+            if candidate.breaking {
+                notes.push("breaking".to_string());
+            }
+            if !candidate.fixes.is_empty() {
+                notes.push(format!("fixes {}", candidate.fixes.join(", ")));
+            }
+            let notes = if !candidate.fixes.is_empty() {
+                Cell::new(notes.join("; ")).color(Color::Green)
+            } else if candidate.breaking {
+                Cell::new(notes.join("; ")).color(Color::Yellow)
+            } else {
+                Cell::new("")
+            };
+
+            let mut row = vec![
+                Cell::new(&candidate.package).color(Color::White),
+                Cell::new(&candidate.current).dimmed(),
+                if candidate.breaking {
+                    Cell::new(&candidate.latest).color(Color::Yellow)
+                } else {
+                    Cell::new(&candidate.latest).color(Color::Green)
+                },
+                if candidate.is_dev {
+                    Cell::new("dev").dimmed()
+                } else {
+                    Cell::new("prod")
+                },
+                notes,
+            ];
+            if numbered {
+                row.insert(0, Cell::new((index + 1).to_string()).dimmed());
+            }
+            table.row(row);
+        }
+        table.print(2);
+        println!();
+
+        if candidates.iter().any(|c| c.notes.is_some()) {
+            self.print_release_notes(candidates);
+        }
+        if candidates.iter().any(|c| c.api.is_some()) {
+            self.print_api_changes(candidates);
+        }
+
+        if !numbered {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "depx update --interactive to pick updates and write them to the manifest".cyan()
+            );
+            println!();
+        }
+    }
+
+    /// Print the breaking changes each update's release notes call out
+    fn print_release_notes(&self, candidates: &[UpdateCandidate]) {
+        const SHOWN: usize = 5;
+
+        println!("{}", "Release Notes".bold());
+        for candidate in candidates {
+            let update = format!(
+                "{} {} -> {}",
+                candidate.package, candidate.current, candidate.latest
+            );
+            let Some(notes) = &candidate.notes else {
+                println!("  {} {}", update.white(), "no release notes found".dimmed());
+                continue;
+            };
+
+            let mut releases = match notes.versions.len() {
+                1 => "1 release".to_string(),
+                n => format!("{} releases", n),
+            };
+            if notes.truncated {
+                releases.push_str(" (older releases not read)");
+            }
+            if notes.breaking.is_empty() {
+                println!(
+                    "  {} {}",
+                    update.white(),
+                    format!("{}, no breaking changes called out", releases).dimmed()
+                );
+                continue;
+            }
+            println!(
+                "  {} {}",
+                update.white(),
+                format!("{}, {}", releases, notes.source).dimmed()
+            );
+            for change in notes.breaking.iter().take(SHOWN) {
+                println!(
+                    "    {} {} {}",
+                    "!".yellow().bold(),
+                    change.version.dimmed(),
+                    change.summary
+                );
+            }
+            if notes.breaking.len() > SHOWN {
+                println!(
+                    "      {}",
+                    format!("... {} more", notes.breaking.len() - SHOWN).dimmed()
+                );
+            }
+        }
+        println!();
+    }
+
+    /// API changes of each update, with those the workspace uses listed
+    fn print_api_changes(&self, candidates: &[UpdateCandidate]) {
+        const SHOWN: usize = 5;
+
+        println!("{}", "API Changes".bold());
+        for candidate in candidates {
+            let update = format!(
+                "{} {} -> {}",
+                candidate.package, candidate.current, candidate.latest
+            );
+            let Some(api) = &candidate.api else {
+                println!("  {} {}", update.white(), "sources not available".dimmed());
+                continue;
+            };
+            if api.changes.is_empty() {
+                println!(
+                    "  {} {}",
+                    update.white(),
+                    "no public items removed or changed".dimmed()
+                );
+                continue;
+            }
+
+            let changed = match api.changes.len() {
+                1 => "1 item removed or changed".to_string(),
+                n => format!("{} items removed or changed", n),
+            };
+            let used: Vec<_> = api.used().collect();
+            if used.is_empty() {
+                println!(
+                    "  {} {}",
+                    update.white(),
+                    format!("{}, none that this workspace uses", changed).dimmed()
+                );
+                continue;
+            }
+            println!(
+                "  {} {}",
+                update.white(),
+                format!("{}, {} used here", changed, used.len()).yellow()
+            );
+            for change in used.iter().take(SHOWN) {
+                println!(
+                    "    {} {} {}",
+                    "!".yellow().bold(),
+                    change.kind.to_string().dimmed(),
+                    change.item
+                );
+            }
+            if used.len() > SHOWN {
+                println!(
+                    "      {}",
+                    format!("... {} more", used.len() - SHOWN).dimmed()
+                );
+            }
+        }
+        println!();
+    }
+
+    /// Report vulnerabilities
+    pub fn report_vulnerabilities(&self, vulnerabilities: &[Vulnerability]) {
+        println!();
+
+        if vulnerabilities.is_empty() {
+            println!("{}", "No known vulnerabilities found!".green().bold());
+            return;
+        }
+
+        println!(
+            "{} {} found",
+            vulnerabilities.len().to_string().red().bold(),
+            if vulnerabilities.len() == 1 {
+                "vulnerability"
+            } else {
+                "vulnerabilities"
+            }
+        );
+        println!();
+
+        // Group by severity
+        let critical: Vec<_> = vulnerabilities
+            .iter()
+            .filter(|v| v.severity == Severity::Critical)
+            .collect();
+        let high: Vec<_> = vulnerabilities
+            .iter()
+            .filter(|v| v.severity == Severity::High)
+            .collect();
+        let medium: Vec<_> = vulnerabilities
+            .iter()
+            .filter(|v| v.severity == Severity::Medium)
+            .collect();
+        let low: Vec<_> = vulnerabilities
+            .iter()
+            .filter(|v| v.severity == Severity::Low)
+            .collect();
+
+        let severity_groups: Vec<(&str, Vec<_>, fn(&str) -> String)> = vec![
+            ("CRITICAL", critical, |s: &str| s.red().bold().to_string()),
+            ("HIGH", high, |s: &str| s.red().to_string()),
+            ("MEDIUM", medium, |s: &str| s.yellow().to_string()),
+            ("LOW", low, |s: &str| s.dimmed().to_string()),
+        ];
+
+        for (severity_name, vulns, color_fn) in severity_groups {
+            if vulns.is_empty() {
+                continue;
+            }
+
+            println!("{}", color_fn(severity_name));
             for vuln in vulns {
                 let used_marker = if vuln.affects_used_code {
                     " [USED]".red().bold().to_string()
                 } else {
-                    " [unused]".dimmed().to_string()
+                    " [unused]".dimmed().to_string()
+                };
+
+                println!(
+                    "  {} {}@{} - {}{}{}",
+                    vuln.id.white(),
+                    vuln.package_name.cyan(),
+                    vuln.installed_version.yellow(),
+                    vuln.title.dimmed(),
+                    used_marker,
+                    owners_suffix(&vuln.owners)
+                );
+
+                if let Some(ref patched) = vuln.patched_version {
+                    println!(
+                        "       {} {} -> {}",
+                        "Fix:".dimmed(),
+                        vuln.installed_version.red(),
+                        patched.green()
+                    );
+                }
+            }
+            println!();
+        }
+    }
+
+    /// Report vulnerabilities found or gone since the previous audit
+    pub fn report_audit_changes(&self, changes: &AuditChanges) {
+        let Some(since) = &changes.since else {
+            println!();
+            println!(
+                "{}",
+                "No previous audit to compare with, so every finding is new".yellow()
+            );
+            self.report_vulnerabilities(&changes.new);
+            return;
+        };
+
+        println!();
+        if changes.new.is_empty() && changes.resolved.is_empty() {
+            println!(
+                "{}",
+                format!("No changes since the audit of {}", since)
+                    .green()
+                    .bold()
+            );
+            return;
+        }
+
+        let plural = |count: usize| {
+            if count == 1 {
+                "vulnerability"
+            } else {
+                "vulnerabilities"
+            }
+        };
+        if !changes.new.is_empty() {
+            println!(
+                "{} new {} since {}",
+                changes.new.len().to_string().red().bold(),
+                plural(changes.new.len()),
+                since
+            );
+            for vuln in &changes.new {
+                println!(
+                    "  {} {} {}@{} - {} {}",
+                    "+".red().bold(),
+                    vuln.id.white(),
+                    vuln.package_name.cyan(),
+                    vuln.installed_version.yellow(),
+                    vuln.title.dimmed(),
+                    format!("({})", vuln.severity).dimmed()
+                );
+                if let Some(ref patched) = vuln.patched_version {
+                    println!(
+                        "       {} {} -> {}",
+                        "Fix:".dimmed(),
+                        vuln.installed_version.red(),
+                        patched.green()
+                    );
+                }
+            }
+            println!();
+        }
+        if !changes.resolved.is_empty() {
+            println!(
+                "{} {} resolved",
+                changes.resolved.len().to_string().green().bold(),
+                plural(changes.resolved.len())
+            );
+            for vuln in &changes.resolved {
+                println!(
+                    "  {} {} {}@{} - {}",
+                    "-".green().bold(),
+                    vuln.id.white(),
+                    vuln.package_name.cyan(),
+                    vuln.installed_version,
+                    vuln.title.dimmed()
+                );
+            }
+            println!();
+        }
+    }
+
+    /// Report deprecated packages
+    pub fn report_deprecated(&self, deprecated: &[DeprecatedPackage]) {
+        println!();
+
+        if deprecated.is_empty() {
+            println!("{}", "No deprecated packages found!".green().bold());
+            return;
+        }
+
+        println!(
+            "{} {} found",
+            deprecated.len().to_string().yellow().bold(),
+            if deprecated.len() == 1 {
+                "deprecated package"
+            } else {
+                "deprecated packages"
+            }
+        );
+        println!();
+
+        let mut table =
+            Table::new(&["Package", "Version", "Used", "Renamed to", "Message"]).wide(self.wide);
+        for dep in deprecated {
+            table.row(vec![
+                Cell::new(&dep.package.name).color(Color::White),
+                Cell::new(&dep.package.version),
+                if dep.is_used {
+                    Cell::new("yes").color(Color::Red).bold()
+                } else {
+                    Cell::new("no").dimmed()
+                },
+                Cell::new(dep.successor.as_deref().unwrap_or("")).color(Color::Cyan),
+                Cell::new(&dep.message).dimmed(),
+            ]);
+        }
+        table.print(2);
+
+        println!();
+
+        if deprecated.iter().any(|d| d.successor.is_some()) {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Run `depx fix --rename` to point imports at the new names".cyan()
+            );
+            println!();
+        }
+    }
+
+    /// Report duplicate dependencies
+    pub fn report_duplicates(&self, analysis: &DuplicateAnalysis) {
+        println!();
+
+        if analysis.duplicates.is_empty() {
+            println!("{}", "No duplicate dependencies found!".green().bold());
+            if !analysis.types_skew.is_empty() || !analysis.peer_splits.is_empty() {
+                println!();
+                self.print_types_skew(analysis);
+                self.print_peer_splits(analysis);
+            }
+            return;
+        }
+
+        println!("{}", "Duplicate Dependencies Analysis".bold().underline());
+        println!();
+
+        // Summary
+        let stats = &analysis.stats;
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} crates with multiple versions",
+            stats.total_duplicates.to_string().yellow()
+        );
+        if stats.critical_severity > 0 {
+            println!(
+                "  {} {}",
+                stats.critical_severity.to_string().red().bold(),
+                "critical (several copies of a package that must be a singleton)"
+                    .red()
+                    .bold()
+            );
+        }
+        if stats.high_severity > 0 {
+            println!(
+                "  {} {}",
+                stats.high_severity.to_string().red().bold(),
+                "high severity (several major versions, or shared widely)".red()
+            );
+        }
+        if stats.medium_severity > 0 {
+            println!(
+                "  {} {}",
+                stats.medium_severity.to_string().yellow(),
+                "medium severity (different major versions)".yellow()
+            );
+        }
+        if stats.low_severity > 0 {
+            println!(
+                "  {} {}",
+                stats.low_severity.to_string().dimmed(),
+                "low severity (minor or patch spread)".dimmed()
+            );
+        }
+        println!(
+            "  {} extra compile units",
+            stats.extra_compile_units.to_string().cyan()
+        );
+        if !analysis.peer_splits.is_empty() {
+            println!(
+                "  {} {}",
+                analysis.peer_splits.len().to_string().yellow(),
+                "versions split by peer dependencies".yellow()
+            );
+        }
+        if !analysis.clusters.is_empty() {
+            let clustered: usize = analysis.clusters.iter().map(|c| c.packages.len()).sum();
+            println!(
+                "  {} {}",
+                analysis.clusters.len().to_string().cyan(),
+                format!("toolchain clusters ({} packages summarized)", clustered).dimmed()
+            );
+        }
+        println!();
+
+        let mut groups: Vec<_> = analysis.duplicates.iter().collect();
+        let transitive = |group: &crate::types::DuplicateGroup| -> usize {
+            group.versions.iter().map(|v| v.transitive_count).sum()
+        };
+        match self.sort.unwrap_or(TableSort::Severity) {
+            TableSort::Name => groups.sort_by(|a, b| a.name.cmp(&b.name)),
+            TableSort::Size => groups.sort_by(|a, b| {
+                transitive(b)
+                    .cmp(&transitive(a))
+                    .then_with(|| b.versions.len().cmp(&a.versions.len()))
+                    .then_with(|| a.name.cmp(&b.name))
+            }),
+            TableSort::Severity => groups.sort_by(|a, b| {
+                b.severity
+                    .cmp(&a.severity)
+                    .then_with(|| a.name.cmp(&b.name))
+            }),
+        }
+
+        // Minor and patch spreads are noise next to the rest
+        let hidden = groups
+            .iter()
+            .filter(|g| g.severity == DuplicateSeverity::Low)
+            .count();
+        let hidden = if self.all {
+            0
+        } else {
+            groups.retain(|g| g.severity != DuplicateSeverity::Low);
+            hidden
+        };
+
+        // Clustered groups sit under their cluster's row, which takes the
+        // place of the cluster's first member in the sort order
+        let cluster_of = |name: &str| {
+            analysis
+                .clusters
+                .iter()
+                .find(|c| c.packages.iter().any(|p| p == name))
+        };
+        let severity_cell = |severity: DuplicateSeverity| {
+            let cell = Cell::new(severity.to_string());
+            match severity {
+                DuplicateSeverity::Critical | DuplicateSeverity::High => {
+                    cell.color(Color::Red).bold()
+                }
+                DuplicateSeverity::Medium => cell.color(Color::Yellow),
+                DuplicateSeverity::Low => cell.dimmed(),
+            }
+        };
+        let mut table = Table::new(&["Package", "Rule", "Severity", "Transitive", "Versions"])
+            .align_right(3)
+            .wide(self.wide);
+        let mut shown_clusters: Vec<&str> = Vec::new();
+        for group in &groups {
+            let cluster = cluster_of(&group.name);
+            if let Some(cluster) = cluster {
+                if shown_clusters.contains(&cluster.name.as_str()) {
+                    continue;
+                }
+                shown_clusters.push(&cluster.name);
+                let mut rules: Vec<&str> = Vec::new();
+                for member in groups.iter().filter(|g| cluster.packages.contains(&g.name)) {
+                    if !rules.contains(&member.rule.id()) {
+                        rules.push(member.rule.id());
+                    }
+                }
+                table.row(vec![
+                    Cell::new(format!(
+                        "{} ({} packages)",
+                        cluster.name,
+                        cluster.packages.len()
+                    ))
+                    .color(Color::Cyan)
+                    .bold(),
+                    Cell::new(rules.join(", ")).dimmed(),
+                    severity_cell(cluster.severity),
+                    Cell::new(cluster.transitive_count.to_string()),
+                    Cell::new(format!("{} versions", cluster.version_count)).dimmed(),
+                ]);
+                if !self.expand {
+                    continue;
+                }
+            }
+
+            let members: Vec<&&crate::types::DuplicateGroup> = match cluster {
+                Some(cluster) => groups
+                    .iter()
+                    .filter(|g| cluster.packages.contains(&g.name))
+                    .collect(),
+                None => vec![group],
+            };
+            for member in members {
+                let versions: Vec<&str> =
+                    member.versions.iter().map(|v| v.version.as_str()).collect();
+                let name = if cluster.is_some() {
+                    Cell::new(format!("  {}", member.name)).color(Color::Cyan)
+                } else {
+                    Cell::new(&member.name).color(Color::Cyan).bold()
+                };
+                table.row(vec![
+                    name,
+                    Cell::new(member.rule.id()).dimmed(),
+                    severity_cell(member.severity),
+                    Cell::new(transitive(member).to_string()),
+                    Cell::new(versions.join(", ")).dimmed(),
+                ]);
+            }
+        }
+        if !groups.is_empty() {
+            table.print(2);
+            println!();
+        }
+
+        if hidden > 0 {
+            println!(
+                "  {} {}",
+                "+".dimmed(),
+                format!(
+                    "{} low-severity duplicates hidden; use --all to list them",
+                    hidden
+                )
+                .dimmed()
+            );
+        }
+        if !analysis.clusters.is_empty() && !self.expand {
+            println!(
+                "  {} {}",
+                "+".dimmed(),
+                "use --expand to list the packages in each cluster".dimmed()
+            );
+        }
+
+        // Per-version dependents (verbose only)
+        if self.verbose {
+            println!("{}", "DETAILS".bold());
+            for group in &groups {
+                self.print_duplicate_group(group);
+            }
+            println!();
+        } else {
+            println!(
+                "  {} {}",
+                "+".dimmed(),
+                "use --verbose to show which packages pull in each version".dimmed()
+            );
+            println!();
+        }
+
+        self.print_singletons(analysis);
+        self.print_types_skew(analysis);
+        self.print_peer_splits(analysis);
+
+        // Tip
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Use `cargo tree -d` for detailed dependency tree".cyan()
+        );
+        let mut rules: Vec<Rule> = analysis.duplicates.iter().map(|g| g.rule).collect();
+        rules.sort();
+        rules.dedup();
+        for rule in rules {
+            self.print_rule_docs(rule);
+        }
+        println!();
+    }
+
+    /// Print packages that must be singletons, with what their copies break
+    fn print_singletons(&self, analysis: &DuplicateAnalysis) {
+        let singletons: Vec<_> = analysis
+            .duplicates
+            .iter()
+            .filter_map(|g| Some((g, g.singleton.as_ref()?)))
+            .collect();
+        if singletons.is_empty() {
+            return;
+        }
+
+        self.print_rule_heading(
+            "SINGLETONS WITH SEVERAL COPIES".red().bold(),
+            Rule::DuplicateSingleton,
+        );
+        for (group, symptoms) in singletons {
+            let versions: Vec<&str> = group.versions.iter().map(|v| v.version.as_str()).collect();
+            println!(
+                "  {} {} {}",
+                "!".red().bold(),
+                group.name.white(),
+                versions.join(", ").dimmed()
+            );
+            println!("      {}", symptoms.dimmed());
+        }
+        println!();
+    }
+
+    /// Print @types packages written for another major version
+    fn print_types_skew(&self, analysis: &DuplicateAnalysis) {
+        if analysis.types_skew.is_empty() {
+            return;
+        }
+
+        self.print_rule_heading("TYPE DEFINITION SKEW".yellow().bold(), Rule::TypesSkew);
+        for skew in &analysis.types_skew {
+            println!(
+                "  {} {}@{} {} {}@{}",
+                "~".yellow(),
+                skew.types_package.white(),
+                skew.types_version,
+                "vs".dimmed(),
+                skew.package.white(),
+                skew.package_version
+            );
+            println!(
+                "      {} {}@{}",
+                "install".dimmed(),
+                skew.types_package.cyan(),
+                skew.suggested_range.cyan()
+            );
+        }
+        self.print_rule_docs(Rule::TypesSkew);
+        println!();
+    }
+
+    /// Print versions pnpm installed once per peer-dependency combination
+    fn print_peer_splits(&self, analysis: &DuplicateAnalysis) {
+        if analysis.peer_splits.is_empty() {
+            return;
+        }
+
+        self.print_rule_heading(
+            "PEER-DEPENDENCY SPLITS (same version, different peers)"
+                .yellow()
+                .bold(),
+            Rule::PeerSplit,
+        );
+        for split in &analysis.peer_splits {
+            println!(
+                "  {} {}@{} {}",
+                "~".yellow(),
+                split.name.white(),
+                split.version,
+                format!("({} copies)", split.variants.len()).dimmed()
+            );
+            for variant in &split.variants {
+                let peers = if variant.peers.is_empty() {
+                    "(no peers)".to_string()
+                } else {
+                    variant.peers.join(", ")
+                };
+                let dependents = if variant.dependents.is_empty() {
+                    String::new()
+                } else {
+                    format!(" <- {}", variant.dependents.join(", "))
+                };
+                println!("      {}{}", peers.cyan(), dependents.dimmed());
+            }
+            if !split.differing_peers.is_empty() {
+                println!(
+                    "      {} {}",
+                    "align:".dimmed(),
+                    split.differing_peers.join(", ").yellow()
+                );
+            }
+        }
+        self.print_rule_docs(Rule::PeerSplit);
+        println!();
+    }
+
+    /// Report overrides, resolutions, and patches
+    pub fn report_overrides(&self, analysis: &OverrideAnalysis) {
+        println!();
+
+        if analysis.entries.is_empty() {
+            println!(
+                "{}",
+                "No overrides, resolutions, or patches found!"
+                    .green()
+                    .bold()
+            );
+            return;
+        }
+
+        println!("{}", "Overrides Audit".bold().underline());
+        println!();
+
+        let stale = analysis
+            .entries
+            .iter()
+            .filter(|e| e.status != OverrideStatus::Active)
+            .count();
+        let vulnerable = analysis
+            .entries
+            .iter()
+            .filter(|e| !e.vulnerabilities.is_empty())
+            .count();
+
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} overrides and patches",
+            analysis.entries.len().to_string().cyan()
+        );
+        if stale > 0 {
+            println!(
+                "  {} {}",
+                stale.to_string().yellow(),
+                "stale or unused (safe to remove)".yellow()
+            );
+        }
+        if vulnerable > 0 {
+            println!(
+                "  {} {}",
+                vulnerable.to_string().red().bold(),
+                "forcing a vulnerable version".red()
+            );
+        }
+        println!();
+
+        for entry in &analysis.entries {
+            let marker = if !entry.vulnerabilities.is_empty() {
+                "!".red().bold()
+            } else if entry.status != OverrideStatus::Active {
+                "~".yellow()
+            } else {
+                "-".dimmed()
+            };
+
+            let status_marker = match entry.status {
+                OverrideStatus::Active => "".to_string(),
+                OverrideStatus::Stale => " [stale]".yellow().to_string(),
+                OverrideStatus::Unused => " [unused]".yellow().to_string(),
+            };
+
+            println!(
+                "  {} {} -> {} {}{}",
+                marker,
+                entry.selector.cyan(),
+                entry.target.white(),
+                format!("({})", entry.source).dimmed(),
+                status_marker
+            );
+
+            if !entry.installed_versions.is_empty() {
+                println!(
+                    "      {} {}",
+                    "installed:".dimmed(),
+                    entry.installed_versions.join(", ")
+                );
+            }
+
+            if !entry.overridden.is_empty() {
+                println!(
+                    "      {} {}",
+                    "overrides:".dimmed(),
+                    entry.overridden.join(", ")
+                );
+            }
+
+            if !entry.vulnerabilities.is_empty() {
+                println!(
+                    "      {} {}",
+                    "vulnerable:".red(),
+                    entry.vulnerabilities.join(", ").red()
+                );
+            }
+        }
+        println!();
+
+        if stale > 0 {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Stale entries can be removed; dependents already resolve the forced version"
+                    .cyan()
+            );
+            println!();
+        }
+    }
+
+    /// Report source files no entry point reaches
+    pub fn report_deadcode(&self, report: &DeadCodeReport) {
+        println!();
+
+        if report.entry_points.is_empty() {
+            println!("{}", "No entry points found".yellow().bold());
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Declare them with `entry-points = [...]` in depx.toml".cyan()
+            );
+            println!();
+            return;
+        }
+
+        if report.unreachable.is_empty()
+            && report.test_only.is_empty()
+            && report.unresolved.is_empty()
+        {
+            println!(
+                "{}",
+                "Every source file is reachable from an entry point!"
+                    .green()
+                    .bold()
+            );
+            return;
+        }
+
+        println!("{}", "Dead Module Analysis".bold().underline());
+        println!();
+
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} files analyzed from {} entry points",
+            report.total_files.to_string().cyan(),
+            report.entry_points.len()
+        );
+        if !report.unreachable.is_empty() {
+            println!(
+                "  {} {}",
+                report.unreachable.len().to_string().red(),
+                "never imported".red()
+            );
+        }
+        if !report.test_only.is_empty() {
+            println!(
+                "  {} {}",
+                report.test_only.len().to_string().yellow(),
+                "only imported by tests".yellow()
+            );
+        }
+        if !report.unresolved.is_empty() {
+            println!(
+                "  {} {}",
+                report.unresolved.len().to_string().yellow(),
+                "relative imports resolve to no file".yellow()
+            );
+        }
+        println!();
+
+        if !report.unreachable.is_empty() {
+            println!("{}", "Unreachable Files:".red().bold());
+            for file in &report.unreachable {
+                println!("  {} {}", "-".red(), file.display());
+            }
+            println!();
+        }
+
+        if !report.test_only.is_empty() {
+            println!("{}", "Only Reachable from Tests:".yellow().bold());
+            for file in &report.test_only {
+                println!("  {} {}", "~".yellow(), file.display());
+            }
+            println!();
+        }
+
+        if !report.unresolved.is_empty() {
+            println!("{}", "Unresolved Local Imports:".yellow().bold());
+            for import in &report.unresolved {
+                println!(
+                    "  {} {}:{} {}",
+                    "?".yellow(),
+                    import.file.display(),
+                    import.line,
+                    import.specifier.dimmed()
+                );
+            }
+            println!();
+        }
+
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Files loaded by convention (routes, plugins) can be added to entry-points in depx.toml"
+                .cyan()
+        );
+        println!();
+    }
+
+    /// Report entry point exports no test, example or workspace package imports
+    pub fn report_unused_exports(&self, report: &UnusedExportsReport) {
+        println!();
+
+        if report.entry_points.is_empty() {
+            println!("{}", "No library entry points found".yellow().bold());
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Point `main` or `exports` in package.json at the package's entry files".cyan()
+            );
+            println!();
+            return;
+        }
+
+        if report
+            .entry_points
+            .iter()
+            .all(|e| e.imported && e.unused.is_empty())
+        {
+            println!(
+                "{} {}",
+                "Every export is imported somewhere".green().bold(),
+                format!("({} consumer files)", report.consumers).dimmed()
+            );
+            println!();
+            return;
+        }
+
+        for entry in &report.entry_points {
+            println!(
+                "{} {} {}",
+                entry.specifier.bold(),
+                format!("({})", entry.entry_point).dimmed(),
+                entry.file.display().to_string().dimmed()
+            );
+            if !entry.imported {
+                println!(
+                    "  {} {}",
+                    "-".red(),
+                    format!(
+                        "never imported; none of its {} exports are used",
+                        entry.exports.len()
+                    )
+                    .red()
+                );
+            } else if entry.unused.is_empty() {
+                println!("  {} every export is used", "+".green());
+            } else {
+                for name in &entry.unused {
+                    println!("  {} {}", "-".yellow(), name);
+                }
+            }
+            println!();
+        }
+
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Unused exports may still be public API: cover them with tests, or remove them in a major release"
+                .cyan()
+        );
+        println!();
+    }
+
+    /// Report module fan-in/fan-out and import cycles
+    pub fn report_coupling(&self, report: &CouplingReport, top: usize) {
+        println!();
+
+        if report.modules.is_empty() {
+            println!("{}", "No source files found".yellow().bold());
+            return;
+        }
+
+        let god_modules: Vec<_> = report.modules.iter().filter(|m| m.is_god_module).collect();
+
+        println!("{}", "Module Coupling Report".bold().underline());
+        println!();
+
+        println!("{}", "Summary".bold());
+        println!("  {} modules", report.modules.len().to_string().cyan());
+        if !god_modules.is_empty() {
+            println!(
+                "  {} {}",
+                god_modules.len().to_string().red(),
+                "god modules".red()
+            );
+        }
+        if !report.clusters.is_empty() {
+            println!(
+                "  {} {}",
+                report.clusters.len().to_string().yellow(),
+                "import cycles".yellow()
+            );
+        }
+        println!();
+
+        println!("{}", "Most Coupled Modules:".bold());
+        for module in report.modules.iter().take(top) {
+            if module.fan_in + module.fan_out == 0 {
+                break;
+            }
+            let marker = if module.is_god_module {
+                "!".red().bold()
+            } else {
+                "-".dimmed()
+            };
+            println!(
+                "  {} {} {}",
+                marker,
+                module.file.display(),
+                format!(
+                    "(in {}, out {}, instability {:.2})",
+                    module.fan_in, module.fan_out, module.instability
+                )
+                .dimmed()
+            );
+        }
+        println!();
+
+        if !report.clusters.is_empty() {
+            println!("{}", "Import Cycles:".yellow().bold());
+            for cluster in &report.clusters {
+                println!(
+                    "  {} {} files",
+                    "~".yellow(),
+                    cluster.len().to_string().yellow()
+                );
+                for file in cluster {
+                    println!("      {}", file.display().to_string().dimmed());
+                }
+            }
+            println!();
+        }
+
+        if !god_modules.is_empty() {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Split god modules so changes to them don't ripple through the project".cyan()
+            );
+            println!();
+        }
+    }
+
+    /// Report lockfile entries failing integrity and source checks
+    pub fn report_check(
+        &self,
+        report: &IntegrityReport,
+        verify_tarballs: bool,
+        max_critical_age: Option<i64>,
+    ) {
+        println!();
+
+        println!("{}", "Lockfile Check".bold().underline());
+        println!();
+
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} lockfile entries checked",
+            report.entries_checked.to_string().cyan()
+        );
+        if verify_tarballs {
+            println!(
+                "  {} tarballs verified against the npm cache {}",
+                report.tarballs_verified.to_string().cyan(),
+                format!("({} not cached)", report.tarballs_not_cached).dimmed()
+            );
+        }
+        let severe = report.issues.iter().filter(|i| i.kind.is_severe()).count();
+        if severe > 0 {
+            println!(
+                "  {} {}",
+                severe.to_string().red().bold(),
+                "missing or mismatched hashes, insecure or disallowed sources".red()
+            );
+        }
+        if report.issues.len() > severe {
+            println!(
+                "  {} {}",
+                (report.issues.len() - severe).to_string().yellow(),
+                "weak hashes, unexpected sources or stale installs".yellow()
+            );
+        }
+        let past_sla = report
+            .critical_vulnerabilities
+            .iter()
+            .filter(|v| v.past_sla)
+            .count();
+        if past_sla > 0 {
+            println!(
+                "  {} {}",
+                past_sla.to_string().red().bold(),
+                "critical vulnerabilities open past the SLA".red()
+            );
+        }
+        let failing = report
+            .lint
+            .iter()
+            .filter(|f| f.severity >= Severity::Medium)
+            .count();
+        if failing > 0 {
+            println!(
+                "  {} {}",
+                failing.to_string().red().bold(),
+                "framework lint findings".red()
+            );
+        }
+        if report.lint.len() > failing {
+            println!(
+                "  {} {}",
+                (report.lint.len() - failing).to_string().yellow(),
+                "framework lint suggestions".yellow()
+            );
+        }
+        println!();
+
+        if let Some(max_age) = max_critical_age {
+            self.print_critical_vulnerabilities(report, max_age);
+        }
+        self.print_lint(&report.lint);
+
+        if report.issues.is_empty() {
+            println!(
+                "{}",
+                "Every lockfile entry is hash-pinned and fetched from a known registry!"
+                    .green()
+                    .bold()
+            );
+            println!();
+            return;
+        }
+
+        let mut kinds: Vec<IntegrityIssueKind> = report.issues.iter().map(|i| i.kind).collect();
+        kinds.dedup();
+        for kind in kinds {
+            let title = match kind {
+                IntegrityIssueKind::HashMismatch => "Cached Tarball Doesn't Match Its Hash:",
+                IntegrityIssueKind::InsecureUrl => "Fetched Over Plain HTTP:",
+                IntegrityIssueKind::MissingIntegrity => "Missing Integrity Hash:",
+                IntegrityIssueKind::WeakIntegrity => "SHA-1 Only Integrity:",
+                IntegrityIssueKind::UnexpectedRegistry => "Resolved Outside Known Registries:",
+                IntegrityIssueKind::DisallowedRegistry => "Resolved Outside Allowed Registries:",
+                IntegrityIssueKind::StaleInstall => "node_modules Differs From the Lockfile:",
+            };
+            if kind.is_severe() {
+                println!("{}", title.red().bold());
+            } else {
+                println!("{}", title.yellow().bold());
+            }
+
+            for issue in report.issues.iter().filter(|i| i.kind == kind) {
+                let marker = if kind.is_severe() {
+                    "!".red().bold()
+                } else {
+                    "~".yellow()
+                };
+                println!(
+                    "  {} {}{}{}",
+                    marker,
+                    format!("{}@{}", issue.package, issue.version).white(),
+                    owners_suffix(&issue.owners),
+                    open_for(issue.first_seen.as_ref())
+                );
+                if !issue.detail.is_empty() {
+                    println!("      {}", issue.detail.dimmed());
+                }
+            }
+            println!();
+        }
+
+        // A stale node_modules only needs reinstalling from the lockfile
+        let tip = if report
+            .issues
+            .iter()
+            .all(|i| i.kind == IntegrityIssueKind::StaleInstall)
+        {
+            "run `npm ci` to reinstall node_modules from the lockfile"
+        } else {
+            "delete the affected entries and run `npm install` to re-resolve them"
+        };
+        println!("  {} {}", "Tip:".dimmed(), tip.cyan());
+        println!();
+    }
+
+    fn print_lint(&self, findings: &[LintFinding]) {
+        if findings.is_empty() {
+            return;
+        }
+
+        println!("{}", "Lint:".bold());
+        for finding in findings {
+            let marker = match finding.severity {
+                Severity::Critical | Severity::High => "!".red().bold(),
+                Severity::Medium => "~".yellow(),
+                Severity::Low => "-".dimmed(),
+            };
+            println!(
+                "  {} {} {}",
+                marker,
+                finding.packages.join(", ").white(),
+                format!("[{}]", finding.rule).dimmed()
+            );
+            println!("      {}", finding.message.dimmed());
+            for site in &finding.sites {
+                println!(
+                    "      {}",
+                    format!("{}:{}", site.file_path.display(), site.line).dimmed()
+                );
+            }
+        }
+        println!();
+    }
+
+    fn print_critical_vulnerabilities(&self, report: &IntegrityReport, max_age: i64) {
+        if report.critical_vulnerabilities.is_empty() {
+            println!(
+                "{}",
+                "No critical vulnerabilities in the lockfile".green().bold()
+            );
+            println!();
+            return;
+        }
+
+        println!(
+            "{}",
+            format!("Critical Vulnerabilities (SLA: {} days):", max_age)
+                .red()
+                .bold()
+        );
+        for tracked in &report.critical_vulnerabilities {
+            let vuln = &tracked.vulnerability;
+            let marker = if tracked.past_sla {
+                "!".red().bold()
+            } else {
+                "~".yellow()
+            };
+            println!(
+                "  {} {} {}{}",
+                marker,
+                format!("{}@{}", vuln.package_name, vuln.installed_version).white(),
+                vuln.id.dimmed(),
+                open_for(tracked.first_seen.as_ref())
+            );
+            if let Some(patched) = &vuln.patched_version {
+                println!("      {}", format!("fixed in {}", patched).dimmed());
+            }
+        }
+        println!();
+    }
+
+    /// Report publishable packages whose manifests would break consumers
+    pub fn report_prepublish(&self, root: &Path, checks: &[PublishCheck]) {
+        println!();
+
+        if checks.is_empty() {
+            println!(
+                "{}",
+                "No publishable packages found (all private)".green().bold()
+            );
+            return;
+        }
+
+        let failing = checks.iter().filter(|c| !c.issues.is_empty()).count();
+
+        println!("{}", "Prepublish Check".bold().underline());
+        println!();
+
+        println!("{}", "Summary".bold());
+        println!("  {} publishable packages", checks.len().to_string().cyan());
+        if failing > 0 {
+            println!(
+                "  {} {}",
+                failing.to_string().red().bold(),
+                "would break for consumers".red()
+            );
+        }
+        println!();
+
+        for check in checks {
+            let dir = check.dir.strip_prefix(root).unwrap_or(&check.dir);
+            let dir = if dir.as_os_str().is_empty() {
+                ".".to_string()
+            } else {
+                dir.display().to_string()
+            };
+            let marker = if check.issues.is_empty() {
+                "-".dimmed()
+            } else {
+                "!".red().bold()
+            };
+            println!(
+                "  {} {} {}{}",
+                marker,
+                check.name.cyan(),
+                format!("({})", dir).dimmed(),
+                owners_suffix(&check.owners)
+            );
+
+            for issue in &check.issues {
+                let reason = match issue.kind {
+                    PublishIssueKind::WorkspaceProtocol => {
+                        "workspace range, only rewritten by pnpm/yarn publish"
+                    }
+                    PublishIssueKind::LocalPath => {
+                        "local path, doesn't exist on consumers' machines"
+                    }
+                    PublishIssueKind::MissingEntry => "file doesn't exist (not built yet?)",
+                    PublishIssueKind::NotPublished => "left out of the tarball by `files`",
+                };
+                // Entry names already say which field they come from
+                let target = match issue.kind {
+                    PublishIssueKind::WorkspaceProtocol | PublishIssueKind::LocalPath => format!(
+                        "{} {}",
+                        issue.field.dimmed(),
+                        format!("{}@{}", issue.subject, issue.value).white()
+                    ),
+                    PublishIssueKind::MissingEntry | PublishIssueKind::NotPublished => {
+                        format!("{} -> {}", issue.subject, issue.value)
+                            .white()
+                            .to_string()
+                    }
+                };
+                println!("      {} {} {}", target, "-".dimmed(), reason.yellow());
+            }
+        }
+        println!();
+
+        let has_kind = |kinds: &[PublishIssueKind]| {
+            checks
+                .iter()
+                .flat_map(|c| &c.issues)
+                .any(|i| kinds.contains(&i.kind))
+        };
+        if has_kind(&[
+            PublishIssueKind::WorkspaceProtocol,
+            PublishIssueKind::LocalPath,
+        ]) {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "replace local ranges with published versions, or publish with `pnpm publish`"
+                    .cyan()
+            );
+        }
+        if has_kind(&[
+            PublishIssueKind::MissingEntry,
+            PublishIssueKind::NotPublished,
+        ]) {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "build before checking, and add missing paths to the `files` field".cyan()
+            );
+        }
+        if failing > 0 {
+            println!();
+        }
+    }
+
+    /// Report problems with the project's own bin entries
+    pub fn report_bins(&self, entries: &[BinEntry]) {
+        println!();
+
+        if entries.is_empty() {
+            println!("{}", "No bin entries in package.json".green().bold());
+            return;
+        }
+
+        let missing = entries
+            .iter()
+            .filter(|e| e.status == BinStatus::Missing)
+            .count();
+        let not_analyzed = entries
+            .iter()
+            .filter(|e| e.status == BinStatus::NotAnalyzed)
+            .count();
+        let undeclared = entries
+            .iter()
+            .filter(|e| !e.undeclared_imports.is_empty())
+            .count();
+
+        println!("{}", "Project Binaries".bold().underline());
+        println!();
+
+        println!("{}", "Summary".bold());
+        println!("  {} bin entries", entries.len().to_string().cyan());
+        if missing > 0 {
+            println!(
+                "  {} {}",
+                missing.to_string().red().bold(),
+                "pointing at missing files".red()
+            );
+        }
+        if undeclared > 0 {
+            println!(
+                "  {} {}",
+                undeclared.to_string().red(),
+                "importing undeclared dependencies".red()
+            );
+        }
+        if not_analyzed > 0 {
+            println!(
+                "  {} {}",
+                not_analyzed.to_string().yellow(),
+                "not covered by import analysis".yellow()
+            );
+        }
+        println!();
+
+        for entry in entries {
+            let marker =
+                if entry.status == BinStatus::Missing || !entry.undeclared_imports.is_empty() {
+                    "!".red().bold()
+                } else if entry.status == BinStatus::NotAnalyzed {
+                    "~".yellow()
+                } else {
+                    "-".dimmed()
+                };
+            let note = match entry.status {
+                BinStatus::Missing => " [missing]".red().to_string(),
+                BinStatus::NotAnalyzed => " [not analyzed]".yellow().to_string(),
+                BinStatus::Ok => String::new(),
+            };
+
+            println!(
+                "  {} {} {}{}",
+                marker,
+                entry.name.cyan(),
+                format!("-> {}", entry.path).dimmed(),
+                note
+            );
+
+            for import in &entry.undeclared_imports {
+                let reason = if import.dev_only {
+                    "only in devDependencies"
+                } else {
+                    "not declared"
+                };
+                println!(
+                    "      {} {} {}",
+                    import.package.white(),
+                    format!("({})", reason).red(),
+                    format!("{}:{}", import.file.display(), import.line).dimmed()
+                );
+            }
+        }
+        println!();
+
+        if undeclared > 0 {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Move these to dependencies; devDependencies aren't installed for users of the package"
+                    .cyan()
+            );
+            println!();
+        }
+        if not_analyzed > 0 {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Imports of unanalyzed bins don't count towards `depx analyze`".cyan()
+            );
+            println!();
+        }
+    }
+
+    /// Report peer dependencies that are missing or out of range
+    pub fn report_peers(&self, issues: &[PeerIssue]) {
+        println!();
+
+        if issues.is_empty() {
+            println!(
+                "{}",
+                "Every peer dependency is installed at a compatible version"
+                    .green()
+                    .bold()
+            );
+            println!();
+            return;
+        }
+
+        for (kind, title) in [
+            (PeerIssueKind::Missing, "Missing Peer Dependencies:"),
+            (PeerIssueKind::Mismatch, "Incompatible Peer Versions:"),
+        ] {
+            let matching: Vec<&PeerIssue> = issues.iter().filter(|i| i.kind == kind).collect();
+            if matching.is_empty() {
+                continue;
+            }
+
+            println!("{}", title.red().bold());
+            for issue in matching {
+                let dependent = if issue.version.is_empty() {
+                    issue.package.clone()
+                } else {
+                    format!("{}@{}", issue.package, issue.version)
+                };
+                let installed = match &issue.installed {
+                    Some(version) => format!(", {} is installed", version),
+                    None => String::new(),
                 };
+                let optional = if issue.optional { " (optional)" } else { "" };
+                println!(
+                    "  {} {}{} {}",
+                    "!".red().bold(),
+                    format!("{}@{}", issue.peer, issue.range).white(),
+                    optional.dimmed(),
+                    format!("required by {}{}", dependent, installed).dimmed()
+                );
+            }
+            println!();
+        }
+
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Install a version every dependent accepts, or upgrade the dependents".cyan()
+        );
+        println!();
+    }
+
+    /// Report installed packages whose `engines` reject the project's
+    /// runtime or package manager
+    pub fn report_engines(&self, report: &EnginesReport) {
+        println!();
+
+        if report.declared.is_empty() {
+            println!(
+                "{}",
+                "No Node.js or package manager version declared"
+                    .yellow()
+                    .bold()
+            );
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Set `engines.node` or `packageManager` in package.json, or add a .nvmrc".cyan()
+            );
+            println!();
+            return;
+        }
+
+        println!("{}", "Declared".bold());
+        for declared in &report.declared {
+            println!(
+                "  {} {} {}",
+                declared.engine.white(),
+                declared.constraint.cyan(),
+                format!("({})", declared.source).dimmed()
+            );
+        }
+        println!();
+
+        if let Some(mismatch) = &report.manager_mismatch {
+            println!(
+                "  {} {}",
+                "!".red().bold(),
+                format!(
+                    "packageManager is {}, but the lockfile is {}",
+                    mismatch.package_manager, mismatch.lockfile
+                )
+                .red()
+            );
+            println!();
+        }
+
+        if report.conflicts.is_empty() {
+            println!(
+                "{} {}",
+                "Every installed package accepts the declared versions"
+                    .green()
+                    .bold(),
+                format!("({} checked)", report.checked).dimmed()
+            );
+            println!();
+            return;
+        }
+
+        println!("{}", "Engine Conflicts:".red().bold());
+        for conflict in &report.conflicts {
+            let package = if conflict.version.is_empty() {
+                conflict.package.clone()
+            } else {
+                format!("{}@{}", conflict.package, conflict.version)
+            };
+            println!(
+                "  {} {} {} {}",
+                "!".red().bold(),
+                package.white(),
+                format!("needs {} {}", conflict.engine, conflict.required).red(),
+                format!(
+                    "but {} {} allows {}",
+                    conflict.declared.source, conflict.declared.constraint, conflict.oldest
+                )
+                .dimmed()
+            );
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Raise the declared minimum, or pin the packages to releases that still support it"
+                .cyan()
+        );
+        println!();
+    }
+
+    /// Report dependency ranges that don't follow the project's pinning style
+    pub fn report_pinning(&self, report: &PinningReport) {
+        println!();
+
+        if report.manifests.is_empty() {
+            println!("{}", "No package.json manifests found".yellow().bold());
+            println!();
+            return;
+        }
+
+        let source = if report.configured {
+            "policy from depx.toml"
+        } else {
+            "most common"
+        };
+        println!("{}", "Range Styles".bold());
+        for (style, count) in &report.styles {
+            let note = if *style == report.policy {
+                format!(" ({})", source)
+            } else {
+                String::new()
+            };
+            println!(
+                "  {} {}{}",
+                format!("{:<10}", style.to_string()).white(),
+                count.to_string().cyan(),
+                note.dimmed()
+            );
+        }
+        println!();
+
+        if report.deviations.is_empty() {
+            println!(
+                "{} {}",
+                format!("Every dependency range is {}", report.policy)
+                    .green()
+                    .bold(),
+                format!("({} manifests checked)", report.manifests.len()).dimmed()
+            );
+            println!();
+            return;
+        }
 
+        println!("{}", "Inconsistent Ranges:".yellow().bold());
+        for manifest in &report.manifests {
+            let deviations: Vec<_> = report
+                .deviations
+                .iter()
+                .filter(|d| &d.manifest == manifest)
+                .collect();
+            if deviations.is_empty() {
+                continue;
+            }
+            println!("  {}", manifest.display().to_string().bold());
+            for deviation in deviations {
+                let marker = if deviation.style == RangeStyle::Any {
+                    "!".red().bold()
+                } else {
+                    "~".yellow()
+                };
+                let suggested = match &deviation.suggested {
+                    Some(range) => format!("-> {}", range).cyan(),
+                    None => "(nothing installed to pin)".dimmed(),
+                };
                 println!(
-                    "  {} {}@{} - {}{}",
-                    vuln.id.white(),
-                    vuln.package_name.cyan(),
-                    vuln.installed_version.yellow(),
-                    vuln.title.dimmed(),
-                    used_marker
+                    "    {} {} {} {} {}",
+                    marker,
+                    deviation.package.white(),
+                    deviation.range.yellow(),
+                    suggested,
+                    format!("({})", deviation.section).dimmed()
+                );
+            }
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Run `depx fix --pinning` to rewrite them, or set `[pinning] policy` in depx.toml"
+                .cyan()
+        );
+        println!();
+    }
+
+    /// Report how many installed packages use each license, and which
+    /// have none depx could tell
+    pub fn report_licenses(&self, report: &LicenseReport) {
+        println!();
+
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for package in &report.packages {
+            if let Some(license) = &package.license {
+                *counts.entry(license.as_str()).or_default() += 1;
+            }
+        }
+        let mut counts: Vec<(&str, usize)> = counts.into_iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+        println!("{}", "Licenses".bold());
+        for (license, count) in &counts {
+            println!(
+                "  {} {}",
+                format!("{:<24}", license).white(),
+                count.to_string().cyan()
+            );
+        }
+        let from_files = report
+            .packages
+            .iter()
+            .filter(|p| p.source == LicenseSource::LicenseFile)
+            .count();
+        if from_files > 0 {
+            println!(
+                "  {}",
+                format!(
+                    "{} read from LICENSE files, as package.json had none",
+                    from_files
+                )
+                .dimmed()
+            );
+        }
+        println!();
+
+        let unknown: Vec<_> = report
+            .packages
+            .iter()
+            .filter(|p| p.license.is_none())
+            .collect();
+        if unknown.is_empty() {
+            println!(
+                "{}",
+                "Every installed package has a known license".green().bold()
+            );
+            println!();
+            return;
+        }
+
+        println!("{}", "Unknown License:".yellow().bold());
+        for package in &unknown {
+            let reason = match package.source {
+                LicenseSource::NotInstalled => "not installed",
+                _ => "no license field or recognized license file",
+            };
+            println!(
+                "  {} {} {}",
+                "?".yellow(),
+                format!("{}@{}", package.name, package.version).white(),
+                format!("({})", reason).dimmed()
+            );
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Install dependencies first so license files can be read".cyan()
+        );
+        println!();
+    }
+
+    /// Report dependencies whose range differs between workspace members
+    pub fn report_alignment(&self, report: &AlignmentReport) {
+        println!();
+
+        if report.manifests.is_empty() {
+            println!("{}", "No package.json manifests found".yellow().bold());
+            println!();
+            return;
+        }
+
+        if report.mismatches.is_empty() {
+            println!(
+                "{} {}",
+                "Workspace members agree on every dependency range"
+                    .green()
+                    .bold(),
+                format!("({} manifests checked)", report.manifests.len()).dimmed()
+            );
+            println!();
+            self.print_catalog_candidates(report);
+            return;
+        }
+
+        println!("{}", "Mismatched Ranges:".yellow().bold());
+        for mismatch in &report.mismatches {
+            let source = match mismatch.source {
+                AlignSource::Declared => format!("{} declared", report.prefer),
+                AlignSource::Pin => "pinned in depx.toml".to_string(),
+                AlignSource::Constraint => "enforced by yarn constraints".to_string(),
+                AlignSource::Catalog => "pnpm catalog".to_string(),
+            };
+            println!(
+                "  {} {} {}",
+                mismatch.package.white().bold(),
+                format!("-> {}", mismatch.target).cyan(),
+                format!("({})", source).dimmed()
+            );
+            for declaration in &mismatch.declarations {
+                let marker = if declaration.range == mismatch.target {
+                    " ".normal()
+                } else {
+                    "~".yellow()
+                };
+                let resolved = match &declaration.resolved {
+                    Some(range) => format!(" = {}", range),
+                    None => String::new(),
+                };
+                println!(
+                    "    {} {} {} {}",
+                    marker,
+                    format!("{:<12}", declaration.range).yellow(),
+                    declaration.manifest.display(),
+                    format!("({}){}", declaration.section, resolved).dimmed()
+                );
+            }
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Run `depx fix --align` to rewrite them, or set `[align]` in depx.toml".cyan()
+        );
+        println!();
+        self.print_catalog_candidates(report);
+    }
+
+    fn print_catalog_candidates(&self, report: &AlignmentReport) {
+        if report.catalog_candidates.is_empty() {
+            return;
+        }
+
+        println!("{}", "Catalog Candidates:".bold());
+        for candidate in &report.catalog_candidates {
+            println!(
+                "  {} {} {}",
+                "-".dimmed(),
+                candidate.package.white(),
+                format!("{} ({} manifests)", candidate.range, candidate.manifests).dimmed()
+            );
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Add them to `catalog` in pnpm-workspace.yaml and declare them as `catalog:`".cyan()
+        );
+        println!();
+    }
+
+    /// Report the bundle bytes each direct dependency is responsible for
+    pub fn report_bundle(&self, report: &BundleReport, top: usize) {
+        println!();
+
+        if report.total_bytes == 0 {
+            println!(
+                "{}",
+                format!("The {} lists no bundled bytes", report.format)
+                    .yellow()
+                    .bold()
+            );
+            println!();
+            return;
+        }
+
+        println!(
+            "{} {}",
+            "Bundle Impact".bold().underline(),
+            format!("({})", report.format).dimmed()
+        );
+        println!();
+
+        let dependency_bytes: u64 = report.dependencies.iter().map(|d| d.total_bytes()).sum();
+        println!("{}", "Summary".bold());
+        println!("  {} bundled", format_bytes(report.total_bytes).cyan());
+        println!(
+            "  {} first-party",
+            format_bytes(report.first_party_bytes).cyan()
+        );
+        println!(
+            "  {} from {} direct dependencies",
+            format_bytes(dependency_bytes).cyan(),
+            report.dependencies.len()
+        );
+        if !report.shared.is_empty() {
+            let shared: u64 = report.shared.iter().map(|p| p.bytes).sum();
+            println!(
+                "  {} shared by several dependencies",
+                format_bytes(shared).cyan()
+            );
+        }
+        if report.unknown_bytes > 0 {
+            println!(
+                "  {} in chunks that name no source",
+                format_bytes(report.unknown_bytes).yellow()
+            );
+        }
+        println!();
+
+        if !report.dependencies.is_empty() {
+            println!("{}", "Largest Dependencies:".bold());
+            for impact in report.dependencies.iter().take(top) {
+                let package = if impact.version.is_empty() {
+                    impact.name.clone()
+                } else {
+                    format!("{}@{}", impact.name, impact.version)
+                };
+                println!(
+                    "  {} {} {}",
+                    "-".dimmed(),
+                    package.white(),
+                    format_bytes(impact.total_bytes()).cyan()
+                );
+                if !impact.transitive.is_empty() {
+                    let pulled: Vec<String> = impact
+                        .transitive
+                        .iter()
+                        .map(|p| format!("{} {}", p.name, format_bytes(p.bytes)))
+                        .collect();
+                    println!(
+                        "      {}",
+                        format!(
+                            "own {}, pulls in {}",
+                            format_bytes(impact.own_bytes),
+                            pulled.join(", ")
+                        )
+                        .dimmed()
+                    );
+                }
+            }
+            if report.dependencies.len() > top {
+                println!(
+                    "  {}",
+                    format!("... and {} more", report.dependencies.len() - top).dimmed()
+                );
+            }
+            println!();
+        }
+
+        if !report.shared.is_empty() {
+            println!("{}", "Shared Packages:".bold());
+            for package in &report.shared {
+                println!(
+                    "  {} {} {} {}",
+                    "-".dimmed(),
+                    package.name.white(),
+                    format_bytes(package.bytes).cyan(),
+                    format!("(via {})", package.dependants.join(", ")).dimmed()
+                );
+            }
+            println!();
+        }
+
+        if !report.unattributed.is_empty() {
+            println!(
+                "{}",
+                "Not Reached From Any Direct Dependency:".yellow().bold()
+            );
+            for package in &report.unattributed {
+                println!(
+                    "  {} {} {}",
+                    "?".yellow(),
+                    package.name.white(),
+                    format_bytes(package.bytes).cyan()
                 );
-
-                if let Some(ref patched) = vuln.patched_version {
-                    println!(
-                        "       {} {} -> {}",
-                        "Fix:".dimmed(),
-                        vuln.installed_version.red(),
-                        patched.green()
-                    );
-                }
             }
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "These are often polyfills the bundler injected, or a stale lockfile".cyan()
+            );
+            println!();
+        }
+
+        if report.format == BundleFormat::Vite {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Vite manifests list chunks: packages bundled into your own chunks count as first-party"
+                    .cyan()
+            );
             println!();
         }
     }
 
-    /// Report deprecated packages
-    pub fn report_deprecated(&self, deprecated: &[DeprecatedPackage]) {
+    /// Report production dependencies only imported from untested code
+    pub fn report_coverage(&self, report: &CoverageReport) {
         println!();
 
-        if deprecated.is_empty() {
-            println!("{}", "No deprecated packages found!".green().bold());
+        if report.files == 0 {
+            println!(
+                "{}",
+                format!("The {} report covers no files", report.format)
+                    .yellow()
+                    .bold()
+            );
+            println!();
             return;
         }
 
         println!(
-            "{} {} found",
-            deprecated.len().to_string().yellow().bold(),
-            if deprecated.len() == 1 {
-                "deprecated package"
-            } else {
-                "deprecated packages"
-            }
+            "{} {}",
+            "Dependency Coverage".bold().underline(),
+            format!("({}, {} files)", report.format, report.files).dimmed()
         );
         println!();
 
-        for dep in deprecated {
-            let used_marker = if dep.is_used {
-                " [USED]".red().bold().to_string()
-            } else {
-                " [unused]".dimmed().to_string()
-            };
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} dependencies imported from tested code",
+            report.covered.len().to_string().green()
+        );
+        if !report.uncovered.is_empty() {
+            println!(
+                "  {} {}",
+                report.uncovered.len().to_string().yellow(),
+                "only imported from code the tests never run".yellow()
+            );
+        }
+        if !report.untracked.is_empty() {
+            println!(
+                "  {} {}",
+                report.untracked.len().to_string().dimmed(),
+                "imported only from files the report leaves out".dimmed()
+            );
+        }
+        println!();
 
+        if report.uncovered.is_empty() {
             println!(
-                "  {} {}@{}{}",
-                "-".yellow(),
-                dep.package.name.white(),
-                dep.package.version,
-                used_marker
+                "{}",
+                "Every dependency the report sees is exercised by tests"
+                    .green()
+                    .bold()
             );
-            println!("    {}", dep.message.dimmed());
+            println!();
+            return;
         }
 
+        println!("{}", "Only Reached From Untested Code:".yellow().bold());
+        for dependency in &report.uncovered {
+            println!(
+                "  {} {}",
+                "~".yellow(),
+                format!("{}@{}", dependency.name, dependency.version).white()
+            );
+            for import in &dependency.imports {
+                println!(
+                    "      {}",
+                    format!("{}:{}", import.file_path.display(), import.line).dimmed()
+                );
+            }
+        }
+        println!();
+        println!(
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Test these paths, or check whether the code and the dependency are still needed"
+                .cyan()
+        );
         println!();
     }
 
-    /// Report duplicate dependencies
-    pub fn report_duplicates(&self, analysis: &DuplicateAnalysis) {
+    /// Report copies of published packages checked into the source tree
+    pub fn report_vendored(&self, packages: &[VendoredPackage]) {
         println!();
 
-        if analysis.duplicates.is_empty() {
-            println!("{}", "No duplicate dependencies found!".green().bold());
+        if packages.is_empty() {
+            println!("{}", "No vendored packages found".green().bold());
+            println!();
             return;
         }
 
-        println!("{}", "Duplicate Dependencies Analysis".bold().underline());
+        println!(
+            "{} {}",
+            "Vendored Packages:".yellow().bold(),
+            format!("({})", packages.len()).dimmed()
+        );
+        for package in packages {
+            println!(
+                "  {} {} {}",
+                "~".yellow(),
+                format!("{}@{}", package.name, package.version).white(),
+                format!("{}/{}", package.path.display(), package.manifest).dimmed()
+            );
+        }
         println!();
 
-        // Summary
-        let stats = &analysis.stats;
-        println!("{}", "Summary".bold());
         println!(
-            "  {} crates with multiple versions",
-            stats.total_duplicates.to_string().yellow()
+            "  {} {}",
+            "Tip:".dimmed(),
+            "Vendored code isn't in the lockfile, so audits and updates skip it; install these from the registry instead"
+                .cyan()
         );
-        if stats.high_severity > 0 {
+        println!();
+    }
+
+    /// Report type declaration coverage of dependencies imported from TypeScript
+    pub fn report_types(&self, results: &[PackageTypes]) {
+        println!();
+
+        if results.is_empty() {
             println!(
-                "  {} {}",
-                stats.high_severity.to_string().red().bold(),
-                "high severity (3+ versions)".red()
+                "{}",
+                "No dependencies imported from TypeScript files"
+                    .green()
+                    .bold()
             );
+            return;
         }
-        if stats.medium_severity > 0 {
+
+        let count = |source: TypesSource| results.iter().filter(|r| r.source == source).count();
+        let missing = count(TypesSource::Missing);
+        let unknown = count(TypesSource::Unknown);
+
+        println!("{}", "Type Declarations".bold().underline());
+        println!();
+
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} dependencies imported from TypeScript",
+            results.len().to_string().cyan()
+        );
+        println!(
+            "  {} bundled, {} via @types, {} vendored",
+            count(TypesSource::Bundled).to_string().green(),
+            count(TypesSource::DefinitelyTyped).to_string().green(),
+            count(TypesSource::Vendored).to_string().green()
+        );
+        if missing > 0 {
             println!(
                 "  {} {}",
-                stats.medium_severity.to_string().yellow(),
-                "medium severity (different major versions)".yellow()
+                missing.to_string().red().bold(),
+                "used but untyped".red()
             );
         }
-        if stats.low_severity > 0 {
+        if unknown > 0 {
             println!(
                 "  {} {}",
-                stats.low_severity.to_string().dimmed(),
-                "low severity (same major version)".dimmed()
+                unknown.to_string().yellow(),
+                "not installed, couldn't check".yellow()
             );
         }
-        println!(
-            "  {} extra compile units",
-            stats.extra_compile_units.to_string().cyan()
-        );
         println!();
 
-        // Group by severity
-        let high: Vec<_> = analysis
-            .duplicates
-            .iter()
-            .filter(|d| d.severity == DuplicateSeverity::High)
-            .collect();
-        let medium: Vec<_> = analysis
-            .duplicates
-            .iter()
-            .filter(|d| d.severity == DuplicateSeverity::Medium)
-            .collect();
-        let low: Vec<_> = analysis
-            .duplicates
-            .iter()
-            .filter(|d| d.severity == DuplicateSeverity::Low)
-            .collect();
-
-        // High severity
-        if !high.is_empty() {
-            println!("{}", "HIGH SEVERITY".red().bold());
-            for group in high {
-                self.print_duplicate_group(group);
+        if missing > 0 {
+            println!("{}", "USED BUT UNTYPED".red().bold());
+            for result in results.iter().filter(|r| r.source == TypesSource::Missing) {
+                println!(
+                    "  {} {}@{} {}",
+                    "!".red().bold(),
+                    result.name.white(),
+                    result.version.dimmed(),
+                    format!("(no {})", result.types_package).dimmed()
+                );
             }
             println!();
         }
 
-        // Medium severity
-        if !medium.is_empty() {
-            println!("{}", "MEDIUM SEVERITY".yellow().bold());
-            for group in medium {
-                self.print_duplicate_group(group);
+        if unknown > 0 {
+            println!("{}", "NOT INSTALLED".yellow().bold());
+            for result in results.iter().filter(|r| r.source == TypesSource::Unknown) {
+                println!(
+                    "  {} {}@{}",
+                    "~".yellow(),
+                    result.name.white(),
+                    result.version.dimmed()
+                );
             }
             println!();
         }
 
-        // Low severity (only in verbose mode)
-        if self.verbose && !low.is_empty() {
-            println!("{}", "LOW SEVERITY".dimmed());
-            for group in low {
-                self.print_duplicate_group(group);
+        if self.verbose {
+            println!("{}", "TYPED".green().bold());
+            for result in results {
+                let source = match result.source {
+                    TypesSource::Bundled => "bundled".to_string(),
+                    TypesSource::DefinitelyTyped => result.types_package.clone(),
+                    TypesSource::Vendored => match &result.declaration {
+                        Some(file) => format!("declared in {}", file.display()),
+                        None => "vendored".to_string(),
+                    },
+                    TypesSource::Missing | TypesSource::Unknown => continue,
+                };
+                println!(
+                    "  {} {} {}",
+                    "-".dimmed(),
+                    result.name.white(),
+                    format!("({})", source).dimmed()
+                );
             }
             println!();
-        } else if !low.is_empty() {
+        }
+
+        if missing > 0 {
+            println!(
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Install the @types package, or add `declare module \"<name>\"` to a .d.ts file"
+                    .cyan()
+            );
+            println!();
+        }
+        if unknown > 0 {
             println!(
-                "  {} {} low severity duplicates (use --verbose to show)",
-                "+".dimmed(),
-                low.len()
+                "  {} {}",
+                "Tip:".dimmed(),
+                "Run your package manager's install so node_modules can be inspected".cyan()
             );
             println!();
         }
+    }
 
-        // Tip
+    /// Report the counts `depx metrics` exposes
+    pub fn report_metrics(&self, metrics: &DependencyMetrics) {
+        println!();
+        println!(
+            "{} {}",
+            "Dependency Metrics".bold().underline(),
+            format!("({})", metrics.project).dimmed()
+        );
+        println!();
+
+        let row = |label: &str, value: String| println!("  {:<16} {}", label, value);
+        row(
+            "Packages",
+            format!(
+                "{} {}",
+                metrics.packages.to_string().cyan(),
+                format!("({} direct)", metrics.direct).dimmed()
+            ),
+        );
+        let count = |count: usize| {
+            if count == 0 {
+                count.to_string().green()
+            } else {
+                count.to_string().yellow()
+            }
+        };
+        row("Unused", count(metrics.unused).to_string());
+        row("Phantom", count(metrics.phantom).to_string());
+        row("Duplicates", count(metrics.duplicates).to_string());
+
+        let vulnerabilities = match &metrics.vulnerabilities {
+            Some(vulnerabilities) => vulnerabilities
+                .iter()
+                .rev()
+                .map(|(severity, count)| {
+                    let count = if *count == 0 {
+                        count.to_string().green()
+                    } else {
+                        count.to_string().red().bold()
+                    };
+                    format!("{} {}", count, severity)
+                })
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => "unknown".dimmed().to_string(),
+        };
+        row("Vulnerabilities", vulnerabilities);
+
+        println!();
         println!(
             "  {} {}",
             "Tip:".dimmed(),
-            "Use `cargo tree -d` for detailed dependency tree".cyan()
+            "Use --prometheus to expose these to a Prometheus scrape".cyan()
+        );
+        println!();
+    }
+
+    /// Report maintenance health of direct dependencies
+    pub fn report_health(&self, report: &HealthReport) {
+        println!();
+
+        let packages = &report.direct;
+        if packages.is_empty() {
+            println!("{}", "No direct dependencies found!".green().bold());
+            return;
+        }
+
+        println!("{}", "Dependency Health Report".bold().underline());
+        println!();
+
+        let abandoned = packages.iter().filter(|p| p.is_abandoned).count();
+        let with_advisories = packages.iter().filter(|p| p.open_advisories > 0).count();
+        let unknown = packages.iter().filter(|p| p.last_publish.is_none()).count();
+        let single_maintainer = packages.iter().filter(|p| p.single_maintainer).count();
+        let maintainer_changes = packages
+            .iter()
+            .filter(|p| p.maintainer_change.is_some())
+            .count();
+
+        println!("{}", "Summary".bold());
+        println!(
+            "  {} direct dependencies",
+            packages.len().to_string().cyan()
         );
+        if abandoned > 0 {
+            println!(
+                "  {} {}",
+                abandoned.to_string().yellow(),
+                "without a release in 2+ years".yellow()
+            );
+        }
+        if with_advisories > 0 {
+            println!(
+                "  {} {}",
+                with_advisories.to_string().red().bold(),
+                "with open advisories".red()
+            );
+        }
+        if single_maintainer > 0 {
+            println!(
+                "  {} {}",
+                single_maintainer.to_string().yellow(),
+                "maintained by a single account".yellow()
+            );
+        }
+        if maintainer_changes > 0 {
+            println!(
+                "  {} {}",
+                maintainer_changes.to_string().red().bold(),
+                "with recent maintainer changes".red()
+            );
+        }
+        if !report.obscure_transitive.is_empty() {
+            println!(
+                "  {} {}",
+                report.obscure_transitive.len().to_string().yellow(),
+                "rarely downloaded transitive packages".yellow()
+            );
+        }
+        if unknown > 0 {
+            println!(
+                "  {} {}",
+                unknown.to_string().dimmed(),
+                "not found on the registry".dimmed()
+            );
+        }
+        println!();
+
+        for pkg in packages {
+            let marker = if pkg.open_advisories > 0 || pkg.maintainer_change.is_some() {
+                "!".red().bold()
+            } else if pkg.is_abandoned || pkg.single_maintainer {
+                "~".yellow()
+            } else {
+                "-".dimmed()
+            };
+
+            let dev_marker = if pkg.is_dev { " (dev)" } else { "" };
+            let latest = match pkg.latest_version {
+                Some(ref latest) if *latest != pkg.version => format!(" -> {}", latest),
+                _ => "".to_string(),
+            };
+
+            println!(
+                "  {} {}{}{}",
+                marker,
+                format!("{}@{}", pkg.name, pkg.version).white(),
+                latest.green(),
+                dev_marker.dimmed()
+            );
+
+            match (&pkg.last_publish, pkg.days_since_publish) {
+                (Some(date), Some(days)) => {
+                    let cadence = match pkg.release_interval_days {
+                        Some(interval) => format!("every ~{}d", interval),
+                        None => "single release".to_string(),
+                    };
+                    let line = format!(
+                        "last release {} ({}), {} releases in the last year, {}",
+                        date,
+                        crate::date::format_age(days),
+                        pkg.releases_last_year,
+                        cadence
+                    );
+                    if pkg.is_abandoned {
+                        println!("      {}", line.yellow());
+                    } else {
+                        println!("      {}", line.dimmed());
+                    }
+                }
+                _ => println!("      {}", "registry metadata unavailable".dimmed()),
+            }
+
+            if let Some(downloads) = pkg.weekly_downloads {
+                let line = format!("{} downloads last week", format_count(downloads));
+                if pkg.is_obscure {
+                    println!("      {}", line.yellow());
+                } else {
+                    println!("      {}", line.dimmed());
+                }
+            }
+
+            if pkg.single_maintainer {
+                println!(
+                    "      {}",
+                    format!("single maintainer ({})", pkg.maintainers.join(", ")).yellow()
+                );
+            }
+
+            if let Some(ref change) = pkg.maintainer_change {
+                println!("      {} {}", "maintainer change:".red(), change.red());
+            }
+
+            if pkg.open_advisories > 0 {
+                println!(
+                    "      {}",
+                    format!(
+                        "{} open {}",
+                        pkg.open_advisories,
+                        if pkg.open_advisories == 1 {
+                            "advisory"
+                        } else {
+                            "advisories"
+                        }
+                    )
+                    .red()
+                );
+            }
+        }
         println!();
+
+        if !report.obscure_transitive.is_empty() {
+            println!(
+                "{}",
+                "Rarely Downloaded Transitive Packages (check for typosquats):"
+                    .yellow()
+                    .bold()
+            );
+            for pkg in &report.obscure_transitive {
+                println!(
+                    "  {} {} {}",
+                    "?".yellow(),
+                    format!("{}@{}", pkg.name, pkg.version).white(),
+                    format!("({}/week)", format_count(pkg.weekly_downloads)).yellow()
+                );
+                if !pkg.dependents.is_empty() {
+                    println!(
+                        "      {}",
+                        format!("← {}", pkg.dependents.join(", ")).dimmed()
+                    );
+                }
+            }
+            println!();
+        }
     }
 
     fn print_duplicate_group(&self, group: &crate::types::DuplicateGroup) {
         let severity_marker = match group.severity {
-            DuplicateSeverity::High => "!".red().bold(),
+            DuplicateSeverity::Critical | DuplicateSeverity::High => "!".red().bold(),
             DuplicateSeverity::Medium => "~".yellow(),
             DuplicateSeverity::Low => "-".dimmed(),
         };
 
         println!(
-            "  {} {} ({} versions)",
+            "  {} {} ({} versions) {}{}",
             severity_marker,
             group.name.cyan().bold(),
-            group.versions.len()
+            group.versions.len(),
+            group.rule.id().dimmed(),
+            owners_suffix(&group.owners)
         );
+        let scored: Vec<String> = group
+            .score
+            .factors
+            .iter()
+            .filter(|f| f.points > 0)
+            .map(|f| format!("{} +{}", f.detail, f.points))
+            .collect();
+        if !scored.is_empty() {
+            println!(
+                "      {}",
+                format!("score {}: {}", group.score.points, scored.join(", ")).dimmed()
+            );
+        }
 
         for version in &group.versions {
             let dependents_str = if version.dependents.is_empty() {
@@ -500,15 +3895,53 @@ impl Reporter {
             );
         }
 
-        // Show suggestion if available
+        // Show suggestions if available
         if self.verbose {
-            if let Some(suggestion) = suggest_resolution(group) {
+            for suggestion in suggest_resolution(group) {
                 println!("      {} {}", "→".green(), suggestion.dimmed());
             }
         }
     }
 }
 
+/// " @org/web @alice" after a finding, or nothing when it's unowned
+fn owners_suffix(owners: &[String]) -> ColoredString {
+    if owners.is_empty() {
+        "".normal()
+    } else {
+        format!(" {}", owners.join(" ")).dimmed()
+    }
+}
+
+/// " (open for 12 days)" after a tracked finding, or nothing untracked
+fn open_for(first_seen: Option<&FirstSeen>) -> ColoredString {
+    match first_seen {
+        None => "".normal(),
+        Some(first) if first.days_open <= 0 => " (new)".dimmed(),
+        Some(first) if first.days_open == 1 => " (open for 1 day)".dimmed(),
+        Some(first) => format!(" (open for {} days)", first.days_open).dimmed(),
+    }
+}
+
+/// Format a byte count ("512 B", "12.3 KB", "4.1 MB")
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        b if b >= 1_000_000_000 => format!("{:.1} GB", b as f64 / 1_000_000_000.0),
+        b if b >= 1_000_000 => format!("{:.1} MB", b as f64 / 1_000_000.0),
+        b if b >= 1_000 => format!("{:.1} KB", b as f64 / 1_000.0),
+        b => format!("{} B", b),
+    }
+}
+
+/// Format a count compactly ("950", "12.3k", "4.1M")
+fn format_count(count: u64) -> String {
+    match count {
+        c if c >= 1_000_000 => format!("{:.1}M", c as f64 / 1_000_000.0),
+        c if c >= 1_000 => format!("{:.1}k", c as f64 / 1_000.0),
+        c => c.to_string(),
+    }
+}
+
 impl Default for Reporter {
     fn default() -> Self {
         Self::new()
@@ -0,0 +1,286 @@
+use clap::ValueEnum;
+use colored::{Color, Colorize};
+
+/// Sort order for tabular reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TableSort {
+    /// Alphabetical
+    Name,
+    /// Largest footprint first
+    Size,
+    /// Most severe first
+    Severity,
+}
+
+/// Space between columns
+const COLUMN_GAP: usize = 2;
+
+/// Columns are never truncated below this many characters
+const MIN_COLUMN_WIDTH: usize = 8;
+
+/// A single table cell: plain text plus how to style it
+#[derive(Debug, Clone)]
+pub struct Cell {
+    text: String,
+    color: Option<Color>,
+    bold: bool,
+    dimmed: bool,
+}
+
+impl Cell {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            color: None,
+            bold: false,
+            dimmed: false,
+        }
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn dimmed(mut self) -> Self {
+        self.dimmed = true;
+        self
+    }
+
+    /// Style already-padded text, so escape codes don't affect alignment
+    fn paint(&self, text: &str) -> String {
+        let mut styled = text.normal();
+        if let Some(color) = self.color {
+            styled = styled.color(color);
+        }
+        if self.bold {
+            styled = styled.bold();
+        }
+        if self.dimmed {
+            styled = styled.dimmed();
+        }
+        styled.to_string()
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::new(text)
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::new(text)
+    }
+}
+
+/// Width-aware table renderer shared by the list-style reports
+///
+/// Columns are sized to their content. When the table is wider than the
+/// terminal, the widest columns are truncated with `…` unless `wide` is set;
+/// output that isn't a terminal is never truncated.
+pub struct Table {
+    headers: Vec<String>,
+    right_aligned: Vec<bool>,
+    rows: Vec<Vec<Cell>>,
+    max_width: Option<usize>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|h| h.to_string()).collect(),
+            right_aligned: vec![false; headers.len()],
+            rows: Vec::new(),
+            max_width: terminal_width(),
+        }
+    }
+
+    /// Right-align a column (counts, sizes)
+    pub fn align_right(mut self, column: usize) -> Self {
+        self.right_aligned[column] = true;
+        self
+    }
+
+    /// Disable truncation
+    pub fn wide(mut self, wide: bool) -> Self {
+        if wide {
+            self.max_width = None;
+        }
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn max_width(mut self, width: usize) -> Self {
+        self.max_width = Some(width);
+        self
+    }
+
+    pub fn row(&mut self, cells: Vec<Cell>) {
+        debug_assert_eq!(cells.len(), self.headers.len());
+        self.rows.push(cells);
+    }
+
+    /// Render the table, each line prefixed with `indent` spaces
+    pub fn render(&self, indent: usize) -> Vec<String> {
+        let widths = self.column_widths(indent);
+        let prefix = " ".repeat(indent);
+
+        let header = Cell::new("").bold();
+        let mut lines = vec![format!(
+            "{}{}",
+            prefix,
+            self.render_row(
+                self.headers.iter().map(|h| Cell {
+                    text: h.clone(),
+                    ..header.clone()
+                }),
+                &widths
+            )
+        )];
+
+        for row in &self.rows {
+            lines.push(format!(
+                "{}{}",
+                prefix,
+                self.render_row(row.iter().cloned(), &widths)
+            ));
+        }
+
+        lines
+    }
+
+    pub fn print(&self, indent: usize) {
+        for line in self.render(indent) {
+            println!("{}", line);
+        }
+    }
+
+    fn render_row(&self, cells: impl Iterator<Item = Cell>, widths: &[usize]) -> String {
+        let last = widths.len() - 1;
+        let mut line = String::new();
+
+        for (i, cell) in cells.enumerate() {
+            let text = truncate(&cell.text, widths[i]);
+            let len = text.chars().count();
+            let padding = " ".repeat(widths[i] - len);
+
+            let padded = if self.right_aligned[i] {
+                format!("{}{}", padding, text)
+            } else if i == last {
+                // No trailing whitespace after the last column
+                text
+            } else {
+                format!("{}{}", text, padding)
+            };
+
+            line.push_str(&cell.paint(&padded));
+            if i != last {
+                line.push_str(&" ".repeat(COLUMN_GAP));
+            }
+        }
+
+        line
+    }
+
+    fn column_widths(&self, indent: usize) -> Vec<usize> {
+        let mut widths: Vec<usize> = self.headers.iter().map(|h| h.chars().count()).collect();
+        for row in &self.rows {
+            for (i, cell) in row.iter().enumerate() {
+                widths[i] = widths[i].max(cell.text.chars().count());
+            }
+        }
+
+        let Some(max_width) = self.max_width else {
+            return widths;
+        };
+
+        let gaps = COLUMN_GAP * (widths.len().saturating_sub(1));
+        let mut total = indent + gaps + widths.iter().sum::<usize>();
+
+        // Shave the widest column one character at a time until the table fits
+        while total > max_width {
+            let Some((widest, _)) = widths
+                .iter()
+                .enumerate()
+                .filter(|(i, w)| **w > MIN_COLUMN_WIDTH.max(self.headers[*i].chars().count()))
+                .max_by_key(|(_, w)| **w)
+            else {
+                break;
+            };
+            widths[widest] -= 1;
+            total -= 1;
+        }
+
+        widths
+    }
+}
+
+/// Terminal width, or `None` when stdout isn't a terminal
+fn terminal_width() -> Option<usize> {
+    terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize)
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(width.saturating_sub(1)).collect();
+    format!("{}…", kept)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Drop ANSI escapes, since whether colors are on depends on the
+    /// environment the tests run in
+    fn plain(lines: Vec<String>) -> Vec<String> {
+        lines
+            .into_iter()
+            .map(|line| {
+                let mut plain = String::new();
+                let mut chars = line.chars();
+                while let Some(c) = chars.next() {
+                    if c == '\x1b' {
+                        chars.by_ref().find(|c| *c == 'm');
+                    } else {
+                        plain.push(c);
+                    }
+                }
+                plain
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_render_table() {
+        let mut table = Table::new(&["Package", "Deps"]).align_right(1).wide(true);
+        table.row(vec![Cell::new("lodash"), Cell::new("0")]);
+        table.row(vec![Cell::new("@babel/core"), Cell::new("42")]);
+
+        assert_eq!(
+            plain(table.render(2)),
+            vec![
+                "  Package      Deps",
+                "  lodash          0",
+                "  @babel/core    42",
+            ]
+        );
+
+        let mut table = Table::new(&["Package", "Dependents"]).max_width(30);
+        table.row(vec![
+            Cell::new("serde"),
+            Cell::new("serde_json, toml, serde_yaml, reqwest"),
+        ]);
+        let lines = plain(table.render(0));
+        assert_eq!(lines[1], "serde    serde_json, toml, se…");
+        assert!(lines.iter().all(|l| l.chars().count() <= 30));
+    }
+}
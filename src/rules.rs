@@ -0,0 +1,235 @@
+//! Stable IDs for the findings depx reports, and the level each is set to
+
+use std::collections::HashSet;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::types::{Classification, UsageAnalysis, UsageClass};
+
+/// Where each rule is documented, one `#depx001` anchor per rule
+const DOCS_PAGE: &str = "blob/main/docs/rules.md";
+
+/// A kind of finding, identified the same way in every output
+///
+/// IDs are never reused: usage findings are DEPX0xx, duplicates DEPX1xx.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rule {
+    UnusedDependency,
+    MisplacedDependency,
+    TypeOnlyDependency,
+    OverlappingDependencies,
+    PhantomDependency,
+    DuplicateSingleton,
+    DuplicateMajor,
+    DuplicateVersion,
+    TypesSkew,
+    PeerSplit,
+}
+
+impl Rule {
+    pub const ALL: [Rule; 10] = [
+        Rule::UnusedDependency,
+        Rule::MisplacedDependency,
+        Rule::TypeOnlyDependency,
+        Rule::OverlappingDependencies,
+        Rule::PhantomDependency,
+        Rule::DuplicateSingleton,
+        Rule::DuplicateMajor,
+        Rule::DuplicateVersion,
+        Rule::TypesSkew,
+        Rule::PeerSplit,
+    ];
+
+    /// "DEPX001"
+    pub fn id(self) -> &'static str {
+        match self {
+            Rule::UnusedDependency => "DEPX001",
+            Rule::MisplacedDependency => "DEPX002",
+            Rule::TypeOnlyDependency => "DEPX003",
+            Rule::OverlappingDependencies => "DEPX004",
+            Rule::PhantomDependency => "DEPX010",
+            Rule::DuplicateSingleton => "DEPX100",
+            Rule::DuplicateMajor => "DEPX101",
+            Rule::DuplicateVersion => "DEPX102",
+            Rule::TypesSkew => "DEPX110",
+            Rule::PeerSplit => "DEPX111",
+        }
+    }
+
+    /// "unused-dependency"; also the check name CI annotations had before
+    /// rules had IDs, so GitLab fingerprints stay the same
+    pub fn name(self) -> &'static str {
+        match self {
+            Rule::UnusedDependency => "unused-dependency",
+            Rule::MisplacedDependency => "misplaced-dependency",
+            Rule::TypeOnlyDependency => "type-only-dependency",
+            Rule::OverlappingDependencies => "overlapping-dependencies",
+            Rule::PhantomDependency => "phantom-dependency",
+            Rule::DuplicateSingleton => "duplicate-singleton",
+            Rule::DuplicateMajor => "duplicate-major",
+            Rule::DuplicateVersion => "duplicate-version",
+            Rule::TypesSkew => "types-skew",
+            Rule::PeerSplit => "peer-split",
+        }
+    }
+
+    /// What its findings are called in counts ("3 unused dependencies")
+    pub fn findings(self) -> &'static str {
+        match self {
+            Rule::UnusedDependency => "unused dependencies",
+            Rule::MisplacedDependency => "dependencies in the wrong package.json section",
+            Rule::TypeOnlyDependency => "dependencies only imported as types",
+            Rule::OverlappingDependencies => "groups of overlapping dependencies",
+            Rule::PhantomDependency => "phantom dependencies",
+            Rule::DuplicateSingleton => "singletons with several copies",
+            Rule::DuplicateMajor => "packages duplicated across major versions",
+            Rule::DuplicateVersion => "packages duplicated within a major version",
+            Rule::TypesSkew => "@types packages for another major version",
+            Rule::PeerSplit => "versions split by peer dependencies",
+        }
+    }
+
+    /// A rule by ID or name, ignoring case
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|rule| {
+            rule.id().eq_ignore_ascii_case(value) || rule.name().eq_ignore_ascii_case(value)
+        })
+    }
+
+    /// The rule's section of docs/rules.md in the repository
+    pub fn docs_url(self) -> String {
+        format!(
+            "{}/{}#{}",
+            env!("CARGO_PKG_REPOSITORY"),
+            DOCS_PAGE,
+            self.id().to_lowercase()
+        )
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+impl Serialize for Rule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.id())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Rule::parse(&value).ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "unknown rule '{}', expected an ID like DEPX001 or a name like unused-dependency",
+                value
+            ))
+        })
+    }
+}
+
+/// What `[rules]` in depx.toml sets a rule to
+///
+/// A rule that isn't set is reported, and fails the run only through
+/// thresholds such as `--fail-if-unused` or `[ci] fail-if-phantom`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleLevel {
+    /// Its findings are dropped before reporting
+    Off,
+    /// Reported, and fails the run only through thresholds
+    Warn,
+    /// Any finding fails the run
+    Error,
+}
+
+/// Drop the findings of `disabled` rules from a usage analysis
+pub fn suppress_usage(analysis: &mut UsageAnalysis, disabled: &HashSet<Rule>) {
+    if disabled.contains(&Rule::UnusedDependency) {
+        analysis.unused_direct.clear();
+    }
+    if disabled.contains(&Rule::MisplacedDependency) {
+        analysis.misplaced.clear();
+    }
+    if disabled.contains(&Rule::TypeOnlyDependency) {
+        analysis.type_only.clear();
+    }
+    if disabled.contains(&Rule::OverlappingDependencies) {
+        analysis.overlapping.clear();
+    }
+    if disabled.contains(&Rule::PhantomDependency) {
+        analysis.phantom.clear();
+    }
+}
+
+/// Mark a classification whose finding a `disabled` rule drops, the way
+/// `suppress_usage` drops it from the analysis
+pub fn suppress_classification(classification: &mut Classification, disabled: &HashSet<Rule>) {
+    let reported_by = match classification.class {
+        UsageClass::Unused if classification.package.is_direct => Rule::UnusedDependency,
+        _ => return,
+    };
+    if disabled.contains(&reported_by) {
+        classification.suppressed_by = Some(reported_by);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::graph::DependencyGraph;
+    use crate::types::Package;
+
+    #[test]
+    fn test_rules() {
+        let ids: HashSet<&str> = Rule::ALL.iter().map(|rule| rule.id()).collect();
+        assert_eq!(ids.len(), Rule::ALL.len());
+
+        assert_eq!(Rule::parse("DEPX010"), Some(Rule::PhantomDependency));
+        assert_eq!(Rule::parse("depx101"), Some(Rule::DuplicateMajor));
+        assert_eq!(
+            Rule::parse("unused-dependency"),
+            Some(Rule::UnusedDependency)
+        );
+        assert_eq!(Rule::parse("DEPX999"), None);
+        assert_eq!(
+            Rule::UnusedDependency.docs_url(),
+            "https://github.com/ruidosujeira/depx/blob/main/docs/rules.md#depx001"
+        );
+
+        assert_eq!(
+            serde_json::to_string(&Rule::DuplicateMajor).unwrap(),
+            "\"DEPX101\""
+        );
+        assert!(serde_json::from_str::<Rule>("\"unused\"").is_err());
+    }
+
+    #[test]
+    fn test_suppress_usage() {
+        let mut packages = HashMap::new();
+        packages.insert("ms".to_string(), Package::new("ms", "2.1.3").direct());
+        let graph = DependencyGraph::new(&packages);
+        let mut analysis = graph.analyze_usage(&HashSet::new(), true);
+        assert_eq!(analysis.unused_direct.len(), 1);
+
+        suppress_usage(&mut analysis, &HashSet::from([Rule::PhantomDependency]));
+        assert_eq!(analysis.unused_direct.len(), 1);
+        suppress_usage(&mut analysis, &HashSet::from([Rule::UnusedDependency]));
+        assert!(analysis.unused_direct.is_empty());
+
+        let mut classification = graph
+            .classify("ms", &HashSet::new(), &Default::default())
+            .remove(0);
+        suppress_classification(
+            &mut classification,
+            &HashSet::from([Rule::UnusedDependency]),
+        );
+        assert_eq!(classification.suppressed_by, Some(Rule::UnusedDependency));
+    }
+}
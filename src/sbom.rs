@@ -0,0 +1,537 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+
+use crate::lockfile::CargoPackageInfo;
+use crate::types::{SbomComponent, SbomDiff, SbomFormat, VersionDrift};
+
+/// Packages listed in a CycloneDX or SPDX JSON document
+#[derive(Debug)]
+pub struct Sbom {
+    pub format: SbomFormat,
+    entries: Vec<SbomEntry>,
+}
+
+/// A listed package, with its package URL type when it has one
+#[derive(Debug)]
+struct SbomEntry {
+    component: SbomComponent,
+    purl_type: Option<String>,
+}
+
+impl Sbom {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read SBOM {}", path.display()))?;
+        Self::parse(&content)
+            .map_err(|e| miette::miette!("Failed to parse SBOM {}: {}", path.display(), e))
+    }
+
+    fn parse(content: &str) -> std::result::Result<Self, String> {
+        let document: JsonValue = serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+        if document.get("bomFormat").and_then(|f| f.as_str()) == Some("CycloneDX") {
+            let bom: CycloneDxBom = serde_json::from_value(document).map_err(|e| e.to_string())?;
+            let mut entries = Vec::new();
+            collect_cyclonedx(&bom.components, &mut entries);
+            return Ok(Self {
+                format: SbomFormat::CycloneDx,
+                entries,
+            });
+        }
+
+        if document.get("spdxVersion").is_some() {
+            let spdx: SpdxDocument = serde_json::from_value(document).map_err(|e| e.to_string())?;
+            return Ok(Self {
+                format: SbomFormat::Spdx,
+                entries: spdx_entries(spdx),
+            });
+        }
+
+        Err("not a CycloneDX or SPDX JSON document".to_string())
+    }
+
+    /// Compare against the installed versions from the lockfile
+    ///
+    /// Components with a package URL of another type (`pkg:github/...`, OS
+    /// packages, ...) aren't from this lockfile and are skipped. Path
+    /// dependencies and workspace members are the project's own code and
+    /// are left out on both sides.
+    pub fn diff(
+        &self,
+        installed: &HashMap<String, Vec<CargoPackageInfo>>,
+        purl_type: &str,
+    ) -> SbomDiff {
+        let local: HashSet<&str> = installed
+            .iter()
+            .filter(|(_, versions)| versions.iter().any(|v| v.is_path_dep))
+            .map(|(name, _)| name.as_str())
+            .collect();
+
+        let mut lockfile: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for (name, versions) in installed {
+            if local.contains(name.as_str()) {
+                continue;
+            }
+            lockfile
+                .entry(name)
+                .or_default()
+                .extend(versions.iter().map(|v| v.version.as_str()));
+        }
+
+        let mut sbom: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for entry in &self.entries {
+            let name = entry.component.name.as_str();
+            if name.is_empty()
+                || local.contains(name)
+                || entry.purl_type.as_deref().is_some_and(|t| t != purl_type)
+            {
+                continue;
+            }
+            sbom.entry(name)
+                .or_default()
+                .insert(entry.component.version.as_str());
+        }
+
+        let component = |name: &str, version: &str| SbomComponent {
+            name: name.to_string(),
+            version: version.to_string(),
+            purl: Some(package_url(purl_type, name, version)),
+        };
+        let mut diff = SbomDiff {
+            format: self.format,
+            sbom_components: sbom.values().map(|v| v.len()).sum(),
+            lockfile_packages: lockfile.values().map(|v| v.len()).sum(),
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        };
+
+        for (name, installed_versions) in &lockfile {
+            match sbom.get(name) {
+                None => diff
+                    .added
+                    .extend(installed_versions.iter().map(|v| component(name, v))),
+                Some(listed) if listed != installed_versions => diff.changed.push(VersionDrift {
+                    name: name.to_string(),
+                    sbom_versions: listed.iter().map(|v| v.to_string()).collect(),
+                    lockfile_versions: installed_versions.iter().map(|v| v.to_string()).collect(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for (name, listed) in &sbom {
+            if !lockfile.contains_key(name) {
+                diff.removed
+                    .extend(listed.iter().map(|v| component(name, v)));
+            }
+        }
+
+        diff
+    }
+}
+
+/// Components and their nested components; purls carry the canonical name
+fn collect_cyclonedx(components: &[CycloneDxComponent], entries: &mut Vec<SbomEntry>) {
+    for c in components {
+        let purl = c.purl.as_deref().and_then(parse_purl);
+        let component = match &purl {
+            Some((_, name, version)) if !name.is_empty() => SbomComponent {
+                name: name.clone(),
+                version: if version.is_empty() {
+                    c.version.clone()
+                } else {
+                    version.clone()
+                },
+                purl: None,
+            },
+            _ => SbomComponent {
+                name: match c.group.as_deref().filter(|g| !g.is_empty()) {
+                    Some(group) => format!("{}/{}", group, c.name),
+                    None => c.name.clone(),
+                },
+                version: c.version.clone(),
+                purl: None,
+            },
+        };
+        entries.push(SbomEntry {
+            component,
+            purl_type: purl.map(|(kind, _, _)| kind),
+        });
+        collect_cyclonedx(&c.components, entries);
+    }
+}
+
+/// Packages of an SPDX document, without the ones it describes (the project)
+fn spdx_entries(document: SpdxDocument) -> Vec<SbomEntry> {
+    let mut described: HashSet<String> = document.document_describes.into_iter().collect();
+    described.extend(
+        document
+            .relationships
+            .into_iter()
+            .filter(|r| r.relationship_type == "DESCRIBES")
+            .map(|r| r.related_spdx_element),
+    );
+
+    document
+        .packages
+        .into_iter()
+        .filter(|p| !described.contains(&p.spdx_id))
+        .map(|p| {
+            let purl = p
+                .external_refs
+                .iter()
+                .find(|r| r.reference_type == "purl")
+                .and_then(|r| parse_purl(&r.reference_locator));
+            let version = p.version_info.unwrap_or_default();
+            match purl {
+                Some((kind, name, purl_version)) if !name.is_empty() => SbomEntry {
+                    component: SbomComponent {
+                        name,
+                        version: if purl_version.is_empty() {
+                            version
+                        } else {
+                            purl_version
+                        },
+                        purl: None,
+                    },
+                    purl_type: Some(kind),
+                },
+                _ => SbomEntry {
+                    component: SbomComponent {
+                        name: p.name,
+                        version,
+                        purl: None,
+                    },
+                    purl_type: None,
+                },
+            }
+        })
+        .collect()
+}
+
+/// Package URL for a registry package, the inverse of `parse_purl`
+///
+/// ("npm", "@babel/core", "7.24.0") -> "pkg:npm/%40babel/core@7.24.0"
+pub fn package_url(kind: &str, name: &str, version: &str) -> String {
+    format!(
+        "pkg:{}/{}@{}",
+        kind,
+        name.replace('@', "%40"),
+        version.replace('+', "%2B")
+    )
+}
+
+/// Best-effort CPE 2.3 name for a registry package
+///
+/// NVD has no rule for naming package vendors, so the scope stands in for
+/// the vendor of scoped packages and the package name otherwise, which
+/// matches many but not all of its entries.
+///
+/// ("npm", "@babel/core", "7.24.0") -> "cpe:2.3:a:babel:core:7.24.0:*:*:*:*:node.js:*:*"
+/// ("cargo", "hyper", "0.14.10") -> "cpe:2.3:a:hyper:hyper:0.14.10:*:*:*:*:rust:*:*"
+pub fn cpe(kind: &str, name: &str, version: &str) -> String {
+    let (vendor, product) = match name.strip_prefix('@').and_then(|n| n.split_once('/')) {
+        Some((scope, product)) => (scope, product),
+        // "github.com/gin-gonic/gin/v2": the repository owner and name
+        None if kind == "golang" => {
+            let mut segments = name.split('/').skip(1);
+            match (segments.next(), segments.next()) {
+                (Some(owner), Some(repo)) => (owner, repo),
+                _ => (name, name),
+            }
+        }
+        None => (name, name),
+    };
+    let target = match kind {
+        "cargo" => "rust",
+        "npm" => "node.js",
+        "golang" => "go",
+        _ => "*",
+    };
+    // NVD lists Go versions without the "v"
+    let version = match kind {
+        "golang" => version.strip_prefix('v').unwrap_or(version),
+        _ => version,
+    };
+    format!(
+        "cpe:2.3:a:{}:{}:{}:*:*:*:*:{}:*:*",
+        cpe_escape(vendor),
+        cpe_escape(product),
+        cpe_escape(version),
+        target
+    )
+}
+
+/// Lowercase and quote characters CPE names don't allow bare
+fn cpe_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars().flat_map(char::to_lowercase) {
+        if !(c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Split a package URL into its type, full name and version
+///
+/// "pkg:npm/%40babel/core@7.24.0?x=y" -> ("npm", "@babel/core", "7.24.0")
+/// "pkg:cargo/serde@1.0.200" -> ("cargo", "serde", "1.0.200")
+fn parse_purl(purl: &str) -> Option<(String, String, String)> {
+    let rest = purl.strip_prefix("pkg:")?;
+    let rest = rest.split(['?', '#']).next()?;
+    let (kind, path) = rest.split_once('/')?;
+    // An unencoded '@' at the start is a scope, not the version
+    let (path, version) = match path.rfind('@').filter(|&at| at > 0) {
+        Some(at) => (&path[..at], &path[at + 1..]),
+        None => (path, ""),
+    };
+    Some((
+        kind.to_ascii_lowercase(),
+        percent_decode(path),
+        percent_decode(version),
+    ))
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[derive(Deserialize)]
+struct CycloneDxBom {
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+struct CycloneDxComponent {
+    #[serde(default)]
+    name: String,
+
+    #[serde(default)]
+    version: String,
+
+    group: Option<String>,
+
+    purl: Option<String>,
+
+    #[serde(default)]
+    components: Vec<CycloneDxComponent>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxDocument {
+    #[serde(default)]
+    packages: Vec<SpdxPackage>,
+
+    #[serde(default)]
+    document_describes: Vec<String>,
+
+    #[serde(default)]
+    relationships: Vec<SpdxRelationship>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxPackage {
+    #[serde(rename = "SPDXID", default)]
+    spdx_id: String,
+
+    #[serde(default)]
+    name: String,
+
+    version_info: Option<String>,
+
+    #[serde(default)]
+    external_refs: Vec<SpdxExternalRef>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxExternalRef {
+    reference_type: String,
+    reference_locator: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpdxRelationship {
+    relationship_type: String,
+    related_spdx_element: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(entries: &[(&str, &str, bool)]) -> HashMap<String, Vec<CargoPackageInfo>> {
+        let mut installed: HashMap<String, Vec<CargoPackageInfo>> = HashMap::new();
+        for (name, version, is_path_dep) in entries {
+            installed
+                .entry(name.to_string())
+                .or_default()
+                .push(CargoPackageInfo {
+                    version: version.to_string(),
+                    is_path_dep: *is_path_dep,
+                    ..Default::default()
+                });
+        }
+        installed
+    }
+
+    #[test]
+    fn test_package_url() {
+        assert_eq!(
+            package_url("npm", "@babel/core", "7.24.0"),
+            "pkg:npm/%40babel/core@7.24.0"
+        );
+        assert_eq!(
+            package_url("cargo", "serde", "1.0.0+build"),
+            "pkg:cargo/serde@1.0.0%2Bbuild"
+        );
+        let purl = package_url("npm", "@babel/core", "7.24.0");
+        assert_eq!(
+            parse_purl(&purl),
+            Some(("npm".into(), "@babel/core".into(), "7.24.0".into()))
+        );
+    }
+
+    #[test]
+    fn test_cpe() {
+        assert_eq!(
+            cpe("npm", "@babel/core", "7.24.0"),
+            "cpe:2.3:a:babel:core:7.24.0:*:*:*:*:node.js:*:*"
+        );
+        assert_eq!(
+            cpe("cargo", "hyper", "0.14.10"),
+            "cpe:2.3:a:hyper:hyper:0.14.10:*:*:*:*:rust:*:*"
+        );
+        assert_eq!(
+            cpe("npm", "Foo", "1.0.0+git:abc"),
+            "cpe:2.3:a:foo:foo:1.0.0\\+git\\:abc:*:*:*:*:node.js:*:*"
+        );
+        assert_eq!(
+            cpe("golang", "github.com/gin-gonic/gin", "v1.9.1"),
+            "cpe:2.3:a:gin-gonic:gin:1.9.1:*:*:*:*:go:*:*"
+        );
+    }
+
+    #[test]
+    fn test_parse_purl() {
+        assert_eq!(
+            parse_purl("pkg:npm/%40babel/core@7.24.0?vcs_url=x"),
+            Some(("npm".into(), "@babel/core".into(), "7.24.0".into()))
+        );
+        assert_eq!(
+            parse_purl("pkg:npm/@types/node"),
+            Some(("npm".into(), "@types/node".into(), "".into()))
+        );
+        assert_eq!(
+            parse_purl("pkg:cargo/serde@1.0.200"),
+            Some(("cargo".into(), "serde".into(), "1.0.200".into()))
+        );
+        assert_eq!(parse_purl("serde"), None);
+    }
+
+    #[test]
+    fn test_cyclonedx_diff() {
+        let sbom = Sbom::parse(
+            r#"{
+                "bomFormat": "CycloneDX",
+                "specVersion": "1.5",
+                "metadata": {"component": {"name": "app", "version": "1.0.0"}},
+                "components": [
+                    {"name": "core", "group": "@babel", "version": "7.24.0",
+                        "purl": "pkg:npm/%40babel/core@7.24.0"},
+                    {"name": "lodash", "version": "4.17.20", "purl": "pkg:npm/lodash@4.17.20"},
+                    {"name": "left-pad", "version": "1.3.0"},
+                    {"name": "checkout", "version": "v4", "purl": "pkg:github/actions/checkout@v4"},
+                    {"name": "ui", "version": "0.1.0", "purl": "pkg:npm/ui@0.1.0"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(sbom.format, SbomFormat::CycloneDx);
+
+        let diff = sbom.diff(
+            &installed(&[
+                ("@babel/core", "7.24.0", false),
+                ("lodash", "4.17.21", false),
+                ("react", "18.2.0", false),
+                ("ui", "0.2.0", true),
+            ]),
+            "npm",
+        );
+        assert_eq!(diff.sbom_components, 3);
+        assert_eq!(diff.lockfile_packages, 3);
+        assert_eq!(
+            diff.added,
+            vec![SbomComponent {
+                name: "react".into(),
+                version: "18.2.0".into(),
+                purl: Some("pkg:npm/react@18.2.0".into()),
+            }]
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].name, "left-pad");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].sbom_versions, vec!["4.17.20"]);
+        assert_eq!(diff.changed[0].lockfile_versions, vec!["4.17.21"]);
+    }
+
+    #[test]
+    fn test_spdx_diff() {
+        let sbom = Sbom::parse(
+            r#"{
+                "spdxVersion": "SPDX-2.3",
+                "packages": [
+                    {"SPDXID": "SPDXRef-root", "name": "app", "versionInfo": "0.1.0"},
+                    {"SPDXID": "SPDXRef-serde", "name": "serde", "versionInfo": "1.0.200",
+                        "externalRefs": [{"referenceCategory": "PACKAGE-MANAGER",
+                            "referenceType": "purl",
+                            "referenceLocator": "pkg:cargo/serde@1.0.200"}]},
+                    {"SPDXID": "SPDXRef-syn", "name": "syn", "versionInfo": "2.0.60"}
+                ],
+                "relationships": [{"spdxElementId": "SPDXRef-DOCUMENT",
+                    "relationshipType": "DESCRIBES", "relatedSpdxElement": "SPDXRef-root"}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(sbom.format, SbomFormat::Spdx);
+
+        let diff = sbom.diff(
+            &installed(&[("serde", "1.0.200", false), ("syn", "2.0.60", false)]),
+            "cargo",
+        );
+        assert!(diff.is_empty());
+        assert_eq!(diff.sbom_components, 2);
+
+        assert!(Sbom::parse(r#"{"packages": []}"#).is_err());
+    }
+}
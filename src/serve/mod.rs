@@ -0,0 +1,267 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use miette::{IntoDiagnostic, Result};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::graph::DependencyGraph;
+use crate::types::GraphExport;
+
+/// The page drawing the graph; it has no dependencies of its own
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Largest request head read before giving up on a connection
+const MAX_REQUEST_HEAD: usize = 64 * 1024;
+
+/// How long a connection may take to send its request head
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves the graph page, the graph export and why-chains over HTTP
+///
+/// Routes:
+/// - `/` - the page
+/// - `/graph.json` - the `GraphExport` that `depx graph` prints
+/// - `/why?package=<id>` - the explanation `depx why` prints, as JSON
+///
+/// Requests must name the server itself in `Host` (`127.0.0.1:<port>` or
+/// `localhost:<port>`), so a page on another origin can't read the graph
+/// by rebinding its own host name to 127.0.0.1.
+pub struct GraphServer {
+    graphs: Vec<DependencyGraph>,
+    graph_json: String,
+}
+
+/// A response to one request
+#[derive(Debug)]
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: String,
+}
+
+impl Response {
+    fn ok(content_type: &'static str, body: String) -> Self {
+        Self {
+            status: "200 OK",
+            content_type,
+            body,
+        }
+    }
+
+    fn error(status: &'static str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", status),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let head = format!(
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.content_type,
+            self.body.len()
+        );
+        [head.as_bytes(), self.body.as_bytes()].concat()
+    }
+}
+
+impl GraphServer {
+    /// `graphs` are the dependency graphs of every lockfile `export` was made from
+    pub fn new(graphs: Vec<DependencyGraph>, export: &GraphExport) -> Result<Self> {
+        let graph_json = serde_json::to_string(export)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        Ok(Self { graphs, graph_json })
+    }
+
+    /// Answer connections on `listener` until the task is dropped
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) -> Result<()> {
+        loop {
+            let (stream, _) = listener.accept().await.into_diagnostic()?;
+            let server = Arc::clone(&self);
+            tokio::spawn(async move {
+                // A client hanging up early only affects its own request
+                let _ = server.handle(stream).await;
+            });
+        }
+    }
+
+    async fn handle(&self, mut stream: TcpStream) -> std::io::Result<()> {
+        let port = stream.local_addr()?.port();
+        let mut head = Vec::new();
+        let read_head = async {
+            let mut chunk = [0; 4096];
+            while !head.windows(4).any(|window| window == b"\r\n\r\n") {
+                let read = stream.read(&mut chunk).await?;
+                if read == 0 || head.len() > MAX_REQUEST_HEAD {
+                    break;
+                }
+                head.extend_from_slice(&chunk[..read]);
+            }
+            std::io::Result::Ok(())
+        };
+        // An idle connection is closed rather than held open
+        let Ok(read) = tokio::time::timeout(READ_TIMEOUT, read_head).await else {
+            return Ok(());
+        };
+        read?;
+
+        let head = String::from_utf8_lossy(&head);
+        let mut request_line = head.lines().next().unwrap_or("").split_whitespace();
+        let response = match (request_line.next(), request_line.next()) {
+            _ if !is_local_host(&head, port) => Response::error("403 Forbidden"),
+            (Some("GET"), Some(target)) => self.respond(target),
+            (Some(_), Some(_)) => Response::error("405 Method Not Allowed"),
+            _ => Response::error("400 Bad Request"),
+        };
+        stream.write_all(&response.to_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    fn respond(&self, target: &str) -> Response {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        match path {
+            "/" | "/index.html" => Response::ok("text/html; charset=utf-8", INDEX_HTML.to_string()),
+            "/graph.json" => Response::ok("application/json", self.graph_json.clone()),
+            "/why" => {
+                let Some(package) = query_param(query, "package") else {
+                    return Response::error("400 Bad Request");
+                };
+                let Some(explanation) = self
+                    .graphs
+                    .iter()
+                    .find_map(|graph| graph.explain_package(&package))
+                else {
+                    return Response::error("404 Not Found");
+                };
+                match serde_json::to_string(&explanation) {
+                    Ok(body) => Response::ok("application/json", body),
+                    Err(_) => Response::error("500 Internal Server Error"),
+                }
+            }
+            _ => Response::error("404 Not Found"),
+        }
+    }
+}
+
+/// Whether the request head's `Host` header names this server
+fn is_local_host(head: &str, port: u16) -> bool {
+    let host = head.lines().skip(1).find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.trim()
+            .eq_ignore_ascii_case("host")
+            .then(|| value.trim())
+    });
+    host.is_some_and(|host| {
+        host == format!("127.0.0.1:{}", port) || host == format!("localhost:{}", port)
+    })
+}
+
+/// The decoded value of `name` in a query string
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| percent_decode(value))
+}
+
+/// Decode `%XX` escapes and `+`, as browsers encode query strings;
+/// malformed escapes are kept as they are
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], escaped) {
+            (_, Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+            (b'+', None) => decoded.push(b' '),
+            (byte, None) => decoded.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::types::Package;
+
+    #[test]
+    fn test_respond() {
+        let mut packages = HashMap::new();
+        packages.insert(
+            "@scope/app".to_string(),
+            Package::new("@scope/app", "1.0.0")
+                .direct()
+                .with_dependencies(vec!["ms".to_string()]),
+        );
+        packages.insert("ms".to_string(), Package::new("ms", "2.1.3"));
+        let graph = DependencyGraph::new(&packages);
+        let export = graph.export(&[]);
+        let server = GraphServer::new(vec![graph], &export).unwrap();
+
+        let page = server.respond("/");
+        assert_eq!(page.status, "200 OK");
+        assert!(page.body.contains("graph.json"));
+
+        let graph_json = server.respond("/graph.json");
+        let parsed: GraphExport = serde_json::from_str(&graph_json.body).unwrap();
+        assert_eq!(parsed.nodes.len(), 2);
+        assert_eq!(parsed.edges.len(), 1);
+
+        let why = server.respond("/why?package=ms");
+        let explanation: serde_json::Value = serde_json::from_str(&why.body).unwrap();
+        assert_eq!(
+            explanation["dependency_chains"],
+            serde_json::json!([["@scope/app", "ms"]])
+        );
+        assert_eq!(
+            server.respond("/why?package=%40scope%2Fapp").status,
+            "200 OK"
+        );
+        assert_eq!(
+            server.respond("/why?package=left-pad").status,
+            "404 Not Found"
+        );
+        assert_eq!(server.respond("/why").status, "400 Bad Request");
+        assert_eq!(server.respond("/favicon.ico").status, "404 Not Found");
+
+        let bytes = graph_json.to_bytes();
+        let head = String::from_utf8_lossy(&bytes);
+        assert!(head.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(head.contains(&format!("Content-Length: {}\r\n", graph_json.body.len())));
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        let head = |host: &str| format!("GET / HTTP/1.1\r\n{}\r\nAccept: */*\r\n\r\n", host);
+        assert!(is_local_host(&head("Host: 127.0.0.1:7878"), 7878));
+        assert!(is_local_host(&head("host: localhost:7878"), 7878));
+        assert!(!is_local_host(&head("Host: localhost:8080"), 7878));
+        assert!(!is_local_host(&head("Host: attacker.example:7878"), 7878));
+        assert!(!is_local_host(&head("Accept-Language: en"), 7878));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("%40babel%2Fcore"), "@babel/core");
+        assert_eq!(percent_decode("serde%401.0.200"), "serde@1.0.200");
+        assert_eq!(percent_decode("a+b"), "a b");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%zz"), "%zz");
+    }
+}
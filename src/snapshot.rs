@@ -0,0 +1,114 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+
+use crate::types::{AnalysisSnapshot, SnapshotDiff, UsageAnalysis};
+
+/// Current snapshot format
+const SNAPSHOT_VERSION: u32 = 1;
+
+impl AnalysisSnapshot {
+    pub fn from_analysis(analysis: &UsageAnalysis) -> Self {
+        let sorted = |names: Vec<String>| -> Vec<String> {
+            names
+                .into_iter()
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect()
+        };
+
+        Self {
+            version: SNAPSHOT_VERSION,
+            unused: sorted(
+                analysis
+                    .unused_direct
+                    .iter()
+                    .map(|p| p.name.clone())
+                    .collect(),
+            ),
+            phantom: sorted(analysis.phantom.iter().map(|p| p.name.clone()).collect()),
+            owners: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_owners(mut self, owners: BTreeMap<String, Vec<String>>) -> Self {
+        self.owners = owners;
+        self
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read snapshot {}", path.display()))?;
+        let snapshot: Self = serde_json::from_str(&content)
+            .map_err(|e| miette::miette!("Failed to parse snapshot {}: {}", path.display(), e))?;
+
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(miette::miette!(
+                "Snapshot {} was written by a newer depx (format {}, expected {})",
+                path.display(),
+                snapshot.version,
+                SNAPSHOT_VERSION
+            ));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let output = serde_json::to_string_pretty(self)
+            .map_err(|e| miette::miette!("Failed to serialize JSON: {}", e))?;
+        std::fs::write(path, output + "\n")
+            .into_diagnostic()
+            .with_context(|| format!("Failed to write snapshot {}", path.display()))
+    }
+
+    /// What changed since `previous`
+    pub fn diff(&self, previous: &AnalysisSnapshot) -> SnapshotDiff {
+        SnapshotDiff {
+            new_unused: difference(&self.unused, &previous.unused),
+            resolved_unused: difference(&previous.unused, &self.unused),
+            new_phantom: difference(&self.phantom, &previous.phantom),
+            resolved_phantom: difference(&previous.phantom, &self.phantom),
+        }
+    }
+}
+
+/// Items of `a` missing from `b`, sorted
+fn difference(a: &[String], b: &[String]) -> Vec<String> {
+    let b: BTreeSet<&String> = b.iter().collect();
+    a.iter()
+        .filter(|item| !b.contains(item))
+        .cloned()
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(unused: &[&str], phantom: &[&str]) -> AnalysisSnapshot {
+        AnalysisSnapshot {
+            version: SNAPSHOT_VERSION,
+            unused: unused.iter().map(|s| s.to_string()).collect(),
+            phantom: phantom.iter().map(|s| s.to_string()).collect(),
+            owners: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_diff() {
+        let before = snapshot(&["left-pad", "moment"], &["ms"]);
+        let after = snapshot(&["moment", "request"], &["debug", "ms"]);
+
+        let diff = after.diff(&before);
+        assert_eq!(diff.new_unused, vec!["request"]);
+        assert_eq!(diff.resolved_unused, vec!["left-pad"]);
+        assert_eq!(diff.new_phantom, vec!["debug"]);
+        assert!(diff.resolved_phantom.is_empty());
+
+        assert!(after.diff(&after).is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+//! Evaluating Cargo `[target.'cfg(...)']` tables for a target platform
+
+use miette::Result;
+
+/// The platform a Cargo project is analyzed for, as the `cfg` values
+/// rustc sets for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub arch: String,
+    pub os: String,
+    pub families: Vec<String>,
+    pub env: String,
+    pub vendor: String,
+    pub pointer_width: u32,
+    pub endian: &'static str,
+}
+
+impl Target {
+    /// The machine depx runs on
+    pub fn host() -> Self {
+        Self {
+            arch: std::env::consts::ARCH.to_string(),
+            os: std::env::consts::OS.to_string(),
+            families: std::env::consts::FAMILY
+                .split(',')
+                .filter(|family| !family.is_empty())
+                .map(str::to_string)
+                .collect(),
+            env: if cfg!(target_env = "musl") {
+                "musl"
+            } else if cfg!(target_env = "msvc") {
+                "msvc"
+            } else if cfg!(target_env = "gnu") {
+                "gnu"
+            } else {
+                ""
+            }
+            .to_string(),
+            vendor: if cfg!(target_vendor = "apple") {
+                "apple"
+            } else if cfg!(target_vendor = "pc") {
+                "pc"
+            } else {
+                "unknown"
+            }
+            .to_string(),
+            pointer_width: usize::BITS,
+            endian: if cfg!(target_endian = "big") {
+                "big"
+            } else {
+                "little"
+            },
+        }
+    }
+
+    /// A target triple such as "x86_64-pc-windows-msvc" or
+    /// "aarch64-apple-darwin"
+    ///
+    /// The values come from the triple's parts rather than rustc's target
+    /// database, so exotic targets get approximate ones.
+    pub fn from_triple(triple: &str) -> Result<Self> {
+        let parts: Vec<&str> = triple.split('-').collect();
+        let (arch, rest) = match parts.split_first() {
+            Some((arch, rest)) if !arch.is_empty() && !rest.is_empty() => (*arch, rest),
+            _ => {
+                return Err(miette::miette!(
+                    "Unrecognized target triple '{}'. Expected one like x86_64-unknown-linux-gnu",
+                    triple
+                ))
+            }
+        };
+
+        // The vendor is optional: "aarch64-linux-android", "wasm32-wasip1".
+        // Bare-metal targets have none for a vendor and an OS.
+        let (vendor, system) = match rest {
+            ["none", system @ ..] => ("unknown", system),
+            [vendor, system @ ..]
+                if system.len() >= 2 || matches!(*vendor, "unknown" | "pc" | "apple") =>
+            {
+                (*vendor, system)
+            }
+            system => ("unknown", system),
+        };
+        let os = match system.first().copied().unwrap_or("none") {
+            _ if rest[0] == "none" => "none",
+            "darwin" => "macos",
+            os if os.starts_with("wasi") => "wasi",
+            os => os,
+        };
+        let env = system.get(1).copied().unwrap_or("");
+        let (os, env) = match (os, env) {
+            ("linux", env) if env.starts_with("android") => ("android", ""),
+            (os, env) => (os, env),
+        };
+        let env = ["gnu", "musl", "msvc", "sgx", "uclibc", "ohos"]
+            .into_iter()
+            .find(|prefix| env.starts_with(prefix))
+            .unwrap_or("");
+
+        let arch = match arch {
+            "i386" | "i486" | "i586" | "i686" => "x86",
+            arch if arch.starts_with("armeb") => "arm",
+            arch if arch.starts_with("arm") || arch.starts_with("thumb") => "arm",
+            arch if arch.starts_with("riscv64") => "riscv64",
+            arch if arch.starts_with("riscv32") => "riscv32",
+            "powerpc64le" => "powerpc64",
+            "mipsel" => "mips",
+            "mips64el" => "mips64",
+            arch => arch,
+        };
+        let families = match os {
+            "windows" => vec!["windows"],
+            "wasi" | "unknown" if arch.starts_with("wasm") => vec!["wasm"],
+            "linux" | "android" | "macos" | "ios" | "tvos" | "watchos" | "visionos" | "freebsd"
+            | "netbsd" | "openbsd" | "dragonfly" | "solaris" | "illumos" | "haiku" | "aix"
+            | "hurd" | "redox" | "nto" | "espidf" => vec!["unix"],
+            _ => vec![],
+        };
+        let pointer_width = match arch {
+            "x86_64" | "aarch64" | "riscv64" | "powerpc64" | "s390x" | "mips64" | "sparc64"
+            | "loongarch64" | "wasm64" => 64,
+            "avr" | "msp430" => 16,
+            _ => 32,
+        };
+        let big_endian = matches!(
+            parts[0],
+            "s390x" | "powerpc" | "powerpc64" | "sparc64" | "mips" | "mips64"
+        ) || parts[0].starts_with("armeb");
+
+        Ok(Self {
+            arch: arch.to_string(),
+            os: os.to_string(),
+            families: families.into_iter().map(str::to_string).collect(),
+            env: env.to_string(),
+            vendor: vendor.to_string(),
+            pointer_width,
+            endian: if big_endian { "big" } else { "little" },
+        })
+    }
+
+    /// Whether a `[target.<key>]` table applies: a `cfg(...)` expression
+    /// or a triple
+    ///
+    /// Expressions depx can't evaluate, such as `target_feature`, are
+    /// taken to apply, so their dependencies are still checked.
+    pub fn matches(&self, key: &str) -> bool {
+        let key = key.trim();
+        let Some(expression) = key
+            .strip_prefix("cfg(")
+            .and_then(|rest| rest.strip_suffix(')'))
+        else {
+            return match Self::from_triple(key) {
+                Ok(target) => {
+                    target.arch == self.arch && target.os == self.os && target.env == self.env
+                }
+                Err(_) => true,
+            };
+        };
+
+        let tokens = tokenize(expression);
+        let mut position = 0;
+        let value = self.evaluate(&tokens, &mut position);
+        match value {
+            Some(value) if position == tokens.len() => value,
+            _ => true,
+        }
+    }
+
+    /// Evaluate the predicate at `tokens[*position]`, `None` when it's
+    /// unknown or malformed
+    fn evaluate(&self, tokens: &[Token], position: &mut usize) -> Option<bool> {
+        let Some(Token::Ident(name)) = tokens.get(*position) else {
+            return None;
+        };
+        *position += 1;
+
+        match (name.as_str(), tokens.get(*position)) {
+            ("all" | "any" | "not", Some(Token::Open)) => {
+                *position += 1;
+                let mut values = Vec::new();
+                while tokens.get(*position) != Some(&Token::Close) {
+                    values.push(self.evaluate(tokens, position));
+                    match tokens.get(*position) {
+                        Some(Token::Comma) => *position += 1,
+                        Some(Token::Close) => {}
+                        _ => return None,
+                    }
+                }
+                *position += 1;
+                match name.as_str() {
+                    "all" if values.contains(&Some(false)) => Some(false),
+                    "all" => values.iter().all(Option::is_some).then_some(true),
+                    "any" if values.contains(&Some(true)) => Some(true),
+                    "any" => values.iter().all(Option::is_some).then_some(false),
+                    _ => match values.as_slice() {
+                        [value] => value.map(|value| !value),
+                        _ => None,
+                    },
+                }
+            }
+            (_, Some(Token::Equals)) => {
+                let Some(Token::Str(value)) = tokens.get(*position + 1) else {
+                    return None;
+                };
+                *position += 2;
+                self.key_value(name, value)
+            }
+            ("unix" | "windows", _) => Some(self.families.iter().any(|family| family == name)),
+            _ => None,
+        }
+    }
+
+    fn key_value(&self, key: &str, value: &str) -> Option<bool> {
+        Some(match key {
+            "target_arch" => self.arch == value,
+            "target_os" => self.os == value,
+            "target_family" => self.families.iter().any(|family| family == value),
+            "target_env" => self.env == value,
+            "target_vendor" => self.vendor == value,
+            "target_pointer_width" => self.pointer_width.to_string() == value,
+            "target_endian" => self.endian == value,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Open,
+    Close,
+    Comma,
+    Equals,
+}
+
+fn tokenize(expression: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => tokens.push(Token::Open),
+            ')' => tokens.push(Token::Close),
+            ',' => tokens.push(Token::Comma),
+            '=' => tokens.push(Token::Equals),
+            '"' => tokens.push(Token::Str(
+                chars.by_ref().take_while(|c| *c != '"').collect(),
+            )),
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if !(next.is_alphanumeric() || next == '_') {
+                        break;
+                    }
+                    ident.push(next);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_triple() {
+        let windows = Target::from_triple("x86_64-pc-windows-msvc").unwrap();
+        assert_eq!(
+            (
+                windows.arch.as_str(),
+                windows.os.as_str(),
+                windows.env.as_str()
+            ),
+            ("x86_64", "windows", "msvc")
+        );
+        assert_eq!(windows.families, ["windows"]);
+
+        let mac = Target::from_triple("aarch64-apple-darwin").unwrap();
+        assert_eq!((mac.os.as_str(), mac.vendor.as_str()), ("macos", "apple"));
+        assert_eq!(mac.families, ["unix"]);
+
+        let android = Target::from_triple("armv7-linux-androideabi").unwrap();
+        assert_eq!(
+            (android.arch.as_str(), android.os.as_str()),
+            ("arm", "android")
+        );
+        assert_eq!(android.pointer_width, 32);
+
+        let wasm = Target::from_triple("wasm32-unknown-unknown").unwrap();
+        assert_eq!(
+            (wasm.os.as_str(), wasm.families.as_slice()),
+            ("unknown", &["wasm".to_string()][..])
+        );
+
+        let embedded = Target::from_triple("thumbv7em-none-eabihf").unwrap();
+        assert_eq!(
+            (embedded.arch.as_str(), embedded.os.as_str()),
+            ("arm", "none")
+        );
+        assert!(embedded.families.is_empty());
+
+        assert!(Target::from_triple("linux").is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let linux = Target::from_triple("x86_64-unknown-linux-gnu").unwrap();
+        let windows = Target::from_triple("x86_64-pc-windows-msvc").unwrap();
+
+        assert!(linux.matches("cfg(unix)"));
+        assert!(!windows.matches("cfg(unix)"));
+        assert!(windows.matches("cfg(windows)"));
+        assert!(linux.matches(r#"cfg(all(target_os = "linux", not(target_env = "musl")))"#));
+        assert!(!linux.matches(r#"cfg(any(target_os = "macos", target_os = "ios"))"#));
+        assert!(linux.matches(r#"cfg(target_pointer_width = "64")"#));
+        assert!(windows.matches("x86_64-pc-windows-msvc"));
+        assert!(!linux.matches("x86_64-pc-windows-msvc"));
+
+        // Unknown predicates apply, unless the rest decides it
+        assert!(linux.matches(r#"cfg(target_feature = "avx2")"#));
+        assert!(!windows.matches(r#"cfg(all(unix, target_feature = "avx2"))"#));
+        assert!(linux.matches("cfg(not(("));
+    }
+}
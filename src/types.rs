@@ -1,8 +1,10 @@
-use std::collections::{HashMap, HashSet};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 
+use crate::rules::Rule;
+
 /// Represents a package in the dependency tree
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Package {
@@ -18,11 +20,131 @@ pub struct Package {
     /// Whether this is a dev dependency
     pub is_dev: bool,
 
+    /// Only needed to build the project, through Cargo `[build-dependencies]`
+    #[serde(default)]
+    pub is_build: bool,
+
     /// Dependencies of this package
     pub dependencies: Vec<String>,
 
+    /// Declared requirement range for each dependency (e.g. "body-parser" -> "^1.20.0")
+    #[serde(default)]
+    pub dependency_ranges: HashMap<String, String>,
+
+    /// Peer dependencies the package expects its consumer to install, with
+    /// their ranges (e.g. "react" -> "^18.0.0" for react-dom)
+    #[serde(default)]
+    pub peer_dependencies: HashMap<String, String>,
+
+    /// Peers marked optional in `peerDependenciesMeta`: used when installed,
+    /// but fine to leave out
+    #[serde(default)]
+    pub optional_peers: Vec<String>,
+
     /// Whether the package is deprecated
     pub deprecated: Option<String>,
+
+    /// Executables the package installs (npm `bin` entries)
+    #[serde(default)]
+    pub bins: Vec<String>,
+
+    /// Whether the package is an optional dependency (npm `optionalDependencies`),
+    /// skipped by the package manager when it can't be installed
+    #[serde(default)]
+    pub is_optional: bool,
+
+    /// Operating systems the package supports (npm `os`), empty when unrestricted
+    #[serde(default)]
+    pub os: Vec<String>,
+
+    /// CPU architectures the package supports (npm `cpu`), empty when unrestricted
+    #[serde(default)]
+    pub cpu: Vec<String>,
+
+    /// C libraries the package supports on Linux (npm `libc`), empty when unrestricted
+    #[serde(default)]
+    pub libc: Vec<String>,
+
+    /// For a platform-specific build (e.g. "@esbuild/linux-x64"), the package
+    /// that pulls it in as an optional dependency ("esbuild")
+    #[serde(default)]
+    pub platform_parent: Option<String>,
+
+    /// Package whose tarball ships this one (npm `bundleDependencies`); it
+    /// isn't installed or removable on its own
+    #[serde(default)]
+    pub bundled_in: Option<String>,
+
+    /// For a Cargo dependency only declared in `[target.<cfg>]` tables that
+    /// don't apply to the target analyzed, the first such `<cfg>`
+    #[serde(default)]
+    pub other_target: Option<String>,
+
+    /// Where the package was installed from
+    #[serde(default)]
+    pub source: PackageSource,
+
+    /// Package URL, e.g. "pkg:npm/%40babel/core@7.24.0"; registry packages only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+
+    /// Best-effort CPE 2.3 name for matching NVD advisories
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpe: Option<String>,
+}
+
+/// Where an installed package comes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PackageSource {
+    /// A package registry
+    #[default]
+    Registry,
+    /// A git repository (`git+https://`, `github:user/repo`, ...)
+    Git,
+    /// A `file:` tarball or directory copied into node_modules
+    File,
+    /// A symlinked directory: `link:` dependencies and workspace members
+    Link,
+    /// The JSR registry (`jsr:@std/path`)
+    Jsr,
+    /// Modules imported by URL (`https://deno.land/x/oak@v12.6.1/mod.ts`)
+    Remote,
+}
+
+impl PackageSource {
+    /// Classify a lockfile reference: a `resolved` URL or a version spec
+    pub fn of(reference: &str) -> Self {
+        const GIT_PREFIXES: &[&str] = &["git+", "git:", "git@", "github:", "gitlab:", "bitbucket:"];
+
+        if reference.starts_with("jsr:") {
+            PackageSource::Jsr
+        } else if reference.starts_with("link:") {
+            PackageSource::Link
+        } else if reference.starts_with("file:") {
+            PackageSource::File
+        } else if GIT_PREFIXES
+            .iter()
+            .any(|prefix| reference.starts_with(prefix))
+        {
+            PackageSource::Git
+        } else {
+            PackageSource::Registry
+        }
+    }
+}
+
+impl std::fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageSource::Registry => write!(f, "registry"),
+            PackageSource::Git => write!(f, "git"),
+            PackageSource::File => write!(f, "file"),
+            PackageSource::Link => write!(f, "link"),
+            PackageSource::Jsr => write!(f, "jsr"),
+            PackageSource::Remote => write!(f, "remote"),
+        }
+    }
 }
 
 impl Package {
@@ -32,8 +154,23 @@ impl Package {
             version: version.into(),
             is_direct: false,
             is_dev: false,
+            is_build: false,
             dependencies: Vec::new(),
+            dependency_ranges: HashMap::new(),
+            peer_dependencies: HashMap::new(),
+            optional_peers: Vec::new(),
             deprecated: None,
+            bins: Vec::new(),
+            is_optional: false,
+            os: Vec::new(),
+            cpu: Vec::new(),
+            libc: Vec::new(),
+            platform_parent: None,
+            bundled_in: None,
+            other_target: None,
+            source: PackageSource::Registry,
+            purl: None,
+            cpe: None,
         }
     }
 
@@ -42,6 +179,7 @@ impl Package {
         self
     }
 
+    #[allow(dead_code)]
     pub fn dev(mut self) -> Self {
         self.is_dev = true;
         self
@@ -51,6 +189,49 @@ impl Package {
         self.dependencies = deps;
         self
     }
+
+    #[allow(dead_code)]
+    pub fn optional(mut self) -> Self {
+        self.is_optional = true;
+        self
+    }
+
+    /// Whether the package comes from a registry, so registry metadata
+    /// and advisories apply to it
+    pub fn is_from_registry(&self) -> bool {
+        self.source == PackageSource::Registry
+    }
+
+    /// Whether a registry publishes releases of the package: an npm-style
+    /// registry or JSR
+    pub fn has_registry_releases(&self) -> bool {
+        matches!(self.source, PackageSource::Registry | PackageSource::Jsr)
+    }
+
+    /// Version as shown in reports, naming the source unless it's a registry
+    ///
+    /// "4.17.21", "1.0.0 (git)", "(link)" for a package without a version
+    pub fn version_label(&self) -> String {
+        match (self.source, self.version.is_empty()) {
+            (PackageSource::Registry, _) => self.version.clone(),
+            (source, true) => format!("({})", source),
+            (source, false) => format!("{} ({})", self.version, source),
+        }
+    }
+
+    /// "name@version" as shown in reports, e.g. "ui@0.3.0 (link)"
+    pub fn label(&self) -> String {
+        if self.version.is_empty() && !self.is_from_registry() {
+            format!("{} ({})", self.name, self.source)
+        } else {
+            format!("{}@{}", self.name, self.version_label())
+        }
+    }
+
+    /// Whether the package's `os`/`cpu`/`libc` constraints allow the current machine
+    pub fn supports_platform(&self) -> bool {
+        crate::platform::supports(&self.os, &self.cpu, &self.libc)
+    }
 }
 
 /// Represents an import statement found in source code
@@ -80,8 +261,96 @@ pub enum ImportKind {
     CommonJs,
     /// Dynamic import()
     Dynamic,
+    /// `require.resolve()` or `import.meta.resolve()`, which find a
+    /// package's file without loading it
+    Resolve,
+    /// Test-runner module mock, such as `jest.mock()` or `vi.mock()`
+    Mock,
     /// Re-export (export ... from ...)
     ReExport,
+    /// Rust `use` item or path rooted at a crate
+    Use,
+    /// Rust derive or attribute macro, attributed to the proc-macro crate
+    /// defining it
+    Macro,
+    /// Crate used by code a build script generates, attributed to the
+    /// generator's reference in `build.rs`
+    Generated,
+    /// Python `import` or `from ... import` statement
+    Python,
+    /// Go import declaration
+    Go,
+    /// TypeScript type-only import or re-export, or any import of a
+    /// declaration file; erased when compiled
+    Type,
+    /// Package a tool's configuration names by string (`extends: ["airbnb"]`)
+    Config,
+    /// Stylesheet `@import`, `@use` or `@forward`
+    Style,
+}
+
+/// What a source file is for, which decides whether its imports ship
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileContext {
+    /// Application or library code
+    Source,
+    /// Tests, mocks and stories
+    Test,
+    /// Tool configuration (`vite.config.ts`, `.eslintrc.js`)
+    Config,
+    /// Build and maintenance scripts (`scripts/`, `gulpfile.js`)
+    Script,
+}
+
+impl std::fmt::Display for FileContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileContext::Source => write!(f, "source"),
+            FileContext::Test => write!(f, "test"),
+            FileContext::Config => write!(f, "config"),
+            FileContext::Script => write!(f, "script"),
+        }
+    }
+}
+
+/// An import of another project file through a relative specifier
+#[derive(Debug, Clone)]
+pub struct LocalImport {
+    /// Line number in the importing file
+    pub line: usize,
+
+    /// The specifier as written (e.g. "./utils", "../lib/cli.js")
+    pub specifier: String,
+
+    /// The file the specifier resolves to, with `.`/`..` collapsed so each
+    /// file has a single key; `None` when nothing on disk matches
+    pub target: Option<PathBuf>,
+}
+
+/// An `import()` or `require()` whose specifier is built at runtime and
+/// can't be tied to a package, so whatever it loads goes uncounted
+#[derive(Debug, Clone)]
+pub struct DynamicImport {
+    pub file_path: PathBuf,
+    pub line: usize,
+
+    /// The specifier expression as written, shortened (e.g. "`${plugin}/register`")
+    pub expression: String,
+}
+
+/// A command-line tool invoked from source code or package.json scripts
+/// (e.g. `execa('eslint', ...)`, `"lint": "prettier --check ."`)
+#[derive(Debug, Clone)]
+pub struct CommandInvocation {
+    /// The file containing the invocation
+    pub file_path: PathBuf,
+
+    /// Line number in the file
+    pub line: usize,
+
+    /// The binary being run (e.g. "eslint", "tsc")
+    pub binary: String,
 }
 
 /// Collection of all imports found in a project
@@ -93,8 +362,20 @@ pub struct ImportMap {
     /// All external package imports (excluding relative imports)
     package_imports: HashMap<String, Vec<Import>>,
 
-    /// Number of files analyzed
-    files_count: usize,
+    /// Command-line tools invoked programmatically or from scripts
+    commands: Vec<CommandInvocation>,
+
+    /// Dynamic imports no package could be read from
+    dynamic_imports: Vec<DynamicImport>,
+
+    /// Relative imports of other project files, indexed by importing file
+    local_imports: HashMap<PathBuf, Vec<LocalImport>>,
+
+    /// Every file analyzed
+    files: HashSet<PathBuf>,
+
+    /// What each analyzed JS/TS file is for
+    contexts: HashMap<PathBuf, FileContext>,
 }
 
 impl ImportMap {
@@ -119,8 +400,68 @@ impl ImportMap {
             .push(import);
     }
 
-    pub fn mark_file_analyzed(&mut self) {
-        self.files_count += 1;
+    pub fn add_command(&mut self, command: CommandInvocation) {
+        self.commands.push(command);
+    }
+
+    pub fn commands(&self) -> &[CommandInvocation] {
+        &self.commands
+    }
+
+    pub fn add_dynamic_import(&mut self, import: DynamicImport) {
+        self.dynamic_imports.push(import);
+    }
+
+    /// Dynamic imports no package could be read from, by file and line
+    pub fn unresolved_dynamic_imports(&self) -> Vec<&DynamicImport> {
+        let mut imports: Vec<&DynamicImport> = self.dynamic_imports.iter().collect();
+        imports.sort_by(|a, b| a.file_path.cmp(&b.file_path).then(a.line.cmp(&b.line)));
+        imports
+    }
+
+    pub fn add_local_import(&mut self, from: PathBuf, import: LocalImport) {
+        self.local_imports.entry(from).or_default().push(import);
+    }
+
+    /// Relative imports made by a file
+    pub fn local_imports_of(&self, file: &Path) -> &[LocalImport] {
+        self.local_imports
+            .get(file)
+            .map(|imports| imports.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Relative imports that don't resolve to any file, with the importing file
+    pub fn unresolved_local_imports(&self) -> Vec<(&PathBuf, &LocalImport)> {
+        let mut unresolved: Vec<(&PathBuf, &LocalImport)> = self
+            .local_imports
+            .iter()
+            .flat_map(|(from, imports)| imports.iter().map(move |i| (from, i)))
+            .filter(|(_, import)| import.target.is_none())
+            .collect();
+        unresolved.sort_by(|a, b| a.0.cmp(b.0).then(a.1.line.cmp(&b.1.line)));
+        unresolved
+    }
+
+    pub fn mark_file_analyzed(&mut self, path: PathBuf) {
+        self.files.insert(path);
+    }
+
+    pub fn set_file_context(&mut self, path: PathBuf, context: FileContext) {
+        self.contexts.insert(path, context);
+    }
+
+    /// What a file is for; `None` for files only other ecosystems' scanners read
+    pub fn file_context(&self, path: &Path) -> Option<FileContext> {
+        self.contexts.get(path).copied()
+    }
+
+    pub fn is_file_analyzed(&self, path: &Path) -> bool {
+        self.files.contains(path)
+    }
+
+    pub fn analyzed_files(&self) -> impl Iterator<Item = &PathBuf> {
+        self.files.iter()
     }
 
     pub fn total_imports(&self) -> usize {
@@ -128,7 +469,7 @@ impl ImportMap {
     }
 
     pub fn files_analyzed(&self) -> usize {
-        self.files_count
+        self.files.len()
     }
 
     pub fn packages_used(&self) -> HashSet<String> {
@@ -142,6 +483,69 @@ impl ImportMap {
     pub fn imports_by_file(&self) -> &HashMap<PathBuf, Vec<Import>> {
         &self.imports_by_file
     }
+
+    /// Every project file reachable from the given files through relative imports
+    pub fn reachable_files(&self, entries: &[PathBuf]) -> HashSet<PathBuf> {
+        let mut reachable = HashSet::new();
+        let mut stack: Vec<&PathBuf> = entries.iter().collect();
+
+        while let Some(file) = stack.pop() {
+            if !reachable.insert(file.clone()) {
+                continue;
+            }
+            if let Some(imports) = self.local_imports.get(file) {
+                stack.extend(imports.iter().filter_map(|i| i.target.as_ref()));
+            }
+        }
+
+        reachable
+    }
+
+    /// Packages imported by any of the given files
+    pub fn packages_imported_by(&self, files: &HashSet<PathBuf>) -> HashSet<String> {
+        files
+            .iter()
+            .filter_map(|file| self.imports_by_file.get(file))
+            .flatten()
+            .filter_map(|import| import.resolved_package.clone())
+            .collect()
+    }
+
+    /// Packages whose every usage is a dynamic `import()`, with those call sites
+    pub fn dynamic_only_packages(&self) -> Vec<(&str, &[Import])> {
+        let mut packages: Vec<(&str, &[Import])> = self
+            .package_imports
+            .iter()
+            .filter(|(_, usages)| usages.iter().all(|i| i.kind == ImportKind::Dynamic))
+            .map(|(name, usages)| (name.as_str(), usages.as_slice()))
+            .collect();
+        packages.sort_by(|a, b| a.0.cmp(b.0));
+        packages
+    }
+
+    /// Add the imports another scanner found, e.g. the Rust crates of a
+    /// project that also has JavaScript
+    pub fn merge(&mut self, other: ImportMap) {
+        for (file, imports) in other.imports_by_file {
+            self.imports_by_file
+                .entry(file)
+                .or_default()
+                .extend(imports);
+        }
+        for (package, imports) in other.package_imports {
+            self.package_imports
+                .entry(package)
+                .or_default()
+                .extend(imports);
+        }
+        self.commands.extend(other.commands);
+        self.dynamic_imports.extend(other.dynamic_imports);
+        for (file, imports) in other.local_imports {
+            self.local_imports.entry(file).or_default().extend(imports);
+        }
+        self.files.extend(other.files);
+        self.contexts.extend(other.contexts);
+    }
 }
 
 /// Result of analyzing dependency usage
@@ -165,17 +569,169 @@ pub struct UsageAnalysis {
 
     /// Direct dependencies that are expected unused (dev/build tools)
     pub expected_unused_direct: Vec<Package>,
+
+    /// Groups of direct dependencies that serve the same purpose
+    pub overlapping: Vec<CategoryOverlap>,
+
+    /// Packages imported in source code but not declared in package.json
+    pub phantom: Vec<PhantomDependency>,
+
+    /// Direct dependencies declared in the wrong section for where
+    /// they're imported
+    pub misplaced: Vec<MisplacedDependency>,
+
+    /// Runtime dependencies shipped code only imports as types
+    pub type_only: Vec<TypeOnlyDependency>,
+
+    /// For each unused direct dependency, how many transitive packages
+    /// would go away with it (nothing else depends on them)
+    pub removable_transitive: HashMap<String, usize>,
+
+    /// Optional packages built for another OS/CPU; never installed here,
+    /// so not being imported says nothing about them
+    pub other_platform: Vec<Package>,
+}
+
+impl UsageAnalysis {
+    /// Add another ecosystem's findings, keeping the lists in name order
+    pub fn merge(&mut self, other: UsageAnalysis) {
+        self.used.extend(other.used);
+        self.unused.extend(other.unused);
+        self.expected_unused.extend(other.expected_unused);
+        self.dev_only.extend(other.dev_only);
+        self.unused_direct.extend(other.unused_direct);
+        self.expected_unused_direct
+            .extend(other.expected_unused_direct);
+        self.overlapping.extend(other.overlapping);
+        self.phantom.extend(other.phantom);
+        self.misplaced.extend(other.misplaced);
+        self.type_only.extend(other.type_only);
+        self.removable_transitive.extend(other.removable_transitive);
+        self.other_platform.extend(other.other_platform);
+
+        self.used
+            .sort_by(|a, b| a.package.name.cmp(&b.package.name));
+        for packages in [
+            &mut self.unused,
+            &mut self.unused_direct,
+            &mut self.expected_unused,
+            &mut self.expected_unused_direct,
+            &mut self.other_platform,
+        ] {
+            packages.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        self.phantom.sort_by(|a, b| a.name.cmp(&b.name));
+        self.misplaced
+            .sort_by(|a, b| a.package.name.cmp(&b.package.name));
+        self.type_only
+            .sort_by(|a, b| a.package.name.cmp(&b.package.name));
+    }
+}
+
+/// Several direct dependencies from the same category (e.g. HTTP clients)
+#[derive(Debug, Clone)]
+pub struct CategoryOverlap {
+    pub category: String,
+    pub packages: Vec<String>,
+}
+
+/// A package imported without being declared as a direct dependency
+///
+/// It only resolves because something else pulled it into node_modules,
+/// and breaks as soon as that dependency drops it.
+#[derive(Debug, Clone)]
+pub struct PhantomDependency {
+    pub name: String,
+
+    /// Version in the lockfile, if it's installed transitively
+    pub installed_version: Option<String>,
+
+    /// First import of the package
+    pub file_path: PathBuf,
+    pub line: usize,
+
+    /// Every import of the package, by file and line
+    pub imports: Vec<Import>,
+}
+
+/// A direct dependency whose section doesn't match where it's imported
+///
+/// A runtime dependency only tests, config files and scripts import
+/// belongs in devDependencies; a dev dependency that shipped code imports
+/// is missing from production installs (`npm install --omit=dev`).
+#[derive(Debug, Clone)]
+pub struct MisplacedDependency {
+    pub package: Package,
+
+    /// The imports that decide it: every import of a runtime dependency,
+    /// or the shipped ones of a dev dependency
+    pub imports: Vec<Import>,
+
+    /// Context of each import, in the same order
+    pub contexts: Vec<FileContext>,
+}
+
+impl MisplacedDependency {
+    /// Whether it should move to devDependencies, rather than out of them
+    pub fn should_be_dev(&self) -> bool {
+        !self.package.is_dev
+    }
+}
+
+/// A runtime dependency whose every import is a type import
+///
+/// TypeScript erases those, so nothing loads it at runtime. It still has
+/// to ship if the package publishes `.d.ts` files that reference it.
+#[derive(Debug, Clone)]
+pub struct TypeOnlyDependency {
+    pub package: Package,
+    pub imports: Vec<Import>,
 }
 
 #[derive(Debug)]
 pub struct PackageUsage {
     pub package: Package,
+    #[allow(dead_code)]
     pub import_count: usize,
+    #[allow(dead_code)]
     pub files: Vec<PathBuf>,
 }
 
+/// Findings of one project in a `depx analyze --recursive` run
+#[derive(Debug, Clone)]
+pub struct ProjectSummary {
+    /// Directory relative to where the run started
+    pub path: PathBuf,
+
+    /// Unused direct dependencies
+    pub unused: usize,
+
+    /// Imported packages no manifest declares
+    pub phantom: usize,
+
+    /// Why the project couldn't be analyzed
+    pub error: Option<String>,
+
+    /// CODEOWNERS owners of the project's directory, from the CODEOWNERS
+    /// at the root `--recursive` searched
+    pub owners: Vec<String>,
+}
+
+/// Unused and phantom findings attributed to one directory or code owner
+#[derive(Debug, Clone)]
+pub struct FindingGroup {
+    /// Directory relative to the project root, or the owners from CODEOWNERS
+    pub name: String,
+
+    /// Unused direct dependencies declared by a manifest in this group
+    pub unused: Vec<String>,
+
+    /// First import of each phantom dependency within this group
+    pub phantom: Vec<Import>,
+}
+
 /// Explanation of why a package is in the dependency tree
-#[derive(Debug)]
+#[derive(Debug, Serialize)]
 pub struct PackageExplanation {
     /// The package being explained
     pub package: Package,
@@ -184,8 +740,87 @@ pub struct PackageExplanation {
     /// Each chain is a list of package names
     pub dependency_chains: Vec<Vec<String>>,
 
+    /// Requirement each link was declared with by the previous link in the
+    /// chain (parallel to `dependency_chains`; `None` for the first link or
+    /// when the lockfile doesn't record ranges)
+    pub chain_ranges: Vec<Vec<Option<String>>>,
+
     /// Whether any chain starts from a dev dependency
     pub is_dev_path: bool,
+
+    /// Installed packages declaring this one as a peer dependency
+    pub peer_of: Vec<PeerDependent>,
+}
+
+/// An installed package that lists another as a peer dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeerDependent {
+    /// Package declaring the peer dependency (e.g. "react-dom")
+    pub package: String,
+
+    /// Range it accepts (e.g. "^18.0.0")
+    pub range: String,
+}
+
+/// How `depx analyze` classifies an installed package
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageClass {
+    /// Imported, invoked, or required by something that is
+    Used,
+    /// Not imported, but a build tool or type package that never is
+    ExpectedUnused,
+    /// Transitive dependency of dev dependencies only
+    DevOnly,
+    /// Never imported and nothing used needs it
+    Unused,
+    /// Optional build for another OS/CPU
+    OtherPlatform,
+    /// Platform build or bundled copy, reported with its parent
+    FollowsParent,
+}
+
+/// Built-in rule that makes an unimported package expected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpectedUnusedRule {
+    /// `@types/*` type definitions
+    TypeDefinitions,
+    /// Listed by name as a build, lint, test or release tool
+    KnownTool,
+    /// Name starts with a tooling prefix (e.g. "eslint-plugin-")
+    Prefix(&'static str),
+    /// Declared in `optionalDependencies`, or only pulled in through them
+    Optional,
+}
+
+/// What decided a package's classification
+#[derive(Debug, Clone)]
+pub enum ClassificationReason {
+    /// Imported in source code at these sites
+    Imported(Vec<Import>),
+    /// Backs a binary run from source code or package.json scripts
+    Invoked(Vec<CommandInvocation>),
+    /// Needed by a used package, through this chain (used package first)
+    RequiredBy(Vec<String>),
+    /// Matched a built-in expected-unused rule
+    Expected(ExpectedUnusedRule),
+    /// Only dev dependencies pull it in
+    DevTransitive,
+    /// Nothing imported, invoked or required it
+    NotReferenced,
+    /// `os`/`cpu`/`libc` exclude the current machine
+    Platform,
+    /// Platform build or bundled copy of this package
+    Parent(String),
+}
+
+/// Why `depx analyze` put a package in the group it did
+#[derive(Debug, Clone)]
+pub struct Classification {
+    pub package: Package,
+    pub class: UsageClass,
+    pub reason: ClassificationReason,
+    /// The rule that would report it, when depx.toml turns it off
+    pub suppressed_by: Option<Rule>,
 }
 
 /// A known vulnerability
@@ -217,6 +852,19 @@ pub struct Vulnerability {
 
     /// The installed version that is vulnerable
     pub installed_version: String,
+
+    /// Package URL of the vulnerable package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+
+    /// Best-effort CPE 2.3 name of the vulnerable package
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cpe: Option<String>,
+
+    /// Who patches it: the package's `[owners]` rule, else the CODEOWNERS
+    /// of the manifest its ecosystem declares dependencies in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -245,6 +893,91 @@ pub struct DeprecatedPackage {
     pub package: Package,
     pub message: String,
     pub is_used: bool,
+
+    /// The name the package moved to, if it was renamed
+    pub successor: Option<String>,
+}
+
+// ============================================================================
+// Graph Export Types
+// ============================================================================
+
+/// The dependency graph as `depx graph` prints it and `depx serve` draws it
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GraphExport {
+    /// Installed packages, sorted by id
+    pub nodes: Vec<GraphNode>,
+
+    /// Dependant -> dependency links between them
+    pub edges: Vec<GraphEdge>,
+}
+
+/// An installed package in a `GraphExport`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// Lockfile key of the package (e.g. "serde@1.0.200" for Cargo)
+    pub id: String,
+
+    pub name: String,
+
+    pub version: String,
+
+    /// Declared in the manifest
+    pub direct: bool,
+
+    /// Declared as a dev dependency
+    pub dev: bool,
+
+    /// Other versions of the package are installed too
+    pub duplicate: bool,
+
+    /// Advisories against the installed version, when the graph was audited
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vulnerabilities: Vec<GraphAdvisory>,
+}
+
+/// An advisory attached to a `GraphNode`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphAdvisory {
+    pub id: String,
+    pub severity: Severity,
+}
+
+/// A dependency of one `GraphNode` on another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub from: String,
+    pub to: String,
+}
+
+// ============================================================================
+// Metrics Types
+// ============================================================================
+
+/// Dependency health counts that `depx metrics` exposes
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyMetrics {
+    /// The package.json name or directory name, used as the `project` label
+    pub project: String,
+
+    /// Installed packages
+    pub packages: usize,
+
+    /// Of them, those declared in the manifest
+    pub direct: usize,
+
+    /// Direct dependencies nothing imports or runs
+    pub unused: usize,
+
+    /// Packages imported without being declared
+    pub phantom: usize,
+
+    /// Package names installed in more than one version
+    pub duplicates: usize,
+
+    /// Known vulnerabilities by severity, every severity included; `None`
+    /// when some advisories couldn't be fetched
+    pub vulnerabilities: Option<BTreeMap<Severity, usize>>,
 }
 
 // ============================================================================
@@ -260,8 +993,26 @@ pub struct DuplicateGroup {
     /// All versions found in the lockfile
     pub versions: Vec<DuplicateVersion>,
 
-    /// Severity level based on version differences
+    /// Severity level derived from `score`
     pub severity: DuplicateSeverity,
+
+    /// DEPX100 for a singleton, DEPX101 across semver-incompatible
+    /// releases, DEPX102 within one
+    pub rule: Rule,
+
+    /// How the severity was scored
+    #[serde(default)]
+    pub score: SeverityScore,
+
+    /// What breaks at runtime when copies of a package that must be a
+    /// singleton don't share state; such groups are critical
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub singleton: Option<String>,
+
+    /// Who untangles the copies: the package's `[owners]` rule, else
+    /// the CODEOWNERS of the root manifest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
 }
 
 /// A specific version of a duplicated crate
@@ -275,18 +1026,48 @@ pub struct DuplicateVersion {
 
     /// Number of transitive dependents
     pub transitive_count: usize,
+
+    /// Range each dependent declares on the package, where the lockfile or
+    /// manifests record it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub requirements: BTreeMap<String, String>,
 }
 
-/// Severity of the duplicate based on version differences
+/// Severity of the duplicate, from the points of its [`SeverityScore`]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum DuplicateSeverity {
-    /// Same major version, different minor/patch (usually fine)
+    /// Under 3 points, e.g. a minor or patch spread (usually fine)
     Low,
-    /// Different major versions (potential issues)
+    /// 3 to 5 points, e.g. two major versions (potential issues)
     Medium,
-    /// 3+ different major versions (likely problematic)
+    /// 6 points or more, e.g. three major versions, or two of a crate in
+    /// the public API (likely problematic)
     High,
+    /// Several copies of a package that must be a singleton, like react or
+    /// graphql (broken at runtime)
+    Critical,
+}
+
+/// Points behind a duplicate's severity, one entry per factor considered
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SeverityScore {
+    /// Sum of the factors' points
+    pub points: u32,
+
+    pub factors: Vec<ScoreFactor>,
+}
+
+/// A single input to a [`SeverityScore`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScoreFactor {
+    /// "major_versions", "versions", "public_api", "dependents" or "size"
+    pub factor: String,
+
+    /// What was measured, e.g. "2 major versions (1, 2)"
+    pub detail: String,
+
+    pub points: u32,
 }
 
 impl std::fmt::Display for DuplicateSeverity {
@@ -295,6 +1076,7 @@ impl std::fmt::Display for DuplicateSeverity {
             DuplicateSeverity::Low => write!(f, "low"),
             DuplicateSeverity::Medium => write!(f, "medium"),
             DuplicateSeverity::High => write!(f, "high"),
+            DuplicateSeverity::Critical => write!(f, "critical"),
         }
     }
 }
@@ -307,6 +1089,79 @@ pub struct DuplicateAnalysis {
 
     /// Summary statistics
     pub stats: DuplicateStats,
+
+    /// `@types/*` packages whose major version doesn't match the runtime package
+    #[serde(default)]
+    pub types_skew: Vec<TypesSkew>,
+
+    /// Versions installed several times for different peer dependencies (pnpm)
+    #[serde(default)]
+    pub peer_splits: Vec<PeerSplit>,
+
+    /// Duplicate groups sharing a scope or name prefix (e.g. `@babel/*`)
+    #[serde(default)]
+    pub clusters: Vec<DuplicateCluster>,
+}
+
+/// Duplicate groups from one toolchain, summarized on a single line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    /// The shared pattern, e.g. "@babel/*" or "windows-*"
+    pub name: String,
+
+    /// Names of the duplicated packages in the cluster
+    pub packages: Vec<String>,
+
+    /// Highest severity among the packages
+    pub severity: DuplicateSeverity,
+
+    /// Installed versions across all packages
+    pub version_count: usize,
+
+    /// Sum of the packages' transitive counts
+    pub transitive_count: usize,
+}
+
+/// One version of a package installed once per peer-dependency combination
+///
+/// pnpm resolves peers per dependent, so `foo@1.0.0(react@17)` and
+/// `foo@1.0.0(react@18)` are separate copies of the same release.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerSplit {
+    pub name: String,
+    pub version: String,
+
+    /// Each peer combination and who pulls it in
+    pub variants: Vec<PeerVariant>,
+
+    /// Peers resolved to different versions across the variants; aligning
+    /// these collapses the copies
+    pub differing_peers: Vec<String>,
+}
+
+/// A single peer combination of a [`PeerSplit`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerVariant {
+    /// Resolved peers (e.g. "react@18.2.0")
+    pub peers: Vec<String>,
+
+    /// Packages (or "root") depending on this copy
+    pub dependents: Vec<String>,
+}
+
+/// An `@types/foo` release written for a different major version of `foo`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypesSkew {
+    /// The runtime package (e.g. "react")
+    pub package: String,
+    pub package_version: String,
+
+    /// The type definitions package (e.g. "@types/react")
+    pub types_package: String,
+    pub types_version: String,
+
+    /// Range of @types releases matching the runtime version (e.g. "^18")
+    pub suggested_range: String,
 }
 
 /// Statistics about duplicates
@@ -315,6 +1170,10 @@ pub struct DuplicateStats {
     /// Total number of crates with duplicates
     pub total_duplicates: usize,
 
+    /// Number of singleton packages installed more than once
+    #[serde(default)]
+    pub critical_severity: usize,
+
     /// Number of high severity duplicates
     pub high_severity: usize,
 
@@ -327,3 +1186,1568 @@ pub struct DuplicateStats {
     /// Estimated additional compile units
     pub extra_compile_units: usize,
 }
+
+// ============================================================================
+// Override Audit Types
+// ============================================================================
+
+/// Where a forced version or patch is declared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverrideSource {
+    /// package.json `overrides` (npm)
+    NpmOverrides,
+    /// package.json `resolutions` (yarn)
+    YarnResolutions,
+    /// package.json `pnpm.overrides`
+    PnpmOverrides,
+    /// package.json `pnpm.patchedDependencies`
+    PnpmPatch,
+    /// Cargo.toml `[patch.<registry>]`
+    CargoPatch,
+    /// Cargo.toml `[replace]`
+    CargoReplace,
+}
+
+impl std::fmt::Display for OverrideSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverrideSource::NpmOverrides => write!(f, "overrides"),
+            OverrideSource::YarnResolutions => write!(f, "resolutions"),
+            OverrideSource::PnpmOverrides => write!(f, "pnpm.overrides"),
+            OverrideSource::PnpmPatch => write!(f, "pnpm.patchedDependencies"),
+            OverrideSource::CargoPatch => write!(f, "[patch]"),
+            OverrideSource::CargoReplace => write!(f, "[replace]"),
+        }
+    }
+}
+
+/// Whether an override still has an effect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverrideStatus {
+    /// The override forces something the dependents wouldn't pick on their own
+    Active,
+    /// Every dependent already accepts the forced version; the override can go
+    Stale,
+    /// Nothing in the lockfile matches the override
+    Unused,
+}
+
+impl std::fmt::Display for OverrideStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OverrideStatus::Active => write!(f, "active"),
+            OverrideStatus::Stale => write!(f, "stale"),
+            OverrideStatus::Unused => write!(f, "unused"),
+        }
+    }
+}
+
+/// A single override, resolution, or patch entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideEntry {
+    /// Where the entry is declared
+    pub source: OverrideSource,
+
+    /// The selector as written in the manifest (e.g. "foo@^1", "parent > foo", "**/foo")
+    pub selector: String,
+
+    /// The package being overridden
+    pub package: String,
+
+    /// Only applies below this parent package (nested npm overrides, pnpm "a>b")
+    pub parent: Option<String>,
+
+    /// Forced version, range, source, or patch file
+    pub target: String,
+
+    /// Versions of the package currently in the lockfile
+    pub installed_versions: Vec<String>,
+
+    /// Whether the override still does anything
+    pub status: OverrideStatus,
+
+    /// Dependents whose own range excludes the installed version, e.g.
+    /// "debug@2.6.9 (^2.0.0)": the resolutions the override changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub overridden: Vec<String>,
+
+    /// Advisories affecting the forced (installed) versions
+    pub vulnerabilities: Vec<String>,
+}
+
+/// Result of auditing overrides and patches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OverrideAnalysis {
+    /// All entries found across manifests
+    pub entries: Vec<OverrideEntry>,
+}
+
+// ============================================================================
+// Health Report Types
+// ============================================================================
+
+/// Maintenance and popularity signals for a project's dependencies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    /// One entry per direct dependency
+    pub direct: Vec<PackageHealth>,
+
+    /// Transitive packages with very few downloads (only when requested)
+    pub obscure_transitive: Vec<ObscurePackage>,
+}
+
+/// A rarely downloaded package pulled in transitively
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObscurePackage {
+    pub name: String,
+    pub version: String,
+
+    /// Package URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+
+    /// Downloads over the last week
+    pub weekly_downloads: u64,
+
+    /// Packages that depend on it
+    pub dependents: Vec<String>,
+}
+
+/// Maintenance signals for a single direct dependency
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageHealth {
+    /// Package name
+    pub name: String,
+
+    /// Installed version
+    pub version: String,
+
+    /// Package URL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+
+    /// Whether this is a dev dependency
+    pub is_dev: bool,
+
+    /// Latest version on the registry
+    pub latest_version: Option<String>,
+
+    /// Date of the most recent release (YYYY-MM-DD)
+    pub last_publish: Option<String>,
+
+    /// Days since the most recent release
+    pub days_since_publish: Option<i64>,
+
+    /// Number of releases in the last 365 days
+    pub releases_last_year: usize,
+
+    /// Average number of days between releases over the package's lifetime
+    pub release_interval_days: Option<i64>,
+
+    /// Known advisories affecting the installed version
+    pub open_advisories: usize,
+
+    /// No release in a long time
+    pub is_abandoned: bool,
+
+    /// Accounts that can publish the package
+    pub maintainers: Vec<String>,
+
+    /// Only one account can publish the package
+    pub single_maintainer: bool,
+
+    /// A new account recently started maintaining or publishing the package
+    pub maintainer_change: Option<String>,
+
+    /// Downloads over the last week
+    pub weekly_downloads: Option<u64>,
+
+    /// Downloaded rarely enough to double-check the name (typosquats, forks)
+    pub is_obscure: bool,
+}
+
+// ============================================================================
+// Project Binary Types
+// ============================================================================
+
+/// A `bin` entry exposed by the project's own package.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BinEntry {
+    /// The command name users run
+    pub name: String,
+
+    /// Path to the implementation, as written in package.json
+    pub path: String,
+
+    /// Whether the file exists and is covered by import analysis
+    pub status: BinStatus,
+
+    /// Packages the implementation imports without declaring them as dependencies
+    pub undeclared_imports: Vec<UndeclaredImport>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BinStatus {
+    /// The file exists and `depx analyze` sees its imports
+    Ok,
+    /// The referenced file doesn't exist
+    Missing,
+    /// The file exists but import analysis skips it (build output, no JS extension)
+    NotAnalyzed,
+}
+
+/// A package imported by a bin's implementation but missing from `dependencies`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndeclaredImport {
+    pub package: String,
+
+    /// File and line of the import
+    pub file: PathBuf,
+    pub line: usize,
+
+    /// Declared only in devDependencies, which consumers of the package don't install
+    pub dev_only: bool,
+}
+
+// ============================================================================
+// Entry Point Types
+// ============================================================================
+
+/// Runtime dependencies reachable from one published entry point
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPointUsage {
+    /// Display name ("main", "exports ./cli", "bin tool", or the configured path)
+    pub name: String,
+
+    /// Files the entry point resolves to
+    pub files: Vec<PathBuf>,
+
+    /// Whether every file was covered by import analysis (build output isn't)
+    pub analyzed: bool,
+
+    /// Direct runtime dependencies imported from files reachable from this entry point
+    pub packages: Vec<String>,
+}
+
+/// A dependency only some entry points need
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialDependency {
+    pub package: String,
+    pub entry_points: Vec<String>,
+}
+
+/// Per-entry-point view of runtime dependency usage
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryPointAnalysis {
+    pub entry_points: Vec<EntryPointUsage>,
+
+    /// Dependencies needed by some entry points but not all
+    pub partial: Vec<PartialDependency>,
+
+    /// Runtime dependencies no entry point reaches
+    pub unreachable: Vec<String>,
+}
+
+// ============================================================================
+// Dead Code Types
+// ============================================================================
+
+/// Source files no entry point reaches through imports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadCodeReport {
+    /// Files reachability started from
+    pub entry_points: Vec<PathBuf>,
+
+    /// Analyzed files that are never imported, directly or transitively
+    pub unreachable: Vec<PathBuf>,
+
+    /// Files only test files import
+    pub test_only: Vec<PathBuf>,
+
+    /// Relative imports that don't resolve to any file
+    pub unresolved: Vec<UnresolvedImport>,
+
+    /// Number of files analyzed
+    pub total_files: usize,
+}
+
+/// A relative import whose specifier matches no file on disk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UnresolvedImport {
+    /// The importing file
+    pub file: PathBuf,
+
+    /// Line number of the import
+    pub line: usize,
+
+    /// The specifier as written
+    pub specifier: String,
+}
+
+// ============================================================================
+// Unused Export Types
+// ============================================================================
+
+/// Names one of the package's entry points exports that no consumer imports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntryExports {
+    /// The entry point, e.g. "main" or "exports ./utils"
+    pub entry_point: String,
+
+    /// What consumers import it as, e.g. "my-lib/utils"
+    pub specifier: String,
+
+    pub file: PathBuf,
+
+    /// Every name it exports, re-exports included
+    pub exports: Vec<String>,
+
+    /// Exported names nothing imports
+    pub unused: Vec<String>,
+
+    /// Whether any consumer imports the entry point at all
+    pub imported: bool,
+}
+
+/// Result of `depx unused-exports`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnusedExportsReport {
+    pub entry_points: Vec<EntryExports>,
+
+    /// Files whose imports were counted: tests, examples, and with
+    /// `--workspace` the other workspace packages
+    pub consumers: usize,
+}
+
+// ============================================================================
+// Module Coupling Types
+// ============================================================================
+
+/// Import coupling of a single source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleCoupling {
+    pub file: PathBuf,
+
+    /// Number of project files importing this one
+    pub fan_in: usize,
+
+    /// Number of project files this one imports
+    pub fan_out: usize,
+
+    /// fan_out / (fan_in + fan_out): 0 is depended on, 1 only depends on others
+    pub instability: f64,
+
+    /// Imports too much of the project, or is both heavily imported and importing
+    pub is_god_module: bool,
+}
+
+/// Coupling metrics for every analyzed source file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CouplingReport {
+    /// Modules sorted by total coupling, highest first
+    pub modules: Vec<ModuleCoupling>,
+
+    /// Groups of files that import each other in a cycle
+    pub clusters: Vec<Vec<PathBuf>>,
+}
+
+// ============================================================================
+// Type Declaration Types
+// ============================================================================
+
+/// Where a dependency's TypeScript declarations come from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypesSource {
+    /// The package ships its own declarations
+    Bundled,
+    /// A matching `@types/*` package is installed
+    DefinitelyTyped,
+    /// The project declares the module itself (`declare module "foo"`)
+    Vendored,
+    /// No declarations found; imports are implicitly `any`
+    Missing,
+    /// The package isn't in node_modules, so it couldn't be checked
+    Unknown,
+}
+
+/// Type declaration status of a dependency imported from TypeScript
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageTypes {
+    pub name: String,
+    pub version: String,
+    pub source: TypesSource,
+
+    /// The `@types` package providing declarations, or the one to install
+    pub types_package: String,
+
+    /// Project file declaring the module, for vendored declarations
+    pub declaration: Option<PathBuf>,
+}
+
+// ============================================================================
+// Audit Cache Types
+// ============================================================================
+
+/// The last `depx audit` result, saved under the project's `.depx` directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditCache {
+    /// Cache format version
+    pub version: u32,
+
+    /// SHA-256 of the audited lockfiles
+    pub lockfile_hash: String,
+
+    /// Day the advisories were fetched from OSV (YYYY-MM-DD)
+    pub advisory_date: String,
+
+    /// For a `--used-only` audit, the packages the source used, which
+    /// decide `affects_used_code`
+    pub used_packages: Option<Vec<String>>,
+
+    /// Everything found, advisories ignored in depx.toml included
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+/// What `depx audit --since-last` reports
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditChanges {
+    /// Advisory date of the previous audit, `None` on the first run
+    pub since: Option<String>,
+
+    /// Vulnerabilities the previous audit didn't report
+    pub new: Vec<Vulnerability>,
+
+    /// Vulnerabilities the previous audit reported that are gone
+    pub resolved: Vec<Vulnerability>,
+}
+
+// ============================================================================
+// Snapshot Types
+// ============================================================================
+
+/// Findings of one `depx analyze` run, saved with `--save`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnalysisSnapshot {
+    /// Snapshot format version
+    pub version: u32,
+
+    /// Unused direct dependencies
+    pub unused: Vec<String>,
+
+    /// Imported but undeclared packages
+    pub phantom: Vec<String>,
+
+    /// Owners of the unused and phantom packages that have any, by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub owners: BTreeMap<String, Vec<String>>,
+}
+
+/// Findings that changed between a saved snapshot and the current run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub new_unused: Vec<String>,
+    pub resolved_unused: Vec<String>,
+    pub new_phantom: Vec<String>,
+    pub resolved_phantom: Vec<String>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.new_unused.is_empty()
+            && self.resolved_unused.is_empty()
+            && self.new_phantom.is_empty()
+            && self.resolved_phantom.is_empty()
+    }
+}
+
+// ============================================================================
+// SBOM Types
+// ============================================================================
+
+/// Format of an SBOM given to `depx diff --sbom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SbomFormat {
+    /// CycloneDX JSON (`bomFormat: "CycloneDX"`)
+    CycloneDx,
+    /// SPDX JSON (`spdxVersion`)
+    Spdx,
+}
+
+impl std::fmt::Display for SbomFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SbomFormat::CycloneDx => write!(f, "CycloneDX"),
+            SbomFormat::Spdx => write!(f, "SPDX"),
+        }
+    }
+}
+
+/// A package listed in an SBOM, or installed according to the lockfile
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct SbomComponent {
+    pub name: String,
+    pub version: String,
+
+    /// Package URL, set on the components a diff reports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub purl: Option<String>,
+}
+
+/// A package the SBOM and the lockfile both list, at different versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionDrift {
+    pub name: String,
+    pub sbom_versions: Vec<String>,
+    pub lockfile_versions: Vec<String>,
+}
+
+/// How an SBOM differs from the packages the lockfile installs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SbomDiff {
+    pub format: SbomFormat,
+
+    /// Components compared from the SBOM
+    pub sbom_components: usize,
+
+    /// Packages compared from the lockfile
+    pub lockfile_packages: usize,
+
+    /// Installed but missing from the SBOM
+    pub added: Vec<SbomComponent>,
+
+    /// In the SBOM but no longer installed
+    pub removed: Vec<SbomComponent>,
+
+    /// Listed on both sides with different versions
+    pub changed: Vec<VersionDrift>,
+}
+
+impl SbomDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+// ============================================================================
+// Fix Plan Types
+// ============================================================================
+
+/// Changes proposed by analyze/audit/duplicates, applied with `depx fix --plan`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixPlan {
+    /// Plan format version
+    pub version: u32,
+
+    pub actions: Vec<FixAction>,
+}
+
+/// A single package.json change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "kebab-case")]
+pub enum FixAction {
+    /// Remove a direct dependency
+    Uninstall { package: String, reason: String },
+
+    /// Raise a direct dependency's declared version
+    Upgrade {
+        package: String,
+        from: String,
+        to: String,
+        reason: String,
+    },
+
+    /// Force a transitive package to a version (npm overrides, yarn
+    /// resolutions, pnpm.overrides)
+    Override {
+        package: String,
+        version: String,
+        reason: String,
+    },
+
+    /// Move a dependency between package.json sections
+    Move {
+        package: String,
+        from: String,
+        to: String,
+        reason: String,
+    },
+
+    /// Rewrite a declared range in another pinning style
+    Restyle {
+        package: String,
+        section: String,
+        from: String,
+        to: String,
+        reason: String,
+    },
+
+    /// Rewrite a declared range to the one other workspace members use
+    Align {
+        package: String,
+        section: String,
+        from: String,
+        to: String,
+        reason: String,
+    },
+}
+
+impl FixAction {
+    pub fn package(&self) -> &str {
+        match self {
+            FixAction::Uninstall { package, .. }
+            | FixAction::Upgrade { package, .. }
+            | FixAction::Override { package, .. }
+            | FixAction::Move { package, .. }
+            | FixAction::Restyle { package, .. }
+            | FixAction::Align { package, .. } => package,
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        match self {
+            FixAction::Uninstall { reason, .. }
+            | FixAction::Upgrade { reason, .. }
+            | FixAction::Override { reason, .. }
+            | FixAction::Move { reason, .. }
+            | FixAction::Restyle { reason, .. }
+            | FixAction::Align { reason, .. } => reason,
+        }
+    }
+}
+
+impl std::fmt::Display for FixAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FixAction::Uninstall { package, .. } => write!(f, "uninstall {}", package),
+            FixAction::Upgrade {
+                package, from, to, ..
+            } => write!(f, "upgrade {} {} -> {}", package, from, to),
+            FixAction::Override {
+                package, version, ..
+            } => write!(f, "override {} to {}", package, version),
+            FixAction::Move {
+                package, from, to, ..
+            } => write!(f, "move {} from {} to {}", package, from, to),
+            FixAction::Restyle {
+                package, from, to, ..
+            } => write!(f, "restyle {} {} -> {}", package, from, to),
+            FixAction::Align {
+                package, from, to, ..
+            } => write!(f, "align {} {} -> {}", package, from, to),
+        }
+    }
+}
+
+/// What `depx fix` changed, and what it couldn't
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FixOutcome {
+    pub applied: Vec<FixAction>,
+    pub skipped: Vec<SkippedFix>,
+
+    /// package.json before and after the fixes
+    #[serde(skip)]
+    pub original: String,
+    #[serde(skip)]
+    pub updated: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedFix {
+    pub action: FixAction,
+    pub reason: String,
+}
+
+/// An imported package to replace with its successor under a new name
+#[derive(Debug, Clone)]
+pub struct PackageRename {
+    pub from: String,
+    pub to: String,
+
+    /// The deprecation message pointing at the successor, if any
+    pub reason: String,
+}
+
+/// A source file whose import specifiers `depx fix --rename` rewrote
+#[derive(Debug, Clone)]
+pub struct RenamedFile {
+    pub path: PathBuf,
+
+    /// How many specifiers were replaced
+    pub rewritten: usize,
+
+    /// The file before and after the rewrite
+    pub original: String,
+    pub updated: String,
+}
+
+/// An unused direct dependency offered for removal by `depx clean`
+#[derive(Debug, Clone)]
+pub struct CleanCandidate {
+    pub package: Package,
+
+    /// Transitive packages that go away with it
+    pub removes: usize,
+
+    /// Bytes under node_modules/<name>, when installed
+    pub disk_size: Option<u64>,
+
+    /// Installed packages that depend on it, so it stays installed after removal
+    pub required_by: Vec<String>,
+
+    /// Last commit touching an import of it, if the project is a git repo
+    pub last_usage: Option<LastUsage>,
+}
+
+/// A commit from `git log`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LastUsage {
+    pub commit: String,
+    /// YYYY-MM-DD
+    pub date: String,
+    pub subject: String,
+}
+
+// ============================================================================
+// Lockfile Integrity Types
+// ============================================================================
+
+/// Result of `depx check`'s lockfile hygiene checks
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// Number of downloaded lockfile entries checked
+    pub entries_checked: usize,
+
+    pub issues: Vec<IntegrityIssue>,
+
+    /// With `--verify-tarballs`: tarballs found in the npm cache and hashed
+    pub tarballs_verified: usize,
+
+    /// With `--verify-tarballs`: entries whose tarball isn't in the npm cache
+    pub tarballs_not_cached: usize,
+
+    /// With `--max-critical-age`: critical vulnerabilities and how long they've been open
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub critical_vulnerabilities: Vec<TrackedVulnerability>,
+
+    /// Findings of the framework rule packs `lint` enables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lint: Vec<LintFinding>,
+}
+
+/// A vulnerability with the age `--history` recorded for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedVulnerability {
+    #[serde(flatten)]
+    pub vulnerability: Vulnerability,
+
+    pub first_seen: Option<FirstSeen>,
+
+    /// Open longer than `--max-critical-age` allows
+    pub past_sla: bool,
+}
+
+/// A lockfile entry that fails a hygiene check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityIssue {
+    pub package: String,
+    pub version: String,
+
+    /// Install path in the lockfile
+    pub path: String,
+
+    pub kind: IntegrityIssueKind,
+
+    /// The offending URL or hash
+    pub detail: String,
+
+    /// Who re-locks it: the package's `[owners]` rule, else the CODEOWNERS
+    /// of the package.json whose node_modules the entry is installed in
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+
+    /// With `--history`: when the issue was first reported
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub first_seen: Option<FirstSeen>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum IntegrityIssueKind {
+    /// The cached tarball doesn't hash to the recorded integrity
+    HashMismatch,
+    /// Fetched over plain http://
+    InsecureUrl,
+    /// No integrity hash, so a changed tarball goes unnoticed
+    MissingIntegrity,
+    /// Only a SHA-1 hash, which is no longer collision resistant
+    WeakIntegrity,
+    /// Resolved from a host other than the default or .npmrc registries
+    UnexpectedRegistry,
+    /// Resolved from outside the `allowed-registries` policy in depx.toml
+    DisallowedRegistry,
+    /// node_modules holds another version than the lockfile, or none
+    StaleInstall,
+}
+
+impl IntegrityIssueKind {
+    /// Whether the issue points at tampering rather than weak hygiene
+    pub fn is_severe(&self) -> bool {
+        matches!(
+            self,
+            IntegrityIssueKind::HashMismatch
+                | IntegrityIssueKind::InsecureUrl
+                | IntegrityIssueKind::MissingIntegrity
+                | IntegrityIssueKind::DisallowedRegistry
+        )
+    }
+}
+
+impl std::fmt::Display for IntegrityIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntegrityIssueKind::HashMismatch => write!(f, "hash mismatch"),
+            IntegrityIssueKind::InsecureUrl => write!(f, "insecure url"),
+            IntegrityIssueKind::MissingIntegrity => write!(f, "missing integrity"),
+            IntegrityIssueKind::WeakIntegrity => write!(f, "weak integrity"),
+            IntegrityIssueKind::UnexpectedRegistry => write!(f, "unexpected registry"),
+            IntegrityIssueKind::DisallowedRegistry => write!(f, "disallowed registry"),
+            IntegrityIssueKind::StaleInstall => write!(f, "stale install"),
+        }
+    }
+}
+
+// ============================================================================
+// Prepublish Types
+// ============================================================================
+
+/// A publishable package.json and what would break for its consumers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishCheck {
+    /// Package name from the manifest
+    pub name: String,
+
+    /// Directory containing the package.json
+    pub dir: PathBuf,
+
+    pub issues: Vec<PublishIssue>,
+
+    /// Who publishes it: the package's `[owners]` rule, else the
+    /// CODEOWNERS of its package.json
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishIssue {
+    pub kind: PublishIssueKind,
+
+    /// Manifest field with the problem (e.g. "dependencies")
+    pub field: String,
+
+    /// Dependency or entry the problem is about
+    pub subject: String,
+
+    /// The offending value, as written
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PublishIssueKind {
+    /// A `workspace:` range; only pnpm and yarn rewrite these on publish
+    WorkspaceProtocol,
+    /// A `file:`, `link:` or `portal:` range pointing at the author's disk
+    LocalPath,
+    /// An entry point (`main`, `exports`, `bin`, `types`) whose file doesn't exist
+    MissingEntry,
+    /// A file an entry point needs that the `files` field leaves out of the tarball
+    NotPublished,
+}
+
+// ============================================================================
+// Notification Types
+// ============================================================================
+
+/// What the previous `depx check --notify` run saw, saved with `--state`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotifyState {
+    /// State format version
+    pub version: u32,
+
+    /// Installed packages
+    pub packages: usize,
+
+    /// Vulnerabilities as "id package@version"
+    pub vulnerabilities: Vec<String>,
+
+    /// Deprecated packages as "package@version"
+    pub deprecated: Vec<String>,
+}
+
+/// Findings posted to the webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct NotifySummary {
+    pub project: String,
+
+    /// Installed packages
+    pub packages: usize,
+
+    /// Change in installed packages since the previous run, if there was one
+    pub package_delta: Option<i64>,
+
+    pub new_vulnerabilities: Vec<Vulnerability>,
+
+    pub new_deprecated: Vec<DeprecationNotice>,
+
+    /// Lockfile integrity issues found by this run
+    pub integrity_issues: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DeprecationNotice {
+    pub package: String,
+    pub version: String,
+    pub message: String,
+}
+
+// ============================================================================
+// Finding History Types
+// ============================================================================
+
+/// When each finding was first reported, saved with `depx check --history`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FindingHistory {
+    /// History format version
+    pub version: u32,
+
+    /// Finding fingerprint ("category/key") -> first-seen date (YYYY-MM-DD)
+    pub first_seen: BTreeMap<String, String>,
+}
+
+/// When a finding was first reported
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FirstSeen {
+    /// YYYY-MM-DD
+    pub date: String,
+
+    pub days_open: i64,
+}
+
+// ============================================================================
+// Update Types
+// ============================================================================
+
+/// A direct dependency with a newer release on the registry
+#[derive(Debug, Clone)]
+pub struct UpdateCandidate {
+    pub package: String,
+    pub current: String,
+    pub latest: String,
+
+    /// The update crosses a major version (a minor one below 1.0.0)
+    pub breaking: bool,
+
+    /// Advisories against the current version that the latest one fixes
+    pub fixes: Vec<String>,
+
+    pub is_dev: bool,
+
+    /// What the releases in between say, with `depx update --changelog`
+    pub notes: Option<ReleaseNotes>,
+
+    /// How the crate's public API changed, with `depx update --api-diff`
+    pub api: Option<ApiImpact>,
+}
+
+/// Release notes between the installed and the latest version of a package
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseNotes {
+    /// Where they were read: the repository's releases page or changelog file
+    pub source: String,
+
+    /// The releases covered, oldest first
+    pub versions: Vec<String>,
+
+    /// Breaking changes the notes call out
+    pub breaking: Vec<BreakingChange>,
+
+    /// Whether the releases ran past the pages read before reaching the
+    /// installed version, leaving the oldest ones out
+    pub truncated: bool,
+}
+
+/// A breaking change called out in a release's notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakingChange {
+    pub version: String,
+
+    /// The note's first line, without markdown and trailing references
+    pub summary: String,
+}
+
+/// Public items of a crate that an update removes or changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiImpact {
+    /// Items the workspace uses come first
+    pub changes: Vec<ApiChange>,
+}
+
+impl ApiImpact {
+    /// Changes to items the workspace uses
+    pub fn used(&self) -> impl Iterator<Item = &ApiChange> {
+        self.changes.iter().filter(|change| change.used)
+    }
+}
+
+/// A public item of a crate that an update removes or changes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApiChange {
+    /// The item's name, methods after their type (`Client::get`)
+    pub item: String,
+    pub kind: ApiChangeKind,
+
+    /// The workspace names the item, or the type it belongs to
+    pub used: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApiChangeKind {
+    Removed,
+    /// A different signature, or another kind of item under the same name
+    Changed,
+}
+
+impl std::fmt::Display for ApiChangeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ApiChangeKind::Removed => write!(f, "removed"),
+            ApiChangeKind::Changed => write!(f, "changed"),
+        }
+    }
+}
+
+// ============================================================================
+// Vendored Package Types
+// ============================================================================
+
+/// A copy of a published package checked into the project's source tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VendoredPackage {
+    pub name: String,
+    pub version: String,
+
+    /// The manifest naming it: "package.json" or "Cargo.toml"
+    pub manifest: String,
+
+    /// Directory of the copy, relative to the project root
+    pub path: PathBuf,
+}
+
+// ============================================================================
+// Peer Dependency Types
+// ============================================================================
+
+/// A peer dependency that isn't installed at a version its dependent accepts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerIssue {
+    /// The package declaring the peer, or "package.json" for the project's own
+    pub package: String,
+    pub version: String,
+
+    pub peer: String,
+
+    /// The range the dependent accepts
+    pub range: String,
+
+    /// The peer's installed version, if any
+    pub installed: Option<String>,
+
+    /// Marked optional in `peerDependenciesMeta`
+    pub optional: bool,
+
+    pub kind: PeerIssueKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PeerIssueKind {
+    /// Not installed at all
+    Missing,
+    /// Installed at a version outside the range
+    Mismatch,
+}
+
+// ============================================================================
+// Engine Types
+// ============================================================================
+
+/// A runtime or package manager version the project declares
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeclaredEngine {
+    /// "node", or a package manager: "npm", "pnpm", "yarn", "bun"
+    pub engine: String,
+
+    /// A range (`engines`) or an exact version (.nvmrc, `packageManager`)
+    pub constraint: String,
+
+    /// Where it's declared, e.g. "engines.node", ".nvmrc", "packageManager"
+    pub source: String,
+}
+
+/// A package whose `engines` range rejects a runtime the project allows
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EngineConflict {
+    /// The installed package, or "package.json" when the project's own
+    /// declarations disagree
+    pub package: String,
+    pub version: String,
+
+    pub engine: String,
+
+    /// The range the package's `engines` field accepts
+    pub required: String,
+
+    /// The project's declaration it conflicts with
+    pub declared: DeclaredEngine,
+
+    /// The oldest version the declaration allows, which `required` rejects
+    pub oldest: String,
+}
+
+/// `packageManager` naming a different tool than the lockfile belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManagerMismatch {
+    pub package_manager: String,
+    pub lockfile: String,
+}
+
+/// Result of `depx engines`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnginesReport {
+    pub declared: Vec<DeclaredEngine>,
+    pub conflicts: Vec<EngineConflict>,
+    pub manager_mismatch: Option<ManagerMismatch>,
+
+    /// Installed packages whose package.json was read
+    pub checked: usize,
+}
+
+// ============================================================================
+// License Types
+// ============================================================================
+
+/// Result of `depx licenses`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LicenseReport {
+    pub packages: Vec<PackageLicense>,
+}
+
+/// The license an installed package is published under
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PackageLicense {
+    pub name: String,
+    pub version: String,
+
+    /// SPDX identifier or expression, `None` when unknown
+    pub license: Option<String>,
+    pub source: LicenseSource,
+
+    /// The license files the license was recognized from
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files: Vec<String>,
+}
+
+/// Where a package's license was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LicenseSource {
+    /// The package.json `license` field
+    Manifest,
+    /// The text of a LICENSE, LICENCE or COPYING file, as the manifest
+    /// has no license or says "UNLICENSED"
+    LicenseFile,
+    /// Neither: no field and no recognizable license file
+    Unknown,
+    /// No installed copy to read
+    NotInstalled,
+}
+
+// ============================================================================
+// Bundle Types
+// ============================================================================
+
+/// Format of the bundler report given to `depx bundle --stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BundleFormat {
+    /// webpack-bundle-analyzer's JSON report (`analyzerMode: "json"`)
+    WebpackBundleAnalyzer,
+    /// An esbuild metafile (`metafile: true`)
+    Esbuild,
+    /// A Vite manifest (`build.manifest`), which lists chunks, not modules
+    Vite,
+}
+
+impl std::fmt::Display for BundleFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BundleFormat::WebpackBundleAnalyzer => write!(f, "webpack-bundle-analyzer"),
+            BundleFormat::Esbuild => write!(f, "esbuild metafile"),
+            BundleFormat::Vite => write!(f, "Vite manifest"),
+        }
+    }
+}
+
+/// Bytes one package emitted into the bundle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledPackage {
+    pub name: String,
+    pub bytes: u64,
+}
+
+/// The bundle bytes a direct dependency is responsible for
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleImpact {
+    pub name: String,
+    pub version: String,
+
+    /// Bytes of the dependency's own modules
+    pub own_bytes: u64,
+
+    /// Bundled packages only this dependency pulls in
+    pub transitive: Vec<BundledPackage>,
+}
+
+impl BundleImpact {
+    /// Bytes removing the dependency would take out of the bundle
+    pub fn total_bytes(&self) -> u64 {
+        self.own_bytes + self.transitive.iter().map(|p| p.bytes).sum::<u64>()
+    }
+}
+
+/// A bundled package several direct dependencies pull in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedBundledPackage {
+    pub name: String,
+    pub bytes: u64,
+    pub dependants: Vec<String>,
+}
+
+/// Result of `depx bundle`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleReport {
+    pub format: BundleFormat,
+
+    /// Bytes of every module the report lists
+    pub total_bytes: u64,
+
+    /// Bytes of the project's own modules
+    pub first_party_bytes: u64,
+
+    /// Bytes of chunks that name no source module (Vite's shared chunks)
+    pub unknown_bytes: u64,
+
+    /// Largest first
+    pub dependencies: Vec<BundleImpact>,
+
+    pub shared: Vec<SharedBundledPackage>,
+
+    /// Bundled packages the lockfile doesn't lead to from any direct
+    /// dependency
+    pub unattributed: Vec<BundledPackage>,
+}
+
+// ============================================================================
+// Coverage Types
+// ============================================================================
+
+/// Format of the coverage report given to `depx coverage --report`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoverageFormat {
+    /// lcov tracefile (`lcov.info`)
+    Lcov,
+    /// istanbul/nyc JSON (`coverage-final.json`)
+    Istanbul,
+}
+
+impl std::fmt::Display for CoverageFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CoverageFormat::Lcov => write!(f, "lcov"),
+            CoverageFormat::Istanbul => write!(f, "istanbul"),
+        }
+    }
+}
+
+/// One import of a dependency and whether the tests ran it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoveredImport {
+    pub file_path: PathBuf,
+    pub line: usize,
+    pub covered: bool,
+}
+
+/// A production dependency and the imports of it the report covers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyCoverage {
+    pub name: String,
+    pub version: String,
+    pub imports: Vec<CoveredImport>,
+}
+
+impl DependencyCoverage {
+    pub fn is_covered(&self) -> bool {
+        self.imports.iter().any(|i| i.covered)
+    }
+}
+
+/// Result of `depx coverage`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub format: CoverageFormat,
+
+    /// Source files the report has data for
+    pub files: usize,
+
+    /// Dependencies some tested code imports
+    pub covered: Vec<DependencyCoverage>,
+
+    /// Dependencies only imported from code the tests never ran
+    pub uncovered: Vec<DependencyCoverage>,
+
+    /// Dependencies imported only from files the report doesn't include
+    pub untracked: Vec<String>,
+}
+
+// ============================================================================
+// Lint Types
+// ============================================================================
+
+/// A framework's rules `depx check` can lint the dependencies with
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, clap::ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum RulePack {
+    React,
+    Nextjs,
+    Nestjs,
+    Express,
+}
+
+impl std::fmt::Display for RulePack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RulePack::React => write!(f, "React"),
+            RulePack::Nextjs => write!(f, "Next.js"),
+            RulePack::Nestjs => write!(f, "NestJS"),
+            RulePack::Express => write!(f, "Express"),
+        }
+    }
+}
+
+/// A file and line a lint finding points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LintSite {
+    pub file_path: PathBuf,
+    pub line: usize,
+}
+
+/// A known framework anti-pattern in the dependencies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LintFinding {
+    pub pack: RulePack,
+
+    /// e.g. "react/multiple-copies"
+    pub rule: String,
+
+    pub severity: Severity,
+
+    /// The packages involved
+    pub packages: Vec<String>,
+
+    pub message: String,
+
+    /// Imports that trigger the rule, for rules about source code
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sites: Vec<LintSite>,
+}
+
+// ============================================================================
+// Pinning Types
+// ============================================================================
+
+/// How a declared range pins its version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RangeStyle {
+    /// "1.2.3"
+    Exact,
+    /// "^1.2.3"
+    Caret,
+    /// "~1.2.3"
+    Tilde,
+    /// "*", "latest", or an empty range: whatever was published last
+    Any,
+    /// Anything else, e.g. ">=1.2 <2" or "1.x || 2.x"
+    Complex,
+}
+
+impl std::fmt::Display for RangeStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RangeStyle::Exact => write!(f, "exact"),
+            RangeStyle::Caret => write!(f, "caret (^)"),
+            RangeStyle::Tilde => write!(f, "tilde (~)"),
+            RangeStyle::Any => write!(f, "any (*)"),
+            RangeStyle::Complex => write!(f, "complex"),
+        }
+    }
+}
+
+/// A declared range that doesn't follow the project's pinning style
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PinningDeviation {
+    /// package.json declaring it, relative to the project root
+    pub manifest: PathBuf,
+
+    /// e.g. "devDependencies"
+    pub section: String,
+
+    pub package: String,
+    pub range: String,
+    pub style: RangeStyle,
+
+    /// The range rewritten in the project's style, when the version to
+    /// pin is known
+    pub suggested: Option<String>,
+}
+
+/// Range styles used across the project's manifests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinningReport {
+    /// Manifests looked at, relative to the project root
+    pub manifests: Vec<PathBuf>,
+
+    /// How many ranges use each style
+    pub styles: BTreeMap<RangeStyle, usize>,
+
+    /// The style every range should use
+    pub policy: RangeStyle,
+
+    /// Whether the policy comes from depx.toml rather than being the most
+    /// common style
+    pub configured: bool,
+
+    pub deviations: Vec<PinningDeviation>,
+}
+
+// ============================================================================
+// Alignment Types
+// ============================================================================
+
+/// Which range wins when workspace members declare several
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlignPreference {
+    /// The range with the highest lower bound, so nobody downgrades
+    #[default]
+    Highest,
+    Lowest,
+}
+
+impl std::fmt::Display for AlignPreference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlignPreference::Highest => write!(f, "highest"),
+            AlignPreference::Lowest => write!(f, "lowest"),
+        }
+    }
+}
+
+/// Where the range a dependency is aligned to comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AlignSource {
+    /// The declared range `[align] prefer` picks
+    Declared,
+
+    /// `[align.pin]` in depx.toml
+    Pin,
+
+    /// A range yarn constraints enforce
+    Constraint,
+
+    /// A pnpm catalog entry, declared as `catalog:`
+    Catalog,
+}
+
+/// One declaration of an external dependency
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeclaredRange {
+    /// package.json declaring it, relative to the project root
+    pub manifest: PathBuf,
+
+    /// e.g. "devDependencies"
+    pub section: String,
+
+    /// As written, e.g. "^18.2.0" or "catalog:"
+    pub range: String,
+
+    /// The catalog range a `catalog:` spec stands for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+}
+
+impl DeclaredRange {
+    /// The range this declaration installs from
+    pub fn effective(&self) -> &str {
+        self.resolved.as_deref().unwrap_or(&self.range)
+    }
+}
+
+/// An external dependency workspace members declare with different ranges
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionMismatch {
+    pub package: String,
+
+    /// The range every declaration should use
+    pub target: String,
+
+    pub source: AlignSource,
+
+    /// Every declaration, including those already on `target`
+    pub declarations: Vec<DeclaredRange>,
+}
+
+/// Dependency ranges compared across a workspace's manifests
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlignmentReport {
+    /// Manifests looked at, relative to the project root
+    pub manifests: Vec<PathBuf>,
+
+    pub prefer: AlignPreference,
+    pub mismatches: Vec<VersionMismatch>,
+
+    /// Dependencies several pnpm workspace members declare that no catalog
+    /// manages yet
+    pub catalog_candidates: Vec<CatalogCandidate>,
+}
+
+/// A dependency worth moving into the pnpm catalog
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CatalogCandidate {
+    pub package: String,
+
+    /// The range the catalog entry would have, once aligned
+    pub range: String,
+
+    /// Manifests declaring it
+    pub manifests: usize,
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::extract_package_name;
+use crate::types::{ImportMap, Package, PackageTypes, TypesSource};
+
+/// Checks whether dependencies imported from TypeScript have type declarations
+pub struct TypesAnalyzer<'a> {
+    root: &'a Path,
+}
+
+impl<'a> TypesAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn analyze(
+        &self,
+        imports: &ImportMap,
+        packages: &HashMap<String, Package>,
+    ) -> Vec<PackageTypes> {
+        let vendored = vendored_declarations(imports);
+
+        let mut names: Vec<&String> = imports
+            .imports_by_file()
+            .iter()
+            .filter(|(file, _)| is_typescript_file(file))
+            .flat_map(|(_, file_imports)| file_imports)
+            .filter_map(|import| import.resolved_package.as_ref())
+            .filter(|name| !name.starts_with("@types/"))
+            .collect();
+        names.sort();
+        names.dedup();
+
+        let mut results: Vec<PackageTypes> = names
+            .into_iter()
+            .filter_map(|name| packages.get(name))
+            .map(|pkg| {
+                let types_package = types_package_name(&pkg.name);
+                let declaration = vendored.get(&pkg.name).cloned();
+
+                let source = if self.has_bundled_types(&pkg.name) {
+                    TypesSource::Bundled
+                } else if packages.contains_key(&types_package)
+                    || self.package_dir(&types_package).is_dir()
+                {
+                    TypesSource::DefinitelyTyped
+                } else if declaration.is_some() {
+                    TypesSource::Vendored
+                } else if !self.package_dir(&pkg.name).is_dir() {
+                    TypesSource::Unknown
+                } else {
+                    TypesSource::Missing
+                };
+
+                PackageTypes {
+                    name: pkg.name.clone(),
+                    version: pkg.version.clone(),
+                    source,
+                    types_package,
+                    declaration: if source == TypesSource::Vendored {
+                        declaration
+                    } else {
+                        None
+                    },
+                }
+            })
+            .collect();
+
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        results
+    }
+
+    fn package_dir(&self, name: &str) -> PathBuf {
+        self.root.join("node_modules").join(name)
+    }
+
+    /// `types`/`typings` fields, a `types` export condition, or a
+    /// declaration file next to the entry point
+    fn has_bundled_types(&self, name: &str) -> bool {
+        let dir = self.package_dir(name);
+        let Ok(content) = std::fs::read_to_string(dir.join("package.json")) else {
+            return false;
+        };
+        let Ok(manifest) = serde_json::from_str::<JsonValue>(&content) else {
+            return false;
+        };
+
+        if ["types", "typings", "typesVersions"]
+            .iter()
+            .any(|field| manifest.get(field).is_some())
+        {
+            return true;
+        }
+
+        if manifest.get("exports").is_some_and(has_types_condition) {
+            return true;
+        }
+
+        let main = manifest
+            .get("main")
+            .and_then(|v| v.as_str())
+            .unwrap_or("index.js");
+        let main_stem = main
+            .trim_end_matches(".js")
+            .trim_end_matches(".cjs")
+            .trim_end_matches(".mjs");
+        dir.join(format!("{}.d.ts", main_stem)).is_file() || dir.join("index.d.ts").is_file()
+    }
+}
+
+/// "lodash" -> "@types/lodash", "@babel/core" -> "@types/babel__core"
+pub fn types_package_name(name: &str) -> String {
+    match name.strip_prefix('@').and_then(|n| n.split_once('/')) {
+        Some((scope, pkg)) => format!("@types/{}__{}", scope, pkg),
+        None => format!("@types/{}", name),
+    }
+}
+
+fn is_typescript_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some("ts" | "tsx" | "mts" | "cts")
+    )
+}
+
+fn has_types_condition(exports: &JsonValue) -> bool {
+    match exports {
+        JsonValue::Object(map) => map
+            .iter()
+            .any(|(key, value)| key == "types" || has_types_condition(value)),
+        JsonValue::Array(items) => items.iter().any(has_types_condition),
+        _ => false,
+    }
+}
+
+/// Packages the project declares itself in `.d.ts` files (`declare module "foo"`)
+fn vendored_declarations(imports: &ImportMap) -> HashMap<String, PathBuf> {
+    let mut declared = HashMap::new();
+
+    for file in imports.analyzed_files() {
+        if !file.to_string_lossy().ends_with(".d.ts") {
+            continue;
+        }
+        let Ok(source) = std::fs::read_to_string(file) else {
+            continue;
+        };
+        for module in declared_modules(&source) {
+            if let Some(package) = extract_package_name(&module) {
+                declared.entry(package).or_insert_with(|| file.clone());
+            }
+        }
+    }
+
+    declared
+}
+
+/// Module names from `declare module "name"` statements
+fn declared_modules(source: &str) -> Vec<String> {
+    source
+        .split("declare module")
+        .skip(1)
+        .filter_map(|rest| {
+            let rest = rest.trim_start();
+            let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+            let name = rest[1..].split(quote).next()?;
+            // `declare module "*.svg"` covers file types, not packages
+            (!name.starts_with('*')).then(|| name.to_string())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_types_package_name() {
+        assert_eq!(types_package_name("lodash"), "@types/lodash");
+        assert_eq!(types_package_name("@babel/core"), "@types/babel__core");
+    }
+
+    #[test]
+    fn test_declared_modules() {
+        let source = r#"
+declare module "legacy-lib";
+declare module 'other-lib/sub' {
+    export const x: number;
+}
+declare module "*.svg";
+"#;
+        assert_eq!(
+            declared_modules(source),
+            vec!["legacy-lib", "other-lib/sub"]
+        );
+    }
+}
@@ -0,0 +1,306 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+
+use crate::analyzer::exports::ModuleSymbols;
+use crate::analyzer::{extract_package_name, is_test_file, resolve, ImportAnalyzer};
+use crate::entry_points::EntryPointAnalyzer;
+use crate::types::{EntryExports, ImportMap, UnusedExportsReport};
+use crate::workspace;
+
+/// Entry points a library publishes; `bin` scripts export nothing
+const LIBRARY_ENTRY_POINTS: &[&str] = &["main", "exports"];
+
+/// Directories whose files use the package the way its users would
+const CONSUMER_DIRS: &[&str] = &[
+    "test",
+    "tests",
+    "__tests__",
+    "example",
+    "examples",
+    "demo",
+    "bench",
+    "benchmarks",
+];
+
+/// Build output directories entry points often point into, mapped back to
+/// the sources they're built from
+const BUILD_DIRS: &[&str] = &["dist", "build", "lib", "out"];
+
+/// Finds names a library's entry points export that nothing imports
+///
+/// Consumers are the project's tests and examples, and with `workspace`
+/// every other package of the enclosing workspace. They count when they
+/// import an entry point by the package's name (`my-lib`, `my-lib/utils`)
+/// or by a relative path to its file. `import * as`, `require()` and
+/// `import()` take a whole entry point, so it's fully used. Entry points
+/// in build output (`dist/index.js`) are read from the matching `src` file.
+pub struct UnusedExportsAnalyzer<'a> {
+    root: &'a Path,
+    workspace: bool,
+}
+
+/// How an entry point is used
+#[derive(Default)]
+struct Usage {
+    imported: bool,
+    all: bool,
+    names: HashSet<String>,
+}
+
+impl<'a> UnusedExportsAnalyzer<'a> {
+    pub fn new(root: &'a Path, workspace: bool) -> Self {
+        Self { root, workspace }
+    }
+
+    pub fn analyze(&self, imports: &ImportMap) -> Result<UnusedExportsReport> {
+        let manifest_path = self.root.join("package.json");
+        let content = std::fs::read_to_string(&manifest_path)
+            .into_diagnostic()
+            .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+        let manifest: JsonValue = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package.json")?;
+        let package = manifest
+            .get("name")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        let specs: Vec<String> = LIBRARY_ENTRY_POINTS.iter().map(|s| s.to_string()).collect();
+        let mut entries: Vec<(String, String, PathBuf)> = Vec::new();
+        for (name, files) in EntryPointAnalyzer::new(self.root, &specs).resolve()? {
+            let specifier = match name.strip_prefix("exports ") {
+                Some(".") | None => package.to_string(),
+                Some(subpath) => format!("{}/{}", package, subpath.trim_start_matches("./")),
+            };
+            for file in files {
+                let Some(file) = self.source_file(&file, imports) else {
+                    continue;
+                };
+                if !entries.iter().any(|(_, _, f)| *f == file) {
+                    entries.push((name.clone(), specifier.clone(), file));
+                }
+            }
+        }
+
+        let mut consumers: Vec<PathBuf> = imports
+            .analyzed_files()
+            .filter(|f| is_consumer(self.root, f))
+            .cloned()
+            .collect();
+        if self.workspace {
+            for member in self.sibling_members()? {
+                consumers.extend(
+                    ImportAnalyzer::new(&member)
+                        .analyze()?
+                        .analyzed_files()
+                        .cloned(),
+                );
+            }
+        }
+        consumers.sort();
+
+        let mut usage: HashMap<PathBuf, Usage> = HashMap::new();
+        for consumer in &consumers {
+            let Ok(source) = std::fs::read_to_string(consumer) else {
+                continue;
+            };
+            for import in ModuleSymbols::extract(consumer, &source).imports {
+                let target = if extract_package_name(&import.specifier).as_deref() == Some(package)
+                {
+                    entries
+                        .iter()
+                        .find(|(_, specifier, _)| *specifier == import.specifier)
+                        .map(|(_, _, file)| file.clone())
+                } else if import.specifier.starts_with('.') {
+                    resolve::resolve_relative(consumer, &import.specifier)
+                        .map(|f| resolve::normalize(&f))
+                } else {
+                    None
+                };
+                let Some(target) = target else {
+                    continue;
+                };
+                let used = usage.entry(target).or_default();
+                used.imported = true;
+                match import.names {
+                    Some(names) => used.names.extend(names),
+                    None => used.all = true,
+                }
+            }
+        }
+
+        let mut entry_points = Vec::new();
+        for (entry_point, specifier, file) in entries {
+            let exports = module_exports(&file, &mut HashSet::new());
+            if exports.is_empty() {
+                continue;
+            }
+            let used = usage.remove(&file).unwrap_or_default();
+            let unused = if used.all {
+                Vec::new()
+            } else {
+                exports
+                    .iter()
+                    .filter(|name| !used.names.contains(*name))
+                    .cloned()
+                    .collect()
+            };
+            entry_points.push(EntryExports {
+                entry_point,
+                specifier,
+                file,
+                exports,
+                unused,
+                imported: used.imported,
+            });
+        }
+
+        Ok(UnusedExportsReport {
+            entry_points,
+            consumers: consumers.len(),
+        })
+    }
+
+    /// The analyzed source of an entry point file, looking past build
+    /// output: `dist/index.js` is read from `src/index.ts`
+    fn source_file(&self, file: &Path, imports: &ImportMap) -> Option<PathBuf> {
+        let file = resolve::normalize(file);
+        if imports.is_file_analyzed(&file) {
+            return Some(file);
+        }
+        let relative = file.strip_prefix(resolve::normalize(self.root)).ok()?;
+        let mut components = relative.components();
+        let build_dir = components.next()?.as_os_str().to_str()?;
+        if !BUILD_DIRS.contains(&build_dir) {
+            return None;
+        }
+        let rest = components.as_path().with_extension("");
+        resolve::resolve_file(&self.root.join("src").join(rest))
+            .map(|f| resolve::normalize(&f))
+            .filter(|f| imports.is_file_analyzed(f))
+    }
+
+    /// The other members of the workspace the project belongs to
+    fn sibling_members(&self) -> Result<Vec<PathBuf>> {
+        let own = std::fs::canonicalize(self.root).into_diagnostic()?;
+        for ancestor in own.ancestors().skip(1) {
+            let members = workspace::member_dirs(ancestor)?;
+            let canonical: Vec<PathBuf> = members
+                .iter()
+                .filter_map(|m| std::fs::canonicalize(m).ok())
+                .collect();
+            if canonical.contains(&own) {
+                return Ok(canonical.into_iter().filter(|m| *m != own).collect());
+            }
+        }
+        Ok(Vec::new())
+    }
+}
+
+/// Tests and examples, which import the package like its users do
+fn is_consumer(root: &Path, file: &Path) -> bool {
+    let relative = file.strip_prefix(root).unwrap_or(file);
+    is_test_file(file)
+        || relative
+            .components()
+            .any(|c| CONSUMER_DIRS.contains(&c.as_os_str().to_str().unwrap_or("")))
+}
+
+/// Every name a module exports, following `export * from` into the
+/// project's own files (which never re-export `default`)
+fn module_exports(file: &Path, visited: &mut HashSet<PathBuf>) -> Vec<String> {
+    if !visited.insert(file.to_path_buf()) {
+        return Vec::new();
+    }
+    let Ok(source) = std::fs::read_to_string(file) else {
+        return Vec::new();
+    };
+    let symbols = ModuleSymbols::extract(file, &source);
+
+    let mut exports = symbols.exports;
+    for specifier in &symbols.star_exports {
+        if let Some(target) = resolve::resolve_relative(file, specifier) {
+            exports.extend(
+                module_exports(&resolve::normalize(&target), visited)
+                    .into_iter()
+                    .filter(|name| name != "default"),
+            );
+        }
+    }
+    let mut seen = HashSet::new();
+    exports.retain(|name| seen.insert(name.clone()));
+    exports
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_unused_exports() {
+        let root = TempDir::new("exports");
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "package.json",
+            r#"{
+                "name": "my-lib",
+                "exports": {".": "./dist/index.js", "./utils": "./dist/utils.js"}
+            }"#,
+        );
+        write(
+            "src/index.ts",
+            "export * from './parse';\nexport function format() {}\nexport default format;\n",
+        );
+        write(
+            "src/parse.ts",
+            "export const parse = 1;\nexport const tokenize = 2;\nexport default parse;\n",
+        );
+        write("src/utils.ts", "export const clamp = 1;\n");
+        write(
+            "src/parse.test.ts",
+            "import { parse } from '../src';\nimport lib from 'my-lib';\n",
+        );
+        write(
+            "examples/basic.js",
+            "const utils = require('my-lib/utils');\n",
+        );
+
+        let imports = ImportAnalyzer::new(&root).analyze().unwrap();
+        let report = UnusedExportsAnalyzer::new(&root, false)
+            .analyze(&imports)
+            .unwrap();
+
+        assert_eq!(report.consumers, 2);
+        let found: Vec<(&str, Vec<&str>, Vec<&str>)> = report
+            .entry_points
+            .iter()
+            .map(|e| {
+                (
+                    e.specifier.as_str(),
+                    e.exports.iter().map(|s| s.as_str()).collect(),
+                    e.unused.iter().map(|s| s.as_str()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (
+                    "my-lib",
+                    vec!["format", "default", "parse", "tokenize"],
+                    vec!["format", "tokenize"]
+                ),
+                ("my-lib/utils", vec!["clamp"], vec![]),
+            ]
+        );
+        assert!(report.entry_points.iter().all(|e| e.imported));
+    }
+}
@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use miette::{Context, IntoDiagnostic, Result};
+use semver::Version;
+
+use crate::fix;
+use crate::lockfile::LockfileType;
+use crate::range::parse_version;
+use crate::registry::PackageMetadata;
+use crate::types::{FixAction, FixOutcome, Package, SkippedFix, UpdateCandidate, Vulnerability};
+
+/// Cargo.toml tables whose entries are dependencies
+const CARGO_DEPENDENCY_TABLES: &[&str] =
+    &["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// Direct dependencies whose latest release is newer than the installed one
+pub fn candidates(
+    packages: &HashMap<String, Package>,
+    metadata: &HashMap<String, PackageMetadata>,
+    vulnerabilities: &[Vulnerability],
+) -> Vec<UpdateCandidate> {
+    let mut candidates: Vec<UpdateCandidate> = packages
+        .values()
+        .filter(|pkg| pkg.is_direct)
+        .filter_map(|pkg| {
+            let latest = metadata.get(&pkg.name)?.latest_version.clone()?;
+            let current = parse_version(&pkg.version)?;
+            let target = parse_version(&latest)?;
+            if target <= current {
+                return None;
+            }
+
+            let mut fixes: Vec<String> = vulnerabilities
+                .iter()
+                .filter(|v| v.package_name == pkg.name && v.installed_version == pkg.version)
+                .filter(|v| {
+                    v.patched_version
+                        .as_deref()
+                        .and_then(parse_version)
+                        .is_some_and(|patched| patched <= target)
+                })
+                .map(|v| v.id.clone())
+                .collect();
+            fixes.sort();
+            fixes.dedup();
+
+            Some(UpdateCandidate {
+                package: pkg.name.clone(),
+                current: pkg.version.clone(),
+                latest,
+                breaking: is_breaking(&current, &target),
+                fixes,
+                is_dev: pkg.is_dev,
+                notes: None,
+                api: None,
+            })
+        })
+        .collect();
+
+    candidates.sort_by(|a, b| a.package.cmp(&b.package));
+    candidates
+}
+
+/// Whether moving between the versions leaves the caret range of `from`
+fn is_breaking(from: &Version, to: &Version) -> bool {
+    if from.major > 0 || to.major > 0 {
+        to.major != from.major
+    } else if from.minor > 0 || to.minor > 0 {
+        to.minor != from.minor
+    } else {
+        to.patch != from.patch
+    }
+}
+
+/// Parse a selection like "1,3-5"; "a" selects everything, an empty reply nothing
+///
+/// Returns zero-based indices in ascending order.
+pub fn parse_selection(reply: &str, count: usize) -> std::result::Result<Vec<usize>, String> {
+    let reply = reply.trim();
+    if reply.eq_ignore_ascii_case("a") || reply.eq_ignore_ascii_case("all") {
+        return Ok((0..count).collect());
+    }
+
+    let mut selected = Vec::new();
+    for part in reply.split([',', ' ']).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start, end),
+            None => (part, part),
+        };
+        let parse = |n: &str| -> std::result::Result<usize, String> {
+            match n.trim().parse::<usize>() {
+                Ok(n) if (1..=count).contains(&n) => Ok(n - 1),
+                _ => Err(format!("'{}' is not a number between 1 and {}", n, count)),
+            }
+        };
+        let (start, end) = (parse(start)?, parse(end)?);
+        if start > end {
+            return Err(format!("'{}' is a backwards range", part));
+        }
+        selected.extend(start..=end);
+    }
+
+    selected.sort_unstable();
+    selected.dedup();
+    Ok(selected)
+}
+
+/// Ask until the selection parses; end of input selects nothing
+pub fn ask_selection(count: usize) -> Result<Vec<usize>> {
+    let stdin = std::io::stdin();
+    loop {
+        print!("Updates to apply (e.g. 1,3-5; a = all; empty = none): ");
+        std::io::stdout().flush().into_diagnostic()?;
+
+        let mut reply = String::new();
+        if stdin.lock().read_line(&mut reply).into_diagnostic()? == 0 {
+            println!();
+            return Ok(Vec::new());
+        }
+        match parse_selection(&reply, count) {
+            Ok(selected) => return Ok(selected),
+            Err(message) => println!("  {}", message),
+        }
+    }
+}
+
+/// Manifest `apply` edits for the lockfile type
+pub fn manifest_name(lockfile_type: LockfileType) -> &'static str {
+    match lockfile_type {
+        LockfileType::Cargo => "Cargo.toml",
+        LockfileType::Poetry => "pyproject.toml",
+        LockfileType::Pip => "requirements.txt",
+        LockfileType::Go => "go.mod",
+        LockfileType::Npm
+        | LockfileType::Pnpm
+        | LockfileType::Yarn
+        | LockfileType::Bun
+        | LockfileType::Deno => "package.json",
+    }
+}
+
+/// How a project with the lockfile type declares dependencies, for the
+/// tips reports print
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackageManager {
+    /// The manifest dependencies are declared in
+    pub manifest: &'static str,
+    add: &'static str,
+    remove: &'static str,
+}
+
+impl PackageManager {
+    pub fn of(lockfile_type: LockfileType) -> Self {
+        let (add, remove) = match lockfile_type {
+            LockfileType::Npm => ("npm install <package>", "npm uninstall <package>"),
+            LockfileType::Pnpm => ("pnpm add <package>", "pnpm remove <package>"),
+            LockfileType::Yarn => ("yarn add <package>", "yarn remove <package>"),
+            LockfileType::Bun => ("bun add <package>", "bun remove <package>"),
+            LockfileType::Deno => ("deno add <package>", "deno remove <package>"),
+            LockfileType::Cargo => ("cargo add <package>", "cargo remove <package>"),
+            LockfileType::Poetry => ("poetry add <package>", "poetry remove <package>"),
+            LockfileType::Pip => (
+                "add <package> to requirements.txt",
+                "remove <package> from requirements.txt",
+            ),
+            LockfileType::Go => ("go get <package>", "go get <package>@none"),
+        };
+        Self {
+            manifest: manifest_name(lockfile_type),
+            add,
+            remove,
+        }
+    }
+
+    /// The command declaring `package` ("<package>" for a placeholder)
+    pub fn add(&self, package: &str) -> String {
+        self.add.replace("<package>", package)
+    }
+
+    /// The command removing `package` ("<package>" for a placeholder)
+    pub fn remove(&self, package: &str) -> String {
+        self.remove.replace("<package>", package)
+    }
+}
+
+/// Write the selected bumps to the project's manifest
+///
+/// package.json goes through `depx fix`, keeping `^`/`~` ranges; Cargo.toml
+/// is edited in place so comments and formatting survive.
+pub fn apply(
+    root: &Path,
+    lockfile_type: LockfileType,
+    selected: &[UpdateCandidate],
+    dry_run: bool,
+) -> Result<FixOutcome> {
+    let actions: Vec<FixAction> = selected
+        .iter()
+        .map(|candidate| FixAction::Upgrade {
+            package: candidate.package.clone(),
+            from: candidate.current.clone(),
+            to: candidate.latest.clone(),
+            reason: if candidate.fixes.is_empty() {
+                "selected in depx update".to_string()
+            } else {
+                format!("fixes {}", candidate.fixes.join(", "))
+            },
+        })
+        .collect();
+
+    if lockfile_type.is_python() || lockfile_type == LockfileType::Go {
+        miette::bail!(
+            "depx update can't edit {} yet; bump the versions there by hand",
+            manifest_name(lockfile_type)
+        );
+    }
+    if lockfile_type != LockfileType::Cargo {
+        let mut manifest = fix::load_manifest(root)?;
+        return fix::apply(Some(lockfile_type), &mut manifest, &actions, dry_run);
+    }
+
+    let path = root.join("Cargo.toml");
+    let original = std::fs::read_to_string(&path)
+        .into_diagnostic()
+        .with_context(|| "Failed to read Cargo.toml")?;
+
+    let mut outcome = FixOutcome::default();
+    let mut updated = original.clone();
+    for action in actions {
+        let FixAction::Upgrade {
+            ref package,
+            ref to,
+            ..
+        } = action
+        else {
+            continue;
+        };
+        match bump_cargo_dependency(&updated, package, to) {
+            Some(content) => {
+                updated = content;
+                outcome.applied.push(action);
+            }
+            None => outcome.skipped.push(SkippedFix {
+                action,
+                reason: "no version requirement for it in Cargo.toml".to_string(),
+            }),
+        }
+    }
+
+    if !dry_run && !outcome.applied.is_empty() {
+        std::fs::write(&path, &updated)
+            .into_diagnostic()
+            .with_context(|| "Failed to write Cargo.toml")?;
+    }
+    outcome.original = original;
+    outcome.updated = updated;
+    Ok(outcome)
+}
+
+/// Rewrite the version requirement of `name` in Cargo.toml text
+///
+/// Handles `name = "1.0"`, `name = { version = "1.0", ... }` and
+/// `[dependencies.name]` tables, including target-specific tables.
+fn bump_cargo_dependency(content: &str, name: &str, to: &str) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let mut in_dependencies = false;
+    let mut in_own_table = false;
+    let mut changed = false;
+
+    for line in &mut lines {
+        let trimmed = line.trim();
+        if let Some(header) = trimmed.strip_prefix('[').and_then(|h| h.split(']').next()) {
+            let header = header.trim();
+            let table = header.rsplit('.').next().unwrap_or(header);
+            in_dependencies = CARGO_DEPENDENCY_TABLES.contains(&table);
+            in_own_table = header
+                .strip_suffix(name)
+                .and_then(|h| h.strip_suffix('.'))
+                .is_some_and(|parent| {
+                    CARGO_DEPENDENCY_TABLES
+                        .iter()
+                        .any(|t| parent == *t || parent.ends_with(&format!(".{}", t)))
+                });
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().trim_matches('"');
+
+        let bumped = if in_dependencies && key == name {
+            let value = value.trim();
+            if value.starts_with('"') {
+                replace_first_string(line, to)
+            } else {
+                replace_version_key(line, to)
+            }
+        } else if in_own_table && key == "version" {
+            replace_first_string(line, to)
+        } else {
+            None
+        };
+        if let Some(bumped) = bumped {
+            *line = bumped;
+            changed = true;
+        }
+    }
+
+    if !changed {
+        return None;
+    }
+    let mut output = lines.join("\n");
+    if content.ends_with('\n') {
+        output.push('\n');
+    }
+    Some(output)
+}
+
+/// Replace the first quoted string after `=`, keeping its comparison operator
+fn replace_first_string(line: &str, to: &str) -> Option<String> {
+    let eq = line.find('=')?;
+    let start = eq + line[eq..].find('"')? + 1;
+    let end = start + line[start..].find('"')?;
+    Some(format!(
+        "{}{}{}",
+        &line[..start],
+        bumped_requirement(&line[start..end], to),
+        &line[end..]
+    ))
+}
+
+/// Replace `version = "..."` inside an inline table
+fn replace_version_key(line: &str, to: &str) -> Option<String> {
+    let key = line.find("version")?;
+    let (head, tail) = line.split_at(key);
+    Some(format!("{}{}", head, replace_first_string(tail, to)?))
+}
+
+/// "^1.2" -> "^2.0.0", "=1.2.3" -> "=2.0.0", "1.2" -> "2.0.0"
+fn bumped_requirement(requirement: &str, to: &str) -> String {
+    let operator: String = requirement
+        .chars()
+        .take_while(|c| matches!(c, '^' | '~' | '='))
+        .collect();
+    format!("{}{}", operator, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_manager() {
+        let poetry = PackageManager::of(LockfileType::Poetry);
+        assert_eq!(poetry.manifest, "pyproject.toml");
+        assert_eq!(poetry.remove("requests"), "poetry remove requests");
+        assert_eq!(poetry.add("<package>"), "poetry add <package>");
+
+        let go = PackageManager::of(LockfileType::Go);
+        assert_eq!(
+            go.remove("golang.org/x/text"),
+            "go get golang.org/x/text@none"
+        );
+    }
+
+    #[test]
+    fn test_is_breaking() {
+        let v = |s: &str| Version::parse(s).unwrap();
+        assert!(!is_breaking(&v("4.17.1"), &v("4.19.2")));
+        assert!(is_breaking(&v("4.17.1"), &v("5.0.0")));
+        assert!(is_breaking(&v("0.3.0"), &v("0.4.0")));
+        assert!(!is_breaking(&v("0.3.0"), &v("0.3.7")));
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("1,3-4", 5), Ok(vec![0, 2, 3]));
+        assert_eq!(parse_selection("a", 3), Ok(vec![0, 1, 2]));
+        assert_eq!(parse_selection("", 3), Ok(vec![]));
+        assert!(parse_selection("6", 5).is_err());
+        assert!(parse_selection("3-1", 5).is_err());
+    }
+
+    #[test]
+    fn test_bump_cargo_dependency() {
+        let content = r#"[package]
+name = "app"
+version = "0.1.0"
+
+[dependencies]
+serde = { version = "1.0", features = ["derive"] } # serialization
+log = "^0.4"
+
+[target.'cfg(unix)'.dependencies]
+libc = "=0.2.100"
+
+[dev-dependencies.tokio]
+version = "1"
+features = ["full"]
+"#;
+        let bumped = bump_cargo_dependency(content, "serde", "2.0.0").unwrap();
+        assert!(bumped
+            .contains(r#"serde = { version = "2.0.0", features = ["derive"] } # serialization"#));
+        let bumped = bump_cargo_dependency(&bumped, "log", "0.5.1").unwrap();
+        assert!(bumped.contains(r#"log = "^0.5.1""#));
+        let bumped = bump_cargo_dependency(&bumped, "libc", "0.2.150").unwrap();
+        assert!(bumped.contains(r#"libc = "=0.2.150""#));
+        let bumped = bump_cargo_dependency(&bumped, "tokio", "1.40.0").unwrap();
+        assert!(bumped.contains("version = \"1.40.0\"\nfeatures"));
+        // The package's own version is left alone
+        assert!(bumped.contains("version = \"0.1.0\""));
+
+        assert!(bump_cargo_dependency(content, "rand", "1.0.0").is_none());
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use miette::{IntoDiagnostic, Result};
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+
+use crate::analyzer::resolve::normalize;
+use crate::lockfile::CargoLockfileParser;
+use crate::types::VendoredPackage;
+use crate::workspace;
+
+/// Install and build output, which package managers already account for
+const SKIPPED_DIRS: &[&str] = &["node_modules", "target", ".git"];
+
+/// Written by `cargo vendor`, whose copies Cargo.lock still tracks
+const CARGO_CHECKSUM: &str = ".cargo-checksum.json";
+
+/// Finds copies of published packages checked into the project
+///
+/// A directory counts as a vendored copy when it holds a package.json or
+/// Cargo.toml naming a published package (a name and version, not marked
+/// private) and isn't the project itself or one of its workspace members.
+/// Crates the project depends on by `path`, which Cargo.lock tracks, are
+/// its own code too. Vendored code never shows up in the lockfile, so
+/// audit and update tooling can't see it.
+pub struct VendoredAnalyzer<'a> {
+    root: &'a Path,
+}
+
+impl<'a> VendoredAnalyzer<'a> {
+    pub fn new(root: &'a Path) -> Self {
+        Self { root }
+    }
+
+    pub fn analyze(&self) -> Result<Vec<VendoredPackage>> {
+        let mut own: HashSet<PathBuf> = HashSet::from([normalize(self.root)]);
+        for dir in workspace::member_dirs(self.root)?
+            .into_iter()
+            .chain(workspace::cargo_member_dirs(self.root)?)
+        {
+            own.insert(normalize(&dir));
+        }
+        own.extend(cargo_path_dependencies(&own));
+        let cargo_lock = self.root.join("Cargo.lock");
+        let locked_locally = if cargo_lock.is_file() {
+            CargoLockfileParser::new(&cargo_lock).local_packages()?
+        } else {
+            HashSet::new()
+        };
+
+        let walker = WalkBuilder::new(self.root)
+            .hidden(true)
+            .git_ignore(true)
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_str().unwrap_or("");
+                !(entry.file_type().is_some_and(|t| t.is_dir()) && SKIPPED_DIRS.contains(&name))
+            })
+            .build();
+
+        let mut vendored: Vec<VendoredPackage> = Vec::new();
+        for entry in walker {
+            let entry = entry.into_diagnostic()?;
+            let path = entry.path();
+            let Some(manifest) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(dir) = path.parent() else {
+                continue;
+            };
+            if own.contains(&normalize(dir)) || dir.join(CARGO_CHECKSUM).exists() {
+                continue;
+            }
+
+            let published = match manifest {
+                "package.json" => published_npm_package(path),
+                "Cargo.toml" => published_crate(path)
+                    .filter(|crate_version| !locked_locally.contains(crate_version)),
+                _ => None,
+            };
+            let Some((name, version)) = published else {
+                continue;
+            };
+
+            let relative = dir.strip_prefix(self.root).unwrap_or(dir).to_path_buf();
+            vendored.push(VendoredPackage {
+                name,
+                version,
+                manifest: manifest.to_string(),
+                path: relative,
+            });
+        }
+
+        // A vendored package's own fixtures and examples aren't copies of their own
+        vendored.sort_by(|a, b| a.path.cmp(&b.path));
+        let mut outermost: Vec<VendoredPackage> = Vec::new();
+        for package in vendored {
+            if !outermost
+                .iter()
+                .any(|outer| package.path != outer.path && package.path.starts_with(&outer.path))
+            {
+                outermost.push(package);
+            }
+        }
+        Ok(outermost)
+    }
+}
+
+/// Directories the Cargo.toml in `dirs` point `path` dependencies at,
+/// followed through the path dependencies of those
+fn cargo_path_dependencies(dirs: &HashSet<PathBuf>) -> HashSet<PathBuf> {
+    let mut found = HashSet::new();
+    let mut queue: Vec<PathBuf> = dirs.iter().cloned().collect();
+    while let Some(dir) = queue.pop() {
+        let Some(manifest) = std::fs::read_to_string(dir.join("Cargo.toml"))
+            .ok()
+            .and_then(|content| toml::from_str::<TomlValue>(&content).ok())
+        else {
+            continue;
+        };
+        for path in manifest_paths(&manifest) {
+            let target = normalize(&dir.join(path));
+            if !dirs.contains(&target) && found.insert(target.clone()) {
+                queue.push(target);
+            }
+        }
+    }
+    found
+}
+
+/// `path` values of a manifest's dependency tables: per target, in
+/// `[workspace.dependencies]` and in `[patch.*]`
+fn manifest_paths(manifest: &TomlValue) -> Vec<&str> {
+    const SECTIONS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+
+    let mut tables: Vec<&TomlValue> = SECTIONS
+        .iter()
+        .filter_map(|section| manifest.get(section))
+        .collect();
+    for target in manifest
+        .get("target")
+        .and_then(|t| t.as_table())
+        .into_iter()
+        .flat_map(|t| t.values())
+    {
+        tables.extend(SECTIONS.iter().filter_map(|section| target.get(section)));
+    }
+    tables.extend(
+        manifest
+            .get("workspace")
+            .and_then(|w| w.get("dependencies")),
+    );
+    tables.extend(
+        manifest
+            .get("patch")
+            .and_then(|p| p.as_table())
+            .into_iter()
+            .flat_map(|p| p.values()),
+    );
+
+    tables
+        .into_iter()
+        .filter_map(|table| table.as_table())
+        .flat_map(|table| table.values())
+        .filter_map(|spec| spec.get("path")?.as_str())
+        .collect()
+}
+
+/// Name and version of a package.json that isn't `"private": true`
+fn published_npm_package(path: &Path) -> Option<(String, String)> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let manifest: JsonValue = serde_json::from_str(&content).ok()?;
+    if manifest.get("private").and_then(|v| v.as_bool()) == Some(true) {
+        return None;
+    }
+    let name = manifest.get("name")?.as_str()?;
+    let version = manifest.get("version")?.as_str()?;
+    Some((name.to_string(), version.to_string()))
+}
+
+/// Name and version of a Cargo.toml package that isn't `publish = false`
+///
+/// A version inherited from a workspace (`version.workspace = true`) marks
+/// a member of some other workspace rather than a copy.
+fn published_crate(path: &Path) -> Option<(String, String)> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let manifest: TomlValue = toml::from_str(&content).ok()?;
+    let package = manifest.get("package")?;
+    if package.get("publish").and_then(|v| v.as_bool()) == Some(false) {
+        return None;
+    }
+    let name = package.get("name")?.as_str()?;
+    let version = package.get("version")?.as_str()?;
+    Some((name.to_string(), version.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_vendored_packages() {
+        let root = TempDir::new("vendored");
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(
+            "package.json",
+            r#"{"name": "app", "version": "1.0.0", "workspaces": ["packages/*"]}"#,
+        );
+        write(
+            "packages/ui/package.json",
+            r#"{"name": "@app/ui", "version": "0.1.0"}"#,
+        );
+        write(
+            "src/vendor/left-pad/package.json",
+            r#"{"name": "left-pad", "version": "1.3.0"}"#,
+        );
+        write(
+            "src/vendor/left-pad/test/fixture/package.json",
+            r#"{"name": "fixture", "version": "0.0.0"}"#,
+        );
+        write(
+            "scripts/package.json",
+            r#"{"name": "scripts", "version": "0.0.0", "private": true}"#,
+        );
+        write(
+            "node_modules/lodash/package.json",
+            r#"{"name": "lodash", "version": "4.17.21"}"#,
+        );
+        write(
+            "third_party/smallvec/Cargo.toml",
+            "[package]\nname = \"smallvec\"\nversion = \"1.13.2\"\n",
+        );
+        write(
+            "vendor/serde/Cargo.toml",
+            "[package]\nname = \"serde\"\nversion = \"1.0.200\"\n",
+        );
+        write("vendor/serde/.cargo-checksum.json", "{}");
+        // A path dependency of a path dependency, and one only Cargo.lock tracks
+        write(
+            "Cargo.toml",
+            "[package]\nname = \"app\"\nversion = \"1.0.0\"\n\n[dependencies]\nparser = { path = \"crates/parser\" }\n",
+        );
+        write(
+            "crates/parser/Cargo.toml",
+            "[package]\nname = \"app-parser\"\nversion = \"0.1.0\"\n\n[dev-dependencies]\nlexer = { path = \"../lexer\" }\n",
+        );
+        write(
+            "crates/lexer/Cargo.toml",
+            "[package]\nname = \"app-lexer\"\nversion = \"0.1.0\"\n",
+        );
+        write(
+            "tools/gen/Cargo.toml",
+            "[package]\nname = \"gen\"\nversion = \"0.2.0\"\n",
+        );
+        write(
+            "Cargo.lock",
+            "version = 3\n\n[[package]]\nname = \"app\"\nversion = \"1.0.0\"\n\n[[package]]\nname = \"gen\"\nversion = \"0.2.0\"\n",
+        );
+
+        let vendored = VendoredAnalyzer::new(&root).analyze().unwrap();
+
+        let found: Vec<(&str, &str, &str)> = vendored
+            .iter()
+            .map(|v| (v.name.as_str(), v.version.as_str(), v.manifest.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("left-pad", "1.3.0", "package.json"),
+                ("smallvec", "1.13.2", "Cargo.toml"),
+            ]
+        );
+        assert_eq!(vendored[0].path, Path::new("src/vendor/left-pad"));
+    }
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use clap::ValueEnum;
+use miette::{Context, IntoDiagnostic, Result};
+use serde_json::{json, Value as JsonValue};
+
+use crate::types::Vulnerability;
+
+/// OpenVEX context the documents conform to
+const OPENVEX_CONTEXT: &str = "https://openvex.dev/ns/v0.2.0";
+
+/// CycloneDX version of standalone VEX documents
+const CYCLONEDX_SPEC_VERSION: &str = "1.5";
+
+/// Document written by `depx audit --vex`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VexFormat {
+    /// An OpenVEX document
+    Openvex,
+    /// A standalone CycloneDX VEX BOM
+    Cyclonedx,
+}
+
+/// The triage decision recorded for one vulnerability
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Triage {
+    /// Listed under `[audit.ignore]`, with the reason given there
+    Ignored(String),
+    /// No project code imports the package
+    Unreachable,
+    /// Imported by project code, so the fix applies
+    Affected,
+    /// Reachability wasn't checked (no `--used-only`)
+    Unknown,
+}
+
+impl Triage {
+    pub fn of(
+        vuln: &Vulnerability,
+        ignore: &BTreeMap<String, String>,
+        reachability_checked: bool,
+    ) -> Self {
+        if let Some(reason) = ignore.get(&vuln.id) {
+            Triage::Ignored(reason.clone())
+        } else if !reachability_checked {
+            Triage::Unknown
+        } else if vuln.affects_used_code {
+            Triage::Affected
+        } else {
+            Triage::Unreachable
+        }
+    }
+}
+
+/// Triage statements for every vulnerability found, for downstream
+/// consumers of the project's SBOM
+pub struct VexDocument<'a> {
+    statements: Vec<(&'a Vulnerability, Triage)>,
+    timestamp: String,
+}
+
+impl<'a> VexDocument<'a> {
+    pub fn new(
+        vulnerabilities: &'a [Vulnerability],
+        ignore: &BTreeMap<String, String>,
+        reachability_checked: bool,
+    ) -> Self {
+        let statements = vulnerabilities
+            .iter()
+            .map(|vuln| (vuln, Triage::of(vuln, ignore, reachability_checked)))
+            .collect();
+        Self {
+            statements,
+            timestamp: crate::date::now_timestamp(),
+        }
+    }
+
+    pub fn to_json(&self, format: VexFormat) -> JsonValue {
+        match format {
+            VexFormat::Openvex => self.openvex(),
+            VexFormat::Cyclonedx => self.cyclonedx(),
+        }
+    }
+
+    pub fn write(&self, path: &Path, format: VexFormat) -> Result<()> {
+        let content = serde_json::to_string_pretty(&self.to_json(format))
+            .map_err(|e| miette::miette!("Failed to serialize VEX document: {}", e))?;
+        std::fs::write(path, content + "\n")
+            .into_diagnostic()
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn openvex(&self) -> JsonValue {
+        let statements: Vec<JsonValue> = self
+            .statements
+            .iter()
+            .map(|(vuln, triage)| {
+                let mut statement = json!({
+                    "vulnerability": { "name": vuln.id },
+                    "products": [{ "@id": self.purl(vuln) }],
+                });
+                let fields = match triage {
+                    Triage::Ignored(reason) => json!({
+                        "status": "not_affected",
+                        "impact_statement": reason,
+                    }),
+                    Triage::Unreachable => json!({
+                        "status": "not_affected",
+                        "justification": "vulnerable_code_not_in_execute_path",
+                        "impact_statement": unreachable_detail(vuln),
+                    }),
+                    Triage::Affected => json!({
+                        "status": "affected",
+                        "action_statement": action(vuln),
+                    }),
+                    Triage::Unknown => json!({ "status": "under_investigation" }),
+                };
+                merge(&mut statement, fields);
+                statement
+            })
+            .collect();
+
+        json!({
+            "@context": OPENVEX_CONTEXT,
+            "@id": format!("urn:depx:vex:{}", self.timestamp),
+            "author": "depx",
+            "timestamp": self.timestamp,
+            "version": 1,
+            "tooling": format!("depx {}", env!("CARGO_PKG_VERSION")),
+            "statements": statements,
+        })
+    }
+
+    fn cyclonedx(&self) -> JsonValue {
+        let vulnerabilities: Vec<JsonValue> = self
+            .statements
+            .iter()
+            .map(|(vuln, triage)| {
+                let analysis = match triage {
+                    Triage::Ignored(reason) => json!({
+                        "state": "not_affected",
+                        "detail": reason,
+                    }),
+                    Triage::Unreachable => json!({
+                        "state": "not_affected",
+                        "justification": "code_not_reachable",
+                        "detail": unreachable_detail(vuln),
+                    }),
+                    Triage::Affected => {
+                        let response = match vuln.patched_version {
+                            Some(_) => "update",
+                            None => "can_not_fix",
+                        };
+                        json!({
+                            "state": "exploitable",
+                            "response": [response],
+                            "detail": action(vuln),
+                        })
+                    }
+                    Triage::Unknown => json!({ "state": "in_triage" }),
+                };
+                let mut entry = json!({
+                    "id": vuln.id,
+                    "affects": [{ "ref": self.purl(vuln) }],
+                    "analysis": analysis,
+                });
+                if let Some(url) = &vuln.url {
+                    merge(&mut entry, json!({ "source": { "url": url } }));
+                }
+                entry
+            })
+            .collect();
+
+        json!({
+            "bomFormat": "CycloneDX",
+            "specVersion": CYCLONEDX_SPEC_VERSION,
+            "version": 1,
+            "metadata": {
+                "timestamp": self.timestamp,
+                "tools": {
+                    "components": [{
+                        "type": "application",
+                        "name": "depx",
+                        "version": env!("CARGO_PKG_VERSION"),
+                    }],
+                },
+            },
+            "vulnerabilities": vulnerabilities,
+        })
+    }
+
+    /// The package's purl, which `depx audit` gives every vulnerability
+    /// in its lockfile's ecosystem
+    fn purl(&self, vuln: &Vulnerability) -> String {
+        vuln.purl.clone().unwrap_or_default()
+    }
+}
+
+fn unreachable_detail(vuln: &Vulnerability) -> String {
+    format!("No project source uses {}", vuln.package_name)
+}
+
+fn action(vuln: &Vulnerability) -> String {
+    match &vuln.patched_version {
+        Some(version) => format!("Update {} to {}", vuln.package_name, version),
+        None => format!("No fixed version of {} is published", vuln.package_name),
+    }
+}
+
+fn merge(target: &mut JsonValue, fields: JsonValue) {
+    if let (Some(target), JsonValue::Object(fields)) = (target.as_object_mut(), fields) {
+        target.extend(fields);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sbom::package_url;
+    use crate::types::Severity;
+
+    fn vuln(id: &str, package: &str, used: bool) -> Vulnerability {
+        Vulnerability {
+            id: id.to_string(),
+            title: "Prototype Pollution".to_string(),
+            severity: Severity::High,
+            package_name: package.to_string(),
+            vulnerable_range: "<1.2.6".to_string(),
+            patched_version: Some("1.2.6".to_string()),
+            url: Some(format!("https://osv.dev/vulnerability/{}", id)),
+            affects_used_code: used,
+            installed_version: "1.2.5".to_string(),
+            purl: Some(package_url("npm", package, "1.2.5")),
+            cpe: None,
+            owners: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_triage() {
+        let ignore = BTreeMap::from([("GHSA-1".to_string(), "build only".to_string())]);
+
+        let ignored = vuln("GHSA-1", "minimist", true);
+        assert_eq!(
+            Triage::of(&ignored, &ignore, false),
+            Triage::Ignored("build only".to_string())
+        );
+        let unused = vuln("GHSA-2", "json5", false);
+        assert_eq!(Triage::of(&unused, &ignore, true), Triage::Unreachable);
+        assert_eq!(Triage::of(&unused, &ignore, false), Triage::Unknown);
+        let used = vuln("GHSA-3", "json5", true);
+        assert_eq!(Triage::of(&used, &ignore, true), Triage::Affected);
+    }
+
+    #[test]
+    fn test_openvex() {
+        let vulns = vec![
+            vuln("GHSA-1", "minimist", true),
+            vuln("GHSA-2", "@scope/json5", false),
+        ];
+        let ignore = BTreeMap::from([("GHSA-1".to_string(), "build only".to_string())]);
+        let document = VexDocument::new(&vulns, &ignore, true).to_json(VexFormat::Openvex);
+
+        assert_eq!(document["@context"], OPENVEX_CONTEXT);
+        let statements = document["statements"].as_array().unwrap();
+        assert_eq!(statements[0]["vulnerability"]["name"], "GHSA-1");
+        assert_eq!(statements[0]["status"], "not_affected");
+        assert_eq!(statements[0]["impact_statement"], "build only");
+        assert_eq!(
+            statements[1]["products"][0]["@id"],
+            "pkg:npm/%40scope/json5@1.2.5"
+        );
+        assert_eq!(
+            statements[1]["justification"],
+            "vulnerable_code_not_in_execute_path"
+        );
+    }
+
+    #[test]
+    fn test_cyclonedx() {
+        let vulns = vec![vuln("GHSA-3", "json5", true), vuln("GHSA-4", "x", true)];
+        let document =
+            VexDocument::new(&vulns, &BTreeMap::new(), true).to_json(VexFormat::Cyclonedx);
+
+        assert_eq!(document["bomFormat"], "CycloneDX");
+        let entries = document["vulnerabilities"].as_array().unwrap();
+        assert_eq!(entries[0]["affects"][0]["ref"], "pkg:npm/json5@1.2.5");
+        assert_eq!(entries[0]["analysis"]["state"], "exploitable");
+        assert_eq!(entries[0]["analysis"]["response"][0], "update");
+        assert_eq!(
+            entries[0]["source"]["url"],
+            "https://osv.dev/vulnerability/GHSA-3"
+        );
+
+        let document =
+            VexDocument::new(&vulns, &BTreeMap::new(), false).to_json(VexFormat::Cyclonedx);
+        assert_eq!(
+            document["vulnerabilities"][1]["analysis"]["state"],
+            "in_triage"
+        );
+    }
+}
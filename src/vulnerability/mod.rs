@@ -3,26 +3,48 @@ use std::collections::{HashMap, HashSet};
 use miette::{Context, IntoDiagnostic, Result};
 use serde::Deserialize;
 
+use crate::fix::rename;
+use crate::registry::PackageMetadata;
 use crate::types::{DeprecatedPackage, Package, Severity, Vulnerability};
 
 /// Batch size for OSV querybatch API
 const BATCH_SIZE: usize = 1000;
 
+/// Advisories OSV has for a set of packages
+#[derive(Debug)]
+pub struct Scan {
+    pub vulnerabilities: Vec<Vulnerability>,
+
+    /// Whether every OSV request succeeded; when one failed, packages it
+    /// covered may have advisories missing from `vulnerabilities`
+    pub complete: bool,
+}
+
 /// Check for known vulnerabilities in packages using OSV querybatch API
 /// Groups packages into batches for efficient querying
 pub async fn check_vulnerabilities(
     packages: &HashMap<String, Package>,
     used_packages: Option<&HashSet<String>>,
-) -> Result<Vec<Vulnerability>> {
+    ecosystem: &str,
+) -> Result<Scan> {
     let client = reqwest::Client::new();
-    let total_packages = packages.len();
+
+    // Advisories are published against registry packages; a git or local
+    // package sharing a name with one isn't affected by them. Unpinned
+    // requirements have no version to match.
+    let packages_vec: Vec<(&String, &Package)> = packages
+        .iter()
+        .filter(|(_, pkg)| pkg.is_from_registry() && !pkg.version.is_empty())
+        .collect();
+    let total_packages = packages_vec.len();
 
     if total_packages == 0 {
-        return Ok(Vec::new());
+        return Ok(Scan {
+            vulnerabilities: Vec::new(),
+            complete: true,
+        });
     }
 
-    // Convert to vec for batching
-    let packages_vec: Vec<(&String, &Package)> = packages.iter().collect();
     let total_batches = total_packages.div_ceil(BATCH_SIZE);
 
     // Show progress for large projects
@@ -34,7 +56,8 @@ pub async fn check_vulnerabilities(
     }
 
     // Step 1: Query all packages in batches to get vulnerability IDs
-    let mut package_vuln_ids: HashMap<String, Vec<(String, String)>> = HashMap::new(); // package_name -> [(vuln_id, version)]
+    let mut package_vuln_ids: HashMap<String, Vec<(String, String)>> = HashMap::new(); // package_key -> [(vuln_id, version)]
+    let mut complete = true;
 
     for (batch_idx, chunk) in packages_vec.chunks(BATCH_SIZE).enumerate() {
         if total_batches > 1 {
@@ -45,22 +68,23 @@ pub async fn check_vulnerabilities(
             );
         }
 
-        match query_batch(&client, chunk).await {
+        match query_batch(&client, chunk, ecosystem).await {
             Ok(batch_results) => {
                 for (i, result) in batch_results.into_iter().enumerate() {
                     if !result.vulns.is_empty() {
-                        let (pkg_name, pkg) = chunk[i];
+                        let (pkg_key, pkg) = chunk[i];
                         let vuln_ids: Vec<(String, String)> = result
                             .vulns
                             .into_iter()
                             .map(|v| (v.id, pkg.version.clone()))
                             .collect();
-                        package_vuln_ids.insert(pkg_name.clone(), vuln_ids);
+                        package_vuln_ids.insert(pkg_key.clone(), vuln_ids);
                     }
                 }
             }
             Err(e) => {
                 // Log error but continue with other batches
+                complete = false;
                 eprintln!(
                     "\n\x1b[1;33m     Warning\x1b[0m Batch {} failed: {}",
                     batch_idx + 1,
@@ -81,7 +105,10 @@ pub async fn check_vulnerabilities(
         .collect();
 
     if unique_vuln_ids.is_empty() {
-        return Ok(Vec::new());
+        return Ok(Scan {
+            vulnerabilities: Vec::new(),
+            complete,
+        });
     }
 
     // Step 3: Fetch full details for each unique vulnerability
@@ -93,14 +120,20 @@ pub async fn check_vulnerabilities(
     }
 
     let vuln_details = fetch_vulnerability_details(&client, &unique_vuln_ids).await?;
+    complete &= vuln_details.len() == unique_vuln_ids.len();
 
     // Step 4: Build final vulnerability list with package mapping
     let mut vulnerabilities = Vec::new();
 
-    for (pkg_name, vuln_ids) in &package_vuln_ids {
+    for (pkg_key, vuln_ids) in &package_vuln_ids {
+        // Keys are "name@version" for Cargo, so match advisories on the real name
+        let pkg_name = &packages[pkg_key].name;
+
         for (vuln_id, version) in vuln_ids {
             if let Some(osv_vuln) = vuln_details.get(vuln_id) {
                 if let Some(mut vuln) = convert_osv_vuln(osv_vuln, pkg_name, version) {
+                    vuln.purl = packages[pkg_key].purl.clone();
+                    vuln.cpe = packages[pkg_key].cpe.clone();
                     vuln.affects_used_code = used_packages
                         .map(|used| used.contains(pkg_name))
                         .unwrap_or(true);
@@ -110,29 +143,41 @@ pub async fn check_vulnerabilities(
         }
     }
 
-    // Sort by severity (critical first), then by package name
+    sort_vulnerabilities(&mut vulnerabilities);
+
+    Ok(Scan {
+        vulnerabilities,
+        complete,
+    })
+}
+
+/// Sort by severity (critical first), then by package name
+pub fn sort_vulnerabilities(vulnerabilities: &mut [Vulnerability]) {
     vulnerabilities.sort_by(|a, b| {
         b.severity
             .cmp(&a.severity)
             .then_with(|| a.package_name.cmp(&b.package_name))
     });
-
-    Ok(vulnerabilities)
 }
 
 /// Query a batch of packages using OSV querybatch API
 async fn query_batch(
     client: &reqwest::Client,
     packages: &[(&String, &Package)],
+    ecosystem: &str,
 ) -> Result<Vec<OsvBatchResult>> {
     let queries: Vec<OsvQueryRequest> = packages
         .iter()
-        .map(|(name, pkg)| OsvQueryRequest {
+        .map(|(_, pkg)| OsvQueryRequest {
             package: OsvPackage {
-                name: (*name).clone(),
-                ecosystem: "npm".to_string(),
+                name: pkg.name.clone(),
+                ecosystem: ecosystem.to_string(),
             },
-            version: Some(pkg.version.clone()),
+            // OSV stores Go versions without the "v"
+            version: Some(match ecosystem {
+                "Go" => pkg.version.trim_start_matches('v').to_string(),
+                _ => pkg.version.clone(),
+            }),
         })
         .collect();
 
@@ -223,16 +268,28 @@ async fn fetch_single_vulnerability(
 }
 
 /// Check for deprecated packages
+///
+/// Lockfiles recording deprecations speak for themselves; otherwise the
+/// installed version's notice comes from registry `metadata`, by name.
 pub async fn check_deprecated(
     packages: &HashMap<String, Package>,
+    metadata: &HashMap<String, PackageMetadata>,
 ) -> Result<Vec<DeprecatedPackage>> {
     let mut deprecated = Vec::new();
 
     for pkg in packages.values() {
-        if let Some(ref message) = pkg.deprecated {
+        let message = pkg.deprecated.clone().or_else(|| {
+            metadata
+                .get(&pkg.name)?
+                .deprecated
+                .get(&pkg.version)
+                .cloned()
+        });
+        if let Some(message) = message {
             deprecated.push(DeprecatedPackage {
                 package: pkg.clone(),
-                message: message.clone(),
+                successor: rename::successor(&pkg.name, Some(&message)),
+                message,
                 is_used: false,
             });
         }
@@ -278,6 +335,9 @@ fn convert_osv_vuln(
         url: osv.references.first().map(|r| r.url.clone()),
         affects_used_code: false,
         installed_version: version.to_string(),
+        purl: None,
+        cpe: None,
+        owners: Vec::new(),
     })
 }
 
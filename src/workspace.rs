@@ -0,0 +1,258 @@
+use std::path::{Path, PathBuf};
+
+use ignore::gitignore::GitignoreBuilder;
+use miette::{Context, IntoDiagnostic, Result};
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use toml::Value as TomlValue;
+
+use crate::lockfile::{Ecosystem, LockfileParser};
+
+/// How deep below the root workspace members are looked for
+const MAX_MEMBER_DEPTH: usize = 5;
+
+/// Directories of the workspace members declared by the project
+///
+/// Patterns come from package.json `workspaces` (npm, yarn; an array or
+/// `{ "packages": [...] }`) and pnpm-workspace.yaml `packages`. A `!pattern`
+/// excludes members. Returns an empty list for single-package projects.
+pub fn member_dirs(root: &Path) -> Result<Vec<PathBuf>> {
+    let patterns = workspace_patterns(root)?;
+    matching_dirs(root, &patterns, "package.json")
+}
+
+/// Directories of the crates in a Cargo workspace, the root package included
+///
+/// Members come from `[workspace] members` in the root Cargo.toml, minus
+/// `exclude`. Returns just the root for a single-crate project, and nothing
+/// when there's no Cargo.toml.
+pub fn cargo_member_dirs(root: &Path) -> Result<Vec<PathBuf>> {
+    let manifest_path = root.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&manifest_path)
+        .into_diagnostic()
+        .with_context(|| "Failed to read Cargo.toml")?;
+    let manifest: TomlValue = toml::from_str(&content)
+        .map_err(|e| miette::miette!("Failed to parse Cargo.toml: {}", e))?;
+
+    let strings = |key: &str| -> Vec<String> {
+        manifest
+            .get("workspace")
+            .and_then(|w| w.get(key))
+            .and_then(|list| list.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|p| p.as_str())
+            .map(String::from)
+            .collect()
+    };
+    let mut patterns = strings("members");
+    patterns.extend(strings("exclude").iter().map(|p| format!("!{}", p)));
+
+    let mut members = matching_dirs(root, &patterns, "Cargo.toml")?;
+    if manifest.get("package").is_some() {
+        members.insert(0, root.to_path_buf());
+    }
+    Ok(members)
+}
+
+/// Directories at or below `root` that are projects of their own: those
+/// with a lockfile, and workspace roots not installed yet
+///
+/// With `ecosystem`, only its lockfiles and workspaces count. Used by
+/// `depx analyze --recursive` in repositories holding several projects.
+pub fn project_dirs(root: &Path, ecosystem: Option<Ecosystem>) -> Result<Vec<PathBuf>> {
+    let mut projects = Vec::new();
+    for entry in walkdir::WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            entry.depth() == 0
+                || (name != "node_modules" && name != "target" && !name.starts_with('.'))
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+    {
+        let dir = entry.path();
+        let js = ecosystem.is_none_or(|e| e == Ecosystem::Js);
+        let rust = ecosystem.is_none_or(|e| e == Ecosystem::Rust);
+        if LockfileParser::detect_all(dir, ecosystem).is_ok()
+            || (js && !workspace_patterns(dir)?.is_empty())
+            || (rust && is_cargo_workspace(dir)?)
+        {
+            projects.push(entry.into_path());
+        }
+    }
+
+    projects.sort();
+    Ok(projects)
+}
+
+fn is_cargo_workspace(dir: &Path) -> Result<bool> {
+    let manifest_path = dir.join("Cargo.toml");
+    if !manifest_path.exists() {
+        return Ok(false);
+    }
+    let content = std::fs::read_to_string(&manifest_path)
+        .into_diagnostic()
+        .with_context(|| format!("Failed to read {}", manifest_path.display()))?;
+    let manifest: TomlValue = toml::from_str(&content)
+        .map_err(|e| miette::miette!("Failed to parse {}: {}", manifest_path.display(), e))?;
+    Ok(manifest.get("workspace").is_some())
+}
+
+/// Directories below `root` holding `manifest` that the patterns select
+fn matching_dirs(root: &Path, patterns: &[String], manifest: &str) -> Result<Vec<PathBuf>> {
+    if patterns.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder = GitignoreBuilder::new(root);
+    for pattern in patterns {
+        let pattern = pattern.trim_end_matches('/');
+        builder
+            .add_line(None, pattern)
+            .map_err(|e| miette::miette!("Invalid workspace pattern '{}': {}", pattern, e))?;
+    }
+    let matcher = builder
+        .build()
+        .map_err(|e| miette::miette!("Invalid workspace patterns: {}", e))?;
+
+    let mut members: Vec<PathBuf> = walkdir::WalkDir::new(root)
+        .min_depth(1)
+        .max_depth(MAX_MEMBER_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            name != "node_modules" && name != "target" && !name.starts_with('.')
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir() && entry.path().join(manifest).is_file())
+        .filter(|entry| {
+            let relative = entry.path().strip_prefix(root).unwrap_or(entry.path());
+            matcher.matched(relative, true).is_ignore()
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+
+    members.sort();
+    Ok(members)
+}
+
+fn workspace_patterns(root: &Path) -> Result<Vec<String>> {
+    let mut patterns = Vec::new();
+
+    let manifest_path = root.join("package.json");
+    if manifest_path.exists() {
+        let content = std::fs::read_to_string(&manifest_path)
+            .into_diagnostic()
+            .with_context(|| "Failed to read package.json")?;
+        let manifest: JsonValue = serde_json::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse package.json")?;
+
+        let workspaces = match manifest.get("workspaces") {
+            Some(JsonValue::Object(config)) => config.get("packages"),
+            other => other,
+        };
+        if let Some(JsonValue::Array(list)) = workspaces {
+            patterns.extend(list.iter().filter_map(|p| p.as_str()).map(String::from));
+        }
+    }
+
+    let pnpm_path = root.join("pnpm-workspace.yaml");
+    if pnpm_path.exists() {
+        let content = std::fs::read_to_string(&pnpm_path)
+            .into_diagnostic()
+            .with_context(|| "Failed to read pnpm-workspace.yaml")?;
+        let config: PnpmWorkspace = serde_yaml::from_str(&content)
+            .into_diagnostic()
+            .with_context(|| "Failed to parse pnpm-workspace.yaml")?;
+        patterns.extend(config.packages);
+    }
+
+    Ok(patterns)
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PnpmWorkspace {
+    #[serde(default)]
+    packages: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn test_member_dirs() {
+        let root = TempDir::new("workspace");
+        for dir in [
+            "packages/a",
+            "packages/b",
+            "packages/internal",
+            "apps/web",
+            "tools/x",
+        ] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+            std::fs::write(root.join(dir).join("package.json"), "{}").unwrap();
+        }
+        std::fs::write(
+            root.join("package.json"),
+            r#"{"workspaces": ["packages/*", "!packages/internal"]}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("pnpm-workspace.yaml"),
+            "packages:\n  - 'apps/*'\n",
+        )
+        .unwrap();
+
+        let members = member_dirs(&root).unwrap();
+
+        let relative: Vec<String> = members
+            .iter()
+            .map(|m| m.strip_prefix(&root).unwrap().display().to_string())
+            .collect();
+        assert_eq!(relative, vec!["apps/web", "packages/a", "packages/b"]);
+    }
+
+    #[test]
+    fn test_project_dirs() {
+        let root = TempDir::new("projects");
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write("web/package.json", "{}");
+        write("web/package-lock.json", "{}");
+        write("web/node_modules/x/package-lock.json", "{}");
+        write(
+            "services/api/Cargo.toml",
+            "[workspace]\nmembers = [\"core\"]\n",
+        );
+        write(
+            "services/api/core/Cargo.toml",
+            "[package]\nname = \"core\"\n",
+        );
+        write("tools/lint/package.json", "{}");
+
+        let relative = |ecosystem| -> Vec<String> {
+            project_dirs(&root, ecosystem)
+                .unwrap()
+                .iter()
+                .map(|p| p.strip_prefix(&root).unwrap().display().to_string())
+                .collect()
+        };
+        let all = relative(None);
+        let js = relative(Some(Ecosystem::Js));
+
+        // A Cargo workspace counts before it has a Cargo.lock
+        assert_eq!(all, vec!["services/api", "web"]);
+        assert_eq!(js, vec!["web"]);
+    }
+}